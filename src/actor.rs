@@ -1,14 +1,17 @@
 use std::time::Duration;
-use futures::{future, Stream};
+use futures::{future, Future, Stream};
 
-use fut::ActorFuture;
+use fut::{wrap_future, ActorFuture, PipeTo};
 use arbiter::Arbiter;
-use address::{Addr, ActorAddress, Syn, Unsync};
+use address::{Addr, ActorAddress, MessageRecipient, Recipient, Syn, Unsync};
 use context::Context;
 use handler::{Handler, Message};
 use stream::StreamHandler;
+use middleware::Middlewares;
+use supervisor::RestartReason;
 use contextitems::{ActorMessageItem, ActorDelayedMessageItem, ActorMessageStreamItem};
-use utils::TimerFunc;
+use utils::{IntervalFunc, ScheduleFunc, TimerFunc};
+use schedule::{Cron, Schedule};
 
 #[allow(unused_variables)]
 /// Actors are objects which encapsulate state and behavior.
@@ -163,10 +166,21 @@ pub trait Actor: Sized + 'static {
 /// Actor execute state changes to `Started` and normal lifecycle process starts.
 ///
 /// `restarting` method get called with newly constructed `Context` object.
+///
+/// If actor needs to know why it failed, it could override `restarting_with` method
+/// instead, it receives [`RestartReason`](supervisor/enum.RestartReason.html).
 pub trait Supervised: Actor {
 
     /// Method called when supervisor restarting failed actor
     fn restarting(&mut self, ctx: &mut <Self as Actor>::Context) {}
+
+    /// Method called when supervisor restarting failed actor, `reason` describes
+    /// why actor failed.
+    ///
+    /// By default this method calls `restarting`.
+    fn restarting_with(&mut self, reason: &RestartReason, ctx: &mut <Self as Actor>::Context) {
+        self.restarting(ctx)
+    }
 }
 
 /// Actor execution state
@@ -209,6 +223,12 @@ pub trait ActorContext: Sized {
 
     /// Actor execution state
     fn state(&self) -> ActorState;
+
+    #[doc(hidden)]
+    /// Middleware registered on the context
+    fn middlewares(&mut self) -> Option<&mut Middlewares> {
+        None
+    }
 }
 
 /// Asynchronous execution context
@@ -242,9 +262,35 @@ pub trait AsyncContext<A>: ActorContext where A: Actor<Context=Self>
     /// Check if context is paused (waiting for future completion or stopping)
     fn waiting(&self) -> bool;
 
-    /// Cancel future. idx is a value returned by `spawn` method.
+    /// Cancel future. handle is a value returned by `spawn` method
+    /// or any other method that spawns future into the context.
+    ///
+    /// Returns `false` if future is not running. Running future could
+    /// cancel itself.
     fn cancel_future(&mut self, handle: SpawnHandle) -> bool;
 
+    /// Spawn future into the context and send its result to `dest`
+    ///
+    /// Message is created from future's result, see
+    /// [`PipeTo::pipe_to()`](fut/trait.PipeTo.html#tymethod.pipe_to).
+    /// Future cancels if actor stops before future resolves.
+    fn pipe<F, R, T, M>(&mut self, fut: F, dest: R) -> SpawnHandle
+        where F: Future + 'static, R: Into<Recipient<T, M>>,
+              T: MessageRecipient<M> + 'static,
+              M: Message + From<Result<F::Item, F::Error>> + 'static,
+              Self: Sized,
+    {
+        self.spawn(wrap_future(fut.pipe_to(dest)))
+    }
+
+    #[doc(hidden)]
+    /// Mark context as stopped because of stream error
+    fn stream_error(&mut self) {}
+
+    #[doc(hidden)]
+    /// Check if streams polling is paused because actor's mailbox is overloaded
+    fn stream_paused(&self) -> bool { false }
+
     /// This method allow to handle `Stream` in similar way as normal actor messages.
     ///
     /// Information to consider. Actor wont receive next item from a stream
@@ -299,6 +345,11 @@ pub trait AsyncContext<A>: ActorContext where A: Actor<Context=Self>
 
     /// This method is similar to `add_stream` but it skips stream errors.
     ///
+    /// Each item of the stream is handled as a message by actor's `Handler`.
+    /// Unlike `add_stream`, completion of the stream does not affect actor's
+    /// lifecycle, so it could be used for bridging external channels into
+    /// an actor.
+    ///
     /// ```rust
     /// # #[macro_use] extern crate actix;
     /// # extern crate futures;
@@ -346,11 +397,58 @@ pub trait AsyncContext<A>: ActorContext where A: Actor<Context=Self>
     }
 
     /// Send message `msg` to self.
+    ///
+    /// Message does not go through actor's address, so it gets delivered
+    /// even if mailbox is full or all addresses are dropped.
+    ///
+    /// ```rust
+    /// # extern crate actix;
+    /// # use std::time::Duration;
+    /// use actix::prelude::*;
+    ///
+    /// struct Connect;
+    ///
+    /// impl Message for Connect {
+    ///     type Result = ();
+    /// }
+    ///
+    /// struct Client {
+    ///     attempts: usize,
+    /// }
+    ///
+    /// impl Actor for Client {
+    ///     type Context = Context<Self>;
+    ///
+    ///     fn started(&mut self, ctx: &mut Context<Self>) {
+    ///         ctx.notify(Connect);
+    ///     }
+    /// }
+    ///
+    /// impl Handler<Connect> for Client {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, _: Connect, ctx: &mut Context<Self>) {
+    ///         self.attempts += 1;
+    ///         if self.attempts < 3 {
+    ///             // connection failed, retry later
+    ///             ctx.notify_later(Connect, Duration::from_millis(10));
+    ///         } else {
+    /// #           Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    ///             println!("Connected");
+    ///         }
+    ///     }
+    /// }
+    /// # fn main() {
+    /// #    let sys = System::new("example");
+    /// #    let _: Addr<Unsync, _> = Client{attempts: 0}.start();
+    /// #    sys.run();
+    /// # }
+    /// ```
     fn notify<M>(&mut self, msg: M)
         where A: Handler<M>, M: Message + 'static
     {
         if self.state() == ActorState::Stopped {
-            error!("Context::add_timeout called for stopped actor.");
+            error!("Context::notify called for stopped actor.");
         } else {
             self.spawn(ActorMessageItem::new(msg));
         }
@@ -363,7 +461,7 @@ pub trait AsyncContext<A>: ActorContext where A: Actor<Context=Self>
         where A: Handler<M>, M: Message + 'static
     {
         if self.state() == ActorState::Stopped {
-            error!("Context::add_timeout called for stopped actor.");
+            error!("Context::notify_later called for stopped actor.");
             SpawnHandle::default()
         } else {
             self.spawn(ActorDelayedMessageItem::new(msg, after))
@@ -377,6 +475,45 @@ pub trait AsyncContext<A>: ActorContext where A: Actor<Context=Self>
     {
         self.spawn(TimerFunc::new(dur, f))
     }
+
+    /// Execute closure periodically with specified interval within same Actor
+    /// and Context. Returns spawn handle which could be used for cancellation.
+    /// Execution get cancelled if context's stop method get called.
+    fn run_interval<F>(&mut self, dur: Duration, f: F) -> SpawnHandle
+        where F: FnMut(&mut A, &mut A::Context) + 'static
+    {
+        self.spawn(IntervalFunc::new(dur, f))
+    }
+
+    /// Execute closure on every occurrence of `schedule` within same Actor
+    /// and Context. Returns spawn handle which could be used for cancellation.
+    /// Execution get cancelled if context's stop method get called.
+    ///
+    /// Occurrences that are missed, e.g. because actor was busy, are skipped.
+    /// Wall time is sampled once when timer is started, later adjustments
+    /// of system clock are not taken into account.
+    fn run_schedule<F>(&mut self, schedule: Schedule, f: F) -> SpawnHandle
+        where F: FnMut(&mut A, &mut A::Context) + 'static
+    {
+        self.spawn(ScheduleFunc::new(schedule, f))
+    }
+
+    /// Execute closure on every occurrence of cron expression `expr`,
+    /// see `schedule` module for expression syntax.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `expr` is not valid cron expression, use `Cron::parse()`
+    /// and `run_schedule()` to handle invalid expressions.
+    fn run_cron<F>(&mut self, expr: &str, f: F) -> SpawnHandle
+        where F: FnMut(&mut A, &mut A::Context) + 'static
+    {
+        let cron = match Cron::parse(expr) {
+            Ok(cron) => cron,
+            Err(err) => panic!("Invalid cron expression {:?}: {}", expr, err),
+        };
+        self.run_schedule(Schedule::Cron(cron), f)
+    }
 }
 
 /// Spawned future handle. Could be used for cancelling spawned future.
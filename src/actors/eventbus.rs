@@ -0,0 +1,129 @@
+//! Publish/subscribe event bus
+//!
+//! `EventBus<M>` is a system service that delivers each published `M` message
+//! to all subscribed recipients. There is separate event bus for each message
+//! type. Recipients get unsubscribed automatically once their actor stops.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate actix;
+//! use actix::prelude::*;
+//! use actix::actors::eventbus::{self, EventBus};
+//!
+//! #[derive(Clone)]
+//! struct ConfigChanged;
+//!
+//! impl Message for ConfigChanged {
+//!     type Result = ();
+//! }
+//!
+//! struct Cache;
+//!
+//! impl Actor for Cache {
+//!     type Context = Context<Self>;
+//!
+//!     fn started(&mut self, ctx: &mut Context<Self>) {
+//!         let addr: Addr<Syn, _> = ctx.address();
+//!         EventBus::<ConfigChanged>::from_registry()
+//!             .do_send(eventbus::Subscribe(addr.recipient()));
+//!     }
+//! }
+//!
+//! impl Handler<ConfigChanged> for Cache {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, _: ConfigChanged, _: &mut Context<Self>) {
+//!         println!("Configuration changed, invalidating cache");
+//! #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     let _: Addr<Unsync, _> = Cache.start();
+//!     Arbiter::handle().spawn_fn(|| {
+//!         EventBus::from_registry().do_send(eventbus::Publish(ConfigChanged));
+//!         Ok(())
+//!     });
+//!
+//!     sys.run();
+//! }
+//! ```
+use std::mem;
+
+use prelude::*;
+
+/// Subscribe to messages of type `M`
+pub struct Subscribe<M>(pub Recipient<Syn, M>)
+    where M: Message<Result=()> + Clone + Send + 'static;
+
+impl<M> Message for Subscribe<M>
+    where M: Message<Result=()> + Clone + Send + 'static
+{
+    type Result = ();
+}
+
+/// Publish message to all subscribers
+pub struct Publish<M>(pub M)
+    where M: Message<Result=()> + Clone + Send + 'static;
+
+impl<M> Message for Publish<M>
+    where M: Message<Result=()> + Clone + Send + 'static
+{
+    type Result = ();
+}
+
+/// Event bus for messages of type `M`
+pub struct EventBus<M>
+    where M: Message<Result=()> + Clone + Send + 'static
+{
+    subscribers: Vec<Recipient<Syn, M>>,
+}
+
+impl<M> Default for EventBus<M>
+    where M: Message<Result=()> + Clone + Send + 'static
+{
+    fn default() -> Self {
+        EventBus{subscribers: Vec::new()}
+    }
+}
+
+impl<M> Actor for EventBus<M>
+    where M: Message<Result=()> + Clone + Send + 'static
+{
+    type Context = Context<Self>;
+}
+
+impl<M> actix::Supervised for EventBus<M>
+    where M: Message<Result=()> + Clone + Send + 'static {}
+
+impl<M> actix::SystemService for EventBus<M>
+    where M: Message<Result=()> + Clone + Send + 'static {}
+
+impl<M> Handler<Subscribe<M>> for EventBus<M>
+    where M: Message<Result=()> + Clone + Send + 'static
+{
+    type Result = ();
+
+    fn handle(&mut self, msg: Subscribe<M>, _: &mut Self::Context) {
+        self.subscribers.retain(|subscr| subscr.connected());
+        self.subscribers.push(msg.0);
+    }
+}
+
+impl<M> Handler<Publish<M>> for EventBus<M>
+    where M: Message<Result=()> + Clone + Send + 'static
+{
+    type Result = ();
+
+    fn handle(&mut self, msg: Publish<M>, _: &mut Self::Context) {
+        let subscribers = mem::replace(&mut self.subscribers, Vec::new());
+        for subscr in subscribers {
+            if subscr.do_send(msg.0.clone()).is_ok() {
+                self.subscribers.push(subscr);
+            }
+        }
+    }
+}
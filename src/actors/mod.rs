@@ -1,6 +1,8 @@
 //! Helper actors
 
 mod resolver;
+pub mod eventbus;
+pub mod server;
 pub mod signal;
 
 pub use self::resolver::{Connect, ConnectAddr, Resolve, Connector, ConnectorError};
@@ -52,6 +52,8 @@ use tokio_core::net::{TcpStream, TcpStreamNew};
 
 use prelude::*;
 
+/// Default connect timeout in seconds
+const CONNECT_TIMEOUT: u64 = 1;
 
 pub struct Resolve {
     name: String,
@@ -74,14 +76,24 @@ impl Message for Resolve {
 pub struct Connect {
     name: String,
     port: Option<u16>,
+    timeout: Duration,
 }
 
 impl Connect {
     pub fn host<T: AsRef<str>>(host: T) -> Connect {
-        Connect{name: host.as_ref().to_owned(), port: None}
+        Connect{name: host.as_ref().to_owned(), port: None, timeout: Duration::from_secs(CONNECT_TIMEOUT)}
     }
     pub fn host_and_port<T: AsRef<str>>(host: T, port: u16) -> Connect {
-        Connect{name: host.as_ref().to_owned(), port: Some(port)}
+        Connect{name: host.as_ref().to_owned(), port: Some(port), timeout: Duration::from_secs(CONNECT_TIMEOUT)}
+    }
+
+    /// Set connect timeout
+    ///
+    /// Timeout limits connection establishment to all resolved addresses,
+    /// by default it is 1 second.
+    pub fn timeout(mut self, timeout: Duration) -> Connect {
+        self.timeout = timeout;
+        self
     }
 }
 
@@ -165,9 +177,10 @@ impl Handler<Connect> for Connector {
     type Result = ResponseActFuture<Self, TcpStream, ConnectorError>;
 
     fn handle(&mut self, msg: Connect, _: &mut Self::Context) -> Self::Result {
+        let timeout = msg.timeout;
         Box::new(
             Resolver::new(msg.name, msg.port.unwrap_or(0), &self.resolver)
-                .and_then(|addrs, _, _| TcpConnector::new(addrs)))
+                .and_then(move |addrs, _, _| TcpConnector::with_timeout(addrs, timeout)))
     }
 }
 
@@ -280,7 +293,7 @@ pub struct TcpConnector {
 impl TcpConnector {
 
     pub fn new(addrs: VecDeque<SocketAddr>) -> TcpConnector {
-        TcpConnector::with_timeout(addrs, Duration::from_secs(1))
+        TcpConnector::with_timeout(addrs, Duration::from_secs(CONNECT_TIMEOUT))
     }
 
     pub fn with_timeout(addrs: VecDeque<SocketAddr>, timeout: Duration) -> TcpConnector {
@@ -0,0 +1,384 @@
+//! TCP and Unix domain socket server utility actors
+//!
+//! `TcpServer` and `UnixServer` accept connections of a bound listener and
+//! start one actor per connection with user provided factory. Connection
+//! actors could be started in a different arbiter. Once number of running
+//! connection actors reaches `max_connections`, server stops accepting until
+//! one of them stops. During graceful stop of server's arbiter, server stops
+//! accepting and waits for running connection actors, same as with
+//! `StopServerGraceful`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate actix;
+//! # extern crate tokio_core;
+//! use std::net;
+//! use tokio_core::net::TcpStream;
+//! use actix::prelude::*;
+//! use actix::actors::server::{TcpServer, StopServer};
+//!
+//! struct Session(TcpStream);
+//!
+//! impl Actor for Session {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     let addr = net::SocketAddr::from(([127, 0, 0, 1], 0));
+//!     let server = TcpServer::bind(&addr, |stream, _| Session(stream).start()).unwrap()
+//!         .max_connections(256);
+//!     println!("Listening on {}", server.local_addr().unwrap());
+//!     let server: Addr<Syn, _> = server.start();
+//!
+//!     server.do_send(StopServer);
+//! #   Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!     sys.run();
+//! }
+//! ```
+use std::{fmt, io};
+use std::marker::PhantomData;
+use std::net::{self, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::{Async, Poll};
+use tokio_core::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net as unix;
+#[cfg(unix)]
+use std::path::Path;
+#[cfg(unix)]
+use tokio_uds::{UnixListener, UnixStream};
+
+use prelude::*;
+use msgs::Execute;
+
+/// Delay before accepting again after accept error, e.g. once process
+/// runs out of file descriptors
+const ACCEPT_RETRY: u64 = 100;
+
+/// Listener that could be served by `Server`
+pub trait Listener: 'static {
+    /// Accepted connection
+    type Stream: 'static;
+    /// Accepted connection that is not registered with event loop yet
+    type Std: Send + 'static;
+    /// Address of the peer
+    type Addr: fmt::Debug + Send + 'static;
+
+    /// Accept pending connection
+    fn accept_std(&mut self) -> Poll<(Self::Std, Self::Addr), io::Error>;
+
+    /// Register accepted connection with event loop of current arbiter
+    fn register(stream: Self::Std) -> io::Result<Self::Stream>;
+
+    /// Local address of the listener
+    fn local_addr(&self) -> io::Result<Self::Addr>;
+}
+
+impl Listener for TcpListener {
+    type Stream = TcpStream;
+    type Std = net::TcpStream;
+    type Addr = SocketAddr;
+
+    fn accept_std(&mut self) -> Poll<(net::TcpStream, SocketAddr), io::Error> {
+        match TcpListener::accept_std(self) {
+            Ok(item) => Ok(Async::Ready(item)),
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => Ok(Async::NotReady),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn register(stream: net::TcpStream) -> io::Result<TcpStream> {
+        TcpStream::from_stream(stream, Arbiter::handle())
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        TcpListener::local_addr(self)
+    }
+}
+
+#[cfg(unix)]
+impl Listener for UnixListener {
+    type Stream = UnixStream;
+    type Std = unix::UnixStream;
+    type Addr = unix::SocketAddr;
+
+    fn accept_std(&mut self) -> Poll<(unix::UnixStream, unix::SocketAddr), io::Error> {
+        self.poll_accept_std()
+    }
+
+    fn register(stream: unix::UnixStream) -> io::Result<UnixStream> {
+        UnixStream::from_std(stream, Arbiter::handle().new_tokio_handle())
+    }
+
+    fn local_addr(&self) -> io::Result<unix::SocketAddr> {
+        UnixListener::local_addr(self)
+    }
+}
+
+/// Stop accepting connections and stop server immediately
+///
+/// Running connection actors are not affected.
+pub struct StopServer;
+
+impl Message for StopServer {
+    type Result = ();
+}
+
+/// Stop accepting connections and stop server once all connection
+/// actors are stopped, or once timeout expires
+pub struct StopServerGraceful(pub Duration);
+
+impl Message for StopServerGraceful {
+    type Result = ();
+}
+
+/// Connection actor stopped
+pub(crate) struct Disconnected;
+
+#[doc(hidden)]
+impl Message for Disconnected {
+    type Result = ();
+}
+
+/// TCP server
+pub type TcpServer<A> = Server<TcpListener, A>;
+
+/// Unix domain socket server
+#[cfg(unix)]
+pub type UnixServer<A> = Server<UnixListener, A>;
+
+/// Starts connection actor for accepted stream
+type Factory<L, A> =
+    Arc<Fn(<L as Listener>::Stream, <L as Listener>::Addr) -> Addr<Syn, A> + Send + Sync>;
+
+/// Actor that accepts connections and starts connection actors
+pub struct Server<L: Listener, A: Actor> {
+    listener: Option<L>,
+    factory: Factory<L, A>,
+    arbiter: Option<Addr<Syn, Arbiter>>,
+    max_connections: usize,
+    connections: usize,
+    accepting: bool,
+    draining: bool,
+}
+
+impl<A: Actor> Server<TcpListener, A> {
+    /// Bind listener to `addr`
+    ///
+    /// `factory` is called for every accepted connection, it has to start
+    /// connection actor and return its address. Server tracks running
+    /// connections by their addresses.
+    pub fn bind<F>(addr: &SocketAddr, factory: F) -> io::Result<TcpServer<A>>
+        where F: Fn(TcpStream, SocketAddr) -> Addr<Syn, A> + Send + Sync + 'static
+    {
+        let listener = TcpListener::bind(addr, Arbiter::handle())?;
+        Ok(Server::from_listener(listener, factory))
+    }
+}
+
+#[cfg(unix)]
+impl<A: Actor> Server<UnixListener, A> {
+    /// Bind listener to socket `path`
+    ///
+    /// Socket file is not removed once server stops.
+    pub fn bind<P, F>(path: P, factory: F) -> io::Result<UnixServer<A>>
+        where P: AsRef<Path>,
+              F: Fn(UnixStream, unix::SocketAddr) -> Addr<Syn, A> + Send + Sync + 'static
+    {
+        let listener = unix::UnixListener::bind(path)?;
+        let listener = UnixListener::from_std(listener, Arbiter::handle().new_tokio_handle())?;
+        Ok(Server::from_listener(listener, factory))
+    }
+}
+
+impl<L: Listener, A: Actor> Server<L, A> {
+    /// Accept connections of already bound listener
+    pub fn from_listener<F>(listener: L, factory: F) -> Server<L, A>
+        where F: Fn(L::Stream, L::Addr) -> Addr<Syn, A> + Send + Sync + 'static
+    {
+        Server {
+            listener: Some(listener),
+            factory: Arc::new(factory),
+            arbiter: None,
+            max_connections: 0,
+            connections: 0,
+            accepting: false,
+            draining: false,
+        }
+    }
+
+    /// Start connection actors in `arbiter`
+    ///
+    /// By default connection actors are started in server's arbiter.
+    pub fn arbiter(mut self, arbiter: Addr<Syn, Arbiter>) -> Self {
+        self.arbiter = Some(arbiter);
+        self
+    }
+
+    /// Set maximum number of running connection actors, zero means no limit
+    ///
+    /// Pending connections wait in listener's backlog until number of
+    /// running connection actors drops below the limit.
+    pub fn max_connections(mut self, max: usize) -> Self {
+        self.max_connections = max;
+        self
+    }
+
+    /// Local address of the listener
+    pub fn local_addr(&self) -> io::Result<L::Addr> {
+        match self.listener {
+            Some(ref listener) => listener.local_addr(),
+            None => Err(io::Error::new(io::ErrorKind::NotConnected, "Server is stopped")),
+        }
+    }
+
+    /// Number of running connection actors
+    pub fn connections(&self) -> usize {
+        self.connections
+    }
+
+    fn accept(&mut self, ctx: &mut Context<Self>) {
+        if !self.accepting && !self.draining && self.listener.is_some() {
+            self.accepting = true;
+            ctx.spawn(Accept{act: PhantomData});
+        }
+    }
+
+    fn connect(&mut self, stream: L::Std, peer: L::Addr, ctx: &mut Context<Self>) {
+        self.connections += 1;
+
+        // slot is released once connection actor stops, or if connection
+        // could not be started, e.g. arbiter is stopped and drops `Execute`
+        let factory = Arc::clone(&self.factory);
+        let slot = Slot::<L, A>(ctx.address());
+        let start = move || {
+            match L::register(stream) {
+                Ok(stream) => {
+                    let addr = factory(stream, peer);
+                    let mut slot = Some(slot);
+                    addr.tx().watch(Box::new(move || { slot.take(); }));
+                }
+                Err(err) => error!("Can not register connection from {:?}: {}", peer, err),
+            }
+            Ok::<_, ()>(())
+        };
+        match self.arbiter {
+            Some(ref arbiter) => arbiter.do_send(Execute::new(start)),
+            None => { let _ = start(); }
+        }
+    }
+}
+
+impl<L: Listener, A: Actor> Actor for Server<L, A> {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        self.accept(ctx)
+    }
+
+    fn stopping(&mut self, _: &mut Context<Self>) -> Running {
+        // arbiter is stopping gracefully, server stops accepting and keeps
+        // running until connection actors are stopped
+        if Arbiter::draining() && !self.draining {
+            self.listener.take();
+            self.draining = true;
+            if self.connections != 0 {
+                return Running::Continue
+            }
+        }
+        Running::Stop
+    }
+}
+
+impl<L: Listener, A: Actor> Handler<StopServer> for Server<L, A> {
+    type Result = ();
+
+    fn handle(&mut self, _: StopServer, ctx: &mut Context<Self>) {
+        self.listener.take();
+        ctx.stop();
+    }
+}
+
+impl<L: Listener, A: Actor> Handler<StopServerGraceful> for Server<L, A> {
+    type Result = ();
+
+    fn handle(&mut self, msg: StopServerGraceful, ctx: &mut Context<Self>) {
+        self.listener.take();
+        self.draining = true;
+        if self.connections == 0 {
+            ctx.stop();
+        } else {
+            ctx.run_later(msg.0, |_, ctx| ctx.stop());
+        }
+    }
+}
+
+#[doc(hidden)]
+impl<L: Listener, A: Actor> Handler<Disconnected> for Server<L, A> {
+    type Result = ();
+
+    fn handle(&mut self, _: Disconnected, ctx: &mut Context<Self>) {
+        self.connections -= 1;
+        if self.draining {
+            if self.connections == 0 {
+                ctx.stop();
+            }
+        } else {
+            self.accept(ctx);
+        }
+    }
+}
+
+/// Connection slot of running connection actor, `Disconnected` is sent to
+/// the server once slot is dropped
+struct Slot<L: Listener, A: Actor>(Addr<Syn, Server<L, A>>);
+
+impl<L: Listener, A: Actor> Drop for Slot<L, A> {
+    fn drop(&mut self) {
+        self.0.do_send(Disconnected);
+    }
+}
+
+/// Accepts connections until limit is reached
+struct Accept<L, A> {
+    act: PhantomData<(L, A)>,
+}
+
+impl<L: Listener, A: Actor> ActorFuture for Accept<L, A> {
+    type Item = ();
+    type Error = ();
+    type Actor = Server<L, A>;
+
+    fn poll(&mut self, act: &mut Server<L, A>, ctx: &mut Context<Server<L, A>>) -> Poll<(), ()> {
+        loop {
+            if act.draining ||
+                (act.max_connections != 0 && act.connections >= act.max_connections)
+            {
+                act.accepting = false;
+                return Ok(Async::Ready(()))
+            }
+            let res = match act.listener {
+                Some(ref mut listener) => listener.accept_std(),
+                None => {
+                    act.accepting = false;
+                    return Ok(Async::Ready(()))
+                }
+            };
+            match res {
+                Ok(Async::Ready((stream, peer))) => act.connect(stream, peer, ctx),
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(err) => {
+                    error!("Can not accept connection: {}", err);
+                    act.accepting = false;
+                    ctx.run_later(Duration::from_millis(ACCEPT_RETRY), |act, ctx| act.accept(ctx));
+                    return Ok(Async::Ready(()))
+                }
+            }
+        }
+    }
+}
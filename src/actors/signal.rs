@@ -2,7 +2,27 @@
 //!
 //! This module implements asynchronous signal handling for Actix. For each signal
 //! `ProcessSignals` actor sends `Signal` message to all subscriber. To subscriber,
-//! send `Subscribe` message to `ProcessSignals` actor.
+//! send `Subscribe` message to `ProcessSignals` actor. On windows only Ctrl-C
+//! is handled, it is reported as `SignalType::Int`.
+//!
+//! If `ProcessSignals` actor has no subscribers, it stops system gracefully
+//! on `SIGINT` and `SIGTERM` and exits system on `SIGQUIT`. So it is enough to
+//! start service to get default signals handling:
+//!
+//! ```rust
+//! # extern crate actix;
+//! use actix::prelude::*;
+//! use actix::actors::signal;
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     let _ = signal::ProcessSignals::from_registry();
+//! #   Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!
+//!     sys.run();
+//! }
+//! ```
 //!
 //! # Examples
 //!
@@ -62,6 +82,7 @@
 //! }
 //! ```
 use std;
+use std::time::Duration;
 use libc;
 use futures::{Future, Stream};
 use tokio_signal;
@@ -69,6 +90,10 @@ use tokio_signal;
 use tokio_signal::unix;
 
 use prelude::*;
+use msgs::{SystemExit, SystemExitGraceful};
+
+/// Default timeout of graceful system shutdown
+pub const DEFAULT_SHUTDOWN_TIMEOUT: u64 = 30;
 
 /// Different types of process signals
 #[derive(PartialEq, Clone, Copy, Debug)]
@@ -164,6 +189,11 @@ impl Handler<SignalType> for ProcessSignals {
     type Result = ();
 
     fn handle(&mut self, sig: SignalType, _: &mut Self::Context) {
+        self.subscribers.retain(|subscr| subscr.connected());
+        if self.subscribers.is_empty() {
+            shutdown(sig, Duration::from_secs(DEFAULT_SHUTDOWN_TIMEOUT));
+            return
+        }
         let subscribers = std::mem::replace(&mut self.subscribers, Vec::new());
         for subscr in subscribers {
             if subscr.do_send(Signal(sig)).is_ok() {
@@ -189,13 +219,45 @@ impl actix::Handler<Subscribe> for ProcessSignals {
     }
 }
 
-/// Default signals handler. This actor sends `SystemExit` message to `System` actor
-/// for each of `SIGINT`, `SIGTERM`, `SIGQUIT` signals.
-pub struct DefaultSignalsHandler;
+/// Stop system on `SIGINT`, `SIGTERM` and `SIGQUIT` signals
+fn shutdown(sig: SignalType, timeout: Duration) {
+    match sig {
+        SignalType::Int => {
+            info!("SIGINT received, exiting");
+            Arbiter::system().do_send(SystemExitGraceful(0, timeout));
+        }
+        SignalType::Hup => {
+            info!("SIGHUP received, reloading");
+        }
+        SignalType::Term => {
+            info!("SIGTERM received, stopping");
+            Arbiter::system().do_send(SystemExitGraceful(0, timeout));
+        }
+        SignalType::Quit => {
+            info!("SIGQUIT received, exiting");
+            Arbiter::system().do_send(SystemExit(0));
+        }
+        _ => (),
+    }
+}
+
+/// Default signals handler. This actor stops system gracefully for each of
+/// `SIGINT`, `SIGTERM` signals and sends `SystemExit` message to `System` actor
+/// for `SIGQUIT` signal.
+pub struct DefaultSignalsHandler {
+    timeout: Duration,
+}
+
+impl DefaultSignalsHandler {
+    /// Create signals handler with specific graceful shutdown timeout
+    pub fn new(timeout: Duration) -> DefaultSignalsHandler {
+        DefaultSignalsHandler{timeout}
+    }
+}
 
 impl Default for DefaultSignalsHandler {
     fn default() -> Self {
-        DefaultSignalsHandler
+        DefaultSignalsHandler::new(Duration::from_secs(DEFAULT_SHUTDOWN_TIMEOUT))
     }
 }
 
@@ -213,29 +275,12 @@ impl Actor for DefaultSignalsHandler {
     }
 }
 
-/// Handle `SIGINT`, `SIGTERM`, `SIGQUIT` signals and send `SystemExit(0)`
-/// message to `System` actor.
+/// Handle `SIGINT`, `SIGTERM` signals and send `SystemExitGraceful(0, timeout)`
+/// message to `System` actor, handle `SIGQUIT` and send `SystemExit(0)` message.
 impl actix::Handler<Signal> for DefaultSignalsHandler {
     type Result = ();
 
     fn handle(&mut self, msg: Signal, _: &mut Self::Context) {
-        match msg.0 {
-            SignalType::Int => {
-                info!("SIGINT received, exiting");
-                Arbiter::system().do_send(actix::msgs::SystemExit(0));
-            }
-            SignalType::Hup => {
-                info!("SIGHUP received, reloading");
-            }
-            SignalType::Term => {
-                info!("SIGTERM received, stopping");
-                Arbiter::system().do_send(actix::msgs::SystemExit(0));
-            }
-            SignalType::Quit => {
-                info!("SIGQUIT received, exiting");
-                Arbiter::system().do_send(actix::msgs::SystemExit(0));
-            }
-            _ => (),
-        }
+        shutdown(msg.0, self.timeout);
     }
 }
@@ -1,10 +1,13 @@
+use std::any::TypeId;
 use std::marker::PhantomData;
+use std::vec;
 use futures::sync::oneshot::Sender as SyncSender;
 use futures::unsync::oneshot::Sender as UnsyncSender;
 
 use actor::{Actor, AsyncContext};
 use context::Context;
-use handler::{Handler, Message, MessageResponse};
+use handler::{self, Handler, Message, MessageResponse};
+use span::{self, CorrelationId};
 use super::{Syn, Unsync,
             MessageDestination, MessageDestinationTransport};
 
@@ -24,6 +27,22 @@ pub trait EnvelopeProxy {
 
     /// handle message within new actor and context
     fn handle(&mut self, act: &mut Self::Actor, ctx: &mut <Self::Actor as Actor>::Context);
+
+    /// Type of the message in this envelope, if known
+    fn message_type(&self) -> Option<TypeId> {
+        None
+    }
+
+    /// Type name of the message in this envelope, if known
+    fn message_name(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Envelope still holds messages, handling got interrupted because
+    /// actor's context started waiting
+    fn is_partial(&self) -> bool {
+        false
+    }
 }
 
 pub struct MessageEnvelope<M: Message> {
@@ -87,9 +106,18 @@ impl<A: Actor> SyncEnvelope<A> {
     {
         SyncEnvelope(Box::new(SyncEnvelopeProxy{msg: Some(msg),
                                                 tx: tx,
+                                                cid: CorrelationId::current(),
                                                 act: PhantomData}))
     }
 
+    /// Envelope with batch of messages, responses are ignored
+    pub fn batch<M>(msgs: Vec<M>) -> SyncEnvelope<A>
+        where A: Handler<M>, A::Context: AsyncContext<A>,
+              M: Message + Send + 'static, M::Result: Send
+    {
+        SyncEnvelope(Box::new(BatchEnvelopeProxy::new(msgs)))
+    }
+
     pub fn with_proxy(proxy: Box<EnvelopeProxy<Actor=A> + Send>) -> SyncEnvelope<A> {
         SyncEnvelope(proxy)
     }
@@ -101,12 +129,25 @@ impl<A: Actor> EnvelopeProxy for SyncEnvelope<A> {
     fn handle(&mut self, act: &mut Self::Actor, ctx: &mut <Self::Actor as Actor>::Context) {
         self.0.handle(act, ctx)
     }
+
+    fn message_type(&self) -> Option<TypeId> {
+        self.0.message_type()
+    }
+
+    fn message_name(&self) -> Option<&'static str> {
+        self.0.message_name()
+    }
+
+    fn is_partial(&self) -> bool {
+        self.0.is_partial()
+    }
 }
 
 pub struct SyncEnvelopeProxy<A, M> where M: Message + Send {
     act: PhantomData<A>,
     msg: Option<M>,
     tx: Option<SyncSender<M::Result>>,
+    cid: CorrelationId,
 }
 
 unsafe impl<A, M: Message + Send> Send for SyncEnvelopeProxy<A, M> {}
@@ -124,10 +165,18 @@ impl<A, M> EnvelopeProxy for SyncEnvelopeProxy<A, M>
         }
 
         if let Some(msg) = self.msg.take() {
-            let fut = <Self::Actor as Handler<M>>::handle(act, msg, ctx);
+            let fut = handler::dispatch(act, msg, ctx, self.cid);
             fut.handle(ctx, tx)
         }
     }
+
+    fn message_type(&self) -> Option<TypeId> {
+        Some(TypeId::of::<M>())
+    }
+
+    fn message_name(&self) -> Option<&'static str> {
+        span::type_name_of::<M>()
+    }
 }
 
 pub struct UnsyncEnvelope<A: Actor>(Box<EnvelopeProxy<Actor=A>>);
@@ -140,8 +189,16 @@ impl<A: Actor> UnsyncEnvelope<A> {
     {
         UnsyncEnvelope(Box::new(UnsyncEnvelopeProxy{msg: Some(msg),
                                                     tx: tx,
+                                                    cid: CorrelationId::current(),
                                                     act: PhantomData}))
     }
+
+    /// Envelope with batch of messages, responses are ignored
+    pub fn batch<M>(msgs: Vec<M>) -> UnsyncEnvelope<A>
+        where A: Handler<M>, A::Context: AsyncContext<A>, M: Message + 'static
+    {
+        UnsyncEnvelope(Box::new(BatchEnvelopeProxy::new(msgs)))
+    }
 }
 
 impl<A: Actor> EnvelopeProxy for UnsyncEnvelope<A> {
@@ -151,12 +208,25 @@ impl<A: Actor> EnvelopeProxy for UnsyncEnvelope<A> {
     fn handle(&mut self, act: &mut Self::Actor, ctx: &mut <Self::Actor as Actor>::Context) {
         self.0.handle(act, ctx)
     }
+
+    fn message_type(&self) -> Option<TypeId> {
+        self.0.message_type()
+    }
+
+    fn message_name(&self) -> Option<&'static str> {
+        self.0.message_name()
+    }
+
+    fn is_partial(&self) -> bool {
+        self.0.is_partial()
+    }
 }
 
 struct UnsyncEnvelopeProxy<A, M> where M: Message {
     msg: Option<M>,
     act: PhantomData<A>,
     tx: Option<UnsyncSender<M::Result>>,
+    cid: CorrelationId,
 }
 
 impl<A, M> EnvelopeProxy for UnsyncEnvelopeProxy<A, M>
@@ -172,7 +242,62 @@ impl<A, M> EnvelopeProxy for UnsyncEnvelopeProxy<A, M>
             return
         }
         if let Some(msg) = self.msg.take() {
-            <Self::Actor as Handler<M>>::handle(act, msg, ctx).handle(ctx, tx)
+            handler::dispatch(act, msg, ctx, self.cid).handle(ctx, tx)
+        }
+    }
+
+    fn message_type(&self) -> Option<TypeId> {
+        Some(TypeId::of::<M>())
+    }
+
+    fn message_name(&self) -> Option<&'static str> {
+        span::type_name_of::<M>()
+    }
+}
+
+/// Envelope with multiple messages of the same type
+///
+/// Messages are handled one after another within single mailbox poll.
+/// If actor's context starts waiting, remaining messages are handled
+/// once waiting is over.
+struct BatchEnvelopeProxy<A, M> {
+    msgs: vec::IntoIter<M>,
+    cid: CorrelationId,
+    act: PhantomData<A>,
+}
+
+unsafe impl<A, M: Message + Send> Send for BatchEnvelopeProxy<A, M> {}
+
+impl<A, M> BatchEnvelopeProxy<A, M> {
+    fn new(msgs: Vec<M>) -> BatchEnvelopeProxy<A, M> {
+        BatchEnvelopeProxy{msgs: msgs.into_iter(), cid: CorrelationId::current(), act: PhantomData}
+    }
+}
+
+impl<A, M> EnvelopeProxy for BatchEnvelopeProxy<A, M>
+    where M: Message + 'static,
+          A: Actor + Handler<M>, A::Context: AsyncContext<A>
+{
+    type Actor = A;
+
+    fn handle(&mut self, act: &mut Self::Actor, ctx: &mut <Self::Actor as Actor>::Context) {
+        while !ctx.waiting() {
+            match self.msgs.next() {
+                Some(msg) => handler::dispatch(act, msg, ctx, self.cid).handle(ctx, None::<()>),
+                None => break,
+            }
         }
     }
+
+    fn message_type(&self) -> Option<TypeId> {
+        Some(TypeId::of::<M>())
+    }
+
+    fn message_name(&self) -> Option<&'static str> {
+        span::type_name_of::<M>()
+    }
+
+    fn is_partial(&self) -> bool {
+        self.msgs.len() != 0
+    }
 }
@@ -2,12 +2,12 @@ use std::time::Duration;
 use std::marker::PhantomData;
 
 use futures::{Async, Future, Poll};
-use tokio_core::reactor::Timeout;
+use clock::Delay;
 
-use arbiter::Arbiter;
+use deadletter::{self, DeadLetterReason};
 use handler::{Handler, Message};
 
-use super::{ToEnvelope, SendError, MailboxError};
+use super::{ToEnvelope, SendError, MailboxError, OverflowPolicy};
 use super::{MessageDestination, MessageDestinationTransport};
 
 
@@ -21,7 +21,8 @@ pub struct Request<T, A, M>
 {
     rx: Option<T::ResultReceiver>,
     info: Option<(T::Transport, M)>,
-    timeout: Option<Timeout>,
+    timeout: Option<Delay>,
+    full: bool,
     act: PhantomData<A>,
 }
 
@@ -33,12 +34,17 @@ impl<T, A, M> Request<T, A, M>
 {
     pub(crate) fn new(rx: Option<T::ResultReceiver>,
                       info: Option<(T::Transport, M)>) -> Request<T, A, M> {
-        Request{rx: rx, info: info, timeout: None, act: PhantomData}
+        Request{rx, info, timeout: None, full: false, act: PhantomData}
+    }
+
+    /// Request to the destination with full mailbox, which does not block senders
+    pub(crate) fn full() -> Request<T, A, M> {
+        Request{rx: None, info: None, timeout: None, full: true, act: PhantomData}
     }
 
     /// Set message delivery timeout
     pub fn timeout(mut self, dur: Duration) -> Self {
-        self.timeout = Some(Timeout::new(dur, Arbiter::handle()).unwrap());
+        self.timeout = Some(Delay::new(dur));
         self
     }
 
@@ -55,6 +61,35 @@ impl<T, A, M> Request<T, A, M>
     }
 }
 
+/// `Forward` is a message response which delegates message to other actor.
+///
+/// This structure is produced by `Addr::forward()`. Response of the other
+/// actor goes directly to the original caller if both actors are called via
+/// the same address type. Otherwise response is proxied through the actor
+/// that forwarded the message.
+#[must_use = "forward does nothing unless returned from handler"]
+pub struct Forward<T, A, M>
+    where T: MessageDestination<A, M>,
+          T::Transport: MessageDestinationTransport<T, A, M>,
+          A: Handler<M>, A::Context: ToEnvelope<T, A, M>,
+          M: Message + 'static,
+{
+    pub(crate) tx: T::Transport,
+    pub(crate) msg: M,
+    act: PhantomData<A>,
+}
+
+impl<T, A, M> Forward<T, A, M>
+    where T: MessageDestination<A, M>,
+          T::Transport: MessageDestinationTransport<T, A, M>,
+          A: Handler<M>, A::Context: ToEnvelope<T, A, M>,
+          M: Message + 'static,
+{
+    pub(crate) fn new(tx: T::Transport, msg: M) -> Forward<T, A, M> {
+        Forward{tx, msg, act: PhantomData}
+    }
+}
+
 impl<T, A, M> Future for Request<T, A, M>
     where T: MessageDestination<A, M>,
           T::Transport: MessageDestinationTransport<T, A, M>,
@@ -64,14 +99,32 @@ impl<T, A, M> Future for Request<T, A, M>
     type Error = MailboxError;
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if self.full {
+            return Err(MailboxError::Full)
+        }
+
         if let Some((sender, msg)) = self.info.take() {
             match sender.send(msg) {
                 Ok(rx) => self.rx = Some(rx),
                 Err(SendError::Full(msg)) => {
+                    if sender.overflow() != OverflowPolicy::Block {
+                        if sender.overflow() == OverflowPolicy::DropNewest {
+                            deadletter::publish_message::<M>(DeadLetterReason::Overflow);
+                        }
+                        return Err(MailboxError::Full)
+                    }
                     self.info = Some((sender, msg));
-                    return Ok(Async::NotReady)
+                    let res = self.poll_timeout();
+                    if res.is_err() {
+                        // blocked message is dropped on timeout
+                        deadletter::publish_message::<M>(DeadLetterReason::Overflow);
+                    }
+                    return res
+                }
+                Err(SendError::Closed(_)) => {
+                    deadletter::publish_message::<M>(DeadLetterReason::Closed);
+                    return Err(MailboxError::Closed)
                 }
-                Err(SendError::Closed(_)) => return Err(MailboxError::Closed),
             }
         }
 
@@ -1,6 +1,11 @@
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
-use futures::Future;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use futures::{Async, AsyncSink, Future, Poll, Sink, StartSend};
+use futures::executor::{self, Notify};
 
 mod envelope;
 mod queue;
@@ -13,9 +18,10 @@ mod unsync;
 mod unsync_channel;
 
 use actor::{Actor, AsyncContext};
+use deadletter::{self, DeadLetterReason};
 use handler::{Handler, Message};
 
-pub use self::message::Request;
+pub use self::message::{Forward, Request};
 pub use self::envelope::{EnvelopeProxy, ToEnvelope, SyncEnvelope, UnsyncEnvelope,
                          MessageEnvelope, SyncMessageEnvelope};
 
@@ -24,22 +30,68 @@ pub use self::unsync::{Unsync, UnsyncRecipientRequest};
 pub(crate) use self::sync_channel::SyncAddressReceiver;
 pub(crate) use self::unsync_channel::UnsyncAddrReceiver;
 
-
+/// Set of error that can occurred during non-blocking message sending
+///
+/// Undelivered message is returned to the caller, so it could be retried
+/// or sent elsewhere.
 pub enum SendError<T> {
+    /// Actor's mailbox is full
     Full(T),
+    /// Actor is stopped
     Closed(T),
 }
 
-#[derive(Fail)]
+#[derive(Fail, Clone, Copy, PartialEq)]
 /// Set of error that can occurred during message delivery process
+///
+/// Errors are reported by `Request` futures returned from `send()` methods,
+/// `Request::timeout()` limits waiting for mailbox space and for response.
 pub enum MailboxError {
     #[fail(display="Mailbox has closed")]
     Closed,
     #[fail(display="Message delivery timed out")]
     Timeout,
+    #[fail(display="Mailbox is full")]
+    Full,
+}
+
+/// Mailbox overflow policy
+///
+/// Policy defines what happens with new message if actor's mailbox is full.
+/// Dropped messages are reported to system `DeadLetters` service.
+/// `do_send()` ignores mailbox capacity, with `DropOldest` policy it still
+/// could cause dropping of the oldest message.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OverflowPolicy {
+    /// `send()` waits until actor processes pending messages, `try_send()`
+    /// fails with `SendError::Full`. This is default policy.
+    Block,
+    /// `send()` fails with `MailboxError::Full`, `try_send()` fails
+    /// with `SendError::Full`
+    Fail,
+    /// Oldest pending message gets dropped
+    DropOldest,
+    /// New message gets dropped, `send()` fails with `MailboxError::Full`.
+    /// `try_send()` fails with `SendError::Full` and returns the message
+    /// back, returned message is not reported to `DeadLetters`.
+    DropNewest,
 }
 
 impl<T> SendError<T> {
+    /// Check if send failed because actor's mailbox is full
+    pub fn is_full(&self) -> bool {
+        match *self {
+            SendError::Full(_) => true,
+            SendError::Closed(_) => false,
+        }
+    }
+
+    /// Check if send failed because actor is stopped
+    pub fn is_closed(&self) -> bool {
+        !self.is_full()
+    }
+
+    /// Get undelivered message back
     pub fn into_inner(self) -> T {
         match self {
             SendError::Full(msg) | SendError::Closed(msg) => msg,
@@ -109,9 +161,22 @@ impl<A> ActorAddress<A, ()> for A where A: Actor {
 
 pub trait Destination<A>: Sized {
     type Transport: Clone;
+    type WeakTransport: Clone;
 
     /// Indicates if destination is still alive
     fn connected(tx: &Self::Transport) -> bool;
+
+    /// Identity of the destination's channel
+    fn id(tx: &Self::Transport) -> usize;
+
+    /// Number of messages pending in destination's mailbox
+    fn pending(tx: &Self::Transport) -> usize;
+
+    /// Get weak transport, weak transport does not keep destination alive
+    fn downgrade(tx: &Self::Transport) -> Self::WeakTransport;
+
+    /// Get transport if destination is still alive
+    fn upgrade(tx: &Self::WeakTransport) -> Option<Self::Transport>;
 }
 
 #[allow(unused_variables)]
@@ -127,6 +192,9 @@ pub trait MessageDestination<A, M>: Destination<A>
     /// Send message unconditionally
     fn do_send(tx: &Self::Transport, msg: M);
 
+    /// Send message unconditionally, response is sent to `result` channel
+    fn forward(tx: &Self::Transport, msg: M, result: Self::ResultSender);
+
     /// Try send message
     fn try_send(tx: &Self::Transport, msg: M) -> Result<(), SendError<M>>;
 
@@ -144,6 +212,11 @@ pub trait MessageDestinationTransport<T: MessageDestination<A, M>, A, M>
           T::Transport: MessageDestinationTransport<T, A, M>,
 {
     fn send(&self, msg: M) -> Result<T::ResultReceiver, SendError<M>>;
+
+    /// Mailbox overflow policy of the destination
+    fn overflow(&self) -> OverflowPolicy {
+        OverflowPolicy::Block
+    }
 }
 
 #[allow(unused_variables)]
@@ -173,6 +246,14 @@ pub trait MessageRecipient<M>: Sized where M: Message + 'static
     /// object get dropped, message cancels.
     fn send(tx: &Self::Transport, msg: M) -> Self::Request;
 
+    /// Indicates if recipient is still alive
+    fn connected(tx: &Self::Transport) -> bool;
+
+    /// Mailbox overflow policy of the recipient
+    fn overflow(tx: &Self::Transport) -> OverflowPolicy {
+        OverflowPolicy::Block
+    }
+
     /// Clone transport
     fn clone(tx: &Self::Transport) -> Self::Transport;
 }
@@ -196,9 +277,18 @@ impl<T: Destination<A>, A> Addr<T, A> {
         T::connected(&self.tx)
     }
 
+    /// Number of messages pending in actor's mailbox
+    ///
+    /// `Syn` and `Unsync` addresses use separate channels, messages sent via
+    /// other address type are not counted.
+    pub fn pending(&self) -> usize {
+        T::pending(&self.tx)
+    }
+
     /// Sendm message unconditionally
     ///
-    /// This method ignores actor's mailbox capacity, it silently fails if mailbox is closed.
+    /// This method ignores actor's mailbox capacity. If mailbox is closed message
+    /// is reported to system `DeadLetters` service.
     pub fn do_send<M>(&self, msg: M)
         where T: MessageDestination<A, M>,
               T::Transport: MessageDestinationTransport<T, A, M>,
@@ -221,6 +311,35 @@ impl<T: Destination<A>, A> Addr<T, A> {
         T::send(&self.tx, msg)
     }
 
+    /// Send asynchronous message and wait for response with timeout.
+    ///
+    /// Timeout covers waiting for space in actor's mailbox as well as waiting
+    /// for response. Elapsed timeout is reported as `MailboxError::Timeout`,
+    /// stopped actor as `MailboxError::Closed`.
+    pub fn send_timeout<M>(&self, msg: M, timeout: Duration) -> Request<T, A, M>
+        where T: MessageDestination<A, M>,
+              T::Transport: MessageDestinationTransport<T, A, M>,
+              M: Message + 'static,
+              A: Handler<M>, A::Context: ToEnvelope<T, A, M>,
+    {
+        T::send(&self.tx, msg).timeout(timeout)
+    }
+
+    /// Forward message to the actor
+    ///
+    /// Returned `Forward` has to be returned from the message handler.
+    /// Message gets delivered with the response channel of the original
+    /// caller, so the actor responds to the caller directly. This method
+    /// ignores actor's mailbox capacity.
+    pub fn forward<M>(&self, msg: M) -> Forward<T, A, M>
+        where T: MessageDestination<A, M>,
+              T::Transport: MessageDestinationTransport<T, A, M>,
+              M: Message + 'static,
+              A: Handler<M>, A::Context: ToEnvelope<T, A, M>,
+    {
+        Forward::new(self.tx.clone(), msg)
+    }
+
     /// Try send message
     ///
     /// This method fails if actor's mailbox is full or closed. This method
@@ -243,6 +362,103 @@ impl<T: Destination<A>, A> Addr<T, A> {
     {
         T::recipient(self.tx)
     }
+
+    /// Get `Sink` of specific message type, `stream.forward(addr.sink())`
+    ///
+    /// Sink is a `Recipient` of the actor, see `Recipient`'s `Sink`
+    /// implementation.
+    pub fn sink<M>(&self) -> Recipient<T, M>
+        where T: MessageDestination<A, M> + MessageRecipient<M>,
+              A: Handler<M>, A::Context: ToEnvelope<T, A, M>,
+              <T as Destination<A>>::Transport: MessageDestinationTransport<T, A, M>,
+              M: Message + 'static,
+    {
+        self.clone().recipient()
+    }
+
+    pub(crate) fn tx(&self) -> &T::Transport {
+        &self.tx
+    }
+
+    /// Get weak address of the actor
+    ///
+    /// Weak address does not prevent actor from stopping.
+    pub fn downgrade(&self) -> WeakAddr<T, A> {
+        WeakAddr{tx: T::downgrade(&self.tx), act: PhantomData}
+    }
+}
+
+impl<A: Actor> Addr<Syn, A> {
+    /// Send batch of messages as single envelope
+    ///
+    /// Actor handles all messages of the batch within one mailbox poll,
+    /// responses are ignored. Batch takes one slot of actor's mailbox and
+    /// ignores mailbox capacity. If mailbox is closed messages are reported to
+    /// system `DeadLetters` service.
+    pub fn do_send_batch<M>(&self, msgs: Vec<M>)
+        where A: Handler<M>, A::Context: AsyncContext<A>,
+              M: Message + Send + 'static, M::Result: Send,
+    {
+        let len = msgs.len();
+        if !self.tx.do_send_envelope(SyncEnvelope::batch(msgs)) {
+            for _ in 0..len {
+                deadletter::publish_message::<M>(DeadLetterReason::Closed);
+            }
+        }
+    }
+
+    /// Send message and block current thread until response is received
+    ///
+    /// This method could be used from threads that are not managed by actix,
+    /// it does not require running event loop. It must not be called from
+    /// an arbiter thread, because it blocks arbiter's event loop. Elapsed
+    /// timeout is reported as `MailboxError::Timeout`, stopped actor as
+    /// `MailboxError::Closed`.
+    pub fn call_blocking<M>(&self, msg: M, timeout: Duration) -> Result<M::Result, MailboxError>
+        where Syn: MessageDestination<A, M>,
+              <Syn as Destination<A>>::Transport: MessageDestinationTransport<Syn, A, M>,
+              M: Message + 'static,
+              A: Handler<M>, A::Context: ToEnvelope<Syn, A, M>,
+    {
+        let deadline = Instant::now() + timeout;
+        let notify = Arc::new(ThreadNotify(thread::current()));
+        let mut fut = executor::spawn(self.send(msg));
+        loop {
+            if let Async::Ready(res) = fut.poll_future_notify(&notify, 0)? {
+                return Ok(res)
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(MailboxError::Timeout)
+            }
+            thread::park_timeout(deadline - now);
+        }
+    }
+}
+
+impl<A: Actor> Addr<Unsync, A> where A::Context: AsyncContext<A> {
+    /// Send batch of messages as single envelope
+    ///
+    /// See `Addr<Syn, A>::do_send_batch()`.
+    pub fn do_send_batch<M>(&self, msgs: Vec<M>)
+        where A: Handler<M>, M: Message + 'static,
+    {
+        let len = msgs.len();
+        if !self.tx.do_send_envelope(UnsyncEnvelope::batch(msgs)) {
+            for _ in 0..len {
+                deadletter::publish_message::<M>(DeadLetterReason::Closed);
+            }
+        }
+    }
+}
+
+/// Wakes up thread blocked in `Addr::call_blocking()`
+struct ThreadNotify(thread::Thread);
+
+impl Notify for ThreadNotify {
+    fn notify(&self, _: usize) {
+        self.0.unpark();
+    }
 }
 
 impl<T: Destination<A>, A> Clone for Addr<T, A> {
@@ -251,6 +467,199 @@ impl<T: Destination<A>, A> Clone for Addr<T, A> {
     }
 }
 
+/// Addresses are equal if they point to the same actor's channel
+impl<T: Destination<A>, A> PartialEq for Addr<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        T::id(&self.tx) == T::id(&other.tx)
+    }
+}
+
+impl<T: Destination<A>, A> Eq for Addr<T, A> {}
+
+impl<T: Destination<A>, A> Hash for Addr<T, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        T::id(&self.tx).hash(state)
+    }
+}
+
+impl<T: Destination<A>, A> fmt::Debug for Addr<T, A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Addr({:#x})", T::id(&self.tx))
+    }
+}
+
+/// Weak address of the actor
+///
+/// Weak address does not keep actor alive, actor stops when all its
+/// `Addr` addresses get dropped.
+pub struct WeakAddr<T: Destination<A>, A> {
+    tx: T::WeakTransport,
+    act: PhantomData<A>,
+}
+
+unsafe impl<A: Actor> Send for WeakAddr<Syn, A> {}
+unsafe impl<A: Actor> Sync for WeakAddr<Syn, A> {}
+
+impl<T: Destination<A>, A> WeakAddr<T, A> {
+    /// Get address of the actor, if actor is still alive
+    pub fn upgrade(&self) -> Option<Addr<T, A>> {
+        T::upgrade(&self.tx).map(Addr::new)
+    }
+}
+
+impl<T: Destination<A>, A> Clone for WeakAddr<T, A> {
+    fn clone(&self) -> Self {
+        WeakAddr{tx: self.tx.clone(), act: PhantomData}
+    }
+}
+
+/// Object-safe sender of one specific message type
+///
+/// `Subscriber` is implemented by `Addr<Syn, _>`, `Addr<Unsync, _>` and
+/// `Recipient`, so libraries could accept `Box<Subscriber<M>>` without
+/// depending on actor type or address type.
+///
+/// ```rust
+/// # extern crate actix;
+/// use actix::prelude::*;
+///
+/// struct Tick;
+///
+/// impl Message for Tick {
+///     type Result = ();
+/// }
+///
+/// struct Clock {
+///     subscribers: Vec<Box<Subscriber<Tick>>>,
+/// }
+///
+/// impl Clock {
+///     fn tick(&self) {
+///         for subscriber in &self.subscribers {
+///             let _ = subscriber.do_send(Tick);
+///         }
+///     }
+/// }
+///
+/// struct Listener;
+///
+/// impl Actor for Listener {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Tick> for Listener {
+///     type Result = ();
+///
+///     fn handle(&mut self, _: Tick, _: &mut Context<Self>) {
+/// #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
+///     }
+/// }
+///
+/// fn main() {
+///     let sys = System::new("test");
+///     let unsync: Addr<Unsync, _> = Listener.start();
+///     let sync: Addr<Syn, _> = Listener.start();
+///     let clock = Clock{subscribers: vec![Box::new(unsync), Box::new(sync.recipient())]};
+///     clock.tick();
+///     sys.run();
+/// }
+/// ```
+pub trait Subscriber<M: Message + 'static> {
+    /// Send message unconditionally
+    ///
+    /// Deliver message even if recipient's mailbox is full
+    fn do_send(&self, msg: M) -> Result<(), SendError<M>>;
+
+    /// Try send message
+    ///
+    /// This method fails if recipient's mailbox is full or closed.
+    fn try_send(&self, msg: M) -> Result<(), SendError<M>>;
+
+    /// Send message and asynchronously wait for response
+    fn send(&self, msg: M) -> Box<Future<Item=M::Result, Error=MailboxError>>;
+
+    /// Indicates if recipient is still alive
+    fn connected(&self) -> bool;
+
+    /// Get boxed copy of the subscriber
+    fn boxed(&self) -> Box<Subscriber<M>>;
+}
+
+impl<A, M> Subscriber<M> for Addr<Syn, A>
+    where A: Handler<M>, A::Context: ToEnvelope<Syn, A, M>,
+          M: Message + Send + 'static, M::Result: Send,
+{
+    fn do_send(&self, msg: M) -> Result<(), SendError<M>> {
+        self.tx.do_send(msg)
+    }
+
+    fn try_send(&self, msg: M) -> Result<(), SendError<M>> {
+        Addr::try_send(self, msg)
+    }
+
+    fn send(&self, msg: M) -> Box<Future<Item=M::Result, Error=MailboxError>> {
+        Box::new(Addr::send(self, msg))
+    }
+
+    fn connected(&self) -> bool {
+        Addr::connected(self)
+    }
+
+    fn boxed(&self) -> Box<Subscriber<M>> {
+        Box::new(self.clone())
+    }
+}
+
+impl<A, M> Subscriber<M> for Addr<Unsync, A>
+    where A: Handler<M>, A::Context: AsyncContext<A> + ToEnvelope<Unsync, A, M>,
+          M: Message + 'static,
+{
+    fn do_send(&self, msg: M) -> Result<(), SendError<M>> {
+        self.tx.do_send(msg)
+    }
+
+    fn try_send(&self, msg: M) -> Result<(), SendError<M>> {
+        Addr::try_send(self, msg)
+    }
+
+    fn send(&self, msg: M) -> Box<Future<Item=M::Result, Error=MailboxError>> {
+        Box::new(Addr::send(self, msg))
+    }
+
+    fn connected(&self) -> bool {
+        Addr::connected(self)
+    }
+
+    fn boxed(&self) -> Box<Subscriber<M>> {
+        Box::new(self.clone())
+    }
+}
+
+impl<T, M> Subscriber<M> for Recipient<T, M>
+    where T: MessageRecipient<M, SendError=SendError<M>, MailboxError=MailboxError> + 'static,
+          T::Transport: 'static, T::Request: 'static, M: Message + 'static,
+{
+    fn do_send(&self, msg: M) -> Result<(), SendError<M>> {
+        Recipient::do_send(self, msg)
+    }
+
+    fn try_send(&self, msg: M) -> Result<(), SendError<M>> {
+        Recipient::try_send(self, msg)
+    }
+
+    fn send(&self, msg: M) -> Box<Future<Item=M::Result, Error=MailboxError>> {
+        Box::new(Recipient::send(self, msg))
+    }
+
+    fn connected(&self) -> bool {
+        Recipient::connected(self)
+    }
+
+    fn boxed(&self) -> Box<Subscriber<M>> {
+        Box::new(self.clone())
+    }
+}
+
 /// `Subscriber` type allows to send one specific message to an actor.
 ///
 /// You can get subscriber with `Addr<_, _>::subscriber()` method.
@@ -273,6 +682,11 @@ impl<T, M> Recipient<T, M>
         Recipient{tx: tx, msg: PhantomData}
     }
 
+    /// Indicates if recipient is still alive
+    pub fn connected(&self) -> bool {
+        T::connected(&self.tx)
+    }
+
     /// Send message
     ///
     /// Deliver message even if recipient's mailbox is full
@@ -297,6 +711,51 @@ impl<T, M> Recipient<T, M>
     }
 }
 
+/// Recipient could terminate stream pipelines, `stream.forward(addr.recipient())`
+///
+/// Sink honors recipient's mailbox overflow policy. With `Block` policy
+/// stream is not polled while mailbox is full, with `DropNewest` policy
+/// message is dropped and reported to `DeadLetters`, `Fail` policy fails the
+/// sink with `SendError::Full`. Responses are ignored. Stopped recipient
+/// fails the sink with `SendError::Closed`.
+impl<T, M> Sink for Recipient<T, M>
+    where T: MessageRecipient<M, SendError=SendError<M>>, M: Message + 'static
+{
+    type SinkItem = M;
+    type SinkError = SendError<M>;
+
+    fn start_send(&mut self, msg: M) -> StartSend<M, SendError<M>> {
+        match T::try_send(&self.tx, msg) {
+            Ok(()) => Ok(AsyncSink::Ready),
+            Err(SendError::Full(msg)) => match T::overflow(&self.tx) {
+                // current task is parked until mailbox has space
+                OverflowPolicy::Block => Ok(AsyncSink::NotReady(msg)),
+                OverflowPolicy::DropNewest => {
+                    deadletter::publish_message::<M>(DeadLetterReason::Overflow);
+                    Ok(AsyncSink::Ready)
+                }
+                _ => Err(SendError::Full(msg)),
+            },
+            Err(err) => Err(err),
+        }
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), SendError<M>> {
+        Ok(Async::Ready(()))
+    }
+}
+
+impl<T, A, M> From<Addr<T, A>> for Recipient<T, M>
+    where T: MessageDestination<A, M> + MessageRecipient<M>,
+          A: Handler<M>, A::Context: ToEnvelope<T, A, M>,
+          <T as Destination<A>>::Transport: MessageDestinationTransport<T, A, M>,
+          M: Message + 'static,
+{
+    fn from(addr: Addr<T, A>) -> Recipient<T, M> {
+        addr.recipient()
+    }
+}
+
 impl<T, M> Clone for Recipient<T, M>
     where T: MessageRecipient<M>, M: Message + 'static
 {
@@ -1,52 +1,35 @@
-/* Copyright (c) 2010-2011 Dmitry Vyukov. All rights reserved.
- * Redistribution and use in source and binary forms, with or without
- * modification, are permitted provided that the following conditions are met:
- *
- *    1. Redistributions of source code must retain the above copyright notice,
- *       this list of conditions and the following disclaimer.
- *
- *    2. Redistributions in binary form must reproduce the above copyright
- *       notice, this list of conditions and the following disclaimer in the
- *       documentation and/or other materials provided with the distribution.
- *
- * THIS SOFTWARE IS PROVIDED BY DMITRY VYUKOV "AS IS" AND ANY EXPRESS OR IMPLIED
- * WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF
- * MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT
- * SHALL DMITRY VYUKOV OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT,
- * INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT
- * LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
- * PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
- * LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE
- * OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF
- * ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
- *
- * The views and conclusions contained in the software and documentation are
- * those of the authors and should not be interpreted as representing official
- * policies, either expressed or implied, of Dmitry Vyukov.
- */
-
-//! A mostly lock-free multi-producer, single consumer queue.
+//! A block-based multi-producer, single consumer queue.
 //!
-//! This module contains an implementation of a concurrent MPSC queue. This
-//! queue can be used to share data between threads, and is also used as the
-//! building block of channels in rust.
+//! Values are stored in blocks of `BLOCK_CAP` slots, blocks are linked into
+//! a list. Producers reserve slots by incrementing tail index, so a push is
+//! a single successful CAS and one allocation per block instead of one
+//! allocation per value. Consumer reads slots in order and frees a block
+//! once all of its slots are read, `pop_batch` drains ready slots at once.
 //!
-//! Note that the current implementation of this queue has a caveat of the `pop`
-//! method, and see the method for more information about it. Due to this
-//! caveat, this queue may not be appropriate for all use-cases.
-
-// http://www.1024cores.net/home/lock-free-algorithms
-//                         /queues/non-intrusive-mpsc-node-based-queue
-
-// NOTE: this implementation is lifted from the standard library and only
-//       slightly modified
+//! Queue is not lock-free: producer that reserves the last slot of a block
+//! installs next block, other producers spin until it is installed.
+//!
+//! Similar to the node-based queue [1] this queue could be observed in an
+//! inconsistent state: slot is reserved by a producer, but value is not
+//! written yet. `pop` reports this state with `PopResult::Inconsistent`.
+//!
+//! [1] http://www.1024cores.net/home/lock-free-algorithms
+//!                         /queues/non-intrusive-mpsc-node-based-queue
 
 pub use self::PopResult::*;
-use std::prelude::v1::*;
 
 use std::cell::UnsafeCell;
+use std::collections::VecDeque;
 use std::ptr;
-use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::thread;
+
+/// Number of slots in a block. One more index per block is used to mark
+/// transition to the next block.
+const BLOCK_CAP: usize = 31;
+
+/// Number of indices per block
+const LAP: usize = BLOCK_CAP + 1;
 
 /// A result of the `pop` function.
 pub enum PopResult<T> {
@@ -61,50 +44,100 @@ pub enum PopResult<T> {
     Inconsistent,
 }
 
-#[derive(Debug)]
-struct Node<T> {
-    next: AtomicPtr<Node<T>>,
-    value: Option<T>,
+struct Slot<T> {
+    value: UnsafeCell<Option<T>>,
+    ready: AtomicBool,
+}
+
+struct Block<T> {
+    next: AtomicPtr<Block<T>>,
+    slots: Vec<Slot<T>>,
+}
+
+impl<T> Block<T> {
+    fn new() -> *mut Block<T> {
+        let slots = (0..BLOCK_CAP)
+            .map(|_| Slot{value: UnsafeCell::new(None), ready: AtomicBool::new(false)})
+            .collect();
+        Box::into_raw(Box::new(Block{next: AtomicPtr::new(ptr::null_mut()), slots}))
+    }
+}
+
+/// Consumer position
+struct Head<T> {
+    index: usize,
+    block: *mut Block<T>,
 }
 
 /// The multi-producer single-consumer structure. This is not cloneable, but it
 /// may be safely shared so long as it is guaranteed that there is only one
 /// popper at a time (many pushers are allowed).
-#[derive(Debug)]
 pub struct Queue<T> {
-    head: AtomicPtr<Node<T>>,
-    tail: UnsafeCell<*mut Node<T>>,
+    head: UnsafeCell<Head<T>>,
+    tail_index: AtomicUsize,
+    tail_block: AtomicPtr<Block<T>>,
 }
 
 unsafe impl<T: Send> Send for Queue<T> { }
 unsafe impl<T: Send> Sync for Queue<T> { }
 
-impl<T> Node<T> {
-    unsafe fn new(v: Option<T>) -> *mut Node<T> {
-        Box::into_raw(Box::new(Node {
-            next: AtomicPtr::new(ptr::null_mut()),
-            value: v,
-        }))
-    }
-}
-
 impl<T> Queue<T> {
     /// Creates a new queue that is safe to share among multiple producers and
     /// one consumer.
     pub fn new() -> Queue<T> {
-        let stub = unsafe { Node::new(None) };
+        let block = Block::new();
         Queue {
-            head: AtomicPtr::new(stub),
-            tail: UnsafeCell::new(stub),
+            head: UnsafeCell::new(Head{index: 0, block}),
+            tail_index: AtomicUsize::new(0),
+            tail_block: AtomicPtr::new(block),
         }
     }
 
     /// Pushes a new value onto this queue.
     pub fn push(&self, t: T) {
-        unsafe {
-            let n = Node::new(Some(t));
-            let prev = self.head.swap(n, Ordering::AcqRel);
-            (*prev).next.store(n, Ordering::Release);
+        let mut next_block = None;
+
+        loop {
+            let tail = self.tail_index.load(Ordering::Acquire);
+            let block = self.tail_block.load(Ordering::Acquire);
+            let offset = tail % LAP;
+
+            // other producer is installing next block
+            if offset == BLOCK_CAP {
+                thread::yield_now();
+                continue
+            }
+
+            // last slot of the block, prepare next block in advance
+            if offset + 1 == BLOCK_CAP && next_block.is_none() {
+                next_block = Some(Block::new());
+            }
+
+            let new_tail = tail.wrapping_add(1);
+            if self.tail_index.compare_exchange_weak(
+                tail, new_tail, Ordering::SeqCst, Ordering::Relaxed).is_err()
+            {
+                continue
+            }
+
+            unsafe {
+                let block = &*block;
+                if offset + 1 == BLOCK_CAP {
+                    let next = next_block.take().unwrap();
+                    self.tail_block.store(next, Ordering::Release);
+                    self.tail_index.store(new_tail.wrapping_add(1), Ordering::Release);
+                    block.next.store(next, Ordering::Release);
+                }
+
+                let slot = &block.slots[offset];
+                *slot.value.get() = Some(t);
+                slot.ready.store(true, Ordering::Release);
+            }
+
+            if let Some(next) = next_block {
+                unsafe { drop(Box::from_raw(next)) };
+            }
+            return
         }
     }
 
@@ -121,31 +154,163 @@ impl<T> Queue<T> {
     ///
     /// This function is unsafe because only one thread can call it at a time.
     pub unsafe fn pop(&self) -> PopResult<T> {
-        let tail = *self.tail.get();
-        let next = (*tail).next.load(Ordering::Acquire);
-
-        if !next.is_null() {
-            *self.tail.get() = next;
-            assert!((*tail).value.is_none());
-            assert!((*next).value.is_some());
-            let ret = (*next).value.take().unwrap();
-            drop(Box::from_raw(tail));
-            return Data(ret);
+        let head = &mut *self.head.get();
+        let offset = head.index % LAP;
+        let slot = &(&*head.block).slots[offset];
+
+        if !slot.ready.load(Ordering::Acquire) {
+            return if self.tail_index.load(Ordering::Acquire) == head.index {
+                Empty
+            } else {
+                Inconsistent
+            }
         }
 
-        if self.head.load(Ordering::Acquire) == tail {Empty} else {Inconsistent}
+        let value = (*slot.value.get()).take().unwrap();
+        if offset + 1 == BLOCK_CAP {
+            // next block is installed before last slot gets ready
+            let next = (&*head.block).next.load(Ordering::Acquire);
+            drop(Box::from_raw(head.block));
+            head.block = next;
+            head.index = head.index.wrapping_add(2);
+        } else {
+            head.index = head.index.wrapping_add(1);
+        }
+        Data(value)
+    }
+
+    /// Pops ready values in order into `buf`, at most `max` values.
+    ///
+    /// Returns number of popped values, or `Empty`/`Inconsistent` state of
+    /// the queue if no value is popped.
+    ///
+    /// This function is unsafe because only one thread can call it at a time.
+    pub unsafe fn pop_batch(&self, buf: &mut VecDeque<T>, max: usize) -> PopResult<usize> {
+        let mut popped = 0;
+        while popped < max {
+            match self.pop() {
+                Data(value) => {
+                    buf.push_back(value);
+                    popped += 1;
+                }
+                Empty if popped == 0 => return Empty,
+                Inconsistent if popped == 0 => return Inconsistent,
+                _ => break,
+            }
+        }
+        Data(popped)
     }
 }
 
 impl<T> Drop for Queue<T> {
     fn drop(&mut self) {
         unsafe {
-            let mut cur = *self.tail.get();
-            while !cur.is_null() {
-                let next = (*cur).next.load(Ordering::Relaxed);
-                drop(Box::from_raw(cur));
-                cur = next;
+            let head = &mut *self.head.get();
+            let tail = *self.tail_index.get_mut();
+            let mut block = head.block;
+
+            // all pushes are complete, values of reserved slots are written
+            while head.index != tail {
+                let offset = head.index % LAP;
+                if offset == BLOCK_CAP {
+                    let next = (&*block).next.load(Ordering::Relaxed);
+                    drop(Box::from_raw(block));
+                    block = next;
+                } else {
+                    (*(&*block).slots[offset].value.get()).take();
+                }
+                head.index = head.index.wrapping_add(1);
+            }
+            drop(Box::from_raw(block));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use super::*;
+
+    fn pop<T>(queue: &Queue<T>) -> Option<T> {
+        loop {
+            match unsafe { queue.pop() } {
+                Data(value) => return Some(value),
+                Empty => return None,
+                Inconsistent => thread::yield_now(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let queue = Queue::new();
+        assert!(pop(&queue).is_none());
+        for idx in 0..LAP * 3 {
+            queue.push(idx);
+        }
+        for idx in 0..LAP * 3 {
+            assert_eq!(pop(&queue), Some(idx));
+        }
+        assert!(pop(&queue).is_none());
+    }
+
+    #[test]
+    fn test_pop_batch() {
+        let queue = Queue::new();
+        let mut buf = VecDeque::new();
+        assert!(match unsafe { queue.pop_batch(&mut buf, LAP) } { Empty => true, _ => false });
+        for idx in 0..LAP + 3 {
+            queue.push(idx);
+        }
+        let mut popped = 0;
+        while let Data(count) = unsafe { queue.pop_batch(&mut buf, LAP) } {
+            assert!(count <= LAP);
+            popped += count;
+        }
+        assert_eq!(popped, LAP + 3);
+        assert_eq!(buf, (0..LAP + 3).collect::<VecDeque<_>>());
+    }
+
+    #[test]
+    fn test_drop_pending() {
+        let value = Arc::new(());
+        let queue = Queue::new();
+        for _ in 0..LAP * 2 + 3 {
+            queue.push(Arc::clone(&value));
+        }
+        for _ in 0..LAP {
+            pop(&queue);
+        }
+        drop(queue);
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_producers() {
+        const PRODUCERS: usize = 8;
+        const VALUES: usize = 10_000;
+
+        let queue = Arc::new(Queue::new());
+        let producers: Vec<_> = (0..PRODUCERS).map(|producer| {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || for idx in 0..VALUES {
+                queue.push((producer, idx));
+            })
+        }).collect();
+
+        let mut next = vec![0; PRODUCERS];
+        let mut received = 0;
+        while received < PRODUCERS * VALUES {
+            if let Data((producer, idx)) = unsafe { queue.pop() } {
+                assert_eq!(next[producer], idx);
+                next[producer] += 1;
+                received += 1;
             }
         }
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert!(pop(&queue).is_none());
     }
 }
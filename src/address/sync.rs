@@ -1,16 +1,17 @@
 use std::time::Duration;
 use futures::{Async, Future, Poll};
 use futures::sync::oneshot::{Sender, Receiver};
-use tokio_core::reactor::Timeout;
+use clock::Delay;
 
 use actor::Actor;
-use arbiter::Arbiter;
+use deadletter::{self, DeadLetterReason};
 use handler::{Handler, Message};
 
 use super::envelope::{ToEnvelope, SyncEnvelope, SyncMessageEnvelope};
-use super::sync_channel::{SyncSender, SyncAddressSender};
+use super::sync_channel::{SyncSender, SyncAddressSender, SyncAddressWeakSender};
 use super::{Request, Recipient};
 use super::{Destination, MessageDestination, MessageRecipient, SendError, MailboxError};
+use super::OverflowPolicy;
 
 
 /// Sync destination of the actor. Actor can run in different thread
@@ -19,11 +20,28 @@ pub struct Syn;
 impl<A: Actor> Destination<A> for Syn
 {
     type Transport = SyncAddressSender<A>;
+    type WeakTransport = SyncAddressWeakSender<A>;
 
     /// Indicates if actor is still alive
     fn connected(tx: &Self::Transport) -> bool {
         tx.connected()
     }
+
+    fn id(tx: &Self::Transport) -> usize {
+        tx.id()
+    }
+
+    fn pending(tx: &Self::Transport) -> usize {
+        tx.pending()
+    }
+
+    fn downgrade(tx: &Self::Transport) -> Self::WeakTransport {
+        tx.downgrade()
+    }
+
+    fn upgrade(tx: &Self::WeakTransport) -> Option<Self::Transport> {
+        tx.upgrade()
+    }
 }
 
 impl<A: Actor, M> MessageDestination<A, M> for Syn
@@ -35,7 +53,16 @@ impl<A: Actor, M> MessageDestination<A, M> for Syn
     type ResultReceiver = Receiver<M::Result>;
 
     fn do_send(tx: &Self::Transport, msg: M) {
-        let _ = tx.do_send(msg);
+        if let Err(SendError::Closed(_)) = tx.do_send(msg) {
+            deadletter::publish_message::<M>(DeadLetterReason::Closed);
+        }
+    }
+
+    fn forward(tx: &Self::Transport, msg: M, result: Sender<M::Result>) {
+        let env = <A::Context as ToEnvelope<Self, A, M>>::pack(msg, Some(result));
+        if !tx.do_send_envelope(env) {
+            deadletter::publish_message::<M>(DeadLetterReason::Closed);
+        }
     }
 
     fn try_send(tx: &Self::Transport, msg: M) -> Result<(), SendError<M>> {
@@ -45,8 +72,18 @@ impl<A: Actor, M> MessageDestination<A, M> for Syn
     fn send(tx: &Self::Transport, msg: M) -> Request<Self, A, M> {
         match tx.send(msg) {
             Ok(rx) => Request::new(Some(rx), None),
-            Err(SendError::Full(msg)) => Request::new(None, Some((tx.clone(), msg))),
-            Err(SendError::Closed(_)) => Request::new(None, None),
+            Err(SendError::Full(msg)) => if tx.overflow() == OverflowPolicy::Block {
+                Request::new(None, Some((tx.clone(), msg)))
+            } else {
+                if tx.overflow() == OverflowPolicy::DropNewest {
+                    deadletter::publish_message::<M>(DeadLetterReason::Overflow);
+                }
+                Request::full()
+            },
+            Err(SendError::Closed(_)) => {
+                deadletter::publish_message::<M>(DeadLetterReason::Closed);
+                Request::new(None, None)
+            }
         }
     }
 
@@ -76,13 +113,29 @@ impl<M> MessageRecipient<M> for Syn
     fn send(tx: &Self::Transport, msg: M) -> SyncRecipientRequest<M> {
         match tx.send(msg) {
             Ok(rx) => SyncRecipientRequest::new(Some(rx), None),
-            Err(SendError::Full(msg)) =>
-                SyncRecipientRequest::new(None, Some((tx.boxed(), msg))),
-            Err(SendError::Closed(_)) =>
-                SyncRecipientRequest::new(None, None),
+            Err(SendError::Full(msg)) => if tx.overflow() == OverflowPolicy::Block {
+                SyncRecipientRequest::new(None, Some((tx.boxed(), msg)))
+            } else {
+                if tx.overflow() == OverflowPolicy::DropNewest {
+                    deadletter::publish_message::<M>(DeadLetterReason::Overflow);
+                }
+                SyncRecipientRequest::full()
+            },
+            Err(SendError::Closed(_)) => {
+                deadletter::publish_message::<M>(DeadLetterReason::Closed);
+                SyncRecipientRequest::new(None, None)
+            }
         }
     }
 
+    fn connected(tx: &Self::Transport) -> bool {
+        tx.connected()
+    }
+
+    fn overflow(tx: &Self::Transport) -> OverflowPolicy {
+        tx.overflow()
+    }
+
     fn clone(tx: &Self::Transport) -> Self::Transport {
         tx.boxed()
     }
@@ -94,7 +147,8 @@ impl<M> MessageRecipient<M> for Syn
 pub struct SyncRecipientRequest<M> where M: Message + Send + 'static, M::Result: Send {
     rx: Option<Receiver<M::Result>>,
     info: Option<(Box<SyncSender<M>>, M)>,
-    timeout: Option<Timeout>,
+    timeout: Option<Delay>,
+    full: bool,
 }
 
 impl<M> SyncRecipientRequest<M>
@@ -103,12 +157,17 @@ impl<M> SyncRecipientRequest<M>
     pub fn new(rx: Option<Receiver<M::Result>>,
                info: Option<(Box<SyncSender<M>>, M)>) -> SyncRecipientRequest<M>
     {
-        SyncRecipientRequest{rx: rx, info: info, timeout: None}
+        SyncRecipientRequest{rx, info, timeout: None, full: false}
+    }
+
+    /// Request to the recipient with full mailbox, which does not block senders
+    pub(crate) fn full() -> SyncRecipientRequest<M> {
+        SyncRecipientRequest{rx: None, info: None, timeout: None, full: true}
     }
 
     /// Set message delivery timeout
     pub fn timeout(mut self, dur: Duration) -> Self {
-        self.timeout = Some(Timeout::new(dur, Arbiter::handle()).unwrap());
+        self.timeout = Some(Delay::new(dur));
         self
     }
 
@@ -132,14 +191,32 @@ impl<M> Future for SyncRecipientRequest<M>
     type Error = MailboxError;
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if self.full {
+            return Err(MailboxError::Full)
+        }
+
         if let Some((sender, msg)) = self.info.take() {
             match sender.send(msg) {
                 Ok(rx) => self.rx = Some(rx),
                 Err(SendError::Full(msg)) => {
+                    if sender.overflow() != OverflowPolicy::Block {
+                        if sender.overflow() == OverflowPolicy::DropNewest {
+                            deadletter::publish_message::<M>(DeadLetterReason::Overflow);
+                        }
+                        return Err(MailboxError::Full)
+                    }
                     self.info = Some((sender, msg));
-                    return Ok(Async::NotReady)
+                    let res = self.poll_timeout();
+                    if res.is_err() {
+                        // blocked message is dropped on timeout
+                        deadletter::publish_message::<M>(DeadLetterReason::Overflow);
+                    }
+                    return res
+                }
+                Err(SendError::Closed(_)) => {
+                    deadletter::publish_message::<M>(DeadLetterReason::Closed);
+                    return Err(MailboxError::Closed)
                 }
-                Err(SendError::Closed(_)) => return Err(MailboxError::Closed),
             }
         }
 
@@ -1,20 +1,22 @@
 //! This is copy of [sync/mpsc/](https://github.com/alexcrichton/futures-rs)
 use std::{usize, thread};
 use std::cell::Cell;
-use std::sync::atomic::AtomicUsize;
+use std::collections::VecDeque;
+use std::sync::atomic::{fence, AtomicBool, AtomicUsize};
 use std::sync::atomic::Ordering::{SeqCst, Relaxed};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
 
 use futures::task::{self, Task};
 use futures::{Async, Poll, Stream};
 use futures::sync::oneshot::{channel as sync_channel, Receiver};
 
 use actor::Actor;
+use deadletter::{self, DeadLetterReason};
 use handler::{Handler, Message};
 
-use super::{SendError, Syn, MessageDestinationTransport};
+use super::{OverflowPolicy, SendError, Syn, MessageDestinationTransport};
 use super::queue::{Queue, PopResult};
-use super::envelope::{ToEnvelope, SyncEnvelope};
+use super::envelope::{EnvelopeProxy, ToEnvelope, SyncEnvelope};
 
 
 pub trait SyncSender<M>: Send
@@ -27,6 +29,10 @@ pub trait SyncSender<M>: Send
 
     fn send(&self, msg: M) -> Result<Receiver<M::Result>, SendError<M>>;
 
+    fn overflow(&self) -> OverflowPolicy;
+
+    fn connected(&self) -> bool;
+
     fn boxed(&self) -> Box<SyncSender<M>>;
 }
 
@@ -49,6 +55,16 @@ pub struct SyncAddressSender<A: Actor> {
 
 unsafe impl<A: Actor> Sync for SyncAddressSender<A> {}
 
+/// The weak transmission end of a channel.
+///
+/// Weak sender does not keep receiver connected.
+pub struct SyncAddressWeakSender<A: Actor> {
+    inner: Weak<Inner<A>>,
+}
+
+unsafe impl<A: Actor> Send for SyncAddressWeakSender<A> {}
+unsafe impl<A: Actor> Sync for SyncAddressWeakSender<A> {}
+
 trait AssertKinds: Send + Sync + Clone {}
 
 
@@ -59,12 +75,20 @@ trait AssertKinds: Send + Sync + Clone {}
 /// `channel` method.
 pub struct SyncAddressReceiver<A: Actor> {
     inner: Arc<Inner<A>>,
+    // Messages popped off the queue, not yet returned by receiver
+    batch: VecDeque<SyncEnvelope<A>>,
 }
 
+/// Max number of messages receiver pops off the queue at once
+const MAX_BATCH: usize = 32;
+
 struct Inner<A: Actor> {
     // Max buffer size of the channel. If `0` then the channel is unbounded.
     buffer: AtomicUsize,
 
+    // Overflow policy of the channel, encoded with `encode_overflow`
+    overflow: AtomicUsize,
+
     // Internal channel state. Consists of the number of messages stored in the
     // channel as well as a flag signalling that the channel is closed.
     state: AtomicUsize,
@@ -78,10 +102,23 @@ struct Inner<A: Actor> {
     // Number of senders in existence
     num_senders: AtomicUsize,
 
+    // Number of messages pushed to the queue
+    enqueued: AtomicUsize,
+
     // Handle to the receiver's task.
     recv_task: Mutex<ReceiverTask>,
+
+    // Copy of `ReceiverTask::unparked`, senders check it without locking
+    // `recv_task`
+    recv_unparked: AtomicBool,
+
+    // Callbacks to run once the receiver is dropped, `None` after that
+    watchers: Mutex<Option<Vec<Watcher>>>,
 }
 
+/// Callback that runs once receiver gets dropped
+pub(crate) type Watcher = Box<FnMut() + Send>;
+
 // Struct representation of `Inner::state`.
 #[derive(Debug, Clone, Copy)]
 struct State {
@@ -162,14 +199,18 @@ pub fn channel<A: Actor>(buffer: usize) -> (SyncAddressSender<A>, SyncAddressRec
 
     let inner = Arc::new(Inner {
         buffer: AtomicUsize::new(buffer),
+        overflow: AtomicUsize::new(encode_overflow(OverflowPolicy::Block)),
         state: AtomicUsize::new(INIT_STATE),
         message_queue: Queue::new(),
         parked_queue: Queue::new(),
         num_senders: AtomicUsize::new(1),
+        enqueued: AtomicUsize::new(0),
         recv_task: Mutex::new(ReceiverTask {
             unparked: false,
             task: None,
         }),
+        recv_unparked: AtomicBool::new(false),
+        watchers: Mutex::new(Some(Vec::new())),
     });
 
     let tx = SyncAddressSender {
@@ -180,6 +221,7 @@ pub fn channel<A: Actor>(buffer: usize) -> (SyncAddressSender<A>, SyncAddressRec
 
     let rx = SyncAddressReceiver {
         inner: inner,
+        batch: VecDeque::new(),
     };
 
     (tx, rx)
@@ -193,6 +235,10 @@ impl<A, M> MessageDestinationTransport<Syn, A, M> for SyncAddressSender<A>
     fn send(&self, msg: M) -> Result<Receiver<M::Result>, SendError<M>> {
         SyncAddressSender::send(self, msg)
     }
+
+    fn overflow(&self) -> OverflowPolicy {
+        SyncAddressSender::overflow(self)
+    }
 }
 
 //
@@ -202,6 +248,26 @@ impl<A, M> MessageDestinationTransport<Syn, A, M> for SyncAddressSender<A>
 //
 impl<A: Actor> SyncAddressSender<A> {
 
+    /// Get weak sender, weak sender does not keep receiver connected
+    pub fn downgrade(&self) -> SyncAddressWeakSender<A> {
+        SyncAddressWeakSender { inner: Arc::downgrade(&self.inner) }
+    }
+
+    /// Run callback once receiver gets dropped, callback runs immediately
+    /// if receiver is dropped already
+    pub fn watch(&self, mut watcher: Watcher) {
+        if let Some(ref mut watchers) = *self.inner.watchers.lock().unwrap() {
+            watchers.push(watcher);
+            return
+        }
+        watcher()
+    }
+
+    /// Identity of the channel
+    pub fn id(&self) -> usize {
+        &*self.inner as *const Inner<A> as usize
+    }
+
     pub fn connected(&self) -> bool {
         let curr = self.inner.state.load(SeqCst);
         let state = decode_state(curr);
@@ -209,6 +275,16 @@ impl<A: Actor> SyncAddressSender<A> {
         state.is_open
     }
 
+    /// Overflow policy of the channel
+    pub fn overflow(&self) -> OverflowPolicy {
+        decode_overflow(self.inner.overflow.load(Relaxed))
+    }
+
+    /// Number of messages in the channel
+    pub fn pending(&self) -> usize {
+        decode_state(self.inner.state.load(SeqCst)).num_messages
+    }
+
     /// Attempts to send a message on this `Sender<A>` with blocking.
     ///
     /// This function, must be called from inside of a task.
@@ -237,8 +313,23 @@ impl<A: Actor> SyncAddressSender<A> {
         // If the channel has reached capacity, then the sender task needs to
         // be parked. This will send the task handle on the parked task queue.
         if park_self {
-            self.park(true);
-            Err(SendError::Full(msg))
+            match self.overflow() {
+                OverflowPolicy::Block => {
+                    self.park(true);
+                    Err(SendError::Full(msg))
+                }
+                OverflowPolicy::Fail | OverflowPolicy::DropNewest => Err(SendError::Full(msg)),
+                OverflowPolicy::DropOldest => {
+                    // receiver drops oldest messages
+                    if self.inc_num_messages_force().is_none() {
+                        return Err(SendError::Closed(msg))
+                    }
+                    let (tx, rx) = sync_channel();
+                    let env = <A::Context as ToEnvelope<Syn, A, M>>::pack(msg, Some(tx));
+                    self.queue_push_and_signal(env);
+                    Ok(rx)
+                }
+            }
         } else {
             let (tx, rx) = sync_channel();
             let env = <A::Context as ToEnvelope<Syn, A, M>>::pack(msg, Some(tx));
@@ -253,8 +344,9 @@ impl<A: Actor> SyncAddressSender<A> {
               M::Result: Send,
               M: Message + Send + 'static,
     {
-        // If the sender is currently blocked, reject the message
-        if !self.poll_unparked(false).is_ready() {
+        // If the sender is currently blocked, reject the message, parked
+        // sender keeps current task for wakeup
+        if !self.poll_unparked(park).is_ready() {
             return Err(SendError::Full(msg))
         }
 
@@ -264,10 +356,24 @@ impl<A: Actor> SyncAddressSender<A> {
         };
 
         if park_self {
-            if park {
-                self.park(true);
+            match self.overflow() {
+                OverflowPolicy::Block => {
+                    if park {
+                        self.park(true);
+                    }
+                    Err(SendError::Full(msg))
+                }
+                OverflowPolicy::Fail | OverflowPolicy::DropNewest => Err(SendError::Full(msg)),
+                OverflowPolicy::DropOldest => {
+                    // receiver drops oldest messages
+                    if self.inc_num_messages_force().is_none() {
+                        return Err(SendError::Closed(msg))
+                    }
+                    let env = <A::Context as ToEnvelope<Syn, A, M>>::pack(msg, None);
+                    self.queue_push_and_signal(env);
+                    Ok(())
+                }
             }
-            Err(SendError::Full(msg))
         } else {
             let env = <A::Context as ToEnvelope<Syn, A, M>>::pack(msg, None);
             self.queue_push_and_signal(env);
@@ -292,10 +398,22 @@ impl<A: Actor> SyncAddressSender<A> {
         }
     }
 
+    /// Send prepared envelope without blocking, returns `false` if
+    /// receiver is dropped.
+    pub(crate) fn do_send_envelope(&self, env: SyncEnvelope<A>) -> bool {
+        if self.inc_num_messages_force().is_none() {
+            false
+        } else {
+            self.queue_push_and_signal(env);
+            true
+        }
+    }
+
     // Push message to the queue and signal to the receiver
     fn queue_push_and_signal(&self, msg: SyncEnvelope<A>) {
         // Push the message onto the message queue
         self.inner.message_queue.push(msg);
+        self.inner.enqueued.fetch_add(1, Relaxed);
 
         // Signal to the receiver that a message has been enqueued. If the
         // receiver is parked, this will unpark the task.
@@ -353,10 +471,15 @@ impl<A: Actor> SyncAddressSender<A> {
 
     // Signal to the receiver task that a message has been enqueued
     fn signal(&self) {
-        // TODO
-        // This logic can probably be improved by guarding the lock with an
-        // atomic.
-        //
+        // Receiver that is already unparked polls the queue before parking
+        // again. The fence pairs with the fence in `try_park()`: either this
+        // sender sees that unpark signal is consumed, or the receiver sees
+        // pushed message.
+        fence(SeqCst);
+        if self.inner.recv_unparked.load(Relaxed) {
+            return;
+        }
+
         // Do this step first so that the lock is dropped when
         // `unpark` is called
         let task = {
@@ -372,6 +495,7 @@ impl<A: Actor> SyncAddressSender<A> {
             // an unpark event happened in order to avoid unnecessarily
             // parking.
             recv_task.unparked = true;
+            self.inner.recv_unparked.store(true, Relaxed);
             recv_task.task.take()
         };
 
@@ -457,11 +581,23 @@ impl<A, M> SyncSender<M> for SyncAddressSender<A>
     fn send(&self, msg: M) -> Result<Receiver<M::Result>, SendError<M>> {
         self.send(msg)
     }
+    fn overflow(&self) -> OverflowPolicy {
+        SyncAddressSender::overflow(self)
+    }
+    fn connected(&self) -> bool {
+        SyncAddressSender::connected(self)
+    }
     fn boxed(&self) -> Box<SyncSender<M>> {
         Box::new(self.clone())
     }
 }
 
+impl<A: Actor> PartialEq for SyncAddressSender<A> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
 impl<A: Actor> Clone for SyncAddressSender<A> {
     fn clone(&self) -> SyncAddressSender<A> {
         // Since this atomic op isn't actually guarding any memory and we don't
@@ -478,7 +614,11 @@ impl<A: Actor> Clone for SyncAddressSender<A> {
             debug_assert!(curr < self.inner.max_senders());
 
             let next = curr + 1;
-            let actual = self.inner.num_senders.compare_and_swap(curr, next, SeqCst);
+            let actual = match self.inner.num_senders
+                .compare_exchange(curr, next, SeqCst, SeqCst)
+            {
+                Ok(actual) | Err(actual) => actual,
+            };
 
             // The ABA problem doesn't matter here. We only care that the
             // number of senders never exceeds the maximum.
@@ -506,6 +646,50 @@ impl<A: Actor> Drop for SyncAddressSender<A> {
     }
 }
 
+impl<A: Actor> SyncAddressWeakSender<A> {
+    /// Get sender if receiver is still open
+    pub fn upgrade(&self) -> Option<SyncAddressSender<A>> {
+        let inner = match self.inner.upgrade() {
+            Some(inner) => inner,
+            None => return None,
+        };
+        if !decode_state(inner.state.load(SeqCst)).is_open {
+            return None
+        }
+
+        // this code same as Sender::clone
+        let mut curr = inner.num_senders.load(SeqCst);
+
+        loop {
+            // If the maximum number of senders has been reached, then fail
+            if curr == inner.max_senders() {
+                panic!("cannot clone `Sender` -- too many outstanding senders");
+            }
+
+            let next = curr + 1;
+            let actual = match inner.num_senders.compare_exchange(curr, next, SeqCst, SeqCst) {
+                Ok(actual) | Err(actual) => actual,
+            };
+
+            if actual == curr {
+                return Some(SyncAddressSender {
+                    inner,
+                    sender_task: Arc::new(Mutex::new(SenderTask::new())),
+                    maybe_parked: Cell::new(false),
+                });
+            }
+
+            curr = actual;
+        }
+    }
+}
+
+impl<A: Actor> Clone for SyncAddressWeakSender<A> {
+    fn clone(&self) -> SyncAddressWeakSender<A> {
+        SyncAddressWeakSender { inner: Weak::clone(&self.inner) }
+    }
+}
+
 //
 //
 // ===== impl Receiver =====
@@ -517,15 +701,45 @@ impl<A: Actor> SyncAddressReceiver<A> {
         self.inner.num_senders.load(SeqCst) != 0
     }
 
+    /// Number of messages in the channel
+    pub fn pending(&self) -> usize {
+        decode_state(self.inner.state.load(SeqCst)).num_messages
+    }
+
+    /// Number of messages sent through the channel
+    pub fn enqueued(&self) -> usize {
+        self.inner.enqueued.load(Relaxed)
+    }
+
+    /// Check if channel has messages, messages stay in the channel.
+    /// Current task is notified once message arrives.
+    pub fn poll_ready(&mut self) -> Async<()> {
+        loop {
+            if self.pending() != 0 {
+                return Async::Ready(())
+            }
+            match self.try_park() {
+                TryPark::Parked => return if self.pending() != 0 {
+                    Async::Ready(())
+                } else {
+                    Async::NotReady
+                },
+                // unpark signal could be left by consumed message, check again
+                TryPark::NotEmpty => continue,
+            }
+        }
+    }
+
     /// Set channel capacity
     ///
-    /// This method wakes up all waiting senders if new capacity is greater than current
+    /// This method wakes up all waiting senders if new capacity is greater than current.
+    /// Zero capacity means unbounded channel.
     pub fn set_capacity(&mut self, cap: usize) {
         let buffer = self.inner.buffer.load(Relaxed);
         self.inner.buffer.store(cap, Relaxed);
 
         // wake up all
-        if cap > buffer {
+        if cap == 0 || (buffer != 0 && cap > buffer) {
             loop {
                 match unsafe { self.inner.parked_queue.pop() } {
                     PopResult::Data(task) => {
@@ -544,6 +758,25 @@ impl<A: Actor> SyncAddressReceiver<A> {
         }
     }
 
+    /// Set channel overflow policy
+    ///
+    /// Blocked senders get woken up if new policy does not block senders
+    pub fn set_overflow(&mut self, policy: OverflowPolicy) {
+        self.inner.overflow.store(encode_overflow(policy), Relaxed);
+
+        if policy != OverflowPolicy::Block {
+            loop {
+                match unsafe { self.inner.parked_queue.pop() } {
+                    PopResult::Data(task) => {
+                        task.lock().unwrap().notify();
+                    }
+                    PopResult::Empty => return,
+                    PopResult::Inconsistent => thread::yield_now(),
+                }
+            }
+        }
+    }
+
     /// Get sender side of the channel
     pub fn sender(&mut self) -> SyncAddressSender<A> {
         // this code same as Sender::clone
@@ -556,7 +789,11 @@ impl<A: Actor> SyncAddressReceiver<A> {
             }
 
             let next = curr + 1;
-            let actual = self.inner.num_senders.compare_and_swap(curr, next, SeqCst);
+            let actual = match self.inner.num_senders
+                .compare_exchange(curr, next, SeqCst, SeqCst)
+            {
+                Ok(actual) | Err(actual) => actual,
+            };
 
             // The ABA problem doesn't matter here. We only care that the
             // number of senders never exceeds the maximum.
@@ -573,11 +810,15 @@ impl<A: Actor> SyncAddressReceiver<A> {
     }
 
     fn next_message(&mut self) -> Async<Option<SyncEnvelope<A>>> {
-        // Pop off a message
+        if let Some(msg) = self.batch.pop_front() {
+            return Async::Ready(Some(msg));
+        }
+
+        // Pop off a batch of messages
         loop {
-            match unsafe { self.inner.message_queue.pop() } {
-                PopResult::Data(msg) => {
-                    return Async::Ready(Some(msg));
+            match unsafe { self.inner.message_queue.pop_batch(&mut self.batch, MAX_BATCH) } {
+                PopResult::Data(_) => {
+                    return Async::Ready(self.batch.pop_front());
                 }
                 PopResult::Empty => {
                     // The queue is empty, return NotReady
@@ -629,6 +870,9 @@ impl<A: Actor> SyncAddressReceiver<A> {
         if recv_task.unparked {
             // Consume the `unpark` signal without actually parking
             recv_task.unparked = false;
+            self.inner.recv_unparked.store(false, Relaxed);
+            drop(recv_task);
+            fence(SeqCst);
             return TryPark::NotEmpty;
         }
 
@@ -636,20 +880,15 @@ impl<A: Actor> SyncAddressReceiver<A> {
         TryPark::Parked
     }
 
-    fn dec_num_messages(&self) {
-        let mut curr = self.inner.state.load(SeqCst);
-
-        loop {
-            let mut state = decode_state(curr);
-
-            state.num_messages -= 1;
-
-            let next = encode_state(&state);
-            match self.inner.state.compare_exchange(curr, next, SeqCst, SeqCst) {
-                Ok(_) => break,
-                Err(actual) => curr = actual,
-            }
-        }
+    // Decrement number of messages. Returns `true` if channel still holds
+    // more messages than its capacity.
+    fn dec_num_messages(&self) -> bool {
+        // open flag is stored in the left-most bit, channel holds at least
+        // one message, so subtraction does not affect the flag
+        let prev = decode_state(self.inner.state.fetch_sub(1, SeqCst));
+        debug_assert!(prev.num_messages > 0, "number of messages underflow");
+        let buffer = self.inner.buffer.load(Relaxed);
+        buffer != 0 && prev.num_messages > buffer
     }
 }
 
@@ -687,7 +926,18 @@ impl<A: Actor> Stream for SyncAddressReceiver<A> {
             self.unpark_one();
 
             // Decrement number of messages
-            self.dec_num_messages();
+            let overflowed = self.dec_num_messages();
+
+            // Drop oldest messages if channel is overflowed
+            if overflowed && self.inner.overflow.load(Relaxed) ==
+                encode_overflow(OverflowPolicy::DropOldest)
+            {
+                if let Some(ref msg) = msg {
+                    deadletter::publish(
+                        msg.message_type(), msg.message_name(), DeadLetterReason::Overflow);
+                }
+                continue
+            }
 
             // Return the message
             return Ok(Async::Ready(msg));
@@ -725,9 +975,15 @@ impl<A: Actor> Drop for SyncAddressReceiver<A> {
             }
         }
 
-        // Drain the channel of all pending messages
-        while self.next_message().is_ready() {
-            // ...
+        // Drain the channel of all pending messages, actor does not handle them
+        while let Async::Ready(Some(msg)) = self.next_message() {
+            deadletter::publish(msg.message_type(), msg.message_name(), DeadLetterReason::Closed);
+        }
+
+        // Notify watchers, callbacks run without lock
+        let watchers = self.inner.watchers.lock().unwrap().take();
+        for mut watcher in watchers.unwrap_or_default() {
+            watcher()
         }
     }
 }
@@ -760,6 +1016,24 @@ fn decode_state(num: usize) -> State {
     }
 }
 
+fn encode_overflow(policy: OverflowPolicy) -> usize {
+    match policy {
+        OverflowPolicy::Block => 0,
+        OverflowPolicy::Fail => 1,
+        OverflowPolicy::DropOldest => 2,
+        OverflowPolicy::DropNewest => 3,
+    }
+}
+
+fn decode_overflow(num: usize) -> OverflowPolicy {
+    match num {
+        1 => OverflowPolicy::Fail,
+        2 => OverflowPolicy::DropOldest,
+        3 => OverflowPolicy::DropNewest,
+        _ => OverflowPolicy::Block,
+    }
+}
+
 fn encode_state(state: &State) -> usize {
     let mut num = state.num_messages;
 
@@ -1,16 +1,17 @@
 use std::time::Duration;
 use futures::{Async, Future, Poll};
 use futures::unsync::oneshot::{Receiver, Sender};
-use tokio_core::reactor::Timeout;
+use clock::Delay;
 
 use actor::{Actor, AsyncContext};
-use arbiter::Arbiter;
+use deadletter::{self, DeadLetterReason};
 use handler::{Handler, Message};
 
 use super::{Request, Recipient};
 use super::{ToEnvelope, UnsyncEnvelope, MessageEnvelope};
 use super::{Destination, MessageDestination, MessageRecipient, SendError, MailboxError};
-use super::unsync_channel::{UnsyncSender, UnsyncAddrSender};
+use super::OverflowPolicy;
+use super::unsync_channel::{UnsyncSender, UnsyncAddrSender, UnsyncAddrWeakSender};
 
 
 /// Unsync destination of the actor
@@ -22,11 +23,28 @@ impl<A: Actor> Destination<A> for Unsync
     where A::Context: AsyncContext<A>
 {
     type Transport = UnsyncAddrSender<A>;
+    type WeakTransport = UnsyncAddrWeakSender<A>;
 
     /// Indicates if actor is still alive
     fn connected(tx: &Self::Transport) -> bool {
         tx.connected()
     }
+
+    fn id(tx: &Self::Transport) -> usize {
+        tx.id()
+    }
+
+    fn pending(tx: &Self::Transport) -> usize {
+        tx.pending()
+    }
+
+    fn downgrade(tx: &Self::Transport) -> Self::WeakTransport {
+        tx.downgrade()
+    }
+
+    fn upgrade(tx: &Self::WeakTransport) -> Option<Self::Transport> {
+        tx.upgrade()
+    }
 }
 
 impl<A, M> MessageDestination<A, M> for Unsync
@@ -38,14 +56,33 @@ impl<A, M> MessageDestination<A, M> for Unsync
     type ResultReceiver = Receiver<M::Result>;
 
     fn do_send(tx: &Self::Transport, msg: M) {
-        let _ = tx.do_send(msg);
+        if let Err(SendError::Closed(_)) = tx.do_send(msg) {
+            deadletter::publish_message::<M>(DeadLetterReason::Closed);
+        }
+    }
+
+    fn forward(tx: &Self::Transport, msg: M, result: Sender<M::Result>) {
+        let env = <A::Context as ToEnvelope<Self, A, M>>::pack(msg, Some(result));
+        if !tx.do_send_envelope(env) {
+            deadletter::publish_message::<M>(DeadLetterReason::Closed);
+        }
     }
 
     fn send(tx: &Self::Transport, msg: M) -> Request<Self, A, M> {
         match tx.send(msg) {
             Ok(rx) => Request::new(Some(rx), None),
-            Err(SendError::Full(msg)) => Request::new(None, Some((tx.clone(), msg))),
-            Err(SendError::Closed(_)) => Request::new(None, None),
+            Err(SendError::Full(msg)) => if tx.overflow() == OverflowPolicy::Block {
+                Request::new(None, Some((tx.clone(), msg)))
+            } else {
+                if tx.overflow() == OverflowPolicy::DropNewest {
+                    deadletter::publish_message::<M>(DeadLetterReason::Overflow);
+                }
+                Request::full()
+            },
+            Err(SendError::Closed(_)) => {
+                deadletter::publish_message::<M>(DeadLetterReason::Closed);
+                Request::new(None, None)
+            }
         }
     }
 
@@ -74,10 +111,18 @@ impl<M> MessageRecipient<M> for Unsync where M: Message + 'static
     fn send(tx: &Self::Transport, msg: M) -> UnsyncRecipientRequest<M> {
         match tx.send(msg) {
             Ok(rx) => UnsyncRecipientRequest::new(Some(rx), None),
-            Err(SendError::Full(msg)) =>
-                UnsyncRecipientRequest::new(None, Some((tx.boxed(), msg))),
-            Err(SendError::Closed(_)) =>
-                UnsyncRecipientRequest::new(None, None),
+            Err(SendError::Full(msg)) => if tx.overflow() == OverflowPolicy::Block {
+                UnsyncRecipientRequest::new(None, Some((tx.boxed(), msg)))
+            } else {
+                if tx.overflow() == OverflowPolicy::DropNewest {
+                    deadletter::publish_message::<M>(DeadLetterReason::Overflow);
+                }
+                UnsyncRecipientRequest::full()
+            },
+            Err(SendError::Closed(_)) => {
+                deadletter::publish_message::<M>(DeadLetterReason::Closed);
+                UnsyncRecipientRequest::new(None, None)
+            }
         }
     }
 
@@ -85,6 +130,14 @@ impl<M> MessageRecipient<M> for Unsync where M: Message + 'static
         tx.try_send(msg)
     }
 
+    fn connected(tx: &Self::Transport) -> bool {
+        tx.connected()
+    }
+
+    fn overflow(tx: &Self::Transport) -> OverflowPolicy {
+        tx.overflow()
+    }
+
     fn clone(tx: &Self::Transport) -> Self::Transport {
         tx.boxed()
     }
@@ -96,7 +149,8 @@ pub struct UnsyncRecipientRequest<M> where M: Message + 'static
 {
     rx: Option<Receiver<M::Result>>,
     info: Option<(Box<UnsyncSender<M>>, M)>,
-    timeout: Option<Timeout>,
+    timeout: Option<Delay>,
+    full: bool,
 }
 
 impl<M> UnsyncRecipientRequest<M> where M: Message + 'static
@@ -104,12 +158,17 @@ impl<M> UnsyncRecipientRequest<M> where M: Message + 'static
     pub fn new(rx: Option<Receiver<M::Result>>,
                info: Option<(Box<UnsyncSender<M>>, M)>) -> UnsyncRecipientRequest<M>
     {
-        UnsyncRecipientRequest{rx: rx, info: info, timeout: None}
+        UnsyncRecipientRequest{rx, info, timeout: None, full: false}
+    }
+
+    /// Request to the recipient with full mailbox, which does not block senders
+    pub(crate) fn full() -> UnsyncRecipientRequest<M> {
+        UnsyncRecipientRequest{rx: None, info: None, timeout: None, full: true}
     }
 
     /// Set message delivery timeout
     pub fn timeout(mut self, dur: Duration) -> Self {
-        self.timeout = Some(Timeout::new(dur, Arbiter::handle()).unwrap());
+        self.timeout = Some(Delay::new(dur));
         self
     }
 
@@ -132,14 +191,32 @@ impl<M> Future for UnsyncRecipientRequest<M> where M: Message + 'static,
     type Error = MailboxError;
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if self.full {
+            return Err(MailboxError::Full)
+        }
+
         if let Some((sender, msg)) = self.info.take() {
             match sender.send(msg) {
                 Ok(rx) => self.rx = Some(rx),
                 Err(SendError::Full(msg)) => {
+                    if sender.overflow() != OverflowPolicy::Block {
+                        if sender.overflow() == OverflowPolicy::DropNewest {
+                            deadletter::publish_message::<M>(DeadLetterReason::Overflow);
+                        }
+                        return Err(MailboxError::Full)
+                    }
                     self.info = Some((sender, msg));
-                    return Ok(Async::NotReady)
+                    let res = self.poll_timeout();
+                    if res.is_err() {
+                        // blocked message is dropped on timeout
+                        deadletter::publish_message::<M>(DeadLetterReason::Overflow);
+                    }
+                    return res
+                }
+                Err(SendError::Closed(_)) => {
+                    deadletter::publish_message::<M>(DeadLetterReason::Closed);
+                    return Err(MailboxError::Closed)
                 }
-                Err(SendError::Closed(_)) => return Err(MailboxError::Closed),
             }
         }
 
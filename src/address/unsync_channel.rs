@@ -15,9 +15,10 @@ use futures::task::{self, Task};
 use futures::unsync::oneshot::{channel, Receiver};
 
 use actor::{Actor, AsyncContext};
+use deadletter::{self, DeadLetterReason};
 use handler::{Handler, Message};
-use super::{SendError, Unsync, ToEnvelope, MessageDestinationTransport};
-use super::envelope::UnsyncEnvelope;
+use super::{OverflowPolicy, SendError, Unsync, ToEnvelope, MessageDestinationTransport};
+use super::envelope::{EnvelopeProxy, UnsyncEnvelope};
 
 
 pub trait UnsyncSender<M: Message + 'static> {
@@ -27,14 +28,21 @@ pub trait UnsyncSender<M: Message + 'static> {
 
     fn send(&self, msg: M) -> Result<Receiver<M::Result>, SendError<M>>;
 
+    fn overflow(&self) -> OverflowPolicy;
+
+    fn connected(&self) -> bool;
+
     fn boxed(&self) -> Box<UnsyncSender<M>>;
 }
 
 struct Shared<A: Actor> {
     buffer: VecDeque<UnsyncEnvelope<A>>,
     capacity: usize,
+    overflow: OverflowPolicy,
     blocked_senders: VecDeque<Task>,
     blocked_recv: Option<Task>,
+    senders: usize,
+    enqueued: usize,
 }
 
 /// The transmission end of a channel.
@@ -42,6 +50,7 @@ struct Shared<A: Actor> {
 /// This is created by the `channel` function.
 pub struct UnsyncAddrSender<A> where A: Actor, A::Context: AsyncContext<A> {
     shared: Weak<RefCell<Shared<A>>>,
+    id: usize,
 }
 
 impl<A, M> MessageDestinationTransport<Unsync, A, M> for UnsyncAddrSender<A>
@@ -52,9 +61,23 @@ impl<A, M> MessageDestinationTransport<Unsync, A, M> for UnsyncAddrSender<A>
     fn send(&self, msg: M) -> Result<Receiver<M::Result>, SendError<M>> {
         UnsyncAddrSender::send(self, msg)
     }
+
+    fn overflow(&self) -> OverflowPolicy {
+        UnsyncAddrSender::overflow(self)
+    }
 }
 
 impl<A> UnsyncAddrSender<A> where A: Actor, A::Context: AsyncContext<A> {
+    /// Get weak sender, weak sender does not keep receiver connected
+    pub fn downgrade(&self) -> UnsyncAddrWeakSender<A> {
+        UnsyncAddrWeakSender { shared: Weak::clone(&self.shared), id: self.id }
+    }
+
+    /// Identity of the channel
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
     pub fn connected(&self) -> bool {
         match self.shared.upgrade() {
             Some(_) => true,
@@ -62,6 +85,22 @@ impl<A> UnsyncAddrSender<A> where A: Actor, A::Context: AsyncContext<A> {
         }
     }
 
+    /// Overflow policy of the channel
+    pub fn overflow(&self) -> OverflowPolicy {
+        match self.shared.upgrade() {
+            Some(shared) => shared.borrow().overflow,
+            None => OverflowPolicy::Block,
+        }
+    }
+
+    /// Number of messages in the channel
+    pub fn pending(&self) -> usize {
+        match self.shared.upgrade() {
+            Some(shared) => shared.borrow().buffer.len(),
+            None => 0,
+        }
+    }
+
     /// Try to put message to a receiver queue, if queue is full
     /// return message back.
     ///
@@ -77,6 +116,7 @@ impl<A> UnsyncAddrSender<A> where A: Actor, A::Context: AsyncContext<A> {
 
         shared.buffer.push_back(
             <A::Context as ToEnvelope<Unsync, A, M>>::pack(msg, None));
+        shared.enqueued += 1;
         if let Some(task) = shared.blocked_recv.take() {
             drop(shared);
             task.notify();
@@ -84,6 +124,24 @@ impl<A> UnsyncAddrSender<A> where A: Actor, A::Context: AsyncContext<A> {
         Ok(())
     }
 
+    /// Put prepared envelope to a receiver queue, returns `false` if
+    /// receiver is dropped.
+    pub(crate) fn do_send_envelope(&self, env: UnsyncEnvelope<A>) -> bool {
+        let shared = match self.shared.upgrade() {
+            Some(shared) => shared,
+            None => return false,
+        };
+        let mut shared = shared.borrow_mut();
+
+        shared.buffer.push_back(env);
+        shared.enqueued += 1;
+        if let Some(task) = shared.blocked_recv.take() {
+            drop(shared);
+            task.notify();
+        }
+        true
+    }
+
     /// Try to put message to a receiver queue, if queue is full
     /// return message back.
     ///
@@ -98,20 +156,28 @@ impl<A> UnsyncAddrSender<A> where A: Actor, A::Context: AsyncContext<A> {
         };
         let mut shared = shared.borrow_mut();
 
-        if shared.capacity == 0 || shared.buffer.len() < shared.capacity {
-            shared.buffer.push_back(
-            <A::Context as ToEnvelope<Unsync, A, M>>::pack(msg, None));
-            if let Some(task) = shared.blocked_recv.take() {
-                drop(shared);
-                task.notify();
-            }
-            Ok(())
-        } else {
-            if park {
-                shared.blocked_senders.push_back(task::current());
+        let accept = shared.capacity == 0 || shared.buffer.len() < shared.capacity;
+        match shared.overflow {
+            _ if accept => (),
+            OverflowPolicy::Block => {
+                if park {
+                    shared.blocked_senders.push_back(task::current());
+                }
+                return Err(SendError::Full(msg))
             }
-            Err(SendError::Full(msg))
+            OverflowPolicy::Fail | OverflowPolicy::DropNewest => return Err(SendError::Full(msg)),
+            // receiver drops oldest messages
+            OverflowPolicy::DropOldest => (),
+        }
+
+        shared.buffer.push_back(
+            <A::Context as ToEnvelope<Unsync, A, M>>::pack(msg, None));
+        shared.enqueued += 1;
+        if let Some(task) = shared.blocked_recv.take() {
+            drop(shared);
+            task.notify();
         }
+        Ok(())
     }
 
     /// Try to put message to a receiver queue, if queue is full
@@ -127,19 +193,27 @@ impl<A> UnsyncAddrSender<A> where A: Actor, A::Context: AsyncContext<A> {
         };
         let mut shared = shared.borrow_mut();
 
-        if shared.capacity == 0 || shared.buffer.len() < shared.capacity {
-            let (tx, rx) = channel();
-            shared.buffer.push_back(
-                <A::Context as ToEnvelope<Unsync, A, M>>::pack(msg, Some(tx)));
-            if let Some(task) = shared.blocked_recv.take() {
-                drop(shared);
-                task.notify();
+        let accept = shared.capacity == 0 || shared.buffer.len() < shared.capacity;
+        match shared.overflow {
+            _ if accept => (),
+            OverflowPolicy::Block => {
+                shared.blocked_senders.push_back(task::current());
+                return Err(SendError::Full(msg))
             }
-            Ok(rx)
-        } else {
-            shared.blocked_senders.push_back(task::current());
-            Err(SendError::Full(msg))
+            OverflowPolicy::Fail | OverflowPolicy::DropNewest => return Err(SendError::Full(msg)),
+            // receiver drops oldest messages
+            OverflowPolicy::DropOldest => (),
+        }
+
+        let (tx, rx) = channel();
+        shared.buffer.push_back(
+            <A::Context as ToEnvelope<Unsync, A, M>>::pack(msg, Some(tx)));
+        shared.enqueued += 1;
+        if let Some(task) = shared.blocked_recv.take() {
+            drop(shared);
+            task.notify();
         }
+        Ok(rx)
     }
 
     /// Get `Sender` for a specific message type
@@ -163,6 +237,12 @@ impl<A, M> UnsyncSender<M> for UnsyncAddrSender<A>
     fn send(&self, msg: M) -> Result<Receiver<M::Result>, SendError<M>> {
         self.send(msg)
     }
+    fn overflow(&self) -> OverflowPolicy {
+        UnsyncAddrSender::overflow(self)
+    }
+    fn connected(&self) -> bool {
+        UnsyncAddrSender::connected(self)
+    }
     fn boxed(&self) -> Box<UnsyncSender<M>> {
         Box::new(self.clone())
     }
@@ -170,7 +250,10 @@ impl<A, M> UnsyncSender<M> for UnsyncAddrSender<A>
 
 impl<A> Clone for UnsyncAddrSender<A> where A: Actor, A::Context: AsyncContext<A> {
     fn clone(&self) -> Self {
-        UnsyncAddrSender { shared: Weak::clone(&self.shared) }
+        if let Some(shared) = self.shared.upgrade() {
+            shared.borrow_mut().senders += 1;
+        }
+        UnsyncAddrSender { shared: Weak::clone(&self.shared), id: self.id }
     }
 }
 
@@ -180,16 +263,49 @@ impl<A> Drop for UnsyncAddrSender<A> where A: Actor, A::Context: AsyncContext<A>
             Some(shared) => shared,
             None => return,
         };
-        if Rc::weak_count(&shared) == 1 {
-            let task = { shared.borrow_mut().blocked_recv.take() };
-            if let Some(task) = task {
-                // Wake up receiver as its stream has ended
-                task.notify();
+        let task = {
+            let mut shared = shared.borrow_mut();
+            shared.senders -= 1;
+            if shared.senders == 0 {
+                shared.blocked_recv.take()
+            } else {
+                None
+            }
+        };
+        if let Some(task) = task {
+            // Wake up receiver as its stream has ended
+            task.notify();
+        }
+    }
+}
+
+/// The weak transmission end of a channel.
+///
+/// Weak sender does not keep receiver connected.
+pub struct UnsyncAddrWeakSender<A> where A: Actor, A::Context: AsyncContext<A> {
+    shared: Weak<RefCell<Shared<A>>>,
+    id: usize,
+}
+
+impl<A> UnsyncAddrWeakSender<A> where A: Actor, A::Context: AsyncContext<A> {
+    /// Get sender if receiver still exists
+    pub fn upgrade(&self) -> Option<UnsyncAddrSender<A>> {
+        match self.shared.upgrade() {
+            Some(shared) => {
+                shared.borrow_mut().senders += 1;
+                Some(UnsyncAddrSender { shared: Weak::clone(&self.shared), id: self.id })
             }
+            None => None,
         }
     }
 }
 
+impl<A> Clone for UnsyncAddrWeakSender<A> where A: Actor, A::Context: AsyncContext<A> {
+    fn clone(&self) -> Self {
+        UnsyncAddrWeakSender { shared: Weak::clone(&self.shared), id: self.id }
+    }
+}
+
 /// The receiving end of a channel which implements the `Stream` trait.
 ///
 /// This is created by the `channel` function.
@@ -210,19 +326,24 @@ impl<A> UnsyncAddrReceiver<A> where A: Actor, A::Context: AsyncContext<A> {
             state: Rc::new(RefCell::new(Shared {
                 buffer: VecDeque::new(),
                 capacity: cap,
+                overflow: OverflowPolicy::Block,
                 blocked_senders: VecDeque::new(),
-                blocked_recv: None }))
+                blocked_recv: None,
+                senders: 0,
+                enqueued: 0 }))
         }
     }
 
     /// Check if receiver connected to senders
     pub fn connected(&self) -> bool {
-        Rc::weak_count(&self.state) != 0
+        self.state.borrow().senders != 0
     }
 
     /// Get the sender half
     pub fn sender(&mut self) -> UnsyncAddrSender<A> {
-        UnsyncAddrSender{shared: Rc::downgrade(&self.state)}
+        self.state.borrow_mut().senders += 1;
+        UnsyncAddrSender{shared: Rc::downgrade(&self.state),
+                         id: &*self.state as *const RefCell<Shared<A>> as usize}
     }
 
     /// Get channel capacity
@@ -230,15 +351,42 @@ impl<A> UnsyncAddrReceiver<A> where A: Actor, A::Context: AsyncContext<A> {
         self.state.borrow().capacity
     }
 
+    /// Number of messages in the channel
+    pub fn pending(&self) -> usize {
+        self.state.borrow().buffer.len()
+    }
+
+    /// Number of messages sent through the channel
+    pub fn enqueued(&self) -> usize {
+        self.state.borrow().enqueued
+    }
+
+    /// Check if channel has messages, messages stay in the channel.
+    /// Current task is notified once message arrives.
+    pub fn poll_ready(&mut self) -> Async<()> {
+        let mut shared = self.state.borrow_mut();
+        if shared.buffer.is_empty() {
+            shared.blocked_recv = Some(task::current());
+            Async::NotReady
+        } else {
+            Async::Ready(())
+        }
+    }
+
     /// Set channel capacity
     ///
-    /// This method also wakes up waiting senders
+    /// This method also wakes up waiting senders. Zero capacity means
+    /// unbounded channel.
     pub fn set_capacity(&mut self, size: usize) {
         let mut shared = self.state.borrow_mut();
         shared.capacity = size;
 
         // wake up senders
-        if shared.buffer.len() < shared.capacity {
+        if shared.capacity == 0 {
+            for task in shared.blocked_senders.drain(..) {
+                task.notify();
+            }
+        } else if shared.buffer.len() < shared.capacity {
             for _ in 0..shared.capacity-shared.buffer.len() {
                 if let Some(task) = shared.blocked_senders.pop_front() {
                     task.notify();
@@ -248,6 +396,25 @@ impl<A> UnsyncAddrReceiver<A> where A: Actor, A::Context: AsyncContext<A> {
             }
         }
     }
+
+    /// Get channel overflow policy
+    pub fn overflow(&self) -> OverflowPolicy {
+        self.state.borrow().overflow
+    }
+
+    /// Set channel overflow policy
+    ///
+    /// Blocked senders get woken up if new policy does not block senders
+    pub fn set_overflow(&mut self, policy: OverflowPolicy) {
+        let mut shared = self.state.borrow_mut();
+        shared.overflow = policy;
+
+        if policy != OverflowPolicy::Block {
+            for task in shared.blocked_senders.drain(..) {
+                task.notify();
+            }
+        }
+    }
 }
 
 impl<A> Stream for UnsyncAddrReceiver<A> where A: Actor, A::Context: AsyncContext<A> {
@@ -255,13 +422,29 @@ impl<A> Stream for UnsyncAddrReceiver<A> where A: Actor, A::Context: AsyncContex
     type Error = ();
 
     fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
-        if let Some(shared) = Rc::get_mut(&mut self.state) {
+        // Drop oldest messages if channel is overflowed, messages get dropped
+        // after borrow ends, they could hold addresses of this channel
+        let evicted: Vec<_> = {
+            let mut shared = self.state.borrow_mut();
+            if shared.overflow == OverflowPolicy::DropOldest && shared.capacity != 0 {
+                let evict = shared.buffer.len().saturating_sub(shared.capacity);
+                shared.buffer.drain(..evict).collect()
+            } else {
+                Vec::new()
+            }
+        };
+        for msg in evicted {
+            deadletter::publish(
+                msg.message_type(), msg.message_name(), DeadLetterReason::Overflow);
+        }
+
+        let mut shared = self.state.borrow_mut();
+        if shared.senders == 0 {
             // All senders have been dropped, so drain the buffer and end the
             // stream.
-            return Ok(Async::Ready(shared.borrow_mut().buffer.pop_front()));
+            return Ok(Async::Ready(shared.buffer.pop_front()));
         }
 
-        let mut shared = self.state.borrow_mut();
         if let Some(msg) = shared.buffer.pop_front() {
             if let Some(task) = shared.blocked_senders.pop_front() {
                 drop(shared);
@@ -277,8 +460,16 @@ impl<A> Stream for UnsyncAddrReceiver<A> where A: Actor, A::Context: AsyncContex
 
 impl<A> Drop for UnsyncAddrReceiver<A> where A: Actor, A::Context: AsyncContext<A> {
     fn drop(&mut self) {
-        for task in &self.state.borrow().blocked_senders {
-            task.notify();
+        // pending messages get dropped after borrow ends, actor does not handle them
+        let pending: Vec<_> = {
+            let mut shared = self.state.borrow_mut();
+            for task in &shared.blocked_senders {
+                task.notify();
+            }
+            shared.buffer.drain(..).collect()
+        };
+        for msg in pending {
+            deadletter::publish(msg.message_type(), msg.message_name(), DeadLetterReason::Closed);
         }
     }
 }
@@ -328,6 +519,17 @@ mod tests {
             let _ = s2.send(Ping);
             assert_eq!(recv.state.borrow().buffer.len(), 2);
 
+            recv.set_capacity(2);
+            let _ = s1.send(Ping);
+            assert_eq!(recv.state.borrow().buffer.len(), 2);
+            assert_eq!(recv.state.borrow().blocked_senders.len(), 1);
+
+            // unbounded
+            recv.set_capacity(0);
+            assert_eq!(recv.state.borrow().blocked_senders.len(), 0);
+            let _ = s1.send(Ping);
+            assert_eq!(recv.state.borrow().buffer.len(), 3);
+
             Arbiter::system().do_send(actix::msgs::SystemExit(0));
             Ok(())
         });
@@ -1,18 +1,27 @@
 use std;
 use std::thread;
-use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use libc;
 use uuid::Uuid;
-use tokio_core::reactor::{Core, Handle};
-use futures::sync::oneshot::{channel, Sender};
+use tokio_core::reactor::{Core, Handle, Interval};
+use futures::{task, Async, Future, IntoFuture, Poll, Stream};
+use futures::task::Task;
+use futures::sync::oneshot::{channel, Receiver, Sender};
 
 use actor::{Actor, AsyncContext};
 use address::{sync_channel, Addr, Syn, Unsync};
 use context::Context;
 use mailbox::DEFAULT_CAPACITY;
-use msgs::{Execute, StartActor, StopArbiter};
+use msgs::{Execute, StartActor, StopArbiter, StopArbiterGraceful, SystemExit};
 use handler::Handler;
 use registry::{Registry, SystemRegistry};
 use system::{System, RegisterArbiter, UnregisterArbiter};
+use wheel;
 
 thread_local!(
     static HND: RefCell<Option<Handle>> = RefCell::new(None);
@@ -24,8 +33,18 @@ thread_local!(
     static SYSARB: RefCell<Option<Addr<Syn, Arbiter>>> = RefCell::new(None);
     static SYSNAME: RefCell<Option<String>> = RefCell::new(None);
     static SYSREG: RefCell<Option<SystemRegistry>> = RefCell::new(None);
+    static NAMED: RefCell<Option<NamedArbiters>> = RefCell::new(None);
+    static CTXID: Cell<usize> = Cell::new(0);
+    static CONTEXTS: RefCell<HashMap<usize, Task>> = RefCell::new(HashMap::new());
+    static DRAINING: Cell<bool> = Cell::new(false);
 );
 
+/// Interval of checking running actors during graceful arbiter stop
+pub(crate) const DRAIN_POLL_INTERVAL: u64 = 5;
+
+/// Named arbiters of the system
+type NamedArbiters = Arc<Mutex<HashMap<String, (Uuid, Addr<Syn, Arbiter>)>>>;
+
 /// Event loop controller
 ///
 /// Arbiter controls event loop in it's thread. Each arbiter runs in separate
@@ -40,9 +59,14 @@ impl Actor for Arbiter {
     type Context = Context<Self>;
 
     fn started(&mut self, ctx: &mut Context<Self>) {
-        // register arbiter within system
-        Arbiter::system().do_send(
-            RegisterArbiter(self.id.simple().to_string(), ctx.address()));
+        // arbiter keeps running until its event loop stops, graceful stop
+        // does not drain it
+        ctx.exclude_from_drain();
+        if !self.sys {
+            // register arbiter within system
+            Arbiter::system().do_send(
+                RegisterArbiter(self.id.simple().to_string(), ctx.address()));
+        }
     }
 }
 
@@ -50,52 +74,63 @@ impl Arbiter {
 
     /// Spawn new thread and run event loop in spawned thread.
     /// Returns address of newly created arbiter.
+    ///
+    /// Use `Arbiter::builder()` to configure arbiter's thread.
     pub fn new<T: Into<String>>(name: T) -> Addr<Syn, Arbiter> {
-        let (tx, rx) = std::sync::mpsc::channel();
-
-        let id = Uuid::new_v4();
-        let sys = Arbiter::system();
-        let sys_name = Arbiter::system_name();
-        let sys_arbiter = Arbiter::system_arbiter();
-        let sys_registry = Arbiter::system_registry().clone();
-        let name = format!("arbiter:{:?}:{:?}",
-                           id.hyphenated().to_string(), name.into());
-
-        let _ = thread::Builder::new().name(name.clone()).spawn(move|| {
-            let mut core = Core::new().unwrap();
-
-            let (stop_tx, stop_rx) = channel();
-            HND.with(|cell| *cell.borrow_mut() = Some(core.handle()));
-            STOP.with(|cell| *cell.borrow_mut() = Some(stop_tx));
-            NAME.with(|cell| *cell.borrow_mut() = Some(name));
-            REG.with(|cell| *cell.borrow_mut() = Some(Registry::new()));
-
-            // system
-            SYS.with(|cell| *cell.borrow_mut() = Some(sys));
-            SYSARB.with(|cell| *cell.borrow_mut() = Some(sys_arbiter));
-            SYSNAME.with(|cell| *cell.borrow_mut() = Some(sys_name));
-            SYSREG.with(|cell| *cell.borrow_mut() = Some(sys_registry));
-
-            // start arbiter
-            let (addr, saddr) = Actor::start(Arbiter {sys: false, id: id});
-            ADDR.with(|cell| *cell.borrow_mut() = Some(addr));
+        Arbiter::builder(name).start()
+    }
 
-            if tx.send(saddr).is_err() {
-                error!("Can not start Arbiter, remote side is dead");
-            } else {
-                // run loop
-                let _ = match core.run(stop_rx) {
-                    Ok(code) => code,
-                    Err(_) => 1,
-                };
-            }
+    /// Create builder for new arbiter
+    ///
+    /// Builder allows to configure arbiter before its thread starts.
+    ///
+    /// ```rust
+    /// # extern crate actix;
+    /// use actix::prelude::*;
+    /// use actix::PanicPolicy;
+    ///
+    /// fn main() {
+    ///     let sys = System::new("test");
+    ///
+    ///     let addr = Arbiter::builder("worker")
+    ///         .panic_policy(PanicPolicy::Ignore)
+    ///         .start();
+    /// #   addr.do_send(actix::msgs::StopArbiter(0));
+    /// #   Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    ///
+    ///     sys.run();
+    /// }
+    /// ```
+    pub fn builder<T: Into<String>>(name: T) -> ArbiterBuilder {
+        ArbiterBuilder {
+            name: name.into(),
+            named: false,
+            panic_policy: None,
+            thread_name: None,
+            stack_size: None,
+            core: None,
+        }
+    }
 
-            // unregister arbiter
-            Arbiter::system().do_send(
-                UnregisterArbiter(id.simple().to_string()));
-        });
+    /// Spawn new named arbiter. Returns address of newly created arbiter.
+    ///
+    /// Address of named arbiter could be found with `Arbiter::lookup()`
+    /// from any arbiter of the system. If arbiter with the same name is
+    /// running, it gets replaced in lookup table. Arbiter is removed
+    /// from lookup table once it stops.
+    pub fn new_named<T: Into<String>>(name: T) -> Addr<Syn, Arbiter> {
+        Arbiter::builder(name).named().start()
+    }
 
-        rx.recv().unwrap()
+    /// Find address of named arbiter
+    ///
+    /// Returns `None` if arbiter with this name is not running or
+    /// system is not running in current thread.
+    pub fn lookup(name: &str) -> Option<Addr<Syn, Arbiter>> {
+        NAMED.with(|cell| match *cell.borrow() {
+            Some(ref named) => named.lock().unwrap().get(name).map(|item| item.1.clone()),
+            None => None,
+        })
     }
 
     pub(crate) fn new_system(name: String) -> Core {
@@ -104,6 +139,7 @@ impl Arbiter {
         REG.with(|cell| *cell.borrow_mut() = Some(Registry::new()));
         NAME.with(|cell| *cell.borrow_mut() = Some(name));
         SYSREG.with(|cell| *cell.borrow_mut() = Some(SystemRegistry::new()));
+        NAMED.with(|cell| *cell.borrow_mut() = Some(Arc::new(Mutex::new(HashMap::new()))));
 
         // start arbiter
         let (addr, sys_addr) = Actor::start(
@@ -167,6 +203,17 @@ impl Arbiter {
         })
     }
 
+    /// Returns system registry, if system is running in current thread
+    pub(crate) fn try_system_registry() -> Option<&'static SystemRegistry> {
+        SYSREG.with(|cell| cell.borrow().as_ref().map(|reg| unsafe{
+            std::mem::transmute::<&SystemRegistry, &'static SystemRegistry>(reg)}))
+    }
+
+    /// Returns current arbiter's name, if arbiter is running in current thread
+    pub(crate) fn try_name() -> Option<String> {
+        NAME.with(|cell| cell.borrow().clone())
+    }
+
     /// This function returns current event loop's handle,
     pub fn handle() -> &'static Handle {
         HND.with(|cell| match *cell.borrow() {
@@ -176,6 +223,9 @@ impl Arbiter {
     }
 
     /// This function returns arbiter's registry,
+    ///
+    /// Arbiter services are unique per arbiter, `Arbiter::registry().get()`
+    /// starts service in current arbiter on first request.
     pub fn registry() -> &'static Registry {
         REG.with(|cell| match *cell.borrow() {
             Some(ref reg) => unsafe{std::mem::transmute(reg)},
@@ -183,6 +233,42 @@ impl Arbiter {
         })
     }
 
+    /// Register context running in current arbiter, returns context id
+    ///
+    /// This method must be called from within context's task.
+    pub(crate) fn register_context() -> usize {
+        let id = CTXID.with(|cell| {
+            let id = cell.get() + 1;
+            cell.set(id);
+            id
+        });
+        CONTEXTS.with(|cell| cell.borrow_mut().insert(id, task::current()));
+        id
+    }
+
+    /// Unregister stopped context
+    pub(crate) fn unregister_context(id: usize) {
+        CONTEXTS.with(|cell| cell.borrow_mut().remove(&id));
+    }
+
+    /// Check if arbiter is stopping gracefully
+    pub(crate) fn draining() -> bool {
+        DRAINING.with(|cell| cell.get())
+    }
+
+    /// Start graceful stop, each context stops once its mailbox is empty
+    pub(crate) fn start_draining() {
+        DRAINING.with(|cell| cell.set(true));
+        CONTEXTS.with(|cell| for task in cell.borrow().values() {
+            task.notify();
+        });
+    }
+
+    /// Check if all contexts of the arbiter are stopped
+    pub(crate) fn drained() -> bool {
+        CONTEXTS.with(|cell| cell.borrow().is_empty())
+    }
+
     /// Start new arbiter and then start actor in created arbiter.
     /// Returns `Addr<Syn, A>` of created actor.
     pub fn start<A, F>(f: F) -> Addr<Syn, A>
@@ -208,6 +294,225 @@ impl Arbiter {
     }
 }
 
+/// Policy of handling panics in arbiter's thread
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PanicPolicy {
+    /// Drop all actors and futures of the arbiter and start new event loop.
+    /// Arbiter's address stays valid.
+    RestartThread,
+    /// Stop system with specified exit code
+    StopSystem(i32),
+    /// Catch panic and continue to run event loop. Only panicked actor
+    /// or future is dropped.
+    Ignore,
+}
+
+/// Arbiter builder
+///
+/// Created with `Arbiter::builder()`.
+pub struct ArbiterBuilder {
+    name: String,
+    named: bool,
+    panic_policy: Option<PanicPolicy>,
+    thread_name: Option<String>,
+    stack_size: Option<usize>,
+    core: Option<usize>,
+}
+
+impl ArbiterBuilder {
+    /// Register arbiter in lookup table, see `Arbiter::new_named()`
+    pub fn named(mut self) -> Self {
+        self.named = true;
+        self
+    }
+
+    /// Set policy of handling panics in arbiter's thread
+    ///
+    /// By default panic is not caught and it takes down arbiter's thread.
+    pub fn panic_policy(mut self, policy: PanicPolicy) -> Self {
+        self.panic_policy = Some(policy);
+        self
+    }
+
+    /// Set name of arbiter's thread
+    ///
+    /// By default thread name is `arbiter:<id>:<name>`.
+    pub fn thread_name<T: Into<String>>(mut self, name: T) -> Self {
+        self.thread_name = Some(name.into());
+        self
+    }
+
+    /// Set stack size of arbiter's thread, in bytes
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.stack_size = Some(size);
+        self
+    }
+
+    /// Pin arbiter's thread to specific cpu core
+    ///
+    /// Core affinity is supported on linux only, on other platforms
+    /// and for non existing core warning is logged and thread is not pinned.
+    pub fn core_affinity(mut self, core: usize) -> Self {
+        self.core = Some(core);
+        self
+    }
+
+    /// Spawn new thread and run event loop in spawned thread.
+    /// Returns address of newly created arbiter.
+    pub fn start(self) -> Addr<Syn, Arbiter> {
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        let id = Uuid::new_v4();
+        let sys = Arbiter::system();
+        let sys_name = Arbiter::system_name();
+        let sys_arbiter = Arbiter::system_arbiter();
+        let sys_registry = Arbiter::system_registry().arbiter_registry();
+        let sys_named = NAMED.with(|cell| cell.borrow().clone());
+        let named = self.named;
+        let policy = self.panic_policy;
+        let arb_name = self.name;
+        let name = format!("arbiter:{:?}:{:?}", id.hyphenated().to_string(), arb_name);
+        let cpu = self.core;
+
+        let mut builder = thread::Builder::new()
+            .name(self.thread_name.unwrap_or_else(|| name.clone()));
+        if let Some(size) = self.stack_size {
+            builder = builder.stack_size(size);
+        }
+
+        let _ = builder.spawn(move|| {
+            if let Some(cpu) = cpu {
+                if !set_core_affinity(cpu) {
+                    warn!("Can not pin arbiter {:?} to cpu core {}", arb_name, cpu);
+                }
+            }
+            let mut core = Core::new().unwrap();
+
+            let (stop_tx, stop_rx) = channel();
+            HND.with(|cell| *cell.borrow_mut() = Some(core.handle()));
+            STOP.with(|cell| *cell.borrow_mut() = Some(stop_tx));
+            NAME.with(|cell| *cell.borrow_mut() = Some(name));
+            REG.with(|cell| *cell.borrow_mut() = Some(Registry::new()));
+
+            // system
+            SYS.with(|cell| *cell.borrow_mut() = Some(sys));
+            SYSARB.with(|cell| *cell.borrow_mut() = Some(sys_arbiter));
+            SYSNAME.with(|cell| *cell.borrow_mut() = Some(sys_name));
+            SYSREG.with(|cell| *cell.borrow_mut() = Some(sys_registry));
+            NAMED.with(|cell| *cell.borrow_mut() = sys_named.clone());
+
+            // start arbiter, arbiter's context lives outside of event loop
+            // so it survives event loop restart
+            let mut ctx = Context::new(Some(Arbiter {sys: false, id}));
+            let addr = ctx.unsync_address();
+            let saddr = ctx.sync_address();
+            ADDR.with(|cell| *cell.borrow_mut() = Some(addr));
+
+            // register named arbiter
+            if named {
+                if let Some(ref named) = sys_named {
+                    named.lock().unwrap().insert(arb_name.clone(), (id, saddr.clone()));
+                }
+            }
+
+            if tx.send(saddr).is_err() {
+                error!("Can not start Arbiter, remote side is dead");
+            } else {
+                // run loop
+                let mut fut = ArbiterLoop {ctx: Some(ctx), stop: stop_rx};
+                loop {
+                    let policy = match policy {
+                        Some(policy) => policy,
+                        None => {
+                            let _ = core.run(&mut fut);
+                            break
+                        }
+                    };
+                    let res = panic::catch_unwind(AssertUnwindSafe(|| core.run(&mut fut)));
+                    if res.is_ok() {
+                        break
+                    }
+                    match policy {
+                        PanicPolicy::Ignore => {
+                            error!("Arbiter {:?} panicked, continue", arb_name);
+                        }
+                        PanicPolicy::RestartThread => {
+                            error!("Arbiter {:?} panicked, restarting event loop", arb_name);
+                            let old = std::mem::replace(&mut core, Core::new().unwrap());
+                            HND.with(|cell| *cell.borrow_mut() = Some(core.handle()));
+                            REG.with(|cell| *cell.borrow_mut() = Some(Registry::new()));
+                            wheel::reset();
+                            drop(old);
+                        }
+                        PanicPolicy::StopSystem(code) => {
+                            error!("Arbiter {:?} panicked, stopping system", arb_name);
+                            Arbiter::system().do_send(SystemExit(code));
+                            break
+                        }
+                    }
+                }
+            }
+
+            // unregister arbiter
+            if named {
+                if let Some(ref named) = sys_named {
+                    let mut named = named.lock().unwrap();
+                    if named.get(&arb_name).map(|item| item.0 == id).unwrap_or(false) {
+                        named.remove(&arb_name);
+                    }
+                }
+            }
+            Arbiter::system().do_send(
+                UnregisterArbiter(id.simple().to_string()));
+        });
+
+        rx.recv().unwrap()
+    }
+}
+
+/// Pin current thread to cpu core
+#[cfg(target_os = "linux")]
+fn set_core_affinity(core: usize) -> bool {
+    if core >= libc::CPU_SETSIZE as usize {
+        return false
+    }
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(core, &mut set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_core_affinity(_: usize) -> bool {
+    false
+}
+
+/// Arbiter's event loop future, runs arbiter actor until stop code is received
+struct ArbiterLoop {
+    ctx: Option<Context<Arbiter>>,
+    stop: Receiver<i32>,
+}
+
+impl Future for ArbiterLoop {
+    type Item = i32;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<i32, ()> {
+        let done = match self.ctx {
+            Some(ref mut ctx) => match ctx.poll() {
+                Ok(Async::NotReady) => false,
+                _ => true,
+            },
+            None => false,
+        };
+        if done {
+            self.ctx.take();
+        }
+        self.stop.poll().map_err(|_| ())
+    }
+}
+
 impl Handler<StopArbiter> for Arbiter {
     type Result = ();
 
@@ -227,6 +532,40 @@ impl Handler<StopArbiter> for Arbiter {
     }
 }
 
+impl Handler<StopArbiterGraceful> for Arbiter {
+    type Result = ();
+
+    fn handle(&mut self, msg: StopArbiterGraceful, _: &mut Context<Self>)
+    {
+        if self.sys {
+            warn!("System arbiter received `StopArbiterGraceful` message.
+                  To shutdown system, `SystemExitGraceful` message should be
+                  send to `Addr<Syn, System>`");
+            return
+        }
+
+        Arbiter::start_draining();
+
+        let code = msg.0;
+        let deadline = Instant::now() + msg.1;
+        let interval = Interval::new(
+            Duration::from_millis(DRAIN_POLL_INTERVAL), Arbiter::handle()).unwrap();
+        Arbiter::handle().spawn(
+            interval
+                .map_err(|_| ())
+                .take_while(move |_| Ok(!Arbiter::drained() && Instant::now() < deadline))
+                .for_each(|_| Ok(()))
+                .then(move |_| {
+                    STOP.with(|cell| {
+                        if let Some(stop) = cell.borrow_mut().take() {
+                            let _ = stop.send(code);
+                        }
+                    });
+                    Ok(())
+                }));
+    }
+}
+
 impl<A> Handler<StartActor<A>> for Arbiter where A: Actor<Context=Context<A>> {
     type Result = Addr<Syn, A>;
 
@@ -243,3 +582,145 @@ impl<I: Send, E: Send> Handler<Execute<I, E>> for Arbiter {
         msg.exec()
     }
 }
+
+/// Placement strategy of `ArbiterPool`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Placement {
+    /// Actors and futures are placed to arbiters in turn
+    RoundRobin,
+    /// Actors and futures are placed to arbiter with fewest running
+    /// actors and futures, first arbiter wins tie
+    LeastLoaded,
+}
+
+/// Pool of arbiters
+///
+/// Pool owns set of arbiter threads and places started actors and futures
+/// to arbiters according to `Placement` strategy. Arbiters of the pool
+/// get stopped once pool is dropped.
+///
+/// ```rust
+/// # extern crate actix;
+/// use actix::prelude::*;
+///
+/// struct Worker;
+///
+/// impl Actor for Worker {
+///     type Context = Context<Self>;
+/// }
+///
+/// fn main() {
+///     let sys = System::new("test");
+///
+///     let mut pool = ArbiterPool::new(4);
+///     let workers: Vec<Addr<Syn, _>> = (0..8).map(|_| pool.start(|_| Worker)).collect();
+/// #   Arbiter::system().do_send(actix::msgs::SystemExit(0));
+///
+///     sys.run();
+/// }
+/// ```
+pub struct ArbiterPool {
+    arbiters: Vec<(Addr<Syn, Arbiter>, Arc<AtomicUsize>)>,
+    placement: Placement,
+    next: usize,
+}
+
+impl ArbiterPool {
+    /// Start pool of `size` arbiters with round robin placement
+    pub fn new(size: usize) -> ArbiterPool {
+        ArbiterPool::with_placement(size, Placement::RoundRobin)
+    }
+
+    /// Start pool of `size` arbiters with specific placement strategy
+    pub fn with_placement(size: usize, placement: Placement) -> ArbiterPool {
+        assert!(size > 0, "Arbiter pool size has to be greater than zero");
+        ArbiterPool {
+            arbiters: (0..size).map(|_| (Arbiter::new("pool"), Arc::new(AtomicUsize::new(0))))
+                .collect(),
+            placement,
+            next: 0,
+        }
+    }
+
+    /// Number of arbiters in the pool
+    pub fn size(&self) -> usize {
+        self.arbiters.len()
+    }
+
+    /// Addresses of arbiters of the pool
+    pub fn arbiters(&self) -> Vec<Addr<Syn, Arbiter>> {
+        self.arbiters.iter().map(|item| item.0.clone()).collect()
+    }
+
+    /// Number of running actors and futures of each arbiter
+    pub fn load(&self) -> Vec<usize> {
+        self.arbiters.iter().map(|item| item.1.load(Ordering::Relaxed)).collect()
+    }
+
+    fn place(&mut self) -> usize {
+        match self.placement {
+            Placement::RoundRobin => {
+                let idx = self.next % self.arbiters.len();
+                self.next = idx + 1;
+                idx
+            }
+            Placement::LeastLoaded => {
+                let load = self.load();
+                (0..load.len()).min_by_key(|idx| load[*idx]).unwrap()
+            }
+        }
+    }
+
+    /// Start actor in one of the arbiters of the pool.
+    /// Returns `Addr<Syn, A>` of created actor.
+    pub fn start<A, F>(&mut self, f: F) -> Addr<Syn, A>
+        where A: Actor<Context=Context<A>>,
+              F: FnOnce(&mut A::Context) -> A + Send + 'static
+    {
+        let (stx, srx) = sync_channel::channel(DEFAULT_CAPACITY);
+        let idx = self.place();
+        let load = Arc::clone(&self.arbiters[idx].1);
+        load.fetch_add(1, Ordering::Relaxed);
+
+        self.arbiters[idx].0.do_send::<Execute>(
+            Execute::new(move || {
+                let mut ctx = Context::with_receiver(None, srx);
+                let act = f(&mut ctx);
+                ctx.set_actor(act);
+                Arbiter::handle().spawn(ctx.then(move |_| {
+                    load.fetch_sub(1, Ordering::Relaxed);
+                    Ok(())
+                }));
+                Ok(())
+            }));
+
+        Addr::new(stx)
+    }
+
+    /// Execute future created by `f` in one of the arbiters of the pool.
+    pub fn spawn_fn<F, R>(&mut self, f: F)
+        where F: FnOnce() -> R + Send + 'static,
+              R: IntoFuture<Item=(), Error=()> + 'static,
+    {
+        let idx = self.place();
+        let load = Arc::clone(&self.arbiters[idx].1);
+        load.fetch_add(1, Ordering::Relaxed);
+
+        self.arbiters[idx].0.do_send::<Execute>(
+            Execute::new(move || {
+                Arbiter::handle().spawn(f().into_future().then(move |res| {
+                    load.fetch_sub(1, Ordering::Relaxed);
+                    res
+                }));
+                Ok(())
+            }));
+    }
+}
+
+impl Drop for ArbiterPool {
+    fn drop(&mut self) {
+        for item in &self.arbiters {
+            item.0.do_send(StopArbiter(0));
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Switching actor behavior at runtime
+//!
+//! [`Behavior`](struct.Behavior.html) is a set of message handlers. Actor
+//! pushes behavior with
+//! [`ctx.become_behavior()`](trait.BehaviorContext.html#tymethod.become_behavior)
+//! and returns to the previous one with
+//! [`ctx.unbecome()`](trait.BehaviorContext.html#tymethod.unbecome).
+//! Behaviors form a stack, messages are handled by the top behavior only.
+//!
+//! Actor's `Handler` passes message to the current behavior with
+//! [`behavior::handle()`](fn.handle.html). If current behavior does not
+//! handle the message, or actor has no behavior pushed, message is
+//! returned back to handler. Restarted actor starts without behaviors.
+//!
+//! `become` is a reserved word in Rust, so context method is called
+//! `become_behavior()`.
+//!
+//! ```rust
+//! # extern crate actix;
+//! use actix::prelude::*;
+//! use actix::behavior::{self, Behavior, BehaviorContext};
+//!
+//! struct Hello;
+//!
+//! impl Message for Hello {
+//!     type Result = ();
+//! }
+//!
+//! struct Data(Vec<u8>);
+//!
+//! impl Message for Data {
+//!     type Result = usize;
+//! }
+//!
+//! struct Connection;
+//!
+//! impl Actor for Connection {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! fn streaming() -> Behavior<Connection> {
+//!     Behavior::new("streaming")
+//!         .on(|_: &mut Connection, msg: Data, _: &mut Context<Connection>| msg.0.len())
+//! }
+//!
+//! impl Handler<Hello> for Connection {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, msg: Hello, ctx: &mut Context<Self>) {
+//!         if behavior::handle(self, msg, ctx).is_err() {
+//!             // handshake is done, stream data
+//!             ctx.become_behavior(streaming());
+//!         }
+//!     }
+//! }
+//!
+//! impl Handler<Data> for Connection {
+//!     type Result = MessageResult<Data>;
+//!
+//!     fn handle(&mut self, msg: Data, ctx: &mut Context<Self>) -> Self::Result {
+//!         // data before handshake is ignored
+//!         MessageResult(behavior::handle(self, msg, ctx).unwrap_or(0))
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use actor::Actor;
+use context::Context;
+use handler::Message;
+
+type HandlerFn<A, M> = Rc<RefCell<Box<FnMut(&mut A, M, &mut Context<A>)
+                                         -> <M as Message>::Result>>>;
+
+/// Set of message handlers
+pub struct Behavior<A> where A: Actor<Context=Context<A>> {
+    name: &'static str,
+    handlers: HashMap<TypeId, Box<Any>>,
+    act: PhantomData<A>,
+}
+
+impl<A> Behavior<A> where A: Actor<Context=Context<A>> {
+    /// Behavior without handlers, `name` is used for logging
+    pub fn new(name: &'static str) -> Behavior<A> {
+        Behavior{name, handlers: HashMap::new(), act: PhantomData}
+    }
+
+    /// Handle messages of type `M` with `f`
+    pub fn on<M, F>(mut self, f: F) -> Self
+        where M: Message + 'static,
+              M::Result: 'static,
+              F: FnMut(&mut A, M, &mut Context<A>) -> M::Result + 'static
+    {
+        let f: HandlerFn<A, M> = Rc::new(RefCell::new(Box::new(f)));
+        self.handlers.insert(TypeId::of::<M>(), Box::new(f));
+        self
+    }
+
+    /// Name of the behavior
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn handler<M>(&self) -> Option<HandlerFn<A, M>>
+        where M: Message + 'static, M::Result: 'static
+    {
+        self.handlers.get(&TypeId::of::<M>())
+            .and_then(|f| f.downcast_ref::<HandlerFn<A, M>>())
+            .cloned()
+    }
+}
+
+/// Behavior stack of context's actor
+struct Behaviors<A> where A: Actor<Context=Context<A>> {
+    stack: Vec<Behavior<A>>,
+}
+
+impl<A> Default for Behaviors<A> where A: Actor<Context=Context<A>> {
+    fn default() -> Self {
+        Behaviors{stack: Vec::new()}
+    }
+}
+
+/// Behavior switching methods of actor context
+pub trait BehaviorContext<A> where A: Actor<Context=Context<A>> {
+    /// Push `behavior`, it handles messages until it is popped or other
+    /// behavior is pushed
+    fn become_behavior(&mut self, behavior: Behavior<A>);
+
+    /// Pop current behavior, previous behavior becomes active again.
+    ///
+    /// Returns `false` if there is no behavior to pop.
+    fn unbecome(&mut self) -> bool;
+
+    /// Name of the current behavior
+    fn behavior(&mut self) -> Option<&'static str>;
+}
+
+impl<A> BehaviorContext<A> for Context<A> where A: Actor<Context=Context<A>> {
+    fn become_behavior(&mut self, behavior: Behavior<A>) {
+        self.extension::<Behaviors<A>>().stack.push(behavior);
+    }
+
+    fn unbecome(&mut self) -> bool {
+        self.extension::<Behaviors<A>>().stack.pop().is_some()
+    }
+
+    fn behavior(&mut self) -> Option<&'static str> {
+        self.extension::<Behaviors<A>>().stack.last().map(|b| b.name)
+    }
+}
+
+/// Handle message with the current behavior
+///
+/// Message is returned back if current behavior does not handle it.
+pub fn handle<A, M>(act: &mut A, msg: M, ctx: &mut Context<A>) -> Result<M::Result, M>
+    where A: Actor<Context=Context<A>>, M: Message + 'static, M::Result: 'static
+{
+    let f = ctx.extension::<Behaviors<A>>().stack.last().and_then(|b| b.handler::<M>());
+    match f {
+        Some(f) => Ok((*f.borrow_mut())(act, msg, ctx)),
+        None => Err(msg),
+    }
+}
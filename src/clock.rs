@@ -0,0 +1,302 @@
+//! Time source of actor timers
+//!
+//! Actor timers (`run_later()`, `run_interval()`, `notify_later()`), request
+//! timeouts and supervisor restart delays are created with `Delay` and
+//! `Interval` from this module. By default they are kept in a timer wheel
+//! shared by all actors of the arbiter and driven by arbiter's event loop
+//! and wall time, so creating and cancelling timers is cheap even with
+//! thousands of them pending. `TestClock` replaces wall time with virtual time
+//! for all timers created in current thread, virtual time moves only with
+//! explicit `TestClock::advance()` call. This makes timer heavy tests fast
+//! and deterministic.
+//!
+//! ```rust
+//! # extern crate actix;
+//! # extern crate futures;
+//! use std::time::Duration;
+//! use futures::Future;
+//! use actix::prelude::*;
+//! use actix::clock::TestClock;
+//!
+//! struct Timer;
+//!
+//! impl Actor for Timer {
+//!     type Context = Context<Self>;
+//!
+//!     fn started(&mut self, ctx: &mut Context<Self>) {
+//!         ctx.run_later(Duration::from_secs(3600), |_, _| {
+//!             Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!         });
+//!     }
+//! }
+//!
+//! struct Started;
+//!
+//! impl Message for Started {
+//!     type Result = ();
+//! }
+//!
+//! impl Handler<Started> for Timer {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, _: Started, _: &mut Context<Self>) {}
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!     let clock = TestClock::install();
+//!
+//!     let addr: Addr<Unsync, _> = Timer.start();
+//!     // timer fires on next iteration of the event loop, no need to wait an hour
+//!     Arbiter::handle().spawn(addr.send(Started).then(move |_| {
+//!         clock.advance(Duration::from_secs(3600));
+//!         Ok(())
+//!     }));
+//!     sys.run();
+//! }
+//! ```
+use std::fmt;
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use futures::{task, Async, Future, Poll, Stream};
+use futures::task::Task;
+
+use wheel::Timer;
+
+thread_local!(
+    static CLOCK: RefCell<Option<Arc<Mutex<VirtualTime>>>> = RefCell::new(None));
+
+struct VirtualTime {
+    start: Instant,
+    /// Wall time at clock installation
+    wall: SystemTime,
+    elapsed: Duration,
+    tasks: Vec<Task>,
+}
+
+impl VirtualTime {
+    fn register(&mut self) {
+        if !self.tasks.iter().any(|t| t.will_notify_current()) {
+            self.tasks.push(task::current());
+        }
+    }
+}
+
+fn virtual_time() -> Option<Arc<Mutex<VirtualTime>>> {
+    CLOCK.with(|clock| clock.borrow().clone())
+}
+
+/// Current time of the clock
+///
+/// Returns virtual time if `TestClock` is installed in current thread,
+/// otherwise wall time.
+pub fn now() -> Instant {
+    match virtual_time() {
+        Some(time) => {
+            let time = time.lock().unwrap();
+            time.start + time.elapsed
+        }
+        None => Instant::now(),
+    }
+}
+
+/// Current wall time of the clock
+///
+/// Returns virtual wall time if `TestClock` is installed in current thread,
+/// otherwise system time. Schedules and cron expressions use this time.
+pub fn system_now() -> SystemTime {
+    match virtual_time() {
+        Some(time) => {
+            let time = time.lock().unwrap();
+            time.wall + time.elapsed
+        }
+        None => SystemTime::now(),
+    }
+}
+
+/// Virtual clock for tests
+///
+/// Clock is installed per thread, timers that get created in this thread
+/// after `TestClock::install()` call use virtual time. Clock could be
+/// advanced from any thread, timers that are due after `advance()` call
+/// fire on next iteration of their event loop.
+#[derive(Clone)]
+pub struct TestClock {
+    time: Arc<Mutex<VirtualTime>>,
+}
+
+impl TestClock {
+    /// Install new virtual clock in current thread
+    ///
+    /// Previously installed clock gets replaced, timers of the old clock
+    /// keep using it. Virtual wall time starts at current system time.
+    pub fn install() -> TestClock {
+        TestClock::install_at(SystemTime::now())
+    }
+
+    /// Install new virtual clock with virtual wall time starting at `wall`
+    ///
+    /// Fixed start makes tests of schedules and cron expressions
+    /// deterministic.
+    pub fn install_at(wall: SystemTime) -> TestClock {
+        let time = Arc::new(Mutex::new(VirtualTime {
+            start: Instant::now(), wall, elapsed: Duration::new(0, 0), tasks: Vec::new()}));
+        CLOCK.with(|clock| *clock.borrow_mut() = Some(Arc::clone(&time)));
+        TestClock{time}
+    }
+
+    /// Virtual clock installed in current thread
+    pub fn current() -> Option<TestClock> {
+        virtual_time().map(|time| TestClock{time})
+    }
+
+    /// Check if both handles refer to the same clock
+    pub(crate) fn same(&self, other: &TestClock) -> bool {
+        Arc::ptr_eq(&self.time, &other.time)
+    }
+
+    /// Remove virtual clock from current thread
+    ///
+    /// Timers that get created afterwards use wall time again.
+    pub fn uninstall() {
+        CLOCK.with(|clock| clock.borrow_mut().take());
+    }
+
+    /// Move virtual time forward
+    pub fn advance(&self, dur: Duration) {
+        let tasks = {
+            let mut time = self.time.lock().unwrap();
+            time.elapsed += dur;
+            time.tasks.drain(..).collect::<Vec<_>>()
+        };
+        for task in tasks {
+            task.notify();
+        }
+    }
+
+    /// Virtual time elapsed since clock installation
+    pub fn elapsed(&self) -> Duration {
+        self.time.lock().unwrap().elapsed
+    }
+
+    /// Current virtual time
+    pub fn now(&self) -> Instant {
+        let time = self.time.lock().unwrap();
+        time.start + time.elapsed
+    }
+}
+
+enum DelayKind {
+    Real(Timer),
+    Virtual(Arc<Mutex<VirtualTime>>, Duration),
+}
+
+/// Future that resolves after specified duration
+#[must_use = "futures do nothing unless polled"]
+pub struct Delay {
+    kind: DelayKind,
+}
+
+impl Delay {
+    /// Create new delay, real delay is registered with timer wheel
+    /// of current arbiter
+    pub fn new(dur: Duration) -> Delay {
+        let kind = match virtual_time() {
+            Some(time) => {
+                let deadline = time.lock().unwrap().elapsed + dur;
+                DelayKind::Virtual(time, deadline)
+            }
+            None => DelayKind::Real(Timer::new(dur)),
+        };
+        Delay{kind}
+    }
+}
+
+impl fmt::Debug for Delay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Delay")
+    }
+}
+
+impl Future for Delay {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        match self.kind {
+            DelayKind::Real(ref mut timer) => if timer.poll() {
+                Ok(Async::Ready(()))
+            } else {
+                Ok(Async::NotReady)
+            },
+            DelayKind::Virtual(ref time, deadline) => {
+                let mut time = time.lock().unwrap();
+                if time.elapsed >= deadline {
+                    Ok(Async::Ready(()))
+                } else {
+                    time.register();
+                    Ok(Async::NotReady)
+                }
+            }
+        }
+    }
+}
+
+enum IntervalKind {
+    Real(Timer, Duration),
+    Virtual(Arc<Mutex<VirtualTime>>, Duration, Duration),
+}
+
+/// Stream that yields item every specified duration
+#[must_use = "streams do nothing unless polled"]
+pub struct Interval {
+    kind: IntervalKind,
+}
+
+impl Interval {
+    /// Create new interval, real interval is registered with timer wheel
+    /// of current arbiter
+    pub fn new(dur: Duration) -> Interval {
+        let kind = match virtual_time() {
+            Some(time) => {
+                let next = time.lock().unwrap().elapsed + dur;
+                IntervalKind::Virtual(time, next, dur)
+            }
+            None => IntervalKind::Real(Timer::new(dur), dur),
+        };
+        Interval{kind}
+    }
+}
+
+impl fmt::Debug for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Interval")
+    }
+}
+
+impl Stream for Interval {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<()>, ()> {
+        match self.kind {
+            IntervalKind::Real(ref mut timer, dur) => if timer.poll() {
+                timer.reset(dur);
+                Ok(Async::Ready(Some(())))
+            } else {
+                Ok(Async::NotReady)
+            },
+            IntervalKind::Virtual(ref time, ref mut next, dur) => {
+                let mut time = time.lock().unwrap();
+                if time.elapsed >= *next {
+                    *next += dur;
+                    Ok(Async::Ready(Some(())))
+                } else {
+                    time.register();
+                    Ok(Async::NotReady)
+                }
+            }
+        }
+    }
+}
@@ -1,16 +1,35 @@
 use std::{mem, fmt};
-use futures::{Future, Poll};
+use std::any::TypeId;
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use futures::{Async, Future, Poll};
 use tokio_core::reactor::Handle;
 
 use fut::ActorFuture;
 use actor::{Actor, Supervised,
             ActorState, ActorContext, AsyncContext, SpawnHandle};
-use address::{SyncAddressReceiver, Addr, Syn, Unsync};
+use address::{SyncAddressReceiver, Addr, EnvelopeProxy, OverflowPolicy, Recipient,
+              Syn, SyncEnvelope, Unsync};
+use address::sync_channel::Watcher;
+use arbiter::Arbiter;
+use clock;
 use contextimpl::ContextImpl;
+use deadletter::DroppedMessage;
+use handler::{self, Handler, Message, MessageResponse, MessageResult};
+use mailbox::Priority;
+use middleware::{MessageMeta, Middleware, Middlewares};
+use msgs::{Exited, MailboxMetrics, MailboxMonitored, MailboxReport, MailboxStats, Passivate,
+           Terminated};
+use path::ActorPath;
+use span::{self, CorrelationId};
+use supervisor::{RestartReason, SupervisedContext};
 
 /// Actor execution context
 pub struct Context<A> where A: Actor<Context=Context<A>> {
     inner: ContextImpl<A>,
+    trap: Option<fn(&mut A, Exited, &mut Context<A>)>,
 }
 
 impl<A> ActorContext for Context<A> where A: Actor<Context=Self> {
@@ -26,6 +45,10 @@ impl<A> ActorContext for Context<A> where A: Actor<Context=Self> {
     fn state(&self) -> ActorState {
         self.inner.state()
     }
+    #[inline]
+    fn middlewares(&mut self) -> Option<&mut Middlewares> {
+        Some(self.inner.middlewares())
+    }
 }
 
 impl<A> AsyncContext<A> for Context<A> where A: Actor<Context=Self> {
@@ -53,6 +76,18 @@ impl<A> AsyncContext<A> for Context<A> where A: Actor<Context=Self> {
         self.inner.cancel_future(handle)
     }
 
+    #[doc(hidden)]
+    #[inline]
+    fn stream_error(&mut self) {
+        self.inner.stream_error()
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    fn stream_paused(&self) -> bool {
+        self.inner.stream_paused()
+    }
+
     #[doc(hidden)]
     #[inline]
     fn unsync_address(&mut self) -> Addr<Unsync, A> {
@@ -77,19 +112,225 @@ impl<A> Context<A> where A: Actor<Context=Self> {
 
     /// Set mailbox capacity
     ///
-    /// By default mailbox capacity is 16 messages.
+    /// By default mailbox capacity is 16 messages. `send()` requests to an actor
+    /// with full mailbox wait until actor processes pending messages,
+    /// `try_send()` fails with `SendError::Full`. Zero capacity means unbounded mailbox.
     pub fn set_mailbox_capacity(&mut self, cap: usize) {
         self.inner.set_mailbox_capacity(cap)
     }
 
+    /// Returns mailbox capacity
+    pub fn mailbox_capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Set mailbox overflow policy
+    ///
+    /// Policy defines what happens with new messages if mailbox is full.
+    /// By default `send()` requests wait until actor processes pending messages.
+    pub fn set_mailbox_overflow(&mut self, policy: OverflowPolicy) {
+        self.inner.set_mailbox_overflow(policy)
+    }
+
+    /// Returns mailbox overflow policy
+    pub fn mailbox_overflow(&self) -> OverflowPolicy {
+        self.inner.mailbox_overflow()
+    }
+
+    /// Set stream high-water mark
+    ///
+    /// Streams registered with `add_stream()` and `add_message_stream()` are not
+    /// polled while number of pending messages in actor's mailbox reaches
+    /// high-water mark, stream polling resumes once pending messages are processed.
+    /// By default high-water mark is zero, which disables check.
+    pub fn set_stream_high_watermark(&mut self, mark: usize) {
+        self.inner.set_stream_high_watermark(mark)
+    }
+
+    /// Returns stream high-water mark
+    pub fn stream_high_watermark(&self) -> usize {
+        self.inner.stream_high_watermark()
+    }
+
+    /// Process messages of type `M` according to message's priority
+    ///
+    /// Once any message type is prioritized, all pending messages are moved from
+    /// address channels to context's priority queue, so mailbox capacity does not
+    /// limit number of pending messages.
+    pub fn prioritize<M>(&mut self) where M: Priority + 'static, A: Handler<M> {
+        self.inner.prioritize::<M>()
+    }
+
+    /// Register middleware, it is called around every message handler
+    /// of the actor. Middleware is dropped on restart.
+    pub fn add_middleware<M: Middleware>(&mut self, middleware: M) {
+        self.inner.middlewares().add(Box::new(middleware))
+    }
+
+    /// Defer message, it is handled again once `unstash_all()` is called.
+    ///
+    /// Actor that is not ready yet, e.g. waits for a database connection,
+    /// stashes messages instead of dropping them. Response to a request is
+    /// the result of the handler that stashed the message, result of the
+    /// handler of replayed message is dropped. Stashed messages are kept
+    /// when supervised actor restarts.
+    pub fn stash<M>(&mut self, msg: M) where A: Handler<M>, M: Message + 'static {
+        self.inner.stash(msg)
+    }
+
+    /// Replay stashed messages in order they were stashed, before any other
+    /// pending message
+    pub fn unstash_all(&mut self) {
+        self.inner.unstash_all()
+    }
+
+    /// Number of stashed messages
+    pub fn stashed(&self) -> usize {
+        self.inner.stashed()
+    }
+
+    /// Handle messages of type `M` even while context waits for futures.
+    ///
+    /// Context that waits does not process its mailbox. If awaited future
+    /// depends on a message sent to this actor, e.g. on a reply of other
+    /// actor, the message never gets handled. Messages of receivable types
+    /// are handled while context waits, messages of other types stay
+    /// in mailbox in order they were received. Waiting context looks for
+    /// receivable messages among first mailbox capacity pending messages,
+    /// senders are blocked by the rest as usual.
+    pub fn receive_while_waiting<M>(&mut self) where A: Handler<M>, M: Message + 'static {
+        self.inner.receive_while_waiting::<M>()
+    }
+
+    /// Suspend message processing until message of type `M` is handled.
+    ///
+    /// Messages of other types stay in mailbox and are processed afterwards,
+    /// in order they were received.
+    pub fn wait_for<M>(&mut self) where A: Handler<M>, M: Message + 'static {
+        let tp = TypeId::of::<M>();
+        let seen = self.inner.begin_wait_for(tp);
+        self.inner.wait(WaitFor{tp, seen, act: PhantomData});
+    }
+
+    /// Path of the actor
+    pub fn path(&self) -> &ActorPath {
+        self.inner.path()
+    }
+
+    /// Set name of the actor, name replaces last segment of actor's path
+    ///
+    /// Actors started by this actor before it got renamed keep old paths,
+    /// so name should be set in `Actor::started()` or in `Actor::create()`.
+    /// Names should be unique among actors started by the same actor.
+    pub fn set_name<N: AsRef<str>>(&mut self, name: N) {
+        self.inner.set_name(name.as_ref())
+    }
+
+    /// Mailbox length and counters
+    pub fn mailbox_stats(&self) -> MailboxMetrics {
+        self.inner.mailbox_metrics()
+    }
+
+    /// Periodically report mailbox metrics to metrics collector
+    ///
+    /// Report is sent every `interval`, reporting stops once collector
+    /// is stopped or returned handle is cancelled.
+    pub fn report_mailbox_stats(&mut self, interval: Duration,
+                                collector: Recipient<Syn, MailboxReport>) -> SpawnHandle
+    {
+        let arbiter = Arbiter::try_name();
+        self.run_interval(interval, move |_, ctx| {
+            let report = MailboxReport {
+                actor_type: TypeId::of::<A>(),
+                arbiter: arbiter.clone(),
+                metrics: ctx.mailbox_stats(),
+            };
+            if collector.do_send(report).is_err() {
+                let handle = ctx.handle();
+                ctx.cancel_future(handle);
+            }
+        })
+    }
+
+    /// Watch other actor
+    ///
+    /// `Terminated` message is sent to this actor once watched actor stops
+    /// and is not restarted by its supervisor, or immediately if watched
+    /// actor is stopped already. Watched actor is not kept alive by watcher,
+    /// message is dropped if this actor is stopped first.
+    pub fn watch<B>(&mut self, addr: &Addr<Syn, B>) where B: Actor, A: Handler<Terminated<B>> {
+        let watcher = self.address::<Addr<Syn, A>>().downgrade();
+        let who = addr.downgrade();
+        addr.tx().watch(Box::new(move || {
+            if let Some(addr) = watcher.upgrade() {
+                addr.do_send(Terminated(who.clone()));
+            }
+        }))
+    }
+
+    /// Link this actor with other actor
+    ///
+    /// Once one of linked actors terminates, other one gets stopped, or
+    /// receives `Exited` message if it traps exits. Actor is terminated
+    /// when it stops and is not restarted by its supervisor. Links do not
+    /// keep actors alive.
+    pub fn link<B>(&mut self, addr: &Addr<Syn, B>) where B: Actor<Context=Context<B>> {
+        let addr2: Addr<Syn, A> = self.address();
+        addr.tx().watch(exit_watcher::<A, B>(&addr2));
+        addr2.tx().watch(exit_watcher::<B, A>(addr));
+    }
+
+    /// Deliver exits of linked actors as `Exited` messages
+    ///
+    /// By default actor is stopped once any of linked actors terminates.
+    pub fn trap_exit(&mut self) where A: Handler<Exited> {
+        self.trap = Some(trap_exit::<A>);
+    }
+
+    /// Send `Passivate` message to the actor once it does not receive
+    /// messages for `timeout`
+    ///
+    /// Idle time is counted from the end of the last handled message.
+    /// `Passivate` is sent once per idle period. Idle timeout is dropped on
+    /// restart.
+    pub fn set_idle_timeout(&mut self, timeout: Duration) where A: Handler<Passivate> {
+        let (last, timer) = {
+            let idle = self.extension::<Idle>();
+            idle.timeout = Some(timeout);
+            (idle.last.clone(), idle.timer.take())
+        };
+        if let Some(timer) = timer {
+            self.cancel_future(timer);
+        }
+        match last {
+            Some(last) => last.set(clock::now()),
+            None => {
+                let last = Rc::new(Cell::new(clock::now()));
+                self.add_middleware(IdleTracker(Rc::clone(&last)));
+                self.extension::<Idle>().last = Some(last);
+            }
+        }
+        schedule_idle(self, timeout);
+    }
+
+    /// Stop idle actor
+    ///
+    /// Supervisor does not apply restart policy to passivated actor, actor
+    /// is restarted once it receives next message. Together with
+    /// `Supervisor::start_lazy()` actor is active only while it is used.
+    /// Actor without supervisor just stops.
+    pub fn passivate(&mut self) {
+        self.inner.passivate()
+    }
+
     #[inline]
     pub(crate) fn new(act: Option<A>) -> Context<A> {
-        Context { inner: ContextImpl::new(act) }
+        Context { inner: ContextImpl::new(act), trap: None }
     }
 
     #[inline]
     pub(crate) fn with_receiver(act: Option<A>, rx: SyncAddressReceiver<A>) -> Context<A> {
-        Context { inner: ContextImpl::with_receiver(act, rx) }
+        Context { inner: ContextImpl::with_receiver(act, rx), trap: None }
     }
 
     #[inline]
@@ -98,16 +339,87 @@ impl<A> Context<A> where A: Actor<Context=Self> {
     }
 
     #[inline]
-    pub(crate) fn restart(&mut self) -> bool where A: Supervised {
+    pub(crate) fn exclude_from_drain(&mut self) {
+        self.inner.exclude_from_drain()
+    }
+
+    #[inline]
+    pub(crate) fn extension<T: Default + 'static>(&mut self) -> &mut T {
+        self.inner.extension()
+    }
+
+    pub(crate) fn set_actor(&mut self, act: A) {
+        self.inner.set_actor(act)
+    }
+
+    pub(crate) fn actor(&mut self) -> &mut A {
+        self.inner.actor()
+    }
+}
+
+impl<A> SupervisedContext<A> for Context<A> where A: Supervised + Actor<Context=Self> {
+
+    #[inline]
+    fn create() -> Context<A> {
+        Context::new(None)
+    }
+
+    #[inline]
+    fn set_actor(&mut self, act: A) {
+        self.inner.set_actor(act)
+    }
+
+    #[inline]
+    fn connected(&self) -> bool {
+        self.inner.connected()
+    }
+
+    #[inline]
+    fn reset(&mut self) -> bool {
+        self.inner.reset()
+    }
+
+    #[inline]
+    fn reset_with_timers(&mut self) -> bool {
+        self.inner.reset_with_timers()
+    }
+
+    #[inline]
+    fn restarting(&mut self, reason: &RestartReason) {
         let ctx: &mut Context<A> = unsafe {
             mem::transmute(self as &mut Context<A>)
         };
-        self.inner.restart(ctx)
+        self.inner.actor().restarting_with(reason, ctx)
     }
 
     #[inline]
-    pub(crate) fn set_actor(&mut self, act: A) {
-        self.inner.set_actor(act)
+    fn stream_failed(&self) -> bool {
+        self.inner.stream_failed()
+    }
+
+    #[inline]
+    fn mailbox_ready(&mut self) -> bool {
+        self.inner.mailbox_ready()
+    }
+
+    #[inline]
+    fn passivated(&self) -> bool {
+        self.inner.passivated()
+    }
+
+    #[inline]
+    fn drop_actor(&mut self) {
+        self.inner.drop_actor()
+    }
+
+    #[inline]
+    fn clear_mailbox(&mut self) -> Vec<DroppedMessage> {
+        self.inner.clear_mailbox()
+    }
+
+    #[inline]
+    fn path(&self) -> Option<ActorPath> {
+        Some(self.inner.path().clone())
     }
 }
 
@@ -126,6 +438,84 @@ impl<A> Future for Context<A> where A: Actor<Context=Self>
     }
 }
 
+/// Watcher that notifies `addr` about termination of actor `B`
+fn exit_watcher<A, B>(addr: &Addr<Syn, A>) -> Watcher
+    where A: Actor<Context=Context<A>>, B: Actor
+{
+    let addr = addr.downgrade();
+    Box::new(move || {
+        if let Some(addr) = addr.upgrade() {
+            let exit = LinkExit{msg: Some(Exited{actor_type: TypeId::of::<B>()}),
+                                act: PhantomData};
+            addr.tx().do_send_envelope(SyncEnvelope::with_proxy(Box::new(exit)));
+        }
+    })
+}
+
+fn trap_exit<A>(act: &mut A, msg: Exited, ctx: &mut Context<A>)
+    where A: Actor<Context=Context<A>> + Handler<Exited>
+{
+    handler::dispatch(act, msg, ctx, CorrelationId::current()).handle::<()>(ctx, None)
+}
+
+/// Exit of linked actor, stops actor unless it traps exits
+struct LinkExit<A> {
+    msg: Option<Exited>,
+    act: PhantomData<A>,
+}
+
+unsafe impl<A> Send for LinkExit<A> {}
+
+impl<A> EnvelopeProxy for LinkExit<A> where A: Actor<Context=Context<A>> {
+    type Actor = A;
+
+    fn handle(&mut self, act: &mut A, ctx: &mut Context<A>) {
+        if let Some(msg) = self.msg.take() {
+            match ctx.trap {
+                Some(trap) => trap(act, msg, ctx),
+                None => ctx.stop(),
+            }
+        }
+    }
+
+    fn message_type(&self) -> Option<TypeId> {
+        Some(TypeId::of::<Exited>())
+    }
+
+    fn message_name(&self) -> Option<&'static str> {
+        span::type_name_of::<Exited>()
+    }
+}
+
+impl<A> Handler<MailboxStats> for A where A: MailboxMonitored {
+    type Result = MessageResult<MailboxStats>;
+
+    fn handle(&mut self, _: MailboxStats, ctx: &mut Context<A>) -> Self::Result {
+        MessageResult(ctx.mailbox_stats())
+    }
+}
+
+/// Resolves once message of type `tp` is handled
+struct WaitFor<A> {
+    tp: TypeId,
+    seen: usize,
+    act: PhantomData<A>,
+}
+
+impl<A> ActorFuture for WaitFor<A> where A: Actor<Context=Context<A>> {
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, _: &mut A, ctx: &mut Context<A>) -> Poll<(), ()> {
+        if ctx.inner.end_wait_for(self.tp, self.seen) {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
 impl<A> fmt::Debug for Context<A> where A: Actor<Context=Self> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Context({:?})", self as *const _)
@@ -157,3 +547,56 @@ impl<A, T> ContextFutureSpawner<A> for T
         ctx.wait(self);
     }
 }
+
+/// Idle timeout of the context
+#[derive(Default)]
+struct Idle {
+    timeout: Option<Duration>,
+    /// End of the last handled message
+    last: Option<Rc<Cell<Instant>>>,
+    /// End of the last handled message at the time `Passivate` was sent
+    notified: Option<Instant>,
+    timer: Option<SpawnHandle>,
+}
+
+/// Records end of handled messages
+struct IdleTracker(Rc<Cell<Instant>>);
+
+impl Middleware for IdleTracker {
+    fn after_handle(&mut self, meta: &MessageMeta, _: Duration) {
+        if meta.message_type() != TypeId::of::<Passivate>() {
+            self.0.set(clock::now());
+        }
+    }
+}
+
+/// Check idle time of the actor after `delay`
+fn schedule_idle<A>(ctx: &mut Context<A>, delay: Duration)
+    where A: Actor<Context=Context<A>> + Handler<Passivate>
+{
+    let timer = ctx.run_later(delay, |_, ctx| {
+        let (timeout, last) = {
+            let idle = ctx.extension::<Idle>();
+            idle.timer = None;
+            (idle.timeout, idle.last.clone())
+        };
+        if let (Some(timeout), Some(last)) = (timeout, last) {
+            let idle = clock::now().duration_since(last.get());
+            if idle < timeout {
+                schedule_idle(ctx, timeout - idle);
+            } else {
+                // `Passivate` is sent once per idle period, actor that
+                // ignores it gets next one after it handles other message
+                let end = last.get();
+                if ctx.extension::<Idle>().notified != Some(end)
+                    && ctx.mailbox_stats().pending == 0
+                {
+                    ctx.extension::<Idle>().notified = Some(end);
+                    ctx.notify(Passivate);
+                }
+                schedule_idle(ctx, timeout);
+            }
+        }
+    });
+    ctx.extension::<Idle>().timer = Some(timer);
+}
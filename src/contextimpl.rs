@@ -1,13 +1,25 @@
 use std::mem;
+use std::cell::Cell;
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use futures::{Async, Poll};
 use smallvec::SmallVec;
 
 use fut::ActorFuture;
+use arbiter::Arbiter;
 use actor::{Actor, AsyncContext, ActorState, Running, SpawnHandle, Supervised};
-use address::{Addr, SyncAddressReceiver, Syn, Unsync};
+use address::{Addr, EnvelopeProxy, OverflowPolicy, SyncAddressReceiver, Syn, Unsync,
+              UnsyncEnvelope};
+use handler::{Handler, Message};
 use contextitems::ActorWaitItem;
-use mailbox::Mailbox;
+use deadletter::{self, DeadLetterReason, DroppedMessage};
+use events;
+use mailbox::{Mailbox, Priority};
+use middleware::Middlewares;
+use msgs::MailboxMetrics;
+use path::{self, ActorInfo, ActorPath};
+use supervisor::RestartReason;
 
 /// internal context state
 bitflags! {
@@ -17,6 +29,7 @@ bitflags! {
         const STOPPING = 0b0000_0100;
         const STOPPED =  0b0001_0000;
         const MODIFIED = 0b0010_0000;
+        const DRAINED =  0b0100_0000;
     }
 }
 
@@ -31,8 +44,28 @@ pub struct ContextImpl<A> where A: Actor, A::Context: AsyncContext<A> {
     mailbox: Mailbox<A>,
     wait: SmallVec<[ActorWaitItem<A>; 2]>,
     items: SmallVec<[Item<A>; 3]>,
+    cancelled: SmallVec<[SpawnHandle; 2]>,
     handle: SpawnHandle,
     curr_handle: SpawnHandle,
+    stream_error: bool,
+    high_watermark: usize,
+    /// Stream polling got paused, streams are polled again once mailbox is drained
+    paused: Cell<bool>,
+    registration: Option<usize>,
+    drain: bool,
+    path: ActorPath,
+    path_registered: bool,
+    extensions: HashMap<TypeId, Box<Any>>,
+    stash: Vec<Box<EnvelopeProxy<Actor=A>>>,
+    unstashed: VecDeque<Box<EnvelopeProxy<Actor=A>>>,
+    /// Message types handled while context waits
+    receivable: HashSet<TypeId>,
+    waiting_for: Vec<TypeId>,
+    /// Number of messages handled while context waits, by type
+    selected: HashMap<TypeId, usize>,
+    middlewares: Middlewares,
+    /// Actor stopped itself after idle timeout
+    passivated: bool,
 }
 
 impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
@@ -43,10 +76,26 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
             act: act,
             wait: SmallVec::new(),
             items: SmallVec::new(),
+            cancelled: SmallVec::new(),
             flags: ContextFlags::RUNNING,
             mailbox: Mailbox::default(),
             handle: SpawnHandle::default(),
             curr_handle: SpawnHandle::default(),
+            stream_error: false,
+            high_watermark: 0,
+            paused: Cell::new(false),
+            registration: None,
+            drain: true,
+            path: ActorPath::spawned(),
+            path_registered: false,
+            extensions: HashMap::new(),
+            stash: Vec::new(),
+            unstashed: VecDeque::new(),
+            receivable: HashSet::new(),
+            waiting_for: Vec::new(),
+            selected: HashMap::new(),
+            middlewares: Middlewares::default(),
+            passivated: false,
         }
     }
 
@@ -56,10 +105,26 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
             act: act,
             wait: SmallVec::new(),
             items: SmallVec::new(),
+            cancelled: SmallVec::new(),
             flags: ContextFlags::RUNNING,
             mailbox: Mailbox::new(rx),
             handle: SpawnHandle::default(),
             curr_handle: SpawnHandle::default(),
+            stream_error: false,
+            high_watermark: 0,
+            paused: Cell::new(false),
+            registration: None,
+            drain: true,
+            path: ActorPath::spawned(),
+            path_registered: false,
+            extensions: HashMap::new(),
+            stash: Vec::new(),
+            unstashed: VecDeque::new(),
+            receivable: HashSet::new(),
+            waiting_for: Vec::new(),
+            selected: HashMap::new(),
+            middlewares: Middlewares::default(),
+            passivated: false,
         }
     }
 
@@ -71,6 +136,27 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
         self.act.as_mut().unwrap()
     }
 
+    #[inline]
+    /// Path of the actor
+    pub fn path(&self) -> &ActorPath {
+        &self.path
+    }
+
+    /// Replace last segment of actor's path
+    pub fn set_name(&mut self, name: &str) {
+        let path = match self.path.parent() {
+            Some(parent) => parent.child(name),
+            None => ActorPath::root().child(name),
+        };
+        path::rename(&self.path, &path);
+        let registered = self.path_registered;
+        self.unregister_path();
+        self.path = path;
+        if registered {
+            self.register_path();
+        }
+    }
+
     #[inline]
     /// Mark context as modified, this cause extra poll loop over all items
     pub fn modify(&mut self) {
@@ -95,6 +181,18 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
         }
     }
 
+    /// Stop actor, supervisor restarts passivated actor once it
+    /// receives next message
+    pub fn passivate(&mut self) {
+        self.passivated = true;
+        self.stop();
+    }
+
+    #[inline]
+    pub fn passivated(&self) -> bool {
+        self.passivated
+    }
+
     #[inline]
     /// Terminate actor execution
     pub fn terminate(&mut self) {
@@ -144,11 +242,18 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
 
     #[inline]
     /// Cancel previously scheduled future.
+    ///
+    /// Items could not be removed while one of them is polled,
+    /// so cancelled item gets removed after current item's poll.
     pub fn cancel_future(&mut self, handle: SpawnHandle) -> bool {
         for idx in 0..self.items.len() {
             if self.items[idx].0 == handle {
                 self.modify();
-                self.items.swap_remove(idx);
+                if self.curr_handle == SpawnHandle::default() {
+                    self.items.swap_remove(idx);
+                } else if !self.cancelled.contains(&handle) {
+                    self.cancelled.push(handle);
+                }
                 return true
             }
         }
@@ -156,16 +261,132 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
     }
 
     #[inline]
-    pub fn capacity(&mut self) -> usize {
+    pub fn capacity(&self) -> usize {
         self.mailbox.capacity()
     }
 
+    #[inline]
+    pub fn mailbox_metrics(&self) -> MailboxMetrics {
+        self.mailbox.metrics()
+    }
+
     #[inline]
     pub fn set_mailbox_capacity(&mut self, cap: usize) {
         self.modify();
         self.mailbox.set_capacity(cap);
     }
 
+    #[inline]
+    pub fn mailbox_overflow(&self) -> OverflowPolicy {
+        self.mailbox.overflow()
+    }
+
+    #[inline]
+    pub fn set_mailbox_overflow(&mut self, policy: OverflowPolicy) {
+        self.modify();
+        self.mailbox.set_overflow(policy);
+    }
+
+    #[inline]
+    pub fn stream_high_watermark(&self) -> usize {
+        self.high_watermark
+    }
+
+    #[inline]
+    pub fn set_stream_high_watermark(&mut self, mark: usize) {
+        self.modify();
+        self.high_watermark = mark;
+    }
+
+    #[inline]
+    /// Check if number of pending messages reached stream high-water mark
+    pub fn stream_paused(&self) -> bool {
+        let paused = self.high_watermark != 0 && self.mailbox.pending() >= self.high_watermark;
+        if paused {
+            self.paused.set(true);
+        }
+        paused
+    }
+
+    #[inline]
+    pub fn prioritize<M>(&mut self) where M: Priority + 'static, A: Handler<M> {
+        self.modify();
+        self.mailbox.prioritize::<M>();
+    }
+
+    #[inline]
+    /// Drop all pending messages, returns types of dropped messages
+    pub fn clear_mailbox(&mut self) -> Vec<DroppedMessage> {
+        let mut dropped = self.clear_stash();
+        dropped.extend(self.mailbox.clear());
+        dropped
+    }
+
+    /// Drop stashed and unstashed messages
+    fn clear_stash(&mut self) -> Vec<DroppedMessage> {
+        self.unstashed.drain(..).chain(self.stash.drain(..))
+            .map(|env| (env.message_type(), env.message_name()))
+            .collect()
+    }
+
+    /// Defer message until `unstash_all()` is called
+    pub fn stash<M>(&mut self, msg: M) where A: Handler<M>, M: Message + 'static {
+        self.stash.push(Box::new(UnsyncEnvelope::new(msg, None)));
+    }
+
+    /// Replay stashed messages before other pending messages
+    pub fn unstash_all(&mut self) {
+        self.modify();
+        for env in self.stash.drain(..).rev() {
+            self.unstashed.push_front(env);
+        }
+    }
+
+    /// Number of stashed messages
+    pub fn stashed(&self) -> usize {
+        self.stash.len()
+    }
+
+    /// Handle messages of type `M` while context waits for futures
+    pub fn receive_while_waiting<M>(&mut self) where A: Handler<M>, M: Message + 'static {
+        self.receivable.insert(TypeId::of::<M>());
+    }
+
+    /// Start waiting for message of type `tp`, returns number of messages
+    /// of this type handled so far
+    pub(crate) fn begin_wait_for(&mut self, tp: TypeId) -> usize {
+        self.waiting_for.push(tp);
+        self.selected.get(&tp).cloned().unwrap_or(0)
+    }
+
+    /// Stop waiting for message of type `tp` once message arrived since `seen`
+    pub(crate) fn end_wait_for(&mut self, tp: TypeId, seen: usize) -> bool {
+        if self.selected.get(&tp).cloned().unwrap_or(0) > seen {
+            if let Some(idx) = self.waiting_for.iter().position(|t| *t == tp) {
+                self.waiting_for.remove(idx);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Message that could be handled while context waits
+    fn select(&mut self) -> Option<Box<EnvelopeProxy<Actor=A>>> {
+        if self.receivable.is_empty() && self.waiting_for.is_empty() {
+            return None
+        }
+        let env = {
+            let receivable = &self.receivable;
+            let waiting_for = &self.waiting_for;
+            self.mailbox.select(|tp| receivable.contains(&tp) || waiting_for.contains(&tp))
+        };
+        if let Some(tp) = env.as_ref().and_then(|env| env.message_type()) {
+            *self.selected.entry(tp).or_insert(0) += 1;
+        }
+        env
+    }
+
     #[inline]
     pub fn unsync_address(&mut self) -> Addr<Unsync, A> {
         self.modify();
@@ -179,14 +400,39 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
     }
 
     #[inline]
+    /// Check if mailbox has pending messages, current task is notified
+    /// once message arrives
+    pub(crate) fn mailbox_ready(&mut self) -> bool {
+        self.mailbox.ready()
+    }
+
     pub fn alive(&self) -> bool {
         if self.flags.intersects(ContextFlags::STOPPING | ContextFlags::STOPPED) {
             false
         } else {
-            self.mailbox.connected() || !self.items.is_empty() || !self.wait.is_empty()
+            self.mailbox.connected() || !self.items.is_empty() || !self.wait.is_empty() ||
+                !self.unstashed.is_empty()
         }
     }
 
+    #[inline]
+    /// Check if context's mailbox is connected to any address
+    pub fn connected(&self) -> bool {
+        self.mailbox.connected()
+    }
+
+    #[inline]
+    /// Mark context as stopped because of stream error
+    pub fn stream_error(&mut self) {
+        self.stream_error = true;
+    }
+
+    #[inline]
+    /// Check if context stopped because of stream error
+    pub fn stream_failed(&self) -> bool {
+        self.stream_error
+    }
+
     #[inline]
     fn stopping(&self) -> bool {
         self.flags.intersects(ContextFlags::STOPPING | ContextFlags::STOPPED)
@@ -194,19 +440,71 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
 
     /// Restart context. Cleanup all futures, except address queue.
     #[inline]
-    pub fn restart(&mut self, ctx: &mut A::Context) -> bool where A: Supervised {
+    pub fn restart(&mut self, reason: &RestartReason, ctx: &mut A::Context) -> bool
+        where A: Supervised
+    {
+        if self.reset() {
+            self.actor().restarting_with(reason, ctx);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Cleanup all futures, except address queue. Actor does not get notified.
+    #[inline]
+    pub fn reset(&mut self) -> bool {
         if self.act.is_none() || !self.mailbox.connected() {
             false
         } else {
             self.flags = ContextFlags::RUNNING;
             self.wait = SmallVec::new();
             self.items = SmallVec::new();
+            self.cancelled = SmallVec::new();
             self.handle = SpawnHandle::default();
-            self.actor().restarting(ctx);
+            self.stream_error = false;
+            self.extensions.clear();
+            self.waiting_for.clear();
+            self.middlewares.clear();
+            self.passivated = false;
             true
         }
     }
 
+    /// Cleanup all futures, except address queue and pending delayed
+    /// notifications. Handles of preserved notifications stay valid.
+    pub fn reset_with_timers(&mut self) -> bool {
+        if self.act.is_none() || !self.mailbox.connected() {
+            false
+        } else {
+            self.flags = ContextFlags::RUNNING;
+            self.wait = SmallVec::new();
+            let cancelled = mem::replace(&mut self.cancelled, SmallVec::new());
+            self.items.retain(
+                |item| item.1.preserve_on_restart() && !cancelled.contains(&item.0));
+            self.curr_handle = SpawnHandle::default();
+            self.stream_error = false;
+            self.extensions.clear();
+            self.waiting_for.clear();
+            self.middlewares.clear();
+            self.passivated = false;
+            true
+        }
+    }
+
+    /// Per-context state of type `T`, created with `T::default()` on first
+    /// access. State is dropped on restart.
+    pub(crate) fn extension<T: Default + 'static>(&mut self) -> &mut T {
+        let ext = self.extensions.entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(T::default()));
+        (**ext).downcast_mut().unwrap()
+    }
+
+    #[inline]
+    pub(crate) fn middlewares(&mut self) -> &mut Middlewares {
+        &mut self.middlewares
+    }
+
     #[inline]
     pub fn set_actor(&mut self, act: A) {
         self.act = Some(act);
@@ -214,8 +512,13 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
     }
 
     #[inline]
-    pub fn into_inner(self) -> Option<A> {
-        self.act
+    pub fn drop_actor(&mut self) {
+        self.act = None;
+    }
+
+    #[inline]
+    pub fn into_inner(mut self) -> Option<A> {
+        self.act.take()
     }
 
     #[inline]
@@ -223,6 +526,42 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
         self.flags.contains(ContextFlags::STARTED)
     }
 
+    /// Keep context running during graceful arbiter stop
+    pub fn exclude_from_drain(&mut self) {
+        self.drain = false;
+        if let Some(id) = self.registration.take() {
+            Arbiter::unregister_context(id);
+        }
+    }
+
+    #[inline]
+    fn unregister(&mut self) {
+        if let Some(id) = self.registration.take() {
+            Arbiter::unregister_context(id);
+        }
+        self.unregister_path();
+    }
+
+    fn register_path(&mut self) {
+        if let Some(registry) = Arbiter::try_system_registry() {
+            registry.register_path(ActorInfo {
+                path: self.path.clone(),
+                actor_type: TypeId::of::<A>(),
+                arbiter: Arbiter::try_name(),
+            });
+            self.path_registered = true;
+        }
+    }
+
+    fn unregister_path(&mut self) {
+        if self.path_registered {
+            self.path_registered = false;
+            if let Some(registry) = Arbiter::try_system_registry() {
+                registry.unregister_path(&self.path);
+            }
+        }
+    }
+
     pub fn poll(&mut self, ctx: &mut A::Context) -> Poll<(), ()> {
         let act: &mut A = if let Some(ref mut act) = self.act {
             unsafe { mem::transmute(act) }
@@ -230,9 +569,15 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
             return Ok(Async::Ready(()))
         };
 
+        let _path = path::enter(&self.path);
+        if self.drain && self.registration.is_none() {
+            self.registration = Some(Arbiter::register_context());
+        }
         if !self.flags.contains(ContextFlags::STARTED) {
             self.flags.insert(ContextFlags::STARTED);
             Actor::started(act, ctx);
+            self.register_path();
+            events::actor_started::<A>(Some(&self.path));
         }
 
         'outer: loop {
@@ -242,13 +587,27 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
             // ctx.wait() always add to the back of the list
             // and we always have to check most recent future
             while !self.wait.is_empty() && !self.stopping() {
-                if let Some(item) = self.wait.last_mut() {
-                    match item.poll(act, ctx) {
-                        Async::Ready(_) => (),
-                        Async::NotReady => return Ok(Async::NotReady),
-                    }
+                let ready = match self.wait.last_mut() {
+                    Some(item) => item.poll(act, ctx).is_ready(),
+                    None => true,
+                };
+                if ready {
+                    self.wait.pop();
+                    continue
+                }
+                // selective receive, handled message could complete the future
+                match self.select() {
+                    Some(mut env) => env.handle(act, ctx),
+                    None => return Ok(Async::NotReady),
+                }
+            }
+
+            // unstashed messages are handled before newer messages
+            while !self.waiting() {
+                match self.unstashed.pop_front() {
+                    Some(mut env) => env.handle(act, ctx),
+                    None => break,
                 }
-                self.wait.pop();
             }
 
             // process mailbox
@@ -261,7 +620,23 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
             let mut idx = 0;
             while idx < self.items.len() && !self.stopping() {
                 self.curr_handle = self.items[idx].0;
-                match self.items[idx].1.poll(act, ctx) {
+                let res = self.items[idx].1.poll(act, ctx);
+                // handle is current only while item is polled
+                let curr = mem::replace(&mut self.curr_handle, SpawnHandle::default());
+
+                // remove cancelled items, current item could be cancelled as well
+                if !self.cancelled.is_empty() {
+                    let cancelled = mem::replace(&mut self.cancelled, SmallVec::new());
+                    let done = match res {
+                        Ok(Async::NotReady) => false,
+                        _ => true,
+                    };
+                    self.items.retain(|item| !(cancelled.contains(&item.0) ||
+                                               (done && item.0 == curr)));
+                    continue 'outer
+                }
+
+                match res {
                     Ok(Async::NotReady) => {
                         // item scheduled wait future
                         if !self.wait.is_empty() && !self.stopping() {
@@ -286,7 +661,12 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
                     },
                 }
             }
-            self.curr_handle = SpawnHandle::default();
+
+            // stream got paused by messages that arrived while items were
+            // polled, process mailbox and resume paused streams
+            if self.paused.replace(false) && !self.waiting() && self.mailbox.pending() != 0 {
+                continue
+            }
 
             // ContextFlags::MODIFIED indicates that new IO item has
             // been added during poll process
@@ -296,26 +676,43 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
                 continue
             }
 
+            // arbiter is stopping, stop actor once pending messages are processed.
+            // actor could refuse to stop, in that case it is not asked again
+            if self.drain && self.flags.contains(ContextFlags::RUNNING) &&
+                !self.flags.contains(ContextFlags::DRAINED) &&
+                self.mailbox.pending() == 0 && Arbiter::draining()
+            {
+                self.flags.insert(ContextFlags::DRAINED);
+                self.stop();
+            }
+
             // check state
             if self.flags.contains(ContextFlags::RUNNING) {
                 // possible stop condition
                 if !self.alive() && Actor::stopping(act, ctx) == Running::Stop {
                     self.flags = ContextFlags::STOPPED;
                     Actor::stopped(act, ctx);
+                    events::actor_stopped::<A>(Some(&self.path));
+                    self.unregister();
                     return Ok(Async::Ready(()))
                 }
             } else if self.flags.contains(ContextFlags::STOPPING) {
                 if Actor::stopping(act, ctx) == Running::Stop {
                     self.flags = ContextFlags::STOPPED;
                     Actor::stopped(act, ctx);
+                    events::actor_stopped::<A>(Some(&self.path));
+                    self.unregister();
                     return Ok(Async::Ready(()))
                 } else {
                     self.flags.remove(ContextFlags::STOPPING);
                     self.flags.insert(ContextFlags::RUNNING);
+                    self.passivated = false;
                     continue
                 }
             } else if self.flags.contains(ContextFlags::STOPPED) {
                 Actor::stopped(act, ctx);
+                events::actor_stopped::<A>(Some(&self.path));
+                self.unregister();
                 return Ok(Async::Ready(()))
             }
 
@@ -323,3 +720,12 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
         }
     }
 }
+
+impl<A> Drop for ContextImpl<A> where A: Actor, A::Context: AsyncContext<A> {
+    fn drop(&mut self) {
+        self.unregister();
+        for (message_type, message_name) in self.clear_stash() {
+            deadletter::publish(message_type, message_name, DeadLetterReason::Closed);
+        }
+    }
+}
@@ -1,12 +1,12 @@
 use std::marker::PhantomData;
 use std::time::Duration;
 use futures::{Async, Future, Poll, Stream};
-use tokio_core::reactor::Timeout;
+use clock::Delay;
 
 use fut::ActorFuture;
-use arbiter::Arbiter;
 use actor::{Actor, ActorContext, AsyncContext};
-use handler::{Handler, MessageResponse, Message};
+use handler::{self, Handler, MessageResponse, Message};
+use span::CorrelationId;
 
 
 pub(crate) struct ActorWaitItem<A: Actor>(Box<ActorFuture<Item=(), Error=(), Actor=A>>);
@@ -35,7 +35,8 @@ impl<A> ActorWaitItem<A> where A: Actor, A::Context: ActorContext + AsyncContext
 pub(crate)
 struct ActorDelayedMessageItem<A, M> where A: Actor, M: Message {
     msg: Option<M>,
-    timeout: Timeout,
+    timeout: Delay,
+    cid: CorrelationId,
     act: PhantomData<A>,
     m: PhantomData<M>,
 }
@@ -44,7 +45,8 @@ impl<A, M> ActorDelayedMessageItem<A, M> where A: Actor, M: Message {
     pub fn new(msg: M, timeout: Duration) -> Self {
         ActorDelayedMessageItem {
             msg: Some(msg),
-            timeout: Timeout::new(timeout, Arbiter::handle()).unwrap(),
+            timeout: Delay::new(timeout),
+            cid: CorrelationId::current(),
             act: PhantomData,
             m: PhantomData,
         }
@@ -62,24 +64,30 @@ impl<A, M> ActorFuture for ActorDelayedMessageItem<A, M>
         match self.timeout.poll() {
             Ok(Async::NotReady) => Ok(Async::NotReady),
             Ok(Async::Ready(_)) => {
-                let fut = A::handle(act, self.msg.take().unwrap(), ctx);
+                let fut = handler::dispatch(act, self.msg.take().unwrap(), ctx, self.cid);
                 fut.handle::<()>(ctx, None);
                 Ok(Async::Ready(()))
             },
             Err(_) => unreachable!(),
         }
     }
+
+    fn preserve_on_restart(&self) -> bool {
+        // message is taken if actor failed while handling it
+        self.msg.is_some()
+    }
 }
 
 pub(crate)
 struct ActorMessageItem<A, M> where A: Actor, M: Message {
     msg: Option<M>,
+    cid: CorrelationId,
     act: PhantomData<A>,
 }
 
 impl<A, M> ActorMessageItem<A, M> where A: Actor, M: Message {
     pub fn new(msg: M) -> Self {
-        ActorMessageItem{msg: Some(msg), act: PhantomData}
+        ActorMessageItem{msg: Some(msg), cid: CorrelationId::current(), act: PhantomData}
     }
 }
 
@@ -91,7 +99,7 @@ impl<A, M: 'static> ActorFuture for ActorMessageItem<A, M>
     type Actor = A;
 
     fn poll(&mut self, act: &mut A, ctx: &mut A::Context) -> Poll<Self::Item, Self::Error> {
-        let fut = Handler::handle(act, self.msg.take().unwrap(), ctx);
+        let fut = handler::dispatch(act, self.msg.take().unwrap(), ctx, self.cid);
         fut.handle::<()>(ctx, None);
         Ok(Async::Ready(()))
     }
@@ -121,9 +129,14 @@ impl<A, M: 'static, S> ActorFuture for ActorMessageStreamItem<A, M, S>
 
     fn poll(&mut self, act: &mut A, ctx: &mut A::Context) -> Poll<Self::Item, Self::Error> {
         loop {
+            // let actor process pending messages first
+            if ctx.stream_paused() {
+                return Ok(Async::NotReady)
+            }
+
             match self.stream.poll() {
                 Ok(Async::Ready(Some(msg))) => {
-                    let fut = Handler::handle(act, msg, ctx);
+                    let fut = handler::dispatch(act, msg, ctx, CorrelationId::current());
                     fut.handle::<()>(ctx, None);
                     if ctx.waiting() {
                         return Ok(Async::NotReady)
@@ -0,0 +1,189 @@
+//! Dead letters
+//!
+//! Messages that could not be delivered to an actor are reported
+//! as [`DeadLetter`](struct.DeadLetter.html) messages.
+//!
+//! Messages sent to stopped actor, messages left in mailbox of stopped actor
+//! and messages dropped by mailbox policies are reported to system wide
+//! [`DeadLetters`](struct.DeadLetters.html) service. Service logs dead letters
+//! or forwards them to subscribed recipients. Message that is handed back to
+//! the sender with `SendError`, e.g. by `Addr::try_send()`, is not a dead
+//! letter.
+//!
+//! ```rust
+//! # extern crate actix;
+//! use actix::prelude::*;
+//! use actix::deadletter::{self, DeadLetter, DeadLetters};
+//!
+//! struct Monitor;
+//!
+//! impl Actor for Monitor {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl Handler<DeadLetter> for Monitor {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, msg: DeadLetter, _: &mut Context<Self>) {
+//!         println!("Message is not delivered: {:?}", msg);
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     let monitor: Addr<Syn, _> = Monitor.start();
+//!     DeadLetters::from_registry().do_send(deadletter::Subscribe(monitor.recipient()));
+//! #   Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!     sys.run();
+//! }
+//! ```
+use std::mem;
+use std::cell::Cell;
+use std::any::TypeId;
+
+use actor::{Actor, Supervised};
+use arbiter::Arbiter;
+use address::{Recipient, Syn};
+use context::Context;
+use events::{self, SystemEvent};
+use handler::{Handler, Message};
+use path::{self, ActorPath};
+use registry::SystemService;
+use span;
+
+thread_local!(static PUBLISHING: Cell<bool> = Cell::new(false));
+
+/// Reason why message was not delivered
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DeadLetterReason {
+    /// Message was dropped from mailbox of restarted actor
+    ActorRestarted,
+    /// Message was sent to stopped actor
+    Closed,
+    /// Message was dropped by mailbox overflow policy
+    Overflow,
+    /// Actor panicked while handling message
+    Panic,
+}
+
+/// Type and type name of dropped message, if known
+pub type DroppedMessage = (Option<TypeId>, Option<&'static str>);
+
+/// Undelivered message
+#[derive(Clone, Debug)]
+pub struct DeadLetter {
+    /// Type of undelivered message, if known
+    pub message_type: Option<TypeId>,
+    /// Type name of undelivered message, requires `spans` feature
+    pub message_name: Option<&'static str>,
+    /// Reason why message was not delivered
+    pub reason: DeadLetterReason,
+    /// Name of the arbiter message was sent from, if known
+    pub sender: Option<String>,
+    /// Path of the actor that was running when message got dropped,
+    /// sender for undeliverable messages, recipient for messages dropped
+    /// from its mailbox
+    pub actor: Option<ActorPath>,
+}
+
+impl DeadLetter {
+    pub(crate) fn new(message_type: Option<TypeId>, message_name: Option<&'static str>,
+                      reason: DeadLetterReason) -> DeadLetter
+    {
+        DeadLetter {
+            message_type,
+            message_name,
+            reason,
+            sender: Arbiter::try_name(),
+            actor: path::current(),
+        }
+    }
+
+    /// Check if undelivered message is of type `M`
+    pub fn is<M: Message + 'static>(&self) -> bool {
+        self.message_type == Some(TypeId::of::<M>())
+    }
+}
+
+impl Message for DeadLetter {
+    type Result = ();
+}
+
+/// Report undelivered message of type `M` to system `DeadLetters` service
+pub(crate) fn publish_message<M: 'static>(reason: DeadLetterReason) {
+    publish(Some(TypeId::of::<M>()), span::type_name_of::<M>(), reason)
+}
+
+/// Report undelivered message to system `DeadLetters` service.
+///
+/// Dead letter is dropped if system is not running.
+pub(crate) fn publish(message_type: Option<TypeId>, message_name: Option<&'static str>,
+                      reason: DeadLetterReason)
+{
+    // dead letters service itself is not reachable
+    if message_type == Some(TypeId::of::<DeadLetter>()) ||
+        message_type == Some(TypeId::of::<SystemEvent>())
+    {
+        return
+    }
+    // starting of dead letters service could fail as well
+    if PUBLISHING.with(|cell| cell.replace(true)) {
+        return
+    }
+    if let Some(registry) = Arbiter::try_system_registry() {
+        let letter = DeadLetter::new(message_type, message_name, reason);
+        events::publish(|| SystemEvent::DeadLetter(letter.clone()));
+        registry.get::<DeadLetters>().do_send(letter);
+    }
+    PUBLISHING.with(|cell| cell.set(false));
+}
+
+/// System wide dead letters service
+///
+/// Without subscribers service logs dead letters, otherwise
+/// dead letters get forwarded to all subscribers.
+#[derive(Default)]
+pub struct DeadLetters {
+    subscribers: Vec<Recipient<Syn, DeadLetter>>,
+}
+
+impl Actor for DeadLetters {
+    type Context = Context<Self>;
+}
+
+impl Supervised for DeadLetters {}
+
+impl SystemService for DeadLetters {}
+
+impl Handler<DeadLetter> for DeadLetters {
+    type Result = ();
+
+    fn handle(&mut self, msg: DeadLetter, _: &mut Context<Self>) {
+        if self.subscribers.is_empty() {
+            debug!("Dead letter: {:?}", msg);
+            return
+        }
+        let subscribers = mem::replace(&mut self.subscribers, Vec::new());
+        for subscr in subscribers {
+            if subscr.do_send(msg.clone()).is_ok() {
+                self.subscribers.push(subscr);
+            }
+        }
+    }
+}
+
+/// Subscribe to dead letters
+pub struct Subscribe(pub Recipient<Syn, DeadLetter>);
+
+impl Message for Subscribe {
+    type Result = ();
+}
+
+impl Handler<Subscribe> for DeadLetters {
+    type Result = ();
+
+    fn handle(&mut self, msg: Subscribe, _: &mut Context<Self>) {
+        self.subscribers.push(msg.0);
+    }
+}
@@ -0,0 +1,248 @@
+//! At-least-once delivery
+//!
+//! [`ReliableDelivery`](struct.ReliableDelivery.html) actor delivers
+//! messages to a recipient and retries every message until recipient
+//! confirms it. Every message posted to delivery actor is assigned a
+//! sequence number and is sent to recipient as
+//! [`Deliver`](struct.Deliver.html) message. Recipient confirms message by
+//! handling it, i.e. by sending response. Message is retried if it is lost,
+//! e.g. if recipient stopped or restarted before it handled the message, so
+//! recipient could receive a message more than once and out of order.
+//! [`Deduplicator`](struct.Deduplicator.html) tracks handled messages on the
+//! receiving side.
+//!
+//! ```rust
+//! # extern crate actix;
+//! use std::time::Duration;
+//! use actix::prelude::*;
+//! use actix::delivery::{Deduplicator, Deliver, Post, ReliableDelivery};
+//!
+//! #[derive(Clone)]
+//! struct Order(u64);
+//!
+//! #[derive(Default)]
+//! struct Shop {
+//!     dedup: Deduplicator,
+//! }
+//!
+//! impl Actor for Shop {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl Handler<Deliver<Order>> for Shop {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, msg: Deliver<Order>, _: &mut Context<Self>) {
+//!         if !self.dedup.is_delivered(&msg) {
+//!             println!("Order: {}", msg.msg().0);
+//!             self.dedup.confirm(&msg);
+//!         }
+//! #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     let shop: Addr<Syn, _> = Shop::default().start();
+//!     let delivery: Addr<Unsync, _> =
+//!         ReliableDelivery::new(shop.recipient(), Duration::from_secs(1)).start();
+//!     delivery.do_send(Post(Order(1)));
+//!
+//!     sys.run();
+//! }
+//! ```
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use actor::{Actor, AsyncContext};
+use address::{Recipient, Syn};
+use clock;
+use context::Context;
+use fut::{self, ActorFuture, WrapFuture};
+use handler::{Handler, Message, MessageResult};
+
+/// Message that is delivered by `ReliableDelivery` actor
+pub struct Deliver<M> {
+    sender: Uuid,
+    seq: u64,
+    msg: M,
+}
+
+impl<M> Deliver<M> {
+    /// Sequence number of the message, assigned by delivery actor
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Delivered message
+    pub fn msg(&self) -> &M {
+        &self.msg
+    }
+
+    /// Unwrap delivered message
+    pub fn into_inner(self) -> M {
+        self.msg
+    }
+}
+
+impl<M: 'static> Message for Deliver<M> {
+    type Result = ();
+}
+
+/// Post message to `ReliableDelivery` actor, response is sequence number
+/// of the message
+pub struct Post<M>(pub M);
+
+impl<M: 'static> Message for Post<M> {
+    type Result = u64;
+}
+
+/// Actor that delivers messages to recipient until they are confirmed
+///
+/// Unconfirmed messages are resent every `retry` period, attempt that is
+/// not confirmed within `retry` period fails. Message is not resent while
+/// its attempt is in flight. Actor stops once all its addresses are dropped
+/// and all messages are confirmed.
+pub struct ReliableDelivery<M> where M: Clone + Send + 'static {
+    id: Uuid,
+    recipient: Recipient<Syn, Deliver<M>>,
+    retry: Duration,
+    next: u64,
+    /// Unconfirmed messages
+    pending: BTreeMap<u64, Pending<M>>,
+    scheduled: bool,
+}
+
+/// Unconfirmed message
+struct Pending<M> {
+    msg: M,
+    /// Time of the last attempt
+    sent: Instant,
+    in_flight: bool,
+}
+
+impl<M> ReliableDelivery<M> where M: Clone + Send + 'static {
+    /// Delivery to `recipient`, unconfirmed messages are resent after `retry`
+    pub fn new(recipient: Recipient<Syn, Deliver<M>>, retry: Duration) -> ReliableDelivery<M> {
+        ReliableDelivery {
+            id: Uuid::new_v4(),
+            recipient,
+            retry,
+            next: 1,
+            pending: BTreeMap::new(),
+            scheduled: false,
+        }
+    }
+
+    fn deliver(&mut self, seq: u64, msg: M, ctx: &mut Context<Self>) {
+        let deliver = Deliver{sender: self.id, seq, msg};
+        ctx.spawn(
+            self.recipient.send(deliver)
+                .timeout(self.retry)
+                .into_actor(self)
+                .then(move |res, act, _| {
+                    if res.is_ok() {
+                        act.pending.remove(&seq);
+                    } else if let Some(pending) = act.pending.get_mut(&seq) {
+                        pending.in_flight = false;
+                    }
+                    fut::ok(())
+                }));
+        self.schedule(ctx);
+    }
+
+    fn schedule(&mut self, ctx: &mut Context<Self>) {
+        if !self.scheduled {
+            self.scheduled = true;
+            ctx.run_later(self.retry, |act, ctx| {
+                act.scheduled = false;
+                act.retry(ctx);
+            });
+        }
+    }
+
+    /// Resend failed messages which last attempt is older than retry period
+    fn retry(&mut self, ctx: &mut Context<Self>) {
+        let now = clock::now();
+        let retry = self.retry;
+        let expired: Vec<_> = self.pending.iter_mut()
+            .filter_map(|(seq, pending)| {
+                if !pending.in_flight && now >= pending.sent + retry {
+                    pending.sent = now;
+                    pending.in_flight = true;
+                    Some((*seq, pending.msg.clone()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        for (seq, msg) in expired {
+            self.deliver(seq, msg, ctx);
+        }
+        if !self.pending.is_empty() {
+            self.schedule(ctx);
+        }
+    }
+}
+
+impl<M> Actor for ReliableDelivery<M> where M: Clone + Send + 'static {
+    type Context = Context<Self>;
+}
+
+impl<M> Handler<Post<M>> for ReliableDelivery<M> where M: Clone + Send + 'static {
+    type Result = MessageResult<Post<M>>;
+
+    fn handle(&mut self, msg: Post<M>, ctx: &mut Context<Self>) -> Self::Result {
+        let seq = self.next;
+        self.next += 1;
+        self.pending.insert(
+            seq, Pending{msg: msg.0.clone(), sent: clock::now(), in_flight: true});
+        self.deliver(seq, msg.0, ctx);
+        MessageResult(seq)
+    }
+}
+
+/// Handled message sequence numbers of a sender
+struct Window {
+    /// All sequence numbers below `next` are handled
+    next: u64,
+    handled: BTreeSet<u64>,
+}
+
+/// Tracks messages handled by recipient of `ReliableDelivery`
+///
+/// Message should be confirmed once it is handled. If actor fails before
+/// it confirms message, redelivered message is handled again.
+#[derive(Default)]
+pub struct Deduplicator {
+    senders: HashMap<Uuid, Window>,
+}
+
+impl Deduplicator {
+    /// Create empty deduplicator
+    pub fn new() -> Deduplicator {
+        Deduplicator::default()
+    }
+
+    /// Check if message is already confirmed
+    pub fn is_delivered<M>(&self, msg: &Deliver<M>) -> bool {
+        match self.senders.get(&msg.sender) {
+            Some(window) => msg.seq < window.next || window.handled.contains(&msg.seq),
+            None => false,
+        }
+    }
+
+    /// Record message as handled
+    pub fn confirm<M>(&mut self, msg: &Deliver<M>) {
+        let window = self.senders.entry(msg.sender)
+            .or_insert_with(|| Window{next: 1, handled: BTreeSet::new()});
+        if msg.seq >= window.next {
+            window.handled.insert(msg.seq);
+            while window.handled.remove(&window.next) {
+                window.next += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! Work-pulling dispatcher
+//!
+//! [`Dispatcher`](struct.Dispatcher.html) is an actor that keeps queue of
+//! jobs. Unlike [`Router`](../router/struct.Router.html), dispatcher does not
+//! push jobs into mailboxes of workers, idle worker pulls next job from the
+//! dispatcher. Job waits in dispatcher's queue until some worker is idle, so
+//! long job does not delay jobs queued behind it in the mailbox of a busy
+//! worker. Message has to implement [`Job`](trait.Job.html) trait to be
+//! dispatched, response of the worker is passed back to the sender.
+//!
+//! Worker is an actor that handles job messages, it starts pulling jobs with
+//! [`pull()`](fn.pull.html). Worker pulls next job once response to the
+//! previous one is resolved. Workers could be started in any arbiter and join or leave at
+//! any time, worker that stopped while waiting for a job does not get one.
+//!
+//! ## Example
+//!
+//! ```rust
+//! # extern crate actix;
+//! # extern crate futures;
+//! # use futures::Future;
+//! use actix::prelude::*;
+//! use actix::dispatcher::{self, Dispatcher, Job};
+//!
+//! struct Resize(usize);
+//!
+//! impl Message for Resize {
+//!     type Result = usize;
+//! }
+//!
+//! impl Job for Resize {}
+//!
+//! struct Worker(Addr<Syn, Dispatcher<Resize>>);
+//!
+//! impl Actor for Worker {
+//!     type Context = Context<Self>;
+//!
+//!     fn started(&mut self, ctx: &mut Context<Self>) {
+//!         dispatcher::pull(ctx, &self.0);
+//!     }
+//! }
+//!
+//! impl Handler<Resize> for Worker {
+//!     type Result = usize;
+//!
+//!     fn handle(&mut self, msg: Resize, _: &mut Context<Self>) -> usize {
+//!         msg.0 / 2
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     let addr: Addr<Syn, _> = Dispatcher::new().start();
+//!     for _ in 0..4 {
+//!         let addr = addr.clone();
+//!         Arbiter::start(move |_| Worker(addr));
+//!     }
+//!
+//!     Arbiter::handle().spawn(
+//!         addr.send(Resize(1024)).then(|res| {
+//!             assert_eq!(res.unwrap(), 512);
+//! #           Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!             Ok(())
+//!         }));
+//!
+//!     sys.run();
+//! }
+//! ```
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use futures::Future;
+use futures::sync::oneshot::{channel, Receiver, Sender};
+
+use actor::{Actor, AsyncContext};
+use address::{Addr, Syn};
+use arbiter::Arbiter;
+use context::Context;
+use fut::{self, ActorFuture};
+use handler::{Handler, Message, MessageResponse, ResponseChannel};
+
+/// Message that could be dispatched by `Dispatcher`
+pub trait Job: Message + Send + 'static {}
+
+/// Job pulled by the worker, with response channel of the sender
+pub struct Work<M: Message> {
+    job: M,
+    tx: Sender<M::Result>,
+}
+
+/// Request of idle worker for next job
+///
+/// Request is resolved once dispatcher has a job for the worker.
+pub struct Pull<M>(PhantomData<M>);
+
+impl<M> Pull<M> {
+    /// Request next job
+    pub fn new() -> Pull<M> {
+        Pull(PhantomData)
+    }
+}
+
+impl<M> Default for Pull<M> {
+    fn default() -> Pull<M> {
+        Pull::new()
+    }
+}
+
+impl<M: Job> Message for Pull<M> where M::Result: Send {
+    type Result = Work<M>;
+}
+
+/// Response that is resolved later, by other actor
+pub struct Deferred<M: Message>(Receiver<M::Result>);
+
+impl<A, M> MessageResponse<A, M> for Deferred<M>
+    where A: Actor, M: Message + 'static, M::Result: 'static
+{
+    fn handle<R: ResponseChannel<M>>(self, _: &mut A::Context, tx: Option<R>) {
+        Arbiter::handle().spawn(self.0.then(move |res| {
+            if let (Some(tx), Ok(res)) = (tx, res) {
+                tx.send(res);
+            }
+            Ok(())
+        }));
+    }
+}
+
+/// Response to the `Pull` request
+///
+/// Job is sent to the worker right away, otherwise response channel of the
+/// worker is kept until the dispatcher gets a job.
+pub struct Pulled<M: Job>(Option<Work<M>>) where M::Result: Send;
+
+impl<M: Job> MessageResponse<Dispatcher<M>, Pull<M>> for Pulled<M> where M::Result: Send {
+    fn handle<R: ResponseChannel<Pull<M>>>(self, ctx: &mut Context<Dispatcher<M>>,
+                                           tx: Option<R>)
+    {
+        let worker: Box<IdleWorker<M>> = Box::new(tx);
+        match self.0 {
+            Some(work) if !worker.is_canceled() => worker.send(work),
+            work => {
+                // response has no access to the dispatcher, return job and
+                // register worker later
+                ctx.spawn(fut::ok(()).map(move |_, act: &mut Dispatcher<M>, _| {
+                    if let Some(work) = work {
+                        act.requeue(work);
+                    }
+                    act.idle(worker)
+                }));
+            }
+        }
+    }
+}
+
+/// Response channel of idle worker
+trait IdleWorker<M: Job>: 'static where M::Result: Send {
+    /// Worker stopped
+    fn is_canceled(&self) -> bool;
+
+    fn send(self: Box<Self>, work: Work<M>);
+}
+
+impl<M: Job, R: ResponseChannel<Pull<M>>> IdleWorker<M> for Option<R> where M::Result: Send {
+    fn is_canceled(&self) -> bool {
+        self.as_ref().map(|tx| tx.is_canceled()).unwrap_or(true)
+    }
+
+    fn send(self: Box<Self>, work: Work<M>) {
+        if let Some(tx) = *self {
+            tx.send(work);
+        }
+    }
+}
+
+/// Dispatcher actor
+///
+/// Dispatcher keeps jobs in order of arrival and hands them out to idle
+/// workers in order of their requests. Job that sender is not waiting for
+/// anymore is dropped. Job handed to worker in other arbiter at the moment
+/// that worker stops is lost, sender's request fails.
+pub struct Dispatcher<M: Job> where M::Result: Send {
+    jobs: VecDeque<Work<M>>,
+    workers: VecDeque<Box<IdleWorker<M>>>,
+}
+
+impl<M: Job> Dispatcher<M> where M::Result: Send {
+    /// Create dispatcher without jobs
+    pub fn new() -> Dispatcher<M> {
+        Dispatcher{jobs: VecDeque::new(), workers: VecDeque::new()}
+    }
+
+    /// Next job that sender is waiting for
+    fn next_job(&mut self) -> Option<Work<M>> {
+        while let Some(work) = self.jobs.pop_front() {
+            if !work.tx.is_canceled() {
+                return Some(work)
+            }
+        }
+        None
+    }
+
+    /// First idle worker, stopped workers are skipped
+    fn idle_worker(&mut self) -> Option<Box<IdleWorker<M>>> {
+        while let Some(worker) = self.workers.pop_front() {
+            if !worker.is_canceled() {
+                return Some(worker)
+            }
+        }
+        None
+    }
+
+    /// Pass job to the first idle worker, job is queued if all workers are busy
+    fn dispatch(&mut self, work: Work<M>) {
+        match self.idle_worker() {
+            Some(worker) => worker.send(work),
+            None => self.jobs.push_back(work),
+        }
+    }
+
+    /// Return job that was not taken by the worker, job keeps its place
+    fn requeue(&mut self, work: Work<M>) {
+        match self.idle_worker() {
+            Some(worker) => worker.send(work),
+            None => self.jobs.push_front(work),
+        }
+    }
+
+    /// Worker is waiting for a job
+    fn idle(&mut self, worker: Box<IdleWorker<M>>) {
+        if worker.is_canceled() {
+            return
+        }
+        match self.next_job() {
+            Some(work) => worker.send(work),
+            None => {
+                self.workers.retain(|worker| !worker.is_canceled());
+                self.workers.push_back(worker);
+            }
+        }
+    }
+}
+
+impl<M: Job> Default for Dispatcher<M> where M::Result: Send {
+    fn default() -> Dispatcher<M> {
+        Dispatcher::new()
+    }
+}
+
+impl<M: Job> Actor for Dispatcher<M> where M::Result: Send {
+    type Context = Context<Self>;
+}
+
+impl<M: Job> Handler<M> for Dispatcher<M> where M::Result: Send {
+    type Result = Deferred<M>;
+
+    fn handle(&mut self, msg: M, _: &mut Self::Context) -> Deferred<M> {
+        let (tx, rx) = channel();
+        self.dispatch(Work{job: msg, tx});
+        Deferred(rx)
+    }
+}
+
+impl<M: Job> Handler<Pull<M>> for Dispatcher<M> where M::Result: Send {
+    type Result = Pulled<M>;
+
+    fn handle(&mut self, _: Pull<M>, _: &mut Self::Context) -> Pulled<M> {
+        Pulled(self.next_job())
+    }
+}
+
+/// Response channel of the pulled job, signals completion of the job
+struct Reply<M: Message> {
+    tx: Sender<M::Result>,
+    done: Sender<()>,
+}
+
+impl<M: Message + 'static> ResponseChannel<M> for Reply<M> {
+    fn is_canceled(&self) -> bool {
+        self.tx.is_canceled()
+    }
+
+    fn send(self, response: M::Result) {
+        let _ = self.tx.send(response);
+        let _ = self.done.send(());
+    }
+}
+
+/// Pull jobs from the dispatcher, one at a time
+///
+/// Worker handles pulled job with its `Handler` implementation and pulls next
+/// job once response of the handler is resolved, so worker with asynchronous
+/// handler does not take jobs while previous job is in progress. Pulling
+/// stops when worker stops or restarts, or when dispatcher stops.
+pub fn pull<A, M>(ctx: &mut Context<A>, dispatcher: &Addr<Syn, Dispatcher<M>>)
+    where A: Actor<Context=Context<A>> + Handler<M>,
+          M: Job, M::Result: Send,
+{
+    let dispatcher = dispatcher.clone();
+    ctx.spawn(
+        fut::wrap_future::<_, A>(dispatcher.send(Pull::new()))
+            .then(move |res, act, ctx| {
+                if let Ok(work) = res {
+                    let Work{job, tx} = work;
+                    let (done, finished) = channel();
+                    <A as Handler<M>>::handle(act, job, ctx)
+                        .handle(ctx, Some(Reply::<M>{tx, done}));
+                    // response could be dropped without reply, pull anyway
+                    ctx.spawn(fut::wrap_future::<_, A>(finished).then(move |_, _, ctx| {
+                        pull(ctx, &dispatcher);
+                        fut::ok(())
+                    }));
+                }
+                fut::ok(())
+            }));
+}
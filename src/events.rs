@@ -0,0 +1,199 @@
+//! System events
+//!
+//! [`EventStream`](struct.EventStream.html) is a system service that publishes
+//! lifecycle events of all actors of the system as
+//! [`SystemEvent`](enum.SystemEvent.html) messages. Events get published only
+//! while service is running, service starts on first subscription.
+//! Actors of sync arbiters are not reported.
+//!
+//! ```rust
+//! # extern crate actix;
+//! use actix::prelude::*;
+//! use actix::events::{self, EventStream, SystemEvent};
+//!
+//! struct Monitor;
+//!
+//! impl Actor for Monitor {
+//!     type Context = Context<Self>;
+//!
+//!     fn started(&mut self, ctx: &mut Context<Self>) {
+//!         let addr: Addr<Syn, _> = ctx.address();
+//!         EventStream::from_registry().do_send(events::Subscribe(addr.recipient()));
+//!     }
+//! }
+//!
+//! impl Handler<SystemEvent> for Monitor {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, msg: SystemEvent, _: &mut Context<Self>) {
+//!         println!("System event: {:?}", msg);
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     let _: Addr<Syn, _> = Monitor.start();
+//! #   Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!     sys.run();
+//! }
+//! ```
+use std::mem;
+use std::cell::Cell;
+use std::any::TypeId;
+
+use actor::{Actor, Supervised};
+use arbiter::Arbiter;
+use address::{Recipient, Syn};
+use context::Context;
+use deadletter::DeadLetter;
+use handler::{Handler, Message};
+use path::ActorPath;
+use registry::SystemService;
+use span;
+use supervisor::RestartReason;
+
+thread_local!(static PUBLISHING: Cell<bool> = Cell::new(false));
+
+/// Lifecycle event of the system
+#[derive(Clone, Debug)]
+pub enum SystemEvent {
+    /// Actor started
+    ActorStarted {
+        /// Type of the actor
+        actor_type: TypeId,
+        /// Name of the arbiter actor runs in, if known
+        arbiter: Option<String>,
+        /// Path of the actor, if known
+        path: Option<ActorPath>,
+    },
+    /// Actor stopped
+    ActorStopped {
+        /// Type of the actor
+        actor_type: TypeId,
+        /// Name of the arbiter actor runs in, if known
+        arbiter: Option<String>,
+        /// Path of the actor, if known
+        path: Option<ActorPath>,
+    },
+    /// Supervisor restarted actor
+    SupervisorRestarted {
+        /// Type of the actor
+        actor_type: TypeId,
+        /// Name of the arbiter actor runs in, if known
+        arbiter: Option<String>,
+        /// Path of the actor, if known
+        path: Option<ActorPath>,
+        /// Reason of actor failure
+        reason: RestartReason,
+    },
+    /// Message was not delivered
+    DeadLetter(DeadLetter),
+}
+
+impl SystemEvent {
+    /// Check if event is related to actor of type `A`
+    pub fn is<A: Actor>(&self) -> bool {
+        match *self {
+            SystemEvent::ActorStarted{actor_type, ..} |
+            SystemEvent::ActorStopped{actor_type, ..} |
+            SystemEvent::SupervisorRestarted{actor_type, ..} =>
+                actor_type == TypeId::of::<A>(),
+            SystemEvent::DeadLetter(_) => false,
+        }
+    }
+}
+
+impl Message for SystemEvent {
+    type Result = ();
+}
+
+/// Publish event to system `EventStream` service.
+///
+/// Event is dropped if service is not running.
+pub(crate) fn publish<F>(f: F) where F: FnOnce() -> SystemEvent {
+    // publishing of event could cause new events
+    if PUBLISHING.with(|cell| cell.replace(true)) {
+        return
+    }
+    if let Some(registry) = Arbiter::try_system_registry() {
+        if let Some(addr) = registry.query::<EventStream>() {
+            addr.do_send(f());
+        }
+    }
+    PUBLISHING.with(|cell| cell.set(false));
+}
+
+/// Publish lifecycle event of actor `A`
+pub(crate) fn actor_started<A: Actor>(path: Option<&ActorPath>) {
+    span::actor_started::<A>();
+    if TypeId::of::<A>() != TypeId::of::<EventStream>() {
+        publish(|| SystemEvent::ActorStarted{
+            actor_type: TypeId::of::<A>(), arbiter: Arbiter::try_name(),
+            path: path.cloned()});
+    }
+}
+
+/// Publish lifecycle event of actor `A`
+pub(crate) fn actor_stopped<A: Actor>(path: Option<&ActorPath>) {
+    span::actor_stopped::<A>();
+    if TypeId::of::<A>() != TypeId::of::<EventStream>() {
+        publish(|| SystemEvent::ActorStopped{
+            actor_type: TypeId::of::<A>(), arbiter: Arbiter::try_name(),
+            path: path.cloned()});
+    }
+}
+
+/// Publish restart event of actor `A`
+pub(crate) fn actor_restarted<A: Actor>(path: Option<&ActorPath>, reason: &RestartReason) {
+    span::actor_restarted::<A>(reason);
+    if TypeId::of::<A>() != TypeId::of::<EventStream>() {
+        publish(|| SystemEvent::SupervisorRestarted{
+            actor_type: TypeId::of::<A>(), arbiter: Arbiter::try_name(),
+            path: path.cloned(), reason: reason.clone()});
+    }
+}
+
+/// System wide event stream service
+///
+/// Service forwards system events to all subscribers.
+#[derive(Default)]
+pub struct EventStream {
+    subscribers: Vec<Recipient<Syn, SystemEvent>>,
+}
+
+impl Actor for EventStream {
+    type Context = Context<Self>;
+}
+
+impl Supervised for EventStream {}
+
+impl SystemService for EventStream {}
+
+impl Handler<SystemEvent> for EventStream {
+    type Result = ();
+
+    fn handle(&mut self, msg: SystemEvent, _: &mut Context<Self>) {
+        let subscribers = mem::replace(&mut self.subscribers, Vec::new());
+        for subscr in subscribers {
+            if subscr.do_send(msg.clone()).is_ok() {
+                self.subscribers.push(subscr);
+            }
+        }
+    }
+}
+
+/// Subscribe to system events
+pub struct Subscribe(pub Recipient<Syn, SystemEvent>);
+
+impl Message for Subscribe {
+    type Result = ();
+}
+
+impl Handler<Subscribe> for EventStream {
+    type Result = ();
+
+    fn handle(&mut self, msg: Subscribe, _: &mut Context<Self>) {
+        self.subscribers.push(msg.0);
+    }
+}
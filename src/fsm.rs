@@ -0,0 +1,239 @@
+//! Finite state machines
+//!
+//! [`StateMachine`](trait.StateMachine.html) actor keeps its current state in
+//! the context. Every message type the machine reacts to implements
+//! [`StateHandler`](trait.StateHandler.html), which declares states the
+//! message is handled in and returns the next state. Actor's `Handler`
+//! delegates to [`fsm::handle()`](fn.handle.html). Messages that arrive in
+//! other states are passed to `StateMachine::unhandled()`.
+//!
+//! Transition to other state calls `StateMachine::on_exit()` for the
+//! previous state and `StateMachine::on_enter()` for the next one. State
+//! could have a timeout, if machine stays in state longer than
+//! `StateMachine::state_timeout()`, `StateMachine::on_timeout()` is called.
+//!
+//! Machine enters its initial state with [`fsm::start()`](fn.start.html) or
+//! on the first handled message. Restarted actor starts from initial
+//! state again.
+//!
+//! ```rust
+//! # extern crate actix;
+//! use std::time::Duration;
+//! use actix::prelude::*;
+//! use actix::fsm::{self, Next, StateHandler, StateMachine};
+//!
+//! #[derive(Clone, PartialEq, Debug)]
+//! enum Door {
+//!     Locked,
+//!     Open,
+//! }
+//!
+//! struct Unlock;
+//!
+//! impl Message for Unlock {
+//!     type Result = ();
+//! }
+//!
+//! struct Turnstile;
+//!
+//! impl Actor for Turnstile {
+//!     type Context = Context<Self>;
+//!
+//!     fn started(&mut self, ctx: &mut Context<Self>) {
+//!         fsm::start(self, ctx);
+//!     }
+//! }
+//!
+//! impl StateMachine for Turnstile {
+//!     type State = Door;
+//!
+//!     fn initial_state(&self) -> Door {
+//!         Door::Locked
+//!     }
+//!
+//!     fn state_timeout(&self, state: &Door) -> Option<Duration> {
+//!         match *state {
+//!             Door::Open => Some(Duration::from_secs(5)),
+//!             Door::Locked => None,
+//!         }
+//!     }
+//!
+//!     fn on_timeout(&mut self, _: &Door, _: &mut Context<Self>) -> Next<Door> {
+//!         Next::Goto(Door::Locked)
+//!     }
+//! }
+//!
+//! impl StateHandler<Unlock> for Turnstile {
+//!     fn handles(state: &Door) -> bool {
+//!         *state == Door::Locked
+//!     }
+//!
+//!     fn handle_in(&mut self, _: &Door, _: Unlock, _: &mut Context<Self>) -> Next<Door> {
+//!         Next::Goto(Door::Open)
+//!     }
+//! }
+//!
+//! impl Handler<Unlock> for Turnstile {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, msg: Unlock, ctx: &mut Context<Self>) {
+//!         fsm::handle(self, msg, ctx);
+//! #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!     let addr: Addr<Unsync, _> = Turnstile.start();
+//!     addr.do_send(Unlock);
+//!     sys.run();
+//! }
+//! ```
+use std::fmt;
+use std::time::Duration;
+
+use actor::{Actor, ActorContext, AsyncContext, SpawnHandle};
+use context::Context;
+use handler::Message;
+
+/// Result of state handler
+#[derive(Clone, PartialEq, Debug)]
+pub enum Next<S> {
+    /// Stay in current state, state timeout keeps running
+    Stay,
+    /// Transition to state, transition to current state re-enters it
+    Goto(S),
+    /// Stop actor
+    Stop,
+}
+
+#[allow(unused_variables)]
+/// Actor which behavior is defined by its current state
+pub trait StateMachine: Actor<Context=Context<Self>> {
+    /// States of the machine
+    type State: Clone + fmt::Debug + 'static;
+
+    /// State machine enters on start
+    fn initial_state(&self) -> Self::State;
+
+    /// Method is called when machine enters `state`, including initial state
+    fn on_enter(&mut self, state: &Self::State, ctx: &mut Context<Self>) {}
+
+    /// Method is called when machine leaves `state`
+    fn on_exit(&mut self, state: &Self::State, ctx: &mut Context<Self>) {}
+
+    /// Maximum time machine stays in `state`. By default states do not
+    /// time out.
+    fn state_timeout(&self, state: &Self::State) -> Option<Duration> {
+        None
+    }
+
+    /// Method is called once `state` times out
+    fn on_timeout(&mut self, state: &Self::State, ctx: &mut Context<Self>) -> Next<Self::State> {
+        Next::Stay
+    }
+
+    /// Method is called for message that is not handled in `state`.
+    /// By default message is dropped.
+    fn unhandled(&mut self, state: &Self::State, ctx: &mut Context<Self>) {
+        warn!("Message is not handled in state {:?}", state);
+    }
+}
+
+/// Handler of message `M` in states of the machine
+pub trait StateHandler<M>: StateMachine where M: Message<Result=()> {
+    /// Check if message is handled in `state`. By default message is
+    /// handled in every state.
+    #[allow(unused_variables)]
+    fn handles(state: &Self::State) -> bool {
+        true
+    }
+
+    /// Handle message in `state`, returns next state
+    fn handle_in(&mut self, state: &Self::State, msg: M, ctx: &mut Context<Self>)
+                 -> Next<Self::State>;
+}
+
+/// State of context's machine
+struct Machine<S> {
+    state: Option<S>,
+    timeout: Option<SpawnHandle>,
+}
+
+impl<S> Default for Machine<S> {
+    fn default() -> Self {
+        Machine{state: None, timeout: None}
+    }
+}
+
+/// Enter initial state, if machine is not started yet
+pub fn start<A>(act: &mut A, ctx: &mut Context<A>) where A: StateMachine {
+    if ctx.extension::<Machine<A::State>>().state.is_none() {
+        let state = act.initial_state();
+        enter(act, state, ctx);
+    }
+}
+
+/// Current state of the machine, `None` if machine is not started
+pub fn state<A>(ctx: &mut Context<A>) -> Option<A::State> where A: StateMachine {
+    ctx.extension::<Machine<A::State>>().state.clone()
+}
+
+/// Pass message to state handler of the current state
+pub fn handle<A, M>(act: &mut A, msg: M, ctx: &mut Context<A>)
+    where A: StateHandler<M>, M: Message<Result=()>
+{
+    start(act, ctx);
+    let state = state(ctx).unwrap();
+    if A::handles(&state) {
+        let next = act.handle_in(&state, msg, ctx);
+        transition(act, next, ctx);
+    } else {
+        act.unhandled(&state, ctx);
+    }
+}
+
+/// Apply result of state handler
+pub fn transition<A>(act: &mut A, next: Next<A::State>, ctx: &mut Context<A>)
+    where A: StateMachine
+{
+    match next {
+        Next::Stay => (),
+        Next::Stop => ctx.stop(),
+        Next::Goto(state) => {
+            let (prev, timeout) = {
+                let machine = ctx.extension::<Machine<A::State>>();
+                (machine.state.take(), machine.timeout.take())
+            };
+            if let Some(handle) = timeout {
+                ctx.cancel_future(handle);
+            }
+            if let Some(prev) = prev {
+                act.on_exit(&prev, ctx);
+            }
+            enter(act, state, ctx);
+        }
+    }
+}
+
+fn enter<A>(act: &mut A, state: A::State, ctx: &mut Context<A>) where A: StateMachine {
+    let timeout = act.state_timeout(&state).map(|dur| {
+        ctx.run_later(dur, |act, ctx| {
+            let state = {
+                let machine = ctx.extension::<Machine<A::State>>();
+                machine.timeout = None;
+                machine.state.clone()
+            };
+            if let Some(state) = state {
+                let next = act.on_timeout(&state, ctx);
+                transition(act, next, ctx);
+            }
+        })
+    });
+    {
+        let machine = ctx.extension::<Machine<A::State>>();
+        machine.state = Some(state.clone());
+        machine.timeout = timeout;
+    }
+    act.on_enter(&state, ctx);
+}
@@ -0,0 +1,98 @@
+use std::mem;
+use futures::{Async, Poll};
+
+use actor::Actor;
+use fut::ActorFuture;
+
+
+/// Future for the `join` combinator, waiting for two futures to complete.
+///
+/// This is created by the `ActorFuture::join` method.
+#[must_use = "futures do nothing unless polled"]
+pub struct Join<A, B> where A: ActorFuture, B: ActorFuture<Actor=A::Actor> {
+    a: MaybeDone<A>,
+    b: MaybeDone<B>,
+}
+
+pub fn new<A, B>(a: A, b: B) -> Join<A, B>
+    where A: ActorFuture, B: ActorFuture<Error=A::Error, Actor=A::Actor>,
+{
+    Join {
+        a: MaybeDone::NotYet(a),
+        b: MaybeDone::NotYet(b),
+    }
+}
+
+impl<A, B> ActorFuture for Join<A, B>
+    where A: ActorFuture, B: ActorFuture<Error=A::Error, Actor=A::Actor>,
+{
+    type Item = (A::Item, B::Item);
+    type Error = A::Error;
+    type Actor = A::Actor;
+
+    fn poll(&mut self,
+            act: &mut A::Actor,
+            ctx: &mut <A::Actor as Actor>::Context) -> Poll<Self::Item, A::Error>
+    {
+        let mut all_done = match self.a.poll(act, ctx) {
+            Ok(done) => done,
+            Err(e) => {
+                self.erase();
+                return Err(e)
+            }
+        };
+        all_done = match self.b.poll(act, ctx) {
+            Ok(done) => all_done && done,
+            Err(e) => {
+                self.erase();
+                return Err(e)
+            }
+        };
+
+        if all_done {
+            Ok(Async::Ready((self.a.take(), self.b.take())))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+impl<A, B> Join<A, B> where A: ActorFuture, B: ActorFuture<Actor=A::Actor> {
+    fn erase(&mut self) {
+        self.a = MaybeDone::Gone;
+        self.b = MaybeDone::Gone;
+    }
+}
+
+enum MaybeDone<A: ActorFuture> {
+    NotYet(A),
+    Done(A::Item),
+    Gone,
+}
+
+impl<A: ActorFuture> MaybeDone<A> {
+    fn poll(&mut self,
+            act: &mut A::Actor,
+            ctx: &mut <A::Actor as Actor>::Context) -> Result<bool, A::Error>
+    {
+        let res = match *self {
+            MaybeDone::NotYet(ref mut a) => a.poll(act, ctx)?,
+            MaybeDone::Done(_) => return Ok(true),
+            MaybeDone::Gone => panic!("cannot poll Join twice"),
+        };
+        match res {
+            Async::Ready(res) => {
+                *self = MaybeDone::Done(res);
+                Ok(true)
+            }
+            Async::NotReady => Ok(false),
+        }
+    }
+
+    fn take(&mut self) -> A::Item {
+        match mem::replace(self, MaybeDone::Gone) {
+            MaybeDone::Done(a) => a,
+            _ => panic!(),
+        }
+    }
+}
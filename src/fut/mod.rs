@@ -8,10 +8,12 @@ mod chain;
 mod and_then;
 mod either;
 mod from_err;
+mod join;
 mod result;
 mod then;
 mod map;
 mod map_err;
+mod select;
 mod timeout;
 mod stream_map;
 mod stream_map_err;
@@ -19,13 +21,19 @@ mod stream_then;
 mod stream_and_then;
 mod stream_finish;
 mod stream_fold;
+mod stream_for_each;
+mod stream_take_while;
 mod stream_timeout;
 mod helpers;
+mod pipe;
+mod scatter;
 
 pub use self::either::Either;
 pub use self::and_then::AndThen;
 pub use self::from_err::FromErr;
 pub use self::then::Then;
+pub use self::join::Join;
+pub use self::select::Select;
 pub use self::timeout::Timeout;
 pub use self::map::Map;
 pub use self::map_err::{MapErr, DropErr};
@@ -36,8 +44,12 @@ pub use self::stream_then::StreamThen;
 pub use self::stream_and_then::StreamAndThen;
 pub use self::stream_finish::StreamFinish;
 pub use self::stream_fold::StreamFold;
+pub use self::stream_for_each::StreamForEach;
+pub use self::stream_take_while::StreamTakeWhile;
 pub use self::stream_timeout::StreamTimeout;
 pub use self::helpers::{Finish, FinishStream};
+pub use self::pipe::{Pipe, PipeTo};
+pub use self::scatter::{scatter, GatherPolicy, Scatter};
 
 use actor::Actor;
 
@@ -61,6 +73,13 @@ pub trait ActorFuture {
     fn poll(&mut self, srv: &mut Self::Actor, ctx: &mut <Self::Actor as Actor>::Context)
             -> Poll<Self::Item, Self::Error>;
 
+    #[doc(hidden)]
+    /// Supervisor keeps future in restarted context if restart policy
+    /// preserves timers
+    fn preserve_on_restart(&self) -> bool {
+        false
+    }
+
     /// Map this future's result to a different type, returning a new future of
     /// the resulting type.
     fn map<F, U>(self, f: F) -> Map<Self, F>
@@ -117,6 +136,28 @@ pub trait ActorFuture {
     fn timeout(self, timeout: Duration, err: Self::Error) -> Timeout<Self> where Self: Sized {
         timeout::new(self, timeout, err)
     }
+
+    /// Join the result of two futures, waiting for them both to complete.
+    ///
+    /// If either future is resolved with an error, the other one is dropped
+    /// and the error is returned.
+    fn join<B>(self, other: B) -> Join<Self, B::Future>
+        where B: IntoActorFuture<Error=Self::Error, Actor=Self::Actor>,
+              Self: Sized,
+    {
+        join::new(self, other.into_future())
+    }
+
+    /// Wait for either of two futures to complete.
+    ///
+    /// Result of the first completed future is returned, the other
+    /// one is dropped.
+    fn select<B>(self, other: B) -> Select<Self, B::Future>
+        where B: IntoActorFuture<Item=Self::Item, Error=Self::Error, Actor=Self::Actor>,
+              Self: Sized,
+    {
+        select::new(self, other.into_future())
+    }
 }
 
 /// A stream of values, not all of which may have been produced yet.
@@ -184,6 +225,31 @@ pub trait ActorStream {
         stream_fold::new(self, f, init)
     }
 
+    /// Runs this stream to completion, executing the provided closure for
+    /// each element on the stream.
+    ///
+    /// Next element is processed once the future returned by the closure
+    /// resolves.
+    fn for_each<F, U>(self, f: F) -> StreamForEach<Self, F, U>
+        where F: FnMut(Self::Item, &mut Self::Actor, &mut <Self::Actor as Actor>::Context) -> U,
+              U: IntoActorFuture<Item=(), Error=Self::Error, Actor=Self::Actor>,
+              Self: Sized
+    {
+        stream_for_each::new(self, f)
+    }
+
+    /// Take elements from this stream while the provided predicate
+    /// resolves to `true`.
+    ///
+    /// Stream is finished once predicate resolves to `false`.
+    fn take_while<F, U>(self, f: F) -> StreamTakeWhile<Self, Self::Item, F, U>
+        where F: FnMut(&Self::Item, &mut Self::Actor, &mut <Self::Actor as Actor>::Context) -> U,
+              U: IntoActorFuture<Item=bool, Error=Self::Error, Actor=Self::Actor>,
+              Self: Sized
+    {
+        stream_take_while::new(self, f)
+    }
+
     /// Add timeout to stream.
     ///
     /// `err` value get returned as a timeout error.
@@ -241,6 +307,10 @@ impl<F: ActorFuture + ?Sized> ActorFuture for Box<F> {
             ctx: &mut <Self::Actor as Actor>::Context) -> Poll<Self::Item, Self::Error> {
         (**self).poll(srv, ctx)
     }
+
+    fn preserve_on_restart(&self) -> bool {
+        (**self).preserve_on_restart()
+    }
 }
 
 /// Helper trait that allows conversion of normal future into `ActorFuture`
@@ -0,0 +1,94 @@
+use futures::{Async, Future, Poll};
+
+use address::{MessageRecipient, Recipient};
+use handler::Message;
+
+/// Helper trait that adds `pipe_to()` method to futures
+pub trait PipeTo: Future + Sized {
+    /// Send result of the future to the recipient as a message
+    ///
+    /// Message is created from future's `Ok` or `Err` result with
+    /// `From<Result<Item, Error>>` conversion. Recipient could be an
+    /// address of the actor or a `Recipient`. Returned future has to be
+    /// spawned, it resolves once message is sent.
+    ///
+    /// ```rust
+    /// # extern crate actix;
+    /// # extern crate futures;
+    /// use actix::prelude::*;
+    /// use actix::fut::PipeTo;
+    ///
+    /// struct Fetched(Result<u32, ()>);
+    ///
+    /// impl Message for Fetched {
+    ///     type Result = ();
+    /// }
+    ///
+    /// impl From<Result<u32, ()>> for Fetched {
+    ///     fn from(res: Result<u32, ()>) -> Fetched {
+    ///         Fetched(res)
+    ///     }
+    /// }
+    ///
+    /// struct MyActor;
+    ///
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Fetched> for MyActor {
+    ///     type Result = ();
+    ///
+    ///     fn handle(&mut self, msg: Fetched, _: &mut Context<Self>) {
+    ///         assert_eq!(msg.0, Ok(42));
+    /// #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let sys = System::new("test");
+    ///     let addr: Addr<Syn, _> = MyActor.start();
+    ///     Arbiter::handle().spawn(futures::future::ok(42).pipe_to(addr));
+    ///     sys.run();
+    /// }
+    /// ```
+    fn pipe_to<R, T, M>(self, dest: R) -> Pipe<Self, T, M>
+        where R: Into<Recipient<T, M>>, T: MessageRecipient<M>,
+              M: Message + From<Result<Self::Item, Self::Error>> + 'static;
+}
+
+impl<F: Future> PipeTo for F {
+    fn pipe_to<R, T, M>(self, dest: R) -> Pipe<Self, T, M>
+        where R: Into<Recipient<T, M>>, T: MessageRecipient<M>,
+              M: Message + From<Result<F::Item, F::Error>> + 'static
+    {
+        Pipe{fut: self, dest: dest.into()}
+    }
+}
+
+/// Future that sends result of the inner future as a message
+///
+/// This structure is produced by the `PipeTo::pipe_to` method.
+#[must_use = "future do nothing unless polled"]
+pub struct Pipe<F, T, M> where T: MessageRecipient<M>, M: Message + 'static {
+    fut: F,
+    dest: Recipient<T, M>,
+}
+
+impl<F, T, M> Future for Pipe<F, T, M>
+    where F: Future, T: MessageRecipient<M>,
+          M: Message + From<Result<F::Item, F::Error>> + 'static
+{
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        let res = match self.fut.poll() {
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            Ok(Async::Ready(item)) => Ok(item),
+            Err(err) => Err(err),
+        };
+        let _ = self.dest.do_send(M::from(res));
+        Ok(Async::Ready(()))
+    }
+}
@@ -0,0 +1,169 @@
+use std::mem;
+use std::time::Duration;
+use std::marker::PhantomData;
+use futures::{Async, Future, Poll};
+
+use actor::Actor;
+use address::{MailboxError, MessageRecipient, Recipient};
+use clock::Delay;
+use fut::ActorFuture;
+use handler::Message;
+
+/// Defines how `Scatter` future handles failed recipients
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GatherPolicy {
+    /// Future fails with error of the first failed recipient.
+    /// This is default policy.
+    All,
+    /// Future waits for all recipients, failed recipients get their
+    /// error in the result slot
+    Partial,
+}
+
+/// Send copy of the message to every recipient and collect responses
+///
+/// Returned future resolves to results in order of recipients, one slot per
+/// recipient. With `GatherPolicy::All` every slot holds response.
+/// `Scatter::timeout()` limits waiting for each recipient, recipient that
+/// does not respond in time fails with `MailboxError::Timeout`.
+///
+/// ```rust
+/// # extern crate actix;
+/// # extern crate futures;
+/// use std::time::Duration;
+/// use actix::prelude::*;
+/// use actix::fut::{self, GatherPolicy};
+///
+/// #[derive(Clone)]
+/// struct Price;
+///
+/// impl Message for Price {
+///     type Result = u32;
+/// }
+///
+/// struct Shop(u32);
+///
+/// impl Actor for Shop {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Price> for Shop {
+///     type Result = u32;
+///
+///     fn handle(&mut self, _: Price, _: &mut Context<Self>) -> u32 {
+///         self.0
+///     }
+/// }
+///
+/// struct Buyer(Vec<Recipient<Syn, Price>>);
+///
+/// impl Actor for Buyer {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         fut::scatter(&self.0, Price)
+///             .timeout(Duration::from_secs(1))
+///             .policy(GatherPolicy::Partial)
+///             .map(|prices, _: &mut Buyer, _| {
+///                 let cheapest = prices.iter().filter_map(|price| price.as_ref().ok()).min();
+///                 assert_eq!(cheapest, Some(&3));
+/// #               Arbiter::system().do_send(actix::msgs::SystemExit(0));
+///             })
+///             .map_err(|_, _, _| ())
+///             .spawn(ctx);
+///     }
+/// }
+///
+/// fn main() {
+///     let sys = System::new("test");
+///     let shops = vec![Shop(5), Shop(3)].into_iter()
+///         .map(|shop| shop.start::<Addr<Syn, _>>().recipient()).collect();
+///     let _: Addr<Unsync, _> = Buyer(shops).start();
+///     sys.run();
+/// }
+/// ```
+pub fn scatter<A, T, M>(recipients: &[Recipient<T, M>], msg: M) -> Scatter<A, T, M>
+    where A: Actor, T: MessageRecipient<M, MailboxError=MailboxError>, M: Message + Clone + 'static
+{
+    Scatter {
+        requests: recipients.iter().map(|rcp| Some(rcp.send(msg.clone()))).collect(),
+        results: recipients.iter().map(|_| None).collect(),
+        policy: GatherPolicy::All,
+        timeout: None,
+        act: PhantomData,
+    }
+}
+
+/// Future for the `scatter()` function
+#[must_use = "futures do nothing unless polled"]
+pub struct Scatter<A, T, M>
+    where T: MessageRecipient<M>, M: Message + 'static
+{
+    requests: Vec<Option<T::Request>>,
+    results: Vec<Option<Result<M::Result, MailboxError>>>,
+    policy: GatherPolicy,
+    timeout: Option<Delay>,
+    act: PhantomData<A>,
+}
+
+impl<A, T, M> Scatter<A, T, M>
+    where T: MessageRecipient<M>, M: Message + 'static
+{
+    /// Set timeout for each recipient's response
+    pub fn timeout(mut self, dur: Duration) -> Self {
+        self.timeout = Some(Delay::new(dur));
+        self
+    }
+
+    /// Set policy for failed recipients
+    pub fn policy(mut self, policy: GatherPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+impl<A, T, M> ActorFuture for Scatter<A, T, M>
+    where A: Actor, T: MessageRecipient<M, MailboxError=MailboxError>, M: Message + 'static
+{
+    type Item = Vec<Result<M::Result, MailboxError>>;
+    type Error = MailboxError;
+    type Actor = A;
+
+    fn poll(&mut self, _: &mut A, _: &mut A::Context) -> Poll<Self::Item, MailboxError> {
+        let timed_out = match self.timeout {
+            Some(ref mut timeout) => match timeout.poll() {
+                Ok(Async::NotReady) => false,
+                Ok(Async::Ready(())) | Err(_) => true,
+            },
+            None => false,
+        };
+
+        let mut pending = false;
+        for (req, res) in self.requests.iter_mut().zip(self.results.iter_mut()) {
+            let item = match *req {
+                Some(ref mut fut) => match fut.poll() {
+                    Ok(Async::Ready(item)) => Ok(item),
+                    Ok(Async::NotReady) if timed_out => Err(MailboxError::Timeout),
+                    Ok(Async::NotReady) => {
+                        pending = true;
+                        continue
+                    },
+                    Err(err) => Err(err),
+                },
+                None => continue,
+            };
+            *req = None;
+            match item {
+                Err(err) if self.policy == GatherPolicy::All => return Err(err),
+                item => *res = Some(item),
+            }
+        }
+
+        if pending {
+            return Ok(Async::NotReady)
+        }
+        // every slot is resolved
+        let results = mem::replace(&mut self.results, Vec::new());
+        Ok(Async::Ready(results.into_iter().filter_map(|res| res).collect()))
+    }
+}
@@ -0,0 +1,52 @@
+use futures::{Async, Poll};
+
+use actor::Actor;
+use fut::ActorFuture;
+
+
+/// Future for the `select` combinator, waiting for one of two futures
+/// to complete.
+///
+/// This is created by the `ActorFuture::select` method.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct Select<A, B> where A: ActorFuture {
+    inner: Option<(A, B)>,
+}
+
+pub fn new<A, B>(a: A, b: B) -> Select<A, B>
+    where A: ActorFuture,
+          B: ActorFuture<Item=A::Item, Error=A::Error, Actor=A::Actor>,
+{
+    Select { inner: Some((a, b)) }
+}
+
+impl<A, B> ActorFuture for Select<A, B>
+    where A: ActorFuture,
+          B: ActorFuture<Item=A::Item, Error=A::Error, Actor=A::Actor>,
+{
+    type Item = A::Item;
+    type Error = A::Error;
+    type Actor = A::Actor;
+
+    fn poll(&mut self,
+            act: &mut A::Actor,
+            ctx: &mut <A::Actor as Actor>::Context) -> Poll<A::Item, A::Error>
+    {
+        let res = {
+            let &mut (ref mut a, ref mut b) =
+                self.inner.as_mut().expect("cannot poll Select twice");
+            match a.poll(act, ctx) {
+                Ok(Async::NotReady) => b.poll(act, ctx),
+                res => res,
+            }
+        };
+
+        // other future gets dropped
+        match res {
+            Ok(Async::NotReady) => (),
+            _ => { self.inner.take(); },
+        }
+        res
+    }
+}
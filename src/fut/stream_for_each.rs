@@ -0,0 +1,59 @@
+use futures::{Async, Poll};
+
+use actor::Actor;
+use fut::{ActorFuture, ActorStream, IntoActorFuture};
+
+/// A stream combinator which executes a unit closure over each item on a
+/// stream.
+///
+/// This structure is returned by the `ActorStream::for_each` method.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct StreamForEach<S, F, U> where U: IntoActorFuture {
+    stream: S,
+    f: F,
+    fut: Option<U::Future>,
+}
+
+pub fn new<S, F, U>(s: S, f: F) -> StreamForEach<S, F, U>
+    where S: ActorStream,
+          F: FnMut(S::Item, &mut S::Actor, &mut <S::Actor as Actor>::Context) -> U,
+          U: IntoActorFuture<Item=(), Error=S::Error, Actor=S::Actor>,
+{
+    StreamForEach {
+        stream: s,
+        f,
+        fut: None,
+    }
+}
+
+impl<S, F, U> ActorFuture for StreamForEach<S, F, U>
+    where S: ActorStream,
+          F: FnMut(S::Item, &mut S::Actor, &mut <S::Actor as Actor>::Context) -> U,
+          U: IntoActorFuture<Item=(), Error=S::Error, Actor=S::Actor>,
+{
+    type Item = ();
+    type Error = S::Error;
+    type Actor = S::Actor;
+
+    fn poll(&mut self,
+            act: &mut S::Actor,
+            ctx: &mut <S::Actor as Actor>::Context) -> Poll<(), S::Error>
+    {
+        loop {
+            if let Some(mut fut) = self.fut.take() {
+                if fut.poll(act, ctx)?.is_not_ready() {
+                    self.fut = Some(fut);
+                    return Ok(Async::NotReady)
+                }
+            }
+
+            match self.stream.poll(act, ctx)? {
+                Async::Ready(Some(e)) =>
+                    self.fut = Some((self.f)(e, act, ctx).into_future()),
+                Async::Ready(None) => return Ok(Async::Ready(())),
+                Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    }
+}
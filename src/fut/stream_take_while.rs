@@ -0,0 +1,80 @@
+use futures::{Async, Poll};
+
+use actor::Actor;
+use fut::{ActorFuture, ActorStream, IntoActorFuture};
+
+/// A stream combinator which takes elements from a stream while a predicate
+/// holds.
+///
+/// This structure is produced by the `ActorStream::take_while` method.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct StreamTakeWhile<S, I, F, U> where U: IntoActorFuture {
+    stream: S,
+    pred: F,
+    pending: Option<(U::Future, I)>,
+    done_taking: bool,
+}
+
+pub fn new<S, F, U>(s: S, f: F) -> StreamTakeWhile<S, S::Item, F, U>
+    where S: ActorStream,
+          F: FnMut(&S::Item, &mut S::Actor, &mut <S::Actor as Actor>::Context) -> U,
+          U: IntoActorFuture<Item=bool, Error=S::Error, Actor=S::Actor>,
+{
+    StreamTakeWhile {
+        stream: s,
+        pred: f,
+        pending: None,
+        done_taking: false,
+    }
+}
+
+impl<S, F, U> ActorStream for StreamTakeWhile<S, S::Item, F, U>
+    where S: ActorStream,
+          F: FnMut(&S::Item, &mut S::Actor, &mut <S::Actor as Actor>::Context) -> U,
+          U: IntoActorFuture<Item=bool, Error=S::Error, Actor=S::Actor>,
+{
+    type Item = S::Item;
+    type Error = S::Error;
+    type Actor = S::Actor;
+
+    fn poll(&mut self,
+            act: &mut S::Actor,
+            ctx: &mut <S::Actor as Actor>::Context) -> Poll<Option<S::Item>, S::Error>
+    {
+        if self.done_taking {
+            return Ok(Async::Ready(None))
+        }
+
+        if self.pending.is_none() {
+            let item = match self.stream.poll(act, ctx)? {
+                Async::Ready(Some(e)) => e,
+                Async::Ready(None) => return Ok(Async::Ready(None)),
+                Async::NotReady => return Ok(Async::NotReady),
+            };
+            let fut = (self.pred)(&item, act, ctx).into_future();
+            self.pending = Some((fut, item));
+        }
+
+        let res = match self.pending {
+            Some((ref mut fut, _)) => fut.poll(act, ctx),
+            None => unreachable!(),
+        };
+        match res {
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Ok(Async::Ready(true)) => {
+                let (_, item) = self.pending.take().unwrap();
+                Ok(Async::Ready(Some(item)))
+            }
+            Ok(Async::Ready(false)) => {
+                self.pending = None;
+                self.done_taking = true;
+                Ok(Async::Ready(None))
+            }
+            Err(e) => {
+                self.pending = None;
+                Err(e)
+            }
+        }
+    }
+}
@@ -1,10 +1,9 @@
 use std::time::Duration;
 use futures::{Async, Future, Poll};
-use tokio_core::reactor::Timeout as TokioTimeout;
+use clock::Delay;
 
 use fut::ActorStream;
 use actor::Actor;
-use arbiter::Arbiter;
 
 
 /// Future for the `timeout` combinator, interrupts computations if it takes more
@@ -18,7 +17,7 @@ pub struct StreamTimeout<S> where S: ActorStream
     stream: S,
     err: S::Error,
     dur: Duration,
-    timeout: Option<TokioTimeout>,
+    timeout: Option<Delay>,
 }
 
 pub fn new<S>(stream: S, timeout: Duration, err: S::Error) -> StreamTimeout<S>
@@ -52,8 +51,7 @@ impl<S> ActorStream for StreamTimeout<S> where S: ActorStream, S::Error: Clone
         }
 
         if self.timeout.is_none() {
-            self.timeout = Some(
-                TokioTimeout::new(self.dur, Arbiter::handle()).unwrap());
+            self.timeout = Some(Delay::new(self.dur));
         }
 
         // check timeout
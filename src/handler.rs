@@ -1,12 +1,17 @@
-use futures::Future;
+use std::any::Any;
+use futures::{Async, Future, Poll, Stream};
+use futures::sync::mpsc;
 use futures::sync::oneshot::Sender as SyncSender;
 use futures::unsync::oneshot::Sender as UnsyncSender;
 
 use arbiter::Arbiter;
 use fut::{self, ActorFuture};
-use actor::{Actor, AsyncContext};
-use address::{Addr, Syn};
+use actor::{Actor, ActorContext, AsyncContext};
+use address::{Addr, Syn, Forward, Request, ToEnvelope};
+use address::{MessageDestination, MessageDestinationTransport};
 use context::Context;
+use intercept;
+use span::{CorrelationId, Span};
 
 /// Message handler
 ///
@@ -23,7 +28,39 @@ pub trait Handler<M> where Self: Actor, M: Message {
     fn handle(&mut self, msg: M, ctx: &mut Self::Context) -> Self::Result;
 }
 
+/// Call message handler, handler latency is recorded with `metrics` feature,
+/// handling span is logged with `spans` feature. Interceptors of the system
+/// and middleware of the context are called around the handler.
+#[inline]
+pub(crate) fn dispatch<A, M>(act: &mut A, msg: M, ctx: &mut A::Context, cid: CorrelationId)
+                             -> A::Result
+    where A: Handler<M>, M: Message + 'static
+{
+    let _span = Span::enter::<A, M>(cid);
+    let intercepted = intercept::before::<A, M>();
+    let hooks = ctx.middlewares().and_then(|m| m.before::<A, M>());
+    #[cfg(feature="metrics")]
+    let start = ::std::time::Instant::now();
+    let res = act.handle(msg, ctx);
+    #[cfg(feature="metrics")]
+    ::metrics::record::<M>(start);
+    if let Some((meta, start)) = hooks {
+        if let Some(m) = ctx.middlewares() {
+            m.after(&meta, start);
+        }
+    }
+    if let Some(intercepted) = intercepted {
+        intercepted.after();
+    }
+    res
+}
+
 /// Message type
+///
+/// Message could implement this trait with `#[derive(Message)]`. Result type
+/// of the message is set with `#[rtype(result="Result<u64, MyError>")]`
+/// attribute, or with `#[rtype(u64)]` for types that are simple paths.
+/// Derived message without the attribute resolves with `()`.
 pub trait Message {
 
     /// The type of value that this message will resolved with if it is successful.
@@ -34,9 +71,74 @@ pub trait Message {
 pub struct MessageResult<M: Message>(pub M::Result);
 
 /// A specialized actor future for async message handler
+///
+/// Future runs within actor's context, so it has access to actor's state.
+/// Result of the future is delivered to the sender of the message.
+///
+/// ```rust
+/// # extern crate actix;
+/// # extern crate futures;
+/// # use std::io;
+/// use actix::prelude::*;
+///
+/// struct Visit;
+///
+/// impl Message for Visit {
+///     type Result = Result<usize, io::Error>;
+/// }
+///
+/// struct Counter(usize);
+///
+/// impl Actor for Counter {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Visit> for Counter {
+///     type Result = ResponseActFuture<Self, usize, io::Error>;
+///
+///     fn handle(&mut self, _: Visit, _: &mut Context<Self>) -> Self::Result {
+///         Box::new(actix::fut::ok(()).map(|_, act: &mut Counter, _| {
+///             act.0 += 1;
+///             act.0
+///         }))
+///     }
+/// }
+/// # fn main() {}
+/// ```
 pub type ResponseActFuture<A, I, E> = Box<ActorFuture<Item=I, Error=E, Actor=A>>;
 
 /// A specialized future for async message handler
+///
+/// Result of the future is delivered to the sender of the message.
+///
+/// ```rust
+/// # extern crate actix;
+/// # extern crate futures;
+/// # use std::io;
+/// use actix::prelude::*;
+/// use futures::future;
+///
+/// struct Resolve(String);
+///
+/// impl Message for Resolve {
+///     type Result = Result<String, io::Error>;
+/// }
+///
+/// struct Resolver;
+///
+/// impl Actor for Resolver {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Resolve> for Resolver {
+///     type Result = ResponseFuture<String, io::Error>;
+///
+///     fn handle(&mut self, msg: Resolve, _: &mut Context<Self>) -> Self::Result {
+///         Box::new(future::ok(format!("{}:80", msg.0)))
+///     }
+/// }
+/// # fn main() {}
+/// ```
 pub type ResponseFuture<I, E> = Box<Future<Item=I, Error=E>>;
 
 /// Trait defines message response channel
@@ -132,6 +234,58 @@ impl<A, M, I: 'static, E: 'static> MessageResponse<A, M> for ResponseFuture<I, E
     }
 }
 
+/// Response of other actor, message could be forwarded to other actor
+///
+/// If forwarded request fails, response channel gets dropped.
+impl<A, B, M, T> MessageResponse<A, M> for Request<T, B, M>
+    where A: Actor, B: Handler<M>, M: Message + 'static,
+          T: MessageDestination<B, M>,
+          T::Transport: MessageDestinationTransport<T, B, M>,
+          B::Context: ToEnvelope<T, B, M>,
+          Request<T, B, M>: 'static,
+{
+    fn handle<R: ResponseChannel<M>>(self, _: &mut A::Context, tx: Option<R>) {
+        Arbiter::handle().spawn(self.then(move |res| {
+            if let (Some(tx), Ok(res)) = (tx, res) {
+                tx.send(res);
+            }
+            Ok(())
+        }));
+    }
+}
+
+/// Message forwarded to other actor, other actor responds to the caller
+///
+/// If response channel of the caller does not match destination, response
+/// is proxied through this actor.
+impl<A, B, M, N, T> MessageResponse<A, M> for Forward<T, B, N>
+    where A: Actor, B: Handler<N>, M: Message<Result=N::Result>, N: Message + 'static,
+          T: MessageDestination<B, N>,
+          T::Transport: MessageDestinationTransport<T, B, N>,
+          T::ResultSender: 'static,
+          B::Context: ToEnvelope<T, B, N>,
+          Request<T, B, N>: 'static,
+{
+    fn handle<R: ResponseChannel<M>>(self, _: &mut A::Context, tx: Option<R>) {
+        let tx = match tx {
+            Some(tx) => Box::new(tx) as Box<Any>,
+            None => return T::do_send(&self.tx, self.msg),
+        };
+        match tx.downcast::<T::ResultSender>() {
+            Ok(result) => T::forward(&self.tx, self.msg, *result),
+            Err(tx) => {
+                let tx = *tx.downcast::<R>().unwrap();
+                Arbiter::handle().spawn(T::send(&self.tx, self.msg).then(move |res| {
+                    if let Ok(res) = res {
+                        tx.send(res);
+                    }
+                    Ok(())
+                }));
+            }
+        }
+    }
+}
+
 enum ResponseTypeItem<I, E> {
     Result(Result<I, E>),
     Fut(Box<Future<Item=I, Error=E>>),
@@ -218,6 +372,96 @@ impl<A, M, I: 'static, E: 'static> MessageResponse<A, M> for ActorResponse<A, I,
     }
 }
 
+/// Number of stream items buffered for the receiver of `MessageStream`
+const RESPONSE_STREAM_BUFFER: usize = 16;
+
+/// Helper type for streaming message responses
+///
+/// Items of the stream are delivered to the sender of the message as
+/// [`MessageStream`](struct.MessageStream.html). Stream is not polled
+/// while receiver does not consume items, stream gets dropped if receiver
+/// gets dropped.
+///
+/// ```rust
+/// # extern crate actix;
+/// # extern crate futures;
+/// use actix::prelude::*;
+/// use futures::stream;
+///
+/// struct Query {
+///     limit: u32,
+/// }
+///
+/// impl Message for Query {
+///     type Result = MessageStream<u32, ()>;
+/// }
+///
+/// struct Db;
+///
+/// impl Actor for Db {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Query> for Db {
+///     type Result = ResponseStream<u32, ()>;
+///
+///     fn handle(&mut self, msg: Query, _: &mut Context<Self>) -> Self::Result {
+///         ResponseStream::new(stream::iter_ok(0..msg.limit))
+///     }
+/// }
+/// # fn main() {}
+/// ```
+pub struct ResponseStream<I, E> {
+    stream: Box<Stream<Item=I, Error=E>>,
+}
+
+impl<I, E> ResponseStream<I, E> {
+    /// Create streaming response
+    pub fn new<S>(stream: S) -> Self where S: Stream<Item=I, Error=E> + 'static {
+        ResponseStream {stream: Box::new(stream)}
+    }
+}
+
+impl<A, M, I: 'static, E: 'static> MessageResponse<A, M> for ResponseStream<I, E>
+    where A: Actor, M: Message<Result=MessageStream<I, E>>,
+{
+    fn handle<R: ResponseChannel<M>>(self, _: &mut A::Context, tx: Option<R>) {
+        // stream is not needed if nobody waits for response
+        if let Some(tx) = tx {
+            let (items_tx, items_rx) = mpsc::channel(RESPONSE_STREAM_BUFFER);
+            tx.send(MessageStream {rx: items_rx});
+            Arbiter::handle().spawn(
+                self.stream
+                    .then(Ok::<_, mpsc::SendError<Result<I, E>>>)
+                    .forward(items_tx)
+                    .map(|_| ())
+                    .map_err(|_| ()));
+        }
+    }
+}
+
+/// Stream of items of streaming message response
+///
+/// Stream finishes once handler's stream is finished or
+/// actor's arbiter is stopped.
+pub struct MessageStream<I, E> {
+    rx: mpsc::Receiver<Result<I, E>>,
+}
+
+impl<I, E> Stream for MessageStream<I, E> {
+    type Item = I;
+    type Error = E;
+
+    fn poll(&mut self) -> Poll<Option<I>, E> {
+        match self.rx.poll() {
+            Ok(Async::Ready(Some(Ok(item)))) => Ok(Async::Ready(Some(item))),
+            Ok(Async::Ready(Some(Err(err)))) => Err(err),
+            Ok(Async::Ready(None)) | Err(_) => Ok(Async::Ready(None)),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+        }
+    }
+}
+
 macro_rules! SIMPLE_RESULT {
     ($type:ty) => {
         impl<A, M> MessageResponse<A, M> for $type where A: Actor, M: Message<Result=$type>
@@ -246,3 +490,41 @@ SIMPLE_RESULT!(f32);
 SIMPLE_RESULT!(f64);
 SIMPLE_RESULT!(String);
 SIMPLE_RESULT!(bool);
+
+impl<A, M, I: 'static> MessageResponse<A, M> for Option<I>
+    where A: Actor, M: Message<Result=Option<I>>,
+{
+    fn handle<R: ResponseChannel<M>>(self, _: &mut A::Context, tx: Option<R>) {
+        if let Some(tx) = tx {
+            tx.send(self);
+        }
+    }
+}
+
+impl<A, M, I: 'static> MessageResponse<A, M> for Vec<I>
+    where A: Actor, M: Message<Result=Vec<I>>,
+{
+    fn handle<R: ResponseChannel<M>>(self, _: &mut A::Context, tx: Option<R>) {
+        if let Some(tx) = tx {
+            tx.send(self);
+        }
+    }
+}
+
+macro_rules! TUPLE_RESULT {
+    ($($T:ident),+) => {
+        impl<A, M, $($T: 'static),+> MessageResponse<A, M> for ($($T,)+)
+            where A: Actor, M: Message<Result=($($T,)+)>
+        {
+            fn handle<R: ResponseChannel<M>>(self, _: &mut A::Context, tx: Option<R>) {
+                if let Some(tx) = tx {
+                    tx.send(self);
+                }
+            }
+        }
+    }
+}
+
+TUPLE_RESULT!(T1, T2);
+TUPLE_RESULT!(T1, T2, T3);
+TUPLE_RESULT!(T1, T2, T3, T4);
@@ -0,0 +1,179 @@
+//! System wide message interceptors
+//!
+//! [`Interceptor`](trait.Interceptor.html) is registered in the system with
+//! `System::add_interceptor()` and is called around every
+//! `Handler::handle()` call of every actor of the system, unlike
+//! [middleware](../middleware/index.html) which is registered by actor
+//! itself. Interceptors are meant for audit logs and debugging tools
+//! that observe actors without changes to the actors.
+//!
+//! Interceptors are called from arbiter threads concurrently. Only
+//! synchronous part of the handler is measured. Actors of sync arbiters
+//! are not intercepted. Type names are available with `spans` feature,
+//! which requires Rust 1.38 or later.
+//!
+//! ```rust
+//! # extern crate actix;
+//! use std::time::Duration;
+//! use actix::prelude::*;
+//! use actix::intercept::{Dispatch, Interceptor};
+//!
+//! struct Audit;
+//!
+//! impl Interceptor for Audit {
+//!     fn after_dispatch(&self, dispatch: &Dispatch, duration: Duration) {
+//!         println!("{:?} handled in {:?}", dispatch.message_name(), duration);
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     let id = System::add_interceptor(Audit);
+//!     // ...
+//!     System::remove_interceptor(id);
+//! #   Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!     sys.run();
+//! }
+//! ```
+#[cfg(feature="spans")]
+use std::any::type_name;
+use std::any::TypeId;
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use arbiter::Arbiter;
+use path::{self, ActorPath};
+
+/// Message dispatch to the actor
+pub struct Dispatch {
+    actor_type: TypeId,
+    message_type: TypeId,
+    actor_name: Option<&'static str>,
+    message_name: Option<&'static str>,
+    path: Option<ActorPath>,
+}
+
+impl Dispatch {
+    fn new<A: 'static, M: 'static>() -> Dispatch {
+        #[cfg(feature="spans")]
+        let names = (Some(type_name::<A>()), Some(type_name::<M>()));
+        #[cfg(not(feature="spans"))]
+        let names = (None, None);
+        Dispatch {
+            actor_type: TypeId::of::<A>(),
+            message_type: TypeId::of::<M>(),
+            actor_name: names.0,
+            message_name: names.1,
+            path: path::current(),
+        }
+    }
+
+    /// Type of the actor
+    pub fn actor_type(&self) -> TypeId {
+        self.actor_type
+    }
+
+    /// Type of the message
+    pub fn message_type(&self) -> TypeId {
+        self.message_type
+    }
+
+    /// Type name of the actor, requires `spans` feature
+    pub fn actor_name(&self) -> Option<&'static str> {
+        self.actor_name
+    }
+
+    /// Type name of the message, requires `spans` feature
+    pub fn message_name(&self) -> Option<&'static str> {
+        self.message_name
+    }
+
+    /// Path of the actor
+    pub fn path(&self) -> Option<&ActorPath> {
+        self.path.as_ref()
+    }
+}
+
+#[allow(unused_variables)]
+/// Hooks called around message handlers of all actors of the system
+pub trait Interceptor: Send + Sync + 'static {
+    /// Method is called before message is handled
+    fn before_dispatch(&self, dispatch: &Dispatch) {}
+
+    /// Method is called after message is handled, `duration` is time
+    /// spent in the handler
+    fn after_dispatch(&self, dispatch: &Dispatch, duration: Duration) {}
+}
+
+/// Id of registered interceptor
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct InterceptorId(usize);
+
+/// Interceptors of the system, shared by all arbiters
+#[derive(Default)]
+pub(crate) struct Interceptors {
+    /// Number of registered interceptors, checked without lock
+    count: AtomicUsize,
+    next: AtomicUsize,
+    list: RwLock<Vec<(InterceptorId, Arc<Interceptor>)>>,
+}
+
+impl Interceptors {
+    pub fn add(&self, interceptor: Arc<Interceptor>) -> InterceptorId {
+        let id = InterceptorId(self.next.fetch_add(1, Ordering::Relaxed));
+        let mut list = self.list.write().unwrap();
+        list.push((id, interceptor));
+        self.count.store(list.len(), Ordering::Release);
+        id
+    }
+
+    pub fn remove(&self, id: InterceptorId) -> bool {
+        let mut list = self.list.write().unwrap();
+        let len = list.len();
+        list.retain(|item| item.0 != id);
+        self.count.store(list.len(), Ordering::Release);
+        list.len() != len
+    }
+}
+
+/// Interceptors called before the handler
+pub(crate) struct Intercepted {
+    dispatch: Dispatch,
+    interceptors: Vec<Arc<Interceptor>>,
+    start: Instant,
+}
+
+impl Intercepted {
+    /// Call `after_dispatch()` hooks, in reverse order
+    pub fn after(self) {
+        let duration = self.start.elapsed();
+        for interceptor in self.interceptors.iter().rev() {
+            interceptor.after_dispatch(&self.dispatch, duration);
+        }
+    }
+}
+
+/// Call `before_dispatch()` hooks of interceptors of the current system
+pub(crate) fn before<A: 'static, M: 'static>() -> Option<Intercepted> {
+    let registry = match Arbiter::try_system_registry() {
+        Some(registry) => registry,
+        None => return None,
+    };
+    let shared = registry.interceptors();
+    if shared.count.load(Ordering::Acquire) == 0 {
+        return None
+    }
+    // lock is not held while interceptors are called
+    let interceptors: Vec<_> = shared.list.read().unwrap()
+        .iter().map(|item| Arc::clone(&item.1)).collect();
+    if interceptors.is_empty() {
+        return None
+    }
+    let dispatch = Dispatch::new::<A, M>();
+    for interceptor in &interceptors {
+        interceptor.before_dispatch(&dispatch);
+    }
+    Some(Intercepted{dispatch, interceptors, start: Instant::now()})
+}
@@ -1,16 +1,25 @@
-use std::{io, mem};
+use std::{fmt, io, mem};
 use std::cell::UnsafeCell;
+use std::collections::VecDeque;
+use std::net::SocketAddr;
 use std::rc::Rc;
 use std::marker::PhantomData;
 extern crate bytes;
 
 use self::bytes::BytesMut;
-use futures::{Async, Poll};
-use tokio_io::AsyncWrite;
-use tokio_io::codec::Encoder;
+use futures::{future, task, Async, Future, Poll, Stream};
+use futures::task::Task;
+use tokio_core::net::{UdpCodec, UdpSocket};
+use tokio_io::{AsyncRead, AsyncWrite};
+use tokio_io::io::{ReadHalf, WriteHalf};
+use tokio_codec::{Decoder, Encoder, FramedRead};
 
 use fut::ActorFuture;
-use actor::{Actor, ActorContext, AsyncContext, Running, SpawnHandle};
+use actor::{Actor, ActorContext, ActorState, AsyncContext, Running, SpawnHandle};
+use address::{ActorAddress, Addr, Syn, Unsync};
+use arbiter::Arbiter;
+use contextimpl::ContextImpl;
+use stream::StreamHandler;
 
 /// Write handler
 ///
@@ -33,12 +42,24 @@ pub trait WriteHandler<E> where Self: Actor, Self::Context: ActorContext
     fn finished(&mut self, ctx: &mut Self::Context) {
         ctx.stop()
     }
+
+    /// Method is called when write buffer grows over high watermark.
+    ///
+    /// Actor should stop producing data until `drained()` is called.
+    fn drain_requested(&mut self, ctx: &mut Self::Context) {}
+
+    /// Method is called when write buffer drains below low watermark
+    /// after `drain_requested()` call.
+    fn drained(&mut self, ctx: &mut Self::Context) {}
 }
 
 bitflags! {
     struct Flags: u8 {
         const CLOSING = 0b0000_0001;
         const CLOSED = 0b0000_0010;
+        const DRAINING = 0b0000_0100;
+        // do not pause actor's context while draining
+        const NO_WAIT = 0b0000_1000;
     }
 }
 
@@ -57,6 +78,7 @@ struct InnerWriter<T: AsyncWrite, E: From<io::Error>> {
     error: Option<E>,
     low: usize,
     high: usize,
+    task: Option<Task>,
     handle: SpawnHandle,
 }
 
@@ -75,6 +97,7 @@ impl<T: AsyncWrite, E: From<io::Error> + 'static> Writer<T, E>
                 error: None,
                 low: LOW_WATERMARK,
                 high: HIGH_WATERMARK,
+                task: None,
                 handle: SpawnHandle::default(),
             }));
         let h = ctx.spawn(WriterFut{inner: Rc::clone(&inner), act: PhantomData});
@@ -99,6 +122,7 @@ impl<T: AsyncWrite, E: From<io::Error> + 'static> Writer<T, E>
     /// Close process is asynchronous.
     pub fn close(&mut self) {
         self.as_mut().flags.insert(Flags::CLOSING);
+        self.as_mut().notify();
     }
 
     /// Check if sink is closed
@@ -116,6 +140,7 @@ impl<T: AsyncWrite, E: From<io::Error> + 'static> Writer<T, E>
     pub fn write(&mut self, msg: &[u8]) {
         let inner = self.as_mut();
         inner.buffer.extend_from_slice(msg);
+        inner.notify();
     }
 
     /// `SpawnHandle` for this writer
@@ -125,6 +150,28 @@ impl<T: AsyncWrite, E: From<io::Error> + 'static> Writer<T, E>
 }
 
 
+impl<T: AsyncWrite, E: From<io::Error>> InnerWriter<T, E> {
+    /// Wake up idle writer, data could be written after writer got polled
+    fn notify(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.notify();
+        }
+    }
+
+    fn drained<A>(&mut self, act: &mut A, ctx: &mut A::Context)
+        where A: Actor + WriteHandler<E>, A::Context: AsyncContext<A>
+    {
+        if self.flags.contains(Flags::DRAINING) {
+            self.flags.remove(Flags::DRAINING);
+            // paused reader could be polled before writer
+            if self.flags.contains(Flags::NO_WAIT) {
+                task::current().notify();
+            }
+            act.drained(ctx);
+        }
+    }
+}
+
 struct WriterFut<T, E, A> where T: AsyncWrite, E: From<io::Error> {
     act: PhantomData<A>,
     inner: Rc<UnsafeCell<InnerWriter<T, E>>>,
@@ -164,7 +211,15 @@ impl<T: 'static, E: 'static, A> ActorFuture for WriterFut<T, E, A>
                 },
                 Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
                     if inner.buffer.len() > inner.high {
-                        ctx.wait(WriterDrain{inner: Rc::clone(&self.inner), act: PhantomData});
+                        if !inner.flags.contains(Flags::DRAINING) {
+                            inner.flags.insert(Flags::DRAINING);
+                            act.drain_requested(ctx);
+                        }
+                        if !inner.flags.contains(Flags::NO_WAIT) {
+                            ctx.wait(WriterDrain{inner: Rc::clone(&self.inner), act: PhantomData});
+                        }
+                    } else if inner.buffer.len() < inner.low {
+                        inner.drained(act, ctx);
                     }
                     return Ok(Async::NotReady)
                 },
@@ -174,6 +229,7 @@ impl<T: 'static, E: 'static, A> ActorFuture for WriterFut<T, E, A>
                 }
             }
         }
+        inner.drained(act, ctx);
 
         // Try flushing the underlying IO
         match inner.io.flush() {
@@ -193,6 +249,7 @@ impl<T: 'static, E: 'static, A> ActorFuture for WriterFut<T, E, A>
             act.finished(ctx);
             Ok(Async::Ready(()))
         } else {
+            inner.task = Some(task::current());
             Ok(Async::NotReady)
         }
     }
@@ -266,6 +323,7 @@ impl<T: AsyncWrite, U: Encoder> FramedWrite<T, U>
                 error: None,
                 low: LOW_WATERMARK,
                 high: HIGH_WATERMARK,
+                task: None,
                 handle: SpawnHandle::default(),
             }));
         let h = ctx.spawn(WriterFut{inner: Rc::clone(&inner), act: PhantomData});
@@ -290,6 +348,7 @@ impl<T: AsyncWrite, U: Encoder> FramedWrite<T, U>
                 error: None,
                 low: LOW_WATERMARK,
                 high: HIGH_WATERMARK,
+                task: None,
                 handle: SpawnHandle::default(),
             }));
         let h = ctx.spawn(WriterFut{inner: Rc::clone(&inner), act: PhantomData});
@@ -314,6 +373,7 @@ impl<T: AsyncWrite, U: Encoder> FramedWrite<T, U>
     /// Close process is asynchronous.
     pub fn close(&mut self) {
         self.as_mut().flags.insert(Flags::CLOSING);
+        self.as_mut().notify();
     }
 
     /// Check if sink is closed
@@ -321,6 +381,11 @@ impl<T: AsyncWrite, U: Encoder> FramedWrite<T, U>
         self.as_ref().flags.contains(Flags::CLOSED)
     }
 
+    /// Check if sink is closing or closed
+    pub fn closing(&self) -> bool {
+        self.as_ref().flags.intersects(Flags::CLOSING | Flags::CLOSED)
+    }
+
     /// Set write buffer capacity
     pub fn set_buffer_capacity(&mut self, low: usize, high: usize) {
         self.as_mut().low = low;
@@ -333,6 +398,7 @@ impl<T: AsyncWrite, U: Encoder> FramedWrite<T, U>
         let _ = self.enc.encode(item, &mut inner.buffer).map_err(|e| {
             inner.error = Some(e);
         });
+        inner.notify();
     }
 
     /// `SpawnHandle` for this writer
@@ -340,3 +406,445 @@ impl<T: AsyncWrite, U: Encoder> FramedWrite<T, U>
         self.as_ref().handle
     }
 }
+
+/// Maximum size of received datagram
+const MAX_DATAGRAM: usize = 64 * 1024;
+
+/// Wrapper for `UdpSocket` and `UdpCodec` types
+///
+/// Datagrams received by the socket are decoded with codec and delivered
+/// to actor's `StreamHandler<U::In, io::Error>`. Datagrams that codec fails
+/// to decode are logged and skipped, socket errors end the stream.
+/// Datagrams sent with `UdpFramed::send()` are encoded immediately, queued
+/// and sent asynchronously, send errors are reported to actor's `WriteHandler`.
+///
+/// ```rust,ignore
+/// impl Actor for Metrics {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         let socket = UdpSocket::bind(&self.addr, Arbiter::handle()).unwrap();
+///         self.framed = Some(UdpFramed::new(socket, StatsdCodec, ctx));
+///     }
+/// }
+/// ```
+pub struct UdpFramed<U: UdpCodec> {
+    inner: Rc<UnsafeCell<InnerUdp<U>>>,
+}
+
+struct InnerUdp<U: UdpCodec> {
+    flags: Flags,
+    socket: UdpSocket,
+    codec: U,
+    buffer: Vec<u8>,
+    queue: VecDeque<(SocketAddr, Vec<u8>)>,
+    task: Option<Task>,
+    handle: SpawnHandle,
+    reader: SpawnHandle,
+}
+
+impl<U: UdpCodec + 'static> UdpFramed<U> {
+    pub fn new<A, C>(socket: UdpSocket, codec: U, ctx: &mut C) -> UdpFramed<U>
+        where A: Actor<Context=C> + StreamHandler<U::In, io::Error> + WriteHandler<io::Error>,
+              C: AsyncContext<A>,
+              U::In: 'static
+    {
+        let inner = Rc::new(UnsafeCell::new(
+            InnerUdp {
+                flags: Flags::empty(),
+                socket,
+                codec,
+                buffer: vec![0; MAX_DATAGRAM],
+                queue: VecDeque::new(),
+                task: None,
+                handle: SpawnHandle::default(),
+                reader: SpawnHandle::default(),
+            }));
+        let r = ctx.add_stream(UdpReader{inner: Rc::clone(&inner)});
+        let h = ctx.spawn(UdpWriterFut{inner: Rc::clone(&inner), act: PhantomData});
+
+        let mut framed = UdpFramed{inner};
+        framed.as_mut().handle = h;
+        framed.as_mut().reader = r;
+        framed
+    }
+
+    #[inline]
+    fn as_ref(&self) -> &InnerUdp<U> {
+        unsafe{ &*self.inner.get() }
+    }
+
+    #[inline]
+    fn as_mut(&mut self) -> &mut InnerUdp<U> {
+        unsafe{ &mut *self.inner.get() }
+    }
+
+    /// Send datagram
+    ///
+    /// Datagrams sent after `close()` are dropped.
+    pub fn send(&mut self, msg: U::Out) {
+        let inner = self.as_mut();
+        if !inner.flags.intersects(Flags::CLOSING | Flags::CLOSED) {
+            let mut buf = Vec::new();
+            let addr = inner.codec.encode(msg, &mut buf);
+            inner.queue.push_back((addr, buf));
+
+            // datagram could be sent outside of actor's context poll
+            if let Some(task) = inner.task.take() {
+                task.notify();
+            }
+        }
+    }
+
+    /// Gracefully close sink
+    ///
+    /// Queued datagrams are sent first, after that `WriteHandler::finished()`
+    /// is called. Received datagrams are delivered until actor stops.
+    pub fn close(&mut self) {
+        let inner = self.as_mut();
+        inner.flags.insert(Flags::CLOSING);
+        if let Some(task) = inner.task.take() {
+            task.notify();
+        }
+    }
+
+    /// Check if sink is closed
+    pub fn closed(&self) -> bool {
+        self.as_ref().flags.contains(Flags::CLOSED)
+    }
+
+    /// Number of queued datagrams
+    pub fn queued(&self) -> usize {
+        self.as_ref().queue.len()
+    }
+
+    /// Local address of the socket
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.as_ref().socket.local_addr()
+    }
+
+    /// `SpawnHandle` for this writer
+    pub fn handle(&self) -> SpawnHandle {
+        self.as_ref().handle
+    }
+
+    /// `SpawnHandle` for received datagrams stream
+    pub fn reader(&self) -> SpawnHandle {
+        self.as_ref().reader
+    }
+}
+
+struct UdpReader<U: UdpCodec> {
+    inner: Rc<UnsafeCell<InnerUdp<U>>>,
+}
+
+impl<U: UdpCodec> Stream for UdpReader<U> {
+    type Item = U::In;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<U::In>, io::Error> {
+        let inner = unsafe{ &mut *self.inner.get() };
+        loop {
+            match inner.socket.recv_from(&mut inner.buffer) {
+                Ok((n, addr)) => match inner.codec.decode(&addr, &inner.buffer[..n]) {
+                    Ok(msg) => return Ok(Async::Ready(Some(msg))),
+                    // malformed datagram does not end the stream
+                    Err(err) => warn!("Can not decode datagram from {}: {}", addr, err),
+                },
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(Async::NotReady),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+struct UdpWriterFut<U: UdpCodec, A> {
+    act: PhantomData<A>,
+    inner: Rc<UnsafeCell<InnerUdp<U>>>,
+}
+
+impl<U: UdpCodec, A> ActorFuture for UdpWriterFut<U, A>
+    where A: Actor + WriteHandler<io::Error>, A::Context: AsyncContext<A>
+{
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut A, ctx: &mut A::Context) -> Poll<Self::Item, Self::Error> {
+        let inner = unsafe{ &mut *self.inner.get() };
+
+        while let Some((addr, buf)) = inner.queue.pop_front() {
+            let err = match inner.socket.send_to(&buf, &addr) {
+                Ok(n) if n == buf.len() => continue,
+                Ok(_) => io::Error::new(io::ErrorKind::WriteZero,
+                                        "failed to write entire datagram to socket"),
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    inner.queue.push_front((addr, buf));
+                    return Ok(Async::NotReady)
+                },
+                Err(e) => e,
+            };
+            if act.error(err, ctx) == Running::Stop {
+                inner.flags |= Flags::CLOSED;
+                act.finished(ctx);
+                return Ok(Async::Ready(()))
+            }
+        }
+
+        if inner.flags.contains(Flags::CLOSING) {
+            inner.flags |= Flags::CLOSED;
+            act.finished(ctx);
+            Ok(Async::Ready(()))
+        } else {
+            inner.task = Some(task::current());
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+/// Actor execution context that owns framed transport
+///
+/// Transport is split into read and write halves. Frames decoded from
+/// the read half are delivered to actor's `StreamHandler<Codec::Item, Codec::Error>`
+/// the same way as items of a stream registered with `add_stream()`,
+/// frames sent with `FramedContext::send()` are encoded into write buffer
+/// and flushed by the context. Write errors are reported to actor's
+/// `WriteHandler`. Codec is cloned, one copy decodes frames and other
+/// encodes them.
+///
+/// By default actor stops once transport is closed by peer.
+pub struct FramedContext<A, Io, Codec>
+    where A: Actor<Context=FramedContext<A, Io, Codec>> +
+             StreamHandler<<Codec as Decoder>::Item, <Codec as Encoder>::Error> +
+             WriteHandler<<Codec as Encoder>::Error>,
+          Io: AsyncRead + AsyncWrite + 'static,
+          Codec: Decoder<Error=<Codec as Encoder>::Error> + Encoder + Clone + 'static,
+{
+    inner: ContextImpl<A>,
+    framed: Option<FramedWrite<WriteHalf<Io>, Codec>>,
+    reader: SpawnHandle,
+}
+
+impl<A, Io, Codec> ActorContext for FramedContext<A, Io, Codec>
+    where A: Actor<Context=Self> +
+             StreamHandler<<Codec as Decoder>::Item, <Codec as Encoder>::Error> +
+             WriteHandler<<Codec as Encoder>::Error>,
+          Io: AsyncRead + AsyncWrite + 'static,
+          Codec: Decoder<Error=<Codec as Encoder>::Error> + Encoder + Clone + 'static,
+{
+    #[inline]
+    fn stop(&mut self) {
+        self.inner.stop()
+    }
+    #[inline]
+    fn terminate(&mut self) {
+        self.inner.terminate()
+    }
+    #[inline]
+    fn state(&self) -> ActorState {
+        self.inner.state()
+    }
+}
+
+impl<A, Io, Codec> AsyncContext<A> for FramedContext<A, Io, Codec>
+    where A: Actor<Context=Self> +
+             StreamHandler<<Codec as Decoder>::Item, <Codec as Encoder>::Error> +
+             WriteHandler<<Codec as Encoder>::Error>,
+          Io: AsyncRead + AsyncWrite + 'static,
+          Codec: Decoder<Error=<Codec as Encoder>::Error> + Encoder + Clone + 'static,
+{
+    #[inline]
+    fn spawn<F>(&mut self, fut: F) -> SpawnHandle
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        self.inner.spawn(fut)
+    }
+
+    #[inline]
+    fn wait<F>(&mut self, fut: F)
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        self.inner.wait(fut)
+    }
+
+    #[inline]
+    fn waiting(&self) -> bool {
+        self.inner.waiting()
+    }
+
+    #[inline]
+    fn cancel_future(&mut self, handle: SpawnHandle) -> bool {
+        self.inner.cancel_future(handle)
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    fn stream_error(&mut self) {
+        self.inner.stream_error()
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    fn stream_paused(&self) -> bool {
+        self.inner.stream_paused()
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    fn unsync_address(&mut self) -> Addr<Unsync, A> {
+        self.inner.unsync_address()
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    fn sync_address(&mut self) -> Addr<Syn, A> {
+        self.inner.sync_address()
+    }
+}
+
+impl<A, Io, Codec> FramedContext<A, Io, Codec>
+    where A: Actor<Context=Self> +
+             StreamHandler<<Codec as Decoder>::Item, <Codec as Encoder>::Error> +
+             WriteHandler<<Codec as Encoder>::Error>,
+          Io: AsyncRead + AsyncWrite + 'static,
+          Codec: Decoder<Error=<Codec as Encoder>::Error> + Encoder + Clone + 'static,
+{
+    /// Start new actor that owns framed transport, returns address of the actor
+    pub fn start<Addr>(act: A, io: Io, codec: Codec) -> Addr where A: ActorAddress<A, Addr> {
+        FramedContext::create(io, codec, move |_| act)
+    }
+
+    /// Start new actor that owns framed transport, use this method if
+    /// context is needed during actor initialization
+    pub fn create<Addr, F>(io: Io, codec: Codec, f: F) -> Addr
+        where A: ActorAddress<A, Addr>, F: FnOnce(&mut Self) -> A + 'static
+    {
+        let mut ctx = FramedContext {
+            inner: ContextImpl::new(None), framed: None, reader: SpawnHandle::default() };
+        let (r, w) = io.split();
+        let mut framed = FramedWrite::new(w, codec.clone(), &mut ctx);
+        framed.as_mut().flags.insert(Flags::NO_WAIT);
+        let reader = FramedReader{framed: FramedRead::new(r, codec),
+                                  writer: Rc::clone(&framed.inner)};
+        ctx.reader = ctx.add_stream(reader);
+        ctx.framed = Some(framed);
+        let addr = <A as ActorAddress<A, Addr>>::get(&mut ctx);
+
+        Arbiter::handle().spawn_fn(move || {
+            let act = f(&mut ctx);
+            ctx.inner.set_actor(act);
+            Arbiter::handle().spawn(ctx.map(|_| ()).map_err(|_| ()));
+            future::ok(())
+        });
+        addr
+    }
+
+    /// Send frame to the peer
+    ///
+    /// Frame is encoded into write buffer, buffer is flushed asynchronously.
+    /// Frames sent after `close()` are dropped.
+    pub fn send(&mut self, frame: <Codec as Encoder>::Item) {
+        if let Some(ref mut framed) = self.framed {
+            if !framed.closing() {
+                framed.write(frame);
+            }
+        }
+    }
+
+    /// Gracefully close write half of the transport
+    ///
+    /// Pending frames are flushed first, after that `WriteHandler::finished()`
+    /// is called. Reading of incoming frames continues until peer closes
+    /// transport or actor stops.
+    pub fn close(&mut self) {
+        if let Some(ref mut framed) = self.framed {
+            framed.close()
+        }
+    }
+
+    /// Check if write half of the transport is closed
+    pub fn closed(&self) -> bool {
+        self.framed.as_ref().map(|framed| framed.closed()).unwrap_or(true)
+    }
+
+    /// Stop reading incoming frames
+    pub fn stop_reading(&mut self) {
+        let reader = mem::replace(&mut self.reader, SpawnHandle::default());
+        self.inner.cancel_future(reader);
+    }
+
+    /// Set write buffer capacity
+    ///
+    /// Once write buffer grows over `high` watermark, reading of incoming frames
+    /// is paused and `WriteHandler::drain_requested()` is called. Reading resumes
+    /// once buffer is drained below `low` watermark, then `WriteHandler::drained()`
+    /// is called. Messages are processed while buffer drains.
+    pub fn set_buffer_capacity(&mut self, low: usize, high: usize) {
+        if let Some(ref mut framed) = self.framed {
+            framed.set_buffer_capacity(low, high)
+        }
+    }
+
+    /// Handle of the running future
+    pub fn handle(&self) -> SpawnHandle {
+        self.inner.curr_handle()
+    }
+
+    /// Set mailbox capacity
+    pub fn set_mailbox_capacity(&mut self, cap: usize) {
+        self.inner.set_mailbox_capacity(cap)
+    }
+}
+
+/// Frames of the read half, not polled while write buffer drains
+struct FramedReader<Io, Codec> where Io: AsyncRead + AsyncWrite, Codec: Decoder + Encoder {
+    framed: FramedRead<ReadHalf<Io>, Codec>,
+    writer: Rc<UnsafeCell<InnerWriter<WriteHalf<Io>, <Codec as Encoder>::Error>>>,
+}
+
+impl<Io, Codec> Stream for FramedReader<Io, Codec>
+    where Io: AsyncRead + AsyncWrite, Codec: Decoder + Encoder
+{
+    type Item = <Codec as Decoder>::Item;
+    type Error = <Codec as Decoder>::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        if unsafe{ &*self.writer.get() }.flags.contains(Flags::DRAINING) {
+            Ok(Async::NotReady)
+        } else {
+            self.framed.poll()
+        }
+    }
+}
+
+#[doc(hidden)]
+impl<A, Io, Codec> Future for FramedContext<A, Io, Codec>
+    where A: Actor<Context=Self> +
+             StreamHandler<<Codec as Decoder>::Item, <Codec as Encoder>::Error> +
+             WriteHandler<<Codec as Encoder>::Error>,
+          Io: AsyncRead + AsyncWrite + 'static,
+          Codec: Decoder<Error=<Codec as Encoder>::Error> + Encoder + Clone + 'static,
+{
+    type Item = ();
+    type Error = ();
+
+    #[inline]
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let ctx: &mut FramedContext<A, Io, Codec> = unsafe {
+            mem::transmute(self as &mut FramedContext<A, Io, Codec>)
+        };
+        self.inner.poll(ctx)
+    }
+}
+
+impl<A, Io, Codec> fmt::Debug for FramedContext<A, Io, Codec>
+    where A: Actor<Context=Self> +
+             StreamHandler<<Codec as Decoder>::Item, <Codec as Encoder>::Error> +
+             WriteHandler<<Codec as Encoder>::Error>,
+          Io: AsyncRead + AsyncWrite + 'static,
+          Codec: Decoder<Error=<Codec as Encoder>::Error> + Encoder + Clone + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FramedContext({:?})", self as *const _)
+    }
+}
@@ -37,7 +37,10 @@ extern crate bitflags;
 extern crate futures;
 extern crate tokio_io;
 extern crate tokio_core;
+extern crate tokio_codec;
 extern crate tokio_signal;
+#[cfg(unix)]
+extern crate tokio_uds;
 extern crate trust_dns_resolver;
 
 #[macro_use]
@@ -50,10 +53,23 @@ extern crate actix_derive;
 
 #[cfg(test)]
 extern crate bytes;
+#[cfg(any(feature="remote", feature="persistence"))]
+extern crate serde;
+#[cfg(feature="remote")]
+#[macro_use] extern crate serde_derive;
+#[cfg(any(feature="remote", feature="persistence"))]
+extern crate serde_json;
+#[cfg(feature="metrics")]
+#[macro_use] extern crate lazy_static;
+#[cfg(feature="tracing")]
+extern crate tracing;
 
 #[doc(hidden)]
 pub use actix_derive::*;
 
+#[macro_use]
+mod macros;
+
 mod actor;
 mod arbiter;
 mod context;
@@ -62,10 +78,11 @@ mod contextitems;
 mod handler;
 mod stream;
 mod system;
-mod supervisor;
 
 mod address;
 mod mailbox;
+mod span;
+mod wheel;
 
 pub mod io;
 pub mod fut;
@@ -73,20 +90,45 @@ pub mod actors;
 pub mod msgs;
 pub mod sync;
 pub mod utils;
+pub mod clock;
+pub mod schedule;
+pub mod test;
 pub mod registry;
+pub mod supervisor;
+pub mod router;
+pub mod dispatcher;
+pub mod shard;
+pub mod behavior;
+pub mod deadletter;
+pub mod delivery;
+pub mod fsm;
+pub mod intercept;
+pub mod middleware;
+pub mod events;
+pub mod path;
+pub mod protocol;
+#[cfg(feature="remote")]
+pub mod remote;
+#[cfg(feature="persistence")]
+pub mod persistence;
+#[cfg(feature="metrics")]
+pub mod metrics;
 
 pub use fut::{ActorFuture, ActorStream, WrapFuture, WrapStream, FinishStream};
 pub use actor::{Actor, ActorState, Supervised,
                 ActorContext, AsyncContext, Running, SpawnHandle};
-pub use handler::{Handler, Response, ActorResponse,
-                  Message, MessageResult, ResponseFuture, ResponseActFuture};
-pub use arbiter::Arbiter;
-pub use address::{Addr, Syn, Unsync, ActorAddress, Recipient, MailboxError};
+pub use handler::{Handler, Response, ActorResponse, Message, MessageResult,
+                  MessageStream, ResponseFuture, ResponseActFuture, ResponseStream};
+pub use arbiter::{Arbiter, ArbiterBuilder, ArbiterPool, PanicPolicy, Placement};
+pub use address::{Addr, WeakAddr, Syn, Unsync, ActorAddress, Recipient, Subscriber,
+                  MailboxError, OverflowPolicy};
+pub use mailbox::Priority;
 pub use context::Context;
 pub use stream::StreamHandler;
 pub use sync::{SyncContext, SyncArbiter};
 pub use system::{System, SystemRunner};
 pub use supervisor::Supervisor;
+pub use path::ActorPath;
 
 #[doc(hidden)]
 pub use context::ContextFutureSpawner;
@@ -119,16 +161,17 @@ pub mod prelude {
     #[deprecated(since="0.5.0", note="Use Addr<Syn<T>>")]
     pub type SyncAddress<T> = Addr<Syn, T>;
 
-    pub use fut::{ActorFuture, ActorStream, WrapFuture, WrapStream};
+    pub use fut::{ActorFuture, ActorStream, PipeTo, WrapFuture, WrapStream};
     pub use actor::{Actor, ActorState, ActorContext, AsyncContext,
                     Running, Supervised, SpawnHandle};
-    pub use arbiter::Arbiter;
-    pub use address::{Addr, Syn, Unsync, SendError, Recipient, MailboxError};
+    pub use arbiter::{Arbiter, ArbiterPool};
+    pub use address::{Addr, WeakAddr, Syn, Unsync, SendError, Recipient, Subscriber,
+                      MailboxError, OverflowPolicy};
     pub use context::{Context, ContextFutureSpawner};
     pub use registry::{ArbiterService, SystemService};
     pub use stream::StreamHandler;
     pub use handler::{Handler, Response, ActorResponse, Message, MessageResult,
-                      ResponseFuture, ResponseActFuture};
+                      MessageStream, ResponseFuture, ResponseActFuture, ResponseStream};
     pub use system::System;
     pub use sync::{SyncContext, SyncArbiter};
     pub use supervisor::Supervisor;
@@ -142,6 +185,7 @@ pub mod prelude {
         pub use actors;
         pub use address::ActorAddress;
         pub use registry::{ArbiterService, SystemService};
+        pub use supervisor;
         pub use utils::Condition;
     }
 }
@@ -161,8 +205,9 @@ pub mod dev {
     pub use prelude::actix::*;
 
     pub use contextimpl::ContextImpl;
+    pub use supervisor::SupervisedContext;
     pub use handler::{MessageResponse, ResponseChannel};
     pub use address::{ActorAddress, ToEnvelope, SyncEnvelope,
-                      Request, SyncRecipientRequest, UnsyncRecipientRequest};
+                      Forward, Request, SyncRecipientRequest, UnsyncRecipientRequest};
     pub use address::{Destination, MessageDestination, MessageDestinationTransport, MessageRecipient};
 }
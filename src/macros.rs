@@ -0,0 +1,169 @@
+/// Implement handlers of several message types with actor's methods
+///
+/// Macro declares an enum with variant per message type and implements
+/// `Handler` of every message type for the actor. Handler calls actor's
+/// method, which takes the message and the context and returns result of
+/// the message. `Handler` of the enum dispatches variants to same methods,
+/// enum message resolves with `()` and results of methods are dropped.
+/// Enum gets `From` implementation for every message type.
+///
+/// ```rust
+/// # #[macro_use] extern crate actix;
+/// use actix::prelude::*;
+///
+/// struct Get(String);
+///
+/// impl Message for Get {
+///     type Result = Option<u64>;
+/// }
+///
+/// struct Put(String, u64);
+///
+/// impl Message for Put {
+///     type Result = ();
+/// }
+///
+/// #[derive(Default)]
+/// struct Store(std::collections::HashMap<String, u64>);
+///
+/// impl Actor for Store {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Store {
+///     fn get(&mut self, msg: Get, _: &mut Context<Self>) -> Option<u64> {
+///         self.0.get(&msg.0).cloned()
+///     }
+///
+///     fn put(&mut self, msg: Put, _: &mut Context<Self>) {
+///         self.0.insert(msg.0, msg.1);
+/// #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
+///     }
+/// }
+///
+/// handler_enum! {
+///     /// Messages of the store
+///     pub enum StoreMsg for Store {
+///         Get(Get) => get,
+///         Put(Put) => put,
+///     }
+/// }
+///
+/// fn main() {
+///     let sys = System::new("test");
+///     let addr: Addr<Unsync, _> = Store::default().start();
+///     addr.do_send(StoreMsg::from(Put("a".to_owned(), 1)));
+///     sys.run();
+/// }
+/// ```
+#[macro_export]
+macro_rules! handler_enum {
+    ($(#[$attr:meta])* pub enum $name:ident for $act:ty {
+        $($variant:ident($msg:ty) => $method:ident),* $(,)*
+    }) => {
+        $(#[$attr])*
+        pub enum $name {
+            $(#[allow(missing_docs)] $variant($msg)),*
+        }
+        handler_enum!(@impl $name, $act, $($variant, $msg, $method);*);
+    };
+    ($(#[$attr:meta])* enum $name:ident for $act:ty {
+        $($variant:ident($msg:ty) => $method:ident),* $(,)*
+    }) => {
+        $(#[$attr])*
+        enum $name {
+            $($variant($msg)),*
+        }
+        handler_enum!(@impl $name, $act, $($variant, $msg, $method);*);
+    };
+    (@impl $name:ident, $act:ty, $($variant:ident, $msg:ty, $method:ident);*) => {
+        impl $crate::Message for $name {
+            type Result = ();
+        }
+
+        impl $crate::Handler<$name> for $act {
+            type Result = ();
+
+            fn handle(&mut self, msg: $name, ctx: &mut <$act as $crate::Actor>::Context) {
+                match msg {
+                    $($name::$variant(msg) => { self.$method(msg, ctx); }),*
+                }
+            }
+        }
+
+        $(
+            impl From<$msg> for $name {
+                fn from(msg: $msg) -> $name {
+                    $name::$variant(msg)
+                }
+            }
+
+            impl $crate::Handler<$msg> for $act {
+                type Result = $crate::MessageResult<$msg>;
+
+                fn handle(&mut self, msg: $msg, ctx: &mut <$act as $crate::Actor>::Context)
+                          -> Self::Result
+                {
+                    $crate::MessageResult(self.$method(msg, ctx))
+                }
+            }
+        )*
+    };
+}
+
+/// Declare protocol, a closed set of messages
+///
+/// Macro declares protocol type and implements
+/// [`Protocol`](protocol/trait.Protocol.html) for every actor that handles
+/// all messages of the protocol. Recipients of protocol messages are kept
+/// in a tuple, so protocol address does not look up recipients at runtime.
+/// Messages of public protocol have to be public. See
+/// [`protocol`](protocol/index.html) module for example.
+#[macro_export]
+macro_rules! protocol {
+    ($(#[$attr:meta])* pub protocol $name:ident { $($msg:ty),* $(,)* }) => {
+        $(#[$attr])*
+        pub struct $name;
+        protocol!(@impl $name, $($msg),*);
+    };
+    ($(#[$attr:meta])* protocol $name:ident { $($msg:ty),* $(,)* }) => {
+        $(#[$attr])*
+        struct $name;
+        protocol!(@impl $name, $($msg),*);
+    };
+    (@impl $name:ident, $($msg:ty),*) => {
+        impl $crate::protocol::Messages for $name {
+            type Recipients = protocol!(@type $($msg),*);
+        }
+
+        impl<A> $crate::protocol::Protocol<A> for $name
+            where A: $crate::Actor,
+                  $(A: $crate::Handler<$msg>,
+                    A::Context: $crate::dev::ToEnvelope<$crate::Syn, A, $msg>,)*
+        {
+            fn recipients(addr: &$crate::Addr<$crate::Syn, A>) -> Self::Recipients {
+                protocol!(@build addr, $($msg),*)
+            }
+        }
+
+        protocol!(@accepts $name, [], $($msg),*);
+    };
+    (@type) => { () };
+    (@type $msg:ty $(, $rest:ty)*) => {
+        ($crate::Recipient<$crate::Syn, $msg>, protocol!(@type $($rest),*))
+    };
+    (@build $addr:ident) => { () };
+    (@build $addr:ident, $msg:ty $(, $rest:ty)*) => {
+        ($addr.clone().recipient::<$msg>(), protocol!(@build $addr $(, $rest)*))
+    };
+    (@accepts $name:ident, [$($path:tt)*]) => {};
+    (@accepts $name:ident, [$($path:tt)*], $msg:ty $(, $rest:ty)*) => {
+        impl $crate::protocol::Accepts<$msg> for $name {
+            fn recipient(recipients: &Self::Recipients) -> &$crate::Recipient<$crate::Syn, $msg> {
+                &recipients $($path)* .0
+            }
+        }
+
+        protocol!(@accepts $name, [$($path)* .1] $(, $rest)*);
+    };
+}
@@ -1,8 +1,16 @@
+use std::mem;
+use std::any::TypeId;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
 use futures::{Async, Stream};
 
 use actor::{Actor, AsyncContext};
 use address::{sync_channel, Addr, Syn, SyncAddressReceiver, Unsync, UnsyncAddrReceiver};
+use address::OverflowPolicy;
 use address::EnvelopeProxy;
+use deadletter::{self, DeadLetterReason, DroppedMessage};
+use handler::Message;
+use msgs::MailboxMetrics;
 
 /// Maximum number of consecutive polls in a loop
 const MAX_SYNC_POLLS: u32 = 256;
@@ -10,10 +18,90 @@ const MAX_SYNC_POLLS: u32 = 256;
 /// Default address channel capacity
 pub const DEFAULT_CAPACITY: usize = 16;
 
+/// Message priority
+///
+/// Actor processes prioritized messages before other pending messages, if message
+/// type is registered with `Context::prioritize()` method. Messages with higher priority
+/// get processed first, messages with same priority get processed in order
+/// they were received. Priorities apply to first mailbox capacity pending
+/// messages, other messages stay in the mailbox and block senders as usual.
+///
+/// ```rust
+/// # extern crate actix;
+/// use actix::prelude::*;
+///
+/// struct Shutdown;
+///
+/// impl Message for Shutdown {
+///     type Result = ();
+/// }
+///
+/// impl actix::Priority for Shutdown {
+///     fn priority() -> usize { 10 }
+/// }
+///
+/// struct MyActor;
+///
+/// impl Actor for MyActor {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         ctx.prioritize::<Shutdown>();
+///     }
+/// }
+///
+/// impl Handler<Shutdown> for MyActor {
+///     type Result = ();
+///
+///     fn handle(&mut self, _: Shutdown, ctx: &mut Context<Self>) {
+///         ctx.stop();
+///     }
+/// }
+/// # fn main() {}
+/// ```
+pub trait Priority: Message {
+    /// Priority of the message type, default priority of messages is `0`
+    fn priority() -> usize;
+}
 
 pub(crate) struct Mailbox<A> where A: Actor, A::Context: AsyncContext<A> {
     sync_msgs: Option<SyncAddressReceiver<A>>,
     unsync_msgs: UnsyncAddrReceiver<A>,
+    priorities: HashMap<TypeId, usize>,
+    prioritized: BinaryHeap<Prioritized<A>>,
+    partial: Option<Box<EnvelopeProxy<Actor=A>>>,
+    /// Messages moved out of address channels by selective receive
+    buffered: VecDeque<Box<EnvelopeProxy<Actor=A>>>,
+    seq: usize,
+    dequeued: usize,
+}
+
+/// Pending message of priority mailbox
+struct Prioritized<A: Actor> {
+    priority: usize,
+    seq: usize,
+    env: Box<EnvelopeProxy<Actor=A>>,
+}
+
+impl<A: Actor> PartialEq for Prioritized<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.seq == other.seq
+    }
+}
+
+impl<A: Actor> Eq for Prioritized<A> {}
+
+impl<A: Actor> PartialOrd for Prioritized<A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<A: Actor> Ord for Prioritized<A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // earlier messages first within same priority
+        self.priority.cmp(&other.priority).then_with(|| other.seq.cmp(&self.seq))
+    }
 }
 
 impl<A> Default for Mailbox<A> where A: Actor, A::Context: AsyncContext<A> {
@@ -22,7 +110,23 @@ impl<A> Default for Mailbox<A> where A: Actor, A::Context: AsyncContext<A> {
     fn default() -> Self {
         Mailbox {
             sync_msgs: None,
-            unsync_msgs: UnsyncAddrReceiver::new(DEFAULT_CAPACITY) }
+            unsync_msgs: UnsyncAddrReceiver::new(DEFAULT_CAPACITY),
+            priorities: HashMap::new(),
+            prioritized: BinaryHeap::new(),
+            partial: None,
+            buffered: VecDeque::new(),
+            seq: 0,
+            dequeued: 0 }
+    }
+}
+
+/// Messages moved out of address channels are reported once actor stops,
+/// channels report their pending messages themselves
+impl<A> Drop for Mailbox<A> where A: Actor, A::Context: AsyncContext<A> {
+    fn drop(&mut self) {
+        for (message_type, message_name) in self.clear_buffered() {
+            deadletter::publish(message_type, message_name, DeadLetterReason::Closed);
+        }
     }
 }
 
@@ -41,7 +145,13 @@ impl<A> Mailbox<A> where A: Actor, A::Context: AsyncContext<A>
     pub fn new(rx: SyncAddressReceiver<A>) -> Self {
         Mailbox {
             sync_msgs: Some(rx),
-            unsync_msgs: UnsyncAddrReceiver::new(16) }
+            unsync_msgs: UnsyncAddrReceiver::new(DEFAULT_CAPACITY),
+            priorities: HashMap::new(),
+            prioritized: BinaryHeap::new(),
+            partial: None,
+            buffered: VecDeque::new(),
+            seq: 0,
+            dequeued: 0 }
     }
 
     pub fn capacity(&self) -> usize {
@@ -52,7 +162,38 @@ impl<A> Mailbox<A> where A: Actor, A::Context: AsyncContext<A>
         self.unsync_msgs.set_capacity(cap);
         self.sync_msgs.as_mut().map(|msgs| msgs.set_capacity(cap));
     }
+
+    pub fn overflow(&self) -> OverflowPolicy {
+        self.unsync_msgs.overflow()
+    }
+
+    pub fn prioritize<M: Priority + 'static>(&mut self) {
+        self.priorities.insert(TypeId::of::<M>(), M::priority());
+    }
+
+    pub fn set_overflow(&mut self, policy: OverflowPolicy) {
+        self.unsync_msgs.set_overflow(policy);
+        if let Some(msgs) = self.sync_msgs.as_mut() { msgs.set_overflow(policy) }
+    }
     
+    /// Number of pending messages
+    pub fn pending(&self) -> usize {
+        self.partial.iter().count() + self.buffered.len() + self.prioritized.len() +
+            self.unsync_msgs.pending() +
+            self.sync_msgs.as_ref().map(|msgs| msgs.pending()).unwrap_or(0)
+    }
+
+    /// Mailbox length and counters
+    pub fn metrics(&self) -> MailboxMetrics {
+        MailboxMetrics {
+            pending: self.pending(),
+            capacity: self.capacity(),
+            enqueued: self.unsync_msgs.enqueued() +
+                self.sync_msgs.as_ref().map(|msgs| msgs.enqueued()).unwrap_or(0),
+            dequeued: self.dequeued,
+        }
+    }
+
     #[inline]
     pub fn connected(&self) -> bool {
         self.unsync_msgs.connected() ||
@@ -61,7 +202,8 @@ impl<A> Mailbox<A> where A: Actor, A::Context: AsyncContext<A>
 
     pub fn remote_address(&mut self) -> Addr<Syn,A> {
         if self.sync_msgs.is_none() {
-            let (tx, rx) = sync_channel::channel(self.unsync_msgs.capacity());
+            let (tx, mut rx) = sync_channel::channel(self.unsync_msgs.capacity());
+            rx.set_overflow(self.unsync_msgs.overflow());
             self.sync_msgs = Some(rx);
             Addr::new(tx)
         } else {
@@ -77,7 +219,61 @@ impl<A> Mailbox<A> where A: Actor, A::Context: AsyncContext<A>
         Addr::new(self.unsync_msgs.sender())
     }
 
+    /// Drop all pending messages, returns types of dropped messages
+    pub fn clear(&mut self) -> Vec<DroppedMessage> {
+        let mut dropped = self.clear_buffered();
+        while let Ok(Async::Ready(Some(msg))) = self.unsync_msgs.poll() {
+            dropped.push((msg.message_type(), msg.message_name()));
+        }
+        if let Some(ref mut msgs) = self.sync_msgs {
+            while let Ok(Async::Ready(Some(msg))) = msgs.poll() {
+                dropped.push((msg.message_type(), msg.message_name()));
+            }
+        }
+        dropped
+    }
+
+    /// Drop messages that are moved out of address channels
+    fn clear_buffered(&mut self) -> Vec<DroppedMessage> {
+        let mut dropped: Vec<_> = self.partial.take()
+            .map(|env| (env.message_type(), env.message_name()))
+            .into_iter().collect();
+        dropped.extend(self.buffered.drain(..)
+                       .map(|env| (env.message_type(), env.message_name())));
+        dropped.extend(self.prioritized.drain()
+                       .map(|msg| (msg.env.message_type(), msg.env.message_name())));
+        dropped
+    }
+
     pub fn poll(&mut self, act: &mut A, ctx: &mut A::Context) {
+        // finish interrupted batch first
+        if let Some(mut env) = self.partial.take() {
+            if !ctx.waiting() {
+                env.handle(act, ctx);
+            }
+            if env.is_partial() {
+                self.partial = Some(env);
+                return
+            }
+        }
+
+        if !self.priorities.is_empty() {
+            return self.poll_prioritized(act, ctx)
+        }
+
+        // buffered messages arrived before messages in address channels
+        while !self.buffered.is_empty() {
+            if ctx.waiting() { return }
+
+            let mut env = self.buffered.pop_front().unwrap();
+            self.dequeued += 1;
+            env.handle(act, ctx);
+            if env.is_partial() {
+                self.partial = Some(env);
+                return
+            }
+        }
+
         let mut n_polls = NumPolls(0);
         loop {
             let mut not_ready = true;
@@ -89,7 +285,12 @@ impl<A> Mailbox<A> where A: Actor, A::Context: AsyncContext<A>
                 match self.unsync_msgs.poll() {
                     Ok(Async::Ready(Some(mut msg))) => {
                         not_ready = false;
+                        self.dequeued += 1;
                         msg.handle(act, ctx);
+                        if msg.is_partial() {
+                            self.partial = Some(Box::new(msg));
+                            return
+                        }
                     }
                     Ok(Async::Ready(None)) | Ok(Async::NotReady) | Err(_) => break,
                 }
@@ -105,7 +306,12 @@ impl<A> Mailbox<A> where A: Actor, A::Context: AsyncContext<A>
                     match msgs.poll() {
                         Ok(Async::Ready(Some(mut msg))) => {
                             not_ready = false;
+                            self.dequeued += 1;
                             msg.handle(act, ctx);
+                            if msg.is_partial() {
+                                self.partial = Some(Box::new(msg));
+                                return
+                            }
                         }
                         Ok(Async::Ready(None)) | Ok(Async::NotReady) | Err(_) => break,
                     }
@@ -119,4 +325,108 @@ impl<A> Mailbox<A> where A: Actor, A::Context: AsyncContext<A>
             }
         }
     }
+
+    /// Move pending messages to priority queue and process them in order
+    /// of priority. Priority queue holds at most mailbox capacity messages,
+    /// other messages stay in address channels.
+    fn poll_prioritized(&mut self, act: &mut A, ctx: &mut A::Context) {
+        let cap = self.capacity();
+        let mut n_polls = NumPolls(0);
+        loop {
+            while let Some(env) = self.buffered.pop_front() {
+                self.push(env);
+            }
+            while cap == 0 || self.prioritized.len() < cap {
+                match self.next_message() {
+                    Some(env) => self.push(env),
+                    None => break,
+                }
+            }
+
+            if ctx.waiting() { return }
+
+            match self.prioritized.pop() {
+                Some(mut msg) => {
+                    self.dequeued += 1;
+                    msg.env.handle(act, ctx);
+                    if msg.env.is_partial() {
+                        self.partial = Some(msg.env);
+                        return
+                    }
+                }
+                None => return,
+            }
+            debug_assert!(n_polls.inc() < MAX_SYNC_POLLS,
+                          "Use Self::Context::notify() instead of direct use of address");
+        }
+    }
+
+    /// Take the earliest pending message which type is accepted by `f`,
+    /// other messages stay in mailbox in order. Only first mailbox capacity
+    /// messages are checked.
+    pub fn select<F>(&mut self, f: F) -> Option<Box<EnvelopeProxy<Actor=A>>>
+        where F: Fn(TypeId) -> bool
+    {
+        if !self.priorities.is_empty() {
+            while let Some(env) = self.buffered.pop_front() {
+                self.push(env);
+            }
+            let mut envs = mem::replace(&mut self.prioritized, BinaryHeap::new()).into_sorted_vec();
+            envs.reverse();
+            self.buffered.extend(envs.into_iter().map(|msg| msg.env));
+        }
+        self.buffer();
+
+        let idx = self.buffered.iter()
+            .position(|env| env.message_type().map(&f).unwrap_or(false));
+        match idx {
+            Some(idx) => {
+                self.dequeued += 1;
+                self.buffered.remove(idx)
+            }
+            None => None,
+        }
+    }
+
+    /// Check if there are pending messages, messages stay in mailbox.
+    /// Current task is notified once message arrives.
+    pub fn ready(&mut self) -> bool {
+        // both channels register current task
+        let unsync = self.unsync_msgs.poll_ready().is_ready();
+        let sync = self.sync_msgs.as_mut()
+            .map(|msgs| msgs.poll_ready().is_ready()).unwrap_or(false);
+        unsync || sync || self.pending() != 0
+    }
+
+    /// Move messages of address channels to buffer, buffer holds at most
+    /// mailbox capacity messages, other messages stay in address channels
+    fn buffer(&mut self) {
+        let cap = self.capacity();
+        while cap == 0 || self.buffered.len() < cap {
+            match self.next_message() {
+                Some(env) => self.buffered.push_back(env),
+                None => break,
+            }
+        }
+    }
+
+    /// Take next message of address channels, unsync messages first
+    fn next_message(&mut self) -> Option<Box<EnvelopeProxy<Actor=A>>> {
+        if let Ok(Async::Ready(Some(msg))) = self.unsync_msgs.poll() {
+            return Some(Box::new(msg))
+        }
+        if let Some(ref mut msgs) = self.sync_msgs {
+            if let Ok(Async::Ready(Some(msg))) = msgs.poll() {
+                return Some(Box::new(msg))
+            }
+        }
+        None
+    }
+
+    fn push(&mut self, env: Box<EnvelopeProxy<Actor=A>>) {
+        let priority = env.message_type()
+            .and_then(|tp| self.priorities.get(&tp).cloned()).unwrap_or(0);
+        self.seq += 1;
+        self.prioritized.push(Prioritized{priority, seq: self.seq, env});
+    }
 }
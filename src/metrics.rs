@@ -0,0 +1,253 @@
+//! Handler latency metrics
+//!
+//! With `metrics` feature enabled, time spent in each `Handler::handle()`
+//! call is measured and aggregated per message type into histograms.
+//! Only synchronous part of the handler is measured, futures returned
+//! from handlers are not. Histograms are collected by
+//! [`MetricsRegistry`](struct.MetricsRegistry.html) system service.
+//!
+//! ```rust
+//! # extern crate actix;
+//! # extern crate futures;
+//! use std::any::TypeId;
+//! use futures::Future;
+//! use actix::prelude::*;
+//! use actix::metrics::{HandlerLatency, MetricsRegistry};
+//!
+//! struct Ping;
+//!
+//! impl Message for Ping {
+//!     type Result = ();
+//! }
+//!
+//! struct MyActor;
+//!
+//! impl Actor for MyActor {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl Handler<Ping> for MyActor {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!     let addr: Addr<Syn, _> = MyActor.start();
+//!
+//!     Arbiter::handle().spawn(
+//!         addr.send(Ping)
+//!             .and_then(|_| MetricsRegistry::from_registry().send(HandlerLatency))
+//!             .then(|res| {
+//!                 let latency = res.unwrap();
+//!                 let ping = &latency[&TypeId::of::<Ping>()];
+//!                 println!("ping: count {}, p99 {:?}", ping.count(), ping.percentile(0.99));
+//! #               Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!                 Ok(())
+//!             }));
+//!     sys.run();
+//! }
+//! ```
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actor::{Actor, Supervised};
+use context::Context;
+use handler::{Handler, Message, MessageResult};
+use registry::SystemService;
+
+/// Number of histogram buckets
+const BUCKETS: usize = 32;
+
+type Latencies = Arc<Mutex<HashMap<TypeId, Histogram>>>;
+
+/// Latencies of running threads and merged latencies of exited threads
+#[derive(Default)]
+struct Stores {
+    running: Vec<Latencies>,
+    exited: HashMap<TypeId, Histogram>,
+}
+
+lazy_static! {
+    static ref STORES: Mutex<Stores> = Mutex::new(Stores::default());
+}
+
+/// Latencies of current thread, store is merged on thread exit
+struct LocalStore(Latencies);
+
+impl Drop for LocalStore {
+    fn drop(&mut self) {
+        if let (Ok(mut stores), Ok(local)) = (STORES.lock(), self.0.lock()) {
+            let store = &self.0;
+            stores.running.retain(|running| !Arc::ptr_eq(running, store));
+            merge(&mut stores.exited, &local);
+        }
+    }
+}
+
+thread_local!(static LOCAL: LocalStore = {
+    let store = Arc::new(Mutex::new(HashMap::new()));
+    STORES.lock().unwrap().running.push(Arc::clone(&store));
+    LocalStore(store)
+});
+
+fn merge(result: &mut HashMap<TypeId, Histogram>, latencies: &HashMap<TypeId, Histogram>) {
+    for (tp, hist) in latencies.iter() {
+        result.entry(*tp).or_insert_with(Histogram::default).merge(hist);
+    }
+}
+
+fn as_micros(dur: Duration) -> u64 {
+    dur.as_secs() * 1_000_000 + u64::from(dur.subsec_nanos() / 1000)
+}
+
+fn from_micros(micros: u64) -> Duration {
+    Duration::new(micros / 1_000_000, (micros % 1_000_000) as u32 * 1000)
+}
+
+/// Latency histogram
+///
+/// Bucket `0` counts durations below 2 microseconds, bucket `n` counts
+/// durations from `2^n` up to `2^(n+1)` microseconds, last bucket counts
+/// all longer durations.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    buckets: [u64; BUCKETS],
+    count: u64,
+    sum: u64,
+    max: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Histogram {
+        Histogram{buckets: [0; BUCKETS], count: 0, sum: 0, max: 0}
+    }
+}
+
+impl Histogram {
+    /// Record duration
+    pub fn record(&mut self, dur: Duration) {
+        let micros = as_micros(dur);
+        let idx = (63 - (micros | 1).leading_zeros() as usize).min(BUCKETS - 1);
+        self.buckets[idx] += 1;
+        self.count += 1;
+        self.sum += micros;
+        self.max = self.max.max(micros);
+    }
+
+    /// Add records of other histogram
+    pub fn merge(&mut self, other: &Histogram) {
+        for (bucket, count) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *bucket += *count;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+        self.max = self.max.max(other.max);
+    }
+
+    /// Number of records
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Counts of the buckets
+    pub fn buckets(&self) -> &[u64] {
+        &self.buckets
+    }
+
+    /// Max recorded duration
+    pub fn max(&self) -> Duration {
+        from_micros(self.max)
+    }
+
+    /// Mean recorded duration
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            Duration::new(0, 0)
+        } else {
+            from_micros(self.sum / self.count)
+        }
+    }
+
+    /// Upper bound of durations of `q` part of records, `q` is in `0.0..1.0`
+    ///
+    /// Returned value is the upper bound of the bucket with percentile,
+    /// limited by max recorded duration.
+    pub fn percentile(&self, q: f64) -> Duration {
+        let rank = (q * self.count as f64).ceil() as u64;
+        let mut seen = 0;
+        for (idx, count) in self.buckets.iter().enumerate() {
+            seen += *count;
+            if seen >= rank && seen > 0 {
+                return from_micros(((2 << idx) - 1).min(self.max))
+            }
+        }
+        from_micros(self.max)
+    }
+}
+
+/// Record handler latency of message type `M`
+pub(crate) fn record<M: 'static>(start: Instant) {
+    let elapsed = start.elapsed();
+    LOCAL.with(|store| {
+        store.0.lock().unwrap()
+            .entry(TypeId::of::<M>()).or_insert_with(Histogram::default).record(elapsed)
+    });
+}
+
+/// Collects handler latency histograms of all threads
+///
+/// Histograms of exited threads are kept until metrics are reset.
+/// Registry answers `HandlerLatency` and `ResetMetrics` messages.
+#[derive(Default)]
+pub struct MetricsRegistry;
+
+impl Actor for MetricsRegistry {
+    type Context = Context<Self>;
+}
+
+impl Supervised for MetricsRegistry {}
+
+impl SystemService for MetricsRegistry {}
+
+/// Get handler latency histograms, histograms are keyed by message type
+pub struct HandlerLatency;
+
+impl Message for HandlerLatency {
+    type Result = HashMap<TypeId, Histogram>;
+}
+
+impl Handler<HandlerLatency> for MetricsRegistry {
+    type Result = MessageResult<HandlerLatency>;
+
+    fn handle(&mut self, _: HandlerLatency, _: &mut Context<Self>) -> Self::Result {
+        let stores = STORES.lock().unwrap();
+        let mut result = stores.exited.clone();
+        for store in &stores.running {
+            merge(&mut result, &store.lock().unwrap());
+        }
+        MessageResult(result)
+    }
+}
+
+/// Drop all recorded latencies
+pub struct ResetMetrics;
+
+impl Message for ResetMetrics {
+    type Result = ();
+}
+
+impl Handler<ResetMetrics> for MetricsRegistry {
+    type Result = ();
+
+    fn handle(&mut self, _: ResetMetrics, _: &mut Context<Self>) {
+        let mut stores = STORES.lock().unwrap();
+        stores.exited.clear();
+        for store in &stores.running {
+            store.lock().unwrap().clear();
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Handler middleware
+//!
+//! [`Middleware`](trait.Middleware.html) is registered on actor's context
+//! with `Context::add_middleware()` and is called around every
+//! `Handler::handle()` call of the actor, including messages sent with
+//! `notify()` and `run_later()`. Cross-cutting concerns like auditing or
+//! latency metrics are implemented once instead of in every handler.
+//!
+//! `before_handle()` hooks are called in order middleware were added,
+//! `after_handle()` hooks in reverse order. Only synchronous part of the
+//! handler is measured, futures returned from handlers are not.
+//! Middleware is dropped on restart, restarted actor registers it again
+//! in `Actor::started()`.
+//!
+//! ```rust
+//! # extern crate actix;
+//! use std::time::Duration;
+//! use actix::prelude::*;
+//! use actix::middleware::{MessageMeta, Middleware};
+//!
+//! struct Audit;
+//!
+//! impl Middleware for Audit {
+//!     fn after_handle(&mut self, meta: &MessageMeta, duration: Duration) {
+//!         println!("message handled by {:?} in {:?}", meta.path(), duration);
+//!     }
+//! }
+//!
+//! struct Ping;
+//!
+//! impl Message for Ping {
+//!     type Result = ();
+//! }
+//!
+//! struct MyActor;
+//!
+//! impl Actor for MyActor {
+//!     type Context = Context<Self>;
+//!
+//!     fn started(&mut self, ctx: &mut Context<Self>) {
+//!         ctx.add_middleware(Audit);
+//!     }
+//! }
+//!
+//! impl Handler<Ping> for MyActor {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, _: Ping, _: &mut Context<Self>) {
+//! #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!     let addr: Addr<Unsync, _> = MyActor.start();
+//!     addr.do_send(Ping);
+//!     sys.run();
+//! }
+//! ```
+use std::any::TypeId;
+use std::time::{Duration, Instant};
+
+use path::{self, ActorPath};
+
+/// Message that is handled by the actor
+pub struct MessageMeta {
+    message_type: TypeId,
+    actor_type: TypeId,
+    path: Option<ActorPath>,
+    /// Number of middleware called before handler
+    called: usize,
+}
+
+impl MessageMeta {
+    /// Type of the message
+    pub fn message_type(&self) -> TypeId {
+        self.message_type
+    }
+
+    /// Type of the actor
+    pub fn actor_type(&self) -> TypeId {
+        self.actor_type
+    }
+
+    /// Check if message is of type `M`
+    pub fn is<M: 'static>(&self) -> bool {
+        self.message_type == TypeId::of::<M>()
+    }
+
+    /// Path of the actor, sync actors do not have paths
+    pub fn path(&self) -> Option<&ActorPath> {
+        self.path.as_ref()
+    }
+}
+
+#[allow(unused_variables)]
+/// Hooks called around message handlers of the actor
+pub trait Middleware: 'static {
+    /// Method is called before message is handled
+    fn before_handle(&mut self, meta: &MessageMeta) {}
+
+    /// Method is called after message is handled, `duration` is time
+    /// spent in the handler
+    fn after_handle(&mut self, meta: &MessageMeta, duration: Duration) {}
+}
+
+#[doc(hidden)]
+/// Middleware registered on the context
+#[derive(Default)]
+pub struct Middlewares(Vec<Box<Middleware>>);
+
+impl Middlewares {
+    pub(crate) fn add(&mut self, m: Box<Middleware>) {
+        self.0.push(m)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    /// Call `before_handle()` hooks, returns `None` if there is no middleware
+    pub(crate) fn before<A: 'static, M: 'static>(&mut self) -> Option<(MessageMeta, Instant)> {
+        if self.0.is_empty() {
+            return None
+        }
+        let meta = MessageMeta {
+            message_type: TypeId::of::<M>(),
+            actor_type: TypeId::of::<A>(),
+            path: path::current(),
+            called: self.0.len(),
+        };
+        for m in &mut self.0 {
+            m.before_handle(&meta);
+        }
+        Some((meta, Instant::now()))
+    }
+
+    /// Call `after_handle()` hooks, middleware added by the handler
+    /// is not called
+    pub(crate) fn after(&mut self, meta: &MessageMeta, start: Instant) {
+        let duration = start.elapsed();
+        let called = ::std::cmp::min(meta.called, self.0.len());
+        for m in self.0[..called].iter_mut().rev() {
+            m.after_handle(meta, duration);
+        }
+    }
+}
@@ -1,7 +1,10 @@
 //! Actix system messages
 
+use std::any::TypeId;
+use std::time::Duration;
+
 use actor::Actor;
-use address::{Addr, Syn};
+use address::{Addr, Syn, WeakAddr};
 use context::Context;
 use handler::Message;
 
@@ -19,6 +22,130 @@ impl Message for StopArbiter {
     type Result = ();
 }
 
+/// Stop system once actors of all arbiters process pending messages
+///
+/// All arbiters are stopped gracefully, see `StopArbiterGraceful`.
+/// System exits with exit code once all arbiters and actors are stopped or
+/// once timeout expires, whichever happens first.
+pub struct SystemExitGraceful(pub i32, pub Duration);
+
+impl Message for SystemExitGraceful {
+    type Result = ();
+}
+
+/// Stop arbiter execution once actors of the arbiter process pending messages
+///
+/// Each actor running in the arbiter gets stopped once its mailbox is empty.
+/// Arbiter stops with exit code once all actors are stopped or
+/// once timeout expires, whichever happens first.
+pub struct StopArbiterGraceful(pub i32, pub Duration);
+
+impl Message for StopArbiterGraceful {
+    type Result = ();
+}
+
+/// Change number of worker threads of `SyncArbiter`
+pub struct SetPoolSize(pub usize);
+
+impl Message for SetPoolSize {
+    type Result = ();
+}
+
+/// Query mailbox metrics of an actor
+///
+/// Actors that implement `MailboxMonitored` handle this message.
+pub struct MailboxStats;
+
+impl Message for MailboxStats {
+    type Result = MailboxMetrics;
+}
+
+/// Actors that answer `MailboxStats` queries
+///
+/// ```rust
+/// # extern crate actix;
+/// use actix::prelude::*;
+///
+/// struct MyActor;
+///
+/// impl Actor for MyActor {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl actix::msgs::MailboxMonitored for MyActor {}
+/// # fn main() {}
+/// ```
+pub trait MailboxMonitored: Actor<Context=Context<Self>> {}
+
+/// Mailbox length and counters of an actor
+///
+/// `Syn` and `Unsync` address channels are counted together.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MailboxMetrics {
+    /// Number of pending messages
+    pub pending: usize,
+    /// Mailbox capacity, zero means unbounded mailbox
+    pub capacity: usize,
+    /// Number of messages sent to the actor
+    pub enqueued: usize,
+    /// Number of messages taken from mailbox for handling
+    pub dequeued: usize,
+}
+
+/// Periodic mailbox metrics report
+///
+/// Reports are sent to metrics collector with
+/// `Context::report_mailbox_stats()`.
+#[derive(Clone, Debug)]
+pub struct MailboxReport {
+    /// Type of the reporting actor
+    pub actor_type: TypeId,
+    /// Name of the arbiter actor is running in
+    pub arbiter: Option<String>,
+    /// Mailbox metrics
+    pub metrics: MailboxMetrics,
+}
+
+impl Message for MailboxReport {
+    type Result = ();
+}
+
+/// Watched actor is terminated
+///
+/// Message is sent to actors that watch actor `A` with `Context::watch()`
+/// once watched actor stops and is not restarted by its supervisor.
+/// Weak address of the terminated actor could not be upgraded.
+pub struct Terminated<A: Actor>(pub WeakAddr<Syn, A>);
+
+impl<A: Actor> Message for Terminated<A> {
+    type Result = ();
+}
+
+/// Linked actor is terminated
+///
+/// Message is sent only to actors that trap exits with
+/// `Context::trap_exit()`, other linked actors get stopped.
+#[derive(Clone, Debug)]
+pub struct Exited {
+    /// Type of the terminated actor
+    pub actor_type: TypeId,
+}
+
+impl Message for Exited {
+    type Result = ();
+}
+
+/// Actor did not receive messages for idle timeout
+///
+/// Message is sent to actors that set idle timeout with
+/// `Context::set_idle_timeout()`. Actor usually stops itself with
+/// `Context::passivate()`.
+pub struct Passivate;
+
+impl Message for Passivate {
+    type Result = ();
+}
+
 /// Start actor in arbiter's thread
 pub struct StartActor<A: Actor>(Box<FnBox<A>>);
 
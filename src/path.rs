@@ -0,0 +1,158 @@
+//! Hierarchical actor paths
+//!
+//! Every actor with `Context` gets a path derived from the actor that
+//! started it, e.g. `/user/payments/worker-3`. Actors started outside of
+//! any actor are placed under `/user` root. Anonymous actors get unique
+//! `$<n>` names, actor could be renamed with `Context::set_name()`.
+//!
+//! Paths of running actors could be looked up in system registry with
+//! `SystemRegistry::lookup_path()`, this is intended for debugging tools.
+//!
+//! ```rust
+//! # extern crate actix;
+//! use actix::prelude::*;
+//!
+//! struct Worker;
+//!
+//! impl Actor for Worker {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! struct Payments;
+//!
+//! impl Actor for Payments {
+//!     type Context = Context<Self>;
+//!
+//!     fn started(&mut self, ctx: &mut Context<Self>) {
+//!         ctx.set_name("payments");
+//!         let _: Addr<Unsync, _> = Worker::create(|ctx| {
+//!             ctx.set_name("worker-3");
+//!             assert_eq!(ctx.path().as_str(), "/user/payments/worker-3");
+//!             Worker
+//!         });
+//! #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!     let _: Addr<Unsync, _> = Payments.start();
+//!     sys.run();
+//! }
+//! ```
+use std::{fmt, mem};
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use arbiter::Arbiter;
+
+/// Root of paths of actors started outside of any actor
+const ROOT: &str = "/user";
+
+thread_local!(static CURRENT: RefCell<Option<ActorPath>> = RefCell::new(None));
+
+/// Path of the actor
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ActorPath(Arc<str>);
+
+impl ActorPath {
+    /// Root path, `/user`
+    pub fn root() -> ActorPath {
+        ActorPath(Arc::from(ROOT))
+    }
+
+    /// Path of the child actor with `name`
+    pub fn child(&self, name: &str) -> ActorPath {
+        ActorPath(Arc::from(format!("{}/{}", self.0, name)))
+    }
+
+    /// Path of the parent actor, root path does not have parent
+    pub fn parent(&self) -> Option<ActorPath> {
+        if &*self.0 == ROOT {
+            return None
+        }
+        self.0.rfind('/').map(|idx| ActorPath(Arc::from(&self.0[..idx])))
+    }
+
+    /// Last segment of the path
+    pub fn name(&self) -> &str {
+        match self.0.rfind('/') {
+            Some(idx) => &self.0[idx+1..],
+            None => &self.0,
+        }
+    }
+
+    /// Path as string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Path of new anonymous actor started by the current actor
+    ///
+    /// Actors are numbered per system, without system all actors are `$0`.
+    pub(crate) fn spawned() -> ActorPath {
+        let num = Arbiter::try_system_registry().map_or(0, |registry| registry.next_anonymous());
+        let name = format!("${}", num);
+        current().unwrap_or_else(ActorPath::root).child(&name)
+    }
+}
+
+impl<'a> From<&'a str> for ActorPath {
+    fn from(path: &'a str) -> ActorPath {
+        ActorPath(Arc::from(path))
+    }
+}
+
+impl fmt::Display for ActorPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for ActorPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ActorPath({})", self.0)
+    }
+}
+
+/// Running actor
+#[derive(Clone, Debug)]
+pub struct ActorInfo {
+    /// Path of the actor
+    pub path: ActorPath,
+    /// Type of the actor
+    pub actor_type: TypeId,
+    /// Name of the arbiter actor runs in
+    pub arbiter: Option<String>,
+}
+
+/// Path of the actor that is currently polled in this thread
+pub(crate) fn current() -> Option<ActorPath> {
+    CURRENT.with(|cell| cell.borrow().clone())
+}
+
+/// Make `path` current until returned guard is dropped
+pub(crate) fn enter(path: &ActorPath) -> Enter {
+    Enter(CURRENT.with(|cell| mem::replace(&mut *cell.borrow_mut(), Some(path.clone()))))
+}
+
+/// Replace current path `old` with `new`, after actor got renamed
+pub(crate) fn rename(old: &ActorPath, new: &ActorPath) {
+    CURRENT.with(|cell| {
+        let mut current = cell.borrow_mut();
+        if current.as_ref() == Some(old) {
+            *current = Some(new.clone());
+        }
+    })
+}
+
+/// Restores previous current path on drop
+pub(crate) struct Enter(Option<ActorPath>);
+
+impl Drop for Enter {
+    fn drop(&mut self) {
+        let prev = self.0.take();
+        CURRENT.with(|cell| *cell.borrow_mut() = prev);
+    }
+}
@@ -0,0 +1,250 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use futures::{future, Future};
+
+use super::PersistenceError;
+
+/// Storage of persisted events
+///
+/// Journal keeps ordered sequence of serialized events per persistence id.
+/// Journal is shared between actors and arbiters.
+pub trait Journal: Send + Sync + 'static {
+    /// Append event to journal of `id`, resolves to sequence number
+    /// of the event. Sequence numbers start from 1.
+    fn append(&self, id: &str, event: Vec<u8>) -> Box<Future<Item=u64, Error=PersistenceError>>;
+
+    /// Events of `id` starting with sequence number `from`, in order
+    fn read_from(&self, id: &str, from: u64)
+                 -> Box<Future<Item=Vec<(u64, Vec<u8>)>, Error=PersistenceError>>;
+
+    /// Delete events of `id` up to and including sequence number `to`,
+    /// e.g. events that are included in a snapshot.
+    ///
+    /// Sequence numbers of deleted events are not reused.
+    fn delete_to(&self, id: &str, to: u64) -> Box<Future<Item=(), Error=PersistenceError>>;
+}
+
+#[derive(Default)]
+struct Events {
+    /// Sequence number of the last appended event
+    seq: u64,
+    events: Vec<(u64, Vec<u8>)>,
+}
+
+/// In-memory journal
+///
+/// Events are lost once journal is dropped, so it is useful for tests
+/// and for actors that only need to survive restarts.
+#[derive(Default)]
+pub struct MemoryJournal {
+    events: Mutex<HashMap<String, Events>>,
+}
+
+impl MemoryJournal {
+    /// Create empty journal
+    pub fn new() -> MemoryJournal {
+        MemoryJournal::default()
+    }
+}
+
+impl Journal for MemoryJournal {
+    fn append(&self, id: &str, event: Vec<u8>) -> Box<Future<Item=u64, Error=PersistenceError>> {
+        let mut events = self.events.lock().unwrap();
+        let events = events.entry(id.to_owned()).or_insert_with(Events::default);
+        events.seq += 1;
+        events.events.push((events.seq, event));
+        Box::new(future::ok(events.seq))
+    }
+
+    fn read_from(&self, id: &str, from: u64)
+                 -> Box<Future<Item=Vec<(u64, Vec<u8>)>, Error=PersistenceError>>
+    {
+        let events = self.events.lock().unwrap();
+        let events = events.get(id)
+            .map(|events| events.events.iter()
+                 .filter(|&&(seq, _)| seq >= from).cloned().collect())
+            .unwrap_or_else(Vec::new);
+        Box::new(future::ok(events))
+    }
+
+    fn delete_to(&self, id: &str, to: u64) -> Box<Future<Item=(), Error=PersistenceError>> {
+        if let Some(events) = self.events.lock().unwrap().get_mut(id) {
+            events.events.retain(|&(seq, _)| seq > to);
+        }
+        Box::new(future::ok(()))
+    }
+}
+
+/// Size of record header, sequence number and length of event
+const HEADER: usize = 12;
+
+/// Length of record that marks deleted events
+const DELETED: u32 = ::std::u32::MAX;
+
+/// Journal that stores events in files
+///
+/// Every persistence id has its own append-only file in journal's
+/// directory. Event is flushed to disk before `append()` resolves.
+/// `delete_to()` rewrites the file. File operations block the calling
+/// thread.
+pub struct FileJournal {
+    dir: PathBuf,
+    /// Sequence numbers of the last events of opened journals
+    seqs: Mutex<HashMap<String, u64>>,
+}
+
+impl FileJournal {
+    /// Journal stored in `dir`, directory is created if it does not exist
+    pub fn new<P: AsRef<Path>>(dir: P) -> io::Result<FileJournal> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(FileJournal{dir: dir.as_ref().to_owned(), seqs: Mutex::new(HashMap::new())})
+    }
+
+    /// Journal file of `id`, bytes other than ascii letters, digits
+    /// and `-` are escaped
+    fn path(&self, id: &str) -> PathBuf {
+        let mut name = String::new();
+        for b in id.bytes() {
+            match b {
+                b'a'...b'z' | b'A'...b'Z' | b'0'...b'9' | b'-' => name.push(b as char),
+                _ => name.push_str(&format!("%{:02x}", b)),
+            }
+        }
+        name.push_str(".journal");
+        self.dir.join(name)
+    }
+
+    /// Read journal file, returns sequence number of the last record, stored
+    /// events and length of the valid part of the file
+    fn read(&self, id: &str) -> io::Result<(u64, Vec<(u64, Vec<u8>)>, usize)> {
+        let mut data = Vec::new();
+        match File::open(self.path(id)) {
+            Ok(mut file) => { file.read_to_end(&mut data)?; }
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok((0, Vec::new(), 0)),
+            Err(err) => return Err(err),
+        }
+
+        let mut seq = 0;
+        let mut events = Vec::new();
+        let mut pos = 0;
+        while pos + HEADER <= data.len() {
+            let (record_seq, len) = decode_header(&data[pos..pos+HEADER]);
+            if len == DELETED {
+                seq = record_seq;
+                pos += HEADER;
+                continue
+            }
+            // event of interrupted append
+            let len = len as usize;
+            if pos + HEADER + len > data.len() {
+                break
+            }
+            events.push((record_seq, data[pos+HEADER..pos+HEADER+len].to_vec()));
+            seq = record_seq;
+            pos += HEADER + len;
+        }
+        Ok((seq, events, pos))
+    }
+
+    /// Sequence number of the last event of `id`
+    fn last_seq(&self, seqs: &mut HashMap<String, u64>, id: &str) -> io::Result<u64> {
+        if let Some(seq) = seqs.get(id) {
+            return Ok(*seq)
+        }
+        let (seq, _, len) = self.read(id)?;
+        // drop incomplete record, so next append is readable
+        let path = self.path(id);
+        if path.exists() && fs::metadata(&path)?.len() > len as u64 {
+            OpenOptions::new().write(true).open(&path)?.set_len(len as u64)?;
+        }
+        seqs.insert(id.to_owned(), seq);
+        Ok(seq)
+    }
+
+    fn write(&self, id: &str, event: &[u8]) -> io::Result<u64> {
+        if event.len() >= DELETED as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Event is too large"))
+        }
+        let mut seqs = self.seqs.lock().unwrap();
+        let seq = self.last_seq(&mut seqs, id)? + 1;
+
+        let mut record = encode_header(seq, event.len() as u32);
+        record.extend_from_slice(event);
+        let mut file = OpenOptions::new().create(true).append(true).open(self.path(id))?;
+        file.write_all(&record)?;
+        file.sync_data()?;
+
+        seqs.insert(id.to_owned(), seq);
+        Ok(seq)
+    }
+
+    fn delete(&self, id: &str, to: u64) -> io::Result<()> {
+        let mut seqs = self.seqs.lock().unwrap();
+        let seq = self.last_seq(&mut seqs, id)?;
+        if seq == 0 {
+            return Ok(())
+        }
+
+        // marker keeps sequence number if every event is deleted
+        let (_, events, _) = self.read(id)?;
+        let mut data = encode_header(cmp::min(to, seq), DELETED);
+        for (seq, event) in events.into_iter().filter(|&(seq, _)| seq > to) {
+            data.extend_from_slice(&encode_header(seq, event.len() as u32));
+            data.extend_from_slice(&event);
+        }
+
+        let path = self.path(id);
+        let tmp = path.with_extension("tmp");
+        {
+            let mut file = File::create(&tmp)?;
+            file.write_all(&data)?;
+            file.sync_data()?;
+        }
+        fs::rename(&tmp, &path)
+    }
+}
+
+impl Journal for FileJournal {
+    fn append(&self, id: &str, event: Vec<u8>) -> Box<Future<Item=u64, Error=PersistenceError>> {
+        Box::new(future::result(
+            self.write(id, &event).map_err(|err| PersistenceError::Journal(err.to_string()))))
+    }
+
+    fn read_from(&self, id: &str, from: u64)
+                 -> Box<Future<Item=Vec<(u64, Vec<u8>)>, Error=PersistenceError>>
+    {
+        let res = {
+            let _seqs = self.seqs.lock().unwrap();
+            self.read(id)
+        };
+        Box::new(future::result(
+            res.map(|(_, events, _)| events.into_iter().filter(|&(seq, _)| seq >= from).collect())
+                .map_err(|err| PersistenceError::Journal(err.to_string()))))
+    }
+
+    fn delete_to(&self, id: &str, to: u64) -> Box<Future<Item=(), Error=PersistenceError>> {
+        Box::new(future::result(
+            self.delete(id, to).map_err(|err| PersistenceError::Journal(err.to_string()))))
+    }
+}
+
+fn encode_header(seq: u64, len: u32) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER);
+    for shift in (0..8).rev() {
+        header.push((seq >> (shift * 8)) as u8);
+    }
+    for shift in (0..4).rev() {
+        header.push((len >> (shift * 8)) as u8);
+    }
+    header
+}
+
+fn decode_header(header: &[u8]) -> (u64, u32) {
+    let seq = header[..8].iter().fold(0, |seq, b| (seq << 8) | u64::from(*b));
+    let len = header[8..HEADER].iter().fold(0, |len, b| (len << 8) | u32::from(*b));
+    (seq, len)
+}
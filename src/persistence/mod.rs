@@ -0,0 +1,454 @@
+//! Event sourcing persistence
+//!
+//! State of [`PersistentActor`](trait.PersistentActor.html) is changed only
+//! by events. Handler emits event with
+//! [`ctx.persist()`](trait.PersistentContext.html#tymethod.persist), event is
+//! appended to actor's [`Journal`](trait.Journal.html) and once it is stored,
+//! closure applies event to actor's state. Actor does not process messages
+//! while event is being persisted.
+//!
+//! Journal is pluggable, crate provides in-memory
+//! [`MemoryJournal`](struct.MemoryJournal.html) and file based
+//! [`FileJournal`](struct.FileJournal.html). Events that are included in a
+//! snapshot could be removed with `Journal::delete_to()`.
+//!
+//! On start actor is rebuilt by replaying its journal, every stored event is
+//! passed to `PersistentActor::recover()`. Actors started with
+//! [`persistence::start()`](fn.start.html) are supervised, each restart
+//! creates new actor instance and replays journal again, so restarted actor
+//! recovers its state.
+//!
+//! Long-lived actors could bound recovery time with snapshots. Actor that
+//! provides `PersistentActor::snapshot_store()` saves its state with
+//! [`ctx.save_snapshot()`](trait.PersistentContext.html#tymethod.save_snapshot),
+//! recovery passes the latest snapshot to
+//! `PersistentActor::recover_snapshot()` and replays only events persisted
+//! after it.
+//!
+//! Journal operations of an actor are processed in order they are issued.
+//!
+//! Events and snapshots are serialized with serde. Persistence is available with
+//! `persistence` feature.
+//!
+//! ## Example
+//!
+//! ```rust
+//! # extern crate actix;
+//! # #[macro_use] extern crate serde_derive;
+//! use std::sync::Arc;
+//! use actix::prelude::*;
+//! use actix::persistence::{self, Journal, MemoryJournal, PersistentActor, PersistentContext};
+//! use actix::supervisor::AlwaysRestart;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Deposited(u64);
+//!
+//! struct Deposit(u64);
+//!
+//! impl Message for Deposit {
+//!     type Result = ();
+//! }
+//!
+//! struct Account {
+//!     balance: u64,
+//!     journal: Arc<Journal>,
+//! }
+//!
+//! impl Actor for Account {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl actix::Supervised for Account {}
+//!
+//! impl PersistentActor for Account {
+//!     type Event = Deposited;
+//!
+//!     fn persistence_id(&self) -> String {
+//!         "account-1".to_owned()
+//!     }
+//!
+//!     fn journal(&self) -> Arc<Journal> {
+//!         Arc::clone(&self.journal)
+//!     }
+//!
+//!     fn recover(&mut self, event: Deposited, _: &mut Context<Self>) {
+//!         self.balance += event.0;
+//!     }
+//! }
+//!
+//! impl Handler<Deposit> for Account {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, msg: Deposit, ctx: &mut Context<Self>) {
+//!         ctx.persist(Deposited(msg.0), |act, event, _| {
+//!             act.balance += event.0;
+//! #           Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!         });
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     let journal: Arc<Journal> = Arc::new(MemoryJournal::new());
+//!     let addr: Addr<Unsync, _> = persistence::start(
+//!         AlwaysRestart, move || Account{balance: 0, journal: Arc::clone(&journal)});
+//!     addr.do_send(Deposit(10));
+//!
+//!     sys.run();
+//! }
+//! ```
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::Arc;
+use futures::{Async, Future, Poll};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use actor::{Actor, ActorContext, AsyncContext, Supervised};
+use address::ActorAddress;
+use context::Context;
+use fut::ActorFuture;
+use supervisor::{RestartPolicy, Supervisor};
+
+mod journal;
+mod snapshot;
+
+pub use self::journal::{FileJournal, Journal, MemoryJournal};
+pub use self::snapshot::{MemorySnapshotStore, Snapshot, SnapshotStore};
+
+/// Persistence error
+#[derive(Fail, Debug)]
+pub enum PersistenceError {
+    /// Journal could not store or read events
+    #[fail(display="Journal error: {}", _0)]
+    Journal(String),
+    /// Snapshot could not be stored or loaded
+    #[fail(display="Snapshot error: {}", _0)]
+    Snapshot(String),
+    /// Event could not be serialized or deserialized
+    #[fail(display="Event serialization error: {}", _0)]
+    Serialization(String),
+}
+
+#[allow(unused_variables)]
+/// Actor which state is rebuilt from persisted events
+pub trait PersistentActor: Actor<Context=Context<Self>> {
+    /// Event that changes actor's state
+    type Event: Serialize + DeserializeOwned + 'static;
+
+    /// Unique id of actor's journal
+    fn persistence_id(&self) -> String;
+
+    /// Journal that stores actor's events
+    fn journal(&self) -> Arc<Journal>;
+
+    /// Apply recovered event to actor's state
+    fn recover(&mut self, event: Self::Event, ctx: &mut Context<Self>);
+
+    /// Method is called once all events are recovered.
+    ///
+    /// `Actor::started()` is called before recovery.
+    fn recovered(&mut self, ctx: &mut Context<Self>) {}
+
+    /// Method is called if event could not be persisted,
+    /// event is not applied. By default actor stops.
+    fn persist_failed(&mut self, err: PersistenceError, ctx: &mut Context<Self>) {
+        error!("Can not persist event of {}: {}", self.persistence_id(), err);
+        ctx.stop();
+    }
+
+    /// Method is called if journal could not be replayed. By default actor stops.
+    fn recovery_failed(&mut self, err: PersistenceError, ctx: &mut Context<Self>) {
+        error!("Can not recover {}: {}", self.persistence_id(), err);
+        ctx.stop();
+    }
+
+    /// Store of actor's snapshots. By default actor does not use snapshots.
+    fn snapshot_store(&self) -> Option<Arc<SnapshotStore>> {
+        None
+    }
+
+    /// Restore actor's state from the latest snapshot.
+    ///
+    /// Only events persisted after the snapshot are replayed afterwards.
+    /// Returns `false` if snapshot is not applied, whole journal is replayed
+    /// then.
+    fn recover_snapshot(&mut self, snapshot: Snapshot, ctx: &mut Context<Self>) -> bool {
+        false
+    }
+
+    /// Method is called once snapshot that includes events up to `seq`
+    /// is stored
+    fn snapshot_saved(&mut self, seq: u64, ctx: &mut Context<Self>) {}
+
+    /// Method is called if snapshot could not be stored. Snapshots are
+    /// optimization only, by default error is logged.
+    fn snapshot_failed(&mut self, err: PersistenceError, ctx: &mut Context<Self>) {
+        warn!("Can not save snapshot of {}: {}", self.persistence_id(), err);
+    }
+}
+
+/// Persistent actor's context
+pub trait PersistentContext<A> where A: PersistentActor {
+    /// Append `event` to actor's journal, `f` is called once event is stored.
+    ///
+    /// Actor does not process messages until event is stored.
+    fn persist<F>(&mut self, event: A::Event, f: F)
+        where F: FnOnce(&mut A, A::Event, &mut Context<A>) + 'static;
+
+    /// Store snapshot of actor's state.
+    ///
+    /// Snapshot has to include every event applied so far, i.e. every event
+    /// which `persist()` closure is already called. Snapshot is stored
+    /// once previously issued events are persisted.
+    fn save_snapshot<S: Serialize>(&mut self, snapshot: S);
+}
+
+impl<A> PersistentContext<A> for Context<A> where A: PersistentActor {
+    fn persist<F>(&mut self, event: A::Event, f: F)
+        where F: FnOnce(&mut A, A::Event, &mut Context<A>) + 'static
+    {
+        push(self, Op::Persist(event, Box::new(f)));
+    }
+
+    fn save_snapshot<S: Serialize>(&mut self, snapshot: S) {
+        let seq = self.extension::<Journaling<A>>().seq;
+        let data = serde_json::to_vec(&snapshot)
+            .map_err(|err| PersistenceError::Serialization(err.to_string()));
+        push(self, Op::Snapshot(seq, data));
+    }
+}
+
+/// Start persistent actor in current arbiter, supervised with `policy`.
+///
+/// `f` is called on start and on each restart, journal is replayed into
+/// every new actor instance.
+pub fn start<A, Addr, P, F>(policy: P, mut f: F) -> Addr
+    where A: PersistentActor + Supervised + ActorAddress<A, Addr>,
+          P: RestartPolicy,
+          F: FnMut() -> A + 'static
+{
+    Supervisor::start_fresh(policy, move |ctx| {
+        recover(ctx);
+        f()
+    })
+}
+
+/// Replay journal into context's actor.
+///
+/// Actor does not process messages until journal is replayed. This is
+/// done by `persistence::start()`, use it to recover actors that are
+/// started differently.
+pub fn recover<A>(ctx: &mut Context<A>) where A: PersistentActor {
+    push(ctx, Op::Recover);
+}
+
+trait PersistFn<A: PersistentActor>: 'static {
+    fn call(self: Box<Self>, &mut A, A::Event, &mut Context<A>);
+}
+
+impl<A, F> PersistFn<A> for F
+    where A: PersistentActor,
+          F: FnOnce(&mut A, A::Event, &mut Context<A>) + 'static
+{
+    #[cfg_attr(feature="cargo-clippy", allow(boxed_local))]
+    fn call(self: Box<Self>, act: &mut A, event: A::Event, ctx: &mut Context<A>) {
+        (*self)(act, event, ctx)
+    }
+}
+
+/// Journal operation
+enum Op<A> where A: PersistentActor {
+    Recover,
+    Persist(A::Event, Box<PersistFn<A>>),
+    Snapshot(u64, Result<Vec<u8>, PersistenceError>),
+}
+
+/// Journal operations of context's actor
+struct Journaling<A> where A: PersistentActor {
+    ops: VecDeque<Op<A>>,
+    running: bool,
+    /// Sequence number of the last applied event
+    seq: u64,
+}
+
+impl<A> Default for Journaling<A> where A: PersistentActor {
+    fn default() -> Self {
+        Journaling{ops: VecDeque::new(), running: false, seq: 0}
+    }
+}
+
+/// Queue operation, context waits until all queued operations are done
+fn push<A>(ctx: &mut Context<A>, op: Op<A>) where A: PersistentActor {
+    let running = {
+        let journaling = ctx.extension::<Journaling<A>>();
+        journaling.ops.push_back(op);
+        mem::replace(&mut journaling.running, true)
+    };
+    // operations issued while driver runs are picked up by the driver,
+    // new wait future would be polled before earlier operations
+    if !running {
+        AsyncContext::wait(ctx, Driver{pending: None});
+    }
+}
+
+/// Operation in progress
+enum Pending<A> where A: PersistentActor {
+    LoadSnapshot(Box<Future<Item=Option<(u64, Vec<u8>)>, Error=PersistenceError>>),
+    Replay(u64, Box<Future<Item=Vec<(u64, Vec<u8>)>, Error=PersistenceError>>),
+    Persist(Option<(A::Event, Box<PersistFn<A>>)>, Box<Future<Item=u64, Error=PersistenceError>>),
+    SaveSnapshot(u64, Box<Future<Item=(), Error=PersistenceError>>),
+}
+
+/// Processes queued journal operations one by one
+struct Driver<A> where A: PersistentActor {
+    pending: Option<Pending<A>>,
+}
+
+impl<A> Driver<A> where A: PersistentActor {
+    fn start(op: Op<A>, act: &mut A, ctx: &mut Context<A>) -> Option<Pending<A>> {
+        match op {
+            Op::Recover => match act.snapshot_store() {
+                Some(store) => Some(Pending::LoadSnapshot(store.load(&act.persistence_id()))),
+                None => Some(Driver::replay(0, act)),
+            },
+            Op::Persist(event, f) => match serde_json::to_vec(&event) {
+                Ok(data) => {
+                    let fut = act.journal().append(&act.persistence_id(), data);
+                    Some(Pending::Persist(Some((event, f)), fut))
+                }
+                Err(err) => {
+                    act.persist_failed(PersistenceError::Serialization(err.to_string()), ctx);
+                    None
+                }
+            },
+            Op::Snapshot(seq, Ok(data)) => match act.snapshot_store() {
+                Some(store) => {
+                    let fut = store.save(&act.persistence_id(), seq, data);
+                    Some(Pending::SaveSnapshot(seq, fut))
+                }
+                None => {
+                    act.snapshot_failed(
+                        PersistenceError::Snapshot("Actor has no snapshot store".to_owned()), ctx);
+                    None
+                }
+            },
+            Op::Snapshot(_, Err(err)) => {
+                act.snapshot_failed(err, ctx);
+                None
+            }
+        }
+    }
+
+    /// Read events persisted after `seq`
+    fn replay(seq: u64, act: &mut A) -> Pending<A> {
+        Pending::Replay(seq, act.journal().read_from(&act.persistence_id(), seq + 1))
+    }
+
+    /// Apply recovered events, returns sequence number of the last one
+    fn apply(mut seq: u64, events: Vec<(u64, Vec<u8>)>, act: &mut A, ctx: &mut Context<A>)
+             -> Result<u64, PersistenceError>
+    {
+        for (event_seq, data) in events {
+            let event = serde_json::from_slice(&data)
+                .map_err(|err| PersistenceError::Serialization(err.to_string()))?;
+            act.recover(event, ctx);
+            seq = event_seq;
+        }
+        Ok(seq)
+    }
+}
+
+impl<A> ActorFuture for Driver<A> where A: PersistentActor {
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut A, ctx: &mut Context<A>) -> Poll<(), ()> {
+        loop {
+            if self.pending.is_none() {
+                let op = ctx.extension::<Journaling<A>>().ops.pop_front();
+                match op {
+                    Some(op) => self.pending = Driver::start(op, act, ctx),
+                    None => {
+                        ctx.extension::<Journaling<A>>().running = false;
+                        return Ok(Async::Ready(()))
+                    }
+                }
+            }
+
+            let next = match self.pending {
+                None => None,
+                Some(Pending::LoadSnapshot(ref mut fut)) => match fut.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(snapshot)) => {
+                        let seq = match snapshot {
+                            Some((seq, data)) =>
+                                if act.recover_snapshot(Snapshot::new(seq, data), ctx) {
+                                    seq
+                                } else {
+                                    0
+                                },
+                            None => 0,
+                        };
+                        Some(Driver::replay(seq, act))
+                    }
+                    Err(err) => {
+                        act.recovery_failed(err, ctx);
+                        None
+                    }
+                },
+                Some(Pending::Replay(seq, ref mut fut)) => {
+                    let res = match fut.poll() {
+                        Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        Ok(Async::Ready(events)) => Driver::apply(seq, events, act, ctx),
+                        Err(err) => Err(err),
+                    };
+                    match res {
+                        Ok(seq) => {
+                            ctx.extension::<Journaling<A>>().seq = seq;
+                            act.recovered(ctx);
+                        }
+                        Err(err) => act.recovery_failed(err, ctx),
+                    }
+                    None
+                }
+                Some(Pending::Persist(ref mut event, ref mut fut)) => match fut.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(seq)) => {
+                        ctx.extension::<Journaling<A>>().seq = seq;
+                        let (event, f) = event.take().unwrap();
+                        f.call(act, event, ctx);
+                        None
+                    }
+                    Err(err) => {
+                        act.persist_failed(err, ctx);
+                        None
+                    }
+                },
+                Some(Pending::SaveSnapshot(seq, ref mut fut)) => match fut.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Ok(Async::Ready(())) => {
+                        act.snapshot_saved(seq, ctx);
+                        None
+                    }
+                    Err(err) => {
+                        act.snapshot_failed(err, ctx);
+                        None
+                    }
+                },
+            };
+            self.pending = next;
+
+            // remaining operations are dropped once actor stops
+            if !ctx.state().alive() {
+                let journaling = ctx.extension::<Journaling<A>>();
+                journaling.ops.clear();
+                journaling.running = false;
+                return Ok(Async::Ready(()))
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use futures::{future, Future};
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use super::PersistenceError;
+
+/// Storage of actor snapshots
+///
+/// Snapshot store keeps the latest serialized snapshot per persistence id,
+/// along with sequence number of the last event included in the snapshot.
+pub trait SnapshotStore: Send + Sync + 'static {
+    /// Store snapshot of `id` taken after event `seq` was applied
+    fn save(&self, id: &str, seq: u64, snapshot: Vec<u8>)
+            -> Box<Future<Item=(), Error=PersistenceError>>;
+
+    /// Latest snapshot of `id`
+    fn load(&self, id: &str) -> Box<Future<Item=Option<(u64, Vec<u8>)>, Error=PersistenceError>>;
+}
+
+/// Recovered snapshot
+pub struct Snapshot {
+    seq: u64,
+    data: Vec<u8>,
+}
+
+impl Snapshot {
+    pub(crate) fn new(seq: u64, data: Vec<u8>) -> Snapshot {
+        Snapshot{seq: seq, data: data}
+    }
+
+    /// Sequence number of the last event included in snapshot
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Deserialize snapshot's state
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T, PersistenceError> {
+        serde_json::from_slice(&self.data)
+            .map_err(|err| PersistenceError::Serialization(err.to_string()))
+    }
+}
+
+/// In-memory snapshot store
+#[derive(Default)]
+pub struct MemorySnapshotStore {
+    snapshots: Mutex<HashMap<String, (u64, Vec<u8>)>>,
+}
+
+impl MemorySnapshotStore {
+    /// Create empty snapshot store
+    pub fn new() -> MemorySnapshotStore {
+        MemorySnapshotStore::default()
+    }
+}
+
+impl SnapshotStore for MemorySnapshotStore {
+    fn save(&self, id: &str, seq: u64, snapshot: Vec<u8>)
+            -> Box<Future<Item=(), Error=PersistenceError>>
+    {
+        let mut snapshots = self.snapshots.lock().unwrap();
+        let newer = snapshots.get(id).map(|&(last, _)| seq >= last).unwrap_or(true);
+        if newer {
+            snapshots.insert(id.to_owned(), (seq, snapshot));
+        }
+        Box::new(future::ok(()))
+    }
+
+    fn load(&self, id: &str) -> Box<Future<Item=Option<(u64, Vec<u8>)>, Error=PersistenceError>> {
+        Box::new(future::ok(self.snapshots.lock().unwrap().get(id).cloned()))
+    }
+}
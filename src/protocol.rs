@@ -0,0 +1,148 @@
+//! Typed actor protocols
+//!
+//! Protocol is a closed set of messages, it is declared with
+//! [`protocol!`](../macro.protocol.html) macro.
+//! [`ProtocolAddr`](struct.ProtocolAddr.html) is an address of any actor
+//! that handles all messages of the protocol, it sends only messages of the
+//! protocol and hides type of the actor. Component that gets protocol
+//! address could send only messages it was meant to send, this is checked
+//! at compile time.
+//!
+//! ```rust
+//! # #[macro_use] extern crate actix;
+//! use actix::prelude::*;
+//! use actix::protocol::ProtocolAddr;
+//!
+//! pub struct Deposit(u64);
+//!
+//! impl Message for Deposit {
+//!     type Result = u64;
+//! }
+//!
+//! pub struct Audit;
+//!
+//! impl Message for Audit {
+//!     type Result = Vec<u64>;
+//! }
+//!
+//! protocol! {
+//!     /// Messages clients of the bank may send
+//!     pub protocol Teller {
+//!         Deposit,
+//!     }
+//! }
+//!
+//! #[derive(Default)]
+//! struct Bank(Vec<u64>);
+//!
+//! impl Actor for Bank {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl Handler<Deposit> for Bank {
+//!     type Result = u64;
+//!
+//!     fn handle(&mut self, msg: Deposit, _: &mut Context<Self>) -> u64 {
+//!         self.0.push(msg.0);
+//! #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!         self.0.iter().sum()
+//!     }
+//! }
+//!
+//! impl Handler<Audit> for Bank {
+//!     type Result = MessageResult<Audit>;
+//!
+//!     fn handle(&mut self, _: Audit, _: &mut Context<Self>) -> Self::Result {
+//!         MessageResult(self.0.clone())
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     let bank: Addr<Syn, _> = Bank::default().start();
+//!     let teller = ProtocolAddr::<Teller>::new(&bank);
+//!     teller.do_send(Deposit(10)).unwrap();
+//!     // teller.do_send(Audit) does not compile
+//!
+//!     sys.run();
+//! }
+//! ```
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use actor::Actor;
+use address::{Addr, Recipient, SendError, Syn, SyncRecipientRequest};
+use handler::Message;
+
+/// Messages of the protocol
+///
+/// Implemented by `protocol!` macro.
+pub trait Messages: 'static {
+    /// Recipients of protocol messages, one for every message type
+    type Recipients: Send + Sync + 'static;
+}
+
+/// Protocol that could be spoken by actor `A`
+///
+/// Implemented by `protocol!` macro for every actor that handles all
+/// messages of the protocol.
+pub trait Protocol<A: Actor>: Messages {
+    /// Recipients of protocol messages
+    fn recipients(addr: &Addr<Syn, A>) -> Self::Recipients;
+}
+
+/// Protocol includes message `M`
+///
+/// Implemented by `protocol!` macro for every message of the protocol.
+pub trait Accepts<M>: Messages where M: Message + Send + 'static, M::Result: Send {
+    /// Recipient of message `M`
+    fn recipient(recipients: &Self::Recipients) -> &Recipient<Syn, M>;
+}
+
+/// Address that accepts only messages of protocol `P`
+pub struct ProtocolAddr<P: Messages> {
+    recipients: Arc<P::Recipients>,
+    protocol: PhantomData<P>,
+}
+
+impl<P: Messages> ProtocolAddr<P> {
+    /// Protocol address of the actor
+    pub fn new<A>(addr: &Addr<Syn, A>) -> ProtocolAddr<P> where A: Actor, P: Protocol<A> {
+        ProtocolAddr{recipients: Arc::new(P::recipients(addr)), protocol: PhantomData}
+    }
+
+    /// Send message and asynchronously wait for response
+    pub fn send<M>(&self, msg: M) -> SyncRecipientRequest<M>
+        where P: Accepts<M>, M: Message + Send + 'static, M::Result: Send
+    {
+        P::recipient(&self.recipients).send(msg)
+    }
+
+    /// Send message unconditionally, response is ignored
+    pub fn do_send<M>(&self, msg: M) -> Result<(), SendError<M>>
+        where P: Accepts<M>, M: Message + Send + 'static, M::Result: Send
+    {
+        P::recipient(&self.recipients).do_send(msg)
+    }
+
+    /// Try send message, fails if actor's mailbox is full or closed
+    pub fn try_send<M>(&self, msg: M) -> Result<(), SendError<M>>
+        where P: Accepts<M>, M: Message + Send + 'static, M::Result: Send
+    {
+        P::recipient(&self.recipients).try_send(msg)
+    }
+
+    /// Recipient of protocol message `M`
+    pub fn recipient<M>(&self) -> Recipient<Syn, M>
+        where P: Accepts<M>, M: Message + Send + 'static, M::Result: Send
+    {
+        P::recipient(&self.recipients).clone()
+    }
+}
+
+impl<P: Messages> Clone for ProtocolAddr<P> {
+    fn clone(&self) -> Self {
+        ProtocolAddr{recipients: Arc::clone(&self.recipients), protocol: PhantomData}
+    }
+}
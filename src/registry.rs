@@ -7,12 +7,16 @@ use std::any::{Any, TypeId};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::default::Default;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use actor::{Actor, Supervised};
 use arbiter::Arbiter;
-use address::{Addr, Syn, Unsync};
+use address::{Addr, Recipient, Syn, Unsync};
 use context::Context;
+use handler::Message;
+use intercept::{Interceptor, InterceptorId, Interceptors};
+use path::{ActorInfo, ActorPath};
 use supervisor::Supervisor;
 
 /// Actors registry
@@ -137,6 +141,12 @@ impl Registry {
     }
 }
 
+/// Paths of actors running in one arbiter
+type PathTable = Mutex<HashMap<ActorPath, ActorInfo>>;
+
+/// Recipients registered by name and message type
+type NamedRecipients = Arc<Mutex<HashMap<(String, TypeId), Box<Any>>>>;
+
 // TODO: Remove lock
 /// System wide actors registry
 ///
@@ -144,29 +154,43 @@ impl Registry {
 /// it is shared across all arbiters.
 pub struct SystemRegistry {
     registry: Arc<Mutex<HashMap<TypeId, Box<Any>>>>,
+    named: NamedRecipients,
+    /// Path tables of all arbiters, actor's start and stop lock only the
+    /// table of its own arbiter
+    paths: Arc<Mutex<Vec<Weak<PathTable>>>>,
+    local_paths: Arc<PathTable>,
+    anonymous: Arc<AtomicUsize>,
+    interceptors: Arc<Interceptors>,
 }
 
 unsafe impl Send for SystemRegistry {}
 
 impl SystemRegistry {
     pub(crate) fn new() -> Self {
-        SystemRegistry{registry: Arc::new(Mutex::new(HashMap::new()))}
+        let local_paths = Arc::new(Mutex::new(HashMap::new()));
+        SystemRegistry{registry: Arc::new(Mutex::new(HashMap::new())),
+                       named: Arc::new(Mutex::new(HashMap::new())),
+                       paths: Arc::new(Mutex::new(vec![Arc::downgrade(&local_paths)])),
+                       local_paths,
+                       anonymous: Arc::new(AtomicUsize::new(0)),
+                       interceptors: Arc::new(Interceptors::default())}
+    }
+
+    /// Registry of new arbiter, arbiter gets its own path table
+    pub(crate) fn arbiter_registry(&self) -> Self {
+        let local_paths = Arc::new(Mutex::new(HashMap::new()));
+        if let Ok(mut tables) = self.paths.lock() {
+            tables.retain(|table| table.upgrade().is_some());
+            tables.push(Arc::downgrade(&local_paths));
+        }
+        SystemRegistry{local_paths, ..self.clone()}
     }
 
     /// Return address of the service. If service actor is not running
     /// it get started in system arbiter.
     pub fn get<A: SystemService + Actor<Context=Context<A>>>(&self) -> Addr<Syn,A> {
-        {
-            if let Ok(hm) = self.registry.lock() {
-                if let Some(addr) = hm.get(&TypeId::of::<A>()) {
-                    match addr.downcast_ref::<Addr<Syn, A>>() {
-                        Some(addr) => {
-                            return addr.clone()
-                        },
-                        None => error!("Got unknown value: {:?}", addr),
-                    }
-                }
-            } else { panic!("System registry lock is poisoned"); }
+        if let Some(addr) = self.query::<A>() {
+            return addr
         }
 
         let addr = Supervisor::start_in(&Arbiter::system_arbiter(), |ctx| {
@@ -180,10 +204,134 @@ impl SystemRegistry {
         }
         panic!("System registry lock is poisoned");
     }
+
+    /// Return address of the service if service actor is running
+    pub(crate) fn query<A>(&self) -> Option<Addr<Syn, A>>
+        where A: SystemService + Actor<Context=Context<A>>
+    {
+        if let Ok(hm) = self.registry.lock() {
+            if let Some(addr) = hm.get(&TypeId::of::<A>()) {
+                match addr.downcast_ref::<Addr<Syn, A>>() {
+                    Some(addr) => {
+                        return Some(addr.clone())
+                    },
+                    None => error!("Got unknown value: {:?}", addr),
+                }
+            }
+            None
+        } else { panic!("System registry lock is poisoned"); }
+    }
+
+    /// Register recipient under name
+    ///
+    /// Recipients of different message types could be registered under
+    /// same name. Recipient replaces previously registered recipient with
+    /// same name and message type.
+    pub fn register_named<N, M>(&self, name: N, recipient: Recipient<Syn, M>)
+        where N: Into<String>, M: Message + Send + 'static, M::Result: Send
+    {
+        if let Ok(mut hm) = self.named.lock() {
+            hm.insert((name.into(), TypeId::of::<M>()), Box::new(recipient));
+        } else { panic!("System registry lock is poisoned"); }
+    }
+
+    /// Find recipient of message `M` registered under name
+    ///
+    /// Returns `None` if recipient is not registered or its actor is stopped.
+    pub fn lookup<M>(&self, name: &str) -> Option<Recipient<Syn, M>>
+        where M: Message + Send + 'static, M::Result: Send
+    {
+        if let Ok(mut hm) = self.named.lock() {
+            let key = (name.to_owned(), TypeId::of::<M>());
+            let recipient = hm.get(&key)
+                .and_then(|item| item.downcast_ref::<Recipient<Syn, M>>()).cloned();
+            match recipient {
+                Some(ref recipient) if recipient.connected() => (),
+                _ => { hm.remove(&key); return None }
+            }
+            recipient
+        } else { panic!("System registry lock is poisoned"); }
+    }
+
+    /// Remove all recipients registered under name
+    pub fn unregister_named(&self, name: &str) {
+        if let Ok(mut hm) = self.named.lock() {
+            let keys: Vec<_> = hm.keys().filter(|key| key.0 == name).cloned().collect();
+            for key in keys {
+                hm.remove(&key);
+            }
+        } else { panic!("System registry lock is poisoned"); }
+    }
+
+    /// Find running actor by path
+    pub fn lookup_path(&self, path: &str) -> Option<ActorInfo> {
+        let path = ActorPath::from(path);
+        self.path_tables().iter()
+            .filter_map(|table| table.lock().ok().and_then(|hm| hm.get(&path).cloned()))
+            .next()
+    }
+
+    /// Running actors started by actor with `path`, sorted by path
+    pub fn children(&self, path: &str) -> Vec<ActorInfo> {
+        let mut children = Vec::new();
+        for table in self.path_tables() {
+            if let Ok(hm) = table.lock() {
+                children.extend(hm.values()
+                    .filter(|info| info.path.parent().as_ref().map(|p| p.as_str()) == Some(path))
+                    .cloned());
+            }
+        }
+        children.sort_by(|a, b| a.path.cmp(&b.path));
+        children
+    }
+
+    /// Path tables of running arbiters
+    fn path_tables(&self) -> Vec<Arc<PathTable>> {
+        if let Ok(tables) = self.paths.lock() {
+            tables.iter().filter_map(|table| table.upgrade()).collect()
+        } else { panic!("System registry lock is poisoned"); }
+    }
+
+    pub(crate) fn register_path(&self, info: ActorInfo) {
+        if let Ok(mut hm) = self.local_paths.lock() {
+            hm.insert(info.path.clone(), info);
+        }
+    }
+
+    pub(crate) fn unregister_path(&self, path: &ActorPath) {
+        if let Ok(mut hm) = self.local_paths.lock() {
+            hm.remove(path);
+        }
+    }
+
+    /// Next number of anonymous actor
+    pub(crate) fn next_anonymous(&self) -> usize {
+        self.anonymous.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Register interceptor, it is called around message handlers of all
+    /// actors of the system
+    pub fn add_interceptor<I: Interceptor>(&self, interceptor: I) -> InterceptorId {
+        self.interceptors.add(Arc::new(interceptor))
+    }
+
+    /// Remove interceptor, returns `false` if it is not registered
+    pub fn remove_interceptor(&self, id: InterceptorId) -> bool {
+        self.interceptors.remove(id)
+    }
+
+    pub(crate) fn interceptors(&self) -> &Interceptors {
+        &self.interceptors
+    }
 }
 
 impl Clone for SystemRegistry {
     fn clone(&self) -> Self {
-        SystemRegistry{registry: Arc::clone(&self.registry)}
+        SystemRegistry{registry: Arc::clone(&self.registry),
+                       paths: Arc::clone(&self.paths),
+                       local_paths: Arc::clone(&self.local_paths),
+                       anonymous: Arc::clone(&self.anonymous),
+                       named: Arc::clone(&self.named),
+                       interceptors: Arc::clone(&self.interceptors)}
     }
 }
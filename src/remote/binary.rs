@@ -0,0 +1,724 @@
+//! Compact binary serde format
+//!
+//! Integers are varint encoded, signed integers are zigzag encoded first.
+//! Floats are little endian, strings, byte arrays, sequences and maps are
+//! prefixed with varint length, enum variants with varint index. Struct
+//! fields are written in order of declaration without names, so format is
+//! not self-describing.
+use std::{char, fmt, str};
+use std::error::Error as StdError;
+
+use serde::{de, ser};
+use serde::de::IntoDeserializer;
+
+/// Binary format serialization error
+#[derive(Debug)]
+pub(crate) struct Error(String);
+
+impl Error {
+    fn new(reason: &str) -> Error {
+        Error(reason.to_owned())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl StdError for Error {
+    fn description(&self) -> &str {
+        &self.0
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error(msg.to_string())
+    }
+}
+
+/// Serialize `item`
+pub(crate) fn to_vec<T: ser::Serialize>(item: &T) -> Result<Vec<u8>, Error> {
+    let mut serializer = Serializer{out: Vec::new()};
+    item.serialize(&mut serializer)?;
+    Ok(serializer.out)
+}
+
+/// Deserialize item, all of `body` has to be consumed
+pub(crate) fn from_slice<'de, T: de::Deserialize<'de>>(body: &'de [u8]) -> Result<T, Error> {
+    let mut deserializer = Deserializer{input: body};
+    let item = T::deserialize(&mut deserializer)?;
+    if deserializer.input.is_empty() {
+        Ok(item)
+    } else {
+        Err(Error::new("Trailing bytes"))
+    }
+}
+
+struct Serializer {
+    out: Vec<u8>,
+}
+
+impl Serializer {
+    fn varint(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.out.push(byte);
+                return
+            }
+            self.out.push(byte | 0x80);
+        }
+    }
+
+    fn zigzag(&mut self, value: i64) {
+        self.varint(((value << 1) ^ (value >> 63)) as u64)
+    }
+
+    fn little_endian(&mut self, value: u64, size: usize) {
+        for idx in 0..size {
+            self.out.push((value >> (idx * 8)) as u8);
+        }
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.out.push(v as u8);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.out.push(v as u8);
+        Ok(())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.zigzag(i64::from(v));
+        Ok(())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.zigzag(i64::from(v));
+        Ok(())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.zigzag(v);
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.out.push(v);
+        Ok(())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.varint(u64::from(v));
+        Ok(())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.varint(u64::from(v));
+        Ok(())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.varint(v);
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.little_endian(u64::from(v.to_bits()), 4);
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.little_endian(v.to_bits(), 8);
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.varint(u64::from(v as u32));
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.serialize_bytes(v.as_bytes())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.varint(v.len() as u64);
+        self.out.extend_from_slice(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.out.push(0);
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + ser::Serialize>(self, value: &T) -> Result<(), Error> {
+        self.out.push(1);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, idx: u32, _: &'static str)
+                              -> Result<(), Error>
+    {
+        self.varint(u64::from(idx));
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + ser::Serialize>(self, _: &'static str, value: &T)
+                                                           -> Result<(), Error>
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + ser::Serialize>(
+        self, _: &'static str, idx: u32, _: &'static str, value: &T) -> Result<(), Error>
+    {
+        self.varint(u64::from(idx));
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self, Error> {
+        match len {
+            Some(len) => {
+                self.varint(len as u64);
+                Ok(self)
+            }
+            None => Err(Error::new("Length of sequence is unknown")),
+        }
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(self, _: &'static str, idx: u32, _: &'static str, _: usize)
+                               -> Result<Self, Error>
+    {
+        self.varint(u64::from(idx));
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self, Error> {
+        match len {
+            Some(len) => {
+                self.varint(len as u64);
+                Ok(self)
+            }
+            None => Err(Error::new("Length of map is unknown")),
+        }
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(self, _: &'static str, idx: u32, _: &'static str, _: usize)
+                                -> Result<Self, Error>
+    {
+        self.varint(u64::from(idx));
+        Ok(self)
+    }
+}
+
+impl<'a> ser::SerializeSeq for &'a mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTuple for &'a mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeMap for &'a mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + ser::Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        key.serialize(&mut **self)
+    }
+
+    fn serialize_value<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeStruct for &'a mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + ser::Serialize>(&mut self, _: &'static str, value: &T)
+                                                  -> Result<(), Error>
+    {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + ser::Serialize>(&mut self, _: &'static str, value: &T)
+                                                  -> Result<(), Error>
+    {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+struct Deserializer<'de> {
+    input: &'de [u8],
+}
+
+impl<'de> Deserializer<'de> {
+    fn take(&mut self, size: usize) -> Result<&'de [u8], Error> {
+        if self.input.len() < size {
+            return Err(Error::new("Unexpected end of input"))
+        }
+        let (head, tail) = self.input.split_at(size);
+        self.input = tail;
+        Ok(head)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, Error> {
+        let mut value = 0;
+        for idx in 0..10 {
+            let byte = self.byte()?;
+            if idx == 9 && byte > 1 {
+                break
+            }
+            value |= u64::from(byte & 0x7f) << (idx * 7);
+            if byte & 0x80 == 0 {
+                return Ok(value)
+            }
+        }
+        Err(Error::new("Varint is too long"))
+    }
+
+    fn unsigned(&mut self, max: u64) -> Result<u64, Error> {
+        let value = self.varint()?;
+        if value > max {
+            Err(Error::new("Integer is out of range"))
+        } else {
+            Ok(value)
+        }
+    }
+
+    fn signed(&mut self, min: i64, max: i64) -> Result<i64, Error> {
+        let value = self.varint()?;
+        let value = ((value >> 1) as i64) ^ -((value & 1) as i64);
+        if value < min || value > max {
+            Err(Error::new("Integer is out of range"))
+        } else {
+            Ok(value)
+        }
+    }
+
+    fn little_endian(&mut self, size: usize) -> Result<u64, Error> {
+        let bytes = self.take(size)?;
+        Ok(bytes.iter().rev().fold(0, |value, byte| (value << 8) | u64::from(*byte)))
+    }
+
+    fn len(&mut self) -> Result<usize, Error> {
+        let len = self.varint()?;
+        if len > self.input.len() as u64 {
+            // every element takes at least one byte, except units
+            Err(Error::new("Length is larger than input"))
+        } else {
+            Ok(len as usize)
+        }
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, _: V) -> Result<V::Value, Error> {
+        Err(Error::new("Binary format is not self-describing"))
+    }
+
+    fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.byte()? {
+            0 => visitor.visit_bool(false),
+            1 => visitor.visit_bool(true),
+            _ => Err(Error::new("Invalid bool")),
+        }
+    }
+
+    fn deserialize_i8<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_i8(self.byte()? as i8)
+    }
+
+    fn deserialize_i16<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let value = self.signed(i64::from(::std::i16::MIN), i64::from(::std::i16::MAX))?;
+        visitor.visit_i16(value as i16)
+    }
+
+    fn deserialize_i32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let value = self.signed(i64::from(::std::i32::MIN), i64::from(::std::i32::MAX))?;
+        visitor.visit_i32(value as i32)
+    }
+
+    fn deserialize_i64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_i64(self.signed(::std::i64::MIN, ::std::i64::MAX)?)
+    }
+
+    fn deserialize_u8<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u8(self.byte()?)
+    }
+
+    fn deserialize_u16<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u16(self.unsigned(u64::from(::std::u16::MAX))? as u16)
+    }
+
+    fn deserialize_u32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u32(self.unsigned(u64::from(::std::u32::MAX))? as u32)
+    }
+
+    fn deserialize_u64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u64(self.varint()?)
+    }
+
+    fn deserialize_f32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_f32(f32::from_bits(self.little_endian(4)? as u32))
+    }
+
+    fn deserialize_f64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_f64(f64::from_bits(self.little_endian(8)?))
+    }
+
+    fn deserialize_char<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let value = self.unsigned(u64::from(::std::u32::MAX))? as u32;
+        match char::from_u32(value) {
+            Some(ch) => visitor.visit_char(ch),
+            None => Err(Error::new("Invalid char")),
+        }
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let len = self.len()?;
+        match str::from_utf8(self.take(len)?) {
+            Ok(s) => visitor.visit_borrowed_str(s),
+            Err(_) => Err(Error::new("Invalid utf-8 string")),
+        }
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let len = self.len()?;
+        visitor.visit_borrowed_bytes(self.take(len)?)
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.byte()? {
+            0 => visitor.visit_none(),
+            1 => visitor.visit_some(self),
+            _ => Err(Error::new("Invalid option")),
+        }
+    }
+
+    fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(self, _: &'static str, visitor: V)
+                                                    -> Result<V::Value, Error>
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(self, _: &'static str, visitor: V)
+                                                       -> Result<V::Value, Error>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let len = self.len()?;
+        visitor.visit_seq(Access{de: self, left: len})
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(self, len: usize, visitor: V)
+                                              -> Result<V::Value, Error>
+    {
+        visitor.visit_seq(Access{de: self, left: len})
+    }
+
+    fn deserialize_tuple_struct<V: de::Visitor<'de>>(self, _: &'static str, len: usize,
+                                                     visitor: V) -> Result<V::Value, Error>
+    {
+        visitor.visit_seq(Access{de: self, left: len})
+    }
+
+    fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let len = self.len()?;
+        visitor.visit_map(Access{de: self, left: len})
+    }
+
+    fn deserialize_struct<V: de::Visitor<'de>>(self, _: &'static str,
+                                               fields: &'static [&'static str], visitor: V)
+                                               -> Result<V::Value, Error>
+    {
+        visitor.visit_seq(Access{de: self, left: fields.len()})
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(self, _: &'static str, _: &'static [&'static str],
+                                             visitor: V) -> Result<V::Value, Error>
+    {
+        visitor.visit_enum(self)
+    }
+
+    fn deserialize_identifier<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_u32(self.unsigned(u64::from(::std::u32::MAX))? as u32)
+    }
+
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(self, _: V) -> Result<V::Value, Error> {
+        Err(Error::new("Binary format is not self-describing"))
+    }
+}
+
+/// Elements of sequence, tuple or map with known number of elements
+struct Access<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    left: usize,
+}
+
+impl<'a, 'de> de::SeqAccess<'de> for Access<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T)
+                                                      -> Result<Option<T::Value>, Error>
+    {
+        if self.left == 0 {
+            return Ok(None)
+        }
+        self.left -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.left)
+    }
+}
+
+impl<'a, 'de> de::MapAccess<'de> for Access<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K)
+                                                  -> Result<Option<K::Value>, Error>
+    {
+        if self.left == 0 {
+            return Ok(None)
+        }
+        self.left -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V)
+                                                    -> Result<V::Value, Error>
+    {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.left)
+    }
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for &'a mut Deserializer<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V)
+                                                 -> Result<(V::Value, Self), Error>
+    {
+        let idx = self.unsigned(u64::from(::std::u32::MAX))? as u32;
+        let variant: de::value::U32Deserializer<Error> = idx.into_deserializer();
+        Ok((seed.deserialize(variant)?, self))
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for &'a mut Deserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T)
+                                                         -> Result<T::Value, Error>
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(self, len: usize, visitor: V)
+                                          -> Result<V::Value, Error>
+    {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(self, fields: &'static [&'static str], visitor: V)
+                                           -> Result<V::Value, Error>
+    {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use super::*;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Rect{width: u32, height: u32},
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Item {
+        id: u64,
+        delta: i32,
+        name: String,
+        tags: Vec<String>,
+        shapes: Vec<Shape>,
+        parent: Option<Box<Item>>,
+        attrs: HashMap<String, (bool, char)>,
+    }
+
+    #[test]
+    fn test_binary_roundtrip() {
+        let mut attrs = HashMap::new();
+        attrs.insert("visible".to_owned(), (true, 'ü'));
+        let item = Item {
+            id: 300,
+            delta: -2,
+            name: "item".to_owned(),
+            tags: vec!["a".to_owned(), "b".to_owned()],
+            shapes: vec![Shape::Empty, Shape::Circle(1.5), Shape::Rect{width: 2, height: 3}],
+            parent: Some(Box::new(Item {
+                id: ::std::u64::MAX, delta: ::std::i32::MIN, name: String::new(), tags: vec![],
+                shapes: vec![], parent: None, attrs: HashMap::new()})),
+            attrs,
+        };
+        let body = to_vec(&item).unwrap();
+        assert_eq!(from_slice::<Item>(&body).unwrap(), item);
+
+        // varints are compact
+        assert_eq!(to_vec(&300u64).unwrap(), vec![0xac, 0x02]);
+        assert_eq!(to_vec(&-2i32).unwrap(), vec![3]);
+    }
+
+    #[test]
+    fn test_binary_invalid() {
+        let body = to_vec(&(1u32, "name")).unwrap();
+        assert!(from_slice::<(u32, String)>(&body[..body.len() - 1]).is_err());
+        assert!(from_slice::<u32>(&body).is_err());
+        assert!(from_slice::<u8>(&to_vec(&300u64).unwrap()).is_err());
+        assert!(from_slice::<u16>(&to_vec(&70_000u64).unwrap()).is_err());
+        assert!(from_slice::<bool>(&[2]).is_err());
+        assert!(from_slice::<u64>(&[0xff; 11]).is_err());
+    }
+}
@@ -0,0 +1,550 @@
+//! Cluster membership
+//!
+//! [`Cluster`](struct.Cluster.html) actor maintains set of member nodes
+//! on top of remote [`Node`](../struct.Node.html). New node joins cluster
+//! through any existing member (seed node) with [`Join`](struct.Join.html)
+//! message, member list is exchanged on join, so each node ends up connected
+//! to all other members.
+//!
+//! Members send heartbeats to each other periodically. Availability of
+//! each member is tracked with phi accrual failure detector, member gets
+//! removed once its suspicion level exceeds threshold. Removed member stays
+//! unreachable, it still gets heartbeats and is reconnected if its
+//! connection is closed. Unreachable member that sends heartbeat or join
+//! request is admitted back to cluster. Subscribers of
+//! cluster receive [`ClusterEvent`](enum.ClusterEvent.html) notifications.
+//! Recipients registered on any member are found with
+//! [`Lookup`](struct.Lookup.html) message.
+//!
+//! ```rust
+//! # extern crate actix;
+//! use std::time::Duration;
+//! use actix::prelude::*;
+//! use actix::remote::Node;
+//! use actix::remote::cluster::{self, Cluster, ClusterEvent};
+//!
+//! struct Monitor;
+//!
+//! impl Actor for Monitor {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl Handler<ClusterEvent> for Monitor {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, msg: ClusterEvent, _: &mut Context<Self>) {
+//!         println!("Cluster event: {:?}", msg);
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     let node = Node::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+//!     let local = node.local_addr();
+//!     let node: Addr<Syn, _> = node.start();
+//!
+//!     let cluster: Addr<Syn, _> = Cluster::new(node, local)
+//!         .heartbeat_interval(Duration::from_millis(500))
+//!         .start();
+//!     let monitor: Addr<Syn, _> = Monitor.start();
+//!     cluster.do_send(cluster::Subscribe(monitor.recipient()));
+//!
+//!     // join cluster through seed node
+//!     cluster.do_send(cluster::Join("127.0.0.1:9000".parse().unwrap()));
+//! #   Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!     sys.run();
+//! }
+//! ```
+use std::mem;
+use std::net::SocketAddr;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+use std::collections::{HashMap, HashSet, VecDeque};
+use futures::{future, Future};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use prelude::*;
+use super::{Connect, Node, Register, RemoteAddress, RemoteError, RemoteMessage, RemoteNode};
+
+/// Name of cluster recipients on node
+const CLUSTER: &'static str = "actix.cluster";
+
+/// Default heartbeat interval
+const DEFAULT_HEARTBEAT_INTERVAL: u64 = 1000;
+
+/// Default suspicion level of failure detector
+pub const DEFAULT_THRESHOLD: f64 = 8.0;
+
+/// Max number of heartbeat intervals kept by failure detector
+const MAX_SAMPLES: usize = 200;
+
+fn millis(dur: Duration) -> f64 {
+    dur.as_secs() as f64 * 1000.0 + f64::from(dur.subsec_nanos()) / 1_000_000.0
+}
+
+/// Phi accrual failure detector
+///
+/// Detector keeps history of intervals between heartbeats and estimates
+/// suspicion level `phi` of node failure from time elapsed since last heartbeat.
+/// Normal distribution of intervals is assumed, `phi` of 1 means
+/// 10% chance of mistake, `phi` of 2 means 1% chance and so on.
+pub struct PhiAccrualDetector {
+    threshold: f64,
+    min_std_deviation: f64,
+    intervals: VecDeque<f64>,
+    last: Option<Instant>,
+}
+
+impl PhiAccrualDetector {
+    /// Create detector for heartbeats expected every `interval`
+    pub fn new(threshold: f64, interval: Duration) -> PhiAccrualDetector {
+        let mean = millis(interval);
+        let std_deviation = mean / 4.0;
+        let mut intervals = VecDeque::with_capacity(MAX_SAMPLES);
+        intervals.push_back(mean - std_deviation);
+        intervals.push_back(mean + std_deviation);
+
+        PhiAccrualDetector {
+            threshold: threshold,
+            min_std_deviation: std_deviation,
+            intervals: intervals,
+            last: None,
+        }
+    }
+
+    /// Record heartbeat arrival
+    pub fn heartbeat(&mut self, now: Instant) {
+        if let Some(last) = self.last {
+            if now > last {
+                if self.intervals.len() >= MAX_SAMPLES {
+                    self.intervals.pop_front();
+                }
+                self.intervals.push_back(millis(now - last));
+            }
+        }
+        self.last = Some(now);
+    }
+
+    /// Suspicion level at the moment `now`
+    ///
+    /// Level is 0 until first heartbeat.
+    pub fn phi(&self, now: Instant) -> f64 {
+        let last = match self.last {
+            Some(last) if now > last => last,
+            _ => return 0.0,
+        };
+        let elapsed = millis(now - last);
+
+        let count = self.intervals.len() as f64;
+        let mean = self.intervals.iter().sum::<f64>() / count;
+        let variance = self.intervals.iter()
+            .map(|i| (i - mean) * (i - mean)).sum::<f64>() / count;
+        let std_deviation = variance.sqrt().max(self.min_std_deviation);
+
+        // logistic approximation of normal distribution cdf
+        let y = (elapsed - mean) / std_deviation;
+        let e = (-y * (1.5976 + 0.070_566 * y * y)).exp();
+        if elapsed > mean {
+            -(e / (1.0 + e)).log10()
+        } else {
+            -(1.0 - 1.0 / (1.0 + e)).log10()
+        }
+    }
+
+    /// Check if suspicion level at the moment `now` is below threshold
+    pub fn is_available(&self, now: Instant) -> bool {
+        self.phi(now) < self.threshold
+    }
+}
+
+/// Cluster membership event
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClusterEvent {
+    /// Node joined cluster
+    NodeUp(SocketAddr),
+    /// Node left cluster or failure detector marked node as unavailable
+    NodeDown(SocketAddr),
+}
+
+impl Message for ClusterEvent {
+    type Result = ();
+}
+
+/// Cluster member
+struct Member {
+    remote: RemoteNode,
+    detector: PhiAccrualDetector,
+}
+
+/// Cluster membership actor
+///
+/// `addr` is address of local node, other members connect to it.
+pub struct Cluster {
+    node: Addr<Syn, Node>,
+    addr: SocketAddr,
+    interval: Duration,
+    threshold: f64,
+    members: HashMap<SocketAddr, Member>,
+    /// Members marked as down by failure detector
+    unreachable: HashMap<SocketAddr, RemoteNode>,
+    connecting: HashSet<SocketAddr>,
+    subscribers: Vec<Recipient<Syn, ClusterEvent>>,
+}
+
+impl Cluster {
+    /// Create cluster actor for local node
+    pub fn new(node: Addr<Syn, Node>, addr: SocketAddr) -> Cluster {
+        Cluster {
+            node: node,
+            addr: addr,
+            interval: Duration::from_millis(DEFAULT_HEARTBEAT_INTERVAL),
+            threshold: DEFAULT_THRESHOLD,
+            members: HashMap::new(),
+            unreachable: HashMap::new(),
+            connecting: HashSet::new(),
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Set heartbeat interval
+    ///
+    /// All members of cluster should use same interval.
+    pub fn heartbeat_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set suspicion level at which member is considered to be down
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    fn publish(&mut self, event: ClusterEvent) {
+        let subscribers = mem::replace(&mut self.subscribers, Vec::new());
+        for subscr in subscribers {
+            if subscr.do_send(event.clone()).is_ok() {
+                self.subscribers.push(subscr);
+            }
+        }
+    }
+
+    /// Connect to node and exchange member lists
+    fn connect(&mut self, addr: SocketAddr, ctx: &mut Context<Self>) {
+        if addr == self.addr || self.members.contains_key(&addr) ||
+            !self.connecting.insert(addr)
+        {
+            return
+        }
+
+        self.node.send(Connect(addr))
+            .into_actor(self)
+            .then(move |res, act, ctx| {
+                act.connecting.remove(&addr);
+                match res {
+                    Ok(Ok(remote)) => {
+                        act.member_up(addr, &remote);
+                        act.join(&remote, ctx);
+                    }
+                    Ok(Err(err)) => if act.unreachable.contains_key(&addr) {
+                        debug!("Can not reconnect to unreachable member {}: {}", addr, err)
+                    } else {
+                        warn!("Can not connect to cluster member {}: {}", addr, err)
+                    },
+                    Err(_) => (),
+                }
+                actix::fut::ok(())
+            })
+            .spawn(ctx);
+    }
+
+    /// Announce local node to member, connect to members it knows about
+    fn join(&mut self, remote: &RemoteNode, ctx: &mut Context<Self>) {
+        remote.recipient::<JoinRequest>(CLUSTER).send(JoinRequest(self.addr))
+            .into_actor(self)
+            .then(|res, act, ctx| {
+                if let Ok(members) = res {
+                    for addr in members {
+                        act.connect(addr, ctx);
+                    }
+                }
+                actix::fut::ok(())
+            })
+            .spawn(ctx);
+    }
+
+    fn member_up(&mut self, addr: SocketAddr, remote: &RemoteNode) {
+        self.unreachable.remove(&addr);
+        let mut detector = PhiAccrualDetector::new(self.threshold, self.interval);
+        detector.heartbeat(Instant::now());
+        self.members.insert(addr, Member {
+            remote: remote.clone(),
+            detector: detector,
+        });
+        info!("Cluster member is up: {}", addr);
+        self.publish(ClusterEvent::NodeUp(addr));
+    }
+
+    /// Remove member, member that left cluster is forgotten, member that
+    /// failed to send heartbeats becomes unreachable
+    fn member_down(&mut self, addr: SocketAddr, left: bool) {
+        if left {
+            self.unreachable.remove(&addr);
+        }
+        if let Some(member) = self.members.remove(&addr) {
+            info!("Cluster member is down: {}", addr);
+            if !left {
+                self.unreachable.insert(addr, member.remote);
+            }
+            self.publish(ClusterEvent::NodeDown(addr));
+        }
+    }
+
+    /// Send heartbeats, check members availability
+    fn heartbeat(&mut self, ctx: &mut Context<Self>) {
+        let now = Instant::now();
+        let mut down = Vec::new();
+        for (addr, member) in &self.members {
+            let _ = member.remote.recipient(CLUSTER).do_send(Heartbeat(self.addr));
+            if !member.detector.is_available(now) {
+                down.push(*addr);
+            }
+        }
+        for addr in down {
+            self.member_down(addr, false);
+        }
+
+        // unreachable member admits local node back once it gets heartbeat
+        let mut closed = Vec::new();
+        for (addr, remote) in &self.unreachable {
+            if remote.connected() {
+                let _ = remote.recipient(CLUSTER).do_send(Heartbeat(self.addr));
+            } else {
+                closed.push(*addr);
+            }
+        }
+        for addr in closed {
+            self.reconnect(addr, ctx);
+        }
+    }
+
+    /// Open new connection to unreachable member
+    fn reconnect(&mut self, addr: SocketAddr, ctx: &mut Context<Self>) {
+        if addr == self.addr || self.members.contains_key(&addr) ||
+            !self.connecting.insert(addr)
+        {
+            return
+        }
+
+        self.node.send(Connect(addr))
+            .into_actor(self)
+            .then(move |res, act, _| {
+                act.connecting.remove(&addr);
+                match res {
+                    Ok(Ok(remote)) => if act.unreachable.contains_key(&addr) {
+                        act.unreachable.insert(addr, remote);
+                    },
+                    Ok(Err(err)) =>
+                        debug!("Can not reconnect to unreachable member {}: {}", addr, err),
+                    Err(_) => (),
+                }
+                actix::fut::ok(())
+            })
+            .spawn(ctx);
+    }
+}
+
+impl Actor for Cluster {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let addr: Addr<Syn, _> = ctx.address();
+        self.node.do_send(Register::new(CLUSTER, addr.clone().recipient::<JoinRequest>()));
+        self.node.do_send(Register::new(CLUSTER, addr.clone().recipient::<LeaveNotice>()));
+        self.node.do_send(Register::new(CLUSTER, addr.recipient::<Heartbeat>()));
+
+        ctx.run_interval(self.interval, |act, ctx| act.heartbeat(ctx));
+    }
+}
+
+/// Join cluster through seed node
+pub struct Join(pub SocketAddr);
+
+impl Message for Join {
+    type Result = ();
+}
+
+impl Handler<Join> for Cluster {
+    type Result = ();
+
+    fn handle(&mut self, msg: Join, ctx: &mut Context<Self>) {
+        self.connect(msg.0, ctx);
+    }
+}
+
+/// Leave cluster, cluster actor stops after notifying members
+pub struct Leave;
+
+impl Message for Leave {
+    type Result = ();
+}
+
+impl Handler<Leave> for Cluster {
+    type Result = ();
+
+    fn handle(&mut self, _: Leave, ctx: &mut Context<Self>) {
+        let members = mem::replace(&mut self.members, HashMap::new());
+        for (addr, member) in members {
+            let _ = member.remote.recipient(CLUSTER).do_send(LeaveNotice(self.addr));
+            self.publish(ClusterEvent::NodeDown(addr));
+        }
+        for (_, remote) in self.unreachable.drain() {
+            let _ = remote.recipient(CLUSTER).do_send(LeaveNotice(self.addr));
+        }
+        ctx.stop();
+    }
+}
+
+/// Subscribe to cluster events
+pub struct Subscribe(pub Recipient<Syn, ClusterEvent>);
+
+impl Message for Subscribe {
+    type Result = ();
+}
+
+impl Handler<Subscribe> for Cluster {
+    type Result = ();
+
+    fn handle(&mut self, msg: Subscribe, _: &mut Context<Self>) {
+        self.subscribers.push(msg.0);
+    }
+}
+
+/// Get addresses of current cluster members, local node is not included
+pub struct Members;
+
+impl Message for Members {
+    type Result = Vec<SocketAddr>;
+}
+
+impl Handler<Members> for Cluster {
+    type Result = Vec<SocketAddr>;
+
+    fn handle(&mut self, _: Members, _: &mut Context<Self>) -> Vec<SocketAddr> {
+        self.members.keys().cloned().collect()
+    }
+}
+
+/// Find recipient registered with name `name` on any cluster member
+///
+/// Only remote members are queried, local recipients are available through
+/// [`System::lookup()`](../../struct.System.html#method.lookup). Resolves to
+/// `RemoteError::UnknownRecipient` error if none of members know the name.
+pub struct Lookup<M>(pub String, PhantomData<M>)
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send;
+
+impl<M> Lookup<M> where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send {
+    /// Create lookup message
+    pub fn new<T: Into<String>>(name: T) -> Self {
+        Lookup(name.into(), PhantomData)
+    }
+}
+
+impl<M> Message for Lookup<M>
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send
+{
+    type Result = Result<RemoteAddress<M>, RemoteError>;
+}
+
+impl<M> Handler<Lookup<M>> for Cluster
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send
+{
+    type Result = ResponseFuture<RemoteAddress<M>, RemoteError>;
+
+    fn handle(&mut self, msg: Lookup<M>, _: &mut Context<Self>) -> Self::Result {
+        if self.members.is_empty() {
+            return Box::new(future::err(RemoteError::UnknownRecipient(msg.0)))
+        }
+        let name = msg.0;
+        let lookups: Vec<_> = self.members.values()
+            .map(|member| member.remote.lookup::<M>(&name))
+            .collect();
+        Box::new(
+            future::select_ok(lookups)
+                .map(|(addr, _)| addr)
+                .map_err(move |_| RemoteError::UnknownRecipient(name)))
+    }
+}
+
+/// Node asks to join cluster, response is list of known members
+#[derive(Serialize, Deserialize)]
+struct JoinRequest(SocketAddr);
+
+impl Message for JoinRequest {
+    type Result = Vec<SocketAddr>;
+}
+
+impl RemoteMessage for JoinRequest {
+    fn type_name() -> &'static str { "actix.cluster.Join" }
+}
+
+impl Handler<JoinRequest> for Cluster {
+    type Result = Vec<SocketAddr>;
+
+    fn handle(&mut self, msg: JoinRequest, ctx: &mut Context<Self>) -> Vec<SocketAddr> {
+        let members = self.members.keys().cloned().filter(|addr| *addr != msg.0).collect();
+        self.connect(msg.0, ctx);
+        members
+    }
+}
+
+/// Node leaves cluster
+#[derive(Serialize, Deserialize)]
+struct LeaveNotice(SocketAddr);
+
+impl Message for LeaveNotice {
+    type Result = ();
+}
+
+impl RemoteMessage for LeaveNotice {
+    fn type_name() -> &'static str { "actix.cluster.Leave" }
+}
+
+impl Handler<LeaveNotice> for Cluster {
+    type Result = ();
+
+    fn handle(&mut self, msg: LeaveNotice, _: &mut Context<Self>) {
+        self.member_down(msg.0, true);
+    }
+}
+
+/// Heartbeat of cluster member
+#[derive(Serialize, Deserialize)]
+struct Heartbeat(SocketAddr);
+
+impl Message for Heartbeat {
+    type Result = ();
+}
+
+impl RemoteMessage for Heartbeat {
+    fn type_name() -> &'static str { "actix.cluster.Heartbeat" }
+}
+
+impl Handler<Heartbeat> for Cluster {
+    type Result = ();
+
+    fn handle(&mut self, msg: Heartbeat, ctx: &mut Context<Self>) {
+        if let Some(member) = self.members.get_mut(&msg.0) {
+            member.detector.heartbeat(Instant::now());
+            return
+        }
+        // unreachable member is alive, admit it back
+        if let Some(remote) = self.unreachable.remove(&msg.0) {
+            info!("Unreachable cluster member is back: {}", msg.0);
+            self.member_up(msg.0, &remote);
+            self.join(&remote, ctx);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Wire protocol of remote connections
+//!
+//! Each frame is prefixed with its length as big endian u32, followed by
+//! frame kind byte and frame fields. Message bodies are opaque bytes.
+use std::io::{self, Cursor};
+extern crate bytes;
+
+use self::bytes::{Buf, BufMut, BytesMut};
+use tokio_codec::{Decoder, Encoder};
+
+use super::RemoteError;
+
+/// Max size of the frame
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Max length of the string field
+const MAX_STR_LEN: usize = ::std::u16::MAX as usize;
+
+const REQUEST: u8 = 0;
+const MESSAGE: u8 = 1;
+const RESPONSE: u8 = 2;
+const FAILURE: u8 = 3;
+const HELLO: u8 = 4;
+
+const UNKNOWN_RECIPIENT: u8 = 1;
+const SERIALIZATION: u8 = 2;
+const DELIVERY: u8 = 3;
+const TOO_LARGE: u8 = 4;
+
+/// Frame of remote connection
+#[derive(Debug)]
+pub(crate) enum Frame {
+    /// First frame of connecting node, contains name of wire format
+    Hello {
+        format: String,
+    },
+    /// Message for remote recipient, response is expected if id is set
+    Request {
+        id: Option<u64>,
+        name: String,
+        message_type: String,
+        body: Vec<u8>,
+    },
+    /// Response for request with the same id
+    Response {
+        id: u64,
+        result: Result<Vec<u8>, RemoteError>,
+    },
+}
+
+/// Check that request fits into the frame
+pub(crate) fn check_request(name: &str, message_type: &str, body: &[u8])
+                            -> Result<(), RemoteError>
+{
+    if name.len() > MAX_STR_LEN || message_type.len() > MAX_STR_LEN ||
+        13 + name.len() + message_type.len() + body.len() > MAX_FRAME_SIZE
+    {
+        Err(RemoteError::TooLarge)
+    } else {
+        Ok(())
+    }
+}
+
+/// Check that response fits into the frame
+pub(crate) fn check_response(body: &[u8]) -> Result<(), RemoteError> {
+    if 9 + body.len() > MAX_FRAME_SIZE {
+        Err(RemoteError::TooLarge)
+    } else {
+        Ok(())
+    }
+}
+
+/// Length delimited codec for `Frame`
+pub(crate) struct FrameCodec;
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.to_owned())
+}
+
+fn get_str(buf: &mut Cursor<BytesMut>) -> io::Result<String> {
+    if buf.remaining() < 2 {
+        return Err(invalid("Frame is truncated"))
+    }
+    let len = buf.get_u16_be() as usize;
+    if buf.remaining() < len {
+        return Err(invalid("Frame is truncated"))
+    }
+    let mut data = vec![0; len];
+    buf.copy_to_slice(&mut data);
+    String::from_utf8(data).map_err(|_| invalid("Invalid utf-8 string"))
+}
+
+fn put_str(dst: &mut BytesMut, s: &str) -> io::Result<()> {
+    if s.len() > MAX_STR_LEN {
+        return Err(invalid("String is too long"))
+    }
+    dst.put_u16_be(s.len() as u16);
+    dst.put_slice(s.as_bytes());
+    Ok(())
+}
+
+fn rest(buf: &mut Cursor<BytesMut>) -> Vec<u8> {
+    let mut data = vec![0; buf.remaining()];
+    buf.copy_to_slice(&mut data);
+    data
+}
+
+impl Decoder for FrameCodec {
+    type Item = Frame;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Frame>, io::Error> {
+        if src.len() < 4 {
+            return Ok(None)
+        }
+        let size = Cursor::new(&src[..4]).get_u32_be() as usize;
+        if size > MAX_FRAME_SIZE {
+            return Err(invalid("Frame is too large"))
+        }
+        if src.len() < size + 4 {
+            return Ok(None)
+        }
+        src.split_to(4);
+        let mut buf = Cursor::new(src.split_to(size));
+        if buf.remaining() < 9 {
+            return Err(invalid("Frame is truncated"))
+        }
+
+        let kind = buf.get_u8();
+        let id = buf.get_u64_be();
+        let frame = match kind {
+            REQUEST | MESSAGE => {
+                let name = get_str(&mut buf)?;
+                let message_type = get_str(&mut buf)?;
+                Frame::Request {
+                    id: if kind == REQUEST { Some(id) } else { None },
+                    name,
+                    message_type,
+                    body: rest(&mut buf),
+                }
+            }
+            RESPONSE => Frame::Response{id, result: Ok(rest(&mut buf))},
+            FAILURE => {
+                if buf.remaining() < 1 {
+                    return Err(invalid("Frame is truncated"))
+                }
+                let code = buf.get_u8();
+                let reason = String::from_utf8_lossy(&rest(&mut buf)).into_owned();
+                let err = match code {
+                    UNKNOWN_RECIPIENT => RemoteError::UnknownRecipient(reason),
+                    SERIALIZATION => RemoteError::Serialization(reason),
+                    DELIVERY => RemoteError::Delivery,
+                    TOO_LARGE => RemoteError::TooLarge,
+                    _ => return Err(invalid("Unknown failure code")),
+                };
+                Frame::Response{id, result: Err(err)}
+            }
+            HELLO => Frame::Hello{format: get_str(&mut buf)?},
+            _ => return Err(invalid("Unknown frame kind")),
+        };
+        Ok(Some(frame))
+    }
+}
+
+impl Encoder for FrameCodec {
+    type Item = Frame;
+    type Error = io::Error;
+
+    fn encode(&mut self, frame: Frame, dst: &mut BytesMut) -> Result<(), io::Error> {
+        let mut buf = BytesMut::with_capacity(64);
+        match frame {
+            Frame::Hello{format} => {
+                buf.reserve(11 + format.len());
+                buf.put_u8(HELLO);
+                buf.put_u64_be(0);
+                put_str(&mut buf, &format)?;
+            }
+            Frame::Request{id, name, message_type, body} => {
+                buf.reserve(13 + name.len() + message_type.len() + body.len());
+                buf.put_u8(if id.is_some() { REQUEST } else { MESSAGE });
+                buf.put_u64_be(id.unwrap_or(0));
+                put_str(&mut buf, &name)?;
+                put_str(&mut buf, &message_type)?;
+                buf.put_slice(&body);
+            }
+            Frame::Response{id, result: Ok(body)} => {
+                buf.reserve(9 + body.len());
+                buf.put_u8(RESPONSE);
+                buf.put_u64_be(id);
+                buf.put_slice(&body);
+            }
+            Frame::Response{id, result: Err(err)} => {
+                let (code, reason) = match err {
+                    RemoteError::UnknownRecipient(reason) => (UNKNOWN_RECIPIENT, reason),
+                    RemoteError::Serialization(reason) => (SERIALIZATION, reason),
+                    RemoteError::TooLarge => (TOO_LARGE, String::new()),
+                    _ => (DELIVERY, String::new()),
+                };
+                buf.reserve(10 + reason.len());
+                buf.put_u8(FAILURE);
+                buf.put_u64_be(id);
+                buf.put_u8(code);
+                buf.put_slice(reason.as_bytes());
+            }
+        }
+        if buf.len() > MAX_FRAME_SIZE {
+            return Err(invalid("Frame is too large"))
+        }
+        dst.reserve(4 + buf.len());
+        dst.put_u32_be(buf.len() as u32);
+        dst.put_slice(&buf);
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+//! Wire formats of remote messages
+//!
+//! Format is chosen per connection with
+//! [`ConnectWith`](../struct.ConnectWith.html) message, connecting node
+//! announces name of the format when connection is established. Recipient
+//! is reachable over connections of a format it is registered with,
+//! [`Register`](../struct.Register.html) uses `Json` format,
+//! [`RegisterWith`](../struct.RegisterWith.html) registers recipient for
+//! any other format.
+//!
+//! `Json` and compact `Binary` formats are provided, other serde formats
+//! could be plugged in by implementing `WireFormat` trait, i.e. bincode:
+//!
+//! ```rust,ignore
+//! struct Bincode;
+//!
+//! impl WireFormat for Bincode {
+//!     fn name() -> &'static str { "bincode" }
+//!
+//!     fn serialize<T: Serialize>(item: &T) -> Result<Vec<u8>, RemoteError> {
+//!         bincode::serialize(item)
+//!             .map_err(|e| RemoteError::Serialization(format!("{}", e)))
+//!     }
+//!
+//!     fn deserialize<T: DeserializeOwned>(body: &[u8]) -> Result<T, RemoteError> {
+//!         bincode::deserialize(body)
+//!             .map_err(|e| RemoteError::Serialization(format!("{}", e)))
+//!     }
+//! }
+//! ```
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use super::RemoteError;
+use super::binary;
+
+/// Serialization format of remote messages and responses
+pub trait WireFormat: Send + 'static {
+    /// Name of the format, has to be the same on all nodes
+    fn name() -> &'static str;
+
+    /// Serialize message or response
+    fn serialize<T: Serialize>(item: &T) -> Result<Vec<u8>, RemoteError>;
+
+    /// Deserialize message or response
+    fn deserialize<T: DeserializeOwned>(body: &[u8]) -> Result<T, RemoteError>;
+}
+
+/// JSON format, default format of remote connections
+pub struct Json;
+
+impl WireFormat for Json {
+    fn name() -> &'static str { "json" }
+
+    fn serialize<T: Serialize>(item: &T) -> Result<Vec<u8>, RemoteError> {
+        serde_json::to_vec(item).map_err(|e| RemoteError::Serialization(format!("{}", e)))
+    }
+
+    fn deserialize<T: DeserializeOwned>(body: &[u8]) -> Result<T, RemoteError> {
+        serde_json::from_slice(body).map_err(|e| RemoteError::Serialization(format!("{}", e)))
+    }
+}
+
+/// Compact binary format
+///
+/// Integers and lengths are varint encoded and struct fields are written
+/// without names, so both nodes have to use the same definitions of
+/// messages. Format is not self-describing, types that rely on
+/// `deserialize_any`, i.e. untagged enums, are not supported.
+pub struct Binary;
+
+impl WireFormat for Binary {
+    fn name() -> &'static str { "binary" }
+
+    fn serialize<T: Serialize>(item: &T) -> Result<Vec<u8>, RemoteError> {
+        binary::to_vec(item).map_err(|e| RemoteError::Serialization(format!("{}", e)))
+    }
+
+    fn deserialize<T: DeserializeOwned>(body: &[u8]) -> Result<T, RemoteError> {
+        binary::from_slice(body).map_err(|e| RemoteError::Serialization(format!("{}", e)))
+    }
+}
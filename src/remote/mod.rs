@@ -0,0 +1,690 @@
+//! Remote actors
+//!
+//! [`Node`](struct.Node.html) actor listens on tcp port and delivers messages
+//! received from remote nodes to local recipients registered with
+//! [`Register`](struct.Register.html) message. Connection to remote node
+//! is established with [`Connect`](struct.Connect.html) message, resulting
+//! [`RemoteNode`](struct.RemoteNode.html) provides
+//! [`RemoteAddress`](struct.RemoteAddress.html) handles for recipients
+//! of remote node. [`RemoteNode::lookup()`](struct.RemoteNode.html#method.lookup)
+//! checks if name is registered on remote node, cluster wide lookup is
+//! provided by [`cluster::Lookup`](cluster/struct.Lookup.html) message.
+//!
+//! Message has to implement [`RemoteMessage`](trait.RemoteMessage.html)
+//! trait to be sent to remote node, messages and responses are serialized
+//! with serde. Serialization format is chosen per connection, `Json` format
+//! is used by default, compact `Binary` format is also provided, see
+//! [`WireFormat`](trait.WireFormat.html).
+//! Remote actors are available with `remote` feature.
+//!
+//! ## Example
+//!
+//! ```rust
+//! # extern crate actix;
+//! # extern crate futures;
+//! # #[macro_use] extern crate serde_derive;
+//! # use futures::Future;
+//! use actix::prelude::*;
+//! use actix::remote::{self, Node, RemoteMessage};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Deposit(u64);
+//!
+//! impl Message for Deposit {
+//!     type Result = u64;
+//! }
+//!
+//! impl RemoteMessage for Deposit {
+//!     fn type_name() -> &'static str { "Deposit" }
+//! }
+//!
+//! struct Ledger(u64);
+//!
+//! impl Actor for Ledger {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl Handler<Deposit> for Ledger {
+//!     type Result = u64;
+//!
+//!     fn handle(&mut self, msg: Deposit, _: &mut Context<Self>) -> u64 {
+//!         self.0 += msg.0;
+//!         self.0
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     // start node and register local recipient
+//!     let node = Node::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+//!     let node_addr = node.local_addr();
+//!     let node: Addr<Syn, _> = node.start();
+//!     let ledger: Addr<Syn, _> = Ledger(0).start();
+//!     node.do_send(remote::Register::new("ledger", ledger.recipient()));
+//!
+//!     // connect to node and send message to remote recipient
+//!     Arbiter::handle().spawn(
+//!         node.send(remote::Connect(node_addr))
+//!             .map_err(|_| ())
+//!             .and_then(|res| {
+//!                 let remote = res.unwrap();
+//!                 remote.recipient::<Deposit>("ledger").send(Deposit(10))
+//!                     .then(|res| {
+//!                         assert_eq!(res.unwrap(), 10);
+//! #                       Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!                         Ok(())
+//!                     })
+//!             }));
+//!
+//!     sys.run();
+//! }
+//! ```
+use std::io;
+use std::net::SocketAddr;
+use std::marker::PhantomData;
+use std::collections::HashMap;
+use std::time::Duration;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use futures::{future, Future};
+use futures::task;
+use futures::sync::oneshot;
+use tokio_io::AsyncRead;
+use tokio_io::io::WriteHalf;
+use tokio_codec::FramedRead;
+use tokio_core::net::{TcpListener, TcpStream};
+
+use prelude::*;
+use io::{FramedWrite, WriteHandler};
+
+mod binary;
+mod codec;
+mod format;
+pub mod cluster;
+
+pub use self::format::{Binary, Json, WireFormat};
+use self::codec::{Frame, FrameCodec};
+
+/// Name of node's registry recipient
+const REGISTRY: &'static str = "actix.registry";
+
+/// Message that could be sent to remote node
+///
+/// Message and its result are serialized with serde.
+pub trait RemoteMessage: Message + Serialize + DeserializeOwned + Send + 'static {
+    /// Name of the message type, has to be the same on all nodes
+    fn type_name() -> &'static str;
+}
+
+/// Remote message delivery error
+#[derive(Fail, Debug)]
+pub enum RemoteError {
+    /// Connection to remote node is closed
+    #[fail(display="Connection is closed")]
+    Closed,
+    /// Recipient is not registered on remote node
+    #[fail(display="Recipient is not registered: {}", _0)]
+    UnknownRecipient(String),
+    /// Message or response could not be serialized
+    #[fail(display="Serialization error: {}", _0)]
+    Serialization(String),
+    /// Mailbox of remote recipient is closed
+    #[fail(display="Remote recipient's mailbox is closed")]
+    Delivery,
+    /// Message or response does not fit into the frame
+    #[fail(display="Message is too large")]
+    TooLarge,
+    /// Remote recipient did not respond in time
+    #[fail(display="Request timed out")]
+    Timeout,
+    /// Connection io error
+    #[fail(display="{}", _0)]
+    Io(#[cause] io::Error),
+}
+
+/// Future of remote message response
+pub type RemoteRequest<M> = Box<Future<Item=<M as Message>::Result, Error=RemoteError>>;
+
+/// Type erased local recipient of remote messages
+trait LocalRecipient {
+    /// Deliver serialized message, resolves to serialized response
+    fn deliver(&self, body: &[u8], reply: bool) -> Box<Future<Item=Vec<u8>, Error=RemoteError>>;
+
+    /// Indicates if recipient is still alive
+    fn connected(&self) -> bool;
+}
+
+/// Recipient registered for wire format `F`
+struct Entry<M, F>
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send, F: WireFormat
+{
+    recipient: Recipient<Syn, M>,
+    format: PhantomData<F>,
+}
+
+impl<M, F> LocalRecipient for Entry<M, F>
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send, F: WireFormat
+{
+    fn deliver(&self, body: &[u8], reply: bool) -> Box<Future<Item=Vec<u8>, Error=RemoteError>> {
+        let msg = match F::deserialize::<M>(body) {
+            Ok(msg) => msg,
+            Err(err) => return Box::new(future::err(err)),
+        };
+        if reply {
+            Box::new(self.recipient.send(msg)
+                     .map_err(|_| RemoteError::Delivery)
+                     .and_then(|res| F::serialize(&res)))
+        } else {
+            Box::new(future::result(
+                self.recipient.do_send(msg)
+                    .map(|_| Vec::new()).map_err(|_| RemoteError::Delivery)))
+        }
+    }
+
+    fn connected(&self) -> bool {
+        self.recipient.connected()
+    }
+}
+
+/// Remote node
+///
+/// Node accepts connections from remote nodes and delivers incoming
+/// messages to registered recipients.
+pub struct Node {
+    addr: SocketAddr,
+    listener: Option<TcpListener>,
+    recipients: HashMap<(String, String, String), Box<LocalRecipient>>,
+}
+
+impl Node {
+    /// Create node listening on `addr`
+    ///
+    /// Node must be started in current arbiter.
+    pub fn bind(addr: &SocketAddr) -> io::Result<Node> {
+        let listener = TcpListener::bind(addr, Arbiter::handle())?;
+        Ok(Node {
+            addr: listener.local_addr()?,
+            listener: Some(listener),
+            recipients: HashMap::new(),
+        })
+    }
+
+    /// Address node is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    fn register<M, F>(&mut self, name: String, recipient: Recipient<Syn, M>,
+                      ctx: &mut Context<Self>)
+        where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send, F: WireFormat
+    {
+        self.recipients.insert(
+            (name, M::type_name().to_owned(), F::name().to_owned()),
+            Box::new(Entry{recipient: recipient, format: PhantomData::<F>}));
+
+        // remote nodes query registered names with the same format
+        let key = (REGISTRY.to_owned(), Query::type_name().to_owned(), F::name().to_owned());
+        if !self.recipients.contains_key(&key) {
+            let addr: Addr<Syn, _> = ctx.address();
+            self.recipients.insert(
+                key, Box::new(Entry{recipient: addr.recipient::<Query>(), format: PhantomData::<F>}));
+        }
+    }
+
+    fn connect<F: WireFormat>(&mut self, peer: SocketAddr)
+                              -> ResponseActFuture<Self, RemoteNode<F>, RemoteError>
+    {
+        Box::new(
+            TcpStream::connect(&peer, Arbiter::handle())
+                .map_err(RemoteError::Io)
+                .into_actor(self)
+                .map(move |stream, _, ctx| {
+                    RemoteNode {
+                        peer: peer,
+                        conn: Connection::start(stream, peer, ctx.address(), Some(F::name())),
+                        format: PhantomData,
+                    }
+                }))
+    }
+}
+
+impl Actor for Node {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        if let Some(listener) = self.listener.take() {
+            ctx.add_stream(listener.incoming());
+        }
+
+        let addr: Addr<Syn, _> = ctx.address();
+        self.register::<Query, Json>(REGISTRY.to_owned(), addr.recipient(), ctx);
+    }
+}
+
+/// Accept connection of remote node
+impl StreamHandler<(TcpStream, SocketAddr), io::Error> for Node {
+    fn handle(&mut self, (stream, peer): (TcpStream, SocketAddr), ctx: &mut Context<Self>) {
+        debug!("Remote node connected: {}", peer);
+        Connection::start(stream, peer, ctx.address(), None);
+    }
+}
+
+/// Register local recipient, so it could be reached from remote nodes
+/// by name
+///
+/// Recipient replaces previously registered recipient with the same name
+/// and message type. Recipient is reachable over connections that use
+/// `Json` wire format.
+pub struct Register<M>(pub String, pub Recipient<Syn, M>)
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send;
+
+impl<M> Register<M> where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send {
+    /// Create register message
+    pub fn new<T: Into<String>>(name: T, recipient: Recipient<Syn, M>) -> Self {
+        Register(name.into(), recipient)
+    }
+}
+
+impl<M> Message for Register<M>
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send
+{
+    type Result = ();
+}
+
+impl<M> Handler<Register<M>> for Node
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send
+{
+    type Result = ();
+
+    fn handle(&mut self, msg: Register<M>, ctx: &mut Context<Self>) {
+        self.register::<M, Json>(msg.0, msg.1, ctx);
+    }
+}
+
+/// Register local recipient for connections with wire format `F`
+pub struct RegisterWith<M, F>
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send, F: WireFormat
+{
+    name: String,
+    recipient: Recipient<Syn, M>,
+    format: PhantomData<F>,
+}
+
+impl<M, F> RegisterWith<M, F>
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send, F: WireFormat
+{
+    /// Create register message
+    pub fn new<T: Into<String>>(name: T, recipient: Recipient<Syn, M>) -> Self {
+        RegisterWith{name: name.into(), recipient: recipient, format: PhantomData}
+    }
+}
+
+impl<M, F> Message for RegisterWith<M, F>
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send, F: WireFormat
+{
+    type Result = ();
+}
+
+impl<M, F> Handler<RegisterWith<M, F>> for Node
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send, F: WireFormat
+{
+    type Result = ();
+
+    fn handle(&mut self, msg: RegisterWith<M, F>, ctx: &mut Context<Self>) {
+        self.register::<M, F>(msg.name, msg.recipient, ctx);
+    }
+}
+
+/// Connect to remote node, connection uses `Json` wire format
+pub struct Connect(pub SocketAddr);
+
+impl Message for Connect {
+    type Result = Result<RemoteNode, RemoteError>;
+}
+
+impl Handler<Connect> for Node {
+    type Result = ResponseActFuture<Self, RemoteNode, RemoteError>;
+
+    fn handle(&mut self, msg: Connect, _: &mut Context<Self>) -> Self::Result {
+        self.connect::<Json>(msg.0)
+    }
+}
+
+/// Connect to remote node, connection uses wire format `F`
+pub struct ConnectWith<F: WireFormat>(pub SocketAddr, PhantomData<F>);
+
+impl<F: WireFormat> ConnectWith<F> {
+    /// Create connect message
+    pub fn new(addr: SocketAddr) -> Self {
+        ConnectWith(addr, PhantomData)
+    }
+}
+
+impl<F: WireFormat> Message for ConnectWith<F> {
+    type Result = Result<RemoteNode<F>, RemoteError>;
+}
+
+impl<F: WireFormat> Handler<ConnectWith<F>> for Node {
+    type Result = ResponseActFuture<Self, RemoteNode<F>, RemoteError>;
+
+    fn handle(&mut self, msg: ConnectWith<F>, _: &mut Context<Self>) -> Self::Result {
+        self.connect::<F>(msg.0)
+    }
+}
+
+/// Deliver message received from remote node to local recipient
+struct Deliver {
+    name: String,
+    message_type: String,
+    format: String,
+    body: Vec<u8>,
+    reply: bool,
+}
+
+impl Message for Deliver {
+    type Result = Result<Vec<u8>, RemoteError>;
+}
+
+impl Handler<Deliver> for Node {
+    type Result = ResponseFuture<Vec<u8>, RemoteError>;
+
+    fn handle(&mut self, msg: Deliver, _: &mut Context<Self>) -> Self::Result {
+        let key = (msg.name, msg.message_type, msg.format);
+        match self.recipients.get(&key) {
+            Some(recipient) => recipient.deliver(&msg.body, msg.reply),
+            None => Box::new(future::err(RemoteError::UnknownRecipient(key.0))),
+        }
+    }
+}
+
+/// Check if recipient is registered on node
+#[derive(Serialize, Deserialize)]
+struct Query {
+    name: String,
+    message_type: String,
+    format: String,
+}
+
+impl Message for Query {
+    type Result = bool;
+}
+
+impl RemoteMessage for Query {
+    fn type_name() -> &'static str { "actix.registry.Query" }
+}
+
+impl Handler<Query> for Node {
+    type Result = bool;
+
+    fn handle(&mut self, msg: Query, _: &mut Context<Self>) -> bool {
+        let key = (msg.name, msg.message_type, msg.format);
+        let connected = self.recipients.get(&key).map(|r| r.connected());
+        if connected == Some(false) {
+            self.recipients.remove(&key);
+        }
+        connected.unwrap_or(false)
+    }
+}
+
+/// Connection to remote node
+///
+/// Returned by `Connect` message, messages are serialized with wire format `F`.
+pub struct RemoteNode<F: WireFormat = Json> {
+    peer: SocketAddr,
+    conn: Addr<Syn, Connection>,
+    format: PhantomData<F>,
+}
+
+impl<F: WireFormat> Clone for RemoteNode<F> {
+    fn clone(&self) -> Self {
+        RemoteNode{peer: self.peer, conn: self.conn.clone(), format: PhantomData}
+    }
+}
+
+impl<F: WireFormat> RemoteNode<F> {
+    /// Address of remote node
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer
+    }
+
+    /// Indicates if connection is still alive
+    pub fn connected(&self) -> bool {
+        self.conn.connected()
+    }
+
+    /// Get address of recipient registered on remote node under `name`
+    pub fn recipient<M>(&self, name: &str) -> RemoteAddress<M, F>
+        where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send
+    {
+        RemoteAddress {
+            name: name.to_owned(),
+            conn: self.conn.clone(),
+            timeout: None,
+            msg: PhantomData,
+            format: PhantomData,
+        }
+    }
+
+    /// Check if recipient is registered on remote node under `name`
+    ///
+    /// Resolves to `RemoteError::UnknownRecipient` error if remote node does
+    /// not have live recipient of message `M` with this name.
+    pub fn lookup<M>(&self, name: &str)
+                     -> Box<Future<Item=RemoteAddress<M, F>, Error=RemoteError>>
+        where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send
+    {
+        let addr = self.recipient::<M>(name);
+        Box::new(
+            self.recipient::<Query>(REGISTRY)
+                .send(Query{name: name.to_owned(),
+                            message_type: M::type_name().to_owned(),
+                            format: F::name().to_owned()})
+                .and_then(move |found| if found {
+                    Ok(addr)
+                } else {
+                    Err(RemoteError::UnknownRecipient(addr.name))
+                }))
+    }
+}
+
+/// Address of recipient of message `M` on remote node
+pub struct RemoteAddress<M, F: WireFormat = Json>
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send
+{
+    name: String,
+    conn: Addr<Syn, Connection>,
+    timeout: Option<Duration>,
+    msg: PhantomData<M>,
+    format: PhantomData<F>,
+}
+
+impl<M, F: WireFormat> Clone for RemoteAddress<M, F>
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send
+{
+    fn clone(&self) -> Self {
+        RemoteAddress{name: self.name.clone(), conn: self.conn.clone(), timeout: self.timeout,
+                      msg: PhantomData, format: PhantomData}
+    }
+}
+
+impl<M, F: WireFormat> RemoteAddress<M, F>
+    where M: RemoteMessage, M::Result: Serialize + DeserializeOwned + Send
+{
+    /// Fail requests with `RemoteError::Timeout` if response does not
+    /// arrive within `dur`
+    pub fn timeout(mut self, dur: Duration) -> Self {
+        self.timeout = Some(dur);
+        self
+    }
+
+    /// Indicates if connection to remote node is still alive
+    pub fn connected(&self) -> bool {
+        self.conn.connected()
+    }
+
+    /// Serialized message, message that does not fit into the frame fails
+    /// with `RemoteError::TooLarge`
+    fn serialize(&self, msg: &M) -> Result<Vec<u8>, RemoteError> {
+        let body = F::serialize(msg)?;
+        codec::check_request(&self.name, M::type_name(), &body)?;
+        Ok(body)
+    }
+
+    /// Send message unconditionally, response is ignored
+    pub fn do_send(&self, msg: M) -> Result<(), RemoteError> {
+        let body = self.serialize(&msg)?;
+        self.conn.try_send(Outgoing {
+            name: self.name.clone(),
+            message_type: M::type_name(),
+            body,
+            tx: None,
+            timeout: None,
+        }).map_err(|_| RemoteError::Closed)
+    }
+
+    /// Send message and wait for response of remote recipient
+    pub fn send(&self, msg: M) -> RemoteRequest<M> {
+        let body = match self.serialize(&msg) {
+            Ok(body) => body,
+            Err(err) => return Box::new(future::err(err)),
+        };
+        let (tx, rx) = oneshot::channel();
+        let sent = self.conn.try_send(Outgoing {
+            name: self.name.clone(),
+            message_type: M::type_name(),
+            body,
+            tx: Some(tx),
+            timeout: self.timeout,
+        });
+        if sent.is_err() {
+            return Box::new(future::err(RemoteError::Closed))
+        }
+        Box::new(rx.map_err(|_| RemoteError::Closed)
+                 .and_then(|res| res.and_then(|body| F::deserialize(&body))))
+    }
+}
+
+/// Message for remote recipient
+struct Outgoing {
+    name: String,
+    message_type: &'static str,
+    body: Vec<u8>,
+    tx: Option<oneshot::Sender<Result<Vec<u8>, RemoteError>>>,
+    timeout: Option<Duration>,
+}
+
+impl Message for Outgoing {
+    type Result = ();
+}
+
+/// Actor that serves tcp connection between two nodes
+struct Connection {
+    peer: SocketAddr,
+    node: Addr<Unsync, Node>,
+    format: String,
+    framed: FramedWrite<WriteHalf<TcpStream>, FrameCodec>,
+    next_id: u64,
+    pending: HashMap<u64, oneshot::Sender<Result<Vec<u8>, RemoteError>>>,
+}
+
+impl Connection {
+    /// Start connection actor, connecting side announces wire format
+    fn start(stream: TcpStream, peer: SocketAddr, node: Addr<Unsync, Node>,
+             format: Option<&'static str>) -> Addr<Syn, Connection>
+    {
+        Connection::create(move |ctx| {
+            let (r, w) = stream.split();
+            Connection::add_stream(FramedRead::new(r, FrameCodec), ctx);
+            let mut framed = FramedWrite::new(w, FrameCodec, ctx);
+            if let Some(format) = format {
+                framed.write(Frame::Hello{format: format.to_owned()});
+            }
+            Connection {
+                peer: peer,
+                node: node,
+                format: format.unwrap_or_else(Json::name).to_owned(),
+                framed: framed,
+                next_id: 0,
+                pending: HashMap::new(),
+            }
+        })
+    }
+}
+
+impl Actor for Connection {
+    type Context = Context<Self>;
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        debug!("Connection to remote node is closed: {}", self.peer);
+    }
+}
+
+impl WriteHandler<io::Error> for Connection {}
+
+impl Handler<Outgoing> for Connection {
+    type Result = ();
+
+    fn handle(&mut self, msg: Outgoing, ctx: &mut Context<Self>) {
+        let id = match msg.tx {
+            Some(tx) => {
+                self.next_id += 1;
+                let id = self.next_id;
+                self.pending.insert(id, tx);
+                if let Some(timeout) = msg.timeout {
+                    ctx.run_later(timeout, move |act, _| {
+                        if let Some(tx) = act.pending.remove(&id) {
+                            let _ = tx.send(Err(RemoteError::Timeout));
+                        }
+                    });
+                }
+                Some(id)
+            }
+            None => None,
+        };
+        self.framed.write(Frame::Request {
+            id: id,
+            name: msg.name,
+            message_type: msg.message_type.to_owned(),
+            body: msg.body,
+        });
+    }
+}
+
+impl StreamHandler<Frame, io::Error> for Connection {
+    fn handle(&mut self, frame: Frame, ctx: &mut Context<Self>) {
+        match frame {
+            Frame::Hello{format} => self.format = format,
+            Frame::Request{id, name, message_type, body} => {
+                self.node.send(Deliver {
+                    name: name,
+                    message_type: message_type,
+                    format: self.format.clone(),
+                    body: body,
+                    reply: id.is_some(),
+                })
+                    .into_actor(self)
+                    .then(move |res, act, _| {
+                        if let Some(id) = id {
+                            let result = match res {
+                                Ok(result) => result.and_then(|body| {
+                                    codec::check_response(&body).map(|_| body)
+                                }),
+                                Err(_) => Err(RemoteError::Delivery),
+                            };
+                            act.framed.write(Frame::Response{id: id, result: result});
+                            // writer could be already polled during this iteration
+                            task::current().notify();
+                        }
+                        actix::fut::ok(())
+                    })
+                    .spawn(ctx);
+            }
+            Frame::Response{id, result} => {
+                if let Some(tx) = self.pending.remove(&id) {
+                    let _ = tx.send(result);
+                }
+            }
+        }
+    }
+}
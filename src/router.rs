@@ -0,0 +1,334 @@
+//! Message routers
+//!
+//! [`Router`](struct.Router.html) is an actor that distributes incoming
+//! messages between set of actors (routees) and exposes single address for
+//! all of them. Message has to implement [`Routable`](trait.Routable.html)
+//! trait to be routed, response of the routee is passed back to the sender.
+//!
+//! Routing strategies:
+//!
+//! * [`RoundRobin`](struct.RoundRobin.html) delivers messages to routees in turn
+//! * [`ConsistentHash`](struct.ConsistentHash.html) delivers messages with same
+//!   [`HashKey`](trait.HashKey.html) to same routee
+//! * [`Broadcast`](struct.Broadcast.html) delivers copy of the message to
+//!   all routees
+//! * [`SmallestMailbox`](struct.SmallestMailbox.html) delivers messages to
+//!   routee with fewest pending messages
+//!
+//! [`pool()`](fn.pool.html) starts set of supervised routees, failed routee
+//! gets restarted by its supervisor. Routees could be added and removed at
+//! runtime with [`AddRoutee`](struct.AddRoutee.html) and
+//! [`RemoveRoutee`](struct.RemoveRoutee.html) messages.
+//!
+//! ## Example
+//!
+//! ```rust
+//! # extern crate actix;
+//! # extern crate futures;
+//! # use futures::Future;
+//! use actix::prelude::*;
+//! use actix::router::{self, Routable, Router};
+//!
+//! struct Job(usize);
+//!
+//! impl Message for Job {
+//!     type Result = usize;
+//! }
+//!
+//! impl Routable for Job {}
+//!
+//! struct Worker;
+//!
+//! impl Actor for Worker {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl actix::Supervised for Worker {}
+//!
+//! impl Handler<Job> for Worker {
+//!     type Result = MessageResult<Job>;
+//!
+//!     fn handle(&mut self, msg: Job, _: &mut Context<Self>) -> Self::Result {
+//!         MessageResult(msg.0 * 2)
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     Arbiter::handle().spawn_fn(|| {
+//!         // start router with 4 workers
+//!         let addr: Addr<Unsync, _> = Router::new(router::pool(4, |_| Worker)).start();
+//!
+//!         addr.send(Job(1)).then(|res| {
+//!             assert_eq!(res.unwrap(), 2);
+//! #           Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!             Ok(())
+//!         })
+//!     });
+//!
+//!     sys.run();
+//! }
+//! ```
+use std::collections::BTreeMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use actor::{Actor, Supervised};
+use address::{Addr, Syn, Request, ToEnvelope};
+use context::Context;
+use handler::{Handler, Message};
+use supervisor::Supervisor;
+
+/// Message that could be routed by `Router`
+pub trait Routable: Message + Send + 'static {}
+
+/// Message that could be routed by `ConsistentHash` router
+///
+/// ```rust
+/// # extern crate actix;
+/// # use std::hash::{Hash, Hasher};
+/// # use actix::prelude::*;
+/// use actix::router::{HashKey, Routable};
+///
+/// struct Login {
+///     user_id: u64,
+///     password: String,
+/// }
+///
+/// impl Message for Login {
+///     type Result = bool;
+/// }
+///
+/// impl Routable for Login {}
+///
+/// // all messages of the same user get delivered to the same routee
+/// impl HashKey for Login {
+///     fn hash_key<H: Hasher>(&self, state: &mut H) {
+///         self.user_id.hash(state)
+///     }
+/// }
+/// # fn main() {}
+/// ```
+pub trait HashKey: Routable {
+    /// Feed routing key of the message into the hasher
+    fn hash_key<H: Hasher>(&self, state: &mut H);
+}
+
+/// Start pool of `size` supervised actors
+///
+/// Each actor gets created with `f`, failed actor gets restarted by its supervisor.
+pub fn pool<A, F>(size: usize, f: F) -> Vec<Addr<Syn, A>>
+    where A: Supervised + Actor<Context=Context<A>>,
+          F: Fn(&mut Context<A>) -> A + Clone + 'static,
+{
+    (0..size).map(|_| {
+        let f = f.clone();
+        Supervisor::start(move |ctx| f(ctx))
+    }).collect()
+}
+
+/// Router actor
+///
+/// Router distributes incoming messages between routees according to
+/// routing strategy `S`. Stopped routees are skipped.
+pub struct Router<A: Actor, S = RoundRobin> {
+    routees: Vec<Addr<Syn, A>>,
+    ids: Vec<usize>,
+    next_id: usize,
+    generation: usize,
+    strategy: S,
+}
+
+impl<A: Actor> Router<A, RoundRobin> {
+    /// Create round robin router
+    pub fn new(routees: Vec<Addr<Syn, A>>) -> Router<A, RoundRobin> {
+        Router::with_strategy(routees, RoundRobin::default())
+    }
+}
+
+impl<A: Actor, S> Router<A, S> {
+    /// Create router with specific routing strategy
+    pub fn with_strategy(routees: Vec<Addr<Syn, A>>, strategy: S) -> Router<A, S> {
+        let ids = (0..routees.len()).collect();
+        Router{next_id: routees.len(),
+               routees,
+               ids,
+               generation: 0,
+               strategy}
+    }
+}
+
+impl<A: Actor, S: 'static> Actor for Router<A, S> {
+    type Context = Context<Self>;
+}
+
+/// Add routee to the router
+pub struct AddRoutee<A: Actor>(pub Addr<Syn, A>);
+
+impl<A: Actor> Message for AddRoutee<A> {
+    type Result = ();
+}
+
+impl<A: Actor, S: 'static> Handler<AddRoutee<A>> for Router<A, S> {
+    type Result = ();
+
+    fn handle(&mut self, msg: AddRoutee<A>, _: &mut Self::Context) {
+        self.routees.push(msg.0);
+        self.ids.push(self.next_id);
+        self.next_id += 1;
+        self.generation += 1;
+    }
+}
+
+/// Remove routee from the router
+///
+/// Removed routee keeps running.
+pub struct RemoveRoutee<A: Actor>(pub Addr<Syn, A>);
+
+impl<A: Actor> Message for RemoveRoutee<A> {
+    type Result = ();
+}
+
+impl<A: Actor, S: 'static> Handler<RemoveRoutee<A>> for Router<A, S> {
+    type Result = ();
+
+    fn handle(&mut self, msg: RemoveRoutee<A>, _: &mut Self::Context) {
+        if let Some(idx) = self.routees.iter().position(|r| r.tx() == msg.0.tx()) {
+            self.routees.remove(idx);
+            self.ids.remove(idx);
+            self.generation += 1;
+        }
+    }
+}
+
+/// Round robin routing strategy
+///
+/// Messages get delivered to routees in turn.
+#[derive(Default)]
+pub struct RoundRobin {
+    next: usize,
+}
+
+impl<A, M> Handler<M> for Router<A, RoundRobin>
+    where A: Handler<M>, A::Context: ToEnvelope<Syn, A, M>,
+          M: Routable, M::Result: Send,
+{
+    type Result = Request<Syn, A, M>;
+
+    fn handle(&mut self, msg: M, _: &mut Self::Context) -> Self::Result {
+        for _ in 0..self.routees.len() {
+            let idx = self.strategy.next % self.routees.len();
+            self.strategy.next = idx + 1;
+
+            if self.routees[idx].connected() {
+                return self.routees[idx].send(msg)
+            }
+        }
+        Request::new(None, None)
+    }
+}
+
+/// Number of points of each routee on the hash ring
+const HASH_RING_POINTS: usize = 64;
+
+/// Consistent hash routing strategy
+///
+/// Messages with same key get delivered to same routee. Routees are placed
+/// on a hash ring, so number of remapped keys is small if set of routees
+/// changes. Keys of stopped routee go to next routee on the ring.
+#[derive(Default)]
+pub struct ConsistentHash {
+    ring: BTreeMap<u64, usize>,
+    generation: Option<usize>,
+}
+
+impl ConsistentHash {
+    fn routee<M, F>(&mut self, msg: &M, ids: &[usize], generation: usize, connected: F)
+                    -> Option<usize>
+        where M: HashKey, F: Fn(usize) -> bool
+    {
+        // place routees on the ring
+        if self.generation != Some(generation) {
+            self.ring.clear();
+            for id in ids {
+                for point in 0..HASH_RING_POINTS {
+                    let mut hasher = DefaultHasher::new();
+                    (*id, point).hash(&mut hasher);
+                    self.ring.insert(hasher.finish(), *id);
+                }
+            }
+            self.generation = Some(generation);
+        }
+
+        let mut hasher = DefaultHasher::new();
+        msg.hash_key(&mut hasher);
+        let hash = hasher.finish();
+
+        self.ring.range(hash..).chain(self.ring.range(..hash))
+            .filter_map(|(_, id)| ids.iter().position(|item| item == id))
+            .find(|idx| connected(*idx))
+    }
+}
+
+impl<A, M> Handler<M> for Router<A, ConsistentHash>
+    where A: Handler<M>, A::Context: ToEnvelope<Syn, A, M>,
+          M: HashKey, M::Result: Send,
+{
+    type Result = Request<Syn, A, M>;
+
+    fn handle(&mut self, msg: M, _: &mut Self::Context) -> Self::Result {
+        let routees = &self.routees;
+        let routee = self.strategy.routee(
+            &msg, &self.ids, self.generation, |idx| routees[idx].connected());
+        match routee {
+            Some(idx) => routees[idx].send(msg),
+            None => Request::new(None, None),
+        }
+    }
+}
+
+/// Broadcast routing strategy
+///
+/// Copy of the message gets delivered to all routees. Message can not
+/// have response.
+#[derive(Default)]
+pub struct Broadcast;
+
+impl<A, M> Handler<M> for Router<A, Broadcast>
+    where A: Handler<M>, A::Context: ToEnvelope<Syn, A, M>,
+          M: Routable<Result=()> + Clone,
+{
+    type Result = ();
+
+    fn handle(&mut self, msg: M, _: &mut Self::Context) {
+        for routee in &self.routees {
+            routee.do_send(msg.clone());
+        }
+    }
+}
+
+/// Smallest mailbox routing strategy
+///
+/// Message gets delivered to routee with fewest pending messages in its
+/// mailbox, first routee wins tie.
+#[derive(Default)]
+pub struct SmallestMailbox;
+
+impl<A, M> Handler<M> for Router<A, SmallestMailbox>
+    where A: Handler<M>, A::Context: ToEnvelope<Syn, A, M>,
+          M: Routable, M::Result: Send,
+{
+    type Result = Request<Syn, A, M>;
+
+    fn handle(&mut self, msg: M, _: &mut Self::Context) -> Self::Result {
+        let routee = self.routees.iter()
+            .filter(|routee| routee.connected())
+            .min_by_key(|routee| routee.pending());
+
+        match routee {
+            Some(routee) => routee.send(msg),
+            None => Request::new(None, None),
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! Recurring schedules of actor timers
+//!
+//! `AsyncContext::run_schedule()` executes closure on every occurrence of
+//! a `Schedule`. Occurrences are computed from previous deadline rather than
+//! from the time closure was called, so periodic jobs do not drift.
+//! `AsyncContext::run_cron()` is a shortcut for cron schedules.
+//!
+//! Cron expression consists of six fields, all times are in UTC:
+//!
+//! ```text
+//! second minute hour day-of-month month day-of-week
+//! ```
+//!
+//! Every field is a comma separated list of `*`, single value `5`, range
+//! `1-5` and step `*/15`, `10-40/10` or `5/20`. Days of the week are
+//! numbered from `0` (Sunday) to `6`, `7` is Sunday as well. If both days
+//! of month and days of week are restricted, day matches if either of
+//! them matches.
+//!
+//! ```rust
+//! # extern crate actix;
+//! use actix::prelude::*;
+//! use actix::schedule::{Cron, Schedule};
+//!
+//! struct Reports;
+//!
+//! impl Actor for Reports {
+//!     type Context = Context<Self>;
+//!
+//!     fn started(&mut self, ctx: &mut Context<Self>) {
+//!         // every five minutes
+//!         ctx.run_cron("0 */5 * * * *", |_, _| println!("Five minutes report"));
+//!         // every day at 6:30
+//!         ctx.run_schedule(Schedule::daily(6, 30), |_, _| println!("Daily report"));
+//!         // on workdays at 9:00
+//!         let cron = Cron::parse("0 0 9 * * 1-5").unwrap();
+//!         ctx.run_schedule(cron.into(), |_, _| println!("Workday report"));
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+use std::cmp;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Cron schedules with no occurrence within this number of years
+/// are considered empty
+const SEARCH_YEARS: u64 = 5;
+
+const DAY: u64 = 86_400;
+
+/// Cron expression parsing error
+#[derive(Fail, Debug)]
+pub enum CronError {
+    /// Expression does not have six fields
+    #[fail(display="Expected 6 fields, got {}", _0)]
+    FieldCount(usize),
+    /// Field is malformed or out of range
+    #[fail(display="Invalid field: {}", _0)]
+    InvalidField(String),
+}
+
+/// Parsed cron expression
+#[derive(Clone, PartialEq, Debug)]
+pub struct Cron {
+    seconds: u64,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    /// Parse six field cron expression
+    pub fn parse(expr: &str) -> Result<Cron, CronError> {
+        let fields: Vec<_> = expr.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(CronError::FieldCount(fields.len()))
+        }
+        let (seconds, _) = field(fields[0], 0, 59)?;
+        let (minutes, _) = field(fields[1], 0, 59)?;
+        let (hours, _) = field(fields[2], 0, 23)?;
+        let (days, any_day) = field(fields[3], 1, 31)?;
+        let (months, _) = field(fields[4], 1, 12)?;
+        let (mut weekdays, any_weekday) = field(fields[5], 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Cron {
+            seconds,
+            minutes,
+            hours,
+            days,
+            months,
+            weekdays,
+            any_day,
+            any_weekday,
+        })
+    }
+
+    /// First occurrence strictly after `time`
+    ///
+    /// Returns `None` if expression has no occurrences in the next few
+    /// years, e.g. `0 0 0 30 2 *`.
+    pub fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+        let start = match time.duration_since(UNIX_EPOCH) {
+            Ok(dur) => dur.as_secs() + 1,
+            Err(_) => 0,
+        };
+        let limit = start + SEARCH_YEARS * 366 * DAY;
+
+        let mut t = start;
+        while t < limit {
+            let days = t / DAY;
+            let secs = t % DAY;
+            let (year, month, day) = civil_from_days(days);
+            if !matches(self.months, month) {
+                t = if month == 12 {
+                    days_from_civil(year + 1, 1, 1)
+                } else {
+                    days_from_civil(year, month + 1, 1)
+                } * DAY;
+                continue
+            }
+            if !self.day_matches(day, (days + 4) % 7) {
+                t = (days + 1) * DAY;
+                continue
+            }
+            let hour = secs / 3600;
+            if !matches(self.hours, hour) {
+                t = days * DAY + (hour + 1) * 3600;
+                continue
+            }
+            let minute = secs % 3600 / 60;
+            if !matches(self.minutes, minute) {
+                t = days * DAY + hour * 3600 + (minute + 1) * 60;
+                continue
+            }
+            if !matches(self.seconds, secs % 60) {
+                t += 1;
+                continue
+            }
+            return Some(UNIX_EPOCH + Duration::from_secs(t))
+        }
+        None
+    }
+
+    fn day_matches(&self, day: u64, weekday: u64) -> bool {
+        let day = matches(self.days, day);
+        let weekday = matches(self.weekdays, weekday);
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        }
+    }
+}
+
+impl FromStr for Cron {
+    type Err = CronError;
+
+    fn from_str(s: &str) -> Result<Cron, CronError> {
+        Cron::parse(s)
+    }
+}
+
+/// Recurring schedule
+#[derive(Clone, PartialEq, Debug)]
+pub enum Schedule {
+    /// Fixed period, first occurrence is one period after timer is started
+    Interval(Duration),
+    /// Cron expression
+    Cron(Cron),
+}
+
+impl Schedule {
+    /// Every day at `hour:minute` UTC
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hour` or `minute` is out of range.
+    pub fn daily(hour: u32, minute: u32) -> Schedule {
+        let cron = Cron::parse(&format!("0 {} {} * * *", minute, hour))
+            .expect("Invalid time of day");
+        Schedule::Cron(cron)
+    }
+
+    /// Every week on `weekday` (`0` is Sunday) at `hour:minute` UTC
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weekday`, `hour` or `minute` is out of range.
+    pub fn weekly(weekday: u32, hour: u32, minute: u32) -> Schedule {
+        let cron = Cron::parse(&format!("0 {} {} * * {}", minute, hour, weekday))
+            .expect("Invalid day of week or time of day");
+        Schedule::Cron(cron)
+    }
+
+    /// Next occurrence after `prev` occurrence
+    ///
+    /// Occurrences that are already missed at `now` are skipped.
+    pub fn next(&self, prev: SystemTime, now: SystemTime) -> Option<SystemTime> {
+        match *self {
+            Schedule::Interval(dur) => {
+                let next = prev + dur;
+                if next > now {
+                    return Some(next)
+                }
+                let step = nanos(dur);
+                if step == 0 {
+                    return Some(now)
+                }
+                let behind = nanos(now.duration_since(next).unwrap_or_else(|_| Duration::new(0, 0)));
+                let skip = step * (behind / step + 1);
+                Some(next + Duration::new(skip / 1_000_000_000, (skip % 1_000_000_000) as u32))
+            }
+            Schedule::Cron(ref cron) => cron.next_after(cmp::max(prev, now)),
+        }
+    }
+}
+
+impl From<Cron> for Schedule {
+    fn from(cron: Cron) -> Schedule {
+        Schedule::Cron(cron)
+    }
+}
+
+impl From<Duration> for Schedule {
+    fn from(dur: Duration) -> Schedule {
+        Schedule::Interval(dur)
+    }
+}
+
+fn nanos(dur: Duration) -> u64 {
+    dur.as_secs() * 1_000_000_000 + u64::from(dur.subsec_nanos())
+}
+
+fn matches(mask: u64, value: u64) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Parse field into bit mask, second value is true if field starts with `*`
+fn field(s: &str, min: u64, max: u64) -> Result<(u64, bool), CronError> {
+    let invalid = || CronError::InvalidField(s.to_owned());
+    let mut mask = 0;
+    for part in s.split(',') {
+        let mut parts = part.splitn(2, '/');
+        let range = parts.next().unwrap();
+        let step = match parts.next() {
+            Some(step) => step.parse::<u64>().map_err(|_| invalid())?,
+            None => 1,
+        };
+        if step == 0 || step > max {
+            return Err(invalid())
+        }
+        let (from, to) = if range == "*" {
+            (min, max)
+        } else {
+            let mut bounds = range.splitn(2, '-');
+            let from = bounds.next().unwrap().parse::<u64>().map_err(|_| invalid())?;
+            match bounds.next() {
+                Some(to) => (from, to.parse::<u64>().map_err(|_| invalid())?),
+                // `5/20` starts at 5 and runs to the end of the range
+                None if step > 1 || part.contains('/') => (from, max),
+                None => (from, from),
+            }
+        };
+        if from < min || to > max || from > to {
+            return Err(invalid())
+        }
+        let mut value = from;
+        while value <= to {
+            mask |= 1 << value;
+            value += step;
+        }
+    }
+    Ok((mask, s.starts_with('*')))
+}
+
+/// Year, month and day of `days` since unix epoch
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// Days since unix epoch of the date
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
@@ -0,0 +1,234 @@
+//! Entity sharding
+//!
+//! [`Shard`](struct.Shard.html) is an actor that manages set of entity
+//! actors, one actor per entity id. Message has to implement
+//! [`EntityMessage`](trait.EntityMessage.html) trait, shard delivers
+//! message to the actor of message's entity and passes response back to the
+//! sender. Entity actor gets created with shard's factory on the first
+//! message of the entity, entity actors are supervised.
+//!
+//! Shard with [`idle_timeout()`](struct.Shard.html#method.idle_timeout)
+//! passivates entities that do not receive messages: shard sends entity
+//! [`Passivate`](../msgs/struct.Passivate.html) message, entity usually
+//! stops itself with `ctx.passivate()`. Message that arrives before
+//! passivated actor stops is delivered to that actor, its supervisor restarts
+//! it. Once passivated actor stops, next message of the entity creates new
+//! actor, so only one actor runs for each entity id.
+//!
+//! ## Example
+//!
+//! ```rust
+//! # extern crate actix;
+//! # extern crate futures;
+//! # use std::time::Duration;
+//! # use futures::Future;
+//! use actix::prelude::*;
+//! use actix::msgs::Passivate;
+//! use actix::shard::{EntityMessage, Shard};
+//!
+//! struct Visit {
+//!     user_id: u64,
+//! }
+//!
+//! impl Message for Visit {
+//!     type Result = usize;
+//! }
+//!
+//! impl EntityMessage for Visit {
+//!     type Id = u64;
+//!
+//!     fn entity_id(&self) -> u64 {
+//!         self.user_id
+//!     }
+//! }
+//!
+//! struct Session {
+//!     visits: usize,
+//! }
+//!
+//! impl Actor for Session {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl actix::Supervised for Session {}
+//!
+//! impl Handler<Visit> for Session {
+//!     type Result = usize;
+//!
+//!     fn handle(&mut self, _: Visit, _: &mut Context<Self>) -> usize {
+//!         self.visits += 1;
+//!         self.visits
+//!     }
+//! }
+//!
+//! impl Handler<Passivate> for Session {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, _: Passivate, ctx: &mut Context<Self>) {
+//!         ctx.passivate();
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     Arbiter::handle().spawn_fn(|| {
+//!         // sessions are stopped after one minute without visits
+//!         let addr: Addr<Unsync, _> = Shard::new(|_: &u64, _| Session{visits: 0})
+//!             .idle_timeout(Duration::from_secs(60))
+//!             .start();
+//!
+//!         addr.send(Visit{user_id: 1}).then(|res| {
+//!             assert_eq!(res.unwrap(), 1);
+//! #           Arbiter::system().do_send(actix::msgs::SystemExit(0));
+//!             Ok(())
+//!         })
+//!     });
+//!
+//!     sys.run();
+//! }
+//! ```
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use actor::{Actor, AsyncContext, Supervised};
+use address::{Addr, Syn, Request, ToEnvelope, WeakAddr};
+use clock;
+use context::Context;
+use handler::{Handler, Message};
+use msgs::Passivate;
+use supervisor::Supervisor;
+
+/// Message that could be delivered to entity by `Shard`
+pub trait EntityMessage: Message + Send + 'static {
+    /// Type of entity id
+    type Id: Hash + Eq + Clone + 'static;
+
+    /// Id of the entity that handles the message
+    fn entity_id(&self) -> Self::Id;
+}
+
+/// Creates entity actor for entity id
+type Factory<K, A> = Rc<Fn(&K, &mut <A as Actor>::Context) -> A>;
+
+/// Passivates idle entity actor
+type PassivateFn<A> = fn(&Addr<Syn, A>);
+
+/// Entity actor of the shard
+struct Entity<A: Actor> {
+    addr: Addr<Syn, A>,
+    /// Time of the last message
+    last: Instant,
+}
+
+/// Shard actor
+///
+/// Shard routes messages to entity actors by entity id of type `K`, entity
+/// actors are started in shard's arbiter.
+pub struct Shard<A: Actor, K> {
+    entities: HashMap<K, Entity<A>>,
+    /// Passivated entities, actor is reused until it stops
+    passivating: HashMap<K, WeakAddr<Syn, A>>,
+    factory: Factory<K, A>,
+    passivation: Option<(Duration, PassivateFn<A>)>,
+}
+
+impl<A, K> Shard<A, K>
+    where A: Supervised + Actor<Context=Context<A>>,
+          K: Hash + Eq + Clone + 'static,
+{
+    /// Create shard, entity actors get created with `f`
+    pub fn new<F>(f: F) -> Shard<A, K>
+        where F: Fn(&K, &mut Context<A>) -> A + 'static
+    {
+        Shard{entities: HashMap::new(), passivating: HashMap::new(),
+              factory: Rc::new(f), passivation: None}
+    }
+
+    /// Passivate entities that do not receive messages for `timeout`
+    ///
+    /// Idle entities are checked every `timeout`, so entity gets passivated
+    /// after idle time between `timeout` and twice the `timeout`. Entity
+    /// with pending messages is not passivated.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self
+        where A: Handler<Passivate>, A::Context: ToEnvelope<Syn, A, Passivate>
+    {
+        self.passivation = Some((timeout, passivate::<A>));
+        self
+    }
+
+    /// Address of the entity actor, actor is started if entity is not active
+    fn entity(&mut self, id: K) -> &mut Entity<A> {
+        let active = self.entities.get(&id).map(|entity| entity.addr.connected());
+        if active != Some(true) {
+            let passivating = self.passivating.remove(&id).and_then(|addr| addr.upgrade());
+            let addr = match passivating {
+                Some(ref addr) if addr.connected() => addr.clone(),
+                _ => {
+                    let factory = Rc::clone(&self.factory);
+                    let key = id.clone();
+                    Supervisor::start(move |ctx| factory(&key, ctx))
+                }
+            };
+            self.entities.insert(id.clone(), Entity{addr, last: clock::now()});
+        }
+        self.entities.get_mut(&id).unwrap()
+    }
+
+    /// Passivate idle entities, forget stopped entities
+    fn passivate_idle(&mut self, timeout: Duration, passivate: PassivateFn<A>) {
+        let now = clock::now();
+        let idle: Vec<K> = self.entities.iter()
+            .filter(|&(_, entity)| {
+                !entity.addr.connected() ||
+                    (entity.addr.pending() == 0 && now.duration_since(entity.last) >= timeout)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in idle {
+            if let Some(entity) = self.entities.remove(&id) {
+                if entity.addr.connected() {
+                    passivate(&entity.addr);
+                    self.passivating.insert(id, entity.addr.downgrade());
+                }
+            }
+        }
+        self.passivating.retain(|_, addr| addr.upgrade().is_some());
+    }
+}
+
+fn passivate<A>(addr: &Addr<Syn, A>)
+    where A: Handler<Passivate>, A::Context: ToEnvelope<Syn, A, Passivate>
+{
+    addr.do_send(Passivate);
+}
+
+impl<A, K> Actor for Shard<A, K>
+    where A: Supervised + Actor<Context=Context<A>>,
+          K: Hash + Eq + Clone + 'static,
+{
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        if let Some((timeout, passivate)) = self.passivation {
+            ctx.run_interval(timeout, move |act, _| act.passivate_idle(timeout, passivate));
+        }
+    }
+}
+
+impl<A, K, M> Handler<M> for Shard<A, K>
+    where A: Supervised + Actor<Context=Context<A>> + Handler<M>,
+          K: Hash + Eq + Clone + 'static,
+          M: EntityMessage<Id=K>, M::Result: Send,
+{
+    type Result = Request<Syn, A, M>;
+
+    fn handle(&mut self, msg: M, _: &mut Self::Context) -> Self::Result {
+        let entity = self.entity(msg.entity_id());
+        entity.last = clock::now();
+        entity.addr.send(msg)
+    }
+}
@@ -0,0 +1,173 @@
+//! Message handling spans
+//!
+//! With `spans` feature enabled, handling of every message is logged with
+//! `trace` level under `actix::span` target, span records actor type,
+//! message type and correlation id. Message sent from a handler inherits
+//! correlation id of the handled message, so chain of messages started by
+//! single request could be followed across actors and threads. Lifecycle
+//! events of actors are logged under `actix::lifecycle` target.
+//!
+//! Fields are logged as `key=value` pairs. Type names require Rust 1.38
+//! or later.
+//!
+//! With `tracing` feature as well, handling of every message is also entered
+//! as `tracing` span named `handle` with `actor`, `message` and `id` fields,
+//! lifecycle events are reported as `tracing` events. `tracing` requires
+//! Rust 1.65 or later. Without `spans` feature correlation id is empty.
+#[cfg(feature="spans")]
+use std::any::type_name;
+#[cfg(feature="spans")]
+use std::cell::Cell;
+#[cfg(feature="spans")]
+use std::time::Instant;
+#[cfg(feature="spans")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use actor::Actor;
+use supervisor::RestartReason;
+
+#[cfg(feature="spans")]
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature="spans")]
+thread_local!(static CURRENT: Cell<Option<usize>> = Cell::new(None));
+
+/// Correlation id of the message
+#[cfg(feature="spans")]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct CorrelationId(usize);
+
+/// Correlation id of the message, ids are not tracked
+#[cfg(not(feature="spans"))]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct CorrelationId;
+
+impl CorrelationId {
+    /// Id of the message that is currently handled or new id
+    #[cfg(feature="spans")]
+    pub fn current() -> CorrelationId {
+        CorrelationId(CURRENT.with(|cell| cell.get())
+                      .unwrap_or_else(|| NEXT_ID.fetch_add(1, Ordering::Relaxed) + 1))
+    }
+
+    #[cfg(not(feature="spans"))]
+    #[inline]
+    pub fn current() -> CorrelationId {
+        CorrelationId
+    }
+}
+
+/// Span of message handling, span ends when it is dropped
+#[cfg(feature="spans")]
+pub(crate) struct Span {
+    actor: &'static str,
+    message: &'static str,
+    id: usize,
+    parent: Option<usize>,
+    start: Instant,
+    #[cfg(feature="tracing")]
+    _entered: tracing::span::EnteredSpan,
+}
+
+#[cfg(feature="spans")]
+impl Span {
+    pub fn enter<A: 'static, M: 'static>(id: CorrelationId) -> Span {
+        let (actor, message) = (type_name::<A>(), type_name::<M>());
+        trace!(target: "actix::span", "enter actor={} message={} id={}", actor, message, id.0);
+        Span {
+            actor: actor,
+            message: message,
+            id: id.0,
+            parent: CURRENT.with(|cell| cell.replace(Some(id.0))),
+            start: Instant::now(),
+            #[cfg(feature="tracing")]
+            _entered: tracing::trace_span!(
+                target: "actix::span", "handle", actor = actor, message = message, id = id.0)
+                .entered(),
+        }
+    }
+}
+
+#[cfg(feature="spans")]
+impl Drop for Span {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        trace!(target: "actix::span", "exit actor={} message={} id={} elapsed_us={}",
+               self.actor, self.message, self.id,
+               elapsed.as_secs() * 1_000_000 + u64::from(elapsed.subsec_nanos() / 1000));
+        CURRENT.with(|cell| cell.set(self.parent));
+    }
+}
+
+#[cfg(not(feature="spans"))]
+pub(crate) struct Span;
+
+#[cfg(not(feature="spans"))]
+impl Span {
+    #[inline]
+    pub fn enter<A: 'static, M: 'static>(_: CorrelationId) -> Span {
+        Span
+    }
+}
+
+/// Type name of `T`, requires `spans` feature
+#[cfg(feature="spans")]
+pub(crate) fn type_name_of<T: ?Sized>() -> Option<&'static str> {
+    Some(type_name::<T>())
+}
+
+#[cfg(not(feature="spans"))]
+#[inline]
+pub(crate) fn type_name_of<T: ?Sized>() -> Option<&'static str> {
+    None
+}
+
+#[cfg(feature="spans")]
+fn arbiter() -> String {
+    ::arbiter::Arbiter::try_name().unwrap_or_else(|| "-".to_owned())
+}
+
+/// Log start of actor `A`
+#[cfg(feature="spans")]
+pub(crate) fn actor_started<A: Actor>() {
+    let arbiter = arbiter();
+    info!(target: "actix::lifecycle", "started actor={} arbiter={}",
+          type_name::<A>(), arbiter);
+    #[cfg(feature="tracing")]
+    tracing::info!(target: "actix::lifecycle", actor = type_name::<A>(), arbiter = %arbiter,
+                   "started");
+}
+
+/// Log stop of actor `A`
+#[cfg(feature="spans")]
+pub(crate) fn actor_stopped<A: Actor>() {
+    let arbiter = arbiter();
+    info!(target: "actix::lifecycle", "stopped actor={} arbiter={}",
+          type_name::<A>(), arbiter);
+    #[cfg(feature="tracing")]
+    tracing::info!(target: "actix::lifecycle", actor = type_name::<A>(), arbiter = %arbiter,
+                   "stopped");
+}
+
+/// Log restart of actor `A`
+#[cfg(feature="spans")]
+pub(crate) fn actor_restarted<A: Actor>(reason: &RestartReason) {
+    let arbiter = arbiter();
+    warn!(target: "actix::lifecycle", "restarted actor={} arbiter={} reason={:?}",
+          type_name::<A>(), arbiter, reason);
+    #[cfg(feature="tracing")]
+    tracing::warn!(target: "actix::lifecycle", actor = type_name::<A>(), arbiter = %arbiter,
+                   reason = ?reason, "restarted");
+}
+
+#[cfg(not(feature="spans"))]
+#[inline]
+pub(crate) fn actor_started<A: Actor>() {}
+
+#[cfg(not(feature="spans"))]
+#[inline]
+pub(crate) fn actor_stopped<A: Actor>() {}
+
+#[cfg(not(feature="spans"))]
+#[inline]
+pub(crate) fn actor_restarted<A: Actor>(_: &RestartReason) {}
@@ -8,17 +8,18 @@ use actor::{Actor, ActorState, ActorContext, AsyncContext, Running, SpawnHandle}
 #[allow(unused_variables)]
 pub trait StreamHandler<I, E> where Self: Actor
 {
-    /// Method is called for every message received by this Actor
+    /// Method is called for every item of the stream
     fn handle(&mut self, item: I, ctx: &mut Self::Context);
 
-    /// Method is called when stream get polled first time.
+    /// Method is called when stream get polled first time,
+    /// before any item of the stream is handled.
     fn started(&mut self, ctx: &mut Self::Context) {}
 
     /// Method is called when stream emits error.
     ///
-    /// If this method returns `ErrorAction::Continue` stream processing continues
-    /// otherwise stream processing stops. Default method
-    /// implementation returns `ErrorAction::Stop`
+    /// If this method returns `Running::Continue` stream processing continues
+    /// otherwise stream processing stops and `finished()` get called.
+    /// Default method implementation returns `Running::Stop`
     fn error(&mut self, err: E, ctx: &mut Self::Context) -> Running {
         Running::Stop
     }
@@ -30,12 +31,12 @@ pub trait StreamHandler<I, E> where Self: Actor
         ctx.stop()
     }
 
-    /// This method is similar to `add_future` but works with streams.
+    /// Register stream in actor's context. Each item of the stream
+    /// is passed to `handle()` method. Returns handle of the stream,
+    /// could be used for cancelling stream processing.
     ///
     /// Information to consider. Actor wont receive next item from a stream
-    /// until `Response` future resolves to a result. `Self::reply` resolves immediately.
-    ///
-    /// This method is similar to `add_stream` but it skips result error.
+    /// while context is waiting for future completion.
     ///
     /// ```rust
     /// # #[macro_use] extern crate actix;
@@ -119,6 +120,11 @@ impl<A, M, E, S> ActorFuture for ActorStream<A, M, E, S>
         }
         
         loop {
+            // let actor process pending messages first
+            if ctx.stream_paused() {
+                return Ok(Async::NotReady)
+            }
+
             match self.stream.poll() {
                 Ok(Async::Ready(Some(msg))) => {
                     A::handle(act, msg, ctx);
@@ -128,6 +134,7 @@ impl<A, M, E, S> ActorFuture for ActorStream<A, M, E, S>
                 }
                 Err(err) => {
                     if A::error(act, err, ctx) == Running::Stop {
+                        ctx.stream_error();
                         A::finished(act, ctx);
                         return Ok(Async::Ready(()))
                     }
@@ -1,17 +1,98 @@
+//! Actor supervision
+//!
+//! [`Supervisor`](struct.Supervisor.html) restarts failed actors. On each failure
+//! supervisor consults [`RestartPolicy`](trait.RestartPolicy.html), policy decides if
+//! actor should be restarted, stopped or if failure should be escalated.
+//!
+//! Default policies:
+//!
+//! * [`AlwaysRestart`](struct.AlwaysRestart.html) restarts actor indefinitely
+//! * [`BoundedRestart`](struct.BoundedRestart.html) limits number of restarts
+//!   within specific period of time
+//! * [`BackoffRestart`](struct.BackoffRestart.html) restarts actor indefinitely,
+//!   with growing delay between restarts
+//!
+//! ## Supervision trees
+//!
+//! Supervisor could be started as a child of other actor with
+//! [`Supervisor::start_child()`](struct.Supervisor.html#method.start_child).
+//! Child supervisor runs within parent's context, if parent actor restarts
+//! or stops, all its children get stopped as well. If child's policy escalates
+//! failure, parent actor gets stopped, so whole subtree gets torn down and parent's
+//! supervisor decides what to do next.
+//!
+//! ## Failure reason
+//!
+//! Supervisor catches panics of supervised actor. Reason of the failure is passed
+//! to restart policy and to `Supervised::restarting_with()` method.
+//!
+//! ## Custom contexts
+//!
+//! Supervisor works with any execution context that implements
+//! [`SupervisedContext`](trait.SupervisedContext.html) trait,
+//! `Context<A>` implements it.
+//!
+//! ## Pending messages
+//!
+//! By default messages pending in mailbox of failed actor are preserved and
+//! processed by restarted actor. Use
+//! [`RestartPolicy::with_retention()`](trait.RestartPolicy.html#method.with_retention)
+//! to drop pending messages on restart, dropped messages could be reported to
+//! dead letters recipient.
+//!
+//! ## Pending timers
+//!
+//! By default all futures of failed actor, including delayed notifications
+//! scheduled with `notify_later()`, are dropped on restart. Use
+//! [`RestartPolicy::with_timers()`](trait.RestartPolicy.html#method.with_timers)
+//! to re-arm pending notifications in restarted context, they keep original
+//! deadlines and spawn handles.
+//!
+//! ## Events
+//!
+//! Supervisor lifecycle events could be delivered to any recipient, use
+//! [`RestartPolicy::subscribe()`](trait.RestartPolicy.html#method.subscribe)
+//! to subscribe recipient to policy's events.
+//!
+//! [`SupervisorMonitor`](struct.SupervisorMonitor.html) actor collects
+//! supervisor events and answers [`SupervisorStats`](struct.SupervisorStats.html)
+//! queries, it could be used for health checks.
+//!
+//! ## One-for-all
+//!
+//! [`GroupSupervisor`](struct.GroupSupervisor.html) supervises set of actors as
+//! a group. If any actor fails, all actors in the group get stopped and restarted
+//! together.
+use std::any::Any;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
 use futures::{Future, Async, Poll};
+use clock::Delay;
+use uuid::Uuid;
 
-use actor::{Actor, Supervised};
+use actor::{Actor, ActorContext, ActorState, AsyncContext, Supervised};
 use arbiter::Arbiter;
-use address::{sync_channel, ActorAddress, Addr, Syn};
+use events;
+use address::{sync_channel, ActorAddress, Addr, Syn, Recipient};
 use context::Context;
+use deadletter::{self, DeadLetter, DeadLetterReason, DroppedMessage};
+use fut::ActorFuture;
+use handler::{Handler, Message, MessageResult};
 use mailbox::DEFAULT_CAPACITY;
-use msgs::Execute;
+use msgs::{Execute, SystemExit};
+use path::{self, ActorPath};
+
+/// Creates actor instance for supervisor
+type Factory<A> = Box<FnMut(&mut <A as Actor>::Context) -> A>;
 
 /// Actor supervisor
 ///
 /// Supervisor manages incoming message for actor. In case of actor failure, supervisor
-/// creates new execution context and restarts actor lifecycle. Supervisor does not
-/// does not re-create actor, it just calls `restarting()` method.
+/// creates new execution context and restarts actor lifecycle. By default supervisor
+/// does not re-create actor, it just calls `restarting()` method. Supervisor started
+/// with [`start_fresh()`](#method.start_fresh) creates new actor instance on each restart.
 ///
 /// Supervisor has same lifecycle as actor. In situation when all addresses to supervisor
 /// get dropped and actor does not execute anything, supervisor terminates.
@@ -59,11 +140,22 @@ use msgs::Execute;
 ///     sys.run();
 /// }
 /// ```
-pub struct Supervisor<A> where A: Supervised + Actor<Context=Context<A>> {
-    ctx: A::Context
+pub struct Supervisor<A> where A: Supervised + Actor, A::Context: SupervisedContext<A> {
+    ctx: A::Context,
+    policy: Box<RestartPolicy>,
+    delay: Option<(Delay, RestartReason)>,
+    restarts: usize,
+    factory: Option<Factory<A>>,
+    /// Creates lazily started actor on first message, re-creates it after
+    /// passivation
+    lazy: Option<Factory<A>>,
+    /// Lazily started actor is created
+    created: bool,
+    /// Actor is passivated, it is restarted or re-created on next message
+    passivated: bool,
 }
 
-impl<A> Supervisor<A> where A: Supervised + Actor<Context=Context<A>>
+impl<A> Supervisor<A> where A: Supervised + Actor, A::Context: SupervisedContext<A>
 {
     /// Start new supervised actor in current Arbiter.
     ///
@@ -91,20 +183,329 @@ impl<A> Supervisor<A> where A: Supervised + Actor<Context=Context<A>>
     /// ```
     pub fn start<Addr, F>(f: F) -> Addr
         where F: FnOnce(&mut A::Context) -> A + 'static,
-              A: Actor<Context=Context<A>> + ActorAddress<A, Addr>
+              A: ActorAddress<A, Addr>
+    {
+        Supervisor::start_with(AlwaysRestart, f)
+    }
+
+    /// Start new supervised actor in current Arbiter with specific restart policy.
+    ///
+    /// If policy decides to stop actor, supervisor gives up, stops actor
+    /// and drops its mailbox.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate actix;
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// use actix::supervisor::BoundedRestart;
+    ///
+    /// struct MyActor;
+    ///
+    /// impl Actor for MyActor {
+    ///    type Context = Context<Self>;
+    /// }
+    ///
+    /// # impl actix::Supervised for MyActor {}
+    /// # fn main() {
+    /// #    let sys = System::new("test");
+    /// // Restart actor at most 3 times within 10 seconds, exit system after that
+    /// let addr: Addr<Unsync, _> = actix::Supervisor::start_with(
+    ///     BoundedRestart::new(3, Duration::from_secs(10)).exit_system(1),
+    ///     |_| MyActor);
+    /// # }
+    /// ```
+    pub fn start_with<Addr, P, F>(policy: P, f: F) -> Addr
+        where F: FnOnce(&mut A::Context) -> A + 'static,
+              P: RestartPolicy,
+              A: ActorAddress<A, Addr>
+    {
+        // create actor
+        let mut ctx = A::Context::create();
+        let act = f(&mut ctx);
+        let addr =  <A as ActorAddress<A, Addr>>::get(&mut ctx);
+        ctx.set_actor(act);
+
+        // create supervisor
+        Arbiter::handle().spawn(Supervisor::<A>::new(ctx, Box::new(policy)));
+
+        addr
+    }
+
+    /// Start new supervised actor in current Arbiter, actor gets re-created on each restart.
+    ///
+    /// Supervisor keeps factory closure and calls it for each restart, so restarted
+    /// actor never sees state of failed instance. `restarting()` method is called
+    /// on newly created instance.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate actix;
+    /// # use actix::prelude::*;
+    /// use actix::supervisor::AlwaysRestart;
+    ///
+    /// struct MyActor {
+    ///     buffer: Vec<u8>,
+    /// }
+    ///
+    /// impl Actor for MyActor {
+    ///    type Context = Context<Self>;
+    /// }
+    ///
+    /// # impl actix::Supervised for MyActor {}
+    /// # fn main() {
+    /// #    let sys = System::new("test");
+    /// let addr: Addr<Unsync, _> = actix::Supervisor::start_fresh(
+    ///     AlwaysRestart, |_| MyActor{buffer: Vec::new()});
+    /// # }
+    /// ```
+    pub fn start_fresh<Addr, P, F>(policy: P, mut f: F) -> Addr
+        where F: FnMut(&mut A::Context) -> A + 'static,
+              P: RestartPolicy,
+              A: ActorAddress<A, Addr>
     {
         // create actor
-        let mut ctx = Context::new(None);
+        let mut ctx = A::Context::create();
         let act = f(&mut ctx);
         let addr =  <A as ActorAddress<A, Addr>>::get(&mut ctx);
         ctx.set_actor(act);
 
         // create supervisor
-        Arbiter::handle().spawn(Supervisor::<A>{ctx: ctx});
+        let mut sup = Supervisor::<A>::new(ctx, Box::new(policy));
+        sup.factory = Some(Box::new(f));
+        Arbiter::handle().spawn(sup);
+
+        addr
+    }
+
+    /// Start new supervised actor in current Arbiter, actor is created once
+    /// it receives first message.
+    ///
+    /// Expensive actors, e.g. connection pools, are not created until they
+    /// are needed. If all addresses of the actor are dropped before first
+    /// message, actor is never created. `policy` decides on restarts of
+    /// created actor. Passivated actor is dropped and re-created with `f` on
+    /// next message.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate actix;
+    /// # use actix::prelude::*;
+    /// use actix::supervisor::AlwaysRestart;
+    ///
+    /// struct DbPool;
+    ///
+    /// impl Actor for DbPool {
+    ///    type Context = Context<Self>;
+    /// }
+    ///
+    /// # impl actix::Supervised for DbPool {}
+    /// # fn main() {
+    /// #    let sys = System::new("test");
+    /// let addr: Addr<Syn, _> = actix::Supervisor::start_lazy(AlwaysRestart, |_| {
+    ///     println!("connecting");
+    ///     DbPool
+    /// });
+    /// # }
+    /// ```
+    pub fn start_lazy<Addr, P, F>(policy: P, f: F) -> Addr
+        where F: FnMut(&mut A::Context) -> A + 'static,
+              P: RestartPolicy,
+              A: ActorAddress<A, Addr>
+    {
+        let mut ctx = A::Context::create();
+        let addr =  <A as ActorAddress<A, Addr>>::get(&mut ctx);
+
+        let mut sup = Supervisor::<A>::new(ctx, Box::new(policy));
+        sup.lazy = Some(Box::new(f));
+        Arbiter::handle().spawn(sup);
+
+        addr
+    }
+
+    /// Start new supervised actor as a child of other actor.
+    ///
+    /// Child supervisor runs in parent's context. Child actor gets stopped if
+    /// parent actor stops or restarts. If `policy` escalates child's failure,
+    /// parent actor gets stopped.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate actix;
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// use actix::supervisor::BoundedRestart;
+    ///
+    /// struct Child;
+    ///
+    /// impl Actor for Child {
+    ///    type Context = Context<Self>;
+    /// }
+    ///
+    /// impl actix::Supervised for Child {}
+    ///
+    /// struct Parent {
+    ///    child: Option<Addr<Unsync, Child>>,
+    /// }
+    ///
+    /// impl Actor for Parent {
+    ///    type Context = Context<Self>;
+    ///
+    ///    fn started(&mut self, ctx: &mut Context<Self>) {
+    ///        // (re)start child, escalate failure if child fails more than 3 times
+    ///        self.child = Some(actix::Supervisor::start_child(
+    ///            ctx, BoundedRestart::new(3, Duration::from_secs(10)), |_| Child));
+    ///    }
+    /// }
+    ///
+    /// impl actix::Supervised for Parent {}
+    /// # fn main() {
+    /// #    let sys = System::new("test");
+    /// let addr: Addr<Unsync, _> = actix::Supervisor::start(|_| Parent{child: None});
+    /// # }
+    /// ```
+    pub fn start_child<B, Addr, P, F>(parent: &mut Context<B>, policy: P, f: F) -> Addr
+        where B: Actor<Context=Context<B>>,
+              F: FnOnce(&mut A::Context) -> A + 'static,
+              P: RestartPolicy,
+              A: ActorAddress<A, Addr>
+    {
+        // create actor
+        let mut ctx = A::Context::create();
+        let act = f(&mut ctx);
+        let addr =  <A as ActorAddress<A, Addr>>::get(&mut ctx);
+        ctx.set_actor(act);
+
+        // run supervisor within parent's context
+        parent.spawn(ChildSupervisor{
+            sup: Supervisor::<A>::new(ctx, Box::new(policy)),
+            act: PhantomData});
 
         addr
     }
 
+    fn new(ctx: A::Context, policy: Box<RestartPolicy>) -> Supervisor<A> {
+        Supervisor{ctx, policy, delay: None, restarts: 0,
+                   factory: None, lazy: None, created: false, passivated: false}
+    }
+
+    /// Restart actor, returns `false` if actor could not be restarted
+    fn restart(&mut self, reason: &RestartReason) -> bool {
+        clear_mailbox::<A>(&mut self.ctx, &self.policy.retention());
+        if self.restart_actor(reason) {
+            self.restarts += 1;
+            self.policy.restarted(self.restarts, reason);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Reset context and restart or re-create actor
+    fn restart_actor(&mut self, reason: &RestartReason) -> bool {
+        let timers = self.policy.preserve_timers();
+        if let Some(ref mut factory) = self.factory {
+            restart_fresh::<A, _>(&mut self.ctx, reason, timers, &mut **factory)
+        } else {
+            restart::<A>(&mut self.ctx, reason, timers)
+        }
+    }
+
+    /// Poll supervised actor, resolves to `true` if failure is escalated
+    fn poll_supervised(&mut self) -> Poll<bool, ()> {
+        loop {
+            // lazy actor is created and passivated actor is restarted or
+            // re-created once message arrives
+            if (self.lazy.is_some() && !self.created) || self.passivated {
+                if !self.ctx.mailbox_ready() {
+                    return if self.ctx.connected() {
+                        Ok(Async::NotReady)
+                    } else {
+                        Ok(Async::Ready(false))
+                    }
+                }
+                if self.lazy.is_some() && !self.created {
+                    self.created = true;
+                    let act = (self.lazy.as_mut().unwrap())(&mut self.ctx);
+                    self.ctx.set_actor(act);
+                } else {
+                    self.passivated = false;
+                    match (self.lazy.as_mut(), self.factory.as_mut()) {
+                        // context was reset when actor got passivated
+                        (Some(f), _) | (None, Some(f)) =>
+                            recreate::<A, _>(&mut self.ctx, &RestartReason::Passivated, &mut **f),
+                        (None, None) => {
+                            let timers = self.policy.preserve_timers();
+                            if !restart::<A>(&mut self.ctx, &RestartReason::Passivated, timers) {
+                                return Ok(Async::Ready(false))
+                            }
+                        }
+                    }
+                }
+            }
+
+            // wait for restart delay
+            if let Some((mut delay, reason)) = self.delay.take() {
+                match delay.poll() {
+                    Ok(Async::NotReady) => {
+                        self.delay = Some((delay, reason));
+                        return Ok(Async::NotReady)
+                    }
+                    Ok(Async::Ready(_)) | Err(_) => {
+                        if !self.restart(&reason) {
+                            return Ok(Async::Ready(false))
+                        }
+                    }
+                }
+            }
+
+            match poll_context::<A>(&mut self.ctx) {
+                None =>
+                    return Ok(Async::NotReady),
+                Some(reason) => {
+                    // stop if context's address is not connected
+                    if !self.ctx.connected() {
+                        return Ok(Async::Ready(false))
+                    }
+                    // arbiter is stopping gracefully, actor is not restarted
+                    if Arbiter::draining() {
+                        return Ok(Async::Ready(false))
+                    }
+                    if reason == RestartReason::Passivated {
+                        // passivated instance is dropped if actor could be
+                        // re-created, otherwise its state is kept
+                        if self.lazy.is_some() || self.factory.is_some() {
+                            if !reset::<A>(&mut self.ctx, self.policy.preserve_timers()) {
+                                return Ok(Async::Ready(false))
+                            }
+                            self.ctx.drop_actor();
+                        }
+                        self.passivated = true;
+                        continue
+                    }
+                    match self.policy.decide(&reason) {
+                        RestartAction::Restart => {
+                            if !self.restart(&reason) {
+                                return Ok(Async::Ready(false))
+                            }
+                        }
+                        RestartAction::RestartAfter(delay) => {
+                            self.delay = Some(
+                                (Delay::new(delay), reason));
+                        }
+                        RestartAction::Stop => {
+                            self.policy.gave_up();
+                            return Ok(Async::Ready(false))
+                        }
+                        RestartAction::Escalate => {
+                            self.policy.gave_up();
+                            return Ok(Async::Ready(true))
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<A> Supervisor<A> where A: Supervised + Actor<Context=Context<A>>
+{
     /// Start new supervised actor in arbiter's thread.
     pub fn start_in<F>(addr: &Addr<Syn, Arbiter>, f: F) -> Addr<Syn, A>
         where A: Actor<Context=Context<A>>,
@@ -116,7 +517,7 @@ impl<A> Supervisor<A> where A: Supervised + Actor<Context=Context<A>>
             let mut ctx = Context::with_receiver(None, rx);
             let act = f(&mut ctx);
             ctx.set_actor(act);
-            Arbiter::handle().spawn(Supervisor::<A>{ctx: ctx});
+            Arbiter::handle().spawn(Supervisor::<A>::new(ctx, Box::new(AlwaysRestart)));
             Ok(())
         }));
 
@@ -125,22 +526,1067 @@ impl<A> Supervisor<A> where A: Supervised + Actor<Context=Context<A>>
 }
 
 #[doc(hidden)]
-impl<A> Future for Supervisor<A> where A: Supervised + Actor<Context=Context<A>> {
+impl<A> Future for Supervisor<A> where A: Supervised + Actor, A::Context: SupervisedContext<A> {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.poll_supervised() {
+            Ok(Async::Ready(true)) => {
+                warn!("Supervised actor failure escalated, no parent supervisor");
+                Ok(Async::Ready(()))
+            }
+            Ok(Async::Ready(false)) => Ok(Async::Ready(())),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Poll supervised context, returns failure reason if actor is stopped
+fn poll_context<A>(ctx: &mut A::Context) -> Option<RestartReason>
+    where A: Supervised + Actor, A::Context: SupervisedContext<A>
+{
+    match panic::catch_unwind(AssertUnwindSafe(|| Future::poll(ctx))) {
+        Ok(Ok(Async::NotReady)) => None,
+        Ok(_) => if ctx.stream_failed() {
+            Some(RestartReason::StreamError)
+        } else if ctx.passivated() {
+            Some(RestartReason::Passivated)
+        } else {
+            Some(RestartReason::Stopped)
+        },
+        Err(err) => Some(RestartReason::Panic(panic_message(err))),
+    }
+}
+
+pub(crate) fn panic_message(err: Box<Any + Send>) -> String {
+    if let Some(msg) = err.downcast_ref::<&'static str>() {
+        (*msg).to_owned()
+    } else if let Some(msg) = err.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+/// Supervisor that runs within parent's context
+struct ChildSupervisor<A, B> where A: Supervised + Actor, A::Context: SupervisedContext<A> {
+    sup: Supervisor<A>,
+    act: PhantomData<B>,
+}
+
+impl<A, B> ActorFuture for ChildSupervisor<A, B>
+    where A: Supervised + Actor, A::Context: SupervisedContext<A>,
+          B: Actor<Context=Context<B>>,
+{
+    type Item = ();
+    type Error = ();
+    type Actor = B;
+
+    fn poll(&mut self, _: &mut B, ctx: &mut Context<B>) -> Poll<(), ()> {
+        match self.sup.poll_supervised() {
+            Ok(Async::Ready(true)) => {
+                ctx.stop();
+                Ok(Async::Ready(()))
+            }
+            Ok(Async::Ready(false)) => Ok(Async::Ready(())),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// One-for-all group supervisor
+///
+/// Group supervisor runs set of supervised actors of different types. If any actor
+/// in the group fails, supervisor stops all other actors in reverse order and then
+/// restarts all actors in order in which they were added to the group. Restart policy
+/// is consulted once per group failure. Failed actor gets restarted with actual
+/// failure reason, other actors get restarted with `RestartReason::Stopped`.
+///
+/// Actor that stops after all its addresses get dropped, leaves the group.
+/// Group supervisor terminates after all actors leave the group.
+///
+/// ```rust
+/// # #[macro_use] extern crate actix;
+/// # use actix::prelude::*;
+/// use actix::supervisor::GroupSupervisor;
+///
+/// struct Db;
+///
+/// impl Actor for Db {
+///    type Context = Context<Self>;
+/// }
+///
+/// impl actix::Supervised for Db {}
+///
+/// struct Cache;
+///
+/// impl Actor for Cache {
+///    type Context = Context<Self>;
+/// }
+///
+/// impl actix::Supervised for Cache {}
+///
+/// # fn main() {
+/// #    let sys = System::new("test");
+/// let mut group = GroupSupervisor::new();
+/// let db: Addr<Unsync, Db> = group.add(|_| Db);
+/// let cache: Addr<Syn, Cache> = group.add(|_| Cache);
+/// group.start();
+/// # }
+/// ```
+pub struct GroupSupervisor {
+    members: Vec<Box<GroupMember>>,
+    policy: Box<RestartPolicy>,
+    delay: Option<Delay>,
+    failed: Option<(usize, RestartReason)>,
+    restarts: usize,
+}
+
+impl Default for GroupSupervisor {
+    fn default() -> GroupSupervisor {
+        GroupSupervisor::new()
+    }
+}
+
+impl GroupSupervisor {
+
+    /// Create new group supervisor, group gets restarted indefinitely
+    pub fn new() -> GroupSupervisor {
+        GroupSupervisor::with_policy(AlwaysRestart)
+    }
+
+    /// Create new group supervisor with specific restart policy
+    pub fn with_policy<P: RestartPolicy>(policy: P) -> GroupSupervisor {
+        GroupSupervisor {
+            members: Vec::new(),
+            policy: Box::new(policy),
+            delay: None,
+            failed: None,
+            restarts: 0,
+        }
+    }
+
+    /// Add new actor to the group.
+    ///
+    /// Actor does not run until group supervisor get started.
+    pub fn add<A, Addr, F>(&mut self, f: F) -> Addr
+        where F: FnOnce(&mut A::Context) -> A + 'static,
+              A: Supervised + Actor + ActorAddress<A, Addr>,
+              A::Context: SupervisedContext<A>,
+    {
+        let mut ctx = A::Context::create();
+        let act = f(&mut ctx);
+        let addr =  <A as ActorAddress<A, Addr>>::get(&mut ctx);
+        ctx.set_actor(act);
+        self.members.push(Box::new(Member::<A>{ctx}));
+
+        addr
+    }
+
+    /// Start group supervisor in current Arbiter
+    pub fn start(self) {
+        Arbiter::handle().spawn(self);
+    }
+
+    /// Restart all actors in order, returns `false` if group is empty
+    fn restart_all(&mut self) -> bool {
+        let (failed, failed_reason) = match self.failed.take() {
+            Some((idx, reason)) => (Some(idx), reason),
+            None => (None, RestartReason::Stopped),
+        };
+        let stopped = RestartReason::Stopped;
+        let retention = self.policy.retention();
+        let timers = self.policy.preserve_timers();
+
+        let mut idx = 0;
+        let mut pos = 0;
+        while idx < self.members.len() {
+            let reason = if failed == Some(pos) { &failed_reason } else { &stopped };
+            if self.members[idx].restart_member(reason, &retention, timers) {
+                idx += 1;
+            } else {
+                self.members.remove(idx);
+            }
+            pos += 1;
+        }
+
+        if self.members.is_empty() {
+            false
+        } else {
+            self.restarts += 1;
+            self.policy.restarted(self.restarts, &failed_reason);
+            true
+        }
+    }
+}
+
+#[doc(hidden)]
+impl Future for GroupSupervisor {
     type Item = ();
     type Error = ();
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         loop {
-            match self.ctx.poll() {
-                Ok(Async::NotReady) =>
-                    return Ok(Async::NotReady),
-                Ok(Async::Ready(_)) | Err(_) => {
-                    // stop if context's address is not connected
-                    if !self.ctx.restart() {
+            // wait for restart delay
+            if let Some(mut delay) = self.delay.take() {
+                match delay.poll() {
+                    Ok(Async::NotReady) => {
+                        self.delay = Some(delay);
+                        return Ok(Async::NotReady)
+                    }
+                    Ok(Async::Ready(_)) | Err(_) => {
+                        if !self.restart_all() {
+                            return Ok(Async::Ready(()))
+                        }
+                    }
+                }
+            }
+
+            let mut idx = 0;
+            while idx < self.members.len() {
+                match self.members[idx].poll_member() {
+                    None => idx += 1,
+                    Some(reason) => {
+                        if self.members[idx].member_connected() {
+                            self.failed = Some((idx, reason));
+                            break
+                        }
+                        // nobody can send messages to this actor
+                        self.members.remove(idx);
+                    }
+                }
+            }
+
+            let reason = if let Some((_, ref reason)) = self.failed {
+                reason.clone()
+            } else {
+                if self.members.is_empty() {
+                    return Ok(Async::Ready(()))
+                } else {
+                    return Ok(Async::NotReady)
+                }
+            };
+
+            // stop whole group
+            for member in self.members.iter_mut().rev() {
+                member.stop_member();
+            }
+
+            match self.policy.decide(&reason) {
+                RestartAction::Restart => {
+                    if !self.restart_all() {
                         return Ok(Async::Ready(()))
                     }
                 }
+                RestartAction::RestartAfter(delay) => {
+                    self.delay = Some(Delay::new(delay));
+                }
+                RestartAction::Stop | RestartAction::Escalate => {
+                    self.policy.gave_up();
+                    self.failed = None;
+                    self.members.clear();
+                    return Ok(Async::Ready(()))
+                }
+            }
+        }
+    }
+}
+
+/// Type erased supervised actor context
+trait GroupMember {
+    fn poll_member(&mut self) -> Option<RestartReason>;
+
+    fn member_connected(&self) -> bool;
+
+    fn stop_member(&mut self);
+
+    fn restart_member(&mut self, reason: &RestartReason, retention: &Retention,
+                      timers: bool) -> bool;
+}
+
+struct Member<A> where A: Supervised + Actor, A::Context: SupervisedContext<A> {
+    ctx: A::Context,
+}
+
+impl<A> GroupMember for Member<A> where A: Supervised + Actor, A::Context: SupervisedContext<A> {
+
+    fn poll_member(&mut self) -> Option<RestartReason> {
+        poll_context::<A>(&mut self.ctx)
+    }
+
+    fn member_connected(&self) -> bool {
+        self.ctx.connected()
+    }
+
+    fn stop_member(&mut self) {
+        match self.ctx.state() {
+            ActorState::Started | ActorState::Running => {
+                self.ctx.stop();
+                let _ = poll_context::<A>(&mut self.ctx);
             }
+            ActorState::Stopping | ActorState::Stopped => (),
         }
     }
+
+    fn restart_member(&mut self, reason: &RestartReason, retention: &Retention,
+                      timers: bool) -> bool {
+        clear_mailbox::<A>(&mut self.ctx, retention);
+        restart::<A>(&mut self.ctx, reason, timers)
+    }
+}
+
+/// Cleanup supervised context, optionally preserving delayed notifications
+fn reset<A>(ctx: &mut A::Context, timers: bool) -> bool
+    where A: Supervised + Actor, A::Context: SupervisedContext<A>
+{
+    if timers {
+        ctx.reset_with_timers()
+    } else {
+        ctx.reset()
+    }
+}
+
+/// Restart supervised context
+fn restart<A>(ctx: &mut A::Context, reason: &RestartReason, timers: bool) -> bool
+    where A: Supervised + Actor, A::Context: SupervisedContext<A>
+{
+    if reset::<A>(ctx, timers) {
+        ctx.restarting(reason);
+        events::actor_restarted::<A>(ctx.path().as_ref(), reason);
+        true
+    } else {
+        false
+    }
+}
+
+/// Restart supervised context with new actor instance created by `f`
+fn restart_fresh<A, F>(ctx: &mut A::Context, reason: &RestartReason, timers: bool, f: &mut F)
+                       -> bool
+    where A: Supervised + Actor, A::Context: SupervisedContext<A>,
+          F: FnMut(&mut A::Context) -> A + ?Sized
+{
+    if reset::<A>(ctx, timers) {
+        recreate::<A, F>(ctx, reason, f);
+        true
+    } else {
+        false
+    }
+}
+
+/// Replace actor of reset context with new instance created by `f`
+fn recreate<A, F>(ctx: &mut A::Context, reason: &RestartReason, f: &mut F)
+    where A: Supervised + Actor, A::Context: SupervisedContext<A>,
+          F: FnMut(&mut A::Context) -> A + ?Sized
+{
+    let act = f(ctx);
+    ctx.set_actor(act);
+    ctx.restarting(reason);
+    events::actor_restarted::<A>(ctx.path().as_ref(), reason);
+}
+
+/// Execution context that could be managed by supervisor
+///
+/// Custom execution context has to implement this trait to make its actors
+/// supervisable. Usually custom context is built on top of
+/// [`ContextImpl`](../dev/struct.ContextImpl.html) and delegates to it.
+pub trait SupervisedContext<A>: AsyncContext<A> + Future<Item=(), Error=()> + 'static
+    where A: Supervised + Actor<Context=Self>
+{
+    /// Create new context without actor
+    fn create() -> Self;
+
+    /// Set context's actor
+    fn set_actor(&mut self, act: A);
+
+    /// Check if context's mailbox is connected to any address
+    fn connected(&self) -> bool;
+
+    /// Cleanup all futures, except address queue.
+    ///
+    /// Returns `false` if context can not be restarted.
+    fn reset(&mut self) -> bool;
+
+    /// Cleanup all futures, except address queue and delayed notifications
+    /// scheduled with `notify_later()`.
+    ///
+    /// By default delayed notifications are dropped as well.
+    fn reset_with_timers(&mut self) -> bool {
+        self.reset()
+    }
+
+    /// Call `Supervised::restarting_with()` method of context's actor
+    fn restarting(&mut self, reason: &RestartReason);
+
+    /// Check if actor stopped because of stream error
+    fn stream_failed(&self) -> bool {
+        false
+    }
+
+    /// Check if context's mailbox has pending messages, current task is
+    /// notified once message arrives.
+    ///
+    /// By default mailbox is considered ready, lazily started actor is
+    /// created immediately.
+    fn mailbox_ready(&mut self) -> bool {
+        true
+    }
+
+    /// Check if actor stopped with `Context::passivate()`
+    fn passivated(&self) -> bool {
+        false
+    }
+
+    /// Drop actor of passivated context, supervisor sets new actor before
+    /// context is polled again.
+    ///
+    /// By default actor is kept until it is replaced.
+    fn drop_actor(&mut self) {}
+
+    /// Drop all pending messages, returns types of dropped messages
+    fn clear_mailbox(&mut self) -> Vec<DroppedMessage> {
+        Vec::new()
+    }
+
+    /// Path of context's actor
+    fn path(&self) -> Option<ActorPath> {
+        None
+    }
+}
+
+/// Apply retention policy to mailbox of failed actor
+fn clear_mailbox<A>(ctx: &mut A::Context, retention: &Retention)
+    where A: Supervised + Actor, A::Context: SupervisedContext<A>
+{
+    match *retention {
+        Retention::Preserve => (),
+        Retention::Drop => {
+            let _path = ctx.path().map(|path| path::enter(&path));
+            for (message_type, message_name) in ctx.clear_mailbox() {
+                deadletter::publish(message_type, message_name, DeadLetterReason::ActorRestarted);
+            }
+        }
+        Retention::DeadLetters(ref recipient) => {
+            for (message_type, message_name) in ctx.clear_mailbox() {
+                let _ = recipient.do_send(DeadLetter::new(
+                    message_type, message_name, DeadLetterReason::ActorRestarted));
+            }
+        }
+    }
+}
+
+/// What supervisor does with messages pending in mailbox of failed actor
+#[derive(Clone)]
+pub enum Retention {
+    /// Preserve pending messages, restarted actor processes them
+    Preserve,
+    /// Drop pending messages, dropped messages are reported to
+    /// system [`DeadLetters`](../deadletter/struct.DeadLetters.html) service
+    Drop,
+    /// Drop pending messages and send `DeadLetter` for each of them
+    /// to the recipient
+    DeadLetters(Recipient<Syn, DeadLetter>),
+}
+
+/// Reason of supervised actor failure
+#[derive(Clone, PartialEq, Debug)]
+pub enum RestartReason {
+    /// Actor panicked, contains panic message
+    Panic(String),
+    /// Actor stopped because of an error in stream added with `add_stream()`
+    StreamError,
+    /// Actor stopped itself
+    Stopped,
+    /// Actor stopped itself with `Context::passivate()`. Supervisor
+    /// restarts passivated actor once it receives next message, group
+    /// supervisor applies restart policy.
+    Passivated,
+}
+
+/// Supervisor decision after actor failure
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RestartAction {
+    /// Restart actor immediately
+    Restart,
+    /// Restart actor after specified delay
+    RestartAfter(Duration),
+    /// Stop actor and drop its mailbox
+    Stop,
+    /// Stop actor and escalate failure to parent supervisor.
+    ///
+    /// Without parent supervisor it is the same as `Stop`.
+    Escalate,
+}
+
+/// Restart policy
+///
+/// Supervisor consults restart policy each time supervised actor fails.
+///
+/// ```rust
+/// # extern crate actix;
+/// use actix::supervisor::{RestartAction, RestartPolicy, RestartReason};
+///
+/// /// Restart actor only once, do not restart actor after panic
+/// struct RestartOnce(bool);
+///
+/// impl RestartPolicy for RestartOnce {
+///     fn decide(&mut self, reason: &RestartReason) -> RestartAction {
+///         if let RestartReason::Panic(_) = *reason {
+///             RestartAction::Stop
+///         } else if self.0 {
+///             RestartAction::Stop
+///         } else {
+///             self.0 = true;
+///             RestartAction::Restart
+///         }
+///     }
+/// }
+/// # fn main() {}
+/// ```
+#[allow(unused_variables)]
+pub trait RestartPolicy: 'static {
+
+    /// Decide what to do with failed actor
+    fn decide(&mut self, reason: &RestartReason) -> RestartAction;
+
+    /// Method is called after supervisor restarts actor. `count` is total number
+    /// of restarts performed by supervisor.
+    fn restarted(&mut self, count: usize, reason: &RestartReason) {}
+
+    /// Method is called after supervisor gives up restarting actor,
+    /// i.e. after `decide()` returns `Stop` or `Escalate`.
+    fn gave_up(&mut self) {}
+
+    /// What to do with pending messages, method is called before each restart.
+    ///
+    /// By default pending messages are preserved.
+    fn retention(&mut self) -> Retention {
+        Retention::Preserve
+    }
+
+    /// Use specific retention policy for pending messages
+    fn with_retention(self, retention: Retention) -> WithRetention<Self>
+        where Self: Sized
+    {
+        WithRetention{policy: self, retention}
+    }
+
+    /// Whether to re-arm pending `notify_later()` notifications of failed
+    /// actor, method is called before each restart.
+    ///
+    /// By default pending notifications are dropped.
+    fn preserve_timers(&mut self) -> bool {
+        false
+    }
+
+    /// Re-arm pending `notify_later()` notifications in restarted context
+    ///
+    /// Notifications keep their deadlines, time spent in restart delay
+    /// counts. Notification that was being handled when actor failed is lost.
+    fn with_timers(self) -> WithTimers<Self>
+        where Self: Sized
+    {
+        WithTimers{policy: self}
+    }
+
+    /// Send supervisor lifecycle events to specified recipient
+    ///
+    /// ```rust
+    /// # extern crate actix;
+    /// # use std::time::Duration;
+    /// # use actix::prelude::*;
+    /// use actix::supervisor::{BoundedRestart, RestartPolicy, SupervisorEvent};
+    ///
+    /// struct Monitor;
+    ///
+    /// impl Actor for Monitor {
+    ///    type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<SupervisorEvent> for Monitor {
+    ///    type Result = ();
+    ///
+    ///    fn handle(&mut self, msg: SupervisorEvent, _: &mut Context<Self>) {
+    ///        println!("{:?}", msg);
+    ///    }
+    /// }
+    ///
+    /// struct MyActor;
+    ///
+    /// impl Actor for MyActor {
+    ///    type Context = Context<Self>;
+    /// }
+    ///
+    /// impl actix::Supervised for MyActor {}
+    ///
+    /// # fn main() {
+    /// #    let sys = System::new("test");
+    /// let monitor: Addr<Syn, _> = Monitor.start();
+    /// let addr: Addr<Unsync, _> = actix::Supervisor::start_with(
+    ///     BoundedRestart::new(3, Duration::from_secs(10)).subscribe(monitor.recipient()),
+    ///     |_| MyActor);
+    /// # }
+    /// ```
+    fn subscribe(self, recipient: Recipient<Syn, SupervisorEvent>) -> Subscribed<Self>
+        where Self: Sized
+    {
+        Subscribed{policy: self, recipient, restarts: 0}
+    }
+}
+
+/// Supervisor lifecycle event
+#[derive(Clone, Debug)]
+pub enum SupervisorEvent {
+    /// Supervised actor got restarted
+    ChildRestarted {
+        /// Total number of restarts
+        count: usize,
+        /// Failure reason
+        reason: RestartReason,
+    },
+    /// Supervisor gave up restarting actor
+    ChildGaveUp {
+        /// Total number of restarts
+        count: usize,
+    },
+}
+
+impl Message for SupervisorEvent {
+    type Result = ();
+}
+
+/// Supervisor statistics collector
+///
+/// Monitor has to be subscribed to supervisor's restart policy events.
+///
+/// ```rust
+/// # extern crate actix;
+/// # extern crate futures;
+/// # use futures::Future;
+/// # use actix::prelude::*;
+/// use actix::supervisor::{AlwaysRestart, RestartPolicy, SupervisorMonitor, SupervisorStats};
+///
+/// struct MyActor;
+///
+/// impl Actor for MyActor {
+///    type Context = Context<Self>;
+/// }
+///
+/// impl actix::Supervised for MyActor {}
+///
+/// fn main() {
+///     let sys = System::new("test");
+///
+///     let monitor: Addr<Syn, _> = SupervisorMonitor::default().start();
+///     let addr: Addr<Unsync, _> = actix::Supervisor::start_with(
+///         AlwaysRestart.subscribe(monitor.clone().recipient()), |_| MyActor);
+///
+///     Arbiter::handle().spawn(
+///         monitor.send(SupervisorStats).then(|res| {
+///             let stats = res.unwrap();
+///             println!("restarts: {}, uptime: {:?}", stats.restarts, stats.uptime);
+/// #           Arbiter::system().do_send(actix::msgs::SystemExit(0));
+///             Ok(())
+///         }));
+///
+///     sys.run();
+/// }
+/// ```
+pub struct SupervisorMonitor {
+    started: Instant,
+    restarts: usize,
+    last_restart: Option<Instant>,
+    last_reason: Option<RestartReason>,
+    gave_up: bool,
+}
+
+impl Default for SupervisorMonitor {
+    fn default() -> SupervisorMonitor {
+        SupervisorMonitor {
+            started: Instant::now(),
+            restarts: 0,
+            last_restart: None,
+            last_reason: None,
+            gave_up: false,
+        }
+    }
+}
+
+impl Actor for SupervisorMonitor {
+    type Context = Context<Self>;
+}
+
+impl Handler<SupervisorEvent> for SupervisorMonitor {
+    type Result = ();
+
+    fn handle(&mut self, msg: SupervisorEvent, _: &mut Context<Self>) {
+        match msg {
+            SupervisorEvent::ChildRestarted{count, reason} => {
+                self.restarts = count;
+                self.last_restart = Some(Instant::now());
+                self.last_reason = Some(reason);
+            }
+            SupervisorEvent::ChildGaveUp{..} => self.gave_up = true,
+        }
+    }
+}
+
+impl Handler<SupervisorStats> for SupervisorMonitor {
+    type Result = MessageResult<SupervisorStats>;
+
+    fn handle(&mut self, _: SupervisorStats, _: &mut Context<Self>) -> Self::Result {
+        let since = self.last_restart.unwrap_or(self.started);
+        MessageResult(Stats {
+            restarts: self.restarts,
+            last_failure: self.last_restart,
+            last_reason: self.last_reason.clone(),
+            uptime: Instant::now().duration_since(since),
+            gave_up: self.gave_up,
+        })
+    }
+}
+
+/// Get supervisor statistics from `SupervisorMonitor`
+pub struct SupervisorStats;
+
+impl Message for SupervisorStats {
+    type Result = Stats;
+}
+
+/// Supervisor statistics
+#[derive(Clone, Debug)]
+pub struct Stats {
+    /// Total number of restarts
+    pub restarts: usize,
+    /// Time of last failure
+    pub last_failure: Option<Instant>,
+    /// Reason of last failure
+    pub last_reason: Option<RestartReason>,
+    /// Time since last restart, or since monitor start if actor never restarted
+    pub uptime: Duration,
+    /// Supervisor gave up restarting actor
+    pub gave_up: bool,
+}
+
+/// Restart policy that sends lifecycle events to recipient
+///
+/// This policy is created by `RestartPolicy::subscribe()` method.
+#[derive(Clone)]
+pub struct Subscribed<P> {
+    policy: P,
+    recipient: Recipient<Syn, SupervisorEvent>,
+    restarts: usize,
+}
+
+impl<P: RestartPolicy> RestartPolicy for Subscribed<P> {
+
+    fn decide(&mut self, reason: &RestartReason) -> RestartAction {
+        self.policy.decide(reason)
+    }
+
+    fn restarted(&mut self, count: usize, reason: &RestartReason) {
+        self.restarts = count;
+        self.policy.restarted(count, reason);
+        let _ = self.recipient.do_send(
+            SupervisorEvent::ChildRestarted{count, reason: reason.clone()});
+    }
+
+    fn gave_up(&mut self) {
+        self.policy.gave_up();
+        let _ = self.recipient.do_send(SupervisorEvent::ChildGaveUp{count: self.restarts});
+    }
+
+    fn retention(&mut self) -> Retention {
+        self.policy.retention()
+    }
+
+    fn preserve_timers(&mut self) -> bool {
+        self.policy.preserve_timers()
+    }
+}
+
+/// Restart policy with specific pending messages retention
+///
+/// This policy is created by `RestartPolicy::with_retention()` method.
+#[derive(Clone)]
+pub struct WithRetention<P> {
+    policy: P,
+    retention: Retention,
+}
+
+impl<P: RestartPolicy> RestartPolicy for WithRetention<P> {
+
+    fn decide(&mut self, reason: &RestartReason) -> RestartAction {
+        self.policy.decide(reason)
+    }
+
+    fn restarted(&mut self, count: usize, reason: &RestartReason) {
+        self.policy.restarted(count, reason)
+    }
+
+    fn gave_up(&mut self) {
+        self.policy.gave_up()
+    }
+
+    fn retention(&mut self) -> Retention {
+        self.retention.clone()
+    }
+
+    fn preserve_timers(&mut self) -> bool {
+        self.policy.preserve_timers()
+    }
+}
+
+/// Restart policy that re-arms pending notifications
+///
+/// This policy is created by `RestartPolicy::with_timers()` method.
+#[derive(Clone)]
+pub struct WithTimers<P> {
+    policy: P,
+}
+
+impl<P: RestartPolicy> RestartPolicy for WithTimers<P> {
+
+    fn decide(&mut self, reason: &RestartReason) -> RestartAction {
+        self.policy.decide(reason)
+    }
+
+    fn restarted(&mut self, count: usize, reason: &RestartReason) {
+        self.policy.restarted(count, reason)
+    }
+
+    fn gave_up(&mut self) {
+        self.policy.gave_up()
+    }
+
+    fn retention(&mut self) -> Retention {
+        self.policy.retention()
+    }
+
+    fn preserve_timers(&mut self) -> bool {
+        true
+    }
+}
+
+/// Restart actor indefinitely
+///
+/// This is default supervisor policy.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AlwaysRestart;
+
+impl RestartPolicy for AlwaysRestart {
+    fn decide(&mut self, _: &RestartReason) -> RestartAction {
+        RestartAction::Restart
+    }
+}
+
+/// Restart limit policy
+///
+/// Supervisor restarts actor at most `max_restarts` times within `within`
+/// period of time. If actor fails more often, policy escalates failure. Optionally
+/// supervisor can exit system or notify escalation address.
+#[derive(Clone)]
+pub struct BoundedRestart {
+    max_restarts: usize,
+    within: Duration,
+    restarts: VecDeque<Instant>,
+    backoff: Option<Backoff>,
+    exit_code: Option<i32>,
+    notify: Option<Recipient<Syn, ChildGaveUp>>,
+}
+
+impl BoundedRestart {
+
+    /// Create new restart limit policy
+    pub fn new(max_restarts: usize, within: Duration) -> BoundedRestart {
+        BoundedRestart {
+            max_restarts,
+            within,
+            restarts: VecDeque::new(),
+            backoff: None,
+            exit_code: None,
+            notify: None,
+        }
+    }
+
+    /// Set delay strategy between restarts
+    ///
+    /// By default actor restarts immediately.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = Some(backoff);
+        self
+    }
+
+    /// Send `SystemExit(code)` to a system after supervisor gives up
+    pub fn exit_system(mut self, code: i32) -> Self {
+        self.exit_code = Some(code);
+        self
+    }
+
+    /// Send `ChildGaveUp` message to specified recipient after supervisor gives up
+    pub fn notify(mut self, recipient: Recipient<Syn, ChildGaveUp>) -> Self {
+        self.notify = Some(recipient);
+        self
+    }
+
+    /// Register failure, returns `false` if restart limit is reached
+    fn restart(&mut self) -> bool {
+        let now = Instant::now();
+        while let Some(first) = self.restarts.front().cloned() {
+            if now.duration_since(first) > self.within {
+                self.restarts.pop_front();
+            } else {
+                break
+            }
+        }
+        if self.restarts.len() < self.max_restarts {
+            self.restarts.push_back(now);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Delay before next restart
+    fn delay(&self) -> Option<Duration> {
+        self.backoff.as_ref().map(|b| b.delay(self.restarts.len()))
+    }
+}
+
+impl RestartPolicy for BoundedRestart {
+
+    fn decide(&mut self, _: &RestartReason) -> RestartAction {
+        if !self.restart() {
+            RestartAction::Escalate
+        } else if let Some(delay) = self.delay() {
+            RestartAction::RestartAfter(delay)
+        } else {
+            RestartAction::Restart
+        }
+    }
+
+    fn gave_up(&mut self) {
+        warn!("Supervisor gave up after {} restarts", self.restarts.len());
+
+        if let Some(ref notify) = self.notify {
+            let _ = notify.do_send(ChildGaveUp{restarts: self.restarts.len()});
+        }
+        if let Some(code) = self.exit_code {
+            Arbiter::system().do_send(SystemExit(code));
+        }
+    }
+}
+
+/// Delay strategy between actor restarts
+#[derive(Clone, Debug)]
+pub enum Backoff {
+    /// Wait same amount of time before each restart
+    Fixed(Duration),
+    /// Double delay after each restart, starting with `initial` and up to `max`.
+    ///
+    /// If `jitter` is set, actual delay is random value between half
+    /// of computed delay and computed delay.
+    Exponential {
+        initial: Duration,
+        max: Duration,
+        jitter: bool,
+    },
+}
+
+impl Backoff {
+
+    /// Create exponential backoff strategy with jitter
+    pub fn exponential(initial: Duration, max: Duration) -> Backoff {
+        Backoff::Exponential{initial, max, jitter: true}
+    }
+
+    /// Compute delay for specific restart attempt, first attempt is `1`
+    pub fn delay(&self, attempt: usize) -> Duration {
+        match *self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential{initial, max, jitter} => {
+                let mut delay = initial;
+                for _ in 1..attempt {
+                    if delay >= max {
+                        break
+                    }
+                    delay = delay.checked_mul(2).unwrap_or(max);
+                }
+                if delay > max {
+                    delay = max;
+                }
+                if jitter {
+                    let bytes = Uuid::new_v4();
+                    let bytes = bytes.as_bytes();
+                    let rnd = (u32::from(bytes[0]) << 8) | u32::from(bytes[1]);
+                    let half = delay / 2;
+                    // divide first, multiplication of large delay overflows
+                    half + (delay - half) / 0xffff * rnd
+                } else {
+                    delay
+                }
+            }
+        }
+    }
+}
+
+/// Restart actor indefinitely, with delay between restarts
+///
+/// Delay grows according to backoff strategy. Attempt counter resets if actor
+/// runs without failure longer than reset period, by default one minute.
+#[derive(Clone)]
+pub struct BackoffRestart {
+    backoff: Backoff,
+    reset: Duration,
+    attempt: usize,
+    last: Option<Instant>,
+}
+
+impl BackoffRestart {
+
+    /// Create new backoff restart policy
+    pub fn new(backoff: Backoff) -> BackoffRestart {
+        BackoffRestart {
+            backoff,
+            reset: Duration::from_secs(60),
+            attempt: 0,
+            last: None,
+        }
+    }
+
+    /// Set period of time after which attempt counter resets
+    pub fn reset_after(mut self, reset: Duration) -> Self {
+        self.reset = reset;
+        self
+    }
+}
+
+impl RestartPolicy for BackoffRestart {
+
+    fn decide(&mut self, _: &RestartReason) -> RestartAction {
+        let now = Instant::now();
+        if let Some(last) = self.last {
+            if now.duration_since(last) > self.reset {
+                self.attempt = 0;
+            }
+        }
+        self.attempt += 1;
+        self.last = Some(now);
+        RestartAction::RestartAfter(self.backoff.delay(self.attempt))
+    }
+}
+
+/// Supervisor gave up restarting actor
+///
+/// This message is sent to escalation address after supervised actor
+/// exceeds restart limit.
+pub struct ChildGaveUp {
+    /// Number of restarts within restart limit period
+    pub restarts: usize,
+}
+
+impl Message for ChildGaveUp {
+    type Result = ();
 }
@@ -1,14 +1,22 @@
 //! Sync actors support
 //!
-//! Sync actors could be used for cpu bound load. Only one sync actor
-//! runs within arbiter's thread. Sync actor process one message at a time.
-//! Sync arbiter can start mutiple threads with separate instance of actor in each.
+//! Sync actors could be used for cpu bound load or for blocking operations,
+//! like database queries or file io, that would otherwise stall event loop.
+//! Only one sync actor runs within arbiter's thread. Sync actor process one
+//! message at a time. Sync arbiter can start mutiple threads with separate
+//! instance of actor in each, all threads consume messages from shared queue.
 //! Note on actor `stopping` lifecycle event, sync actor can not prevent
 //! stopping by returning `false` from `stopping` method.
 //! Multi consumer queue is used as a communication channel queue.
 //! To be able to start sync actor via `SyncArbiter`
 //! Actor has to use `SyncContext` as an execution context.
 //!
+//! If actor panics while handling message, sync arbiter replaces worker
+//! thread with new one and creates new actor instance. Workers of sync arbiter
+//! started with `SyncArbiter::start_supervised()` consult restart policy
+//! instead and re-create actor in the same thread. Failures are reported
+//! to `EventStream` and message is reported to `DeadLetters` service.
+//!
 //! ## Example
 //!
 //! ```rust
@@ -71,27 +79,41 @@
 //!     sys.run();
 //! }
 //! ```
+use std::any::TypeId;
 use std::{mem, thread};
+use std::rc::Rc;
 use std::sync::Arc;
 use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
 
 use crossbeam_channel as channel;
-use futures::{Async, Future, Poll, Stream};
+use futures::{Async, Poll, Stream};
 use futures::sync::oneshot::Sender as SyncSender;
 
-use actor::{Actor, ActorContext, ActorState, Running};
-use arbiter::Arbiter;
+use actor::{Actor, ActorContext, ActorState, AsyncContext, Running, Supervised};
 use address::sync_channel;
 use address::{Addr, Syn, SyncEnvelope, SyncAddressReceiver, EnvelopeProxy, ToEnvelope};
 use context::Context;
-use handler::{Handler, Message, MessageResponse};
+use deadletter::{self, DeadLetterReason};
+use events;
+use fut::ActorFuture;
+use handler::{self, Handler, Message, MessageResponse};
+use msgs::SetPoolSize;
+use span::{self, CorrelationId};
+use supervisor::{panic_message, RestartAction, RestartPolicy, RestartReason};
 
 
 /// Sync arbiter
+///
+/// Sync arbiter is an actor that distributes messages of sync actor between
+/// worker threads. Address of sync arbiter is returned by
+/// `SyncArbiter::start_resizable()`, number of worker threads could be
+/// changed with `SetPoolSize` message.
 pub struct SyncArbiter<A> where A: Actor<Context=SyncContext<A>> {
     queue: channel::Sender<SyncContextProtocol<A>>,
-    msgs: SyncAddressReceiver<A>,
     threads: usize,
+    worker: Rc<Fn(Addr<Syn, SyncArbiter<A>>)>,
+    closed: bool,
 }
 
 impl<A> SyncArbiter<A> where A: Actor<Context=SyncContext<A>> + Send {
@@ -100,24 +122,123 @@ impl<A> SyncArbiter<A> where A: Actor<Context=SyncContext<A>> + Send {
     /// Returns address of the started actor.
     pub fn start<F>(threads: usize, factory: F) -> Addr<Syn, A>
         where F: Fn() -> A + Send + Sync + 'static
+    {
+        SyncArbiter::start_resizable(threads, factory).0
+    }
+
+    /// Start new sync arbiter with specified number of worker threads.
+    /// Returns address of the started actor and address of sync arbiter.
+    ///
+    /// ```rust
+    /// # extern crate actix;
+    /// use actix::prelude::*;
+    /// use actix::msgs::SetPoolSize;
+    ///
+    /// struct DbExecutor;
+    ///
+    /// impl Actor for DbExecutor {
+    ///     type Context = SyncContext<Self>;
+    /// }
+    ///
+    /// # fn main() {
+    /// #    let sys = System::new("test");
+    /// let (addr, pool) = SyncArbiter::start_resizable(2, || DbExecutor);
+    ///
+    /// // more database connections are available
+    /// pool.do_send(SetPoolSize(5));
+    /// # }
+    /// ```
+    pub fn start_resizable<F>(threads: usize, factory: F) -> (Addr<Syn, A>, Addr<Syn, Self>)
+        where F: Fn() -> A + Send + Sync + 'static
     {
         let factory = Arc::new(factory);
         let (sender, receiver) = channel::unbounded();
 
-        for _ in 0..threads {
+        let worker = move |arbiter| {
             let f = Arc::clone(&factory);
             let actor_queue = receiver.clone();
 
             thread::spawn(move || {
-                SyncContext::new(f, actor_queue).run()
+                SyncContext::new(f, actor_queue, arbiter, None).run()
             });
-        }
+        };
+        SyncArbiter::spawn(threads, sender, Rc::new(worker))
+    }
 
+    fn spawn(threads: usize, queue: channel::Sender<SyncContextProtocol<A>>,
+             worker: Rc<Fn(Addr<Syn, SyncArbiter<A>>)>) -> (Addr<Syn, A>, Addr<Syn, Self>)
+    {
         let (tx, rx) = sync_channel::channel(0);
-        Arbiter::handle().spawn(
-            SyncArbiter{queue: sender, msgs: rx, threads: threads});
+        let w = Rc::clone(&worker);
+        let arb: Addr<Syn, _> = SyncArbiter::create(move |ctx| {
+            ctx.spawn(SyncArbiterForward{msgs: rx});
+            SyncArbiter{queue, threads, worker: w, closed: false}
+        });
+        for _ in 0..threads {
+            worker(arb.clone());
+        }
 
-        Addr::new(tx)
+        (Addr::new(tx), arb)
+    }
+}
+
+impl<A> SyncArbiter<A> where A: Actor<Context=SyncContext<A>> + Supervised + Send {
+
+    /// Start new sync arbiter with specified number of supervised worker threads.
+    /// Returns address of the started actor.
+    ///
+    /// Each worker catches panics of its actor and consults own copy of
+    /// restart `policy`. If policy decides to restart actor, worker creates new
+    /// actor instance with `factory` and calls `Supervised::restarting_with()`.
+    /// `RestartAction::RestartAfter` blocks worker thread for specified period
+    /// of time. If policy gives up, worker thread exits.
+    ///
+    /// Workers share same message queue, so messages pending in
+    /// the queue are always preserved.
+    ///
+    /// ```rust
+    /// # extern crate actix;
+    /// # use std::time::Duration;
+    /// use actix::prelude::*;
+    /// use actix::supervisor::BoundedRestart;
+    ///
+    /// struct Worker;
+    ///
+    /// impl Actor for Worker {
+    ///     type Context = SyncContext<Self>;
+    /// }
+    ///
+    /// impl actix::Supervised for Worker {}
+    ///
+    /// # fn main() {
+    /// #    let sys = System::new("test");
+    /// // restart each worker at most 5 times within a minute
+    /// let addr = SyncArbiter::start_supervised(
+    ///     3, BoundedRestart::new(5, Duration::from_secs(60)), || Worker);
+    /// # }
+    /// ```
+    pub fn start_supervised<P, F>(threads: usize, policy: P, factory: F) -> Addr<Syn, A>
+        where F: Fn() -> A + Send + Sync + 'static,
+              P: RestartPolicy + Clone + Send,
+    {
+        let factory = Arc::new(factory);
+        let (sender, receiver) = channel::unbounded();
+
+        let worker = move |arbiter| {
+            let f = Arc::clone(&factory);
+            let actor_queue = receiver.clone();
+            let policy = policy.clone();
+
+            thread::spawn(move || {
+                let supervisor = SyncSupervisor {
+                    policy: Box::new(policy),
+                    restarting: sync_restarting::<A>,
+                    restarts: 0,
+                };
+                SyncContext::new(f, actor_queue, arbiter, Some(supervisor)).run()
+            });
+        };
+        SyncArbiter::spawn(threads, sender, Rc::new(worker)).0
     }
 }
 
@@ -125,17 +246,92 @@ impl<A> Actor for SyncArbiter<A> where A: Actor<Context=SyncContext<A>> {
     type Context = Context<Self>;
 }
 
-#[doc(hidden)]
-impl<A> Future for SyncArbiter<A> where A: Actor<Context=SyncContext<A>>
+/// Change number of worker threads of sync arbiter
+///
+/// New workers start immediately, retired workers exit after messages
+/// that are already in the queue get processed.
+impl<A> Handler<SetPoolSize> for SyncArbiter<A> where A: Actor<Context=SyncContext<A>> {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetPoolSize, ctx: &mut Context<Self>) {
+        if self.closed {
+            return
+        }
+        while self.threads < msg.0 {
+            (self.worker)(ctx.address());
+            self.threads += 1;
+        }
+        while self.threads > msg.0 {
+            let _ = self.queue.send(SyncContextProtocol::Stop);
+            self.threads -= 1;
+        }
+    }
+}
+
+/// Actor of worker thread panicked
+struct WorkerFailed {
+    reason: RestartReason,
+    message_type: Option<TypeId>,
+    message_name: Option<&'static str>,
+    respawn: bool,
+}
+
+impl Message for WorkerFailed {
+    type Result = ();
+}
+
+impl<A> Handler<WorkerFailed> for SyncArbiter<A> where A: Actor<Context=SyncContext<A>> {
+    type Result = ();
+
+    fn handle(&mut self, msg: WorkerFailed, ctx: &mut Context<Self>) {
+        events::actor_restarted::<A>(None, &msg.reason);
+        deadletter::publish(msg.message_type, msg.message_name, DeadLetterReason::Panic);
+        if msg.respawn {
+            // closed arbiter replaces failed worker only to process queued
+            // messages, replacement exits on stop message of failed worker
+            if self.closed && self.queue.is_empty() {
+                self.threads = self.threads.saturating_sub(1);
+            } else {
+                (self.worker)(ctx.address());
+            }
+        }
+    }
+}
+
+/// Supervised worker gave up, worker thread exited
+struct WorkerGaveUp;
+
+impl Message for WorkerGaveUp {
+    type Result = ();
+}
+
+impl<A> Handler<WorkerGaveUp> for SyncArbiter<A> where A: Actor<Context=SyncContext<A>> {
+    type Result = ();
+
+    fn handle(&mut self, _: WorkerGaveUp, _: &mut Context<Self>) {
+        // retired worker could give up before it gets stop message
+        self.threads = self.threads.saturating_sub(1);
+    }
+}
+
+/// Forwards messages of sync actor to worker threads
+struct SyncArbiterForward<A> where A: Actor<Context=SyncContext<A>> {
+    msgs: SyncAddressReceiver<A>,
+}
+
+impl<A> ActorFuture for SyncArbiterForward<A> where A: Actor<Context=SyncContext<A>>
 {
     type Item = ();
     type Error = ();
+    type Actor = SyncArbiter<A>;
 
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+    fn poll(&mut self, act: &mut SyncArbiter<A>, _: &mut Context<SyncArbiter<A>>)
+            -> Poll<Self::Item, Self::Error>
+    {
         loop {
             match self.msgs.poll() {
                 Ok(Async::Ready(Some(msg))) =>
-                    self.queue.send(SyncContextProtocol::Envelope(msg))
+                    act.queue.send(SyncContextProtocol::Envelope(msg))
                     .expect("Should not fail"),
                 Ok(Async::NotReady) => break,
                 Ok(Async::Ready(None)) | Err(_) => unreachable!(),
@@ -146,10 +342,11 @@ impl<A> Future for SyncArbiter<A> where A: Actor<Context=SyncContext<A>>
         if self.msgs.connected() {
             Ok(Async::NotReady)
         } else {
-            // stop sync arbiters
-            for _ in 0..self.threads {
-                let _ = self.queue.send(SyncContextProtocol::Stop);
+            // stop sync arbiters, arbiter stops once all workers exit
+            for _ in 0..act.threads {
+                let _ = act.queue.send(SyncContextProtocol::Stop);
             }
+            act.closed = true;
             Ok(Async::Ready(()))
         }
     }
@@ -169,6 +366,19 @@ enum SyncContextProtocol<A> where A: Actor<Context=SyncContext<A>> {
     Envelope(SyncEnvelope<A>),
 }
 
+/// Supervision state of sync worker
+struct SyncSupervisor<A> where A: Actor<Context=SyncContext<A>> {
+    policy: Box<RestartPolicy>,
+    restarting: fn(&mut A, &RestartReason, &mut SyncContext<A>),
+    restarts: usize,
+}
+
+fn sync_restarting<A>(act: &mut A, reason: &RestartReason, ctx: &mut SyncContext<A>)
+    where A: Actor<Context=SyncContext<A>> + Supervised
+{
+    act.restarting_with(reason, ctx)
+}
+
 /// Sync actor execution context
 pub struct SyncContext<A> where A: Actor<Context=SyncContext<A>> {
     act: A,
@@ -176,17 +386,24 @@ pub struct SyncContext<A> where A: Actor<Context=SyncContext<A>> {
     stopping: bool,
     state: ActorState,
     factory: Arc<Fn() -> A>,
+    arbiter: Addr<Syn, SyncArbiter<A>>,
+    supervisor: Option<SyncSupervisor<A>>,
 }
 
 impl<A> SyncContext<A> where A: Actor<Context=Self> {
     /// Create new SyncContext
-    fn new(factory: Arc<Fn() -> A>, queue: channel::Receiver<SyncContextProtocol<A>>) -> Self {
+    fn new(factory: Arc<Fn() -> A>,
+           queue: channel::Receiver<SyncContextProtocol<A>>,
+           arbiter: Addr<Syn, SyncArbiter<A>>,
+           supervisor: Option<SyncSupervisor<A>>) -> Self {
         SyncContext {
             act: factory(),
             queue: queue,
             stopping: false,
             state: ActorState::Started,
             factory: factory,
+            arbiter,
+            supervisor,
         }
     }
 
@@ -211,12 +428,44 @@ impl<A> SyncContext<A> where A: Actor<Context=Self> {
                     return
                 },
                 Ok(SyncContextProtocol::Envelope(mut env)) => {
-                    env.handle(&mut self.act, ctx);
+                    let (message_type, message_name) = (env.message_type(), env.message_name());
+                    let result = {
+                        let act = &mut self.act;
+                        panic::catch_unwind(AssertUnwindSafe(|| env.handle(act, ctx)))
+                    };
+                    if let Err(err) = result {
+                        let reason = RestartReason::Panic(panic_message(err));
+                        let respawn = self.supervisor.is_none();
+
+                        // sync arbiter replaces unsupervised worker
+                        self.arbiter.do_send(WorkerFailed {
+                            reason: reason.clone(),
+                            message_type,
+                            message_name,
+                            respawn,
+                        });
+                        if respawn || !self.restart(reason) {
+                            return
+                        }
+                        continue
+                    }
                 },
-                Err(_) => (),
+                // sync arbiter is gone
+                Err(_) => return,
             }
 
-            if self.stopping {
+            if self.stopping && self.supervisor.is_some() {
+                self.stopping = false;
+
+                // stop old actor
+                A::stopping(&mut self.act, ctx);
+                self.state = ActorState::Stopped;
+                A::stopped(&mut self.act, ctx);
+
+                if !self.restart(RestartReason::Stopped) {
+                    return
+                }
+            } else if self.stopping {
                 self.stopping = false;
 
                 // stop old actor
@@ -232,6 +481,44 @@ impl<A> SyncContext<A> where A: Actor<Context=Self> {
             }
         }
     }
+
+    /// Consult restart policy and re-create actor,
+    /// returns `false` if worker has to exit
+    fn restart(&mut self, reason: RestartReason) -> bool {
+        let ctx: &mut SyncContext<A> = unsafe {
+            mem::transmute(self as &mut SyncContext<A>)
+        };
+
+        let restarting = if let Some(ref mut sup) = self.supervisor {
+            match sup.policy.decide(&reason) {
+                RestartAction::Restart => (),
+                RestartAction::RestartAfter(delay) => thread::sleep(delay),
+                RestartAction::Stop | RestartAction::Escalate => {
+                    warn!("Sync worker gave up after {} restarts", sup.restarts);
+                    sup.policy.gave_up();
+                    self.arbiter.do_send(WorkerGaveUp);
+                    return false
+                }
+            }
+            sup.restarting
+        } else {
+            return false
+        };
+
+        // start new actor
+        self.stopping = false;
+        self.state = ActorState::Started;
+        self.act = (*self.factory)();
+        restarting(&mut self.act, &reason, ctx);
+        A::started(&mut self.act, ctx);
+        self.state = ActorState::Running;
+
+        if let Some(ref mut sup) = self.supervisor {
+            sup.restarts += 1;
+            sup.policy.restarted(sup.restarts, &reason);
+        }
+        true
+    }
 }
 
 impl<A> ActorContext for SyncContext<A> where A: Actor<Context=Self>
@@ -259,6 +546,7 @@ pub(crate) struct SyncContextEnvelope<A, M>
 {
     msg: Option<M>,
     tx: Option<SyncSender<M::Result>>,
+    cid: CorrelationId,
     actor: PhantomData<A>,
 }
 
@@ -272,6 +560,7 @@ impl<A, M> SyncContextEnvelope<A, M>
     pub fn new(msg: M, tx: Option<SyncSender<M::Result>>) -> Self {
         SyncContextEnvelope{msg: Some(msg),
                             tx: tx,
+                            cid: CorrelationId::current(),
                             actor: PhantomData}
     }
 }
@@ -289,8 +578,16 @@ impl<A, M> EnvelopeProxy for SyncContextEnvelope<A, M>
         }
 
         if let Some(msg) = self.msg.take() {
-            let mut response = <A as Handler<M>>::handle(act, msg, ctx);
+            let response = handler::dispatch(act, msg, ctx, self.cid);
             response.handle(ctx, tx)
         }
     }
+
+    fn message_type(&self) -> Option<TypeId> {
+        Some(TypeId::of::<M>())
+    }
+
+    fn message_name(&self) -> Option<&'static str> {
+        span::type_name_of::<M>()
+    }
 }
@@ -1,14 +1,17 @@
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use tokio_core::reactor::{Core, Handle};
 use futures::Future;
 use futures::sync::oneshot::{channel, Receiver, Sender};
 
-use actor::Actor;
-use address::{Addr, Syn};
-use arbiter::Arbiter;
+use actor::{Actor, AsyncContext};
+use address::{Addr, Recipient, Syn};
+use arbiter::{Arbiter, DRAIN_POLL_INTERVAL};
+use registry::SystemRegistry;
 use handler::{Handler, Message};
 use context::Context;
-use msgs::{SystemExit, StopArbiter};
+use intercept::{Interceptor, InterceptorId};
+use msgs::{SystemExit, SystemExitGraceful, StopArbiter, StopArbiterGraceful};
 
 /// System is an actor which manages process.
 ///
@@ -53,10 +56,16 @@ use msgs::{SystemExit, StopArbiter};
 pub struct System {
     stop: Option<Sender<i32>>,
     arbiters: HashMap<String, Addr<Syn, Arbiter>>,
+    draining: Option<(i32, Instant)>,
 }
 
 impl Actor for System {
     type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        // system controls graceful stop, it is not drained
+        ctx.exclude_from_drain();
+    }
 }
 
 impl System {
@@ -70,7 +79,7 @@ impl System {
 
         // start system
         let sys = System {
-            arbiters: HashMap::new(), stop: Some(stop_tx)}.start();
+            arbiters: HashMap::new(), stop: Some(stop_tx), draining: None}.start();
         Arbiter::set_system(sys, name);
 
         SystemRunner {
@@ -78,6 +87,130 @@ impl System {
             stop: stop_rx,
         }
     }
+
+    /// Returns system registry
+    ///
+    /// System services get started lazily on first request and can be
+    /// looked up by type from any arbiter of the system.
+    ///
+    /// ```rust
+    /// # extern crate actix;
+    /// use actix::prelude::*;
+    ///
+    /// #[derive(Default)]
+    /// struct Config;
+    ///
+    /// impl Actor for Config {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl actix::Supervised for Config {}
+    ///
+    /// impl SystemService for Config {}
+    ///
+    /// fn main() {
+    ///     let sys = System::new("test");
+    ///
+    ///     let config: Addr<Syn, Config> = System::registry().get::<Config>();
+    /// #   Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    ///     sys.run();
+    /// }
+    /// ```
+    pub fn registry() -> &'static SystemRegistry {
+        Arbiter::system_registry()
+    }
+
+    /// Register recipient under name in system registry
+    ///
+    /// Recipient could be found with `System::lookup()` from any arbiter
+    /// of the system.
+    ///
+    /// ```rust
+    /// # extern crate actix;
+    /// use actix::prelude::*;
+    ///
+    /// struct Charge(u64);
+    ///
+    /// impl Message for Charge {
+    ///     type Result = bool;
+    /// }
+    ///
+    /// struct Payments;
+    ///
+    /// impl Actor for Payments {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// impl Handler<Charge> for Payments {
+    ///     type Result = bool;
+    ///
+    ///     fn handle(&mut self, _: Charge, _: &mut Context<Self>) -> bool {
+    ///         true
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let sys = System::new("test");
+    ///
+    ///     let addr: Addr<Syn, _> = Payments.start();
+    ///     System::register_named("payments", addr.recipient::<Charge>());
+    ///
+    ///     // somewhere else
+    ///     let payments = System::lookup::<Charge>("payments").unwrap();
+    ///     payments.do_send(Charge(10)).unwrap();
+    /// #   Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    ///     sys.run();
+    /// }
+    /// ```
+    pub fn register_named<N, M>(name: N, recipient: Recipient<Syn, M>)
+        where N: Into<String>, M: Message + Send + 'static, M::Result: Send
+    {
+        Arbiter::system_registry().register_named(name, recipient)
+    }
+
+    /// Find recipient of message `M` registered under name
+    pub fn lookup<M>(name: &str) -> Option<Recipient<Syn, M>>
+        where M: Message + Send + 'static, M::Result: Send
+    {
+        Arbiter::system_registry().lookup(name)
+    }
+
+    /// Remove all recipients registered under name
+    pub fn unregister_named(name: &str) {
+        Arbiter::system_registry().unregister_named(name)
+    }
+
+    /// Register interceptor in system registry, it is called around
+    /// message handlers of all actors of the system
+    pub fn add_interceptor<I: Interceptor>(interceptor: I) -> InterceptorId {
+        Arbiter::system_registry().add_interceptor(interceptor)
+    }
+
+    /// Remove interceptor, returns `false` if it is not registered
+    pub fn remove_interceptor(id: InterceptorId) -> bool {
+        Arbiter::system_registry().remove_interceptor(id)
+    }
+
+    /// Stop system gracefully
+    ///
+    /// Actors of all arbiters process pending messages and get stopped,
+    /// then system exits with exit code 0. System exits after `timeout`
+    /// even if some actors are still running. This is shortcut for sending
+    /// `SystemExitGraceful(0, timeout)` message to `System` actor.
+    pub fn stop_graceful(timeout: Duration) {
+        Arbiter::system().do_send(SystemExitGraceful(0, timeout));
+    }
+
+    fn exit(&mut self, code: i32) {
+        // stop arbiters
+        for addr in self.arbiters.values() {
+            addr.do_send(StopArbiter(code));
+        }
+        // stop event loop
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(code);
+        }
+    }
 }
 
 /// Helper object that runs System's event loop
@@ -118,14 +251,28 @@ impl Handler<SystemExit> for System {
 
     fn handle(&mut self, msg: SystemExit, _: &mut Context<Self>)
     {
-        // stop arbiters
+        self.exit(msg.0);
+    }
+}
+
+impl Handler<SystemExitGraceful> for System {
+    type Result = ();
+
+    fn handle(&mut self, msg: SystemExitGraceful, ctx: &mut Context<Self>)
+    {
         for addr in self.arbiters.values() {
-            addr.do_send(StopArbiter(msg.0));
-        }
-        // stop event loop
-        if let Some(stop) = self.stop.take() {
-            let _ = stop.send(msg.0);
+            addr.do_send(StopArbiterGraceful(msg.0, msg.1));
         }
+        Arbiter::start_draining();
+
+        let code = msg.0;
+        let deadline = Instant::now() + msg.1;
+        self.draining = Some((code, deadline));
+        ctx.run_interval(Duration::from_millis(DRAIN_POLL_INTERVAL), move |act, _| {
+            if (act.arbiters.is_empty() && Arbiter::drained()) || Instant::now() >= deadline {
+                act.exit(code);
+            }
+        });
     }
 }
 
@@ -142,6 +289,12 @@ impl Handler<RegisterArbiter> for System {
     type Result = ();
 
     fn handle(&mut self, msg: RegisterArbiter, _: &mut Context<Self>) {
+        // arbiter started during graceful stop
+        if let Some((code, deadline)) = self.draining {
+            let now = Instant::now();
+            let timeout = if deadline > now { deadline - now } else { Duration::new(0, 0) };
+            msg.1.do_send(StopArbiterGraceful(code, timeout));
+        }
         self.arbiters.insert(msg.0, msg.1);
     }
 }
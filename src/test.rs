@@ -0,0 +1,464 @@
+//! Helpers for actor unit tests
+//!
+//! `TestContext` runs an actor without `System` and event loop. Messages
+//! are delivered synchronously and context poll loop is stepped manually,
+//! so actor's behavior could be checked step by step. `TestProbe` records
+//! messages sent to it, so interactions between running actors could be
+//! verified.
+//!
+//! ```rust
+//! # extern crate actix;
+//! use actix::prelude::*;
+//! use actix::test::TestContext;
+//!
+//! struct Counter(usize);
+//!
+//! impl Actor for Counter {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! struct Incr;
+//!
+//! impl Message for Incr {
+//!     type Result = usize;
+//! }
+//!
+//! impl Handler<Incr> for Counter {
+//!     type Result = MessageResult<Incr>;
+//!
+//!     fn handle(&mut self, _: Incr, _: &mut Context<Self>) -> Self::Result {
+//!         self.0 += 1;
+//!         MessageResult(self.0)
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let mut ctx = TestContext::new(Counter(0));
+//!     assert_eq!(ctx.send(Incr).unwrap(), 1);
+//!     assert_eq!(ctx.send(Incr).unwrap(), 2);
+//!     assert_eq!(ctx.actor().0, 2);
+//! }
+//! ```
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use futures::{task, Async, Future, Poll};
+use futures::executor::{self, Notify, Spawn};
+use futures::task::Task;
+
+use actor::{Actor, ActorContext, ActorState, AsyncContext};
+use address::{ActorAddress, Addr, Destination, MailboxError, MessageDestination,
+              MessageDestinationTransport, Recipient, Syn, ToEnvelope, Unsync};
+use clock::{self, Delay};
+use context::Context;
+use handler::{Handler, Message, MessageResult};
+
+pub use clock::TestClock;
+
+/// Records notifications of the test tasks
+struct Flag(AtomicBool);
+
+impl Notify for Flag {
+    fn notify(&self, _: usize) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+thread_local!(
+    /// Number of test contexts that use clock installed by test context
+    static CLOCK_USERS: Cell<usize> = Cell::new(0));
+
+/// Execution context for actor tests
+///
+/// `TestContext` runs in current thread and does not require running
+/// system or arbiter. Actor timers use `TestClock` of current thread, clock
+/// is installed by `TestContext::new()` if current thread has no virtual
+/// clock and is removed once last context that uses it is dropped. Context
+/// keeps actor's address, so actor does not stop until it is stopped
+/// explicitly.
+pub struct TestContext<A> where A: Actor<Context=Context<A>> {
+    ctx: Spawn<Context<A>>,
+    addr: Addr<Unsync, A>,
+    clock: TestClock,
+    /// Clock is installed by test context
+    installed: bool,
+    flag: Arc<Flag>,
+    finished: bool,
+}
+
+impl<A> TestContext<A> where A: Actor<Context=Context<A>> {
+    /// Create test context for the actor
+    ///
+    /// Actor starts on first step of the context.
+    pub fn new(act: A) -> TestContext<A> {
+        TestContext::create(|_| act)
+    }
+
+    /// Create test context, actor is created by the closure
+    ///
+    /// Closure is called immediately, so context could be configured,
+    /// i.e. mailbox capacity could be set.
+    pub fn create<F>(f: F) -> TestContext<A> where F: FnOnce(&mut Context<A>) -> A
+    {
+        let (clock, installed) = match TestClock::current() {
+            Some(clock) => {
+                // clock of other test context is shared
+                let installed = CLOCK_USERS.with(|users| {
+                    let shared = users.get() != 0;
+                    if shared {
+                        users.set(users.get() + 1);
+                    }
+                    shared
+                });
+                (clock, installed)
+            }
+            None => {
+                CLOCK_USERS.with(|users| users.set(1));
+                (TestClock::install(), true)
+            }
+        };
+        let mut ctx = Context::new(None);
+        let act = f(&mut ctx);
+        ctx.set_actor(act);
+        let addr = ctx.unsync_address();
+
+        TestContext {
+            ctx: executor::spawn(ctx),
+            addr,
+            clock,
+            installed,
+            flag: Arc::new(Flag(AtomicBool::new(false))),
+            finished: false,
+        }
+    }
+
+    /// Address of the actor
+    pub fn address<T>(&mut self) -> T where A: ActorAddress<A, T> {
+        self.ctx.get_mut().address()
+    }
+
+    /// Reference to the actor
+    pub fn actor(&mut self) -> &mut A {
+        self.ctx.get_mut().actor()
+    }
+
+    /// Reference to the actor's context
+    pub fn context(&mut self) -> &mut Context<A> {
+        self.ctx.get_mut()
+    }
+
+    /// Actor state
+    pub fn state(&mut self) -> ActorState {
+        self.ctx.get_mut().state()
+    }
+
+    /// Virtual clock of actor timers
+    pub fn clock(&self) -> &TestClock {
+        &self.clock
+    }
+
+    /// Poll context once
+    ///
+    /// Returns `false` if actor is stopped.
+    pub fn step(&mut self) -> bool {
+        if !self.finished {
+            self.flag.0.store(false, Ordering::SeqCst);
+            if let Ok(Async::NotReady) = self.ctx.poll_future_notify(&self.flag, 0) {
+                return true
+            }
+            self.finished = true;
+        }
+        false
+    }
+
+    /// Poll context until all pending work is done
+    ///
+    /// Context is idle when there are no messages in mailbox and none of
+    /// context's futures are ready. Returns `false` if actor is stopped.
+    pub fn run(&mut self) -> bool {
+        while self.step() {
+            if !self.flag.0.load(Ordering::SeqCst) {
+                return true
+            }
+        }
+        false
+    }
+
+    /// Move virtual time forward and run due timers
+    ///
+    /// Returns `false` if actor is stopped.
+    pub fn advance(&mut self, dur: Duration) -> bool {
+        self.clock.advance(dur);
+        self.run()
+    }
+
+    /// Stop actor and run context until actor is stopped
+    pub fn stop(&mut self) {
+        self.ctx.get_mut().stop();
+        self.run();
+    }
+
+    /// Deliver message without waiting for response
+    ///
+    /// Message is handled on next step of the context.
+    pub fn do_send<M>(&mut self, msg: M)
+        where Unsync: MessageDestination<A, M>,
+              <Unsync as Destination<A>>::Transport: MessageDestinationTransport<Unsync, A, M>,
+              M: Message + 'static,
+              A: Handler<M>, A::Context: ToEnvelope<Unsync, A, M>,
+    {
+        self.addr.do_send(msg)
+    }
+
+    /// Deliver message and run context until response is ready
+    ///
+    /// Returns `MailboxError::Timeout` if context becomes idle before
+    /// response is ready, i.e. response waits for a timer, and
+    /// `MailboxError::Closed` if actor stops without responding.
+    pub fn send<M>(&mut self, msg: M) -> Result<M::Result, MailboxError>
+        where Unsync: MessageDestination<A, M>,
+              <Unsync as Destination<A>>::Transport: MessageDestinationTransport<Unsync, A, M>,
+              M: Message + 'static,
+              A: Handler<M>, A::Context: ToEnvelope<Unsync, A, M>,
+    {
+        let mut req = executor::spawn(self.addr.send(msg));
+        loop {
+            let alive = self.step();
+            if let Async::Ready(res) = req.poll_future_notify(&self.flag, 0)? {
+                return Ok(res)
+            }
+            if !alive {
+                return Err(MailboxError::Closed)
+            }
+            if !self.flag.0.load(Ordering::SeqCst) {
+                return Err(MailboxError::Timeout)
+            }
+        }
+    }
+}
+
+impl<A> Drop for TestContext<A> where A: Actor<Context=Context<A>> {
+    fn drop(&mut self) {
+        if !self.installed {
+            return
+        }
+        let last = CLOCK_USERS.with(|users| {
+            users.set(users.get() - 1);
+            users.get() == 0
+        });
+        // clock could be replaced by other clock meanwhile
+        let current = TestClock::current().map(|clock| clock.same(&self.clock));
+        if last && current == Some(true) {
+            TestClock::uninstall();
+        }
+    }
+}
+
+/// Error of `TestProbe` expectations
+#[derive(Fail, Debug)]
+pub enum ProbeError {
+    /// Expected message was not received in time
+    #[fail(display="Expected message was not received")]
+    Timeout,
+    /// Probe received message of other type, message stays in probe's queue
+    #[fail(display="Unexpected message")]
+    Unexpected,
+}
+
+/// Message recorded by `TestProbe`
+#[derive(Debug)]
+pub struct Received<M> {
+    /// Received message
+    pub msg: M,
+    /// Time message was received at
+    pub at: Instant,
+}
+
+struct ProbeState {
+    received: VecDeque<Received<Box<Any + Send>>>,
+    tasks: Vec<Task>,
+}
+
+impl ProbeState {
+    fn register(&mut self) {
+        if !self.tasks.iter().any(|t| t.will_notify_current()) {
+            self.tasks.push(task::current());
+        }
+    }
+}
+
+/// Actor that records messages for `TestProbe`
+///
+/// Probe handles message of any type and responds with default value
+/// of message's result.
+pub struct Probe(Arc<Mutex<ProbeState>>);
+
+impl Actor for Probe {
+    type Context = Context<Self>;
+}
+
+impl<M> Handler<M> for Probe where M: Message + Send + 'static, M::Result: Default + Send {
+    type Result = MessageResult<M>;
+
+    fn handle(&mut self, msg: M, _: &mut Context<Self>) -> MessageResult<M> {
+        let mut state = self.0.lock().unwrap();
+        state.received.push_back(Received{msg: Box::new(msg), at: clock::now()});
+        for task in state.tasks.drain(..) {
+            task.notify();
+        }
+        MessageResult(M::Result::default())
+    }
+}
+
+/// Probe for verifying interactions between actors
+///
+/// Probe actor is started in current arbiter, its recipients could be
+/// passed to actors under test instead of real recipients. Received
+/// messages are queued together with receive time, expectations check
+/// messages in order of arrival.
+///
+/// ```rust
+/// # extern crate actix;
+/// # extern crate futures;
+/// use std::time::Duration;
+/// use futures::Future;
+/// use actix::prelude::*;
+/// use actix::test::TestProbe;
+///
+/// struct Ping;
+///
+/// impl Message for Ping {
+///     type Result = ();
+/// }
+///
+/// fn main() {
+///     let sys = System::new("test");
+///     let probe = TestProbe::new();
+///
+///     probe.recipient::<Ping>().do_send(Ping).unwrap();
+///     Arbiter::handle().spawn(
+///         probe.expect_msg::<Ping>(Duration::from_secs(1))
+///             .then(|res| {
+///                 assert!(res.is_ok());
+///                 Arbiter::system().do_send(actix::msgs::SystemExit(0));
+///                 Ok(())
+///             }));
+///     sys.run();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct TestProbe {
+    state: Arc<Mutex<ProbeState>>,
+    addr: Addr<Syn, Probe>,
+}
+
+impl TestProbe {
+    /// Start probe actor in current arbiter
+    pub fn new() -> TestProbe {
+        let state = Arc::new(Mutex::new(ProbeState {
+            received: VecDeque::new(), tasks: Vec::new()}));
+        let addr = Probe(Arc::clone(&state)).start();
+        TestProbe{state, addr}
+    }
+
+    /// Address of the probe actor
+    pub fn address(&self) -> Addr<Syn, Probe> {
+        self.addr.clone()
+    }
+
+    /// Recipient of the probe actor for messages of type `M`
+    pub fn recipient<M>(&self) -> Recipient<Syn, M>
+        where M: Message + Send + 'static, M::Result: Default + Send
+    {
+        self.addr.clone().recipient()
+    }
+
+    /// Number of received messages that are not checked yet
+    pub fn pending(&self) -> usize {
+        self.state.lock().unwrap().received.len()
+    }
+
+    /// Expect next received message to be of type `M`
+    ///
+    /// Resolves with first message from probe's queue. Fails with
+    /// `ProbeError::Timeout` if no message is received within timeout,
+    /// and with `ProbeError::Unexpected` if message is of other type.
+    pub fn expect_msg<M: Message + 'static>(&self, timeout: Duration) -> ExpectMsg<M> {
+        ExpectMsg{state: Arc::clone(&self.state), timeout: Delay::new(timeout), m: PhantomData}
+    }
+
+    /// Expect no messages during specified duration
+    ///
+    /// Fails with `ProbeError::Unexpected` if probe receives any message.
+    pub fn expect_no_msg(&self, dur: Duration) -> ExpectNoMsg {
+        ExpectNoMsg{state: Arc::clone(&self.state), timeout: Delay::new(dur)}
+    }
+}
+
+impl Default for TestProbe {
+    fn default() -> TestProbe {
+        TestProbe::new()
+    }
+}
+
+/// Future returned by `TestProbe::expect_msg()`
+#[must_use = "futures do nothing unless polled"]
+pub struct ExpectMsg<M> {
+    state: Arc<Mutex<ProbeState>>,
+    timeout: Delay,
+    m: PhantomData<M>,
+}
+
+impl<M: Message + 'static> Future for ExpectMsg<M> {
+    type Item = Received<M>;
+    type Error = ProbeError;
+
+    fn poll(&mut self) -> Poll<Received<M>, ProbeError> {
+        {
+            let mut state = self.state.lock().unwrap();
+            if let Some(item) = state.received.pop_front() {
+                if !item.msg.is::<M>() {
+                    state.received.push_front(item);
+                    return Err(ProbeError::Unexpected)
+                }
+                let msg = *item.msg.downcast::<M>().unwrap();
+                return Ok(Async::Ready(Received{msg, at: item.at}))
+            }
+            state.register();
+        }
+        match self.timeout.poll() {
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            _ => Err(ProbeError::Timeout),
+        }
+    }
+}
+
+/// Future returned by `TestProbe::expect_no_msg()`
+#[must_use = "futures do nothing unless polled"]
+pub struct ExpectNoMsg {
+    state: Arc<Mutex<ProbeState>>,
+    timeout: Delay,
+}
+
+impl Future for ExpectNoMsg {
+    type Item = ();
+    type Error = ProbeError;
+
+    fn poll(&mut self) -> Poll<(), ProbeError> {
+        {
+            let mut state = self.state.lock().unwrap();
+            if !state.received.is_empty() {
+                return Err(ProbeError::Unexpected)
+            }
+            state.register();
+        }
+        match self.timeout.poll() {
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            _ => Ok(Async::Ready(())),
+        }
+    }
+}
@@ -1,11 +1,11 @@
-use std::time::Duration;
-use futures::{Async, Future, Poll};
+use std::time::{Duration, Instant, SystemTime};
+use futures::{Async, Future, Poll, Stream};
 use futures::unsync::oneshot;
-use tokio_core::reactor::Timeout;
+use clock::{self, Delay, Interval};
+use schedule::Schedule;
 
 use fut::ActorFuture;
-use actor::Actor;
-use arbiter::Arbiter;
+use actor::{Actor, ActorContext, ActorState};
 
 pub struct Condition<T> where T: Clone {
     waiters: Vec<oneshot::Sender<T>>,
@@ -35,7 +35,7 @@ impl<T> Default for Condition<T> where T: Clone {
 pub(crate)
 struct TimerFunc<A> where A: Actor {
     f: Option<Box<TimerFuncBox<A>>>,
-    timeout: Timeout,
+    timeout: Delay,
 }
 
 impl<A> TimerFunc<A> where A: Actor {
@@ -44,7 +44,7 @@ impl<A> TimerFunc<A> where A: Actor {
     {
         TimerFunc {
             f: Some(Box::new(f)),
-            timeout: Timeout::new(timeout, Arbiter::handle()).unwrap()}
+            timeout: Delay::new(timeout)}
     }
 }
 
@@ -81,3 +81,127 @@ impl<A> ActorFuture for TimerFunc<A> where A: Actor {
         }
     }
 }
+
+pub(crate)
+struct IntervalFunc<A> where A: Actor {
+    f: Box<IntervalFuncBox<A>>,
+    interval: Interval,
+}
+
+impl<A> IntervalFunc<A> where A: Actor {
+    pub fn new<F>(interval: Duration, f: F) -> IntervalFunc<A>
+        where F: FnMut(& mut A, & mut A::Context) + 'static
+    {
+        IntervalFunc {
+            f: Box::new(f),
+            interval: Interval::new(interval)}
+    }
+}
+
+trait IntervalFuncBox<A: Actor>: 'static {
+    fn call(&mut self, _: &mut A, _: &mut A::Context);
+}
+
+impl<A: Actor, F: FnMut(&mut A, &mut A::Context) + 'static> IntervalFuncBox<A> for F {
+    fn call(&mut self, act: &mut A, ctx: &mut A::Context) {
+        self(act, ctx)
+    }
+}
+
+#[doc(hidden)]
+impl<A> ActorFuture for IntervalFunc<A> where A: Actor {
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut Self::Actor, ctx: &mut <Self::Actor as Actor>::Context)
+            -> Poll<Self::Item, Self::Error>
+    {
+        loop {
+            match self.interval.poll() {
+                Ok(Async::Ready(Some(_))) => {
+                    self.f.call(act, ctx);
+                    // closure could stop actor
+                    if ctx.state() != ActorState::Running {
+                        return Ok(Async::Ready(()))
+                    }
+                }
+                Ok(Async::Ready(None)) => return Ok(Async::Ready(())),
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(_) => unreachable!(),
+            }
+        }
+    }
+}
+
+pub(crate)
+struct ScheduleFunc<A> where A: Actor {
+    f: Box<IntervalFuncBox<A>>,
+    schedule: Schedule,
+    wall: SystemTime,
+    start: Instant,
+    next: Option<(SystemTime, Delay)>,
+}
+
+impl<A> ScheduleFunc<A> where A: Actor {
+    pub fn new<F>(schedule: Schedule, f: F) -> ScheduleFunc<A>
+        where F: FnMut(& mut A, & mut A::Context) + 'static
+    {
+        let mut func = ScheduleFunc {
+            f: Box::new(f),
+            schedule,
+            wall: clock::system_now(),
+            start: clock::now(),
+            next: None};
+        let now = func.now();
+        func.next = func.delay(now, now);
+        func
+    }
+
+    /// Wall time, moves with `TestClock` if one is installed
+    fn now(&self) -> SystemTime {
+        self.wall + (clock::now() - self.start)
+    }
+
+    fn delay(&self, prev: SystemTime, now: SystemTime) -> Option<(SystemTime, Delay)> {
+        self.schedule.next(prev, now).map(|next| {
+            let dur = next.duration_since(now).unwrap_or_else(|_| Duration::new(0, 0));
+            (next, Delay::new(dur))
+        })
+    }
+}
+
+#[doc(hidden)]
+impl<A> ActorFuture for ScheduleFunc<A> where A: Actor {
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut Self::Actor, ctx: &mut <Self::Actor as Actor>::Context)
+            -> Poll<Self::Item, Self::Error>
+    {
+        loop {
+            let deadline = match self.next {
+                Some((deadline, ref mut delay)) => match delay.poll() {
+                    Ok(Async::Ready(_)) => deadline,
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Err(_) => unreachable!(),
+                },
+                None => return Ok(Async::Ready(())),
+            };
+            let now = self.now();
+            if now < deadline {
+                let dur = deadline.duration_since(now).unwrap();
+                self.next = Some((deadline, Delay::new(dur)));
+                continue
+            }
+
+            self.f.call(act, ctx);
+            // closure could stop actor
+            if ctx.state() != ActorState::Running {
+                return Ok(Async::Ready(()))
+            }
+            self.next = self.delay(deadline, now);
+        }
+    }
+}
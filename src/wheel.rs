@@ -0,0 +1,617 @@
+//! Hashed timer wheel
+//!
+//! Timers of all actors of an arbiter are kept in a single wheel. Timer
+//! with deadline within one revolution is stored in a slot of its deadline
+//! tick, slots are intrusive doubly linked lists of entries and occupied
+//! slots are tracked in a bitmap, so insert, cancel and lookup of the
+//! earliest tick are O(1). Timers with deadlines more than one revolution
+//! away are kept ordered by deadline and move into slots once their tick
+//! comes within a revolution. Wheel is driven by a single event loop timeout
+//! that is armed for the earliest pending tick.
+use std::cell::RefCell;
+use std::cmp;
+use std::collections::BTreeSet;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use futures::{task, Async, Future, Poll};
+use futures::task::Task;
+use tokio_core::reactor::Timeout as TokioTimeout;
+
+use arbiter::Arbiter;
+
+/// Number of slots of the wheel
+const SLOTS: usize = 256;
+
+/// Number of words of the occupied slots bitmap
+const WORDS: usize = SLOTS / 64;
+
+/// Wheel resolution in milliseconds
+const TICK: u64 = 1;
+
+const NIL: usize = ::std::usize::MAX;
+
+thread_local!(static WHEEL: RefCell<Wheel> = RefCell::new(Wheel::new(Instant::now())));
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum State {
+    Pending,
+    Fired,
+    Free,
+}
+
+struct Entry {
+    tick: u64,
+    state: State,
+    /// Timer is more than one revolution away and is not linked to a slot
+    far: bool,
+    task: Option<Task>,
+    prev: usize,
+    next: usize,
+}
+
+/// Part of the wheel that is shared with timers moved to other threads
+struct Remote {
+    alive: AtomicBool,
+    /// Timers dropped in other threads
+    cancelled: Mutex<Vec<usize>>,
+    has_cancelled: AtomicBool,
+}
+
+impl Remote {
+    fn cancel(&self, key: usize) {
+        self.cancelled.lock().unwrap().push(key);
+        self.has_cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+pub(crate) struct Wheel {
+    start: Instant,
+    /// Last processed tick
+    tick: u64,
+    slots: Vec<usize>,
+    occupied: [u64; WORDS],
+    far: BTreeSet<(u64, usize)>,
+    entries: Vec<Entry>,
+    free: Vec<usize>,
+    pending: usize,
+    /// Tick driver should be armed for
+    armed: Option<u64>,
+    driver: Option<Task>,
+    running: bool,
+    remote: Arc<Remote>,
+}
+
+impl Wheel {
+    pub(crate) fn new(start: Instant) -> Wheel {
+        Wheel {
+            start,
+            tick: 0,
+            slots: vec![NIL; SLOTS],
+            occupied: [0; WORDS],
+            far: BTreeSet::new(),
+            entries: Vec::new(),
+            free: Vec::new(),
+            pending: 0,
+            armed: None,
+            driver: None,
+            running: false,
+            remote: Arc::new(Remote {
+                alive: AtomicBool::new(true),
+                cancelled: Mutex::new(Vec::new()),
+                has_cancelled: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Tick of the `deadline`, rounded up so timer never fires early
+    fn tick_of(&self, deadline: Instant) -> u64 {
+        if deadline <= self.start {
+            0
+        } else {
+            ticks(deadline - self.start)
+        }
+    }
+
+    fn instant_of(&self, tick: u64) -> Instant {
+        let ms = tick * TICK;
+        self.start + Duration::new(ms / 1000, (ms % 1000) as u32 * 1_000_000)
+    }
+
+    /// Add timer that fires at `tick`, returns key of the timer
+    pub(crate) fn insert(&mut self, tick: u64) -> usize {
+        self.drain_cancelled();
+
+        let entry = Entry {
+            tick, state: State::Pending, far: false, task: None, prev: NIL, next: NIL };
+        let key = match self.free.pop() {
+            Some(key) => {
+                self.entries[key] = entry;
+                key
+            }
+            None => {
+                self.entries.push(entry);
+                self.entries.len() - 1
+            }
+        };
+        self.schedule(key);
+        key
+    }
+
+    /// Link pending timer to its slot or to far timers
+    fn schedule(&mut self, key: usize) {
+        let tick = self.entries[key].tick;
+        if tick <= self.tick {
+            self.entries[key].state = State::Fired;
+        } else {
+            if tick > self.tick + SLOTS as u64 {
+                self.entries[key].far = true;
+                self.far.insert((tick, key));
+            } else {
+                self.link(key);
+            }
+            self.pending += 1;
+            if self.armed.map(|armed| tick < armed).unwrap_or(true) {
+                self.armed = Some(tick);
+                if let Some(ref driver) = self.driver {
+                    driver.notify();
+                }
+            }
+        }
+    }
+
+    /// Check if timer fired, otherwise current task is notified once it fires
+    pub(crate) fn poll(&mut self, key: usize) -> bool {
+        let entry = &mut self.entries[key];
+        match entry.state {
+            State::Pending => {
+                if !entry.task.as_ref().map(|t| t.will_notify_current()).unwrap_or(false) {
+                    entry.task = Some(task::current());
+                }
+                false
+            }
+            State::Fired => true,
+            State::Free => unreachable!(),
+        }
+    }
+
+    /// Move fired timer to new tick, used by intervals
+    ///
+    /// Timer keeps its key, so key does not go through free list.
+    pub(crate) fn reset(&mut self, key: usize, tick: u64) {
+        self.cancel(key);
+        {
+            let entry = &mut self.entries[key];
+            entry.tick = tick;
+            entry.state = State::Pending;
+        }
+        self.schedule(key);
+    }
+
+    /// Cancel timer and free its key
+    pub(crate) fn remove(&mut self, key: usize) {
+        self.cancel(key);
+        let entry = &mut self.entries[key];
+        entry.state = State::Free;
+        entry.task = None;
+        self.free.push(key);
+    }
+
+    /// Unlink pending timer
+    fn cancel(&mut self, key: usize) {
+        if self.entries[key].state == State::Pending {
+            if self.entries[key].far {
+                self.far.remove(&(self.entries[key].tick, key));
+                self.entries[key].far = false;
+            } else {
+                self.unlink(key);
+            }
+            self.pending -= 1;
+        }
+    }
+
+    /// Remove timers that got dropped in other threads
+    fn drain_cancelled(&mut self) {
+        if self.remote.has_cancelled.swap(false, Ordering::SeqCst) {
+            let keys = mem::replace(&mut *self.remote.cancelled.lock().unwrap(), Vec::new());
+            for key in keys {
+                self.remove(key);
+            }
+        }
+    }
+
+    /// Fire all timers with deadline up to `tick`
+    pub(crate) fn advance(&mut self, tick: u64) {
+        self.drain_cancelled();
+        if tick <= self.tick {
+            return
+        }
+        while let Some(t) = self.first_slot() {
+            if t > tick {
+                break
+            }
+            let slot = (t % SLOTS as u64) as usize;
+            let mut key = mem::replace(&mut self.slots[slot], NIL);
+            self.occupied[slot / 64] &= !(1 << (slot % 64));
+            while key != NIL {
+                let next = self.entries[key].next;
+                self.entries[key].prev = NIL;
+                self.entries[key].next = NIL;
+                self.fire(key);
+                key = next;
+            }
+        }
+        self.tick = tick;
+
+        // far timers that came within a revolution
+        loop {
+            let (t, key) = match self.far.iter().next() {
+                Some(&first) => first,
+                None => break,
+            };
+            if t > self.tick + SLOTS as u64 {
+                break
+            }
+            self.far.remove(&(t, key));
+            self.entries[key].far = false;
+            if t <= self.tick {
+                self.fire(key);
+            } else {
+                self.link(key);
+            }
+        }
+    }
+
+    fn fire(&mut self, key: usize) {
+        self.pending -= 1;
+        let entry = &mut self.entries[key];
+        entry.state = State::Fired;
+        if let Some(task) = entry.task.take() {
+            task.notify();
+        }
+    }
+
+    /// Earliest pending tick
+    pub(crate) fn next_tick(&self) -> Option<u64> {
+        self.first_slot().or_else(|| self.far.iter().next().map(|&(tick, _)| tick))
+    }
+
+    /// Tick of the first occupied slot after current tick
+    fn first_slot(&self) -> Option<u64> {
+        let start = ((self.tick + 1) % SLOTS as u64) as usize;
+        let mut idx = start;
+        let mut checked = 0;
+        while checked <= SLOTS {
+            let bits = self.occupied[idx / 64] >> (idx % 64);
+            if bits != 0 {
+                let found = idx + bits.trailing_zeros() as usize;
+                return Some(self.tick + 1 + ((found + SLOTS - start) % SLOTS) as u64)
+            }
+            checked += 64 - idx % 64;
+            idx = (idx + 64 - idx % 64) % SLOTS;
+        }
+        None
+    }
+
+    fn link(&mut self, key: usize) {
+        let slot = (self.entries[key].tick % SLOTS as u64) as usize;
+        let head = self.slots[slot];
+        self.entries[key].next = head;
+        self.entries[key].prev = NIL;
+        if head != NIL {
+            self.entries[head].prev = key;
+        }
+        self.slots[slot] = key;
+        self.occupied[slot / 64] |= 1 << (slot % 64);
+    }
+
+    fn unlink(&mut self, key: usize) {
+        let (prev, next) = (self.entries[key].prev, self.entries[key].next);
+        if prev != NIL {
+            self.entries[prev].next = next;
+        } else {
+            let slot = (self.entries[key].tick % SLOTS as u64) as usize;
+            self.slots[slot] = next;
+            if next == NIL {
+                self.occupied[slot / 64] &= !(1 << (slot % 64));
+            }
+        }
+        if next != NIL {
+            self.entries[next].prev = prev;
+        }
+        self.entries[key].prev = NIL;
+        self.entries[key].next = NIL;
+    }
+
+    /// Spawn driver if it is not running and there are pending timers
+    fn start_driver(&mut self) {
+        if !self.running && self.pending > 0 {
+            self.running = true;
+            Arbiter::handle().spawn(Driver::new(self));
+        }
+    }
+}
+
+impl Drop for Wheel {
+    fn drop(&mut self) {
+        self.remote.alive.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Drop wheel of current thread, used when arbiter replaces its event loop
+///
+/// Driver of the old wheel is gone with the old event loop, new wheel spawns
+/// driver on the new event loop.
+pub(crate) fn reset() {
+    let old = WHEEL.with(|wheel| {
+        mem::replace(&mut *wheel.borrow_mut(), Wheel::new(Instant::now()))
+    });
+    drop(old);
+}
+
+/// Number of ticks in `dur`, rounded up
+pub(crate) fn ticks(dur: Duration) -> u64 {
+    let ms = dur.as_secs() * 1000 + u64::from((dur.subsec_nanos() + 999_999) / 1_000_000);
+    (ms + TICK - 1) / TICK
+}
+
+/// Timer of current thread's wheel
+///
+/// Timer could be polled and dropped in other threads, e.g. as a timeout
+/// of `Syn` request. Timer that is polled in other thread moves to the wheel
+/// of that thread, so that thread has to run an arbiter. Timer that is
+/// dropped in other thread gets removed by its wheel on next insert or tick.
+pub(crate) struct Timer {
+    remote: Arc<Remote>,
+    key: usize,
+    deadline: Instant,
+}
+
+impl Timer {
+    /// Timer that fires after `dur`
+    pub(crate) fn new(dur: Duration) -> Timer {
+        Timer::at(Instant::now() + dur)
+    }
+
+    fn at(deadline: Instant) -> Timer {
+        WHEEL.with(|wheel| {
+            let mut w = wheel.borrow_mut();
+            let tick = w.tick_of(deadline);
+            let key = w.insert(tick);
+            w.start_driver();
+            Timer{remote: Arc::clone(&w.remote), key, deadline}
+        })
+    }
+
+    /// Register timer with wheel of current thread
+    fn migrate(&mut self, w: &mut Wheel) {
+        if !Arc::ptr_eq(&self.remote, &w.remote) {
+            self.remote.cancel(self.key);
+            self.key = w.insert(w.tick_of(self.deadline));
+            self.remote = Arc::clone(&w.remote);
+            w.start_driver();
+        }
+    }
+
+    /// Check if timer fired
+    pub(crate) fn poll(&mut self) -> bool {
+        WHEEL.with(|wheel| {
+            let mut w = wheel.borrow_mut();
+            self.migrate(&mut w);
+            w.poll(self.key)
+        })
+    }
+
+    /// Re-arm timer to fire `dur` after its previous deadline
+    pub(crate) fn reset(&mut self, dur: Duration) {
+        WHEEL.with(|wheel| {
+            let mut w = wheel.borrow_mut();
+            self.migrate(&mut w);
+            let tick = w.entries[self.key].tick + cmp::max(ticks(dur), 1);
+            w.reset(self.key, tick);
+            self.deadline = w.instant_of(tick);
+            w.start_driver();
+        })
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        // wheel of the timer is gone, e.g. timer gets dropped during
+        // destruction of thread locals
+        if !self.remote.alive.load(Ordering::SeqCst) {
+            return
+        }
+        WHEEL.with(|wheel| {
+            let mut w = wheel.borrow_mut();
+            if Arc::ptr_eq(&self.remote, &w.remote) {
+                w.remove(self.key);
+            } else {
+                self.remote.cancel(self.key);
+            }
+        })
+    }
+}
+
+/// Event loop timeout armed for the earliest pending tick of the wheel
+struct Driver {
+    timeout: TokioTimeout,
+    armed: u64,
+}
+
+impl Driver {
+    fn new(w: &Wheel) -> Driver {
+        let armed = w.armed.unwrap_or(w.tick);
+        let timeout = TokioTimeout::new_at(w.instant_of(armed), Arbiter::handle()).unwrap();
+        Driver{timeout, armed}
+    }
+}
+
+impl Future for Driver {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        WHEEL.with(|wheel| {
+            let mut wheel = wheel.borrow_mut();
+            loop {
+                match wheel.armed {
+                    None => {
+                        wheel.running = false;
+                        wheel.driver = None;
+                        return Ok(Async::Ready(()))
+                    }
+                    Some(tick) if tick != self.armed => {
+                        self.armed = tick;
+                        self.timeout.reset(wheel.instant_of(tick));
+                    }
+                    _ => (),
+                }
+                match self.timeout.poll() {
+                    Ok(Async::NotReady) => {
+                        wheel.driver = Some(task::current());
+                        return Ok(Async::NotReady)
+                    }
+                    Ok(Async::Ready(())) => {
+                        let now = wheel.tick_of(Instant::now());
+                        // timeout could fire slightly ahead of the tick
+                        let now = cmp::max(now.saturating_sub(1), self.armed);
+                        wheel.advance(now);
+                        wheel.armed = wheel.next_tick();
+                        self.armed = ::std::u64::MAX;
+                    }
+                    Err(err) => {
+                        // pending timers do not fire, next timer that gets
+                        // inserted or reset starts new driver
+                        error!("Timer wheel driver failed: {}", err);
+                        wheel.running = false;
+                        wheel.driver = None;
+                        return Err(())
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fired(wheel: &Wheel, key: usize) -> bool {
+        wheel.entries[key].state == State::Fired
+    }
+
+    #[test]
+    fn test_wheel_fire() {
+        let mut wheel = Wheel::new(Instant::now());
+        let first = wheel.insert(5);
+        let second = wheel.insert(5 + SLOTS as u64);
+        let third = wheel.insert(3);
+        assert_eq!(wheel.next_tick(), Some(3));
+
+        wheel.advance(4);
+        assert!(fired(&wheel, third));
+        assert!(!fired(&wheel, first));
+        assert_eq!(wheel.next_tick(), Some(5));
+
+        wheel.advance(5);
+        assert!(fired(&wheel, first));
+        assert!(!fired(&wheel, second));
+        assert_eq!(wheel.next_tick(), Some(5 + SLOTS as u64));
+
+        wheel.advance(10 * SLOTS as u64);
+        assert!(fired(&wheel, second));
+        assert_eq!(wheel.next_tick(), None);
+    }
+
+    #[test]
+    fn test_wheel_remove() {
+        let mut wheel = Wheel::new(Instant::now());
+        let keys: Vec<_> = (0..1000).map(|idx| wheel.insert(10 + idx % 7)).collect();
+        for key in &keys[..999] {
+            wheel.remove(*key);
+        }
+        assert_eq!(wheel.pending, 1);
+        assert_eq!(wheel.next_tick(), Some(10 + 999 % 7));
+
+        // keys are reused
+        let key = wheel.insert(20);
+        assert!(keys.contains(&key));
+        assert_eq!(wheel.entries.len(), 1000);
+
+        wheel.advance(20);
+        assert!(fired(&wheel, key));
+        assert!(fired(&wheel, keys[999]));
+        assert_eq!(wheel.pending, 0);
+    }
+
+    #[test]
+    fn test_wheel_far() {
+        let mut wheel = Wheel::new(Instant::now());
+        let far: Vec<_> = (0..10).map(|idx| wheel.insert(10 * SLOTS as u64 - idx)).collect();
+        let near = wheel.insert(SLOTS as u64);
+        assert_eq!(wheel.far.len(), 10);
+        assert_eq!(wheel.next_tick(), Some(SLOTS as u64));
+
+        wheel.remove(far[9]);
+        wheel.advance(SLOTS as u64);
+        assert!(fired(&wheel, near));
+        assert_eq!(wheel.next_tick(), Some(10 * SLOTS as u64 - 8));
+
+        // far timers move to slots once they are within a revolution
+        wheel.advance(9 * SLOTS as u64);
+        assert!(wheel.far.is_empty());
+        assert_eq!(wheel.next_tick(), Some(10 * SLOTS as u64 - 8));
+
+        wheel.advance(10 * SLOTS as u64 - 5);
+        assert!(fired(&wheel, far[8]));
+        assert!(fired(&wheel, far[5]));
+        assert!(!fired(&wheel, far[4]));
+        assert_eq!(wheel.next_tick(), Some(10 * SLOTS as u64 - 4));
+        assert_eq!(wheel.pending, 5);
+    }
+
+    #[test]
+    fn test_wheel_remote_cancel() {
+        let mut wheel = Wheel::new(Instant::now());
+        let key = wheel.insert(5);
+        wheel.remote.cancel(key);
+        assert_eq!(wheel.pending, 1);
+
+        wheel.advance(1);
+        assert_eq!(wheel.pending, 0);
+        assert_eq!(wheel.next_tick(), None);
+        assert_eq!(wheel.entries[key].state, State::Free);
+    }
+
+    #[test]
+    fn test_wheel_reset_remote_cancel() {
+        let mut wheel = Wheel::new(Instant::now());
+        let interval = wheel.insert(2);
+        let dropped = wheel.insert(5);
+        wheel.advance(2);
+        assert!(fired(&wheel, interval));
+
+        // interval keeps its key while dropped timer waits for removal
+        wheel.remote.cancel(dropped);
+        wheel.reset(interval, 4);
+        let key = wheel.insert(6);
+        assert_eq!(key, dropped);
+        assert_eq!(wheel.pending, 2);
+        assert_eq!(wheel.next_tick(), Some(4));
+
+        wheel.advance(4);
+        assert!(fired(&wheel, interval));
+        assert!(!fired(&wheel, key));
+        assert_eq!(wheel.free.len(), 0);
+    }
+
+    #[test]
+    fn test_ticks() {
+        assert_eq!(ticks(Duration::from_millis(5)), 5);
+        assert_eq!(ticks(Duration::new(0, 1)), 1);
+        assert_eq!(ticks(Duration::new(1, 1_500_000)), 1002);
+    }
+}
@@ -114,3 +114,46 @@ pub fn response_derive_one() {
 
     system.run();
 }
+
+#[derive(Debug, PartialEq)]
+enum SumError {
+    Overflow,
+}
+
+#[derive(Message)]
+#[rtype(result="Result<u64, SumError>")]
+struct CheckedSum(u64, u64);
+
+struct CheckedSumActor;
+
+impl Actor for CheckedSumActor {
+    type Context = Context<Self>;
+}
+
+impl Handler<CheckedSum> for CheckedSumActor {
+    type Result = Result<u64, SumError>;
+
+    fn handle(&mut self, message: CheckedSum, _context: &mut Context<Self>) -> Self::Result {
+        message.0.checked_add(message.1).ok_or(SumError::Overflow)
+    }
+}
+
+#[test]
+pub fn derive_result_custom_error() {
+    let system = System::new("test");
+    let addr: Addr<Unsync, _> = CheckedSumActor.start();
+    let res = addr.send(CheckedSum(10, 5))
+        .join(addr.send(CheckedSum(u64::max_value(), 1)));
+
+    system.handle().spawn(res.then(|res| {
+        match res {
+            Ok(result) => assert_eq!(result, (Ok(15), Err(SumError::Overflow))),
+            _ => panic!("Something went wrong"),
+        }
+
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        future::result(Ok(()))
+    }));
+
+    system.run();
+}
@@ -103,6 +103,49 @@ fn test_stream_with_error_no_stop() {
 }
 
 
+struct StartedActor(Arc<AtomicUsize>, Arc<AtomicUsize>);
+
+impl Actor for StartedActor {
+    type Context = actix::Context<Self>;
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    }
+}
+
+impl StreamHandler<Num, ()> for StartedActor {
+
+    fn started(&mut self, _: &mut Context<StartedActor>) {
+        // no items handled yet
+        assert_eq!(self.1.load(Ordering::Relaxed), 0);
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn handle(&mut self, msg: Num, _: &mut Context<StartedActor>) {
+        self.1.fetch_add(msg.0, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_stream_started() {
+    let sys = System::new("test");
+    let started = Arc::new(AtomicUsize::new(0));
+    let count = Arc::new(AtomicUsize::new(0));
+    let items = vec![Num(1), Num(1), Num(1)];
+
+    let act_started = Arc::clone(&started);
+    let act_count = Arc::clone(&count);
+    StartedActor::create::<(), _>(move |ctx| {
+        ctx.add_stream(futures::stream::iter_ok::<_, ()>(items));
+        StartedActor(act_started, act_count)
+    });
+
+    sys.run();
+    assert_eq!(started.load(Ordering::Relaxed), 1);
+    assert_eq!(count.load(Ordering::Relaxed), 3);
+}
+
+
 struct MySyncActor {
     started: Arc<AtomicUsize>,
     stopping: Arc<AtomicUsize>,
@@ -1,8 +1,14 @@
 extern crate actix;
 extern crate futures;
-use futures::Future;
+extern crate tokio_core;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::{future, Future};
+use tokio_core::reactor::Timeout;
 use actix::prelude::*;
 use actix::actors::{self, signal};
+use actix::actors::eventbus::{self, EventBus};
 
 
 #[test]
@@ -72,3 +78,118 @@ fn test_signal_int() {
     });
     sys.run();
 }
+
+struct Work(Arc<Mutex<Vec<usize>>>);
+
+impl Actor for Work {
+    type Context = Context<Self>;
+}
+
+impl Handler<Event> for Work {
+    type Result = ();
+
+    fn handle(&mut self, msg: Event, _: &mut Context<Self>) {
+        self.0.lock().unwrap().push(msg.0);
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn test_signal_no_subscribers() {
+    let sys = System::new("test");
+    let done = Arc::new(Mutex::new(Vec::new()));
+    let addr: Addr<Syn, _> = Work(Arc::clone(&done)).start();
+    Arbiter::handle().spawn_fn(move || {
+        for i in 0..3 {
+            addr.do_send(Event(i));
+        }
+        // system stops gracefully without explicit handler
+        signal::ProcessSignals::from_registry().do_send(signal::SignalType::Term);
+        Ok(())
+    });
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*done.lock().unwrap(), vec![0, 1, 2]);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_signal_graceful_timeout() {
+    let sys = System::new("test");
+    let _: Addr<Syn, _> =
+        signal::DefaultSignalsHandler::new(Duration::from_millis(10)).start();
+    let _: Addr<Syn, _> = Stubborn.start();
+    Arbiter::handle().spawn_fn(move || {
+        let sig = Arbiter::system_registry().get::<signal::ProcessSignals>();
+        sig.do_send(signal::SignalType::Int);
+        Ok(())
+    });
+    assert_eq!(sys.run(), 0);
+}
+
+struct Stubborn;
+
+impl Actor for Stubborn {
+    type Context = Context<Self>;
+
+    fn stopping(&mut self, _: &mut Context<Self>) -> Running {
+        Running::Continue
+    }
+}
+
+#[derive(Clone)]
+struct Event(usize);
+
+impl Message for Event {
+    type Result = ();
+}
+
+struct EventListener(Arc<Mutex<Vec<usize>>>, bool);
+
+impl Actor for EventListener {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let addr: Addr<Syn, _> = ctx.address();
+        EventBus::from_registry().do_send(eventbus::Subscribe(addr.recipient()));
+    }
+}
+
+impl Handler<Event> for EventListener {
+    type Result = ();
+
+    fn handle(&mut self, msg: Event, ctx: &mut Context<Self>) {
+        self.0.lock().unwrap().push(msg.0);
+        if self.1 {
+            ctx.stop();
+        }
+    }
+}
+
+#[test]
+fn test_eventbus() {
+    let sys = System::new("test");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let _: Addr<Unsync, _> = EventListener(Arc::clone(&events), false).start();
+    let _: Addr<Unsync, _> = EventListener(Arc::clone(&events), true).start();
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+            .then(|_| {
+                EventBus::from_registry().do_send(eventbus::Publish(Event(1)));
+                Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+            })
+            .then(|_| {
+                // stopped listener is unsubscribed
+                EventBus::from_registry().do_send(eventbus::Publish(Event(2)));
+                Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+            })
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+    assert_eq!(*events.lock().unwrap(), vec![1, 1, 2]);
+}
@@ -2,12 +2,13 @@
 extern crate futures;
 extern crate tokio_core;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 use futures::{future, Future};
 use tokio_core::reactor::Timeout;
 use actix::prelude::*;
+use actix::deadletter::{self, DeadLetter, DeadLetterReason, DeadLetters};
 
 #[derive(Message, Debug)]
 struct Ping(usize);
@@ -322,3 +323,666 @@ fn test_sync_call_message_timeout() {
     sys.run();
     assert_eq!(count.load(Ordering::Relaxed), 1);
 }
+
+#[test]
+fn test_mailbox_backpressure() {
+    let sys = System::new("test");
+    let count = Arc::new(AtomicUsize::new(0));
+    let count2 = Arc::clone(&count);
+
+    let addr: Addr<Unsync, _> = MyActor::create(move |ctx| {
+        ctx.set_mailbox_capacity(1);
+        assert_eq!(ctx.mailbox_capacity(), 1);
+        MyActor(count2)
+    });
+
+    Arbiter::handle().spawn_fn(move || {
+        assert!(addr.try_send(Ping(0)).is_ok());
+        match addr.try_send(Ping(1)) {
+            Err(SendError::Full(Ping(1))) => (),
+            _ => panic!("Mailbox should be full"),
+        }
+
+        // requests wait until actor processes pending messages
+        addr.send(Ping(2)).join(addr.send(Ping(3))).then(|res| {
+            assert!(res.is_ok());
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::result(Ok(()))
+        })
+    });
+
+    sys.run();
+    assert_eq!(count.load(Ordering::Relaxed), 3);
+}
+
+struct StoppedActor;
+
+impl Actor for StoppedActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+impl actix::Handler<Ping> for StoppedActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Self::Context) {}
+}
+
+#[test]
+fn test_try_send_returns_message() {
+    let sys = System::new("test");
+    let (addr, addr2): (Addr<Unsync, _>, Addr<Syn, _>) = StoppedActor.start();
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::new(0, 10_000_000), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                assert!(!addr.connected());
+                match addr.try_send(Ping(1)) {
+                    Err(err) => {
+                        assert!(err.is_closed());
+                        assert_eq!(err.into_inner().0, 1);
+                    }
+                    _ => panic!("Should not happen"),
+                }
+                match addr2.clone().recipient().try_send(Ping(2)) {
+                    Err(SendError::Closed(Ping(2))) => (),
+                    _ => panic!("Should not happen"),
+                }
+                match addr2.try_send(Ping(3)) {
+                    Err(SendError::Closed(Ping(3))) => (),
+                    _ => panic!("Should not happen"),
+                }
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    sys.run();
+}
+
+struct StopRecorder(Arc<AtomicUsize>);
+
+impl Actor for StopRecorder {
+    type Context = Context<Self>;
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl actix::Handler<Ping> for StopRecorder {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Self::Context) {}
+}
+
+#[test]
+fn test_weak_address() {
+    let sys = System::new("test");
+    let count = Arc::new(AtomicUsize::new(0));
+    let count2 = Arc::clone(&count);
+
+    Arbiter::handle().spawn_fn(move || {
+        let (addr, addr2): (Addr<Unsync, _>, Addr<Syn, _>) =
+            StopRecorder(Arc::clone(&count2)).start();
+        let weak = addr.downgrade();
+        let weak2 = addr2.downgrade();
+        assert!(weak.upgrade().unwrap().connected());
+        assert!(weak2.upgrade().unwrap().connected());
+
+        // weak addresses do not keep actor alive
+        drop(addr);
+        drop(addr2);
+
+        Timeout::new(Duration::new(0, 10_000_000), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                assert_eq!(count2.load(Ordering::Relaxed), 1);
+                assert!(weak.upgrade().is_none());
+                assert!(weak2.upgrade().is_none());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    });
+
+    sys.run();
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+}
+
+fn ping_all(recipients: &[Recipient<Syn, Ping>], ping: usize) -> usize {
+    recipients.iter().filter(|r| r.do_send(Ping(ping)).is_ok()).count()
+}
+
+#[test]
+fn test_recipient_connected() {
+    let sys = System::new("test");
+    let count = Arc::new(AtomicUsize::new(0));
+
+    Arbiter::handle().spawn_fn(move || {
+        let addr: Addr<Syn, _> = MyActor(count).start();
+        let addr2: Addr<Syn, _> = StoppedActor.start();
+        let recipients = vec![addr.recipient(), addr2.recipient()];
+
+        Timeout::new(Duration::new(0, 10_000_000), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                assert!(recipients[0].connected());
+                assert!(!recipients[1].connected());
+                assert_eq!(ping_all(&recipients, 1), 1);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    });
+
+    sys.run();
+}
+
+struct WedgedActor;
+
+impl Actor for WedgedActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        future::empty::<(), ()>().into_actor(self).wait(ctx);
+    }
+}
+
+impl actix::Handler<Ping> for WedgedActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Self::Context) {}
+}
+
+#[test]
+fn test_send_timeout_full_mailbox() {
+    let sys = System::new("test");
+    let count = Arc::new(AtomicUsize::new(0));
+    let count2 = Arc::clone(&count);
+
+    let addr: Addr<Syn, _> = WedgedActor::create(|ctx| {
+        ctx.set_mailbox_capacity(1);
+        WedgedActor
+    });
+
+    Arbiter::handle().spawn_fn(move || {
+        assert!(addr.try_send(Ping(0)).is_ok());
+        addr.send_timeout(Ping(1), Duration::new(0, 10_000_000))
+            .then(move |res| {
+                match res {
+                    Err(MailboxError::Timeout) => {
+                        count2.fetch_add(1, Ordering::Relaxed);
+                    },
+                    _ => panic!("Should not happen"),
+                }
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    });
+
+    sys.run();
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_recipient_request_errors() {
+    let sys = System::new("test");
+
+    let addr: Addr<Syn, _> = WedgedActor::create(|ctx| {
+        ctx.set_mailbox_capacity(1);
+        WedgedActor
+    });
+    let recipient = addr.recipient();
+    let closed: Addr<Syn, _> = StoppedActor.start();
+
+    Arbiter::handle().spawn_fn(move || {
+        assert!(recipient.try_send(Ping(0)).is_ok());
+        recipient.send(Ping(1)).timeout(Duration::new(0, 10_000_000))
+            .then(move |res| {
+                assert_eq!(res, Err(MailboxError::Timeout));
+                closed.send(Ping(2))
+            })
+            .then(|res| {
+                assert_eq!(res, Err(MailboxError::Closed));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    });
+
+    assert_eq!(sys.run(), 0);
+}
+
+struct Recorder(Arc<Mutex<Vec<usize>>>);
+
+impl Actor for Recorder {
+    type Context = Context<Self>;
+}
+
+impl actix::Handler<Ping> for Recorder {
+    type Result = ();
+
+    fn handle(&mut self, msg: Ping, _: &mut Self::Context) {
+        self.0.lock().unwrap().push(msg.0);
+    }
+}
+
+struct Letters(Arc<AtomicUsize>);
+
+impl Actor for Letters {
+    type Context = Context<Self>;
+}
+
+impl actix::Handler<DeadLetter> for Letters {
+    type Result = ();
+
+    fn handle(&mut self, msg: DeadLetter, _: &mut Self::Context) {
+        if msg.reason == DeadLetterReason::Overflow && msg.is::<Ping>() {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn recorder<Addr>(policy: OverflowPolicy, pings: &Arc<Mutex<Vec<usize>>>) -> Addr
+    where Recorder: actix::ActorAddress<Recorder, Addr>
+{
+    let pings = Arc::clone(pings);
+    Recorder::create(move |ctx| {
+        ctx.set_mailbox_capacity(2);
+        ctx.set_mailbox_overflow(policy);
+        assert_eq!(ctx.mailbox_overflow(), policy);
+        Recorder(pings)
+    })
+}
+
+#[test]
+fn test_mailbox_overflow() {
+    let sys = System::new("test");
+
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let letters: Addr<Syn, _> = Letters(Arc::clone(&dropped)).start();
+    DeadLetters::from_registry().do_send(deadletter::Subscribe(letters.recipient()));
+
+    let failed = Arc::new(Mutex::new(Vec::new()));
+    let oldest = Arc::new(Mutex::new(Vec::new()));
+    let newest = Arc::new(Mutex::new(Vec::new()));
+    let sync_oldest = Arc::new(Mutex::new(Vec::new()));
+    let fail: Addr<Unsync, _> = recorder(OverflowPolicy::Fail, &failed);
+    let drop_oldest: Addr<Unsync, _> = recorder(OverflowPolicy::DropOldest, &oldest);
+    let drop_newest: Addr<Unsync, _> = recorder(OverflowPolicy::DropNewest, &newest);
+    let sync_drop_oldest: Addr<Syn, _> = recorder(OverflowPolicy::DropOldest, &sync_oldest);
+    let sync_newest = Arc::new(Mutex::new(Vec::new()));
+    let sync_drop_newest: Addr<Syn, _> = recorder(OverflowPolicy::DropNewest, &sync_newest);
+
+    Arbiter::handle().spawn_fn(move || {
+        assert!(fail.try_send(Ping(0)).is_ok());
+        assert!(fail.try_send(Ping(1)).is_ok());
+        match fail.try_send(Ping(2)) {
+            Err(SendError::Full(Ping(2))) => (),
+            _ => panic!("Mailbox should be full"),
+        }
+
+        for i in 0..4 {
+            assert!(drop_oldest.try_send(Ping(i)).is_ok());
+            assert!(sync_drop_oldest.try_send(Ping(i)).is_ok());
+            // rejected message is returned back, it is not a dead letter
+            match drop_newest.try_send(Ping(i)) {
+                Ok(()) => assert!(i < 2),
+                Err(SendError::Full(Ping(n))) => assert_eq!(n, i),
+                _ => panic!("Mailbox should be full"),
+            }
+            match sync_drop_newest.try_send(Ping(i)) {
+                Ok(()) => assert!(i < 2),
+                Err(SendError::Full(Ping(n))) => assert_eq!(n, i),
+                _ => panic!("Mailbox should be full"),
+            }
+        }
+
+        let dropped_newest = drop_newest.send(Ping(4)).join(sync_drop_newest.send(Ping(4)))
+            .then(|res| match res {
+                Err(MailboxError::Full) => Ok(()),
+                _ => panic!("New message should be dropped"),
+            });
+        fail.send(Ping(3)).join(dropped_newest).then(|res| {
+            match res {
+                Err(MailboxError::Full) => (),
+                _ => panic!("Request should fail"),
+            }
+            Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+        }).then(|_| {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::result(Ok(()))
+        })
+    });
+
+    sys.run();
+    assert_eq!(*failed.lock().unwrap(), vec![0, 1]);
+    assert_eq!(*oldest.lock().unwrap(), vec![2, 3]);
+    assert_eq!(*newest.lock().unwrap(), vec![0, 1]);
+    assert_eq!(*sync_oldest.lock().unwrap(), vec![2, 3]);
+    assert_eq!(*sync_newest.lock().unwrap(), vec![0, 1]);
+    // 4 oldest messages and messages sent to full mailboxes
+    assert_eq!(dropped.load(Ordering::Relaxed), 6);
+}
+
+struct Hold(Option<Addr<Unsync, Holder>>);
+
+impl Message for Hold {
+    type Result = ();
+}
+
+struct Holder(Arc<AtomicUsize>);
+
+impl Actor for Holder {
+    type Context = Context<Self>;
+}
+
+impl actix::Handler<Hold> for Holder {
+    type Result = ();
+
+    fn handle(&mut self, _: Hold, _: &mut Self::Context) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_drop_oldest_own_address() {
+    let sys = System::new("test");
+    let handled = Arc::new(AtomicUsize::new(0));
+    let handled2 = Arc::clone(&handled);
+
+    Arbiter::handle().spawn_fn(move || {
+        let addr: Addr<Unsync, _> = Holder::create(move |ctx| {
+            ctx.set_mailbox_capacity(1);
+            ctx.set_mailbox_overflow(OverflowPolicy::DropOldest);
+            Holder(handled2)
+        });
+        // evicted messages drop addresses of the same mailbox
+        for _ in 0..3 {
+            assert!(addr.try_send(Hold(Some(addr.clone()))).is_ok());
+        }
+        addr.send(Hold(None)).then(|res| {
+            assert!(res.is_ok());
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::result(Ok(()))
+        })
+    });
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(handled.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_call_blocking() {
+    let sys = System::new("test");
+    let count = Arc::new(AtomicUsize::new(0));
+    let addr: Addr<Syn, _> = MyActor(Arc::clone(&count)).start();
+    let wedged: Addr<Syn, _> = WedgedActor.start();
+    let system = Arbiter::system();
+
+    let handle = std::thread::spawn(move || {
+        let res = addr.call_blocking(Ping(0), Duration::from_secs(5));
+        let timeout = wedged.call_blocking(Ping(1), Duration::from_millis(50));
+        system.do_send(actix::msgs::SystemExit(0));
+        (res, timeout)
+    });
+
+    sys.run();
+    let (res, timeout) = handle.join().unwrap();
+    assert!(res.is_ok());
+    match timeout {
+        Err(MailboxError::Timeout) => (),
+        _ => panic!("Request should time out"),
+    }
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+}
+
+struct Double(usize);
+
+impl Message for Double {
+    type Result = usize;
+}
+
+/// Doubles numbers, stops immediately if created with `true`
+struct Doubler(bool);
+
+impl Actor for Doubler {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        if self.0 {
+            ctx.stop();
+        }
+    }
+}
+
+impl Handler<Double> for Doubler {
+    type Result = MessageResult<Double>;
+
+    fn handle(&mut self, msg: Double, _: &mut Context<Self>) -> Self::Result {
+        MessageResult(msg.0 * 2)
+    }
+}
+
+/// Forwards doubling requests to doubler
+struct Front(Addr<Syn, Doubler>);
+
+impl Actor for Front {
+    type Context = Context<Self>;
+}
+
+impl Handler<Double> for Front {
+    type Result = actix::dev::Forward<Syn, Doubler, Double>;
+
+    fn handle(&mut self, msg: Double, _: &mut Context<Self>) -> Self::Result {
+        self.0.forward(msg)
+    }
+}
+
+#[test]
+fn test_forward() {
+    let sys = System::new("test");
+    let (front, front2): (Addr<Unsync, _>, Addr<Syn, _>) = Front(Doubler(false).start()).start();
+
+    Arbiter::handle().spawn(
+        front.send(Double(1)).join(front2.send(Double(2)))
+            .then(move |res| {
+                assert_eq!(res.unwrap(), (2, 4));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[test]
+fn test_forward_closed() {
+    let sys = System::new("test");
+    let front: Addr<Syn, _> = Front(Doubler(true).start()).start();
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+            .then(move |_| front.send(Double(1)))
+            .then(|res| {
+                assert_eq!(res, Err(MailboxError::Closed));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
+
+/// Records pings, waits after ping `0`, exits system after ping `last`
+struct BatchRecorder {
+    pings: Arc<Mutex<Vec<usize>>>,
+    last: usize,
+}
+
+impl Actor for BatchRecorder {
+    type Context = Context<Self>;
+}
+
+impl actix::Handler<Ping> for BatchRecorder {
+    type Result = ();
+
+    fn handle(&mut self, msg: Ping, ctx: &mut Self::Context) {
+        self.pings.lock().unwrap().push(msg.0);
+        if msg.0 == 0 {
+            actix::clock::Delay::new(Duration::from_millis(10))
+                .into_actor(self).map_err(|_, _, _| ()).wait(ctx);
+        }
+        if msg.0 == self.last {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }
+    }
+}
+
+#[test]
+fn test_batch() {
+    let sys = System::new("test");
+    let pings = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Addr<Syn, _> = BatchRecorder{pings: Arc::clone(&pings), last: 999}.start();
+    addr.do_send_batch((1..1000).map(Ping).collect());
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*pings.lock().unwrap(), (1..1000).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_batch_wait() {
+    let sys = System::new("test");
+    let pings = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Addr<Unsync, _> = BatchRecorder{pings: Arc::clone(&pings), last: 3}.start();
+    addr.do_send_batch(vec![Ping(0), Ping(1), Ping(2)]);
+    addr.do_send(Ping(3));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*pings.lock().unwrap(), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn test_recipient_sink() {
+    use futures::Stream;
+
+    let sys = System::new("test");
+    let pings = Arc::new(Mutex::new(Vec::new()));
+    let addr: Addr<Syn, _> = recorder(OverflowPolicy::Block, &pings);
+    let stopped: Addr<Syn, _> = StoppedActor.start();
+
+    Arbiter::handle().spawn(
+        futures::stream::iter_ok::<_, SendError<Ping>>((0..100).map(Ping))
+            .forward(addr.clone().recipient())
+            .and_then(move |_| addr.send(Ping(100)).map_err(|_| panic!("Recorder is alive")))
+            .and_then(move |_| Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+                      .map_err(|_| unreachable!()))
+            .and_then(move |_| futures::stream::iter_ok(vec![Ping(0)]).forward(stopped.recipient()))
+            .then(|res| {
+                match res {
+                    Err(SendError::Closed(Ping(0))) => (),
+                    _ => panic!("Sink should fail"),
+                }
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*pings.lock().unwrap(), (0..101).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_addr_sink_overflow() {
+    use futures::Stream;
+
+    let sys = System::new("test");
+    let pings = Arc::new(Mutex::new(Vec::new()));
+    let failed = Arc::new(Mutex::new(Vec::new()));
+    let sync_failed = Arc::new(Mutex::new(Vec::new()));
+    let addr: Addr<Unsync, _> = recorder(OverflowPolicy::Block, &pings);
+    let fail: Addr<Unsync, _> = recorder(OverflowPolicy::Fail, &failed);
+    let sync_fail: Addr<Syn, _> = recorder(OverflowPolicy::Fail, &sync_failed);
+
+    Arbiter::handle().spawn(
+        futures::stream::iter_ok::<_, SendError<Ping>>((0..10).map(Ping))
+            .forward(addr.sink())
+            .and_then(move |_| futures::stream::iter_ok((0..10).map(Ping)).forward(fail.sink()))
+            .then(move |res| {
+                match res {
+                    Err(SendError::Full(Ping(2))) => (),
+                    _ => panic!("Sink should fail"),
+                }
+                futures::stream::iter_ok((0..10).map(Ping)).forward(sync_fail.sink())
+            })
+            .then(|res| {
+                match res {
+                    Err(SendError::Full(Ping(2))) => (),
+                    _ => panic!("Sink should fail"),
+                }
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*pings.lock().unwrap(), (0..10).collect::<Vec<_>>());
+    assert_eq!(*failed.lock().unwrap(), vec![0, 1]);
+    assert_eq!(*sync_failed.lock().unwrap(), vec![0, 1]);
+}
+
+#[test]
+fn test_address_eq_hash() {
+    use std::collections::HashSet;
+
+    let sys = System::new("test");
+    let count = Arc::new(AtomicUsize::new(0));
+
+    let (addr, sync_addr): (Addr<Unsync, _>, Addr<Syn, _>) = MyActor(Arc::clone(&count)).start();
+    let other: Addr<Unsync, _> = MyActor(Arc::clone(&count)).start();
+    assert_eq!(addr, addr.clone());
+    assert_eq!(sync_addr, sync_addr.clone());
+    assert_ne!(addr, other);
+    assert_eq!(format!("{:?}", addr), format!("{:?}", addr.clone()));
+
+    let mut set = HashSet::new();
+    set.insert(addr.clone());
+    set.insert(other.clone());
+    set.insert(addr.downgrade().upgrade().unwrap());
+    assert_eq!(set.len(), 2);
+    assert!(set.contains(&other));
+
+    Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    sys.run();
+}
+
+#[test]
+fn test_subscriber() {
+    let sys = System::new("test");
+    let count = Arc::new(AtomicUsize::new(0));
+
+    let (unsync, sync): (Addr<Unsync, _>, Addr<Syn, _>) = MyActor(Arc::clone(&count)).start();
+    let stopped: Addr<Syn, _> = StoppedActor.start();
+    let subscribers: Vec<Box<Subscriber<Ping>>> = vec![
+        Box::new(unsync), Box::new(sync.clone()), Box::new(sync.recipient())];
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                let stopped: Box<Subscriber<Ping>> = Box::new(stopped);
+                assert!(!stopped.connected());
+                assert!(stopped.do_send(Ping(0)).unwrap_err().is_closed());
+
+                let cloned: Vec<_> = subscribers.iter().map(|sub| sub.boxed()).collect();
+                for sub in &cloned {
+                    assert!(sub.connected());
+                    sub.do_send(Ping(0)).unwrap();
+                    sub.try_send(Ping(0)).unwrap();
+                }
+                future::join_all(subscribers.iter().map(|sub| sub.send(Ping(0))).collect::<Vec<_>>())
+            })
+            .then(|res| {
+                assert_eq!(res.unwrap().len(), 3);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(count.load(Ordering::Relaxed), 9);
+}
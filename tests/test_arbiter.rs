@@ -1,9 +1,12 @@
 extern crate futures;
+extern crate tokio_core;
 #[macro_use]extern crate actix;
 
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 use futures::Future;
+use tokio_core::reactor::Timeout;
 use actix::prelude::*;
 
 #[derive(Debug, Message)]
@@ -60,3 +63,416 @@ fn test_start_actor_message() {
     sys.run();
     assert_eq!(count.load(Ordering::Relaxed), 1);
 }
+
+#[test]
+fn test_named_arbiter() {
+    let sys = System::new("test");
+
+    let io = Arbiter::new_named("io");
+    assert!(Arbiter::lookup("io").is_some());
+    assert!(Arbiter::lookup("compute").is_none());
+
+    // lookup from other arbiter
+    let other = Arbiter::new("other");
+    Arbiter::handle().spawn(
+        other.send(actix::msgs::Execute::new(|| -> Result<bool, ()> {
+            Ok(Arbiter::lookup("io").is_some())
+        })).then(move |res| {
+            assert!(res.unwrap().unwrap());
+            io.send(actix::msgs::StopArbiter(0))
+        }).then(|_| {
+            Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+        }).then(|_| {
+            // stopped arbiter is removed
+            assert!(Arbiter::lookup("io").is_none());
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+}
+
+struct Worker;
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+struct WhereAmI;
+
+impl Message for WhereAmI {
+    type Result = String;
+}
+
+impl Handler<WhereAmI> for Worker {
+    type Result = String;
+
+    fn handle(&mut self, _: WhereAmI, _: &mut Context<Self>) -> String {
+        Arbiter::name()
+    }
+}
+
+#[derive(Message)]
+struct Stop;
+
+impl Handler<Stop> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+#[test]
+fn test_arbiter_pool_round_robin() {
+    let sys = System::new("test");
+
+    let mut pool = ArbiterPool::new(2);
+    let workers: Vec<Addr<Syn, _>> = (0..4).map(|_| pool.start(|_| Worker)).collect();
+    assert_eq!(pool.load(), vec![2, 2]);
+
+    let names: Vec<_> = workers.iter().map(|w| w.send(WhereAmI)).collect();
+    Arbiter::handle().spawn(
+        futures::future::join_all(names).then(move |res| {
+            let names = res.unwrap();
+            assert_eq!(names[0], names[2]);
+            assert_eq!(names[1], names[3]);
+            assert_ne!(names[0], names[1]);
+            drop(pool);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+}
+
+#[test]
+fn test_arbiter_pool_least_loaded() {
+    let sys = System::new("test");
+
+    let mut pool = ArbiterPool::with_placement(2, actix::Placement::LeastLoaded);
+    let first: Addr<Syn, _> = pool.start(|_| Worker);
+    let second: Addr<Syn, _> = pool.start(|_| Worker);
+    first.do_send(Stop);
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                // first arbiter is idle
+                assert_eq!(pool.load(), vec![0, 1]);
+                let third: Addr<Syn, _> = pool.start(|_| Worker);
+                assert_eq!(pool.load(), vec![1, 1]);
+                // keep pool running until responses are received
+                third.send(WhereAmI).join(second.send(WhereAmI)).map(move |res| {
+                    drop(pool);
+                    res
+                })
+            })
+            .then(|res| {
+                let (third, second) = res.unwrap();
+                assert_ne!(third, second);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+struct Slow(Arc<AtomicUsize>, Arc<AtomicUsize>, Running);
+
+impl Actor for Slow {
+    type Context = Context<Self>;
+
+    fn stopping(&mut self, _: &mut Context<Self>) -> Running {
+        self.2
+    }
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        self.1.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Handler<Ping> for Slow {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {
+        std::thread::sleep(Duration::from_millis(2));
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn start_slow(arbiter: &Addr<Syn, Arbiter>, running: Running)
+              -> (Addr<Syn, Slow>, Arc<AtomicUsize>, Arc<AtomicUsize>)
+{
+    let (tx, rx) = std::sync::mpsc::channel();
+    let count = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicUsize::new(0));
+    let (count2, stopped2) = (Arc::clone(&count), Arc::clone(&stopped));
+    arbiter.do_send(actix::msgs::Execute::new(move || -> Result<(), ()> {
+        let addr: Addr<Syn, _> = Slow(count2, stopped2, running).start();
+        let _ = tx.send(addr);
+        Ok(())
+    }));
+    (rx.recv().unwrap(), count, stopped)
+}
+
+#[test]
+fn test_arbiter_stop_graceful() {
+    let sys = System::new("test");
+
+    let arbiter = Arbiter::new_named("drain");
+    let (addr, count, stopped) = start_slow(&arbiter, Running::Stop);
+    for i in 0..10 {
+        addr.do_send(Ping(i));
+    }
+    arbiter.do_send(actix::msgs::StopArbiterGraceful(0, Duration::from_secs(5)));
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(200), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                // pending messages are processed before arbiter stops
+                assert_eq!(count.load(Ordering::Relaxed), 10);
+                assert_eq!(stopped.load(Ordering::Relaxed), 1);
+                assert!(Arbiter::lookup("drain").is_none());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+#[test]
+fn test_arbiter_stop_graceful_timeout() {
+    let sys = System::new("test");
+
+    let arbiter = Arbiter::new_named("stubborn");
+    let (_addr, _, stopped) = start_slow(&arbiter, Running::Continue);
+    arbiter.do_send(actix::msgs::StopArbiterGraceful(0, Duration::from_millis(20)));
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(200), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                // actor refused to stop, arbiter stopped after timeout
+                assert_eq!(stopped.load(Ordering::Relaxed), 0);
+                assert!(Arbiter::lookup("stubborn").is_none());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+#[test]
+fn test_arbiter_execute_while_draining() {
+    let sys = System::new("test");
+
+    let arbiter = Arbiter::new("draining");
+    let (_addr, _, _) = start_slow(&arbiter, Running::Continue);
+    arbiter.do_send(actix::msgs::StopArbiterGraceful(0, Duration::from_millis(300)));
+
+    let executed = Arc::new(AtomicUsize::new(0));
+    let executed2 = Arc::clone(&executed);
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                // arbiter actor is not drained, it runs until arbiter stops
+                arbiter.do_send(actix::msgs::Execute::new(move || -> Result<(), ()> {
+                    executed2.fetch_add(1, Ordering::Relaxed);
+                    Ok(())
+                }));
+                Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            })
+            .then(move |_| {
+                assert_eq!(executed.load(Ordering::Relaxed), 1);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+struct Crash;
+
+impl Message for Crash {
+    type Result = ();
+}
+
+struct Fragile;
+
+impl Actor for Fragile {
+    type Context = Context<Self>;
+}
+
+impl Handler<Crash> for Fragile {
+    type Result = ();
+
+    fn handle(&mut self, _: Crash, ctx: &mut Context<Self>) {
+        // timer is pending when event loop crashes
+        ctx.run_later(Duration::from_secs(10), |_, _| ());
+        panic!("Fragile crashed");
+    }
+}
+
+fn start_fragile(arbiter: &Addr<Syn, Arbiter>) -> Addr<Syn, Fragile> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    arbiter.do_send(actix::msgs::Execute::new(move || -> Result<(), ()> {
+        let addr: Addr<Syn, _> = Fragile.start();
+        let _ = tx.send(addr);
+        Ok(())
+    }));
+    rx.recv().unwrap()
+}
+
+fn check_after_panic<F>(policy: actix::PanicPolicy, f: F)
+    where F: FnOnce(Addr<Syn, Fragile>, Addr<Syn, Fragile>) + 'static
+{
+    let sys = System::new("test");
+
+    let arbiter = Arbiter::builder("fragile").panic_policy(policy).start();
+    let crashed = start_fragile(&arbiter);
+    let healthy = start_fragile(&arbiter);
+    crashed.do_send(Crash);
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                // arbiter survives panic
+                arbiter.send(actix::msgs::Execute::new(|| -> Result<_, ()> {
+                    Ok(Arbiter::name())
+                }))
+            })
+            .then(move |res| {
+                assert!(res.unwrap().unwrap().contains("fragile"));
+                f(crashed, healthy);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[test]
+fn test_arbiter_panic_ignore() {
+    // only crashed actor is dropped
+    check_after_panic(actix::PanicPolicy::Ignore, |crashed, healthy| {
+        assert!(!crashed.connected());
+        assert!(healthy.connected());
+    });
+}
+
+#[test]
+fn test_arbiter_panic_restart_thread() {
+    // all actors of crashed event loop are dropped
+    check_after_panic(actix::PanicPolicy::RestartThread, |crashed, healthy| {
+        assert!(!crashed.connected());
+        assert!(!healthy.connected());
+    });
+
+    // timers fire on restarted event loop
+    let sys = System::new("test");
+
+    let arbiter = Arbiter::builder("fragile")
+        .panic_policy(actix::PanicPolicy::RestartThread).start();
+    start_fragile(&arbiter).do_send(Crash);
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                arbiter.send(actix::msgs::Execute::new(|| -> Result<(), ()> {
+                    let _: Addr<Syn, _> = Later.start();
+                    Ok(())
+                }))
+            })
+            .then(|_| Ok(())));
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_secs(2), Arbiter::handle()).unwrap()
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(1));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
+
+struct Later;
+
+impl Actor for Later {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.run_later(Duration::from_millis(20), |_, _| {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        });
+    }
+}
+
+#[test]
+fn test_arbiter_panic_stop_system() {
+    let sys = System::new("test");
+
+    let arbiter = Arbiter::builder("fragile")
+        .panic_policy(actix::PanicPolicy::StopSystem(3)).start();
+    start_fragile(&arbiter).do_send(Crash);
+
+    assert_eq!(sys.run(), 3);
+}
+
+#[test]
+fn test_arbiter_thread_options() {
+    let sys = System::new("test");
+
+    let arbiter = Arbiter::builder("pinned")
+        .thread_name("pinned-thread")
+        .stack_size(4 * 1024 * 1024)
+        .core_affinity(0)
+        .start();
+
+    Arbiter::handle().spawn(
+        arbiter.send(actix::msgs::Execute::new(|| -> Result<_, ()> {
+            Ok(std::thread::current().name().map(|name| name.to_owned()))
+        })).then(|res| {
+            assert_eq!(res.unwrap().unwrap(), Some("pinned-thread".to_owned()));
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+}
+
+#[test]
+fn test_system_stop_graceful() {
+    let sys = System::new("test");
+
+    let arbiter = Arbiter::new("drain");
+    let (addr, count, stopped) = start_slow(&arbiter, Running::Stop);
+    let count2 = Arc::new(AtomicUsize::new(0));
+    let stopped2 = Arc::new(AtomicUsize::new(0));
+    let addr2: Addr<Syn, _> = Slow(
+        Arc::clone(&count2), Arc::clone(&stopped2), Running::Stop).start();
+    for i in 0..10 {
+        addr.do_send(Ping(i));
+        addr2.do_send(Ping(i));
+    }
+    System::stop_graceful(Duration::from_secs(5));
+
+    assert_eq!(sys.run(), 0);
+    // pending messages are processed before system stops
+    assert_eq!(count.load(Ordering::Relaxed), 10);
+    assert_eq!(stopped.load(Ordering::Relaxed), 1);
+    assert_eq!(count2.load(Ordering::Relaxed), 10);
+    assert_eq!(stopped2.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_system_stop_graceful_timeout() {
+    let sys = System::new("test");
+
+    let stopped = Arc::new(AtomicUsize::new(0));
+    let _: Addr<Syn, _> = Slow(
+        Arc::new(AtomicUsize::new(0)), Arc::clone(&stopped), Running::Continue).start();
+    Arbiter::system().do_send(actix::msgs::SystemExitGraceful(2, Duration::from_millis(20)));
+
+    // actor refused to stop, system stopped after timeout
+    assert_eq!(sys.run(), 2);
+    assert_eq!(stopped.load(Ordering::Relaxed), 0);
+}
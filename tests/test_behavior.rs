@@ -0,0 +1,133 @@
+extern crate actix;
+extern crate futures;
+
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::behavior::{self, Behavior, BehaviorContext};
+
+struct Hello;
+
+impl Message for Hello {
+    type Result = ();
+}
+
+struct Data(usize);
+
+impl Message for Data {
+    type Result = usize;
+}
+
+struct Close;
+
+impl Message for Close {
+    type Result = ();
+}
+
+struct State;
+
+impl Message for State {
+    type Result = (Option<&'static str>, usize, usize);
+}
+
+#[derive(Default)]
+struct Connection {
+    received: usize,
+    ignored: usize,
+}
+
+impl Actor for Connection {
+    type Context = Context<Self>;
+}
+
+fn streaming() -> Behavior<Connection> {
+    Behavior::new("streaming")
+        .on(|act: &mut Connection, msg: Data, _: &mut Context<Connection>| {
+            act.received += msg.0;
+            act.received
+        })
+        .on(|_: &mut Connection, _: Close, ctx: &mut Context<Connection>| {
+            ctx.become_behavior(closing());
+        })
+}
+
+fn closing() -> Behavior<Connection> {
+    Behavior::new("closing")
+        .on(|_: &mut Connection, _: Hello, ctx: &mut Context<Connection>| {
+            // reconnect, back to streaming
+            ctx.unbecome();
+        })
+}
+
+impl Handler<Hello> for Connection {
+    type Result = ();
+
+    fn handle(&mut self, msg: Hello, ctx: &mut Context<Self>) {
+        if behavior::handle(self, msg, ctx).is_err() {
+            ctx.become_behavior(streaming());
+        }
+    }
+}
+
+impl Handler<Data> for Connection {
+    type Result = MessageResult<Data>;
+
+    fn handle(&mut self, msg: Data, ctx: &mut Context<Self>) -> Self::Result {
+        match behavior::handle(self, msg, ctx) {
+            Ok(received) => MessageResult(received),
+            Err(_) => {
+                self.ignored += 1;
+                MessageResult(0)
+            }
+        }
+    }
+}
+
+impl Handler<Close> for Connection {
+    type Result = ();
+
+    fn handle(&mut self, msg: Close, ctx: &mut Context<Self>) {
+        let _ = behavior::handle(self, msg, ctx);
+    }
+}
+
+impl Handler<State> for Connection {
+    type Result = MessageResult<State>;
+
+    fn handle(&mut self, _: State, ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult((ctx.behavior(), self.received, self.ignored))
+    }
+}
+
+#[test]
+fn test_become_unbecome() {
+    let sys = System::new("test");
+
+    let addr: Addr<Unsync, _> = Connection::default().start();
+    // data before handshake is ignored
+    addr.do_send(Data(1));
+    addr.do_send(Hello);
+    addr.do_send(Data(10));
+    addr.do_send(Close);
+    addr.do_send(Data(100));
+
+    let addr2 = addr.clone();
+    let addr3 = addr.clone();
+    Arbiter::handle().spawn(
+        addr.send(State)
+            .and_then(move |res| {
+                assert_eq!(res, (Some("closing"), 10, 2));
+                addr2.do_send(Hello);
+                addr2.send(Data(5))
+            })
+            .and_then(move |received| {
+                assert_eq!(received, 15);
+                addr3.send(State)
+            })
+            .then(|res| {
+                assert_eq!(res.unwrap(), (Some("streaming"), 15, 2));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
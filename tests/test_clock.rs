@@ -0,0 +1,171 @@
+extern crate actix;
+extern crate futures;
+
+use std::time::{Duration, Instant};
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::clock::{self, TestClock};
+
+struct Tick;
+
+impl Message for Tick {
+    type Result = ();
+}
+
+struct Stats;
+
+impl Message for Stats {
+    type Result = (usize, usize, usize);
+}
+
+#[derive(Default)]
+struct Timers {
+    later: usize,
+    notified: usize,
+    ticks: usize,
+}
+
+impl Actor for Timers {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.run_later(Duration::from_secs(3600), |act, _| act.later += 1);
+        ctx.notify_later(Tick, Duration::from_secs(3600));
+        ctx.run_interval(Duration::from_secs(600), |act, _| act.ticks += 1);
+    }
+}
+
+impl Handler<Tick> for Timers {
+    type Result = ();
+
+    fn handle(&mut self, _: Tick, _: &mut Context<Self>) {
+        self.notified += 1;
+    }
+}
+
+impl Handler<Stats> for Timers {
+    type Result = MessageResult<Stats>;
+
+    fn handle(&mut self, _: Stats, _: &mut Context<Self>) -> Self::Result {
+        MessageResult((self.later, self.notified, self.ticks))
+    }
+}
+
+/// Stats of the actor after due timers had a chance to fire
+fn stats(addr: &Addr<Unsync, Timers>) -> Box<Future<Item=(usize, usize, usize), Error=()>> {
+    let addr2 = addr.clone();
+    Box::new(addr.send(Stats)
+             .and_then(move |_| addr2.send(Stats))
+             .map_err(|_| ()))
+}
+
+#[test]
+fn test_clock_timers() {
+    let sys = System::new("test");
+    let clock = TestClock::install();
+    let start = clock::now();
+
+    let addr: Addr<Unsync, _> = Timers::default().start();
+    let addr2 = addr.clone();
+    let addr3 = addr.clone();
+    let clock2 = clock.clone();
+
+    Arbiter::handle().spawn(
+        stats(&addr)
+            .and_then(move |res| {
+                assert_eq!(res, (0, 0, 0));
+                clock.advance(Duration::from_secs(1800));
+                stats(&addr2)
+            })
+            .and_then(move |res| {
+                assert_eq!(res, (0, 0, 3));
+                clock2.advance(Duration::from_secs(1800));
+                stats(&addr3)
+            })
+            .then(|res| {
+                assert_eq!(res, Ok((1, 1, 6)));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(clock::now().duration_since(start), Duration::from_secs(3600));
+    TestClock::uninstall();
+}
+
+struct Wedged;
+
+impl Actor for Wedged {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        future::empty::<(), ()>().into_actor(self).wait(ctx);
+    }
+}
+
+impl Handler<Tick> for Wedged {
+    type Result = ();
+
+    fn handle(&mut self, _: Tick, _: &mut Context<Self>) {}
+}
+
+#[test]
+fn test_clock_request_timeout() {
+    let sys = System::new("test");
+    let clock = TestClock::install();
+
+    let addr: Addr<Unsync, _> = Wedged.start();
+    Arbiter::handle().spawn(
+        addr.send_timeout(Tick, Duration::from_secs(3600))
+            .then(|res| {
+                match res {
+                    Err(MailboxError::Timeout) => (),
+                    _ => panic!("Request should time out"),
+                }
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+    Arbiter::handle().spawn_fn(move || {
+        clock.advance(Duration::from_secs(3600));
+        future::result(Ok(()))
+    });
+
+    assert_eq!(sys.run(), 0);
+    TestClock::uninstall();
+}
+
+/// Schedules thousands of timers, cancels every other one
+struct Sessions {
+    fired: usize,
+}
+
+const SESSIONS: usize = 10_000;
+
+impl Actor for Sessions {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        for idx in 0..SESSIONS {
+            let dur = Duration::from_millis((idx % 50) as u64);
+            let deadline = Instant::now() + dur;
+            let handle = ctx.run_later(dur, move |act, _| {
+                assert!(Instant::now() >= deadline);
+                act.fired += 1;
+            });
+            if idx % 2 == 1 {
+                ctx.cancel_future(handle);
+            }
+        }
+        ctx.run_later(Duration::from_millis(200), |act, _| {
+            assert_eq!(act.fired, SESSIONS / 2);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        });
+    }
+}
+
+#[test]
+fn test_wheel_timers() {
+    let sys = System::new("test");
+    let _: Addr<Unsync, _> = Sessions{fired: 0}.start();
+    assert_eq!(sys.run(), 0);
+}
@@ -0,0 +1,273 @@
+#![cfg(feature="remote")]
+extern crate actix;
+extern crate futures;
+#[macro_use] extern crate serde_derive;
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use futures::Future;
+use actix::prelude::*;
+use actix::remote::{self, Node, RemoteError, RemoteMessage};
+use actix::remote::cluster::{self, Cluster, ClusterEvent, PhiAccrualDetector};
+
+type Events = Arc<Mutex<Vec<(SocketAddr, ClusterEvent)>>>;
+
+struct Recorder(SocketAddr, Events);
+
+impl Actor for Recorder {
+    type Context = Context<Self>;
+}
+
+impl Handler<ClusterEvent> for Recorder {
+    type Result = ();
+
+    fn handle(&mut self, msg: ClusterEvent, _: &mut Context<Self>) {
+        self.1.lock().unwrap().push((self.0, msg));
+    }
+}
+
+/// Stops system once condition is met, fails after timeout
+struct Check(Box<FnMut() -> bool>);
+
+impl Actor for Check {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.run_interval(Duration::from_millis(10), |act, _| {
+            if (act.0)() {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            }
+        });
+        ctx.run_later(Duration::from_secs(5), |_, _| {
+            Arbiter::system().do_send(actix::msgs::SystemExit(1));
+        });
+    }
+}
+
+fn check<F: FnMut() -> bool + 'static>(f: F) {
+    let _: Addr<Unsync, _> = Check(Box::new(f)).start();
+}
+
+fn start_member(events: &Events) -> (SocketAddr, Addr<Syn, Cluster>) {
+    let (local, _, cluster) = start_node(events);
+    (local, cluster)
+}
+
+fn start_node(events: &Events) -> (SocketAddr, Addr<Syn, Node>, Addr<Syn, Cluster>) {
+    start_node_with(events, Duration::from_millis(50))
+}
+
+fn start_node_with(events: &Events, interval: Duration)
+                   -> (SocketAddr, Addr<Syn, Node>, Addr<Syn, Cluster>)
+{
+    let node = Node::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let local = node.local_addr();
+    let node: Addr<Syn, _> = node.start();
+    let cluster: Addr<Syn, _> = Cluster::new(node.clone(), local)
+        .heartbeat_interval(interval)
+        .start();
+    let recorder: Addr<Syn, _> = Recorder(local, Arc::clone(events)).start();
+    cluster.do_send(cluster::Subscribe(recorder.recipient()));
+    (local, node, cluster)
+}
+
+fn count(events: &Events, event: &(SocketAddr, ClusterEvent)) -> usize {
+    events.lock().unwrap().iter().filter(|ev| *ev == event).count()
+}
+
+#[test]
+fn test_phi_accrual_detector() {
+    let interval = Duration::from_millis(100);
+    let mut detector = PhiAccrualDetector::new(cluster::DEFAULT_THRESHOLD, interval);
+    let start = Instant::now();
+    assert_eq!(detector.phi(start), 0.0);
+
+    for i in 0..10 {
+        detector.heartbeat(start + interval * i);
+    }
+    let last = start + interval * 9;
+    assert!(detector.phi(last + Duration::from_millis(50)) < 1.0);
+    assert!(detector.is_available(last + Duration::from_millis(150)));
+
+    let phi = detector.phi(last + Duration::from_millis(200));
+    assert!(phi < detector.phi(last + Duration::from_millis(300)));
+    assert!(!detector.is_available(last + Duration::from_secs(1)));
+
+    // heartbeat resets suspicion
+    detector.heartbeat(last + Duration::from_secs(1));
+    assert!(detector.is_available(last + Duration::from_millis(1050)));
+}
+
+#[test]
+fn test_cluster_join_leave() {
+    let sys = System::new("test");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let (a, _cluster_a) = start_member(&events);
+    let (b, cluster_b) = start_member(&events);
+    let (c, cluster_c) = start_member(&events);
+    cluster_b.do_send(cluster::Join(a));
+
+    let ev = Arc::clone(&events);
+    let mut joined = false;
+    check(move || {
+        if !joined {
+            if count(&ev, &(a, ClusterEvent::NodeUp(b))) == 1 &&
+                count(&ev, &(b, ClusterEvent::NodeUp(a))) == 1
+            {
+                // c knows only about b, a is discovered through b
+                cluster_c.do_send(cluster::Join(b));
+                joined = true;
+            }
+            false
+        } else if count(&ev, &(a, ClusterEvent::NodeUp(c))) == 1 &&
+            count(&ev, &(b, ClusterEvent::NodeUp(c))) == 1 &&
+            count(&ev, &(c, ClusterEvent::NodeUp(a))) == 1 &&
+            count(&ev, &(c, ClusterEvent::NodeUp(b))) == 1
+        {
+            cluster_c.do_send(cluster::Leave);
+            count(&ev, &(a, ClusterEvent::NodeDown(c))) == 1 &&
+                count(&ev, &(b, ClusterEvent::NodeDown(c))) == 1
+        } else {
+            false
+        }
+    });
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(events.lock().unwrap().len(), 10);
+}
+
+#[test]
+fn test_cluster_failure_detection() {
+    let sys = System::new("test");
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (a, cluster_a) = start_member(&events);
+
+    // member in separate arbiter, stopping arbiter stops heartbeats
+    let arbiter = Arbiter::new("member");
+    let ev = Arc::clone(&events);
+    let b = Arc::new(Mutex::new(None));
+    let b2 = Arc::clone(&b);
+    arbiter.do_send(actix::msgs::Execute::new(move || -> Result<(), ()> {
+        let (b, cluster_b) = start_member(&ev);
+        cluster_b.do_send(cluster::Join(a));
+        *b2.lock().unwrap() = Some(b);
+        Ok(())
+    }));
+
+    let ev = Arc::clone(&events);
+    let mut stopped = false;
+    check(move || {
+        let b = match *b.lock().unwrap() {
+            Some(b) => b,
+            None => return false,
+        };
+        if !stopped {
+            if count(&ev, &(a, ClusterEvent::NodeUp(b))) == 1 {
+                arbiter.do_send(actix::msgs::StopArbiter(0));
+                stopped = true;
+            }
+            false
+        } else {
+            count(&ev, &(a, ClusterEvent::NodeDown(b))) == 1
+        }
+    });
+
+    assert_eq!(sys.run(), 0);
+    drop(cluster_a);
+}
+
+#[test]
+fn test_cluster_rejoin() {
+    let sys = System::new("test");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    // a expects heartbeats every 50ms, b sends them every 500ms, so a marks
+    // b as down and admits it back on its next heartbeat
+    let (a, cluster_a) = start_member(&events);
+    let (b, _, cluster_b) = start_node_with(&events, Duration::from_millis(500));
+    cluster_b.do_send(cluster::Join(a));
+
+    let ev = Arc::clone(&events);
+    check(move || {
+        count(&ev, &(a, ClusterEvent::NodeDown(b))) >= 1 &&
+            count(&ev, &(a, ClusterEvent::NodeUp(b))) >= 2
+    });
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(count(&events, &(b, ClusterEvent::NodeUp(a))), 1);
+    assert_eq!(count(&events, &(b, ClusterEvent::NodeDown(a))), 0);
+    drop(cluster_a);
+}
+
+#[derive(Serialize, Deserialize)]
+struct Deposit(u64);
+
+impl Message for Deposit {
+    type Result = u64;
+}
+
+impl RemoteMessage for Deposit {
+    fn type_name() -> &'static str { "Deposit" }
+}
+
+struct Ledger(u64);
+
+impl Actor for Ledger {
+    type Context = Context<Self>;
+}
+
+impl Handler<Deposit> for Ledger {
+    type Result = u64;
+
+    fn handle(&mut self, msg: Deposit, _: &mut Context<Self>) -> u64 {
+        self.0 += msg.0;
+        self.0
+    }
+}
+
+#[test]
+fn test_cluster_lookup() {
+    let sys = System::new("test");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let (a, cluster_a) = start_member(&events);
+    let (b, cluster_b) = start_member(&events);
+    let (c, node_c, cluster_c) = start_node(&events);
+    let ledger: Addr<Syn, _> = Ledger(0).start();
+    node_c.do_send(remote::Register::new("ledger", ledger.recipient()));
+    cluster_b.do_send(cluster::Join(a));
+    cluster_c.do_send(cluster::Join(a));
+
+    let ev = Arc::clone(&events);
+    let mut started = false;
+    check(move || {
+        if !started && count(&ev, &(a, ClusterEvent::NodeUp(b))) == 1 &&
+            count(&ev, &(a, ClusterEvent::NodeUp(c))) == 1
+        {
+            started = true;
+            let cluster = cluster_a.clone();
+            Arbiter::handle().spawn(
+                cluster_a.send(cluster::Lookup::<Deposit>::new("missing"))
+                    .then(move |res| {
+                        match res {
+                            Ok(Err(RemoteError::UnknownRecipient(name))) =>
+                                assert_eq!(name, "missing"),
+                            _ => panic!("Should not happen"),
+                        }
+                        cluster.send(cluster::Lookup::<Deposit>::new("ledger"))
+                    })
+                    .map_err(|_| ())
+                    .and_then(|res| res.unwrap().send(Deposit(5)).map_err(|_| ()))
+                    .then(|res| {
+                        assert_eq!(res.unwrap(), 5);
+                        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                        Ok(())
+                    }));
+        }
+        false
+    });
+
+    assert_eq!(sys.run(), 0);
+}
@@ -4,14 +4,15 @@ extern crate futures;
 extern crate tokio_core;
 
 use std::time::Duration;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use futures::{future, Future};
 use futures::stream::once;
 use futures::unsync::mpsc::unbounded;
 use tokio_core::reactor::Timeout;
 use actix::prelude::*;
-use actix::msgs::SystemExit;
+use actix::msgs::{MailboxMetrics, MailboxReport, MailboxStats, SystemExit};
+use actix::test::{TestContext, TestProbe};
 
 #[derive(Debug, PartialEq)]
 enum Op {
@@ -128,6 +129,76 @@ fn test_run_after_stop() {
     sys.run();
 }
 
+struct IntervalActor {cnt: Arc<AtomicUsize>}
+
+impl Actor for IntervalActor {
+    type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(Duration::new(0, 1_000_000), |act, ctx| {
+            if act.cnt.fetch_add(1, Ordering::Relaxed) == 2 {
+                ctx.stop();
+            }
+        });
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        Arbiter::system().do_send(SystemExit(0));
+    }
+}
+
+#[test]
+fn test_run_interval() {
+    let sys = System::new("test");
+    let cnt = Arc::new(AtomicUsize::new(0));
+
+    let _addr: Addr<Unsync, _> = IntervalActor{cnt: Arc::clone(&cnt)}.start();
+
+    sys.run();
+    assert_eq!(cnt.load(Ordering::Relaxed), 3);
+}
+
+struct CancelActor {
+    cnt: Arc<AtomicUsize>,
+    fired: Arc<AtomicUsize>,
+    timer: Option<SpawnHandle>,
+    interval: Option<SpawnHandle>,
+}
+
+impl Actor for CancelActor {
+    type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.timer = Some(ctx.run_later(Duration::from_millis(100), |act, _| {
+            act.fired.fetch_add(1, Ordering::Relaxed);
+        }));
+        self.interval = Some(ctx.run_interval(Duration::new(0, 1_000_000), |act, ctx| {
+            if act.cnt.fetch_add(1, Ordering::Relaxed) == 2 {
+                // cancel pending timer and interval itself
+                assert!(ctx.cancel_future(act.timer.take().unwrap()));
+                assert!(ctx.cancel_future(act.interval.take().unwrap()));
+                ctx.run_later(Duration::from_millis(20), |_, _| {
+                    Arbiter::system().do_send(SystemExit(0));
+                });
+            }
+        }));
+    }
+}
+
+#[test]
+fn test_cancel_future() {
+    let sys = System::new("test");
+    let cnt = Arc::new(AtomicUsize::new(0));
+    let fired = Arc::new(AtomicUsize::new(0));
+
+    let _addr: Addr<Unsync, _> = CancelActor{cnt: Arc::clone(&cnt),
+                                             fired: Arc::clone(&fired),
+                                             timer: None, interval: None}.start();
+
+    sys.run();
+    assert_eq!(cnt.load(Ordering::Relaxed), 3);
+    assert_eq!(fired.load(Ordering::Relaxed), 0);
+}
 
 struct ContextWait {cnt: Arc<AtomicUsize>}
 
@@ -260,6 +331,125 @@ fn test_message_stream_nowait_context() {
     assert_eq!(m.load(Ordering::Relaxed), 3);
 }
 
+struct Bridge {cnt: Arc<AtomicUsize>}
+
+impl Actor for Bridge {
+    type Context = actix::Context<Self>;
+}
+
+impl Handler<Ping> for Bridge {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Self::Context) {
+        self.cnt.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_message_stream_finished() {
+    let sys = System::new("test");
+    let cnt = Arc::new(AtomicUsize::new(0));
+    let cnt2 = Arc::clone(&cnt);
+
+    Arbiter::handle().spawn_fn(move || {
+        let addr: Addr<Unsync, _> = Bridge::create(move |ctx| {
+            ctx.add_message_stream(futures::stream::iter_ok(vec![Ping, Ping]));
+            Bridge{cnt: cnt2}
+        });
+
+        // actor keeps running after stream is finished
+        Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+            .then(move |_| addr.send(Ping))
+            .then(|res| {
+                assert!(res.is_ok());
+                Arbiter::system().do_send(SystemExit(0));
+                future::result(Ok(()))
+            })
+    });
+
+    sys.run();
+    assert_eq!(cnt.load(Ordering::Relaxed), 3);
+}
+
+struct Flood {
+    events: Arc<Mutex<Vec<char>>>,
+    addr: Addr<Unsync, Flood>,
+}
+
+impl Actor for Flood {
+    type Context = actix::Context<Self>;
+}
+
+impl StreamHandler<usize, ()> for Flood {
+
+    fn handle(&mut self, _: usize, _: &mut Self::Context) {
+        self.events.lock().unwrap().push('s');
+        self.addr.do_send(Ping);
+    }
+
+    fn finished(&mut self, _: &mut Self::Context) {}
+}
+
+impl Handler<Ping> for Flood {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Self::Context) {
+        let mut events = self.events.lock().unwrap();
+        events.push('m');
+        if events.iter().filter(|ev| **ev == 'm').count() == 20 {
+            Arbiter::system().do_send(SystemExit(0));
+        }
+    }
+}
+
+#[test]
+fn test_stream_high_watermark() {
+    let sys = System::new("test");
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events2 = Arc::clone(&events);
+
+    Arbiter::handle().spawn_fn(move || {
+        let _: Addr<Unsync, _> = Flood::create(move |ctx| {
+            ctx.set_stream_high_watermark(4);
+            ctx.add_stream(futures::stream::iter_ok::<_, ()>(0..20));
+            Flood{events: events2, addr: ctx.address()}
+        });
+        Ok(())
+    });
+
+    sys.run();
+
+    // stream is paused once 4 messages are pending
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 40);
+    for chunk in events.chunks(8) {
+        assert_eq!(chunk, &['s', 's', 's', 's', 'm', 'm', 'm', 'm']);
+    }
+}
+
+#[test]
+fn test_stream_default_watermark() {
+    let sys = System::new("test");
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events2 = Arc::clone(&events);
+
+    Arbiter::handle().spawn_fn(move || {
+        let _: Addr<Unsync, _> = Flood::create(move |ctx| {
+            assert_eq!(ctx.stream_high_watermark(), 0);
+            ctx.add_stream(futures::stream::iter_ok::<_, ()>(0..20));
+            Flood{events: events2, addr: ctx.address()}
+        });
+        Ok(())
+    });
+
+    sys.run();
+
+    // stream is not paused by default
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 40);
+    assert!(events[..20].iter().all(|ev| *ev == 's'));
+}
+
 #[test]
 fn test_stream_nowait_context() {
     let sys = System::new("test");
@@ -298,6 +488,45 @@ fn test_notify() {
 }
 
 
+struct Retry {cnt: Arc<AtomicUsize>}
+
+impl Actor for Retry {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.notify(Ping);
+    }
+}
+
+impl Handler<Ping> for Retry {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, ctx: &mut Self::Context) {
+        if self.cnt.fetch_add(1, Ordering::Relaxed) < 2 {
+            ctx.notify_later(Ping, Duration::from_millis(1));
+        } else {
+            Arbiter::system().do_send(SystemExit(0));
+        }
+    }
+}
+
+#[test]
+fn test_notify_later_without_address() {
+    let sys = System::new("test");
+    let cnt = Arc::new(AtomicUsize::new(0));
+    let cnt2 = Arc::clone(&cnt);
+
+    Arbiter::handle().spawn_fn(move || {
+        // address is dropped, notifications still get delivered
+        let _: Addr<Unsync, _> = Retry{cnt: cnt2}.start();
+        Ok(())
+    });
+
+    sys.run();
+    assert_eq!(cnt.load(Ordering::Relaxed), 3);
+}
+
+
 struct ContextHandle {h: Arc<AtomicUsize>}
 impl Actor for ContextHandle {
     type Context = Context<Self>;
@@ -311,6 +540,49 @@ impl StreamHandler<Ping, ()> for ContextHandle {
     }
 }
 
+struct WaitingHandle {h: Arc<AtomicUsize>}
+
+impl Actor for WaitingHandle {
+    type Context = Context<Self>;
+}
+
+impl StreamHandler<Ping, ()> for WaitingHandle {
+
+    fn handle(&mut self, _: Ping, ctx: &mut Self::Context) {
+        Timeout::new(Duration::from_millis(5), Arbiter::handle()).unwrap()
+            .map_err(|_| ())
+            .into_actor(self)
+            .wait(ctx);
+        let addr: Addr<Unsync, _> = ctx.address();
+        addr.do_send(Ping);
+    }
+}
+
+impl Handler<Ping> for WaitingHandle {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, ctx: &mut Self::Context) {
+        self.h.store(ctx.handle().into_usize(), Ordering::Relaxed);
+        Arbiter::system().do_send(SystemExit(0));
+    }
+}
+
+#[test]
+fn test_context_handle_after_wait() {
+    let sys = System::new("test");
+
+    // message is not handled by stream item that scheduled wait future
+    let h = Arc::new(AtomicUsize::new(999));
+    let h2 = Arc::clone(&h);
+    let _addr: Addr<Unsync, _> = WaitingHandle::create(move |ctx| {
+        WaitingHandle::add_stream(once::<Ping, ()>(Ok(Ping)), ctx);
+        WaitingHandle{h: h2}
+    });
+    sys.run();
+
+    assert_eq!(h.load(Ordering::Relaxed), SpawnHandle::default().into_usize());
+}
+
 #[test]
 fn test_current_context_handle() {
     let sys = System::new("test");
@@ -350,3 +622,170 @@ fn test_start_from_context() {
 
     assert_eq!(m.load(Ordering::Relaxed), h.load(Ordering::Relaxed));
 }
+
+struct Data(usize);
+
+impl Message for Data {
+    type Result = ();
+}
+
+struct Control(usize);
+
+impl Message for Control {
+    type Result = ();
+}
+
+impl actix::Priority for Control {
+    fn priority() -> usize { 1 }
+}
+
+struct Prioritized(Arc<Mutex<Vec<(bool, usize)>>>);
+
+impl Actor for Prioritized {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.prioritize::<Control>();
+    }
+}
+
+impl actix::Handler<Data> for Prioritized {
+    type Result = ();
+
+    fn handle(&mut self, msg: Data, _: &mut Self::Context) {
+        self.0.lock().unwrap().push((false, msg.0));
+    }
+}
+
+impl actix::Handler<Control> for Prioritized {
+    type Result = ();
+
+    fn handle(&mut self, msg: Control, _: &mut Self::Context) {
+        let mut seen = self.0.lock().unwrap();
+        seen.push((true, msg.0));
+        if msg.0 == 1 {
+            Arbiter::system().do_send(SystemExit(0));
+        }
+    }
+}
+
+#[test]
+fn test_prioritized_messages() {
+    let sys = System::new("test");
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let addr: Addr<Syn, _> = Prioritized(Arc::clone(&seen)).start();
+    addr.do_send(Data(0));
+    addr.do_send(Data(1));
+    addr.do_send(Control(0));
+    addr.do_send(Data(2));
+    addr.do_send(Control(1));
+    sys.run();
+
+    assert_eq!(*seen.lock().unwrap(),
+               vec![(true, 0), (true, 1), (false, 0), (false, 1), (false, 2)]);
+}
+
+/// Prioritizing actor, checks if its mailbox is full while handling messages
+struct FullPrioritized(Arc<Mutex<Vec<bool>>>);
+
+impl Actor for FullPrioritized {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_mailbox_capacity(2);
+        ctx.prioritize::<Control>();
+    }
+}
+
+impl actix::Handler<Data> for FullPrioritized {
+    type Result = ();
+
+    fn handle(&mut self, msg: Data, ctx: &mut Self::Context) {
+        if msg.0 < 100 {
+            let addr: Addr<Unsync, _> = ctx.address();
+            self.0.lock().unwrap().push(addr.try_send(Data(100)).is_ok());
+        }
+    }
+}
+
+impl actix::Handler<Control> for FullPrioritized {
+    type Result = ();
+
+    fn handle(&mut self, _: Control, _: &mut Self::Context) {
+        Arbiter::system().do_send(SystemExit(0));
+    }
+}
+
+#[test]
+fn test_prioritized_messages_bounded() {
+    let sys = System::new("test");
+
+    // priority queue holds at most capacity messages, other messages stay
+    // in the mailbox and block senders
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let addr: Addr<Unsync, _> = FullPrioritized(Arc::clone(&sent)).start();
+    for i in 0..6 {
+        addr.do_send(Data(i));
+    }
+    addr.do_send(Control(0));
+    sys.run();
+
+    assert_eq!(*sent.lock().unwrap(), vec![false, false, false, false, true, true]);
+}
+
+struct Quiet(Option<Recipient<Syn, MailboxReport>>);
+
+impl Actor for Quiet {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        if let Some(collector) = self.0.take() {
+            ctx.report_mailbox_stats(Duration::from_millis(10), collector);
+        }
+    }
+}
+
+impl actix::msgs::MailboxMonitored for Quiet {}
+
+impl Handler<Ping> for Quiet {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+}
+
+#[test]
+fn test_mailbox_stats() {
+    let mut ctx = TestContext::new(Quiet(None));
+    let addr: Addr<Syn, _> = ctx.address();
+    addr.do_send(Ping);
+    ctx.do_send(Ping);
+    ctx.do_send(Ping);
+
+    assert_eq!(ctx.context().mailbox_stats(),
+               MailboxMetrics{pending: 3, capacity: 16, enqueued: 3, dequeued: 0});
+    assert!(ctx.run());
+    assert_eq!(ctx.context().mailbox_stats(),
+               MailboxMetrics{pending: 0, capacity: 16, enqueued: 3, dequeued: 3});
+    assert_eq!(ctx.send(MailboxStats).unwrap(),
+               MailboxMetrics{pending: 0, capacity: 16, enqueued: 4, dequeued: 4});
+}
+
+#[test]
+fn test_mailbox_report() {
+    let sys = System::new("test");
+    let probe = TestProbe::new();
+    let _addr: Addr<Unsync, _> = Quiet(Some(probe.recipient())).start();
+
+    Arbiter::handle().spawn(
+        probe.expect_msg::<MailboxReport>(Duration::from_secs(5))
+            .then(|res| {
+                let report = res.unwrap().msg;
+                assert_eq!(report.actor_type, std::any::TypeId::of::<Quiet>());
+                assert_eq!(report.metrics.pending, 0);
+                Arbiter::system().do_send(SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
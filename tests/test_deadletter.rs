@@ -0,0 +1,195 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::{future, Future};
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::deadletter::{self, DeadLetter, DeadLetterReason, DeadLetters};
+
+struct Ping;
+
+impl Message for Ping {
+    type Result = ();
+}
+
+struct MyActor;
+
+impl Actor for MyActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+impl Handler<Ping> for MyActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+}
+
+struct Stop;
+
+impl Message for Stop {
+    type Result = ();
+}
+
+struct StopActor;
+
+impl Actor for StopActor {
+    type Context = Context<Self>;
+}
+
+impl Handler<Ping> for StopActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+}
+
+impl Handler<Stop> for StopActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+struct Monitor(Arc<Mutex<Vec<DeadLetter>>>);
+
+impl Actor for Monitor {
+    type Context = Context<Self>;
+}
+
+impl Handler<DeadLetter> for Monitor {
+    type Result = ();
+
+    fn handle(&mut self, msg: DeadLetter, _: &mut Context<Self>) {
+        self.0.lock().unwrap().push(msg);
+    }
+}
+
+#[test]
+fn test_dead_letters() {
+    let sys = System::new("test");
+
+    let letters = Arc::new(Mutex::new(Vec::new()));
+    let monitor: Addr<Syn, _> = Monitor(Arc::clone(&letters)).start();
+    DeadLetters::from_registry().do_send(deadletter::Subscribe(monitor.recipient()));
+
+    let addr: Addr<Unsync, _> = MyActor.start();
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                addr.do_send(Ping);
+                Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            })
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+
+    let letters = letters.lock().unwrap();
+    assert_eq!(letters.len(), 1);
+    assert!(letters[0].is::<Ping>());
+    #[cfg(feature="spans")]
+    assert!(letters[0].message_name.unwrap().ends_with("Ping"));
+    #[cfg(not(feature="spans"))]
+    assert!(letters[0].message_name.is_none());
+    assert_eq!(letters[0].reason, DeadLetterReason::Closed);
+    assert!(letters[0].sender.is_some());
+    assert!(letters[0].actor.is_none());
+}
+
+fn monitor() -> Arc<Mutex<Vec<DeadLetter>>> {
+    let letters = Arc::new(Mutex::new(Vec::new()));
+    let monitor: Addr<Syn, _> = Monitor(Arc::clone(&letters)).start();
+    DeadLetters::from_registry().do_send(deadletter::Subscribe(monitor.recipient()));
+    letters
+}
+
+fn exit_later() {
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+}
+
+#[test]
+fn test_dead_letters_pending() {
+    let sys = System::new("test");
+    let letters = monitor();
+
+    let addr: Addr<Syn, _> = StopActor.start();
+    addr.do_send(Stop);
+    addr.do_send(Ping);
+    addr.do_send(Ping);
+    exit_later();
+
+    sys.run();
+
+    let letters = letters.lock().unwrap();
+    assert_eq!(letters.len(), 2);
+    assert!(letters.iter().all(|letter| letter.is::<Ping>()));
+    assert!(letters.iter().all(|letter| letter.reason == DeadLetterReason::Closed));
+}
+
+#[test]
+fn test_dead_letters_send_closed() {
+    let sys = System::new("test");
+    let letters = monitor();
+
+    let addr: Addr<Unsync, _> = MyActor.start();
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| addr.send(Ping))
+            .then(|res| {
+                match res {
+                    Err(MailboxError::Closed) => (),
+                    _ => panic!("Should fail"),
+                }
+                future::result(Ok(()))
+            })
+    );
+    exit_later();
+
+    sys.run();
+
+    let letters = letters.lock().unwrap();
+    assert_eq!(letters.len(), 1);
+    assert!(letters[0].is::<Ping>());
+    assert_eq!(letters[0].reason, DeadLetterReason::Closed);
+}
+
+#[test]
+fn test_dead_letters_try_send() {
+    let sys = System::new("test");
+    let letters = monitor();
+
+    let addr: Addr<Unsync, _> = MyActor.start();
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                // message is handed back to the sender
+                match addr.try_send(Ping) {
+                    Err(SendError::Closed(Ping)) => (),
+                    _ => panic!("Should fail"),
+                }
+                future::result(Ok(()))
+            })
+    );
+    exit_later();
+
+    sys.run();
+
+    assert!(letters.lock().unwrap().is_empty());
+}
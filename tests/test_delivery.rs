@@ -0,0 +1,129 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::Future;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::dev::{MessageResponse, ResponseChannel};
+use actix::delivery::{Deduplicator, Deliver, Post, ReliableDelivery};
+
+#[derive(Clone)]
+struct Order(u64);
+
+struct Shop {
+    dedup: Deduplicator,
+    failed: HashSet<u64>,
+    handled: Arc<Mutex<Vec<u64>>>,
+    duplicates: usize,
+}
+
+impl Actor for Shop {
+    type Context = Context<Self>;
+}
+
+impl actix::Supervised for Shop {}
+
+impl Handler<Deliver<Order>> for Shop {
+    type Result = ();
+
+    fn handle(&mut self, msg: Deliver<Order>, _: &mut Context<Self>) {
+        let first = self.failed.insert(msg.seq());
+        if msg.seq() == 2 && first {
+            panic!("Shop failed before order is handled");
+        }
+        if self.dedup.is_delivered(&msg) {
+            self.duplicates += 1;
+        } else {
+            self.handled.lock().unwrap().push(msg.msg().0);
+            self.dedup.confirm(&msg);
+        }
+        if self.handled.lock().unwrap().len() == 3 && self.duplicates == 1 {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }
+        if msg.seq() == 3 && first {
+            panic!("Shop failed before order is confirmed");
+        }
+    }
+}
+
+#[test]
+fn test_reliable_delivery() {
+    let sys = System::new("test");
+    let handled = Arc::new(Mutex::new(Vec::new()));
+
+    let handled2 = Arc::clone(&handled);
+    let shop: Addr<Syn, _> = actix::Supervisor::start(move |_| Shop {
+        dedup: Deduplicator::new(), failed: HashSet::new(),
+        handled: handled2, duplicates: 0});
+    let delivery: Addr<Unsync, _> =
+        ReliableDelivery::new(shop.recipient(), Duration::from_millis(50)).start();
+    for idx in 1..4 {
+        delivery.do_send(Post(Order(idx * 10)));
+    }
+
+    assert_eq!(sys.run(), 0);
+
+    // every order is handled exactly once
+    let mut handled = handled.lock().unwrap().clone();
+    handled.sort();
+    assert_eq!(handled, vec![10, 20, 30]);
+}
+
+/// Requests of silent recipient, checks if requester still waits
+type Requests = Rc<RefCell<Vec<Box<Fn() -> bool>>>>;
+
+/// Recipient that never confirms messages
+struct Silent(Requests);
+
+impl Actor for Silent {
+    type Context = Context<Self>;
+}
+
+struct Unanswered(Requests);
+
+impl MessageResponse<Silent, Deliver<Order>> for Unanswered {
+    fn handle<R: ResponseChannel<Deliver<Order>>>(self, _: &mut Context<Silent>, tx: Option<R>) {
+        if let Some(tx) = tx {
+            self.0.borrow_mut().push(Box::new(move || !tx.is_canceled()));
+        }
+    }
+}
+
+impl Handler<Deliver<Order>> for Silent {
+    type Result = Unanswered;
+
+    fn handle(&mut self, _: Deliver<Order>, _: &mut Context<Self>) -> Unanswered {
+        Unanswered(Rc::clone(&self.0))
+    }
+}
+
+#[test]
+fn test_reliable_delivery_timeout() {
+    let sys = System::new("test");
+    let requests: Requests = Rc::new(RefCell::new(Vec::new()));
+
+    let silent: Addr<Syn, _> = Silent(Rc::clone(&requests)).start();
+    let delivery: Addr<Unsync, _> =
+        ReliableDelivery::new(silent.recipient(), Duration::from_millis(10)).start();
+    delivery.do_send(Post(Order(1)));
+
+    let requests2 = Rc::clone(&requests);
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                // message is retried, failed attempts time out
+                let requests = requests2.borrow();
+                assert!(requests.len() > 2);
+                assert!(requests.iter().filter(|waits| waits()).count() <= 1);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
@@ -0,0 +1,208 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::thread;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use futures::{future, Future};
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::dispatcher::{self, Dispatcher, Job};
+
+struct Sleep(u64);
+
+impl Message for Sleep {
+    type Result = usize;
+}
+
+impl Job for Sleep {}
+
+struct Stop;
+
+impl Message for Stop {
+    type Result = ();
+}
+
+struct Worker {
+    id: usize,
+    dispatcher: Addr<Syn, Dispatcher<Sleep>>,
+    done: Arc<Mutex<Vec<u64>>>,
+}
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        dispatcher::pull(ctx, &self.dispatcher);
+    }
+}
+
+impl Handler<Sleep> for Worker {
+    type Result = usize;
+
+    fn handle(&mut self, msg: Sleep, _: &mut Context<Self>) -> usize {
+        thread::sleep(Duration::from_millis(msg.0));
+        self.done.lock().unwrap().push(msg.0);
+        self.id
+    }
+}
+
+impl Handler<Stop> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+#[test]
+fn test_dispatcher_pull() {
+    let sys = System::new("test");
+    let done = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Addr<Syn, _> = Dispatcher::new().start();
+    let _workers: Vec<Addr<Syn, _>> = (0..2).map(|id| {
+        let (addr, done) = (addr.clone(), Arc::clone(&done));
+        Arbiter::start(move |_| Worker{id, dispatcher: addr, done})
+    }).collect();
+
+    let jobs: Vec<_> = [100, 1, 1, 1, 1].iter().map(|ms| addr.send(Sleep(*ms))).collect();
+    Arbiter::handle().spawn(
+        future::join_all(jobs).then(|res| {
+            let ids = res.unwrap();
+            // idle worker takes all short jobs
+            assert!(ids[1..].iter().all(|id| *id == ids[1]));
+            assert!(ids[0] != ids[1]);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::result(Ok(()))
+        }));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*done.lock().unwrap(), vec![1, 1, 1, 1, 100]);
+}
+
+#[test]
+fn test_dispatcher_stopped_worker() {
+    let sys = System::new("test");
+    let done = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Addr<Syn, _> = Dispatcher::new().start();
+    let (addr2, done2) = (addr.clone(), Arc::clone(&done));
+    let stopped: Addr<Syn, _> =
+        Worker{id: 0, dispatcher: addr.clone(), done: Arc::clone(&done)}.start();
+
+    Arbiter::handle().spawn(
+        stopped.send(Stop)
+            .and_then(move |_| {
+                // job waits for next idle worker
+                let job = addr2.send(Sleep(0));
+                let _: Addr<Syn, _> = Worker{id: 1, dispatcher: addr2, done: done2}.start();
+                job
+            })
+            .then(|res| {
+                assert_eq!(res.unwrap(), 1);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*done.lock().unwrap(), vec![0]);
+}
+
+#[test]
+fn test_dispatcher_idle_worker_stopped() {
+    let sys = System::new("test");
+    let done = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Addr<Syn, _> = Dispatcher::new().start();
+    let (addr2, done2) = (addr.clone(), Arc::clone(&done));
+    let idle: Addr<Syn, _> =
+        Worker{id: 0, dispatcher: addr.clone(), done: Arc::clone(&done)}.start();
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                // worker stops while it waits for a job
+                idle.send(Stop)
+            })
+            .then(move |_| {
+                let _: Addr<Syn, _> =
+                    Worker{id: 1, dispatcher: addr2.clone(), done: done2}.start();
+                addr2.send(Sleep(0))
+            })
+            .then(|res| {
+                assert_eq!(res.unwrap(), 1);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*done.lock().unwrap(), vec![0]);
+}
+
+struct Wait(u64);
+
+impl Message for Wait {
+    type Result = Result<(), ()>;
+}
+
+impl Job for Wait {}
+
+struct AsyncWorker {
+    dispatcher: Addr<Syn, Dispatcher<Wait>>,
+    busy: Arc<AtomicUsize>,
+    max_busy: Arc<AtomicUsize>,
+}
+
+impl Actor for AsyncWorker {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        dispatcher::pull(ctx, &self.dispatcher);
+    }
+}
+
+impl Handler<Wait> for AsyncWorker {
+    type Result = ResponseFuture<(), ()>;
+
+    fn handle(&mut self, msg: Wait, _: &mut Context<Self>) -> Self::Result {
+        let busy = self.busy.fetch_add(1, Ordering::SeqCst) + 1;
+        if busy > self.max_busy.load(Ordering::SeqCst) {
+            self.max_busy.store(busy, Ordering::SeqCst);
+        }
+        let busy = Arc::clone(&self.busy);
+        Box::new(Timeout::new(Duration::from_millis(msg.0), Arbiter::handle()).unwrap()
+                 .then(move |_| {
+                     busy.fetch_sub(1, Ordering::SeqCst);
+                     Ok(())
+                 }))
+    }
+}
+
+#[test]
+fn test_dispatcher_async_worker() {
+    let sys = System::new("test");
+    let busy = Arc::new(AtomicUsize::new(0));
+    let max_busy = Arc::new(AtomicUsize::new(0));
+
+    let addr: Addr<Syn, _> = Dispatcher::new().start();
+    let _workers: Vec<Addr<Syn, _>> = (0..2).map(|_| {
+        AsyncWorker{dispatcher: addr.clone(),
+                    busy: Arc::clone(&busy),
+                    max_busy: Arc::clone(&max_busy)}.start()
+    }).collect();
+
+    let jobs: Vec<_> = (0..5).map(|_| addr.send(Wait(10))).collect();
+    Arbiter::handle().spawn(
+        future::join_all(jobs).then(|res| {
+            res.unwrap();
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::result(Ok(()))
+        }));
+
+    assert_eq!(sys.run(), 0);
+    // worker does not pull next job until response is resolved
+    assert_eq!(max_busy.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,108 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::Future;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::events::{self, EventStream, SystemEvent};
+
+struct Ping;
+
+impl Message for Ping {
+    type Result = ();
+}
+
+struct Short;
+
+impl Actor for Short {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+impl Handler<Ping> for Short {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+}
+
+struct Die;
+
+impl Message for Die {
+    type Result = ();
+}
+
+struct Phoenix;
+
+impl Actor for Phoenix {
+    type Context = Context<Self>;
+}
+
+impl actix::Supervised for Phoenix {}
+
+impl Handler<Die> for Phoenix {
+    type Result = ();
+
+    fn handle(&mut self, _: Die, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+struct Monitor(Arc<Mutex<Vec<&'static str>>>);
+
+impl Actor for Monitor {
+    type Context = Context<Self>;
+}
+
+impl Handler<SystemEvent> for Monitor {
+    type Result = ();
+
+    fn handle(&mut self, msg: SystemEvent, _: &mut Context<Self>) {
+        let name = match msg {
+            SystemEvent::ActorStarted{..} if msg.is::<Short>() => "short started",
+            SystemEvent::ActorStopped{..} if msg.is::<Short>() => "short stopped",
+            SystemEvent::ActorStarted{..} if msg.is::<Phoenix>() => "phoenix started",
+            SystemEvent::SupervisorRestarted{..} if msg.is::<Phoenix>() => "phoenix restarted",
+            SystemEvent::DeadLetter(ref letter) if letter.is::<Ping>() => "dead letter",
+            _ => return,
+        };
+        self.0.lock().unwrap().push(name);
+    }
+}
+
+#[test]
+fn test_system_events() {
+    let sys = System::new("test");
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen2 = Arc::clone(&seen);
+
+    Arbiter::handle().spawn_fn(move || {
+        let monitor: Addr<Syn, _> = Monitor(seen2).start();
+        EventStream::from_registry().send(events::Subscribe(monitor.recipient()))
+            .then(|_| {
+                let short: Addr<Syn, _> = Short.start();
+                let phoenix: Addr<Syn, _> = Supervisor::start(|_| Phoenix);
+                phoenix.do_send(Die);
+                Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+                    .then(move |_| {
+                        short.do_send(Ping);
+                        drop(phoenix);
+                        Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+                    })
+            })
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            })
+    });
+
+    sys.run();
+    assert_eq!(*seen.lock().unwrap(),
+               vec!["short started", "short stopped", "phoenix started",
+                    "phoenix restarted", "phoenix started", "dead letter"]);
+}
@@ -0,0 +1,175 @@
+extern crate actix;
+extern crate futures;
+
+use std::time::Duration;
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::clock::TestClock;
+use actix::fsm::{self, Next, StateHandler, StateMachine};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Gate {
+    Locked,
+    Unlocked,
+}
+
+struct Coin;
+
+impl Message for Coin {
+    type Result = ();
+}
+
+struct Push;
+
+impl Message for Push {
+    type Result = ();
+}
+
+struct Trace;
+
+impl Message for Trace {
+    type Result = (Option<Gate>, Vec<String>);
+}
+
+#[derive(Default)]
+struct Turnstile {
+    log: Vec<String>,
+}
+
+impl Actor for Turnstile {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        fsm::start(self, ctx);
+    }
+}
+
+impl StateMachine for Turnstile {
+    type State = Gate;
+
+    fn initial_state(&self) -> Gate {
+        Gate::Locked
+    }
+
+    fn on_enter(&mut self, state: &Gate, _: &mut Context<Self>) {
+        self.log.push(format!("enter {:?}", state));
+    }
+
+    fn on_exit(&mut self, state: &Gate, _: &mut Context<Self>) {
+        self.log.push(format!("exit {:?}", state));
+    }
+
+    fn state_timeout(&self, state: &Gate) -> Option<Duration> {
+        match *state {
+            Gate::Unlocked => Some(Duration::from_secs(10)),
+            Gate::Locked => None,
+        }
+    }
+
+    fn on_timeout(&mut self, state: &Gate, _: &mut Context<Self>) -> Next<Gate> {
+        self.log.push(format!("timeout {:?}", state));
+        Next::Goto(Gate::Locked)
+    }
+
+    fn unhandled(&mut self, state: &Gate, _: &mut Context<Self>) {
+        self.log.push(format!("unhandled {:?}", state));
+    }
+}
+
+impl StateHandler<Coin> for Turnstile {
+    fn handles(state: &Gate) -> bool {
+        *state == Gate::Locked
+    }
+
+    fn handle_in(&mut self, _: &Gate, _: Coin, _: &mut Context<Self>) -> Next<Gate> {
+        Next::Goto(Gate::Unlocked)
+    }
+}
+
+impl StateHandler<Push> for Turnstile {
+    fn handles(state: &Gate) -> bool {
+        *state == Gate::Unlocked
+    }
+
+    fn handle_in(&mut self, _: &Gate, _: Push, _: &mut Context<Self>) -> Next<Gate> {
+        Next::Goto(Gate::Locked)
+    }
+}
+
+impl Handler<Coin> for Turnstile {
+    type Result = ();
+
+    fn handle(&mut self, msg: Coin, ctx: &mut Context<Self>) {
+        fsm::handle(self, msg, ctx);
+    }
+}
+
+impl Handler<Push> for Turnstile {
+    type Result = ();
+
+    fn handle(&mut self, msg: Push, ctx: &mut Context<Self>) {
+        fsm::handle(self, msg, ctx);
+    }
+}
+
+impl Handler<Trace> for Turnstile {
+    type Result = MessageResult<Trace>;
+
+    fn handle(&mut self, _: Trace, ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult((fsm::state(ctx), self.log.clone()))
+    }
+}
+
+#[test]
+fn test_fsm_transitions() {
+    let sys = System::new("test");
+
+    let addr: Addr<Unsync, _> = Turnstile::default().start();
+    addr.do_send(Coin);
+    addr.do_send(Coin);
+    addr.do_send(Push);
+    addr.do_send(Push);
+
+    Arbiter::handle().spawn(
+        addr.send(Trace).then(|res| {
+            let (state, log) = res.unwrap();
+            assert_eq!(state, Some(Gate::Locked));
+            assert_eq!(log, vec!["enter Locked", "exit Locked", "enter Unlocked",
+                                 "unhandled Unlocked", "exit Unlocked", "enter Locked",
+                                 "unhandled Locked"]);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::result(Ok(()))
+        }));
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[test]
+fn test_fsm_state_timeout() {
+    let sys = System::new("test");
+    let clock = TestClock::install();
+
+    let addr: Addr<Unsync, _> = Turnstile::default().start();
+    addr.do_send(Coin);
+
+    let addr2 = addr.clone();
+    Arbiter::handle().spawn(
+        addr.send(Trace)
+            .and_then(move |(state, _)| {
+                assert_eq!(state, Some(Gate::Unlocked));
+                clock.advance(Duration::from_secs(10));
+                // due timer fires while first request is handled
+                let addr3 = addr2.clone();
+                addr2.send(Trace).and_then(move |_| addr3.send(Trace))
+            })
+            .then(|res| {
+                let (state, log) = res.unwrap();
+                assert_eq!(state, Some(Gate::Locked));
+                assert_eq!(&log[3..], ["timeout Unlocked", "exit Unlocked", "enter Locked"]);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+    TestClock::uninstall();
+}
@@ -2,7 +2,7 @@ extern crate actix;
 extern crate futures;
 extern crate tokio_core;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use futures::{Future, Stream};
@@ -89,3 +89,225 @@ fn test_stream_timeout() {
     sys.run();
     assert!(timeout.load(Ordering::Relaxed), "Not timeout");
 }
+
+fn delay(ms: u64) -> Box<Future<Item=(), Error=Error>> {
+    Box::new(Timeout::new(Duration::from_millis(ms), Arbiter::handle()).unwrap()
+             .map_err(|_| Error::Generic))
+}
+
+struct Combinators {
+    events: Vec<&'static str>,
+    result: Arc<AtomicBool>,
+}
+
+impl Actor for Combinators {
+    type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let slow = delay(10).into_actor(self)
+            .map(|_, act: &mut Combinators, _| { act.events.push("slow"); 2 });
+        let fast = delay(1).into_actor(self)
+            .map(|_, act: &mut Combinators, _| { act.events.push("fast"); 1 });
+
+        slow.join(fast)
+            .and_then(|res, act, _| {
+                assert_eq!(res, (2, 1));
+                assert_eq!(act.events, vec!["fast", "slow"]);
+
+                let slow = delay(10).into_actor(act)
+                    .map(|_, act: &mut Combinators, _| { act.events.push("slow"); 2 });
+                let fast = delay(1).into_actor(act)
+                    .map(|_, act: &mut Combinators, _| { act.events.push("fast"); 1 });
+                slow.select(fast)
+            })
+            .and_then(|res, act, _| {
+                assert_eq!(res, 1);
+                // slow future is dropped
+                delay(20).into_actor(act).map(|_, act: &mut Combinators, _| {
+                    assert_eq!(act.events, vec!["fast", "slow", "fast"]);
+                    act.result.store(true, Ordering::Relaxed);
+                })
+            })
+            .then(|_, _, _| {
+                Arbiter::system().do_send(SystemExit(0));
+                actix::fut::ok(())
+            })
+            .wait(ctx)
+    }
+}
+
+#[test]
+fn test_fut_join_select() {
+    let sys = System::new("test");
+    let result = Arc::new(AtomicBool::new(false));
+
+    let _addr: Addr<Unsync, _> = Combinators {
+        events: Vec::new(), result: Arc::clone(&result)}.start();
+
+    sys.run();
+    assert!(result.load(Ordering::Relaxed));
+}
+
+struct Summer {
+    items: Vec<usize>,
+    result: Arc<Mutex<Vec<usize>>>,
+}
+
+impl Actor for Summer {
+    type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        futures::stream::iter_ok::<_, ()>(1..10).into_actor(self)
+            .take_while(|item, _, _| actix::fut::ok(*item < 5))
+            .for_each(|item, act: &mut Summer, _| {
+                act.items.push(item);
+                actix::fut::ok(())
+            })
+            .then(|_, act, _| {
+                *act.result.lock().unwrap() = act.items.clone();
+                Arbiter::system().do_send(SystemExit(0));
+                actix::fut::ok(())
+            })
+            .wait(ctx)
+    }
+}
+
+#[test]
+fn test_stream_take_while_for_each() {
+    let sys = System::new("test");
+    let result = Arc::new(Mutex::new(Vec::new()));
+
+    let _addr: Addr<Unsync, _> = Summer {
+        items: Vec::new(), result: Arc::clone(&result)}.start();
+
+    sys.run();
+    assert_eq!(*result.lock().unwrap(), vec![1, 2, 3, 4]);
+}
+
+struct Fetched(Result<u32, Error>);
+
+impl Message for Fetched {
+    type Result = ();
+}
+
+impl From<Result<u32, Error>> for Fetched {
+    fn from(res: Result<u32, Error>) -> Fetched {
+        Fetched(res)
+    }
+}
+
+struct Fetcher(Arc<Mutex<Vec<Result<u32, Error>>>>);
+
+impl Actor for Fetcher {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let addr: Addr<Unsync, _> = ctx.address();
+        ctx.pipe(futures::future::err::<u32, _>(Error::Generic), addr);
+    }
+}
+
+impl Handler<Fetched> for Fetcher {
+    type Result = ();
+
+    fn handle(&mut self, msg: Fetched, _: &mut Context<Self>) {
+        let mut results = self.0.lock().unwrap();
+        results.push(msg.0);
+        if results.len() == 2 {
+            Arbiter::system().do_send(SystemExit(0));
+        }
+    }
+}
+
+#[test]
+fn test_pipe_to() {
+    let sys = System::new("test");
+    let results = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Addr<Syn, _> = Fetcher(Arc::clone(&results)).start();
+    Arbiter::handle().spawn(futures::future::ok(42).pipe_to(addr.recipient()));
+
+    assert_eq!(sys.run(), 0);
+    let results = results.lock().unwrap();
+    assert!(results.iter().any(|res| *res == Ok(42)));
+    assert!(results.iter().any(|res| *res == Err(Error::Generic)));
+}
+
+#[derive(Clone)]
+struct Price;
+
+impl Message for Price {
+    type Result = Result<u32, ()>;
+}
+
+/// Responds with price after `delay` milliseconds
+struct Shop {
+    price: u32,
+    delay: u64,
+}
+
+impl Actor for Shop {
+    type Context = Context<Self>;
+}
+
+impl Handler<Price> for Shop {
+    type Result = ResponseFuture<u32, ()>;
+
+    fn handle(&mut self, _: Price, _: &mut Context<Self>) -> Self::Result {
+        let price = self.price;
+        Box::new(actix::clock::Delay::new(Duration::from_millis(self.delay))
+                 .map(move |_| price).map_err(|_| ()))
+    }
+}
+
+type ScatterResult = Result<Vec<Result<Result<u32, ()>, MailboxError>>, MailboxError>;
+
+struct Buyer {
+    shops: Vec<Recipient<Syn, Price>>,
+    policy: actix::fut::GatherPolicy,
+    result: Arc<Mutex<Option<ScatterResult>>>,
+}
+
+impl Actor for Buyer {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        actix::fut::scatter(&self.shops, Price)
+            .timeout(Duration::from_millis(50))
+            .policy(self.policy)
+            .then(|res, act: &mut Buyer, _| {
+                *act.result.lock().unwrap() = Some(res);
+                Arbiter::system().do_send(SystemExit(0));
+                actix::fut::ok(())
+            })
+            .spawn(ctx);
+    }
+}
+
+fn scatter_shops(policy: actix::fut::GatherPolicy) -> ScatterResult {
+    let sys = System::new("test");
+    let result = Arc::new(Mutex::new(None));
+
+    let shops = vec![
+        Shop{price: 5, delay: 0}.start::<Addr<Syn, _>>().recipient(),
+        Shop{price: 10, delay: 500}.start::<Addr<Syn, _>>().recipient(),
+        Shop{price: 3, delay: 10}.start::<Addr<Syn, _>>().recipient(),
+    ];
+    let _buyer: Addr<Unsync, _> = Buyer{shops, policy, result: Arc::clone(&result)}.start();
+
+    assert_eq!(sys.run(), 0);
+    let res = result.lock().unwrap().take();
+    res.unwrap()
+}
+
+#[test]
+fn test_scatter_all() {
+    assert_eq!(scatter_shops(actix::fut::GatherPolicy::All), Err(MailboxError::Timeout));
+}
+
+#[test]
+fn test_scatter_partial() {
+    // failed recipient keeps its slot
+    assert_eq!(scatter_shops(actix::fut::GatherPolicy::Partial),
+               Ok(vec![Ok(Ok(5)), Err(MailboxError::Timeout), Ok(Ok(3))]));
+}
@@ -0,0 +1,85 @@
+#[macro_use] extern crate actix;
+extern crate futures;
+
+use futures::{future, Future};
+use actix::prelude::*;
+
+struct Add(u64);
+
+impl Message for Add {
+    type Result = u64;
+}
+
+struct Reset;
+
+impl Message for Reset {
+    type Result = ();
+}
+
+struct Get;
+
+impl Message for Get {
+    type Result = Option<u64>;
+}
+
+#[derive(Default)]
+struct Counter {
+    total: u64,
+    resets: usize,
+}
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+}
+
+impl Counter {
+    fn add(&mut self, msg: Add, _: &mut Context<Self>) -> u64 {
+        self.total += msg.0;
+        self.total
+    }
+
+    fn reset(&mut self, _: Reset, _: &mut Context<Self>) {
+        self.total = 0;
+        self.resets += 1;
+    }
+
+    fn get(&mut self, _: Get, _: &mut Context<Self>) -> Option<u64> {
+        if self.resets > 0 { Some(self.total) } else { None }
+    }
+}
+
+handler_enum! {
+    enum CounterMsg for Counter {
+        Add(Add) => add,
+        Reset(Reset) => reset,
+        Get(Get) => get,
+    }
+}
+
+#[test]
+fn test_handler_enum() {
+    let sys = System::new("test");
+
+    let addr: Addr<Unsync, _> = Counter::default().start();
+    let addr2 = addr.clone();
+    let addr3 = addr.clone();
+    Arbiter::handle().spawn(
+        addr.send(Add(2))
+            .and_then(move |total| {
+                assert_eq!(total, 2);
+                addr2.send(Get)
+            })
+            .and_then(move |res| {
+                assert_eq!(res, None);
+                addr3.do_send(CounterMsg::from(Reset));
+                addr3.do_send(CounterMsg::Add(Add(5)));
+                addr3.send(Get)
+            })
+            .then(|res| {
+                assert_eq!(res.unwrap(), Some(5));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
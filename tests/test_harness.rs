@@ -0,0 +1,201 @@
+extern crate actix;
+extern crate futures;
+
+use std::time::Duration;
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::clock::Delay;
+use actix::test::{ProbeError, TestContext, TestProbe};
+
+struct Counter {
+    count: usize,
+    timers: usize,
+    stopped: bool,
+}
+
+impl Counter {
+    fn new() -> Counter {
+        Counter{count: 0, timers: 0, stopped: false}
+    }
+}
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.run_later(Duration::from_secs(10), |act, _| act.timers += 1);
+    }
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        self.stopped = true;
+    }
+}
+
+struct Incr;
+
+impl Message for Incr {
+    type Result = usize;
+}
+
+impl Handler<Incr> for Counter {
+    type Result = MessageResult<Incr>;
+
+    fn handle(&mut self, _: Incr, _: &mut Context<Self>) -> Self::Result {
+        self.count += 1;
+        MessageResult(self.count)
+    }
+}
+
+struct Later(u64);
+
+impl Message for Later {
+    type Result = Result<usize, ()>;
+}
+
+impl Handler<Later> for Counter {
+    type Result = ResponseActFuture<Self, usize, ()>;
+
+    fn handle(&mut self, msg: Later, _: &mut Context<Self>) -> Self::Result {
+        Box::new(Delay::new(Duration::from_secs(msg.0))
+                 .into_actor(self)
+                 .map(|_, act, _| act.count))
+    }
+}
+
+#[test]
+fn test_harness_send() {
+    let mut ctx = TestContext::new(Counter::new());
+    assert_eq!(ctx.send(Incr).unwrap(), 1);
+    assert_eq!(ctx.state(), ActorState::Running);
+
+    ctx.do_send(Incr);
+    ctx.do_send(Incr);
+    assert_eq!(ctx.actor().count, 1);
+    assert!(ctx.run());
+    assert_eq!(ctx.actor().count, 3);
+}
+
+#[test]
+fn test_harness_timers() {
+    let mut ctx = TestContext::new(Counter::new());
+    assert!(ctx.run());
+    assert_eq!(ctx.actor().timers, 0);
+
+    assert!(ctx.advance(Duration::from_secs(5)));
+    assert_eq!(ctx.actor().timers, 0);
+    assert!(ctx.advance(Duration::from_secs(5)));
+    assert_eq!(ctx.actor().timers, 1);
+
+    match ctx.send(Later(1)) {
+        Err(MailboxError::Timeout) => (),
+        _ => panic!("Response should wait for timer"),
+    }
+    assert_eq!(ctx.send(Later(0)).unwrap(), Ok(0));
+}
+
+#[test]
+fn test_harness_clock_restored() {
+    use actix::test::TestClock;
+
+    // clock is removed once last context that uses it is dropped
+    let ctx = TestContext::new(Counter::new());
+    let other = TestContext::new(Counter::new());
+    drop(ctx);
+    assert!(TestClock::current().is_some());
+    drop(other);
+    assert!(TestClock::current().is_none());
+
+    // installed clock is kept
+    let clock = TestClock::install();
+    drop(TestContext::new(Counter::new()));
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(TestClock::current().map(|current| current.elapsed()),
+               Some(Duration::from_secs(1)));
+    TestClock::uninstall();
+}
+
+#[test]
+fn test_harness_stop() {
+    let mut ctx = TestContext::new(Counter::new());
+    assert_eq!(ctx.send(Incr).unwrap(), 1);
+
+    ctx.stop();
+    assert_eq!(ctx.state(), ActorState::Stopped);
+    assert!(ctx.actor().stopped);
+    assert!(!ctx.step());
+    match ctx.send(Incr) {
+        Err(MailboxError::Closed) => (),
+        _ => panic!("Actor is stopped"),
+    }
+}
+
+struct Pong(usize);
+
+impl Message for Pong {
+    type Result = ();
+}
+
+struct Done;
+
+impl Message for Done {
+    type Result = ();
+}
+
+/// Replies to `Incr` with `Pong` and notifies about stop with `Done`
+struct Ponger(Recipient<Syn, Pong>, Recipient<Syn, Done>);
+
+impl Actor for Ponger {
+    type Context = Context<Self>;
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        let _ = self.1.do_send(Done);
+    }
+}
+
+impl Handler<Incr> for Ponger {
+    type Result = MessageResult<Incr>;
+
+    fn handle(&mut self, _: Incr, ctx: &mut Context<Self>) -> Self::Result {
+        let _ = self.0.do_send(Pong(1));
+        ctx.stop();
+        MessageResult(0)
+    }
+}
+
+#[test]
+fn test_probe() {
+    let sys = System::new("test");
+    let probe = TestProbe::new();
+    let addr: Addr<Syn, _> = Ponger(probe.recipient(), probe.recipient()).start();
+    addr.do_send(Incr);
+
+    let p1 = probe.clone();
+    let p2 = probe.clone();
+    let p3 = probe.clone();
+    Arbiter::handle().spawn(
+        probe.expect_msg::<Pong>(Duration::from_secs(5))
+            .and_then(move |res| {
+                assert_eq!(res.msg.0, 1);
+                p1.expect_msg::<Pong>(Duration::from_secs(5))
+                    .then(move |res| {
+                        match res {
+                            Err(ProbeError::Unexpected) => (),
+                            _ => panic!("Next message is Done"),
+                        }
+                        assert_eq!(p2.pending(), 1);
+                        p2.expect_msg::<Done>(Duration::from_secs(5))
+                    })
+            })
+            .and_then(move |_| p3.expect_no_msg(Duration::from_millis(20))
+                      .and_then(move |_| p3.expect_msg::<Pong>(Duration::from_millis(20))))
+            .then(|res| {
+                match res {
+                    Err(ProbeError::Timeout) => (),
+                    _ => panic!("No messages expected"),
+                }
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
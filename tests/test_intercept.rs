@@ -0,0 +1,77 @@
+extern crate actix;
+extern crate futures;
+
+use std::any::TypeId;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::intercept::{Dispatch, Interceptor};
+
+struct Ping;
+
+impl Message for Ping {
+    type Result = ();
+}
+
+struct MyActor;
+
+impl Actor for MyActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_name("intercepted");
+    }
+}
+
+impl Handler<Ping> for MyActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+}
+
+#[derive(Clone, Default)]
+struct Audit {
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Interceptor for Audit {
+    fn before_dispatch(&self, dispatch: &Dispatch) {
+        if dispatch.message_type() == TypeId::of::<Ping>() {
+            assert_eq!(dispatch.actor_type(), TypeId::of::<MyActor>());
+            let name = dispatch.path().unwrap().name().to_owned();
+            self.log.lock().unwrap().push(format!("before {}", name));
+        }
+    }
+
+    fn after_dispatch(&self, dispatch: &Dispatch, _: Duration) {
+        if dispatch.message_type() == TypeId::of::<Ping>() {
+            self.log.lock().unwrap().push("after".to_owned());
+        }
+    }
+}
+
+#[test]
+fn test_interceptor() {
+    let sys = System::new("test");
+    let audit = Audit::default();
+    let id = System::add_interceptor(audit.clone());
+
+    let addr: Addr<Syn, _> = Arbiter::start(|_| MyActor);
+    let addr2 = addr.clone();
+    Arbiter::handle().spawn(
+        addr.send(Ping)
+            .and_then(move |_| {
+                assert!(System::remove_interceptor(id));
+                assert!(!System::remove_interceptor(id));
+                addr2.send(Ping)
+            })
+            .then(|res| {
+                res.unwrap();
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*audit.log.lock().unwrap(), vec!["before intercepted", "after"]);
+}
@@ -0,0 +1,295 @@
+extern crate actix;
+extern crate bytes;
+extern crate futures;
+extern crate tokio_core;
+extern crate tokio_io;
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net;
+use std::str;
+use std::thread;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use bytes::BytesMut;
+use futures::{Future, Stream};
+use tokio_core::net::{TcpListener, TcpStream, UdpCodec, UdpSocket};
+use tokio_io::codec::{Decoder, Encoder};
+use actix::prelude::*;
+use actix::io::{FramedContext, UdpFramed, WriteHandler};
+
+#[derive(Clone)]
+struct LineCodec;
+
+impl Decoder for LineCodec {
+    type Item = String;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<String>, io::Error> {
+        match src.iter().position(|b| *b == b'\n') {
+            Some(pos) => {
+                let line = src.split_to(pos + 1);
+                Ok(Some(String::from_utf8_lossy(&line[..pos]).into_owned()))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl Encoder for LineCodec {
+    type Item = String;
+    type Error = io::Error;
+
+    fn encode(&mut self, item: String, dst: &mut BytesMut) -> Result<(), io::Error> {
+        dst.extend_from_slice(item.as_bytes());
+        dst.extend_from_slice(b"\n");
+        Ok(())
+    }
+}
+
+/// Replies with upper-cased lines
+struct Upper;
+
+impl Actor for Upper {
+    type Context = FramedContext<Self, TcpStream, LineCodec>;
+}
+
+impl WriteHandler<io::Error> for Upper {}
+
+impl StreamHandler<String, io::Error> for Upper {
+    fn handle(&mut self, line: String, ctx: &mut Self::Context) {
+        ctx.send(line.to_uppercase());
+    }
+}
+
+struct Shout(String);
+
+impl Message for Shout {
+    type Result = ();
+}
+
+struct Client(Arc<Mutex<Vec<String>>>);
+
+impl Actor for Client {
+    type Context = FramedContext<Self, TcpStream, LineCodec>;
+}
+
+impl WriteHandler<io::Error> for Client {}
+
+impl StreamHandler<String, io::Error> for Client {
+    fn handle(&mut self, line: String, ctx: &mut Self::Context) {
+        self.0.lock().unwrap().push(line);
+        if self.0.lock().unwrap().len() == 2 {
+            ctx.close();
+        }
+    }
+
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        ctx.stop();
+    }
+}
+
+impl Handler<Shout> for Client {
+    type Result = ();
+
+    fn handle(&mut self, msg: Shout, ctx: &mut Self::Context) {
+        ctx.send(msg.0);
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    }
+}
+
+#[test]
+fn test_framed_context() {
+    let sys = System::new("test");
+    let lines = Arc::new(Mutex::new(Vec::new()));
+
+    let addr = net::SocketAddr::from(([127, 0, 0, 1], 0));
+    let listener = TcpListener::bind(&addr, Arbiter::handle()).unwrap();
+    let addr = listener.local_addr().unwrap();
+    Arbiter::handle().spawn(
+        listener.incoming().for_each(|(stream, _)| {
+            let _: () = FramedContext::start(Upper, stream, LineCodec);
+            Ok(())
+        }).map_err(|_| ()));
+
+    let lines2 = Arc::clone(&lines);
+    Arbiter::handle().spawn(
+        TcpStream::connect(&addr, Arbiter::handle()).map(move |stream| {
+            let client: Addr<Unsync, _> = FramedContext::create(
+                stream, LineCodec, move |ctx: &mut FramedContext<_, _, _>| {
+                    ctx.send("hello".to_owned());
+                    Client(lines2)
+                });
+            client.do_send(Shout("world".to_owned()));
+        }).map_err(|_| ()));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*lines.lock().unwrap(), vec!["HELLO".to_owned(), "WORLD".to_owned()]);
+}
+
+/// `name:value` counters
+struct CounterCodec;
+
+impl UdpCodec for CounterCodec {
+    type In = (net::SocketAddr, String, u64);
+    type Out = (net::SocketAddr, u64);
+
+    fn decode(&mut self, src: &net::SocketAddr, buf: &[u8]) -> io::Result<Self::In> {
+        let line = str::from_utf8(buf)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid utf8"))?;
+        let mut parts = line.splitn(2, ':');
+        let name = parts.next().unwrap().to_owned();
+        let value = parts.next().and_then(|value| value.parse().ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid counter"))?;
+        Ok((*src, name, value))
+    }
+
+    fn encode(&mut self, (addr, total): Self::Out, buf: &mut Vec<u8>) -> net::SocketAddr {
+        buf.extend_from_slice(total.to_string().as_bytes());
+        addr
+    }
+}
+
+/// Sums counters, replies with running total
+struct Counters {
+    framed: Option<UdpFramed<CounterCodec>>,
+    total: u64,
+}
+
+impl Actor for Counters {
+    type Context = Context<Self>;
+}
+
+impl WriteHandler<io::Error> for Counters {}
+
+impl StreamHandler<(net::SocketAddr, String, u64), io::Error> for Counters {
+    fn handle(&mut self, (addr, name, value): (net::SocketAddr, String, u64),
+              _: &mut Context<Self>) {
+        assert_eq!(name, "requests");
+        self.total += value;
+        self.framed.as_mut().unwrap().send((addr, self.total));
+    }
+}
+
+#[test]
+fn test_udp_framed() {
+    let sys = System::new("test");
+
+    let addr = net::SocketAddr::from(([127, 0, 0, 1], 0));
+    let socket = UdpSocket::bind(&addr, Arbiter::handle()).unwrap();
+    let addr = socket.local_addr().unwrap();
+    let _: Addr<Unsync, _> = Counters::create(move |ctx| {
+        let framed = UdpFramed::new(socket, CounterCodec, ctx);
+        Counters{framed: Some(framed), total: 0}
+    });
+
+    let client = thread::spawn(move || {
+        let socket = net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut buf = [0; 32];
+        // malformed datagram is skipped
+        socket.send_to(b"requests", addr).unwrap();
+        for &(value, total) in &[(3, "3"), (4, "7")] {
+            socket.send_to(format!("requests:{}", value).as_bytes(), addr).unwrap();
+            let (n, src) = socket.recv_from(&mut buf).unwrap();
+            assert_eq!(src, addr);
+            assert_eq!(&buf[..n], total.as_bytes());
+        }
+    });
+
+    let system = Arbiter::system();
+    thread::spawn(move || {
+        let code = if client.join().is_ok() { 0 } else { 1 };
+        system.do_send(actix::msgs::SystemExit(code));
+    });
+
+    assert_eq!(sys.run(), 0);
+}
+
+const FLOOD_FRAMES: usize = 256;
+
+/// Floods the peer, records backpressure events
+struct Flood(Arc<Mutex<Vec<&'static str>>>);
+
+impl Actor for Flood {
+    type Context = FramedContext<Self, TcpStream, LineCodec>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.set_buffer_capacity(1024, 16 * 1024);
+    }
+}
+
+impl WriteHandler<io::Error> for Flood {
+    fn drain_requested(&mut self, _: &mut Self::Context) {
+        self.0.lock().unwrap().push("drain");
+    }
+
+    fn drained(&mut self, _: &mut Self::Context) {
+        self.0.lock().unwrap().push("drained");
+    }
+}
+
+impl StreamHandler<String, io::Error> for Flood {
+    fn handle(&mut self, line: String, ctx: &mut Self::Context) {
+        if line == "flood" {
+            for _ in 0..FLOOD_FRAMES {
+                ctx.send(String::from_utf8(vec![b'x'; 16 * 1024]).unwrap());
+            }
+        } else {
+            self.0.lock().unwrap().push("ping");
+            ctx.send(line);
+        }
+    }
+}
+
+#[test]
+fn test_framed_backpressure() {
+    let sys = System::new("test");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let addr = net::SocketAddr::from(([127, 0, 0, 1], 0));
+    let listener = TcpListener::bind(&addr, Arbiter::handle()).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let events2 = Arc::clone(&events);
+    Arbiter::handle().spawn(
+        listener.incoming().for_each(move |(stream, _)| {
+            let _: () = FramedContext::start(Flood(Arc::clone(&events2)), stream, LineCodec);
+            Ok(())
+        }).map_err(|_| ()));
+
+    let client = thread::spawn(move || {
+        let mut stream = net::TcpStream::connect(addr).unwrap();
+        stream.write_all(b"flood\n").unwrap();
+        thread::sleep(Duration::from_millis(100));
+
+        // reading is paused until write buffer is drained
+        stream.write_all(b"ping\n").unwrap();
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(*events.lock().unwrap(), vec!["drain"]);
+
+        let mut received = 0;
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            if line == "ping\n" {
+                break
+            }
+            received += 1;
+        }
+        assert_eq!(received, FLOOD_FRAMES);
+        assert_eq!(*events.lock().unwrap(), vec!["drain", "drained", "ping"]);
+    });
+
+    let system = Arbiter::system();
+    thread::spawn(move || {
+        let code = if client.join().is_ok() { 0 } else { 1 };
+        system.do_send(actix::msgs::SystemExit(code));
+    });
+
+    assert_eq!(sys.run(), 0);
+}
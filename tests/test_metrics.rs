@@ -0,0 +1,133 @@
+#![cfg(feature="metrics")]
+extern crate actix;
+extern crate futures;
+
+use std::any::TypeId;
+use std::thread;
+use std::time::Duration;
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::metrics::{HandlerLatency, Histogram, MetricsRegistry};
+
+struct Slow;
+
+impl Message for Slow {
+    type Result = ();
+}
+
+struct Fast;
+
+impl Message for Fast {
+    type Result = ();
+}
+
+struct MyActor;
+
+impl Actor for MyActor {
+    type Context = Context<Self>;
+}
+
+impl Handler<Slow> for MyActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Slow, _: &mut Context<Self>) {
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+impl Handler<Fast> for MyActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Fast, _: &mut Context<Self>) {}
+}
+
+struct Remote;
+
+impl Message for Remote {
+    type Result = ();
+}
+
+impl Handler<Remote> for MyActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Remote, _: &mut Context<Self>) {}
+}
+
+#[test]
+fn test_histogram() {
+    let mut hist = Histogram::default();
+    for _ in 0..9 {
+        hist.record(Duration::new(0, 1000));
+    }
+    hist.record(Duration::from_millis(10));
+
+    assert_eq!(hist.count(), 10);
+    assert_eq!(hist.buckets()[0], 9);
+    assert_eq!(hist.buckets()[13], 1);
+    assert_eq!(hist.max(), Duration::from_millis(10));
+    assert_eq!(hist.mean(), Duration::from_millis(1));
+    assert_eq!(hist.percentile(0.5), Duration::new(0, 1000));
+    assert_eq!(hist.percentile(1.0), Duration::from_millis(10));
+
+    let mut other = Histogram::default();
+    other.merge(&hist);
+    assert_eq!(other.count(), 10);
+    assert_eq!(other.max(), Duration::from_millis(10));
+}
+
+#[test]
+fn test_handler_latency() {
+    let sys = System::new("test");
+    let addr: Addr<Syn, _> = MyActor.start();
+
+    for _ in 0..3 {
+        addr.do_send(Slow);
+        addr.do_send(Fast);
+    }
+    Arbiter::handle().spawn(
+        addr.send(Fast)
+            .and_then(|_| MetricsRegistry::from_registry().send(HandlerLatency))
+            .then(|res| {
+                let latency = res.unwrap();
+                let slow = &latency[&TypeId::of::<Slow>()];
+                assert_eq!(slow.count(), 3);
+                assert!(slow.mean() >= Duration::from_millis(5));
+                assert!(slow.percentile(0.5) >= Duration::from_millis(5));
+
+                let fast = &latency[&TypeId::of::<Fast>()];
+                assert_eq!(fast.count(), 4);
+                assert!(fast.max() < Duration::from_millis(5));
+
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[test]
+fn test_exited_thread_latency() {
+    // latencies of exited thread are kept
+    thread::spawn(|| {
+        let sys = System::new("exited");
+        let addr: Addr<Syn, _> = MyActor.start();
+        addr.do_send(Remote);
+        Arbiter::handle().spawn(
+            addr.send(Remote).then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+        sys.run();
+    }).join().unwrap();
+
+    let sys = System::new("test");
+    Arbiter::handle().spawn(
+        MetricsRegistry::from_registry().send(HandlerLatency)
+            .then(|res| {
+                assert_eq!(res.unwrap()[&TypeId::of::<Remote>()].count(), 2);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
@@ -0,0 +1,90 @@
+extern crate actix;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use actix::prelude::*;
+use actix::middleware::{MessageMeta, Middleware};
+
+struct Ping;
+
+impl Message for Ping {
+    type Result = ();
+}
+
+struct Tick;
+
+impl Message for Tick {
+    type Result = ();
+}
+
+struct Recorder {
+    name: &'static str,
+    log: Rc<RefCell<Vec<String>>>,
+}
+
+impl Recorder {
+    fn record(&self, hook: &str, meta: &MessageMeta) {
+        let msg = if meta.is::<Ping>() { "Ping" } else { "Tick" };
+        assert_eq!(meta.path().unwrap().as_str(), "/user/recorded");
+        self.log.borrow_mut().push(format!("{} {} {}", self.name, hook, msg));
+    }
+}
+
+impl Middleware for Recorder {
+    fn before_handle(&mut self, meta: &MessageMeta) {
+        self.record("before", meta);
+    }
+
+    fn after_handle(&mut self, meta: &MessageMeta, _: Duration) {
+        self.record("after", meta);
+    }
+}
+
+struct MyActor {
+    log: Rc<RefCell<Vec<String>>>,
+}
+
+impl Actor for MyActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_name("recorded");
+        ctx.add_middleware(Recorder{name: "outer", log: Rc::clone(&self.log)});
+        ctx.add_middleware(Recorder{name: "inner", log: Rc::clone(&self.log)});
+    }
+}
+
+impl Handler<Ping> for MyActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, ctx: &mut Context<Self>) {
+        self.log.borrow_mut().push("handle Ping".to_owned());
+        ctx.notify(Tick);
+    }
+}
+
+impl Handler<Tick> for MyActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Tick, _: &mut Context<Self>) {
+        self.log.borrow_mut().push("handle Tick".to_owned());
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    }
+}
+
+#[test]
+fn test_middleware() {
+    let sys = System::new("test");
+    let log = Rc::new(RefCell::new(Vec::new()));
+
+    let addr: Addr<Unsync, _> = MyActor{log: Rc::clone(&log)}.start();
+    addr.do_send(Ping);
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*log.borrow(),
+               vec!["outer before Ping", "inner before Ping", "handle Ping",
+                    "inner after Ping", "outer after Ping",
+                    "outer before Tick", "inner before Tick", "handle Tick",
+                    "inner after Tick", "outer after Tick"]);
+}
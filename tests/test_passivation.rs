@@ -0,0 +1,199 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use futures::{future, Future};
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::msgs::Passivate;
+
+struct Ping;
+
+impl Message for Ping {
+    type Result = ();
+}
+
+struct Entity {
+    starts: Arc<AtomicUsize>,
+    passivated: Arc<AtomicUsize>,
+}
+
+impl Actor for Entity {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        self.starts.fetch_add(1, Ordering::Relaxed);
+        ctx.set_idle_timeout(Duration::from_millis(30));
+    }
+}
+
+impl actix::Supervised for Entity {}
+
+impl Handler<Ping> for Entity {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+}
+
+impl Handler<Passivate> for Entity {
+    type Result = ();
+
+    fn handle(&mut self, _: Passivate, ctx: &mut Context<Self>) {
+        self.passivated.fetch_add(1, Ordering::Relaxed);
+        ctx.passivate();
+    }
+}
+
+#[test]
+fn test_passivation() {
+    let sys = System::new("test");
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let passivated = Arc::new(AtomicUsize::new(0));
+    let act = Entity{starts: Arc::clone(&starts), passivated: Arc::clone(&passivated)};
+    let addr: Addr<Syn, _> = actix::Supervisor::start(|_| act);
+
+    let (starts2, passivated2) = (Arc::clone(&starts), Arc::clone(&passivated));
+    let (starts3, passivated3) = (Arc::clone(&starts), Arc::clone(&passivated));
+    let (addr2, addr3) = (addr.clone(), addr.clone());
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(15), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                // message resets idle timer
+                addr2.do_send(Ping);
+                Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+            })
+            .then(move |_| {
+                assert_eq!(passivated2.load(Ordering::Relaxed), 0);
+                Timeout::new(Duration::from_millis(60), Arbiter::handle()).unwrap()
+            })
+            .then(move |_| {
+                // passivated actor is not restarted until next message
+                assert_eq!(passivated3.load(Ordering::Relaxed), 1);
+                assert_eq!(starts2.load(Ordering::Relaxed), 1);
+                addr3.send(Ping)
+            })
+            .then(move |res| {
+                res.unwrap();
+                assert_eq!(starts3.load(Ordering::Relaxed), 2);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+    drop(addr);
+}
+
+struct Count;
+
+impl Message for Count {
+    type Result = usize;
+}
+
+struct Counter {
+    count: usize,
+    passivated: Arc<AtomicUsize>,
+    passivate: bool,
+}
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_idle_timeout(Duration::from_millis(20));
+    }
+}
+
+impl actix::Supervised for Counter {}
+
+impl Handler<Count> for Counter {
+    type Result = usize;
+
+    fn handle(&mut self, _: Count, _: &mut Context<Self>) -> usize {
+        self.count += 1;
+        self.count
+    }
+}
+
+impl Handler<Passivate> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, _: Passivate, ctx: &mut Context<Self>) {
+        self.passivated.fetch_add(1, Ordering::Relaxed);
+        if self.passivate {
+            ctx.passivate();
+        }
+    }
+}
+
+#[test]
+fn test_passivation_lazy_fresh() {
+    let sys = System::new("test");
+
+    let created = Arc::new(AtomicUsize::new(0));
+    let passivated = Arc::new(AtomicUsize::new(0));
+    let (created2, passivated2) = (Arc::clone(&created), Arc::clone(&passivated));
+    let addr: Addr<Syn, _> = actix::Supervisor::start_lazy(
+        actix::supervisor::AlwaysRestart, move |_| {
+            created2.fetch_add(1, Ordering::Relaxed);
+            Counter{count: 0, passivated: Arc::clone(&passivated2), passivate: true}
+        });
+
+    let (created3, passivated3) = (Arc::clone(&created), Arc::clone(&passivated));
+    let (addr2, addr3) = (addr.clone(), addr.clone());
+    Arbiter::handle().spawn(
+        addr.send(Count)
+            .then(move |res| {
+                assert_eq!(res.unwrap(), 1);
+                Timeout::new(Duration::from_millis(60), Arbiter::handle()).unwrap()
+            })
+            .then(move |_| {
+                assert_eq!(passivated3.load(Ordering::Relaxed), 1);
+                assert_eq!(created3.load(Ordering::Relaxed), 1);
+                addr2.send(Count)
+            })
+            .then(move |res| {
+                // passivated actor is re-created with factory, old state is dropped
+                assert_eq!(res.unwrap(), 1);
+                drop(addr3);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(created.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_passivation_once_per_idle_period() {
+    let sys = System::new("test");
+
+    let passivated = Arc::new(AtomicUsize::new(0));
+    let act = Counter{count: 0, passivated: Arc::clone(&passivated), passivate: false};
+    let addr: Addr<Syn, _> = actix::Supervisor::start(|_| act);
+
+    let (passivated2, passivated3) = (Arc::clone(&passivated), Arc::clone(&passivated));
+    let addr2 = addr.clone();
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                // actor ignored `Passivate`, it is not sent again while idle
+                assert_eq!(passivated2.load(Ordering::Relaxed), 1);
+                addr2.send(Count)
+            })
+            .then(move |res| {
+                assert_eq!(res.unwrap(), 1);
+                Timeout::new(Duration::from_millis(60), Arbiter::handle()).unwrap()
+            })
+            .then(move |_| {
+                assert_eq!(passivated3.load(Ordering::Relaxed), 2);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+    drop(addr);
+}
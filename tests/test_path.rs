@@ -0,0 +1,142 @@
+extern crate actix;
+extern crate futures;
+
+use std::any::TypeId;
+use std::sync::mpsc;
+use std::time::Duration;
+use futures::Future;
+use actix::prelude::*;
+use actix::ActorPath;
+use actix::clock::Delay;
+
+struct Die;
+
+impl Message for Die {
+    type Result = ();
+}
+
+struct Worker;
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+impl Handler<Die> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Die, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+struct Payments {
+    workers: Vec<Addr<Syn, Worker>>,
+}
+
+impl Actor for Payments {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_name("payments");
+        for idx in 0..3 {
+            self.workers.push(Worker::create(move |ctx| {
+                ctx.set_name(format!("worker-{}", idx));
+                Worker
+            }));
+        }
+        self.workers.push(Worker.start());
+    }
+}
+
+impl Handler<Die> for Payments {
+    type Result = ();
+
+    fn handle(&mut self, _: Die, _: &mut Context<Self>) {
+        self.workers[1].do_send(Die);
+    }
+}
+
+#[test]
+fn test_path() {
+    let path = ActorPath::root().child("payments").child("worker-3");
+    assert_eq!(path.as_str(), "/user/payments/worker-3");
+    assert_eq!(path.name(), "worker-3");
+    assert_eq!(format!("{}", path.parent().unwrap()), "/user/payments");
+    assert_eq!(path.parent().unwrap().parent(), Some(ActorPath::root()));
+    assert_eq!(ActorPath::root().parent(), None);
+}
+
+#[test]
+fn test_path_lookup() {
+    let sys = System::new("test");
+    let payments: Addr<Syn, _> = Payments{workers: Vec::new()}.start();
+    let payments2 = payments.clone();
+
+    Arbiter::handle().spawn(
+        Delay::new(Duration::from_millis(20)).map(move |_| {
+            let registry = System::registry();
+            let info = registry.lookup_path("/user/payments").unwrap();
+            assert_eq!(info.actor_type, TypeId::of::<Payments>());
+            assert_eq!(info.arbiter, Some("test".to_owned()));
+
+            let children = registry.children("/user/payments");
+            assert_eq!(children.len(), 4);
+            assert!(children[0].path.name().starts_with('$'));
+            assert_eq!(children[1].path.as_str(), "/user/payments/worker-0");
+            assert_eq!(children[3].path.as_str(), "/user/payments/worker-2");
+            assert!(children.iter().all(|info| info.actor_type == TypeId::of::<Worker>()));
+            payments2.do_send(Die);
+        }));
+
+    Arbiter::handle().spawn(
+        Delay::new(Duration::from_millis(40)).map(move |_| {
+            let registry = System::registry();
+            assert!(registry.lookup_path("/user/payments").is_some());
+            assert!(registry.lookup_path("/user/payments/worker-1").is_none());
+            assert_eq!(registry.children("/user/payments").len(), 3);
+            drop(payments);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }));
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[test]
+fn test_path_lookup_arbiter() {
+    let sys = System::new("test");
+    let (tx, rx) = mpsc::channel();
+    let (addr_tx, addr_rx) = mpsc::channel();
+    let arbiter = Arbiter::new("worker");
+    arbiter.do_send(actix::msgs::Execute::new(move || -> Result<(), ()> {
+        let addr: Addr<Syn, _> = Worker::create(move |ctx| {
+            ctx.set_name("remote");
+            tx.send(ctx.path().clone()).unwrap();
+            Worker
+        });
+        addr_tx.send(addr).unwrap();
+        Ok(())
+    }));
+    let path = rx.recv().unwrap();
+    let _worker = addr_rx.recv().unwrap();
+
+    let path2 = path.clone();
+    Arbiter::handle().spawn(
+        Delay::new(Duration::from_millis(40)).map(move |_| {
+            let registry = System::registry();
+            let info = registry.lookup_path(path2.as_str()).unwrap();
+            assert_eq!(info.actor_type, TypeId::of::<Worker>());
+            assert_ne!(info.arbiter, Some("test".to_owned()));
+            let parent = path2.parent().unwrap();
+            assert_eq!(registry.children(parent.as_str()).iter()
+                       .filter(|info| info.path == path2).count(), 1);
+            arbiter.do_send(actix::msgs::StopArbiter(0));
+        }));
+
+    Arbiter::handle().spawn(
+        Delay::new(Duration::from_millis(100)).map(move |_| {
+            assert!(System::registry().lookup_path(path.as_str()).is_none());
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }));
+
+    assert_eq!(sys.run(), 0);
+}
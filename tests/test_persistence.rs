@@ -0,0 +1,339 @@
+#![cfg(feature="persistence")]
+extern crate actix;
+extern crate futures;
+#[macro_use] extern crate serde_derive;
+
+use std::{env, fs};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use futures::Future;
+use actix::prelude::*;
+use actix::persistence::{self, FileJournal, Journal, MemoryJournal, MemorySnapshotStore,
+                         PersistentActor, PersistentContext, Snapshot, SnapshotStore};
+use actix::supervisor::AlwaysRestart;
+
+#[derive(Serialize, Deserialize)]
+enum Event {
+    Deposited(u64),
+    Withdrawn(u64),
+}
+
+struct Deposit(u64);
+
+impl Message for Deposit {
+    type Result = u64;
+}
+
+struct Withdraw(u64);
+
+impl Message for Withdraw {
+    type Result = ();
+}
+
+struct Balance;
+
+impl Message for Balance {
+    type Result = (u64, usize);
+}
+
+struct Crash;
+
+impl Message for Crash {
+    type Result = ();
+}
+
+struct Account {
+    balance: u64,
+    recovered: usize,
+    journal: Arc<Journal>,
+}
+
+impl Account {
+    fn apply(&mut self, event: &Event) {
+        match *event {
+            Event::Deposited(amount) => self.balance += amount,
+            Event::Withdrawn(amount) => self.balance -= amount,
+        }
+    }
+}
+
+impl Actor for Account {
+    type Context = Context<Self>;
+}
+
+impl actix::Supervised for Account {}
+
+impl PersistentActor for Account {
+    type Event = Event;
+
+    fn persistence_id(&self) -> String {
+        "account".to_owned()
+    }
+
+    fn journal(&self) -> Arc<Journal> {
+        Arc::clone(&self.journal)
+    }
+
+    fn recover(&mut self, event: Event, _: &mut Context<Self>) {
+        self.recovered += 1;
+        self.apply(&event);
+    }
+}
+
+impl Handler<Deposit> for Account {
+    type Result = MessageResult<Deposit>;
+
+    fn handle(&mut self, msg: Deposit, ctx: &mut Context<Self>) -> Self::Result {
+        ctx.persist(Event::Deposited(msg.0), |act, event, _| act.apply(&event));
+        // event is applied after handler returns
+        MessageResult(self.balance)
+    }
+}
+
+impl Handler<Withdraw> for Account {
+    type Result = ();
+
+    fn handle(&mut self, msg: Withdraw, ctx: &mut Context<Self>) {
+        if msg.0 <= self.balance {
+            ctx.persist(Event::Withdrawn(msg.0), |act, event, _| act.apply(&event));
+        }
+    }
+}
+
+impl Handler<Balance> for Account {
+    type Result = MessageResult<Balance>;
+
+    fn handle(&mut self, _: Balance, _: &mut Context<Self>) -> Self::Result {
+        MessageResult((self.balance, self.recovered))
+    }
+}
+
+impl Handler<Crash> for Account {
+    type Result = ();
+
+    fn handle(&mut self, _: Crash, _: &mut Context<Self>) {
+        panic!("Account crashed");
+    }
+}
+
+fn account(journal: &Arc<Journal>) -> Addr<Unsync, Account> {
+    let journal = Arc::clone(journal);
+    persistence::start(
+        AlwaysRestart, move || Account{balance: 0, recovered: 0, journal: Arc::clone(&journal)})
+}
+
+#[test]
+fn test_persistent_actor() {
+    let sys = System::new("test");
+    let journal: Arc<Journal> = Arc::new(MemoryJournal::new());
+
+    let addr = account(&journal);
+    addr.do_send(Deposit(10));
+    addr.do_send(Withdraw(30));
+    addr.do_send(Deposit(20));
+    addr.do_send(Withdraw(5));
+
+    let journal2 = Arc::clone(&journal);
+    let addr2 = addr.clone();
+    Arbiter::handle().spawn(
+        addr.clone().send(Deposit(0))
+            .and_then(move |balance| {
+                // previous events are applied before next message is handled
+                assert_eq!(balance, 25);
+                addr2.do_send(Crash);
+                addr2.send(Balance)
+            })
+            .and_then(move |res| {
+                // restarted actor replays journal
+                assert_eq!(res, (25, 4));
+                let other = account(&journal2);
+                other.send(Balance).map(move |res| (other, res))
+            })
+            .then(|res| {
+                assert_eq!(res.unwrap().1, (25, 4));
+                drop(addr);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[derive(Serialize, Deserialize)]
+struct Added(u64);
+
+struct AddAll(Vec<u64>);
+
+impl Message for AddAll {
+    type Result = ();
+}
+
+struct TakeSnapshot;
+
+impl Message for TakeSnapshot {
+    type Result = ();
+}
+
+struct State;
+
+impl Message for State {
+    type Result = (u64, usize, Vec<u64>);
+}
+
+struct Counter {
+    total: u64,
+    replayed: usize,
+    applied: Vec<u64>,
+    journal: Arc<Journal>,
+    snapshots: Arc<SnapshotStore>,
+}
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+}
+
+impl actix::Supervised for Counter {}
+
+impl PersistentActor for Counter {
+    type Event = Added;
+
+    fn persistence_id(&self) -> String {
+        "counter".to_owned()
+    }
+
+    fn journal(&self) -> Arc<Journal> {
+        Arc::clone(&self.journal)
+    }
+
+    fn snapshot_store(&self) -> Option<Arc<SnapshotStore>> {
+        Some(Arc::clone(&self.snapshots))
+    }
+
+    fn recover(&mut self, event: Added, _: &mut Context<Self>) {
+        self.replayed += 1;
+        self.total += event.0;
+    }
+
+    fn recover_snapshot(&mut self, snapshot: Snapshot, _: &mut Context<Self>) -> bool {
+        match snapshot.decode() {
+            Ok(total) => {
+                self.total = total;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+impl Handler<AddAll> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, msg: AddAll, ctx: &mut Context<Self>) {
+        for value in msg.0 {
+            ctx.persist(Added(value), |act, event, _| {
+                act.total += event.0;
+                act.applied.push(event.0);
+            });
+        }
+    }
+}
+
+impl Handler<TakeSnapshot> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, _: TakeSnapshot, ctx: &mut Context<Self>) {
+        ctx.save_snapshot(self.total);
+    }
+}
+
+impl Handler<State> for Counter {
+    type Result = MessageResult<State>;
+
+    fn handle(&mut self, _: State, _: &mut Context<Self>) -> Self::Result {
+        MessageResult((self.total, self.replayed, self.applied.clone()))
+    }
+}
+
+impl Handler<Crash> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, _: Crash, _: &mut Context<Self>) {
+        panic!("Counter crashed");
+    }
+}
+
+#[test]
+fn test_persistent_snapshot() {
+    let sys = System::new("test");
+    let journal: Arc<Journal> = Arc::new(MemoryJournal::new());
+    let snapshots: Arc<SnapshotStore> = Arc::new(MemorySnapshotStore::new());
+
+    let store = Arc::clone(&snapshots);
+    let addr: Addr<Unsync, _> = persistence::start(AlwaysRestart, move || Counter {
+        total: 0, replayed: 0, applied: Vec::new(),
+        journal: Arc::clone(&journal), snapshots: Arc::clone(&store)});
+    addr.do_send(AddAll(vec![1, 2, 3]));
+    addr.do_send(TakeSnapshot);
+    addr.do_send(AddAll(vec![4]));
+
+    let addr2 = addr.clone();
+    Arbiter::handle().spawn(
+        addr.send(State)
+            .and_then(move |res| {
+                // events of one handler are applied in order
+                assert_eq!(res, (10, 0, vec![1, 2, 3, 4]));
+                addr2.do_send(Crash);
+                addr2.send(State).map(move |res| (addr2, res))
+            })
+            .then(move |res| {
+                // only event persisted after the snapshot is replayed
+                assert_eq!(res.unwrap().1, (10, 1, vec![]));
+                let snapshot = snapshots.load("counter").wait().unwrap();
+                assert_eq!(snapshot.map(|(seq, _)| seq), Some(3));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
+
+fn seqs(journal: &Journal, id: &str, from: u64) -> Vec<u64> {
+    journal.read_from(id, from).wait().unwrap().into_iter().map(|(seq, _)| seq).collect()
+}
+
+fn check_journal(journal: &Journal) {
+    for idx in 1..4 {
+        assert_eq!(journal.append("orders/1", vec![idx as u8]).wait().unwrap(), idx);
+    }
+    assert_eq!(journal.read_from("orders/1", 2).wait().unwrap(),
+               vec![(2, vec![2]), (3, vec![3])]);
+
+    journal.delete_to("orders/1", 2).wait().unwrap();
+    assert_eq!(seqs(journal, "orders/1", 1), vec![3]);
+
+    // sequence numbers of deleted events are not reused
+    journal.delete_to("orders/1", 10).wait().unwrap();
+    assert!(seqs(journal, "orders/1", 1).is_empty());
+    assert_eq!(journal.append("orders/1", vec![4]).wait().unwrap(), 4);
+
+    assert!(seqs(journal, "orders/2", 1).is_empty());
+}
+
+#[test]
+fn test_memory_journal() {
+    check_journal(&MemoryJournal::new());
+}
+
+#[test]
+fn test_file_journal() {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().subsec_nanos();
+    let dir = env::temp_dir().join(format!("actix-journal-{}", nanos));
+    check_journal(&FileJournal::new(&dir).unwrap());
+
+    // journal is recovered from files
+    let journal = FileJournal::new(&dir).unwrap();
+    assert_eq!(journal.read_from("orders/1", 1).wait().unwrap(), vec![(4, vec![4])]);
+    assert_eq!(journal.append("orders/1", vec![5]).wait().unwrap(), 5);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,146 @@
+#[macro_use] extern crate actix;
+extern crate futures;
+
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::protocol::ProtocolAddr;
+
+struct Greet(&'static str);
+
+impl Message for Greet {
+    type Result = String;
+}
+
+struct Count;
+
+impl Message for Count {
+    type Result = usize;
+}
+
+protocol! {
+    /// Messages of greeters
+    protocol Greeter {
+        Greet,
+        Count,
+    }
+}
+
+struct Reset;
+
+impl Message for Reset {
+    type Result = ();
+}
+
+protocol! {
+    /// Messages of resettable greeters
+    protocol Resettable {
+        Greet,
+        Count,
+        Reset,
+    }
+}
+
+#[derive(Default)]
+struct English(usize);
+
+impl Actor for English {
+    type Context = Context<Self>;
+}
+
+impl Handler<Greet> for English {
+    type Result = String;
+
+    fn handle(&mut self, msg: Greet, _: &mut Context<Self>) -> String {
+        self.0 += 1;
+        format!("Hello, {}", msg.0)
+    }
+}
+
+impl Handler<Count> for English {
+    type Result = usize;
+
+    fn handle(&mut self, _: Count, _: &mut Context<Self>) -> usize {
+        self.0
+    }
+}
+
+impl Handler<Reset> for English {
+    type Result = ();
+
+    fn handle(&mut self, _: Reset, _: &mut Context<Self>) {
+        self.0 = 0;
+    }
+}
+
+#[derive(Default)]
+struct French(usize);
+
+impl Actor for French {
+    type Context = Context<Self>;
+}
+
+impl Handler<Greet> for French {
+    type Result = String;
+
+    fn handle(&mut self, msg: Greet, _: &mut Context<Self>) -> String {
+        self.0 += 1;
+        format!("Bonjour, {}", msg.0)
+    }
+}
+
+impl Handler<Count> for French {
+    type Result = usize;
+
+    fn handle(&mut self, _: Count, _: &mut Context<Self>) -> usize {
+        self.0
+    }
+}
+
+#[test]
+fn test_protocol_addr() {
+    let sys = System::new("test");
+
+    let english: Addr<Syn, _> = English::default().start();
+    let french: Addr<Syn, _> = Arbiter::start(|_| French::default());
+    let greeters: Vec<ProtocolAddr<Greeter>> =
+        vec![ProtocolAddr::new(&english), ProtocolAddr::new(&french)];
+
+    let greetings = future::join_all(
+        greeters.iter().map(|greeter| greeter.send(Greet("world"))).collect::<Vec<_>>());
+    let greeter = greeters[1].clone();
+    Arbiter::handle().spawn(
+        greetings
+            .and_then(move |greetings| {
+                assert_eq!(greetings, vec!["Hello, world", "Bonjour, world"]);
+                greeter.recipient::<Greet>().do_send(Greet("again")).unwrap();
+                greeter.send(Count)
+            })
+            .then(|res| {
+                assert_eq!(res.unwrap(), 2);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[test]
+fn test_protocol_recipients() {
+    let sys = System::new("test");
+
+    let english: Addr<Syn, _> = English::default().start();
+    let greeter = ProtocolAddr::<Resettable>::new(&english);
+    greeter.do_send(Greet("first")).unwrap();
+    greeter.do_send(Greet("second")).unwrap();
+    greeter.try_send(Reset).unwrap();
+    greeter.do_send(Greet("third")).unwrap();
+
+    Arbiter::handle().spawn(
+        greeter.send(Count).then(|res| {
+            assert_eq!(res.unwrap(), 1);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::result(Ok(()))
+        }));
+
+    assert_eq!(sys.run(), 0);
+}
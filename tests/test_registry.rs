@@ -0,0 +1,188 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::time::Duration;
+use futures::Future;
+use futures::sync::oneshot;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+
+struct Incr;
+
+impl Message for Incr {
+    type Result = usize;
+}
+
+#[derive(Default)]
+struct Counter(usize);
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+}
+
+impl actix::Supervised for Counter {}
+
+impl SystemService for Counter {}
+
+impl Handler<Incr> for Counter {
+    type Result = MessageResult<Incr>;
+
+    fn handle(&mut self, _: Incr, _: &mut Context<Self>) -> Self::Result {
+        self.0 += 1;
+        MessageResult(self.0)
+    }
+}
+
+#[test]
+fn test_system_registry() {
+    let sys = System::new("test");
+
+    let arbiter = Arbiter::new("other");
+    Arbiter::handle().spawn(
+        System::registry().get::<Counter>().send(Incr)
+            .and_then(move |res| {
+                assert_eq!(res, 1);
+                // same service instance is visible from other arbiter
+                arbiter.send(actix::msgs::Execute::new(|| -> Result<_, ()> {
+                    Ok(System::registry().get::<Counter>().send(Incr))
+                }))
+            })
+            .map_err(|_| ())
+            .and_then(|res| res.unwrap().map_err(|_| ()))
+            .then(|res| {
+                assert_eq!(res.unwrap(), 2);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+#[derive(Default)]
+struct Cache(usize);
+
+impl Actor for Cache {
+    type Context = Context<Self>;
+}
+
+impl actix::Supervised for Cache {}
+
+impl ArbiterService for Cache {}
+
+impl Handler<Incr> for Cache {
+    type Result = MessageResult<Incr>;
+
+    fn handle(&mut self, _: Incr, _: &mut Context<Self>) -> Self::Result {
+        self.0 += 1;
+        MessageResult(self.0)
+    }
+}
+
+#[test]
+fn test_arbiter_registry() {
+    let sys = System::new("test");
+
+    let arbiter = Arbiter::new("other");
+    Arbiter::handle().spawn(
+        Arbiter::registry().get::<Cache>().send(Incr)
+            .and_then(|_| Cache::from_registry().send(Incr))
+            .and_then(move |res| {
+                // same instance within arbiter
+                assert_eq!(res, 2);
+                // other arbiter has its own instance
+                let (tx, rx) = oneshot::channel();
+                arbiter.do_send(actix::msgs::Execute::new(|| -> Result<_, ()> {
+                    Arbiter::handle().spawn(
+                        Cache::from_registry().send(Incr).then(move |res| {
+                            let _ = tx.send(res.unwrap());
+                            Ok(())
+                        }));
+                    Ok(())
+                }));
+                rx.map_err(|_| MailboxError::Closed)
+            })
+            .then(|res| {
+                assert_eq!(res.unwrap(), 1);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+struct Stop;
+
+impl Message for Stop {
+    type Result = ();
+}
+
+struct Named;
+
+impl Actor for Named {
+    type Context = Context<Self>;
+}
+
+impl Handler<Incr> for Named {
+    type Result = MessageResult<Incr>;
+
+    fn handle(&mut self, _: Incr, _: &mut Context<Self>) -> Self::Result {
+        MessageResult(10)
+    }
+}
+
+impl Handler<Stop> for Named {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+#[test]
+fn test_named_recipients() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let addr: Addr<Syn, _> = Named.start();
+        System::register_named("named", addr.clone().recipient::<Incr>());
+        System::register_named("named", addr.recipient::<Stop>());
+        assert!(System::lookup::<Incr>("other").is_none());
+
+        let arbiter = Arbiter::new("other");
+        arbiter.send(actix::msgs::Execute::new(|| -> Result<_, ()> {
+            // recipient is visible from other arbiter
+            Ok(System::lookup::<Incr>("named").unwrap().send(Incr).wait())
+        })).then(|res| {
+            assert_eq!(res.unwrap().unwrap().unwrap(), 10);
+            System::lookup::<Stop>("named").unwrap().send(Stop)
+        }).then(|_| {
+            Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+        }).then(|_| {
+            // stopped actor is removed
+            assert!(System::lookup::<Incr>("named").is_none());
+            assert!(System::lookup::<Stop>("named").is_none());
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        })
+    });
+
+    sys.run();
+}
+
+#[test]
+fn test_unregister_named() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let addr: Addr<Syn, _> = Named.start();
+        System::register_named("named", addr.recipient::<Incr>());
+        assert!(System::lookup::<Incr>("named").is_some());
+        System::unregister_named("named");
+        assert!(System::lookup::<Incr>("named").is_none());
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        Ok(())
+    });
+
+    sys.run();
+}
@@ -0,0 +1,345 @@
+#![cfg(feature="remote")]
+extern crate actix;
+extern crate futures;
+extern crate serde;
+extern crate serde_json;
+#[macro_use] extern crate serde_derive;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::{future, Future};
+use actix::prelude::*;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use actix::remote::{self, Binary, Node, RemoteError, RemoteMessage, RemoteNode, WireFormat};
+
+#[derive(Serialize, Deserialize)]
+struct Add(u64);
+
+impl Message for Add {
+    type Result = u64;
+}
+
+impl RemoteMessage for Add {
+    fn type_name() -> &'static str { "Add" }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Log(String);
+
+impl Message for Log {
+    type Result = ();
+}
+
+impl RemoteMessage for Log {
+    fn type_name() -> &'static str { "Log" }
+}
+
+/// Message that never gets response
+#[derive(Serialize, Deserialize)]
+struct Hang;
+
+impl Message for Hang {
+    type Result = Result<(), ()>;
+}
+
+impl RemoteMessage for Hang {
+    fn type_name() -> &'static str { "Hang" }
+}
+
+struct Counter(u64, Arc<Mutex<Vec<String>>>);
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+}
+
+impl Handler<Add> for Counter {
+    type Result = u64;
+
+    fn handle(&mut self, msg: Add, _: &mut Context<Self>) -> u64 {
+        self.0 += msg.0;
+        self.0
+    }
+}
+
+impl Handler<Log> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, msg: Log, _: &mut Context<Self>) {
+        self.1.lock().unwrap().push(msg.0);
+    }
+}
+
+impl Handler<Hang> for Counter {
+    type Result = ResponseFuture<(), ()>;
+
+    fn handle(&mut self, _: Hang, _: &mut Context<Self>) -> Self::Result {
+        Box::new(future::empty())
+    }
+}
+
+/// Start node with registered counter, connect to it
+fn connect<F, R>(log: Arc<Mutex<Vec<String>>>, f: F)
+    where F: FnOnce(RemoteNode) -> R + 'static,
+          R: Future<Item=(), Error=()> + 'static
+{
+    let node = Node::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let node_addr = node.local_addr();
+    let node: Addr<Syn, _> = node.start();
+    let counter: Addr<Syn, _> = Counter(0, log).start();
+    node.do_send(remote::Register::new("counter", counter.clone().recipient::<Add>()));
+    node.do_send(remote::Register::new("counter", counter.clone().recipient::<Log>()));
+    node.do_send(remote::Register::new("counter", counter.recipient::<Hang>()));
+
+    Arbiter::handle().spawn(
+        node.send(remote::Connect(node_addr))
+            .map_err(|_| ())
+            .and_then(move |res| f(res.unwrap())));
+}
+
+#[test]
+fn test_remote_send() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    connect(Arc::clone(&log), |node| {
+        let counter = node.recipient::<Add>("counter");
+        let logger = node.recipient::<Log>("counter");
+        logger.do_send(Log("first".to_owned())).unwrap();
+        counter.send(Add(2))
+            .and_then(move |res| {
+                assert_eq!(res, 2);
+                logger.do_send(Log("second".to_owned())).unwrap();
+                counter.send(Add(3))
+            })
+            .then(|res| {
+                assert_eq!(res.unwrap(), 5);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            })
+    });
+
+    sys.run();
+    assert_eq!(*log.lock().unwrap(), vec!["first".to_owned(), "second".to_owned()]);
+}
+
+#[test]
+fn test_remote_unknown_recipient() {
+    let sys = System::new("test");
+
+    connect(Arc::new(Mutex::new(Vec::new())), |node| {
+        node.recipient::<Add>("other").send(Add(1))
+            .then(|res| {
+                match res {
+                    Err(RemoteError::UnknownRecipient(name)) => assert_eq!(name, "other"),
+                    _ => panic!("Should not happen"),
+                }
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            })
+    });
+
+    sys.run();
+}
+
+#[test]
+fn test_remote_connect_error() {
+    let sys = System::new("test");
+
+    // nothing listens on this port
+    let node = Node::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let node: Addr<Syn, _> = node.start();
+    let free = Node::bind(&"127.0.0.1:0".parse().unwrap()).unwrap().local_addr();
+
+    Arbiter::handle().spawn(
+        node.send(remote::Connect(free)).then(|res| {
+            match res {
+                Ok(Err(RemoteError::Io(_))) => (),
+                _ => panic!("Should not happen"),
+            }
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+}
+
+#[test]
+fn test_remote_lookup() {
+    let sys = System::new("test");
+
+    connect(Arc::new(Mutex::new(Vec::new())), |node| {
+        let node2 = node.clone();
+        node.lookup::<Add>("counter")
+            .and_then(|counter| counter.send(Add(1)))
+            .then(move |res| {
+                assert_eq!(res.unwrap(), 1);
+                node2.lookup::<Add>("other")
+            })
+            .then(|res| {
+                match res {
+                    Err(RemoteError::UnknownRecipient(name)) => assert_eq!(name, "other"),
+                    _ => panic!("Should not happen"),
+                }
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            })
+    });
+
+    sys.run();
+}
+
+/// Json with reversed bytes
+struct Reversed;
+
+impl WireFormat for Reversed {
+    fn name() -> &'static str { "reversed" }
+
+    fn serialize<T: Serialize>(item: &T) -> Result<Vec<u8>, RemoteError> {
+        let mut body = serde_json::to_vec(item)
+            .map_err(|e| RemoteError::Serialization(format!("{}", e)))?;
+        body.reverse();
+        Ok(body)
+    }
+
+    fn deserialize<T: DeserializeOwned>(body: &[u8]) -> Result<T, RemoteError> {
+        let mut body = body.to_vec();
+        body.reverse();
+        serde_json::from_slice(&body).map_err(|e| RemoteError::Serialization(format!("{}", e)))
+    }
+}
+
+#[test]
+fn test_remote_wire_format() {
+    let sys = System::new("test");
+
+    let node = Node::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let node_addr = node.local_addr();
+    let node: Addr<Syn, _> = node.start();
+    let counter: Addr<Syn, _> = Counter(0, Arc::new(Mutex::new(Vec::new()))).start();
+    node.do_send(remote::Register::new("json", counter.clone().recipient::<Add>()));
+    node.do_send(remote::RegisterWith::<_, Reversed>::new("counter", counter.recipient::<Add>()));
+
+    Arbiter::handle().spawn(
+        node.send(remote::ConnectWith::<Reversed>::new(node_addr))
+            .map_err(|_| ())
+            .and_then(|res| {
+                let remote = res.unwrap();
+                let remote2 = remote.clone();
+                remote.lookup::<Add>("counter")
+                    .and_then(|counter| counter.send(Add(3)))
+                    .then(move |res| {
+                        assert_eq!(res.unwrap(), 3);
+                        // recipient is registered for json connections only
+                        remote2.recipient::<Add>("json").send(Add(1))
+                    })
+                    .then(|res| {
+                        match res {
+                            Err(RemoteError::UnknownRecipient(name)) => assert_eq!(name, "json"),
+                            _ => panic!("Should not happen"),
+                        }
+                        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                        Ok(())
+                    })
+            }));
+
+    sys.run();
+}
+
+fn is_send<T: Send>(_: &T) {}
+
+#[test]
+fn test_remote_binary_format() {
+    let sys = System::new("test");
+
+    let node = Node::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let node_addr = node.local_addr();
+    let node: Addr<Syn, _> = node.start();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let counter: Addr<Syn, _> = Counter(0, Arc::clone(&log)).start();
+    node.do_send(
+        remote::RegisterWith::<_, Binary>::new("counter", counter.clone().recipient::<Add>()));
+    node.do_send(
+        remote::RegisterWith::<_, Binary>::new("counter", counter.recipient::<Log>()));
+
+    Arbiter::handle().spawn(
+        node.send(remote::ConnectWith::<Binary>::new(node_addr))
+            .map_err(|_| ())
+            .and_then(|res| {
+                let remote = res.unwrap();
+                is_send(&remote);
+                remote.recipient::<Log>("counter").do_send(Log("binary".to_owned())).unwrap();
+                remote.lookup::<Add>("counter")
+                    .and_then(|counter| {
+                        is_send(&counter);
+                        counter.send(Add(300))
+                    })
+                    .then(|res| {
+                        assert_eq!(res.unwrap(), 300);
+                        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                        Ok(())
+                    })
+            }));
+
+    sys.run();
+    assert_eq!(*log.lock().unwrap(), vec!["binary".to_owned()]);
+}
+
+#[test]
+fn test_remote_too_large() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    connect(Arc::clone(&log), |node| {
+        // name does not fit into the frame
+        let name: String = ::std::iter::repeat('a').take(70_000).collect();
+        match node.recipient::<Log>(&name).do_send(Log("name".to_owned())) {
+            Err(RemoteError::TooLarge) => (),
+            _ => panic!("Should not happen"),
+        }
+
+        let logger = node.recipient::<Log>("counter");
+        let body: String = ::std::iter::repeat('a').take(17 * 1024 * 1024).collect();
+        logger.send(Log(body))
+            .then(move |res| {
+                match res {
+                    Err(RemoteError::TooLarge) => (),
+                    _ => panic!("Should not happen"),
+                }
+                // connection is still usable
+                logger.send(Log("small".to_owned()))
+            })
+            .then(|res| {
+                res.unwrap();
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            })
+    });
+
+    sys.run();
+    assert_eq!(*log.lock().unwrap(), vec!["small".to_owned()]);
+}
+
+#[test]
+fn test_remote_timeout() {
+    let sys = System::new("test");
+
+    connect(Arc::new(Mutex::new(Vec::new())), |node| {
+        let counter = node.recipient::<Add>("counter").timeout(Duration::from_millis(50));
+        node.recipient::<Hang>("counter").timeout(Duration::from_millis(50)).send(Hang)
+            .then(move |res| {
+                match res {
+                    Err(RemoteError::Timeout) => (),
+                    _ => panic!("Should not happen"),
+                }
+                counter.send(Add(1))
+            })
+            .then(|res| {
+                assert_eq!(res.unwrap(), 1);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            })
+    });
+
+    sys.run();
+}
@@ -0,0 +1,52 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::net;
+use std::time::Duration;
+use futures::{future, Future};
+use tokio_core::net::TcpListener;
+use actix::prelude::*;
+use actix::actors::{Connect, Connector, Resolve};
+
+#[test]
+fn test_resolve_addr() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let connector: Addr<Unsync, _> = Connector::from_registry();
+        connector.send(Resolve::host("127.0.0.1:8080")).then(|res| {
+            let addrs = res.unwrap().unwrap();
+            assert_eq!(addrs.into_iter().collect::<Vec<_>>(),
+                       vec![net::SocketAddr::from(([127, 0, 0, 1], 8080))]);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::ok(())
+        })
+    });
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[test]
+fn test_connect() {
+    let sys = System::new("test");
+
+    let addr = net::SocketAddr::from(([127, 0, 0, 1], 0));
+    let listener = TcpListener::bind(&addr, Arbiter::handle()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    Arbiter::handle().spawn_fn(move || {
+        let connector: Addr<Unsync, _> = Connector::from_registry();
+        connector.send(Connect::host(format!("127.0.0.1:{}", addr.port()))
+                       .timeout(Duration::from_secs(5)))
+            .then(move |res| {
+                let stream = res.unwrap().unwrap();
+                assert_eq!(stream.peer_addr().unwrap(), addr);
+                drop(listener);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::ok(())
+            })
+    });
+
+    assert_eq!(sys.run(), 0);
+}
@@ -0,0 +1,217 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::time::Duration;
+use futures::{future, Future, Stream};
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+
+struct Delayed(u64);
+
+impl Message for Delayed {
+    type Result = Result<u64, ()>;
+}
+
+struct Visit;
+
+impl Message for Visit {
+    type Result = Result<usize, ()>;
+}
+
+struct Counter(usize);
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+}
+
+impl Handler<Delayed> for Counter {
+    type Result = ResponseFuture<u64, ()>;
+
+    fn handle(&mut self, msg: Delayed, _: &mut Context<Self>) -> Self::Result {
+        Box::new(Timeout::new(Duration::from_millis(msg.0), Arbiter::handle()).unwrap()
+                 .map_err(|_| ())
+                 .map(move |_| msg.0))
+    }
+}
+
+impl Handler<Visit> for Counter {
+    type Result = ResponseActFuture<Self, usize, ()>;
+
+    fn handle(&mut self, _: Visit, _: &mut Context<Self>) -> Self::Result {
+        let fut = Timeout::new(Duration::from_millis(1), Arbiter::handle()).unwrap();
+        Box::new(fut.map_err(|_| ()).into_actor(self).map(|_, act: &mut Counter, _| {
+            act.0 += 1;
+            act.0
+        }))
+    }
+}
+
+#[test]
+fn test_response_future() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let addr: Addr<Syn, _> = Counter(0).start();
+
+        addr.send(Delayed(10)).join(addr.send(Delayed(1))).then(|res| {
+            let (res1, res2) = res.unwrap();
+            assert_eq!(res1, Ok(10));
+            assert_eq!(res2, Ok(1));
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::result(Ok(()))
+        })
+    });
+
+    sys.run();
+}
+
+#[test]
+fn test_response_act_future() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let addr: Addr<Unsync, _> = Counter(0).start();
+
+        let visits: Vec<_> = (0..3).map(|_| addr.send(Visit)).collect();
+        future::join_all(visits).then(|res| {
+            let mut res: Vec<_> = res.unwrap().into_iter().map(|res| res.unwrap()).collect();
+            res.sort();
+            assert_eq!(res, vec![1, 2, 3]);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::result(Ok(()))
+        })
+    });
+
+    sys.run();
+}
+
+struct Stats;
+
+impl Message for Stats {
+    type Result = (usize, String);
+}
+
+struct Find(usize);
+
+impl Message for Find {
+    type Result = Option<usize>;
+}
+
+struct List;
+
+impl Message for List {
+    type Result = Vec<usize>;
+}
+
+struct Storage(Vec<usize>);
+
+impl Actor for Storage {
+    type Context = Context<Self>;
+}
+
+impl Handler<Stats> for Storage {
+    type Result = (usize, String);
+
+    fn handle(&mut self, _: Stats, _: &mut Context<Self>) -> Self::Result {
+        (self.0.len(), "storage".to_owned())
+    }
+}
+
+impl Handler<Find> for Storage {
+    type Result = Option<usize>;
+
+    fn handle(&mut self, msg: Find, _: &mut Context<Self>) -> Self::Result {
+        self.0.iter().position(|item| *item == msg.0)
+    }
+}
+
+impl Handler<List> for Storage {
+    type Result = Vec<usize>;
+
+    fn handle(&mut self, _: List, _: &mut Context<Self>) -> Self::Result {
+        self.0.clone()
+    }
+}
+
+#[test]
+fn test_container_responses() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let addr: Addr<Unsync, _> = Storage(vec![5, 7]).start();
+
+        addr.send(Stats)
+            .join4(addr.send(Find(7)), addr.send(Find(1)), addr.send(List))
+            .then(|res| {
+                let (stats, found, missing, list) = res.unwrap();
+                assert_eq!(stats, (2, "storage".to_owned()));
+                assert_eq!(found, Some(1));
+                assert_eq!(missing, None);
+                assert_eq!(list, vec![5, 7]);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    });
+
+    sys.run();
+}
+
+struct Query(Vec<Result<u32, ()>>);
+
+impl Message for Query {
+    type Result = MessageStream<u32, ()>;
+}
+
+struct Db;
+
+impl Actor for Db {
+    type Context = Context<Self>;
+}
+
+impl Handler<Query> for Db {
+    type Result = ResponseStream<u32, ()>;
+
+    fn handle(&mut self, msg: Query, _: &mut Context<Self>) -> Self::Result {
+        ResponseStream::new(futures::stream::iter_result(msg.0))
+    }
+}
+
+#[test]
+fn test_response_stream() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let addr: Addr<Syn, _> = Db.start();
+        let items = (0..100).map(Ok).collect();
+
+        addr.send(Query(items)).and_then(|items| {
+            items.collect().then(|res| {
+                assert_eq!(res.unwrap(), (0..100).collect::<Vec<_>>());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+        }).map_err(|_| ())
+    });
+
+    sys.run();
+}
+
+#[test]
+fn test_response_stream_error() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let addr: Addr<Unsync, _> = Db.start();
+
+        addr.send(Query(vec![Ok(1), Err(()), Ok(2)])).and_then(|items| {
+            items.then(Ok::<_, ()>).collect().then(|res| {
+                assert_eq!(res.unwrap(), vec![Ok(1), Err(()), Ok(2)]);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+        }).map_err(|_| ())
+    });
+
+    sys.run();
+}
@@ -0,0 +1,353 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use futures::{future, Future};
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::router::{self, AddRoutee, Broadcast, ConsistentHash, HashKey, RemoveRoutee,
+                    Routable, Router, SmallestMailbox};
+
+struct Job;
+
+impl Message for Job {
+    type Result = usize;
+}
+
+impl Routable for Job {}
+
+struct Crash;
+
+impl Message for Crash {
+    type Result = ();
+}
+
+impl Routable for Crash {}
+
+struct Stop;
+
+impl Message for Stop {
+    type Result = ();
+}
+
+struct Keyed(u64);
+
+impl Message for Keyed {
+    type Result = usize;
+}
+
+impl Routable for Keyed {}
+
+impl HashKey for Keyed {
+    fn hash_key<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+struct Worker(usize);
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+impl actix::Supervised for Worker {}
+
+impl Handler<Job> for Worker {
+    type Result = MessageResult<Job>;
+
+    fn handle(&mut self, _: Job, _: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.0)
+    }
+}
+
+impl Handler<Keyed> for Worker {
+    type Result = MessageResult<Keyed>;
+
+    fn handle(&mut self, _: Keyed, _: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.0)
+    }
+}
+
+impl Handler<Stop> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+impl Handler<Crash> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Crash, _: &mut Context<Self>) {
+        panic!("Worker crashed");
+    }
+}
+
+fn workers(size: usize) -> Vec<Addr<Syn, Worker>> {
+    let ids = Arc::new(AtomicUsize::new(0));
+    router::pool(size, move |_| Worker(ids.fetch_add(1, Ordering::Relaxed)))
+}
+
+#[test]
+fn test_round_robin() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let addr: Addr<Unsync, _> = Router::new(workers(3)).start();
+        let jobs: Vec<_> = (0..6).map(|_| addr.send(Job)).collect();
+
+        future::join_all(jobs).then(|res| {
+            assert_eq!(res.unwrap(), vec![0, 1, 2, 0, 1, 2]);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        })
+    });
+
+    sys.run();
+}
+
+#[test]
+fn test_round_robin_restart() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let addr: Addr<Unsync, _> = Router::new(workers(2)).start();
+        let addr2 = addr.clone();
+
+        addr.send(Crash).then(move |res| {
+            assert!(res.is_err());
+            let jobs: Vec<_> = (0..4).map(|_| addr2.send(Job)).collect();
+            future::join_all(jobs)
+        }).then(|res| {
+            // crashed routee got restarted
+            assert_eq!(res.unwrap(), vec![1, 0, 1, 0]);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        })
+    });
+
+    sys.run();
+}
+
+#[test]
+fn test_dynamic_routees() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let pool = workers(3);
+        let addr: Addr<Unsync, _> = Router::new(vec![pool[0].clone()]).start();
+        addr.do_send(AddRoutee(pool[1].clone()));
+        addr.do_send(AddRoutee(pool[2].clone()));
+        let addr2 = addr.clone();
+
+        let jobs: Vec<_> = (0..3).map(|_| addr.send(Job)).collect();
+        future::join_all(jobs).then(move |res| {
+            assert_eq!(res.unwrap(), vec![0, 1, 2]);
+
+            addr2.do_send(RemoveRoutee(pool[1].clone()));
+            let jobs: Vec<_> = (0..4).map(|_| addr2.send(Job)).collect();
+            future::join_all(jobs)
+        }).then(|res| {
+            assert_eq!(res.unwrap(), vec![2, 0, 2, 0]);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        })
+    });
+
+    sys.run();
+}
+
+#[test]
+fn test_no_routees() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let addr: Addr<Unsync, _> = Router::new(workers(0)).start();
+
+        addr.send(Job).then(|res| {
+            match res {
+                Err(MailboxError::Closed) => (),
+                _ => panic!("Should not happen"),
+            }
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        })
+    });
+
+    sys.run();
+}
+
+#[test]
+fn test_consistent_hash() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let addr: Addr<Unsync, _> = Router::with_strategy(
+            workers(3), ConsistentHash::default()).start();
+        let addr2: Addr<Unsync, _> = Router::with_strategy(
+            workers(4), ConsistentHash::default()).start();
+
+        let jobs: Vec<_> = (0..100).chain(0..100).map(|key| addr.send(Keyed(key))).collect();
+        let jobs2: Vec<_> = (0..100).map(|key| addr2.send(Keyed(key))).collect();
+
+        future::join_all(jobs).join(future::join_all(jobs2)).then(|res| {
+            let (res, res2) = res.unwrap();
+
+            // same key, same routee
+            assert_eq!(&res[..100], &res[100..]);
+            for idx in 0..3 {
+                assert!(res.contains(&idx));
+            }
+
+            // new routee takes over part of the keys, other keys stay in place
+            for (idx, idx2) in res.iter().zip(res2.iter()) {
+                assert!(idx == idx2 || *idx2 == 3);
+            }
+            assert!(res2.contains(&3));
+
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        })
+    });
+
+    sys.run();
+}
+
+#[test]
+fn test_consistent_hash_stopped_routee() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let routees: Vec<Addr<Syn, _>> = (0..3).map(|idx| Worker(idx).start()).collect();
+        routees[1].do_send(Stop);
+        let addr: Addr<Unsync, _> = Router::with_strategy(
+            routees, ConsistentHash::default()).start();
+
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                let jobs: Vec<_> = (0..100).map(|key| addr.send(Keyed(key))).collect();
+                future::join_all(jobs)
+            })
+            .then(|res| {
+                // keys of stopped routee go to other routees
+                let res = res.unwrap();
+                assert!(!res.contains(&1));
+                assert!(res.contains(&0) && res.contains(&2));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            })
+    });
+
+    sys.run();
+}
+
+#[derive(Clone)]
+struct Invalidate;
+
+impl Message for Invalidate {
+    type Result = ();
+}
+
+impl Routable for Invalidate {}
+
+struct Cache(usize, Arc<Mutex<Vec<usize>>>);
+
+impl Actor for Cache {
+    type Context = Context<Self>;
+}
+
+impl Handler<Invalidate> for Cache {
+    type Result = ();
+
+    fn handle(&mut self, _: Invalidate, _: &mut Context<Self>) {
+        self.1.lock().unwrap().push(self.0);
+    }
+}
+
+impl Handler<Job> for Cache {
+    type Result = MessageResult<Job>;
+
+    fn handle(&mut self, _: Job, _: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.0)
+    }
+}
+
+#[test]
+fn test_broadcast() {
+    let sys = System::new("test");
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen2 = Arc::clone(&seen);
+
+    Arbiter::handle().spawn_fn(move || {
+        let caches: Vec<Addr<Syn, _>> =
+            (0..3).map(|idx| Cache(idx, Arc::clone(&seen2)).start()).collect();
+        let caches2 = caches.clone();
+        let addr: Addr<Unsync, _> = Router::with_strategy(caches, Broadcast).start();
+
+        addr.send(Invalidate).then(move |res| {
+            assert!(res.is_ok());
+            // wait until all routees process message
+            future::join_all(caches2.iter().map(|cache| cache.send(Job)).collect::<Vec<_>>())
+        }).then(|_| {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        })
+    });
+
+    sys.run();
+    let mut seen = seen.lock().unwrap();
+    seen.sort();
+    assert_eq!(*seen, vec![0, 1, 2]);
+}
+
+struct Wedged;
+
+impl Actor for Wedged {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        future::empty::<(), ()>().into_actor(self).wait(ctx);
+    }
+}
+
+impl Handler<Job> for Wedged {
+    type Result = MessageResult<Job>;
+
+    fn handle(&mut self, _: Job, _: &mut Context<Self>) -> Self::Result {
+        MessageResult(0)
+    }
+}
+
+#[test]
+fn test_smallest_mailbox() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn_fn(|| {
+        let routees: Vec<Addr<Syn, _>> = (0..3).map(|_| Wedged.start()).collect();
+        routees[0].do_send(Job);
+        routees[0].do_send(Job);
+        routees[1].do_send(Job);
+
+        let addr: Addr<Unsync, _> =
+            Router::with_strategy(routees.clone(), SmallestMailbox).start();
+        // first routee wins tie
+        for _ in 0..2 {
+            addr.do_send(Job);
+        }
+
+        Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                let pending: Vec<_> = routees.iter().map(|r| r.pending()).collect();
+                assert_eq!(pending, vec![2, 2, 1]);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    });
+
+    sys.run();
+}
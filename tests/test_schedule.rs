@@ -0,0 +1,125 @@
+extern crate actix;
+extern crate futures;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::clock::TestClock;
+use actix::schedule::{Cron, CronError, Schedule};
+
+/// Thursday, 2018-03-01 00:00:00 UTC
+const MARCH_1: u64 = 1_519_862_400;
+
+const DAY: u64 = 86_400;
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn next(expr: &str, secs: u64) -> Option<SystemTime> {
+    Cron::parse(expr).unwrap().next_after(at(secs))
+}
+
+#[test]
+fn test_cron_next() {
+    assert_eq!(next("0 */5 * * * *", MARCH_1 + 1), Some(at(MARCH_1 + 300)));
+    assert_eq!(next("0 */5 * * * *", MARCH_1 + 300), Some(at(MARCH_1 + 600)));
+    assert_eq!(next("10-20/5,45 * * * * *", MARCH_1 + 16), Some(at(MARCH_1 + 20)));
+    assert_eq!(next("10-20/5,45 * * * * *", MARCH_1 + 21), Some(at(MARCH_1 + 45)));
+
+    // workdays at 9:00, from friday 10:00 to monday
+    assert_eq!(next("0 0 9 * * 1-5", MARCH_1 + DAY + 10 * 3600),
+               Some(at(MARCH_1 + 4 * DAY + 9 * 3600)));
+    // restricted day of month or day of week, first sunday comes first
+    assert_eq!(next("0 0 0 15 * 7", MARCH_1), Some(at(MARCH_1 + 3 * DAY)));
+    // leap day
+    assert_eq!(next("0 0 0 29 2 *", MARCH_1), Some(at(1_582_934_400)));
+    assert_eq!(next("0 0 0 30 2 *", MARCH_1), None);
+}
+
+#[test]
+fn test_cron_parse_error() {
+    match Cron::parse("* * *") {
+        Err(CronError::FieldCount(3)) => (),
+        res => panic!("Unexpected result: {:?}", res),
+    }
+    for expr in &["60 * * * * *", "* * * 0 * *", "*/0 * * * * *", "5-1 * * * * *", "a * * * * *",
+                  "*/60 * * * * *", "* * * 1/18446744073709551615 * *"] {
+        match expr.parse::<Cron>() {
+            Err(CronError::InvalidField(_)) => (),
+            res => panic!("Unexpected result for {:?}: {:?}", expr, res),
+        }
+    }
+}
+
+#[test]
+fn test_schedule_next() {
+    let interval = Schedule::Interval(Duration::from_secs(10));
+    assert_eq!(interval.next(at(MARCH_1), at(MARCH_1 + 3)), Some(at(MARCH_1 + 10)));
+    // missed occurrences are skipped
+    assert_eq!(interval.next(at(MARCH_1), at(MARCH_1 + 25)), Some(at(MARCH_1 + 30)));
+
+    let daily = Schedule::daily(6, 30);
+    assert_eq!(daily.next(at(MARCH_1), at(MARCH_1 + 7 * 3600)),
+               Some(at(MARCH_1 + DAY + 6 * 3600 + 1800)));
+    assert_eq!(Schedule::weekly(0, 0, 0).next(at(MARCH_1), at(MARCH_1)),
+               Some(at(MARCH_1 + 3 * DAY)));
+}
+
+struct Stats;
+
+impl Message for Stats {
+    type Result = (usize, usize);
+}
+
+#[derive(Default)]
+struct Jobs {
+    cron: usize,
+    interval: usize,
+}
+
+impl Actor for Jobs {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.run_cron("*/10 * * * * *", |act, _| act.cron += 1);
+        ctx.run_schedule(Duration::from_secs(7).into(), |act, _| act.interval += 1);
+    }
+}
+
+impl Handler<Stats> for Jobs {
+    type Result = MessageResult<Stats>;
+
+    fn handle(&mut self, _: Stats, _: &mut Context<Self>) -> Self::Result {
+        MessageResult((self.cron, self.interval))
+    }
+}
+
+#[test]
+fn test_run_schedule() {
+    let sys = System::new("test");
+    // start at whole minute, cron fires 6 times a minute regardless of when
+    // actor starts
+    let clock = TestClock::install_at(at(MARCH_1));
+
+    let addr: Addr<Unsync, _> = Jobs::default().start();
+    Arbiter::handle().spawn(
+        future::loop_fn(0, move |step| {
+            clock.advance(Duration::from_secs(1));
+            let addr2 = addr.clone();
+            addr.send(Stats)
+                .and_then(move |_| addr2.send(Stats))
+                .map(move |res| if step == 59 {
+                    future::Loop::Break(res)
+                } else {
+                    future::Loop::Continue(step + 1)
+                })
+        }).then(|res| {
+            assert_eq!(res.unwrap(), (6, 8));
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::result(Ok(()))
+        }));
+
+    assert_eq!(sys.run(), 0);
+    TestClock::uninstall();
+}
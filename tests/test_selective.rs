@@ -0,0 +1,215 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::time::Duration;
+use futures::{future, Future};
+use futures::sync::oneshot;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+
+struct Ask(Addr<Unsync, Client>);
+
+impl Message for Ask {
+    type Result = ();
+}
+
+struct Reply;
+
+impl Message for Reply {
+    type Result = ();
+}
+
+struct Start;
+
+impl Message for Start {
+    type Result = ();
+}
+
+struct Note;
+
+impl Message for Note {
+    type Result = ();
+}
+
+struct Log;
+
+impl Message for Log {
+    type Result = Vec<&'static str>;
+}
+
+struct Server;
+
+impl Actor for Server {
+    type Context = Context<Self>;
+}
+
+impl Handler<Ask> for Server {
+    type Result = ();
+
+    fn handle(&mut self, msg: Ask, _: &mut Context<Self>) {
+        msg.0.do_send(Reply);
+    }
+}
+
+struct Client {
+    server: Addr<Unsync, Server>,
+    /// wait on oneshot future instead of `wait_for()`
+    oneshot: bool,
+    reply: Option<oneshot::Sender<()>>,
+    log: Vec<&'static str>,
+}
+
+impl Client {
+    fn new(server: Addr<Unsync, Server>, oneshot: bool) -> Client {
+        Client{server, oneshot, reply: None, log: Vec::new()}
+    }
+}
+
+impl Actor for Client {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        if self.oneshot {
+            ctx.receive_while_waiting::<Reply>();
+        }
+    }
+}
+
+impl Handler<Start> for Client {
+    type Result = ();
+
+    fn handle(&mut self, _: Start, ctx: &mut Context<Self>) {
+        self.server.do_send(Ask(ctx.address()));
+        if self.oneshot {
+            let (tx, rx) = oneshot::channel();
+            self.reply = Some(tx);
+            actix::fut::wrap_future::<_, Self>(rx)
+                .map(|_, act, _| act.log.push("done"))
+                .map_err(|_, _, _| ())
+                .wait(ctx);
+        } else {
+            ctx.wait_for::<Reply>();
+        }
+    }
+}
+
+impl Handler<Reply> for Client {
+    type Result = ();
+
+    fn handle(&mut self, _: Reply, _: &mut Context<Self>) {
+        self.log.push("reply");
+        if let Some(tx) = self.reply.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+impl Handler<Note> for Client {
+    type Result = ();
+
+    fn handle(&mut self, _: Note, _: &mut Context<Self>) {
+        self.log.push("note");
+    }
+}
+
+impl Handler<Log> for Client {
+    type Result = MessageResult<Log>;
+
+    fn handle(&mut self, _: Log, _: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.log.clone())
+    }
+}
+
+fn check(oneshot: bool, expected: Vec<&'static str>) {
+    let sys = System::new("test");
+
+    let server: Addr<Unsync, _> = Server.start();
+    let client: Addr<Unsync, _> = Client::new(server, oneshot).start();
+    client.do_send(Start);
+    client.do_send(Note);
+
+    Arbiter::handle().spawn(
+        client.send(Log).then(move |res| {
+            assert_eq!(res.unwrap(), expected);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::result(Ok(()))
+        }));
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[test]
+fn test_wait_for() {
+    // note is handled once awaited reply arrives
+    check(false, vec!["reply", "note"]);
+}
+
+#[test]
+fn test_receive_while_waiting() {
+    // reply is handled while context waits for future that depends on it
+    check(true, vec!["reply", "done", "note"]);
+}
+
+/// Actor that waits for reply that never arrives
+struct Waiter;
+
+impl Actor for Waiter {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_mailbox_capacity(2);
+        ctx.wait_for::<Reply>();
+    }
+}
+
+impl Handler<Reply> for Waiter {
+    type Result = ();
+
+    fn handle(&mut self, _: Reply, _: &mut Context<Self>) {}
+}
+
+impl Handler<Note> for Waiter {
+    type Result = ();
+
+    fn handle(&mut self, _: Note, _: &mut Context<Self>) {}
+}
+
+fn sleep() -> Box<Future<Item=(), Error=()>> {
+    Box::new(Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+             .map_err(|_| ()))
+}
+
+#[test]
+fn test_wait_for_bounded() {
+    let sys = System::new("test");
+
+    let addr: Addr<Unsync, _> = Waiter.start();
+    let addr2 = addr.clone();
+    let addr3 = addr.clone();
+
+    // waiting context buffers at most capacity messages, other messages
+    // stay in the mailbox and block senders
+    Arbiter::handle().spawn(
+        sleep()
+            .and_then(move |_| {
+                assert!(addr.try_send(Note).is_ok());
+                assert!(addr.try_send(Note).is_ok());
+                sleep()
+            })
+            .and_then(move |_| {
+                assert!(addr2.try_send(Note).is_ok());
+                assert!(addr2.try_send(Note).is_ok());
+                sleep()
+            })
+            .and_then(move |_| {
+                match addr3.try_send(Note) {
+                    Err(SendError::Full(Note)) => (),
+                    _ => panic!("Mailbox should be full"),
+                }
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
@@ -0,0 +1,294 @@
+extern crate actix;
+extern crate bytes;
+extern crate tokio_core;
+extern crate tokio_io;
+#[cfg(unix)]
+extern crate tokio_uds;
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use bytes::BytesMut;
+use tokio_core::net::TcpStream;
+use tokio_io::codec::{Decoder, Encoder};
+use actix::prelude::*;
+use actix::actors::server::{TcpServer, StopServerGraceful};
+use actix::io::{FramedContext, WriteHandler};
+use actix::msgs::Terminated;
+
+#[derive(Clone)]
+struct LineCodec;
+
+impl Decoder for LineCodec {
+    type Item = String;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<String>, io::Error> {
+        match src.iter().position(|b| *b == b'\n') {
+            Some(pos) => {
+                let line = src.split_to(pos + 1);
+                Ok(Some(String::from_utf8_lossy(&line[..pos]).into_owned()))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl Encoder for LineCodec {
+    type Item = String;
+    type Error = io::Error;
+
+    fn encode(&mut self, item: String, dst: &mut BytesMut) -> Result<(), io::Error> {
+        dst.extend_from_slice(item.as_bytes());
+        dst.extend_from_slice(b"\n");
+        Ok(())
+    }
+}
+
+/// Echoes lines back to the client
+struct Echo;
+
+impl Actor for Echo {
+    type Context = FramedContext<Self, TcpStream, LineCodec>;
+}
+
+impl WriteHandler<io::Error> for Echo {}
+
+impl StreamHandler<String, io::Error> for Echo {
+    fn handle(&mut self, line: String, ctx: &mut Self::Context) {
+        ctx.send(line);
+    }
+}
+
+#[cfg(unix)]
+struct UnixEcho;
+
+#[cfg(unix)]
+impl Actor for UnixEcho {
+    type Context = FramedContext<Self, tokio_uds::UnixStream, LineCodec>;
+}
+
+#[cfg(unix)]
+impl WriteHandler<io::Error> for UnixEcho {}
+
+#[cfg(unix)]
+impl StreamHandler<String, io::Error> for UnixEcho {
+    fn handle(&mut self, line: String, ctx: &mut Self::Context) {
+        ctx.send(line);
+    }
+}
+
+fn request<S: Read + Write>(stream: &mut S, line: &str) -> io::Result<String> {
+    stream.write_all(format!("{}\n", line).as_bytes())?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(reply)
+}
+
+#[test]
+fn test_tcp_server() {
+    let sys = System::new("test");
+
+    let addr = net::SocketAddr::from(([127, 0, 0, 1], 0));
+    let server = TcpServer::bind(&addr, |stream, _| FramedContext::start(
+        Echo, stream, LineCodec)).unwrap()
+        .arbiter(Arbiter::new("connections"))
+        .max_connections(1);
+    let addr = server.local_addr().unwrap();
+    let server: Addr<Syn, _> = server.start();
+
+    let client = thread::spawn(move || {
+        let mut first = net::TcpStream::connect(&addr).unwrap();
+        assert_eq!(request(&mut first, "one").unwrap(), "one\n");
+
+        // second connection waits in backlog while first one is running
+        let mut second = net::TcpStream::connect(&addr).unwrap();
+        second.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+        assert!(request(&mut second, "two").is_err());
+
+        drop(first);
+        second.set_read_timeout(None).unwrap();
+        let mut reply = String::new();
+        BufReader::new(&mut second).read_line(&mut reply).unwrap();
+        assert_eq!(reply, "two\n");
+
+        // server stops once running connection is closed
+        server.do_send(StopServerGraceful(Duration::from_secs(5)));
+        thread::sleep(Duration::from_millis(50));
+        assert!(net::TcpStream::connect(&addr).is_err());
+        assert!(server.connected());
+
+        assert_eq!(request(&mut second, "three").unwrap(), "three\n");
+        drop(second);
+        while server.connected() {
+            thread::sleep(Duration::from_millis(10));
+        }
+    });
+
+    let system = Arbiter::system();
+    thread::spawn(move || {
+        let code = if client.join().is_ok() { 0 } else { 1 };
+        system.do_send(actix::msgs::SystemExit(code));
+    });
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unix_server() {
+    use std::fs;
+    use std::os::unix::net::UnixStream;
+    use actix::actors::server::{StopServer, UnixServer};
+
+    let sys = System::new("test");
+
+    let path = std::env::temp_dir().join("actix-test-unix-server.sock");
+    let _ = fs::remove_file(&path);
+    let server = UnixServer::bind(&path, |stream, _| FramedContext::start(
+        UnixEcho, stream, LineCodec)).unwrap();
+    let server: Addr<Syn, _> = server.start();
+
+    let client = thread::spawn(move || {
+        let mut first = UnixStream::connect(&path).unwrap();
+        let mut second = UnixStream::connect(&path).unwrap();
+        assert_eq!(request(&mut first, "one").unwrap(), "one\n");
+        assert_eq!(request(&mut second, "two").unwrap(), "two\n");
+
+        server.do_send(StopServer);
+        while server.connected() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        // running connections are not affected
+        assert_eq!(request(&mut first, "three").unwrap(), "three\n");
+        let _ = fs::remove_file(&path);
+    });
+
+    let system = Arbiter::system();
+    thread::spawn(move || {
+        let code = if client.join().is_ok() { 0 } else { 1 };
+        system.do_send(actix::msgs::SystemExit(code));
+    });
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[test]
+fn test_server_stopped_arbiter() {
+    let sys = System::new("test");
+
+    let arbiter = Arbiter::new("connections");
+    arbiter.do_send(actix::msgs::StopArbiter(0));
+    thread::sleep(Duration::from_millis(50));
+
+    let addr = net::SocketAddr::from(([127, 0, 0, 1], 0));
+    let server = TcpServer::bind(&addr, |stream, _| FramedContext::start(
+        Echo, stream, LineCodec)).unwrap()
+        .arbiter(arbiter)
+        .max_connections(1);
+    let addr = server.local_addr().unwrap();
+    let _: Addr<Syn, _> = server.start();
+
+    let client = thread::spawn(move || {
+        // connection that could not be started does not hold its slot
+        for _ in 0..2 {
+            let mut stream = net::TcpStream::connect(&addr).unwrap();
+            stream.set_read_timeout(Some(Duration::from_millis(500))).unwrap();
+            let mut buf = [0; 1];
+            assert_eq!(stream.read(&mut buf).unwrap(), 0);
+        }
+    });
+
+    let system = Arbiter::system();
+    thread::spawn(move || {
+        let code = if client.join().is_ok() { 0 } else { 1 };
+        system.do_send(actix::msgs::SystemExit(code));
+    });
+
+    assert_eq!(sys.run(), 0);
+}
+
+/// Connection that delays its stop, once
+struct Slow {
+    _stream: TcpStream,
+    log: Arc<Mutex<Vec<&'static str>>>,
+    delayed: bool,
+}
+
+impl Actor for Slow {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        self.log.lock().unwrap().push("connected");
+        // keep running until arbiter stops
+        ctx.run_later(Duration::from_secs(10), |_, _| ());
+    }
+
+    fn stopping(&mut self, ctx: &mut Context<Self>) -> Running {
+        if self.delayed {
+            return Running::Stop
+        }
+        self.delayed = true;
+        ctx.run_later(Duration::from_millis(100), |_, ctx| ctx.stop());
+        Running::Continue
+    }
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        self.log.lock().unwrap().push("connection stopped");
+    }
+}
+
+/// Records termination of the server
+struct Observer(Arc<Mutex<Vec<&'static str>>>);
+
+impl Actor for Observer {
+    type Context = Context<Self>;
+
+    fn stopping(&mut self, _: &mut Context<Self>) -> Running {
+        Running::Continue
+    }
+}
+
+impl Handler<Terminated<TcpServer<Slow>>> for Observer {
+    type Result = ();
+
+    fn handle(&mut self, _: Terminated<TcpServer<Slow>>, _: &mut Context<Self>) {
+        self.0.lock().unwrap().push("server stopped");
+    }
+}
+
+#[test]
+fn test_server_graceful_system_stop() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let addr = net::SocketAddr::from(([127, 0, 0, 1], 0));
+    let log2 = Arc::clone(&log);
+    let server = TcpServer::bind(&addr, move |stream, _| {
+        Slow{_stream: stream, log: Arc::clone(&log2), delayed: false}.start()
+    }).unwrap();
+    let addr = server.local_addr().unwrap();
+    let server: Addr<Syn, _> = server.start();
+    let log2 = Arc::clone(&log);
+    let _: Addr<Unsync, _> = Observer::create(move |ctx| {
+        ctx.watch(&server);
+        Observer(log2)
+    });
+
+    let log2 = Arc::clone(&log);
+    let system = Arbiter::system();
+    thread::spawn(move || {
+        let _stream = net::TcpStream::connect(&addr).unwrap();
+        while log2.lock().unwrap().is_empty() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        system.do_send(actix::msgs::SystemExitGraceful(0, Duration::from_millis(500)));
+        thread::sleep(Duration::from_millis(1000));
+    });
+
+    // server keeps running until its connection is stopped
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*log.lock().unwrap(), vec!["connected", "connection stopped", "server stopped"]);
+}
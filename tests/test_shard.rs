@@ -0,0 +1,156 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::{future, Future};
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::msgs::Passivate;
+use actix::shard::{EntityMessage, Shard};
+
+struct Visit(&'static str);
+
+impl Message for Visit {
+    type Result = usize;
+}
+
+impl EntityMessage for Visit {
+    type Id = &'static str;
+
+    fn entity_id(&self) -> &'static str {
+        self.0
+    }
+}
+
+struct Session {
+    visits: usize,
+    log: Arc<Mutex<Vec<String>>>,
+    /// Passivation is delayed, entity keeps running meanwhile
+    delay: Option<Duration>,
+}
+
+impl Actor for Session {
+    type Context = Context<Self>;
+}
+
+impl actix::Supervised for Session {}
+
+impl Handler<Visit> for Session {
+    type Result = usize;
+
+    fn handle(&mut self, _: Visit, _: &mut Context<Self>) -> usize {
+        self.visits += 1;
+        self.visits
+    }
+}
+
+impl Handler<Passivate> for Session {
+    type Result = ();
+
+    fn handle(&mut self, _: Passivate, ctx: &mut Context<Self>) {
+        self.log.lock().unwrap().push(format!("passivated {}", self.visits));
+        match self.delay {
+            Some(delay) => { ctx.run_later(delay, |_, ctx| ctx.passivate()); }
+            None => ctx.passivate(),
+        }
+    }
+}
+
+fn sessions(log: &Arc<Mutex<Vec<String>>>) -> Shard<Session, &'static str> {
+    delayed_sessions(log, None)
+}
+
+fn delayed_sessions(log: &Arc<Mutex<Vec<String>>>, delay: Option<Duration>)
+                    -> Shard<Session, &'static str>
+{
+    let log = Arc::clone(log);
+    Shard::new(move |id: &&'static str, _| {
+        log.lock().unwrap().push(format!("created {}", id));
+        Session{visits: 0, log: Arc::clone(&log), delay}
+    })
+}
+
+#[test]
+fn test_shard_entities() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let log2 = Arc::clone(&log);
+
+    Arbiter::handle().spawn_fn(move || {
+        let addr: Addr<Unsync, _> = sessions(&log2).start();
+        let visits: Vec<_> = ["a", "b", "a", "a", "b"].iter()
+            .map(|id| addr.send(Visit(id))).collect();
+
+        future::join_all(visits).then(|res| {
+            assert_eq!(res.unwrap(), vec![1, 1, 2, 3, 2]);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        })
+    });
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*log.lock().unwrap(), vec!["created a", "created b"]);
+}
+
+#[test]
+fn test_shard_passivation() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let log2 = Arc::clone(&log);
+
+    Arbiter::handle().spawn_fn(move || {
+        let addr: Addr<Unsync, _> = sessions(&log2)
+            .idle_timeout(Duration::from_millis(20))
+            .start();
+        let (addr2, addr3) = (addr.clone(), addr.clone());
+
+        addr.send(Visit("a"))
+            .and_then(move |_| addr2.send(Visit("a")))
+            .then(|res| {
+                assert_eq!(res.unwrap(), 2);
+                Timeout::new(Duration::from_millis(70), Arbiter::handle()).unwrap()
+            })
+            .then(move |_| addr3.send(Visit("a")))
+            .then(|res| {
+                // passivated entity starts from scratch
+                assert_eq!(res.unwrap(), 1);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    });
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*log.lock().unwrap(), vec!["created a", "passivated 2", "created a"]);
+}
+
+#[test]
+fn test_shard_passivating() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let log2 = Arc::clone(&log);
+
+    Arbiter::handle().spawn_fn(move || {
+        let addr: Addr<Unsync, _> = delayed_sessions(&log2, Some(Duration::from_millis(60)))
+            .idle_timeout(Duration::from_millis(20))
+            .start();
+        let addr2 = addr.clone();
+
+        addr.send(Visit("a"))
+            .then(|res| {
+                assert_eq!(res.unwrap(), 1);
+                Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            })
+            .then(move |_| addr2.send(Visit("a")))
+            .then(|res| {
+                // message is delivered to passivating entity, no second actor is created
+                assert_eq!(res.unwrap(), 2);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    });
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*log.lock().unwrap(), vec!["created a", "passivated 1"]);
+}
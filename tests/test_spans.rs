@@ -0,0 +1,208 @@
+#![cfg(feature="spans")]
+extern crate actix;
+extern crate futures;
+extern crate log;
+#[cfg(feature="tracing")]
+extern crate tracing;
+
+use std::cell::RefCell;
+#[cfg(feature="tracing")]
+use std::collections::HashMap;
+#[cfg(feature="tracing")]
+use std::fmt;
+#[cfg(feature="tracing")]
+use std::sync::{Arc, Mutex};
+use futures::future;
+use log::{Log, Metadata, Record, LevelFilter};
+use actix::prelude::*;
+
+thread_local!(static RECORDS: RefCell<Vec<(String, String)>> = RefCell::new(Vec::new()));
+
+struct Capture;
+
+impl Log for Capture {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        if record.target().starts_with("actix::") {
+            RECORDS.with(|cell| cell.borrow_mut().push(
+                (record.target().to_owned(), format!("{}", record.args()))));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: Capture = Capture;
+
+/// Value of the field `name` of the record
+fn field<'a>(record: &'a str, name: &str) -> &'a str {
+    record.split(' ')
+        .find(|item| item.starts_with(name) && item[name.len()..].starts_with('='))
+        .map(|item| &item[name.len()+1..]).unwrap()
+}
+
+struct Ping;
+
+impl Message for Ping {
+    type Result = ();
+}
+
+struct Pong;
+
+impl Message for Pong {
+    type Result = ();
+}
+
+struct Pinger(Addr<Unsync, Ponger>);
+
+impl Actor for Pinger {
+    type Context = Context<Self>;
+}
+
+impl Handler<Ping> for Pinger {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {
+        self.0.do_send(Pong);
+    }
+}
+
+struct Ponger;
+
+impl Actor for Ponger {
+    type Context = Context<Self>;
+}
+
+impl Handler<Pong> for Ponger {
+    type Result = ();
+
+    fn handle(&mut self, _: Pong, _: &mut Context<Self>) {
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    }
+}
+
+#[test]
+fn test_spans() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let sys = System::new("test");
+    let ponger: Addr<Unsync, _> = Ponger.start();
+    let pinger: Addr<Unsync, _> = Pinger(ponger).start();
+    Arbiter::handle().spawn_fn(move || {
+        pinger.do_send(Ping);
+        future::result(Ok(()))
+    });
+    assert_eq!(sys.run(), 0);
+
+    let records = RECORDS.with(|cell| cell.borrow().clone());
+    let started = records.iter()
+        .find(|rec| rec.0 == "actix::lifecycle" && rec.1.contains("Pinger"))
+        .expect("Lifecycle event should be logged");
+    assert!(started.1.starts_with("started "));
+    assert_eq!(field(&started.1, "arbiter"), "test");
+
+    let enter: Vec<_> = records.iter()
+        .filter(|rec| rec.0 == "actix::span" && rec.1.starts_with("enter "))
+        .map(|rec| &rec.1)
+        .collect();
+    let ping = enter.iter().find(|rec| field(rec, "message").ends_with("Ping")).unwrap();
+    let pong = enter.iter().find(|rec| field(rec, "message").ends_with("Pong")).unwrap();
+    assert!(field(ping, "actor").ends_with("Pinger"));
+    assert!(field(pong, "actor").ends_with("Ponger"));
+    assert_eq!(field(ping, "id"), field(pong, "id"));
+
+    assert!(records.iter().any(|rec| rec.0 == "actix::span"
+                               && rec.1.starts_with("exit ")
+                               && field(&rec.1, "message").ends_with("Pong")));
+}
+
+/// Fields of `tracing` span or event
+#[cfg(feature="tracing")]
+#[derive(Default)]
+struct Fields(HashMap<&'static str, String>);
+
+#[cfg(feature="tracing")]
+impl tracing::field::Visit for Fields {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        self.0.insert(field.name(), value.to_owned());
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &fmt::Debug) {
+        self.0.insert(field.name(), format!("{:?}", value));
+    }
+}
+
+#[cfg(feature="tracing")]
+#[derive(Clone, Default)]
+struct Collector {
+    spans: Arc<Mutex<Vec<(&'static str, Fields)>>>,
+    events: Arc<Mutex<Vec<Fields>>>,
+}
+
+#[cfg(feature="tracing")]
+impl tracing::Subscriber for Collector {
+    fn enabled(&self, _: &tracing::Metadata) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &tracing::span::Attributes) -> tracing::span::Id {
+        let mut fields = Fields::default();
+        span.record(&mut fields);
+        let mut spans = self.spans.lock().unwrap();
+        spans.push((span.metadata().name(), fields));
+        tracing::span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record) {}
+
+    fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+    fn event(&self, event: &tracing::Event) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        self.events.lock().unwrap().push(fields);
+    }
+
+    fn enter(&self, _: &tracing::span::Id) {}
+
+    fn exit(&self, _: &tracing::span::Id) {}
+}
+
+#[cfg(feature="tracing")]
+#[test]
+fn test_tracing_spans() {
+    let collector = Collector::default();
+
+    tracing::subscriber::with_default(collector.clone(), || {
+        let sys = System::new("test");
+        let ponger: Addr<Unsync, _> = Ponger.start();
+        let pinger: Addr<Unsync, _> = Pinger(ponger).start();
+        Arbiter::handle().spawn_fn(move || {
+            pinger.do_send(Ping);
+            future::result(Ok(()))
+        });
+        assert_eq!(sys.run(), 0);
+    });
+
+    let spans = collector.spans.lock().unwrap();
+    let handle: Vec<_> = spans.iter()
+        .filter(|span| span.0 == "handle")
+        .map(|span| &(span.1).0)
+        .collect();
+    let ping = handle.iter().find(|span| span["message"].ends_with("Ping")).unwrap();
+    let pong = handle.iter().find(|span| span["message"].ends_with("Pong")).unwrap();
+    assert!(ping["actor"].ends_with("Pinger"));
+    assert!(pong["actor"].ends_with("Ponger"));
+    assert_eq!(ping["id"], pong["id"]);
+
+    let events = collector.events.lock().unwrap();
+    let started = events.iter()
+        .find(|event| event.0.get("actor").map(|actor| actor.ends_with("Pinger")) == Some(true))
+        .expect("Lifecycle event should be reported");
+    assert_eq!(started.0["message"], "started");
+    assert_eq!(started.0["arbiter"], "test");
+}
@@ -0,0 +1,90 @@
+extern crate actix;
+extern crate futures;
+
+use futures::{future, Future};
+use actix::prelude::*;
+
+struct Query(usize);
+
+impl Message for Query {
+    type Result = ();
+}
+
+struct Connected;
+
+impl Message for Connected {
+    type Result = usize;
+}
+
+struct Handled;
+
+impl Message for Handled {
+    type Result = Vec<usize>;
+}
+
+#[derive(Default)]
+struct Repository {
+    connected: bool,
+    handled: Vec<usize>,
+}
+
+impl Actor for Repository {
+    type Context = Context<Self>;
+}
+
+impl Handler<Query> for Repository {
+    type Result = ();
+
+    fn handle(&mut self, msg: Query, ctx: &mut Context<Self>) {
+        if self.connected {
+            self.handled.push(msg.0);
+        } else {
+            ctx.stash(msg);
+        }
+    }
+}
+
+impl Handler<Connected> for Repository {
+    type Result = usize;
+
+    fn handle(&mut self, _: Connected, ctx: &mut Context<Self>) -> usize {
+        self.connected = true;
+        let stashed = ctx.stashed();
+        ctx.unstash_all();
+        stashed
+    }
+}
+
+impl Handler<Handled> for Repository {
+    type Result = MessageResult<Handled>;
+
+    fn handle(&mut self, _: Handled, _: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.handled.clone())
+    }
+}
+
+#[test]
+fn test_stash() {
+    let sys = System::new("test");
+
+    let addr: Addr<Unsync, _> = Repository::default().start();
+    for idx in 0..3 {
+        addr.do_send(Query(idx));
+    }
+    let addr2 = addr.clone();
+    Arbiter::handle().spawn(
+        addr.send(Connected)
+            .and_then(move |stashed| {
+                assert_eq!(stashed, 3);
+                addr2.do_send(Query(3));
+                addr2.send(Handled)
+            })
+            .then(|res| {
+                // stashed messages are replayed before newer messages
+                assert_eq!(res.unwrap(), vec![0, 1, 2, 3]);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    assert_eq!(sys.run(), 0);
+}
@@ -2,10 +2,10 @@ extern crate actix;
 extern crate futures;
 extern crate tokio_core;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
-use futures::{future, Future};
+use futures::{future, stream, Future};
 use tokio_core::reactor::Timeout;
 use actix::prelude::*;
 
@@ -70,3 +70,808 @@ fn test_supervisor_restart() {
     assert_eq!(restarts.load(Ordering::Relaxed), 2);
     assert_eq!(messages.load(Ordering::Relaxed), 2);
 }
+
+struct GaveUp(Arc<AtomicUsize>);
+
+impl Actor for GaveUp {
+    type Context = Context<Self>;
+}
+
+impl actix::Handler<actix::supervisor::ChildGaveUp> for GaveUp {
+    type Result = ();
+
+    fn handle(&mut self, msg: actix::supervisor::ChildGaveUp, _: &mut Context<Self>) {
+        self.0.store(msg.restarts, Ordering::Relaxed);
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    }
+}
+
+#[test]
+fn test_supervisor_restart_limit() {
+    let sys = System::new("test");
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let messages = Arc::new(AtomicUsize::new(0));
+    let gave_up = Arc::new(AtomicUsize::new(0));
+    let starts2 = Arc::clone(&starts);
+    let restarts2 = Arc::clone(&restarts);
+    let messages2 = Arc::clone(&messages);
+
+    let notify: Addr<Syn, _> = GaveUp(Arc::clone(&gave_up)).start();
+    let limit = actix::supervisor::BoundedRestart::new(1, Duration::from_secs(10))
+        .notify(notify.recipient());
+
+    let addr: Addr<Unsync, _> = actix::Supervisor::start_with(
+        limit, move |_| MyActor(starts2, restarts2, messages2));
+    addr.do_send(Die);
+    addr.do_send(Die);
+    addr.do_send(Die);
+
+    sys.run();
+    assert_eq!(starts.load(Ordering::Relaxed), 2);
+    assert_eq!(restarts.load(Ordering::Relaxed), 1);
+    assert_eq!(messages.load(Ordering::Relaxed), 2);
+    assert_eq!(gave_up.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_backoff_delay() {
+    use actix::supervisor::Backoff;
+
+    let backoff = Backoff::Fixed(Duration::from_millis(10));
+    assert_eq!(backoff.delay(1), Duration::from_millis(10));
+    assert_eq!(backoff.delay(5), Duration::from_millis(10));
+
+    let backoff = Backoff::Exponential{
+        initial: Duration::from_millis(10), max: Duration::from_millis(50), jitter: false};
+    assert_eq!(backoff.delay(1), Duration::from_millis(10));
+    assert_eq!(backoff.delay(2), Duration::from_millis(20));
+    assert_eq!(backoff.delay(3), Duration::from_millis(40));
+    assert_eq!(backoff.delay(4), Duration::from_millis(50));
+    assert_eq!(backoff.delay(100), Duration::from_millis(50));
+
+    let backoff = Backoff::exponential(Duration::from_millis(10), Duration::from_millis(50));
+    for attempt in 1..10 {
+        let delay = backoff.delay(attempt);
+        assert!(delay >= Duration::from_millis(5));
+        assert!(delay <= Duration::from_millis(50));
+    }
+
+    let max = Duration::from_secs(std::u64::MAX);
+    let backoff = Backoff::exponential(Duration::from_secs(1), max);
+    for attempt in 65..70 {
+        let delay = backoff.delay(attempt);
+        assert!(delay >= max / 2);
+        assert!(delay <= max);
+    }
+}
+
+#[test]
+fn test_supervisor_backoff() {
+    let sys = System::new("test");
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let messages = Arc::new(AtomicUsize::new(0));
+    let starts2 = Arc::clone(&starts);
+    let restarts2 = Arc::clone(&restarts);
+    let messages2 = Arc::clone(&messages);
+
+    let limit = actix::supervisor::BoundedRestart::new(5, Duration::from_secs(10))
+        .backoff(actix::supervisor::Backoff::Fixed(Duration::from_millis(10)));
+    let addr: Addr<Unsync, _> = actix::Supervisor::start_with(
+        limit, move |_| MyActor(starts2, restarts2, messages2));
+    addr.do_send(Die);
+    addr.do_send(Die);
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+    assert_eq!(starts.load(Ordering::Relaxed), 3);
+    assert_eq!(restarts.load(Ordering::Relaxed), 2);
+    assert_eq!(messages.load(Ordering::Relaxed), 2);
+}
+
+struct StopAfter(usize, Arc<AtomicUsize>);
+
+impl actix::supervisor::RestartPolicy for StopAfter {
+    fn decide(&mut self, _: &actix::supervisor::RestartReason)
+              -> actix::supervisor::RestartAction
+    {
+        if self.0 == 0 {
+            actix::supervisor::RestartAction::Stop
+        } else {
+            self.0 -= 1;
+            actix::supervisor::RestartAction::Restart
+        }
+    }
+
+    fn gave_up(&mut self) {
+        self.1.fetch_add(1, Ordering::Relaxed);
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    }
+}
+
+#[test]
+fn test_supervisor_custom_policy() {
+    let sys = System::new("test");
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let messages = Arc::new(AtomicUsize::new(0));
+    let gave_up = Arc::new(AtomicUsize::new(0));
+    let starts2 = Arc::clone(&starts);
+    let restarts2 = Arc::clone(&restarts);
+    let messages2 = Arc::clone(&messages);
+
+    let addr: Addr<Unsync, _> = actix::Supervisor::start_with(
+        StopAfter(2, Arc::clone(&gave_up)),
+        move |_| MyActor(starts2, restarts2, messages2));
+    addr.do_send(Die);
+    addr.do_send(Die);
+    addr.do_send(Die);
+    addr.do_send(Die);
+
+    sys.run();
+    assert_eq!(starts.load(Ordering::Relaxed), 3);
+    assert_eq!(restarts.load(Ordering::Relaxed), 2);
+    assert_eq!(messages.load(Ordering::Relaxed), 3);
+    assert_eq!(gave_up.load(Ordering::Relaxed), 1);
+}
+
+struct Parent {
+    starts: Arc<AtomicUsize>,
+    child_starts: Arc<AtomicUsize>,
+    child_messages: Arc<AtomicUsize>,
+    child: Option<Addr<Unsync, MyActor>>,
+}
+
+impl Actor for Parent {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let first = self.starts.fetch_add(1, Ordering::Relaxed) == 0;
+
+        let limit = actix::supervisor::BoundedRestart::new(1, Duration::from_secs(10));
+        let child: Addr<Unsync, _> = actix::Supervisor::start_child(
+            ctx, limit, {
+                let starts = Arc::clone(&self.child_starts);
+                let messages = Arc::clone(&self.child_messages);
+                move |_| MyActor(starts, Arc::new(AtomicUsize::new(0)), messages)
+            });
+        if first {
+            child.do_send(Die);
+            child.do_send(Die);
+        }
+        self.child = Some(child);
+    }
+}
+
+impl actix::Supervised for Parent {}
+
+#[test]
+fn test_supervisor_escalate() {
+    let sys = System::new("test");
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let child_starts = Arc::new(AtomicUsize::new(0));
+    let child_messages = Arc::new(AtomicUsize::new(0));
+    let parent = Parent{
+        starts: Arc::clone(&starts),
+        child_starts: Arc::clone(&child_starts),
+        child_messages: Arc::clone(&child_messages),
+        child: None};
+
+    let _addr: Addr<Unsync, _> = actix::Supervisor::start(move |_| parent);
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+    assert_eq!(starts.load(Ordering::Relaxed), 2);
+    assert_eq!(child_starts.load(Ordering::Relaxed), 3);
+    assert_eq!(child_messages.load(Ordering::Relaxed), 2);
+}
+
+struct Member(Arc<AtomicUsize>, Arc<AtomicUsize>);
+
+impl Actor for Member {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Context<Self>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl actix::Supervised for Member {
+    fn restarting(&mut self, _: &mut Context<Self>) {
+        self.1.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_group_supervisor() {
+    let sys = System::new("test");
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let messages = Arc::new(AtomicUsize::new(0));
+    let member_starts = Arc::new(AtomicUsize::new(0));
+    let member_restarts = Arc::new(AtomicUsize::new(0));
+    let (starts2, restarts2, messages2) =
+        (Arc::clone(&starts), Arc::clone(&restarts), Arc::clone(&messages));
+    let (member_starts2, member_restarts2) =
+        (Arc::clone(&member_starts), Arc::clone(&member_restarts));
+
+    let mut group = actix::supervisor::GroupSupervisor::new();
+    let _member: Addr<Syn, _> = group.add(move |_| Member(member_starts2, member_restarts2));
+    let addr: Addr<Unsync, _> = group.add(move |_| MyActor(starts2, restarts2, messages2));
+    group.start();
+
+    addr.do_send(Die);
+    addr.do_send(Die);
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+    assert_eq!(starts.load(Ordering::Relaxed), 3);
+    assert_eq!(restarts.load(Ordering::Relaxed), 2);
+    assert_eq!(messages.load(Ordering::Relaxed), 2);
+    assert_eq!(member_starts.load(Ordering::Relaxed), 3);
+    assert_eq!(member_restarts.load(Ordering::Relaxed), 2);
+}
+
+struct Panic;
+
+impl Message for Panic {
+    type Result = ();
+}
+
+struct Reasons {
+    started: bool,
+    reasons: Arc<Mutex<Vec<actix::supervisor::RestartReason>>>,
+}
+
+impl Actor for Reasons {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        if !self.started {
+            self.started = true;
+            ctx.add_stream(stream::once::<(), ()>(Err(())));
+        }
+    }
+}
+
+impl StreamHandler<(), ()> for Reasons {
+    fn handle(&mut self, _: (), _: &mut Context<Self>) {}
+}
+
+impl actix::Supervised for Reasons {
+    fn restarting_with(&mut self, reason: &actix::supervisor::RestartReason,
+                       ctx: &mut Context<Self>)
+    {
+        use actix::supervisor::RestartReason;
+
+        self.reasons.lock().unwrap().push(reason.clone());
+        match *reason {
+            RestartReason::StreamError => ctx.notify(Panic),
+            RestartReason::Panic(_) => ctx.notify(Die),
+            RestartReason::Stopped =>
+                Arbiter::system().do_send(actix::msgs::SystemExit(0)),
+            RestartReason::Passivated => unreachable!(),
+        }
+    }
+}
+
+impl actix::Handler<Panic> for Reasons {
+    type Result = ();
+
+    fn handle(&mut self, _: Panic, _: &mut Context<Self>) {
+        panic!("test panic");
+    }
+}
+
+impl actix::Handler<Die> for Reasons {
+    type Result = ();
+
+    fn handle(&mut self, _: Die, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+#[test]
+fn test_supervisor_restart_reason() {
+    use actix::supervisor::RestartReason;
+
+    let sys = System::new("test");
+
+    let reasons = Arc::new(Mutex::new(Vec::new()));
+    let reasons2 = Arc::clone(&reasons);
+    let _addr: Addr<Unsync, _> = actix::Supervisor::start(
+        move |_| Reasons{started: false, reasons: reasons2});
+
+    sys.run();
+    assert_eq!(*reasons.lock().unwrap(),
+               vec![RestartReason::StreamError,
+                    RestartReason::Panic("test panic".to_owned()),
+                    RestartReason::Stopped]);
+}
+
+struct Events(Arc<Mutex<Vec<String>>>);
+
+impl Actor for Events {
+    type Context = Context<Self>;
+}
+
+impl actix::Handler<actix::supervisor::SupervisorEvent> for Events {
+    type Result = ();
+
+    fn handle(&mut self, msg: actix::supervisor::SupervisorEvent, _: &mut Context<Self>) {
+        use actix::supervisor::SupervisorEvent;
+
+        match msg {
+            SupervisorEvent::ChildRestarted{count, reason} =>
+                self.0.lock().unwrap().push(format!("restarted {} {:?}", count, reason)),
+            SupervisorEvent::ChildGaveUp{count} => {
+                self.0.lock().unwrap().push(format!("gave up {}", count));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_supervisor_events() {
+    use actix::supervisor::RestartPolicy;
+
+    let sys = System::new("test");
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let counter = Arc::new(AtomicUsize::new(0));
+    let (c1, c2, c3) = (Arc::clone(&counter), Arc::clone(&counter), Arc::clone(&counter));
+
+    let monitor: Addr<Syn, _> = Events(Arc::clone(&events)).start();
+    let policy = actix::supervisor::BoundedRestart::new(2, Duration::from_secs(10))
+        .subscribe(monitor.recipient());
+    let addr: Addr<Unsync, _> = actix::Supervisor::start_with(
+        policy, move |_| MyActor(c1, c2, c3));
+    addr.do_send(Die);
+    addr.do_send(Die);
+    addr.do_send(Die);
+
+    sys.run();
+    assert_eq!(*events.lock().unwrap(),
+               vec!["restarted 1 Stopped".to_owned(),
+                    "restarted 2 Stopped".to_owned(),
+                    "gave up 2".to_owned()]);
+}
+
+struct DeadLetters(Arc<AtomicUsize>);
+
+impl Actor for DeadLetters {
+    type Context = Context<Self>;
+}
+
+impl actix::Handler<actix::deadletter::DeadLetter> for DeadLetters {
+    type Result = ();
+
+    fn handle(&mut self, msg: actix::deadletter::DeadLetter, _: &mut Context<Self>) {
+        assert!(msg.is::<Die>());
+        assert_eq!(msg.reason, actix::deadletter::DeadLetterReason::ActorRestarted);
+        if self.0.fetch_add(1, Ordering::Relaxed) == 1 {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }
+    }
+}
+
+#[test]
+fn test_supervisor_drop_pending() {
+    use actix::supervisor::{AlwaysRestart, RestartPolicy, Retention};
+
+    let sys = System::new("test");
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let messages = Arc::new(AtomicUsize::new(0));
+    let dead = Arc::new(AtomicUsize::new(0));
+    let starts2 = Arc::clone(&starts);
+    let restarts2 = Arc::clone(&restarts);
+    let messages2 = Arc::clone(&messages);
+
+    let letters: Addr<Syn, _> = DeadLetters(Arc::clone(&dead)).start();
+    let policy = AlwaysRestart.with_retention(Retention::DeadLetters(letters.recipient()));
+    let addr: Addr<Unsync, _> = actix::Supervisor::start_with(
+        policy, move |_| MyActor(starts2, restarts2, messages2));
+    addr.do_send(Die);
+    addr.do_send(Die);
+    addr.do_send(Die);
+
+    sys.run();
+    assert_eq!(starts.load(Ordering::Relaxed), 2);
+    assert_eq!(restarts.load(Ordering::Relaxed), 1);
+    assert_eq!(messages.load(Ordering::Relaxed), 1);
+    assert_eq!(dead.load(Ordering::Relaxed), 2);
+}
+
+struct Fresh {
+    handled: usize,
+    seen: Arc<Mutex<Vec<usize>>>,
+}
+
+impl Actor for Fresh {
+    type Context = Context<Self>;
+}
+
+impl actix::Supervised for Fresh {}
+
+impl actix::Handler<Die> for Fresh {
+    type Result = ();
+
+    fn handle(&mut self, _: Die, ctx: &mut Context<Self>) {
+        self.handled += 1;
+        let mut seen = self.seen.lock().unwrap();
+        seen.push(self.handled);
+        if seen.len() == 3 {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }
+        ctx.stop();
+    }
+}
+
+#[test]
+fn test_supervisor_fresh_instance() {
+    let sys = System::new("test");
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen2 = Arc::clone(&seen);
+    let addr: Addr<Unsync, _> = actix::Supervisor::start_fresh(
+        actix::supervisor::AlwaysRestart,
+        move |_| Fresh{handled: 0, seen: Arc::clone(&seen2)});
+    addr.do_send(Die);
+    addr.do_send(Die);
+    addr.do_send(Die);
+
+    sys.run();
+    assert_eq!(*seen.lock().unwrap(), vec![1, 1, 1]);
+}
+
+#[test]
+fn test_supervisor_stats() {
+    use actix::supervisor::{AlwaysRestart, RestartPolicy, RestartReason,
+                            SupervisorMonitor, SupervisorStats};
+
+    let sys = System::new("test");
+
+    let counter = Arc::new(AtomicUsize::new(0));
+    let (c1, c2, c3) = (Arc::clone(&counter), Arc::clone(&counter), Arc::clone(&counter));
+
+    let monitor: Addr<Syn, _> = SupervisorMonitor::default().start();
+    let addr: Addr<Unsync, _> = actix::Supervisor::start_with(
+        AlwaysRestart.subscribe(monitor.clone().recipient()),
+        move |_| MyActor(c1, c2, c3));
+    addr.do_send(Die);
+    addr.do_send(Die);
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| monitor.send(SupervisorStats))
+            .then(|res| {
+                let stats = res.unwrap();
+                assert_eq!(stats.restarts, 2);
+                assert_eq!(stats.last_reason, Some(RestartReason::Stopped));
+                assert!(stats.last_failure.is_some());
+                assert!(!stats.gave_up);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+}
+
+/// Custom execution context built on top of `ContextImpl`
+struct CustomContext<A> where A: Actor<Context=CustomContext<A>> {
+    inner: actix::dev::ContextImpl<A>,
+}
+
+impl<A> ActorContext for CustomContext<A> where A: Actor<Context=Self> {
+    fn stop(&mut self) {
+        self.inner.stop()
+    }
+    fn terminate(&mut self) {
+        self.inner.terminate()
+    }
+    fn state(&self) -> actix::ActorState {
+        self.inner.state()
+    }
+}
+
+impl<A> AsyncContext<A> for CustomContext<A> where A: Actor<Context=Self> {
+    fn spawn<F>(&mut self, fut: F) -> SpawnHandle
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        self.inner.spawn(fut)
+    }
+    fn wait<F>(&mut self, fut: F)
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        self.inner.wait(fut)
+    }
+    fn waiting(&self) -> bool {
+        self.inner.waiting()
+    }
+    fn cancel_future(&mut self, handle: SpawnHandle) -> bool {
+        self.inner.cancel_future(handle)
+    }
+    fn unsync_address(&mut self) -> Addr<Unsync, A> {
+        self.inner.unsync_address()
+    }
+    fn sync_address(&mut self) -> Addr<Syn, A> {
+        self.inner.sync_address()
+    }
+}
+
+impl<A> Future for CustomContext<A> where A: Actor<Context=Self> {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> futures::Poll<(), ()> {
+        let ctx: &mut CustomContext<A> = unsafe {
+            std::mem::transmute(self as &mut CustomContext<A>)
+        };
+        self.inner.poll(ctx)
+    }
+}
+
+impl<A> actix::dev::SupervisedContext<A> for CustomContext<A>
+    where A: actix::Supervised + Actor<Context=Self>
+{
+    fn create() -> Self {
+        CustomContext{inner: actix::dev::ContextImpl::new(None)}
+    }
+    fn set_actor(&mut self, act: A) {
+        self.inner.set_actor(act)
+    }
+    fn connected(&self) -> bool {
+        self.inner.connected()
+    }
+    fn reset(&mut self) -> bool {
+        self.inner.reset()
+    }
+    fn restarting(&mut self, reason: &actix::supervisor::RestartReason) {
+        let ctx: &mut CustomContext<A> = unsafe {
+            std::mem::transmute(self as &mut CustomContext<A>)
+        };
+        self.inner.actor().restarting_with(reason, ctx)
+    }
+}
+
+struct Custom(Arc<AtomicUsize>, Arc<AtomicUsize>);
+
+impl Actor for Custom {
+    type Context = CustomContext<Self>;
+
+    fn started(&mut self, _: &mut CustomContext<Self>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl actix::Supervised for Custom {
+    fn restarting(&mut self, _: &mut CustomContext<Self>) {
+        self.1.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl actix::Handler<Die> for Custom {
+    type Result = ();
+
+    fn handle(&mut self, _: Die, ctx: &mut CustomContext<Self>) {
+        ctx.stop();
+    }
+}
+
+#[test]
+fn test_supervisor_custom_context() {
+    let sys = System::new("test");
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let starts2 = Arc::clone(&starts);
+    let restarts2 = Arc::clone(&restarts);
+
+    let addr: Addr<Unsync, _> = actix::Supervisor::start(
+        move |_| Custom(starts2, restarts2));
+    addr.do_send(Die);
+    addr.do_send(Die);
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+    assert_eq!(starts.load(Ordering::Relaxed), 3);
+    assert_eq!(restarts.load(Ordering::Relaxed), 2);
+}
+
+struct Expire(usize);
+
+impl Message for Expire {
+    type Result = ();
+}
+
+/// Schedules session expirations on first start, panics on `Die`
+struct Sessions {
+    starts: usize,
+    expired: Arc<Mutex<Vec<usize>>>,
+    handle: Option<SpawnHandle>,
+}
+
+impl Actor for Sessions {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        self.starts += 1;
+        if self.starts == 1 {
+            ctx.notify_later(Expire(1), Duration::from_millis(20));
+            self.handle = Some(ctx.notify_later(Expire(2), Duration::from_millis(30)));
+            ctx.notify_later(Expire(3), Duration::from_millis(40));
+            ctx.run_later(Duration::from_millis(20), |act, _| {
+                act.expired.lock().unwrap().push(0)
+            });
+        }
+    }
+}
+
+impl actix::Supervised for Sessions {
+    fn restarting(&mut self, ctx: &mut Context<Self>) {
+        // handles of preserved notifications stay valid
+        if let Some(handle) = self.handle.take() {
+            ctx.cancel_future(handle);
+        }
+    }
+}
+
+impl actix::Handler<Expire> for Sessions {
+    type Result = ();
+
+    fn handle(&mut self, msg: Expire, _: &mut Context<Self>) {
+        self.expired.lock().unwrap().push(msg.0);
+    }
+}
+
+impl actix::Handler<Die> for Sessions {
+    type Result = ();
+
+    fn handle(&mut self, _: Die, _: &mut Context<Self>) {
+        panic!("Session store failed");
+    }
+}
+
+#[test]
+fn test_supervisor_preserve_timers() {
+    use actix::supervisor::{AlwaysRestart, RestartPolicy};
+
+    let sys = System::new("test");
+
+    let preserved = Arc::new(Mutex::new(Vec::new()));
+    let dropped = Arc::new(Mutex::new(Vec::new()));
+    let expired = Arc::clone(&preserved);
+    let addr: Addr<Unsync, _> = actix::Supervisor::start_with(
+        AlwaysRestart.with_timers(), move |_| Sessions{starts: 0, expired, handle: None});
+    addr.do_send(Die);
+    let expired = Arc::clone(&dropped);
+    let addr: Addr<Unsync, _> = actix::Supervisor::start_with(
+        AlwaysRestart, move |_| Sessions{starts: 0, expired, handle: None});
+    addr.do_send(Die);
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+    assert_eq!(*preserved.lock().unwrap(), vec![1, 3]);
+    assert!(dropped.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_supervisor_lazy_start() {
+    let sys = System::new("test");
+
+    let created = Arc::new(AtomicUsize::new(0));
+    let starts = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let messages = Arc::new(AtomicUsize::new(0));
+    let (created2, starts2) = (Arc::clone(&created), Arc::clone(&starts));
+    let (restarts2, messages2) = (Arc::clone(&restarts), Arc::clone(&messages));
+
+    let addr: Addr<Syn, _> = actix::Supervisor::start_lazy(
+        actix::supervisor::AlwaysRestart, move |_| {
+            created2.fetch_add(1, Ordering::Relaxed);
+            MyActor(Arc::clone(&starts2), Arc::clone(&restarts2), Arc::clone(&messages2))
+        });
+
+    // actor without messages is never created
+    let unused = Arc::new(AtomicUsize::new(0));
+    let unused2 = Arc::clone(&unused);
+    let _: Addr<Unsync, MyActor> = actix::Supervisor::start_lazy(
+        actix::supervisor::AlwaysRestart, move |_| {
+            unused2.fetch_add(1, Ordering::Relaxed);
+            MyActor(Arc::clone(&unused2), Arc::clone(&unused2), Arc::clone(&unused2))
+        });
+
+    let (created3, addr2) = (Arc::clone(&created), addr.clone());
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                assert_eq!(created3.load(Ordering::Relaxed), 0);
+                addr2.do_send(Die);
+                Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+            })
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    sys.run();
+    assert_eq!(created.load(Ordering::Relaxed), 1);
+    assert_eq!(starts.load(Ordering::Relaxed), 2);
+    assert_eq!(restarts.load(Ordering::Relaxed), 1);
+    assert_eq!(messages.load(Ordering::Relaxed), 1);
+    assert_eq!(unused.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn test_supervisor_lazy_start_policy() {
+    let sys = System::new("test");
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let messages = Arc::new(AtomicUsize::new(0));
+    let (starts2, restarts2) = (Arc::clone(&starts), Arc::clone(&restarts));
+    let messages2 = Arc::clone(&messages);
+
+    // failed actor is not restarted
+    let addr: Addr<Syn, _> = actix::Supervisor::start_lazy(
+        actix::supervisor::BoundedRestart::new(0, Duration::from_secs(10)),
+        move |_| MyActor(Arc::clone(&starts2), Arc::clone(&restarts2), Arc::clone(&messages2)));
+    addr.do_send(Die);
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    sys.run();
+    assert_eq!(starts.load(Ordering::Relaxed), 1);
+    assert_eq!(restarts.load(Ordering::Relaxed), 0);
+    assert_eq!(messages.load(Ordering::Relaxed), 1);
+}
@@ -2,10 +2,14 @@ extern crate actix;
 extern crate futures;
 extern crate tokio_core;
 
+use std::any::TypeId;
 use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use futures::future;
+use futures::{future, Future};
+use tokio_core::reactor::Timeout;
 use actix::prelude::*;
+use actix::deadletter::{self, DeadLetter, DeadLetterReason, DeadLetters};
 
 
 struct Fibonacci(pub u32);
@@ -101,3 +105,225 @@ fn test_sync() {
     assert_eq!(counter.load(Ordering::Relaxed), 2, "Not started");
     assert_eq!(messages.load(Ordering::Relaxed), 5, "Wrong number of messages");
 }
+
+struct Panic(bool);
+
+impl Message for Panic {
+    type Result = bool;
+}
+
+struct Worker {
+    starts: Arc<AtomicUsize>,
+    restarts: Arc<AtomicUsize>,
+}
+
+impl Actor for Worker {
+    type Context = SyncContext<Self>;
+
+    fn started(&mut self, _: &mut Self::Context) {
+        self.starts.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl actix::Supervised for Worker {
+    fn restarting_with(&mut self, reason: &actix::supervisor::RestartReason,
+                       _: &mut Self::Context)
+    {
+        assert_eq!(*reason, actix::supervisor::RestartReason::Panic("worker panic".to_owned()));
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Handler<Panic> for Worker {
+    type Result = bool;
+
+    fn handle(&mut self, msg: Panic, _: &mut Self::Context) -> bool {
+        if msg.0 {
+            panic!("worker panic");
+        }
+        true
+    }
+}
+
+#[test]
+fn test_sync_supervised() {
+    let sys = System::new("test");
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let starts2 = Arc::clone(&starts);
+    let restarts2 = Arc::clone(&restarts);
+
+    let addr = SyncArbiter::start_supervised(
+        1, actix::supervisor::AlwaysRestart,
+        move || Worker{starts: Arc::clone(&starts2), restarts: Arc::clone(&restarts2)});
+
+    Arbiter::handle().spawn(
+        addr.send(Panic(true))
+            .then(move |res| {
+                assert!(res.is_err());
+                addr.send(Panic(false))
+            })
+            .then(|res| {
+                assert!(res.unwrap());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+    assert_eq!(starts.load(Ordering::Relaxed), 2);
+    assert_eq!(restarts.load(Ordering::Relaxed), 1);
+}
+
+struct Member {
+    starts: Arc<AtomicUsize>,
+    stops: Arc<AtomicUsize>,
+}
+
+impl Actor for Member {
+    type Context = SyncContext<Self>;
+
+    fn started(&mut self, _: &mut Self::Context) {
+        self.starts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.stops.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Stops system once condition is met
+struct Check(Box<FnMut() -> bool>);
+
+impl Actor for Check {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.run_interval(Duration::from_millis(10), |act, _| {
+            if (act.0)() {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            }
+        });
+        ctx.run_later(Duration::from_secs(5), |_, _| {
+            Arbiter::system().do_send(actix::msgs::SystemExit(1));
+        });
+    }
+}
+
+#[test]
+fn test_sync_set_pool_size() {
+    let sys = System::new("test");
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let stops = Arc::new(AtomicUsize::new(0));
+    let starts2 = Arc::clone(&starts);
+    let stops2 = Arc::clone(&stops);
+
+    let (_addr, pool) = SyncArbiter::start_resizable(
+        1, move || Member{starts: Arc::clone(&starts2), stops: Arc::clone(&stops2)});
+    pool.do_send(actix::msgs::SetPoolSize(3));
+
+    let starts2 = Arc::clone(&starts);
+    let stops2 = Arc::clone(&stops);
+    let mut grown = false;
+    let _: Addr<Unsync, _> = Check(Box::new(move || {
+        if !grown {
+            if starts2.load(Ordering::Relaxed) == 3 {
+                pool.do_send(actix::msgs::SetPoolSize(1));
+                grown = true;
+            }
+            false
+        } else {
+            stops2.load(Ordering::Relaxed) == 2
+        }
+    })).start();
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(starts.load(Ordering::Relaxed), 3);
+    assert_eq!(stops.load(Ordering::Relaxed), 2);
+}
+
+struct Letters(Arc<Mutex<Vec<DeadLetter>>>);
+
+impl Actor for Letters {
+    type Context = Context<Self>;
+}
+
+impl Handler<DeadLetter> for Letters {
+    type Result = ();
+
+    fn handle(&mut self, msg: DeadLetter, _: &mut Context<Self>) {
+        self.0.lock().unwrap().push(msg);
+    }
+}
+
+#[test]
+fn test_sync_worker_panic() {
+    let sys = System::new("test");
+
+    let letters = Arc::new(Mutex::new(Vec::new()));
+    let monitor: Addr<Syn, _> = Letters(Arc::clone(&letters)).start();
+    DeadLetters::from_registry().do_send(deadletter::Subscribe(monitor.recipient()));
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let starts2 = Arc::clone(&starts);
+    let restarts2 = Arc::clone(&restarts);
+
+    // single worker, failed worker has to be replaced
+    let addr = SyncArbiter::start(
+        1, move || Worker{starts: Arc::clone(&starts2), restarts: Arc::clone(&restarts2)});
+
+    let letters2 = Arc::clone(&letters);
+    Arbiter::handle().spawn(
+        addr.send(Panic(true))
+            .then(move |res| {
+                assert!(res.is_err());
+                addr.send(Panic(false))
+            })
+            .then(move |res| {
+                assert!(res.unwrap());
+                let _: Addr<Unsync, _> = Check(Box::new(move || {
+                    !letters2.lock().unwrap().is_empty()
+                })).start();
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(starts.load(Ordering::Relaxed), 2);
+    assert_eq!(restarts.load(Ordering::Relaxed), 0);
+
+    let letters = letters.lock().unwrap();
+    assert_eq!(letters.len(), 1);
+    assert_eq!(letters[0].reason, DeadLetterReason::Panic);
+    assert_eq!(letters[0].message_type, Some(TypeId::of::<Panic>()));
+}
+
+#[test]
+fn test_sync_worker_panic_closed() {
+    let sys = System::new("test");
+
+    let starts = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let starts2 = Arc::clone(&starts);
+    let restarts2 = Arc::clone(&restarts);
+
+    // closed sync arbiter replaces failed worker to process queued messages
+    let addr = SyncArbiter::start(
+        1, move || Worker{starts: Arc::clone(&starts2), restarts: Arc::clone(&restarts2)});
+    addr.do_send(Panic(true));
+    let res = addr.send(Panic(false));
+    drop(addr);
+
+    Arbiter::handle().spawn(
+        res.then(|res| {
+            assert!(res.unwrap());
+            Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+        }).then(|_| {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(starts.load(Ordering::Relaxed), 2);
+}
@@ -0,0 +1,172 @@
+extern crate actix;
+extern crate futures;
+
+use std::any::TypeId;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use futures::Future;
+use actix::prelude::*;
+use actix::clock::Delay;
+use actix::msgs::Terminated;
+use actix::supervisor::BoundedRestart;
+
+struct Die;
+
+impl Message for Die {
+    type Result = ();
+}
+
+struct Worker(Arc<AtomicUsize>);
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+impl actix::Supervised for Worker {
+    fn restarting(&mut self, _: &mut Context<Self>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Handler<Die> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Die, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+/// Watches workers and kills them, stops system once all workers are terminated
+struct Watcher {
+    workers: Vec<Addr<Syn, Worker>>,
+    kills: usize,
+    restarts: Arc<AtomicUsize>,
+    terminated: usize,
+}
+
+impl Watcher {
+    fn new(workers: Vec<Addr<Syn, Worker>>, kills: usize, restarts: Arc<AtomicUsize>) -> Self {
+        Watcher{workers: workers, kills: kills, restarts: restarts, terminated: 0}
+    }
+}
+
+impl Actor for Watcher {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        for worker in &self.workers {
+            ctx.watch(worker);
+            for _ in 0..self.kills {
+                worker.do_send(Die);
+            }
+        }
+    }
+}
+
+impl Handler<Terminated<Worker>> for Watcher {
+    type Result = ();
+
+    fn handle(&mut self, msg: Terminated<Worker>, _: &mut Context<Self>) {
+        assert!(msg.0.upgrade().is_none());
+        assert_eq!(self.restarts.load(Ordering::Relaxed), self.kills - 1);
+        self.terminated += 1;
+        if self.terminated == self.workers.len() {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }
+    }
+}
+
+#[test]
+fn test_watch() {
+    let sys = System::new("test");
+    let restarts = Arc::new(AtomicUsize::new(0));
+
+    let w1: Addr<Syn, _> = Worker(Arc::clone(&restarts)).start();
+    let w2: Addr<Syn, _> = Worker(Arc::clone(&restarts)).start();
+    let _watcher: Addr<Syn, _> = Watcher::new(vec![w1, w2], 1, restarts).start();
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[test]
+fn test_watch_stopped() {
+    let sys = System::new("test");
+    let restarts = Arc::new(AtomicUsize::new(0));
+
+    let worker: Addr<Syn, _> = Worker(Arc::clone(&restarts)).start();
+    worker.do_send(Die);
+
+    Arbiter::handle().spawn(
+        Delay::new(Duration::from_millis(50)).map(move |_| {
+            assert!(!worker.connected());
+            let _watcher: Addr<Syn, _> = Watcher::new(vec![worker], 1, restarts).start();
+        }));
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[test]
+fn test_watch_supervised() {
+    let sys = System::new("test");
+    let restarts = Arc::new(AtomicUsize::new(0));
+
+    let restarts2 = Arc::clone(&restarts);
+    let worker: Addr<Syn, _> = actix::Supervisor::start_with(
+        BoundedRestart::new(1, Duration::from_secs(10)),
+        move |_| Worker(restarts2));
+    let _watcher: Addr<Syn, _> = Watcher::new(vec![worker], 2, restarts).start();
+
+    assert_eq!(sys.run(), 0);
+}
+
+/// Linked to worker, exits system when stopped or when exit is trapped
+struct Linked {
+    worker: Addr<Syn, Worker>,
+    trap: bool,
+}
+
+impl Actor for Linked {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        if self.trap {
+            ctx.trap_exit();
+        }
+        ctx.link(&self.worker);
+        self.worker.do_send(Die);
+    }
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        assert!(!self.trap);
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    }
+}
+
+impl Handler<actix::msgs::Exited> for Linked {
+    type Result = ();
+
+    fn handle(&mut self, msg: actix::msgs::Exited, ctx: &mut Context<Self>) {
+        assert_eq!(msg.actor_type, TypeId::of::<Worker>());
+        assert_eq!(ctx.state(), ActorState::Running);
+        Arbiter::system().do_send(actix::msgs::SystemExit(1));
+    }
+}
+
+#[test]
+fn test_link() {
+    let sys = System::new("test");
+    let worker: Addr<Syn, _> = Worker(Arc::new(AtomicUsize::new(0))).start();
+    let _linked: Addr<Syn, _> = Linked{worker: worker, trap: false}.start();
+
+    assert_eq!(sys.run(), 0);
+}
+
+#[test]
+fn test_link_trap_exit() {
+    let sys = System::new("test");
+    let worker: Addr<Syn, _> = Worker(Arc::new(AtomicUsize::new(0))).start();
+    let _linked: Addr<Syn, _> = Linked{worker: worker, trap: true}.start();
+
+    assert_eq!(sys.run(), 1);
+}